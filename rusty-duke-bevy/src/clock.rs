@@ -0,0 +1,210 @@
+//! Game clock that survives focus loss and OS sleep.
+//!
+//! Bevy systems only feed focus events and samples in, the policy is plain
+//! Rust. All times come from `Instant`, which is monotonic, so wall clock
+//! changes never move the game clocks.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Gap between two samples that can't be explained by desktop app update
+/// throttling. Such a gap means the app was suspended, e.g. by OS sleep, and
+/// is handled as time away.
+pub const SLEEP_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// What to do with time that passes while the window is not focused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AwayPolicy {
+    /// Clocks stop while away. For casual games.
+    Pause,
+    /// Clocks keep running while away. For rated and multiplayer games.
+    KeepRunning,
+}
+
+/// What happened while away, for telling the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resumed {
+    pub away: Duration,
+    /// True if the time away was charged to the running clock.
+    pub charged: bool,
+}
+
+impl fmt::Display for Resumed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.charged {
+            write!(f, "Away for {}s, clock kept running.", self.away.as_secs())
+        } else {
+            write!(f, "Away for {}s, clock was paused.", self.away.as_secs())
+        }
+    }
+}
+
+/// Turns samples of a monotonic clock into time to charge to the running
+/// game clock.
+pub struct SessionClock {
+    policy: AwayPolicy,
+    focused: bool,
+    last_sample: Instant,
+    /// Time charged at focus changes, but not yet returned by `sample`.
+    pending: Duration,
+    resumed: Option<Resumed>,
+}
+
+impl SessionClock {
+    pub fn new(policy: AwayPolicy, now: Instant) -> SessionClock {
+        SessionClock {
+            policy,
+            focused: true,
+            last_sample: now,
+            pending: Duration::ZERO,
+            resumed: None,
+        }
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Time to charge to the running clock since last sample.
+    pub fn sample(&mut self, now: Instant) -> Duration {
+        let charged = self.advance(now);
+        std::mem::take(&mut self.pending) + charged
+    }
+
+    /// Window lost focus.
+    pub fn focus_lost(&mut self, now: Instant) {
+        if !self.focused {
+            return;
+        }
+        let charged = self.advance(now);
+        self.pending += charged;
+        self.focused = false;
+    }
+
+    /// Window got focus back.
+    pub fn focus_gained(&mut self, now: Instant) {
+        if self.focused {
+            return;
+        }
+        let charged = self.advance(now);
+        self.pending += charged;
+        self.focused = true;
+    }
+
+    /// What happened during the last time away, once per time away. Time
+    /// away is either focus loss or a sleep detected by `SLEEP_THRESHOLD`.
+    pub fn take_resumed(&mut self) -> Option<Resumed> {
+        if !self.focused {
+            return None;
+        }
+        self.resumed.take()
+    }
+
+    /// Move last sample to now, and return time to charge for the gap.
+    fn advance(&mut self, now: Instant) -> Duration {
+        let gap = now.saturating_duration_since(self.last_sample);
+        self.last_sample = now;
+
+        if self.focused && gap <= SLEEP_THRESHOLD {
+            return gap;
+        }
+
+        // Away. Focus loss is reported when focus comes back, so away time
+        // is collected until then.
+        let charged = self.policy == AwayPolicy::KeepRunning;
+        let resumed = self.resumed.get_or_insert(Resumed {
+            away: Duration::ZERO,
+            charged,
+        });
+        resumed.away += gap;
+
+        if charged {
+            gap
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn focused_time_is_charged() {
+        let start = Instant::now();
+        let mut clock = SessionClock::new(AwayPolicy::Pause, start);
+        assert_eq!(clock.sample(start + secs(1)), secs(1));
+        // Throttled updates, but not asleep.
+        assert_eq!(clock.sample(start + secs(11)), secs(10));
+        assert_eq!(clock.take_resumed(), None);
+    }
+
+    #[test]
+    fn focus_loss_pauses_casual_games() {
+        let start = Instant::now();
+        let mut clock = SessionClock::new(AwayPolicy::Pause, start);
+        clock.focus_lost(start + secs(2));
+        assert_eq!(clock.sample(start + secs(60)), secs(2));
+        assert!(!clock.is_focused());
+        // Not told until back.
+        assert_eq!(clock.take_resumed(), None);
+
+        clock.focus_gained(start + secs(300));
+        assert_eq!(clock.sample(start + secs(301)), secs(1));
+        let resumed = Resumed { away: secs(298), charged: false };
+        assert_eq!(clock.take_resumed(), Some(resumed));
+        assert_eq!(resumed.to_string(), "Away for 298s, clock was paused.");
+        assert_eq!(clock.take_resumed(), None);
+    }
+
+    #[test]
+    fn focus_loss_keeps_multiplayer_clocks_running() {
+        let start = Instant::now();
+        let mut clock = SessionClock::new(AwayPolicy::KeepRunning, start);
+        clock.focus_lost(start + secs(2));
+        clock.focus_gained(start + secs(300));
+        assert_eq!(clock.sample(start + secs(301)), secs(301));
+        assert_eq!(clock.take_resumed(), Some(Resumed { away: secs(298), charged: true }));
+    }
+
+    #[test]
+    fn sleep_is_time_away() {
+        let start = Instant::now();
+        let gap = SLEEP_THRESHOLD + secs(1);
+
+        // OS sleep, no focus events.
+        let mut casual = SessionClock::new(AwayPolicy::Pause, start);
+        assert_eq!(casual.sample(start + gap), Duration::ZERO);
+        assert_eq!(casual.take_resumed(), Some(Resumed { away: gap, charged: false }));
+        assert_eq!(casual.sample(start + gap + secs(1)), secs(1));
+
+        let mut multiplayer = SessionClock::new(AwayPolicy::KeepRunning, start);
+        assert_eq!(multiplayer.sample(start + gap), gap);
+        assert_eq!(multiplayer.take_resumed(), Some(Resumed { away: gap, charged: true }));
+    }
+
+    #[test]
+    fn clock_never_runs_backwards() {
+        let start = Instant::now() + secs(10);
+        let mut clock = SessionClock::new(AwayPolicy::KeepRunning, start);
+        assert_eq!(clock.sample(start - secs(5)), Duration::ZERO);
+    }
+
+    #[test]
+    fn repeated_focus_events_are_ignored() {
+        let start = Instant::now();
+        let mut clock = SessionClock::new(AwayPolicy::Pause, start);
+        clock.focus_gained(start + secs(1));
+        clock.focus_lost(start + secs(2));
+        clock.focus_lost(start + secs(5));
+        clock.focus_gained(start + secs(6));
+        clock.focus_gained(start + secs(7));
+        assert_eq!(clock.sample(start + secs(8)), secs(4));
+        assert_eq!(clock.take_resumed(), Some(Resumed { away: secs(4), charged: false }));
+    }
+}
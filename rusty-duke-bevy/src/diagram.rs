@@ -0,0 +1,140 @@
+//! Movement diagram of one side of a tile, as on the printed cards. A grid
+//! of squares around the tile, each labeled with the actions the tile can do
+//! there.
+
+use bevy::prelude::*;
+use rusty_duke_logic::logic::{ActionType, Offset, TileColor};
+
+/// Squares from the tile to the edge of the grid, more if offsets reach
+/// further. Two is the 5x5 grid of the cards.
+const MIN_RADIUS: i8 = 2;
+const CELL_SIZE_PX: f32 = 22.0;
+const CELL_MARGIN_PX: f32 = 1.0;
+const CELL_FONT_SIZE: f32 = 10.0;
+const CELL_COLOR: Color = Color::BEIGE;
+const TILE_CELL_COLOR: Color = Color::DARK_GRAY;
+const MOVE_CELL_COLOR: Color = Color::OLIVE;
+const STRIKE_CELL_COLOR: Color = Color::SALMON;
+const COMMAND_CELL_COLOR: Color = Color::TEAL;
+const CELL_TEXT_COLOR: Color = Color::BLACK;
+
+/// Short label of action type, e.g. "S" for slide.
+fn label(kind: &ActionType) -> &'static str {
+    match kind {
+        ActionType::Move => "M",
+        ActionType::Jump => "J",
+        ActionType::Slide => "S",
+        ActionType::JumpSlide => "JS",
+        ActionType::Command => "C",
+        ActionType::Strike => "X",
+        ActionType::NewFromBag | ActionType::PlaceNew => "",
+    }
+}
+
+/// Color of square with actions. Strikes and commands stand out from
+/// moves, like on the board.
+fn cell_color(kinds: &[&ActionType]) -> Color {
+    if kinds.iter().any(|kind| **kind == ActionType::Strike) {
+        STRIKE_CELL_COLOR
+    } else if kinds.iter().any(|kind| **kind == ActionType::Command) {
+        COMMAND_CELL_COLOR
+    } else if kinds.is_empty() {
+        CELL_COLOR
+    } else {
+        MOVE_CELL_COLOR
+    }
+}
+
+/// Spawn diagram of actions as a child of parent. Offsets are in board
+/// coordinates, as from `logic::tile_movement_grid` for the color of the
+/// tile, so White tiles point down the board. The grid is laid out like the
+/// board seen from view.
+pub fn spawn_diagram(
+    parent: &mut ChildBuilder,
+    actions: &[(Offset, ActionType)],
+    view: TileColor,
+    font: Handle<Font>,
+) {
+    let radius = actions
+        .iter()
+        .map(|(offset, _)| offset.x.abs().max(offset.y.abs()))
+        .fold(MIN_RADIUS, i8::max);
+
+    // Rows bottom up, like the board.
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            for y in -radius..=radius {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            ..default()
+                        },
+                        color: Color::NONE.into(),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for x in -radius..=radius {
+                            // Board turned to White is mirrored.
+                            let offset = match view {
+                                TileColor::Black => Offset { x, y },
+                                TileColor::White => Offset { x: -x, y: -y },
+                            };
+                            spawn_cell(parent, actions, offset, font.clone());
+                        }
+                    });
+            }
+        });
+}
+
+/// One square of the diagram, the tile itself at offset zero.
+fn spawn_cell(
+    parent: &mut ChildBuilder,
+    actions: &[(Offset, ActionType)],
+    offset: Offset,
+    font: Handle<Font>,
+) {
+    let kinds: Vec<&ActionType> = actions
+        .iter()
+        .filter(|(o, _)| *o == offset)
+        .map(|(_, kind)| kind)
+        .collect();
+    let color = if offset.x == 0 && offset.y == 0 {
+        TILE_CELL_COLOR
+    } else {
+        cell_color(&kinds)
+    };
+    let text: Vec<&str> = kinds.iter().map(|kind| label(kind)).collect();
+
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(CELL_SIZE_PX), Val::Px(CELL_SIZE_PX)),
+                margin: UiRect::all(Val::Px(CELL_MARGIN_PX)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: color.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle::from_section(
+                text.join(""),
+                TextStyle {
+                    font,
+                    font_size: CELL_FONT_SIZE,
+                    color: CELL_TEXT_COLOR,
+                },
+            ));
+        });
+}
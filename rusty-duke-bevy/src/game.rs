@@ -1,11 +1,21 @@
 use crate::{AppState, despawn_screen, NORMAL_BUTTON_COLOR};
+use crate::clock::{AwayPolicy, SessionClock};
+use crate::diagram::spawn_diagram;
 use crate::menu::*;
-use bevy::{prelude::*, ui::Interaction};
-use rusty_duke_logic::logic::{get_actions, do_unsafe_action};
+use bevy::{prelude::*, ui::Interaction, window::{RequestRedraw, WindowFocused}};
 use rusty_duke_logic::{
-    ai::alpha_beta::{self, Agent},
-    logic::{self, Action, Coordinate, GameState, Tile, TileColor},
+    ai::{alpha_beta::Agent, opening::OpeningBook, DukeAgent, SearchHandle, SearchPoll},
+    logic::{
+        self,
+        clock::{format_time, GameClock},
+        record::{GameRecord, RecordError},
+        variant::Variant,
+        Action, Coordinate, GameState, Tile, TileColor, WinReason, Winner,
+    },
+    session::{Controller, GameSession},
 };
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 // Constants
@@ -22,7 +32,15 @@ const COMMANDED_SQUARE_COLOR: Color = Color::TEAL;
 const ATTACKED_SQUARE_COLOR: Color = Color::TOMATO;
 const STRIKED_SQUARE_COLOR: Color = Color::SALMON;
 const MOVE_SQUARE_COLOR: Color = Color::OLIVE;
+/// Squares the last action moved a tile from and to.
+const LAST_ACTION_SQUARE_COLOR: Color = Color::rgba(0.3, 0.5, 0.8, 0.5);
+/// Squares of own tiles the opponent can capture, on the player's turn.
+const THREATENED_SQUARE_COLOR: Color = Color::rgba(0.9, 0.2, 0.2, 0.6);
 const DEPLOYABLE_SQUARE_COLOR: Color = Color::ORANGE;
+const INVALID_SQUARE_COLOR: Color = Color::MAROON;
+/// Translucent, the board shows through.
+const PREVIEW_SQUARE_COLOR: Color = Color::rgba(0.5, 0.5, 0.0, 0.4);
+const FLASH_DURATION_S: f32 = 0.3;
 const SQUARE_EFFECT_TEXT_COLOR: Color = Color::RED;
 const SQUARE_MARGIN_PX: f32 = 5.0;
 
@@ -35,6 +53,7 @@ pub const WHITE_TILE_COLOR: Color = Color::BISQUE;
 pub const WHITE_TILE_TEXT_COLOR: Color = Color::NONE;
 const TILE_TEXT_FONT: &str = "fonts/FiraSans-Bold.ttf";
 const TILE_TEXT_FONT_SIZE: f32 = 15.0;
+const TILE_SIDE_FONT_SIZE: f32 = 10.0;
 const TILE_MARGIN_PX: f32 = 5.0;
 
 /*const SELECTED_TILE_COLOR: Color = Color::TEAL;
@@ -42,6 +61,9 @@ const ATTACKED_TILE_COLOR: Color = Color::TOMATO;
 const COMMANDED_TILE_COLOR: Color = Color::NONE;*/
 
 const DOUBLE_CLICK_TIME_NS: u32 = 500 * 1000 * 1000; // 500 ms
+const TOAST_DURATION_S: f32 = 4.0;
+const TOAST_FONT_SIZE: f32 = 30.0;
+const CAPTURED_FONT_SIZE: f32 = 20.0;
 
 // Components
 #[derive(Component)]
@@ -54,10 +76,12 @@ struct DoubleClicked;
 struct DrawnTile;
 #[derive(Component)]
 struct OnGameScreen;
+/// Remaining time of the color the board is seen from.
 #[derive(Component)]
-struct PlayerTime(Timer);
+struct PlayerTime;
+/// Remaining time of the other color.
 #[derive(Component)]
-struct OpponentTime(Timer);
+struct OpponentTime;
 #[derive(Component)]
 struct Cord(Coordinate);
 #[derive(Component)]
@@ -73,17 +97,86 @@ struct DrawNewTile;
 #[derive(Component)]
 struct TilePlaceholder;
 #[derive(Component)]
-struct Ai(Agent);
+struct Toast(Timer);
+/// Square flashes in `INVALID_SQUARE_COLOR` until the timer finishes, after
+/// a click that can't be done, e.g. placing the drawn tile off spawn squares.
+#[derive(Component)]
+struct Flash(Timer);
+/// Square of a tile hovered by the pointer. The squares the tile can act on
+/// are highlighted, unless a tile is selected or a drawn tile is deployed.
+#[derive(Component)]
+struct HoverPreview;
+/// Text that shows the opponent is thinking, empty when no search runs.
+#[derive(Component)]
+struct ThinkingText;
+/// Tiles captured by each color, from the graveyard of the game.
+#[derive(Component)]
+struct CapturedText;
+/// Movement diagrams of both sides of the hovered tile, see
+/// `inspect_system`.
+#[derive(Component)]
+struct InspectPanel;
+/// Small text next to a tile, effects on its square or its part in the last
+/// action. Respawned with the tiles.
+#[derive(Component)]
+struct SquareMarker;
 
 // Resources
-struct Game(GameState);
+/// The game, with the clock of timed games. The opponent AI searches in
+/// background, so it is a remote controller of the session.
+struct Game(GameSession);
+/// AI playing the opponent. Owned by the game session, not by an entity, and
+/// replaced when a new game is set up. Shared with the search thread, any
+/// agent will do.
+struct OpponentController(Arc<Mutex<Box<dyn DukeAgent + Send>>>);
+/// Pending AI search of the opponent. Searches run on a copy of the game, so
+/// results are only applied to the position the search was started from.
+/// Replacing or clearing the handle cancels the search.
+#[derive(Default)]
+struct OpponentSearch(Option<SearchHandle>);
+/// Game record file given on the command line, loaded at startup.
+struct GameFile(Option<PathBuf>);
+/// Record loaded from the `GameFile`. The next game set up continues it,
+/// instead of starting a new game.
+#[derive(Default)]
+struct LoadedRecord(Option<GameRecord>);
+/// Why the `GameFile` could not be loaded, shown in the main menu.
+#[derive(Default)]
+pub struct LoadError(pub Option<String>);
 #[derive(PartialEq)]
 enum Turn {
     Player,
     Opponent
 }
 struct TurnTracker(Turn);
+/// Colors played by humans on this device. One in single player games, both
+/// in hotseat games.
+struct HumanColors(Vec<TileColor>);
+/// Color the board is seen from. Squares are laid out for this color, see
+/// `rotate_board_system`.
+struct BoardView(TileColor);
+/// Game time of a timed game, the `now` of the clock of `Game`. The time
+/// limit is the `GameTime` setting of the menu. Game time only moves by what
+/// the session clock charges, so time away is handled by `SessionClock`.
+struct ClockTime(Instant);
+/// How the last game ended, shown on the game over screen.
+pub struct GameResult {
+    pub winner: Winner,
+    pub reason: Option<WinReason>,
+    /// Game state to go back to for a rematch.
+    pub game: AppState,
+    /// Color of the player, or of player one in hotseat games.
+    pub player: TileColor,
+    /// Turns played, see `GameState::turn`.
+    pub turns: u16,
+    /// Time left of Black and White, in timed games.
+    pub times: Option<[Duration; 2]>,
+}
 struct ClickTime(Instant);
+/// Last action done in game, by any player. Drawing a tile is not kept, the
+/// placement after it is.
+#[derive(Default)]
+struct LastAction(Option<Action>);
 enum TileState {
     Normal,
     Drawn,
@@ -93,6 +186,14 @@ enum TileState {
     Commanded,
 }
 
+// Labels
+/// Clock systems, game time is only charged after time away is known.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+enum ClockSystem {
+    Away,
+    Timers,
+}
+
 // Events
 struct ClearBoardEvent;
 struct UpdateBoardEvent;
@@ -118,25 +219,77 @@ impl Plugin for GamePlugin {
         app
         .add_event::<ClearBoardEvent>()
         .add_event::<UpdateBoardEvent>()
-        .insert_resource(Game(
-            GameState::new(
-                //
-            )
-        ))
-        .insert_resource(TurnTracker)
+        .insert_resource(Game(GameSession::new(Variant::Standard, None, [Controller::Human, Controller::Human])))
+        .insert_resource(TurnTracker(Turn::Player))
         .insert_resource(ClickTime(Instant::now()))
+        .insert_resource(OpponentSearch::default())
+        .insert_resource(LastAction::default())
+        .insert_resource(GameFile(std::env::args_os().nth(1).map(PathBuf::from)))
+        .insert_resource(LoadedRecord::default())
+        .insert_resource(LoadError::default())
+        .add_startup_system(load_game_file)
         .add_system_set(
             SystemSet::on_enter(AppState::SingleplayerGame).with_system(setup_game)
         )
+        // Leaving to main menu ends the game, even if the game state is
+        // still on the state stack below the in game menu.
         .add_system_set(
-            SystemSet::on_update(AppState::SingleplayerMenu)
+            SystemSet::on_enter(AppState::MainMenu).with_system(cancel_opponent_search)
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::SingleplayerGame)
                 .with_system(interaction_system)
-                .with_system(clear_board_effects)
-                .with_system(update_board_system)
+                .with_system(clear_board_effects.after(interaction_system))
+                .with_system(update_board_system.after(clear_board_effects))
                 .with_system(draw_button_system)
+                .with_system(flash_system)
+                .with_system(rotate_board_system)
+                .with_system(away_system.label(ClockSystem::Away))
+                .with_system(
+                    timers_system
+                        .label(ClockSystem::Timers)
+                        .after(ClockSystem::Away)
+                        .after(poll_opponent_search)
+                )
+                .with_system(toast_system)
+                .with_system(start_opponent_search)
+                .with_system(poll_opponent_search.after(start_opponent_search))
+                .with_system(thinking_system.after(poll_opponent_search))
+                .with_system(captured_text_system)
+                .with_system(inspect_system.after(rotate_board_system))
+                .with_system(game_over_system.after(poll_opponent_search).after(ClockSystem::Timers))
         )
         .add_system_set(
             SystemSet::on_exit(AppState::SingleplayerGame)
+                .with_system(despawn_screen::<OnGameScreen>)
+                .with_system(cancel_opponent_search),
+        )
+        // Hotseat, both players on this device and no AI.
+        .add_system_set(
+            SystemSet::on_enter(AppState::MultiplayerGame).with_system(setup_game)
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::MultiplayerGame)
+                .with_system(interaction_system)
+                .with_system(clear_board_effects.after(interaction_system))
+                .with_system(update_board_system.after(clear_board_effects))
+                .with_system(draw_button_system)
+                .with_system(flash_system)
+                .with_system(rotate_board_system.after(interaction_system))
+                .with_system(away_system.label(ClockSystem::Away))
+                .with_system(
+                    timers_system
+                        .label(ClockSystem::Timers)
+                        .after(ClockSystem::Away)
+                        .after(interaction_system)
+                )
+                .with_system(toast_system)
+                .with_system(captured_text_system)
+                .with_system(inspect_system.after(rotate_board_system))
+                .with_system(game_over_system.after(interaction_system).after(ClockSystem::Timers))
+        )
+        .add_system_set(
+            SystemSet::on_exit(AppState::MultiplayerGame)
                 .with_system(despawn_screen::<OnGameScreen>),
         );
     }
@@ -147,10 +300,26 @@ fn setup_game(
     asset_server: Res<AssetServer>,
     app_state: Res<State<AppState>>,
     game_time: Res<GameTime>,
-    player_color: Res<PlayerColor>) {
+    game_time_increment: Res<GameTimeIncrement>,
+    player_color: Res<PlayerColor>,
+    ai_level: Res<AiLevel>,
+    variant: Res<GameVariant>,
+    mut loaded: ResMut<LoadedRecord>) {
 
     let font = asset_server.load(DEFAULT_TEXT_FONT);
 
+    // Search of an earlier game must not deliver into this one.
+    commands.insert_resource(OpponentSearch::default());
+    commands.insert_resource(LastAction::default());
+
+    // Time away is free in casual games, but not when someone is waiting.
+    let away_policy = match app_state.current() {
+        AppState::MultiplayerGame => AwayPolicy::KeepRunning,
+        _ => AwayPolicy::Pause,
+    };
+    commands.insert_resource(SessionClock::new(away_policy, Instant::now()));
+
+
     // Common style for all buttons on the screen
     let button_style = Style {
         min_size: Size::new(Val::Px(32.0), Val::Px(32.0)),
@@ -190,23 +359,59 @@ fn setup_game(
     // If the game logic was implemented in ECS, things would be different.
     // commands.spawn().insert(GameLogic("Elaina Proctor".to_string()));
 
-    // Add player
-    commands.spawn().insert(Player).insert(TColor(player_color.0));
-
-    // Add opponent
-    if let AppState::SingleplayerGame = app_state.0 {
-
-        if player_color.0 == TileColor::Black {
-            commands.spawn().insert(Opponent).insert(Ai(logic::ai::alpha_beta::new(
-                TileColor::White,
-                Some(ai_depth),
-                Some(AI_TIMEOUT_MS),
-
-            ));
+    // Add players
+    let player = player_color.0.tile_color();
+    let humans = if let AppState::SingleplayerGame = app_state.current() {
+        commands.spawn().insert(Player).insert(TColor(player)).insert(OnGameScreen);
+        vec![player]
+    }
+    else {
+        // Hotseat, player one is seen first.
+        let colors = vec![player, player.opponent()];
+        for color in colors.iter() {
+            commands.spawn().insert(Player).insert(TColor(*color)).insert(OnGameScreen);
         }
+        colors
+    };
+    // New game of the variant selected in menu.
+    let controllers = [TileColor::Black, TileColor::White].map(|color| {
+        if humans.contains(&color) {
+            Controller::Human
+        }
+        else {
+            Controller::Remote
+        }
+    });
+    let mut game = match loaded.0.take() {
+        Some(record) => GameSession::from_record(record, controllers),
+        None => GameSession::new(variant.0, None, controllers),
+    };
+
+    // No time set in menu means untimed game.
+    if game_time.0.is_zero() {
+        commands.remove_resource::<ClockTime>();
     }
     else {
-        todo!();
+        game.set_clock(GameClock::new(game_time.0, game_time_increment.0));
+        commands.insert_resource(ClockTime(Instant::now()));
+    }
+
+    commands.insert_resource(TurnTracker(turn_of(&game, &humans)));
+    commands.insert_resource(HumanColors(humans));
+    commands.insert_resource(Game(game));
+    // Squares are spawned as seen from Black.
+    commands.insert_resource(BoardView(TileColor::Black));
+
+    // Add opponent
+    if let AppState::SingleplayerGame = app_state.current() {
+        let opponent = player.opponent();
+        commands.spawn().insert(Opponent).insert(TColor(opponent)).insert(OnGameScreen);
+        // Keep the time limit of the level if it is shorter.
+        let mut agent = Agent::from_level(opponent, ai_level.0);
+        let timeout = Duration::from_millis(AI_TIMEOUT_MS as u64);
+        agent.duration = Some(agent.duration.map_or(timeout, |d| d.min(timeout)));
+        let agent = agent.with_opening_book(OpeningBook::standard());
+        commands.insert_resource(OpponentController(Arc::new(Mutex::new(Box::new(agent)))));
     }
 
     // Create game screen
@@ -239,8 +444,13 @@ fn setup_game(
 
                     // Opponent time
                     parent
-                        .spawn_bundle(TextBundle::from_section("00:00", timer_text_style.clone()))
-                        .insert(OpponentTime(Timer::new(game_time.0, false)));
+                        .spawn_bundle(TextBundle::from_section("", timer_text_style.clone()))
+                        .insert(OpponentTime);
+
+                    // Opponent thinking
+                    parent
+                        .spawn_bundle(TextBundle::from_section("", timer_text_style.clone()))
+                        .insert(ThinkingText);
 
                     // Menu hamburger button
                     parent
@@ -333,8 +543,33 @@ fn setup_game(
 
             // Player time
             parent
-                .spawn_bundle(TextBundle::from_section("00:00", timer_text_style.clone()))
-                .insert(PlayerTime(Timer::new(game_time.0, false)));
+                .spawn_bundle(TextBundle::from_section("", timer_text_style.clone()))
+                .insert(PlayerTime);
+
+            // Captured tiles
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: CAPTURED_FONT_SIZE,
+                        ..timer_text_style.clone()
+                    },
+                ))
+                .insert(CapturedText);
+
+            // Both sides of the hovered tile
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Px(5.0)),
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::FlexStart,
+                        ..default()
+                    },
+                    color: Color::NONE.into(),
+                    ..default()
+                })
+                .insert(InspectPanel);
         });
 }
 
@@ -343,58 +578,92 @@ fn update_board_system(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut ev_update: EventReader<UpdateBoardEvent>,
-    state: Res<GameState>,
+    mut game: ResMut<Game>,
+    turn: Res<TurnTracker>,
+    last_action: Res<LastAction>,
     mut squares_query: Query<
         (
             Entity,
             &Cord, // Square specific.
             Option<&Selected>,
             Option<&Commanded>,
-            Option<&DoubleClicked>,
+            Option<&Flash>,
             Option<&Children>,
             &mut UiColor
         )
     >,
     selected_query: Query<&Cord, With<Selected>>,
-    commanded_query: Query<&Cord, With<Selected>>,
+    commanded_query: Query<&Cord, With<Commanded>>,
+    hover_query: Query<&Cord, With<HoverPreview>>,
     tile_placeholder: Query<(Entity, Option<&Children>), With<TilePlaceholder>>
 ) {
     let font: Handle<Font> = asset_server.load(TILE_TEXT_FONT);
 
-    let state = &state;
-    let board = &state.board;
-
     // Get actions before updating the board.
     let mut actions: Vec<Action> = Vec::new();
 
     if !selected_query.is_empty() {
         // Only get tile actions if a tile is selected
+        // Only legal ones, player may not leave own Duke in check.
         let cord = selected_query.single().0;
-        actions = logic::get_tile_actions(state, cord);
-    } else if !state.drawn().is_empty() {
+        let legal = game.0.current_legal_actions().to_vec();
+        actions = logic::get_tile_actions(&game.0, cord)
+            .into_iter()
+            .filter(|a| legal.contains(a))
+            .collect();
+    } else if !game.0.drawn().is_empty() && turn.0 == Turn::Player {
         // Or get all actions (only contains deploy actions) if new tile is drawn.
-        actions = logic::get_actions(state);
+        // Runs every frame, so only generated when the game changed.
+        actions = game.0.current_legal_actions().to_vec();
     }
 
-    if !state.drawn().is_empty() {
-        let tile = state.drawn().last().unwrap();
-        let ui_tile = create_ui_tile(
-            &mut commands,
-            &asset_server,
-            tile,
-            TileState::Drawn);
-            commands.entity(tile_placeholder.single().0).push_children(&[ui_tile]);
+    let state = &game.0;
+    let commanded_cord = commanded_query.get_single().ok().map(|cord| cord.0);
+
+    // Selection and deployment take precedence over hover preview.
+    let mut preview: Vec<Coordinate> = Vec::new();
+    if actions.is_empty() && selected_query.is_empty() && state.drawn().is_empty() {
+        if let Ok(cord) = hover_query.get_single() {
+            preview = logic::get_tile_targets(state, cord.0);
+        }
     }
-    else {
-        // Remove any drawn tile.
-        if tile_placeholder.single().1 .is_some() {
-            for child in tile_placeholder.single().1.unwrap() {
-                commands.entity(*child).despawn_recursive();
-            }
+
+    // Preview of the drawn tile is spawned once, and despawned when the tile
+    // is placed.
+    let (placeholder, drawn_preview) = tile_placeholder.single();
+    let has_preview = drawn_preview.is_some_and(|children| !children.is_empty());
+    if let Some(tile) = state.drawn().last() {
+        if !has_preview {
+            let ui_tile = create_ui_tile(
+                &mut commands,
+                &asset_server,
+                tile,
+                TileState::Drawn);
+            commands.entity(ui_tile).insert(DrawnTile);
+            commands.entity(placeholder).push_children(&[ui_tile]);
+        }
+    }
+    else if has_preview {
+        for child in drawn_preview.unwrap() {
+            commands.entity(*child).despawn_recursive();
         }
     }
 
-    for (square, cord, selected, commanded, double_clicked, children, mut color) in squares_query.iter_mut() {
+    // Last action is shown until a tile is selected or deployed.
+    let shown_action = last_action.0.as_ref().filter(|_| actions.is_empty());
+
+    // Own tiles the opponent can capture next turn.
+    let threatened: Vec<Coordinate> = if turn.0 == Turn::Player {
+        logic::threatened_squares(state, state.ply.opponent())
+            .into_iter()
+            .map(|(cord, _)| cord)
+            .collect()
+    }
+    else {
+        Vec::new()
+    };
+
+    for (square, cord, selected, commanded, flash, children, mut color) in squares_query.iter_mut() {
 
         // This is not pretty, but works for now. First, remove all tiles and
         // then re-add them. Performance is not really an issue here. I think.
@@ -405,10 +674,20 @@ fn update_board_system(
         }
 
         let cord = cord.0;
-        let tile = board[cord.y as usize][cord.x as usize].tile;
+        let tile = state.square(cord).tile;
 
         let mut ui_tile: Option<Entity> = None;
 
+        // Highlights of earlier updates are gone unless set again below.
+        *color = UiColor::default();
+
+        if shown_action.is_some_and(|a| action_squares(a).contains(&cord)) {
+            *color = LAST_ACTION_SQUARE_COLOR.into();
+        }
+        if threatened.contains(&cord) {
+            *color = THREATENED_SQUARE_COLOR.into();
+        }
+
         // Add tiles and effects.
         for a in actions.iter() {
             match a {
@@ -431,7 +710,19 @@ fn update_board_system(
                             *color = MOVE_SQUARE_COLOR.into();
                         }
                 }
-                Action::Command(cd) if cd.target_pos == cord => {
+                // Command is two stage, first the tiles that can be
+                // commanded, then the targets of the commanded one.
+                Action::Command(cd)
+                    if commanded_cord.is_none() && cd.command_tile_pos == cord && ui_tile.is_none() => {
+                        ui_tile = Some(create_ui_tile(
+                                                &mut commands,
+                                                &asset_server,
+                                                tile.as_ref().unwrap(),
+                                                TileState::Commanded));
+                        commands.entity(square).push_children(&[ui_tile.unwrap()]);
+                }
+                Action::Command(cd)
+                    if commanded_cord == Some(cd.command_tile_pos) && cd.target_pos == cord => {
                     if tile.is_some() {
                         ui_tile = Some(create_ui_tile(
                                                 &mut commands,
@@ -459,43 +750,197 @@ fn update_board_system(
             }
         }
 
-        if tile.is_some() && ui_tile.is_none() {
-
-            if selected.is_some() {
-                    ui_tile = Some(create_ui_tile(
-                                                &mut commands,
-                                                &asset_server,
-                                                tile.as_ref().unwrap(),
-                                                TileState::Selected));
-                    commands.entity(square).push_children(&[ui_tile.unwrap()]);
+        // Tiles without action on them.
+        if let (Some(tile), None) = (tile.as_ref(), ui_tile) {
+            let tile_state = if selected.is_some() {
+                TileState::Selected
             }
-
-            if commanded.is_some() {
-                ui_tile = Some(create_ui_tile(
-                    &mut commands,
-                    &asset_server,
-                    tile.as_ref().unwrap(),
-                    TileState::Commanded));
-                    commands.entity(square).push_children(&[ui_tile.unwrap()]);
+            else if commanded.is_some() {
+                TileState::Commanded
             }
+            else {
+                TileState::Normal
+            };
+            ui_tile = Some(create_ui_tile(
+                &mut commands,
+                &asset_server,
+                tile,
+                tile_state));
+            commands.entity(square).push_children(&[ui_tile.unwrap()]);
+        }
+
+        // Markers after the tile.
+        let mut markers: Vec<String> = state
+            .square(cord)
+            .effects
+            .iter()
+            .map(|(effect, color)| format!("{:?} {:?}", color, effect))
+            .collect();
+        if let Some((_, marker)) = shown_action.and_then(action_marker).filter(|(c, _)| *c == cord) {
+            markers.push(marker.to_string());
+        }
+        for marker in markers {
+            let text = create_square_marker(&mut commands, &asset_server, marker);
+            commands.entity(square).push_children(&[text]);
+        }
+
+        if preview.contains(&cord) {
+            *color = PREVIEW_SQUARE_COLOR.into();
+        }
+        if flash.is_some() {
+            *color = INVALID_SQUARE_COLOR.into();
         }
     }
 }
 
+/// Run the clock of the color to go, and end the game when a time runs out.
+/// The increment is added when the turn passes, see `GameClock::end_turn`.
+/// Clocks stop at game over.
 fn timers_system(
+    mut session: ResMut<SessionClock>,
+    game_time: Option<ResMut<ClockTime>>,
+    mut game: ResMut<Game>,
+    view: Res<BoardView>,
+    mut player_time: Query<&mut Text, (With<PlayerTime>, Without<OpponentTime>)>,
+    mut opponent_time: Query<&mut Text, (With<OpponentTime>, Without<PlayerTime>)>,
+    mut ev_redraw: EventWriter<RequestRedraw>,
+) {
+    // Not Time::delta, app updates are throttled and the app may have slept.
+    let delta = session.sample(Instant::now());
+    let mut game_time = match game_time {
+        Some(game_time) => game_time,
+        None => return,
+    };
+    game_time.0 += delta;
+    let now = game_time.0;
+    game.0.update_clock(now);
+    let clock = match game.0.clock() {
+        Some(clock) => clock,
+        None => return,
+    };
+
+    // Player time is under the board.
+    for (color, texts) in [
+        (view.0, player_time.iter_mut().collect::<Vec<_>>()),
+        (view.0.opponent(), opponent_time.iter_mut().collect::<Vec<_>>()),
+    ] {
+        let value = format!("{:?} {}", color, format_time(clock.remaining(color, now)));
+        for mut text in texts {
+            if text.sections[0].value != value {
+                text.sections[0].value = value.clone();
+            }
+        }
+    }
+
+    // The app only updates on input, the running clock has to be redrawn.
+    if clock.running().is_some() && session.is_focused() {
+        ev_redraw.send(RequestRedraw);
+    }
+}
+
+/// Feeds window focus changes to the session clock, and shows a toast when
+/// the player is back.
+fn away_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut clock: ResMut<SessionClock>,
+    mut ev_focus: EventReader<WindowFocused>,
+) {
+    for event in ev_focus.iter() {
+        if event.focused {
+            clock.focus_gained(Instant::now());
+        }
+        else {
+            clock.focus_lost(Instant::now());
+        }
+    }
+
+    if let Some(resumed) = clock.take_resumed() {
+        info!("{}", resumed);
+        commands
+            .spawn_bundle(
+                TextBundle::from_section(
+                    resumed.to_string(),
+                    TextStyle {
+                        font: asset_server.load(DEFAULT_TEXT_FONT),
+                        font_size: TOAST_FONT_SIZE,
+                        color: TEXT_COLOR,
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    ..default()
+                }),
+            )
+            .insert(Toast(Timer::from_seconds(TOAST_DURATION_S, false)))
+            .insert(OnGameScreen);
+    }
+}
+
+fn toast_system(
+    mut commands: Commands,
     time: Res<Time>,
-    mut player_time: ResMut<PlayerTime>,
-    mut opponent_time: ResMut<OpponentTime>,
-    turn: Res<TurnTracker>
+    mut toasts: Query<(Entity, &mut Toast)>,
 ) {
-    if turn.0 == Turn::Player {
-        player_time.0.tick(time.delta());
+    for (entity, mut toast) in toasts.iter_mut() {
+        if toast.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Whose turn it is in state, from the colors played by humans on this
+/// device. In hotseat games it is always a player's turn.
+fn turn_of(state: &GameState, humans: &[TileColor]) -> Turn {
+    if humans.contains(&state.ply) {
+        Turn::Player
     }
     else {
-        opponent_time.0.tick(time.delta());
+        Turn::Opponent
     }
 }
 
+/// True if the tile on square can be selected, i.e. it is a tile of the
+/// color to go.
+fn can_select(state: &GameState, cord: Coordinate) -> bool {
+    state.tile_at(cord).is_some_and(|tile| tile.color == state.ply)
+}
+
+/// Color the board is seen from. With rotation, a hotseat board turns to the
+/// player to go, otherwise it is seen from the first human color.
+fn view_of(state: &GameState, humans: &[TileColor], rotate: bool) -> TileColor {
+    if rotate && humans.contains(&state.ply) {
+        state.ply
+    }
+    else {
+        humans.first().copied().unwrap_or(TileColor::Black)
+    }
+}
+
+/// Turn the board 180 degrees when the view color changes. Squares keep
+/// their place on screen, their coordinates are mirrored.
+fn rotate_board_system(
+    game: Res<Game>,
+    humans: Res<HumanColors>,
+    rotate: Res<RotateBoard>,
+    mut view: ResMut<BoardView>,
+    mut squares: Query<&mut Cord>,
+    mut ev_clear: EventWriter<ClearBoardEvent>,
+) {
+    let color = view_of(&game.0, &humans.0, rotate.0);
+    if color == view.0 {
+        return;
+    }
+
+    for mut cord in squares.iter_mut() {
+        cord.0 = Coordinate::new(logic::WIDTH - 1 - cord.0.x, logic::HEIGHT - 1 - cord.0.y);
+    }
+    view.0 = color;
+
+    // Selections are on square entities, which now show other squares.
+    ev_clear.send(ClearBoardEvent);
+}
+
 /// Takes input and changes game and UI state. No UI updates are done here.
 /// Should probably be splitted into several systems if one wants to be ECS
 /// purist.
@@ -503,7 +948,9 @@ fn interaction_system(
     mut commands: Commands,
     mut turn: ResMut<TurnTracker>,
     mut game_state: ResMut<Game>,
+    humans: Res<HumanColors>,
     mut click_time: ResMut<ClickTime>,
+    mut last_action: ResMut<LastAction>,
     mut interaction_query: Query<
         (Entity, &Interaction, Option<&Cord>, Option<&GameTile>),
         Changed<Interaction>,
@@ -524,6 +971,11 @@ fn interaction_system(
     let mut selected = None;
     let mut commanded = None;
 
+    // Board is for show only when the game is over.
+    if game.game_over.is_some() {
+        return;
+    }
+
     if !selected_query.is_empty() {
         // FIXME: This is ugly.
         let c = selected_query.single().1;
@@ -534,7 +986,7 @@ fn interaction_system(
         commanded = Some((*c).0);
     }
 
-    for (e, i, c, gt) in interaction_query.iter() {
+    for (e, i, c, _) in interaction_query.iter() {
 
         match i {
 
@@ -558,10 +1010,11 @@ fn interaction_system(
                 // Double clicked?
                 if (now - click_time.0) < Duration::new(0, DOUBLE_CLICK_TIME_NS) {
                     // Clear square components
-                    commands.entity(selected_query.single().0)
-                    .remove::<Selected>();
-                    commands.entity(selected_query.single().0)
-                        .remove::<Commanded>();
+                    if let Ok((selected_square, _)) = selected_query.get_single() {
+                        commands.entity(selected_square)
+                            .remove::<Selected>()
+                            .remove::<Commanded>();
+                    }
                     commands.entity(e).insert(DoubleClicked);
                     return;
                 }
@@ -571,76 +1024,79 @@ fn interaction_system(
                 // If there is a drawn tile, it has to be deployed.
                 if !game.drawn().is_empty() {
 
-                    let actions = logic::get_actions(game);
-
-                    for a in actions {
-                        match a {
-                            Action::PlaceNew(c) if c == cord => {
-                                logic::do_unsafe_action(game, &a);
-
-                                // Opponent turn
-                                turn.0 = Turn::Opponent;
-                                // Opponent turn event?
-                            }
-                            _ => {}
+                    let place = Action::PlaceNew(cord);
+                    if turn.0 == Turn::Player && game.current_legal_actions().contains(&place) {
+                        if let Err(e) = game.submit_action(place) {
+                            warn!("Placement {:?} rejected: {}", place, e);
+                            return;
                         }
+                        last_action.0 = Some(place);
+                        ev_clear.send(ClearBoardEvent);
+                        ev_update.send(UpdateBoardEvent);
+
+                        // Let opponent do her turn.
+                        turn.0 = turn_of(game, &humans.0);
+                    }
+                    else {
+                        // Only highlighted spawn squares take the tile.
+                        commands.entity(e).insert(Flash(Timer::from_seconds(FLASH_DURATION_S, false)));
                     }
                 }
                 else {
 
                     // If a tile is selected and of player color, try to perform action.
-                    if selected.is_some() && turn.0 == Turn::Player {
+                    if let (Some(selected), Turn::Player) = (selected, &turn.0) {
 
                         // If selected, check if current click means an action, if
                         // so, perform the action.
 
-                        let actions = logic::get_tile_actions(game, selected.unwrap());
-
-                        for a in actions.iter() {
-                            match a {
-                                Action::Move(ad)
-                                | Action::Jump(ad)
-                                | Action::Slide(ad)
-                                | Action::JumpSlide(ad)
-                                | Action::Strike(ad)
-                                if ad.target_pos == cord =>
-                                {
-
-                                    logic::do_unsafe_action(game, a);
-
-                                    // Clear components
-                                    ev_clear.send(ClearBoardEvent);
-
-                                    // Let opponent do her turn.
-                                    turn.0 = Turn::Opponent;
-
-                                }
-                                Action::Command(cd) if cd.target_pos == cord => {
-                                    // Command is two stage
-                                    if commanded.is_some() {
-                                        let sc = commanded.unwrap();
-                                        if sc == cd.command_tile_pos {
-                                            logic::do_unsafe_action(game, a);
-
-                                            // Clear square components
-                                            ev_clear.send(ClearBoardEvent);
-
-                                            // Let opponent do her turn.
-                                            turn.0 = Turn::Opponent;
+                        let legal = game.current_legal_actions().to_vec();
+                        let actions: Vec<Action> = logic::get_tile_actions(game, selected)
+                            .into_iter()
+                            .filter(|a| legal.contains(a))
+                            .collect();
+
+                        // Command is two stage, the commanded tile is
+                        // clicked before its target.
+                        let action = actions.iter().find(|a| match a {
+                            Action::Move(ad)
+                            | Action::Jump(ad)
+                            | Action::Slide(ad)
+                            | Action::JumpSlide(ad)
+                            | Action::Strike(ad) => ad.target_pos == cord,
+                            Action::Command(cd) => {
+                                commanded == Some(cd.command_tile_pos) && cd.target_pos == cord
+                            }
+                            _ => false,
+                        });
+                        let commands_tile = actions.iter().any(|a| {
+                            matches!(a, Action::Command(cd) if cd.command_tile_pos == cord)
+                        });
 
-                                        }
-                                    }
-                                }
-                                _ => {
-                                    // No match, clear selected.
-                                    ev_clear.send(ClearBoardEvent);
-                                }
+                        if let Some(a) = action {
+                            if let Err(e) = game.submit_action(*a) {
+                                warn!("Action {:?} rejected: {}", a, e);
+                                return;
                             }
+                            last_action.0 = Some(*a);
+                            ev_clear.send(ClearBoardEvent);
+                            ev_update.send(UpdateBoardEvent);
+
+                            // Let opponent do her turn.
+                            turn.0 = turn_of(game, &humans.0);
+                        }
+                        else if commanded.is_none() && commands_tile {
+                            commands.entity(e).insert(Commanded);
+                        }
+                        else {
+                            // No match, clear selected.
+                            ev_clear.send(ClearBoardEvent);
                         }
                     }
                     else {
-                        // If not selected and tile on square, select.
-                        if gt.is_some() {
+                        // If not selected and tile of the player to go on
+                        // square, select.
+                        if turn.0 == Turn::Player && can_select(game, cord) {
                             // If not selected, select.
                             commands.entity(e).insert(Selected);
                         }
@@ -648,9 +1104,18 @@ fn interaction_system(
                 }
             }
             Interaction::Hovered => {
-                // FIXME: Change square color.
+                // Preview moves of own tiles, see `update_board_system`.
+                if let Some(cord) = c {
+                    if turn.0 == Turn::Player && can_select(game, cord.0) {
+                        commands.entity(e).insert(HoverPreview);
+                    }
+                }
+            }
+            Interaction::None => {
+                if c.is_some() {
+                    commands.entity(e).remove::<HoverPreview>();
+                }
             }
-            _ => {}
         }
     }
 }
@@ -661,13 +1126,37 @@ fn draw_button_system(
         &Interaction,
         (Changed<Interaction>, With<Button>, With<DrawNewTile>),
     >,
-    mut state: ResMut<GameState>
+    mut game: ResMut<Game>,
+    turn: Res<TurnTracker>,
+    mut ev_update: EventWriter<UpdateBoardEvent>,
 ) {
-    if let Interaction::Clicked = interaction_query.single() {
-        for action in get_actions(&state) {
-            if let Action::NewFromBag = action {
-                do_unsafe_action(&mut state, &action);
+    let state = &mut game.0;
+    if state.game_over.is_some() || turn.0 != Turn::Player {
+        return;
+    }
+    for interaction in interaction_query.iter() {
+        // Turn goes on, the drawn tile is placed with a click on a square.
+        if *interaction == Interaction::Clicked
+            && state.current_legal_actions().contains(&Action::NewFromBag)
+        {
+            if let Err(e) = state.submit_action(Action::NewFromBag) {
+                warn!("Draw rejected: {}", e);
+                continue;
             }
+            ev_update.send(UpdateBoardEvent);
+        }
+    }
+}
+
+/// End flashes of squares, see `Flash`.
+fn flash_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut flashes: Query<(Entity, &mut Flash)>,
+) {
+    for (entity, mut flash) in flashes.iter_mut() {
+        if flash.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Flash>();
         }
     }
 }
@@ -675,12 +1164,16 @@ fn draw_button_system(
 // Clear select, actions etc.
 fn clear_board_effects(
     mut commands: Commands,
-    _ev_clear: EventReader<ClearBoardEvent>,
+    mut ev_clear: EventReader<ClearBoardEvent>,
     things: Query<
         Entity,
         With<Cord>,
     >,
 ) {
+    // Only when asked, selections live until then.
+    if ev_clear.iter().count() == 0 {
+        return;
+    }
     for e in things.iter() {
         commands.entity(e).remove::<Selected>();
         commands.entity(e).remove::<Commanded>();
@@ -690,6 +1183,48 @@ fn clear_board_effects(
     }
 }
 
+/// Squares action moved a tile from and to. Placing a tile only has the
+/// spawn square, striking only the target.
+fn action_squares(action: &Action) -> Vec<Coordinate> {
+    match action {
+        Action::NewFromBag => vec![],
+        Action::PlaceNew(cord) => vec![*cord],
+        Action::Move(ad) | Action::Jump(ad) | Action::JumpSlide(ad) | Action::Slide(ad) => {
+            vec![ad.tile_pos, ad.target_pos]
+        }
+        Action::Command(cd) => vec![cd.command_tile_pos, cd.target_pos],
+        Action::Strike(ad) => vec![ad.target_pos],
+    }
+}
+
+/// Square and marker text of the tile that did action without moving, the
+/// commander of a command or the striking tile.
+fn action_marker(action: &Action) -> Option<(Coordinate, &'static str)> {
+    match action {
+        Action::Command(cd) => Some((cd.tile_pos, "Command")),
+        Action::Strike(ad) => Some((ad.tile_pos, "Strike")),
+        _ => None,
+    }
+}
+
+fn create_square_marker(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    text: String,
+) -> Entity {
+    commands
+        .spawn_bundle(TextBundle::from_section(
+            text,
+            TextStyle {
+                font: asset_server.load(TILE_TEXT_FONT),
+                font_size: TILE_SIDE_FONT_SIZE,
+                color: SQUARE_EFFECT_TEXT_COLOR,
+            },
+        ))
+        .insert(SquareMarker)
+        .id()
+}
+
 fn create_ui_tile(
     mut commands: &mut Commands,
     asset_server: &Res<AssetServer>,
@@ -751,6 +1286,15 @@ fn create_ui_tile(
             tile.kind.to_string(),
             tts.clone(),
         ));
+
+        // Side marker, I for front and II for back.
+        parent.spawn_bundle(TextBundle::from_section(
+            if tile.flipped { "II" } else { "I" },
+            TextStyle {
+                font_size: TILE_SIDE_FONT_SIZE,
+                ..tts.clone()
+            },
+        ));
     })
     .insert(GameTile)
     .id();
@@ -758,43 +1302,338 @@ fn create_ui_tile(
     ui_tile
 }
 
-fn opponent_turn(
-    mut _ev_opponent_turn: EventReader<OpponentTurn>,
-    state: Res<State<AppState>>,
-    mut game_state: ResMut<Game>,
-    opponent: Query<Option<&Ai>, With<Opponent>>
+/// Start AI search when it is the opponent's turn and no search is running.
+fn start_opponent_search(
+    game: Res<Game>,
+    turn: Res<TurnTracker>,
+    controller: Option<Res<OpponentController>>,
+    mut search: ResMut<OpponentSearch>,
 ) {
+    if turn.0 != Turn::Opponent || search.0.is_some() || game.0.game_over.is_some() {
+        return;
+    }
+    let agent = match controller {
+        Some(controller) => controller.0.clone(),
+        None => return,
+    };
 
-    let mut state = &mut game_state.0;
+    // A cancelled search still holds the agent for a moment, the next one
+    // waits for it.
+    search.0 = Some(SearchHandle::spawn(&game.0, move |state, token| {
+        agent.lock().expect("Opponent search panicked.").choose_action_cancellable(state, token)
+    }));
+}
 
-    match state {
-        AppState::SingleplayerGame => {
+/// Apply result of AI search. Results for any other position than the live
+/// game are dropped, the game may have been reset while searching.
+fn poll_opponent_search(
+    mut game: ResMut<Game>,
+    mut turn: ResMut<TurnTracker>,
+    humans: Res<HumanColors>,
+    mut search: ResMut<OpponentSearch>,
+    mut last_action: ResMut<LastAction>,
+    mut ev_update: EventWriter<UpdateBoardEvent>,
+) {
+    let handle = match &search.0 {
+        Some(handle) => handle,
+        None => return,
+    };
 
-            let a = alpha_beta::get_action(opponent.single().unwrap(), state);
+    // Game ended while searching, e.g. player resigned.
+    if game.0.game_over.is_some() {
+        search.0 = None;
+        return;
+    }
 
-            if a.is_none() {
-                // This means game over. But don't do anything now.
-                return Ok(());
+    match handle.poll(&game.0) {
+        SearchPoll::Pending => return,
+        SearchPoll::Done(Some(action)) => {
+            if let Err(e) = game.0.submit_action(action) {
+                error!("AI action {:?} rejected: {}", action, e);
+                return;
             }
+            if action != Action::NewFromBag {
+                last_action.0 = Some(action);
+            }
+
+            // Drawn tile is placed by the next search, turn goes on.
+            turn.0 = turn_of(&game.0, &humans.0);
+            ev_update.send(UpdateBoardEvent);
+        }
+        SearchPoll::Done(None) => {
+            // Nothing to do, game is over.
+        }
+        SearchPoll::Stale => {
+            info!("Dropped AI result for a position that is not in game anymore.");
+        }
+        SearchPoll::Lost => {
+            error!("AI search ended without result.");
+        }
+    }
+
+    search.0 = None;
+}
 
-            let mut a = a.unwrap();
+/// Show thinking text while AI search is pending. The dots move, so a long
+/// search can be told apart from a stuck UI.
+///
+/// The app only updates on input, see `WinitSettings::desktop_app`. Redraws
+/// are requested while searching, so the search is polled and the text moves
+/// without the player touching anything.
+fn thinking_system(
+    search: Res<OpponentSearch>,
+    time: Res<Time>,
+    mut texts: Query<&mut Text, With<ThinkingText>>,
+    mut ev_redraw: EventWriter<RequestRedraw>,
+) {
+    let value = if search.0.is_some() {
+        ev_redraw.send(RequestRedraw);
+        let dots = (time.seconds_since_startup() * 2.0) as usize % 4;
+        format!("Thinking{:<3}", ".".repeat(dots))
+    }
+    else {
+        String::new()
+    };
 
-            logic::do_unsafe_action(state, &a);
+    for mut text in texts.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
 
-            // New from bag action is 2 stage
-            match a {
-                Action::NewFromBag => {
-                    a = alpha_beta::get_action(agent, state).expect("AI is unable to deploy drawn tile.");
-                    logic::do_unsafe_action(state, &a);
-                }
-                _ => {}
+/// Show front and back of the tile on the hovered square, as the player
+/// sees them. Rebuilt only when the hovered tile or the view changes.
+fn inspect_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game: Res<Game>,
+    view: Res<BoardView>,
+    squares: Query<(&Interaction, &Cord)>,
+    panels: Query<(Entity, Option<&Children>), With<InspectPanel>>,
+    mut shown: Local<Option<(Tile, TileColor)>>,
+) {
+    let hovered = squares
+        .iter()
+        .find(|(interaction, _)| **interaction != Interaction::None)
+        .and_then(|(_, cord)| game.0.tile_at(cord.0).copied())
+        .map(|tile| (tile, view.0));
+    if hovered == *shown {
+        return;
+    }
+    *shown = hovered;
+
+    let font: Handle<Font> = asset_server.load(TILE_TEXT_FONT);
+    for (panel, children) in panels.iter() {
+        if let Some(children) = children {
+            for child in children.iter() {
+                commands.entity(*child).despawn_recursive();
             }
         }
-        AppState::MultiplayerGame => {
-            todo!();
+        let (tile, view) = match hovered {
+            Some(hovered) => hovered,
+            None => continue,
+        };
+        commands.entity(panel).with_children(|parent| {
+            for flipped in [false, true] {
+                let title = format!(
+                    "{} {}{}",
+                    tile.kind,
+                    if flipped { "back" } else { "front" },
+                    if flipped == tile.flipped { " (up)" } else { "" }
+                );
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            margin: UiRect::all(Val::Px(5.0)),
+                            flex_direction: FlexDirection::ColumnReverse,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        color: Color::NONE.into(),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn_bundle(TextBundle::from_section(
+                            title,
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: TILE_TEXT_FONT_SIZE,
+                                color: TEXT_COLOR,
+                            },
+                        ));
+                        let actions = logic::tile_movement_grid(tile.kind, flipped, tile.color);
+                        spawn_diagram(parent, &actions, view, font.clone());
+                    });
+            }
+        });
+    }
+}
+
+/// Captured tiles of each color and material balance, one line each.
+fn captured_text(state: &GameState) -> String {
+    let mut lines = Vec::new();
+    for color in [TileColor::Black, TileColor::White] {
+        let tiles: Vec<String> = state
+            .captures_by(color)
+            .iter()
+            .map(|c| c.tile.kind.to_string())
+            .collect();
+        lines.push(format!("{:?} captured: {}", color, tiles.join(", ")));
+    }
+    let balance = state.material_balance();
+    if balance > 0 {
+        lines.push(format!("Material: Black +{}", balance));
+    }
+    else if balance < 0 {
+        lines.push(format!("Material: White +{}", -balance));
+    }
+    lines.join("\n")
+}
+
+fn captured_text_system(game: Res<Game>, mut texts: Query<&mut Text, With<CapturedText>>) {
+    if !game.is_changed() {
+        return;
+    }
+    let value = captured_text(&game.0);
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
+
+/// Go to the game over screen when the game is over, after the action of
+/// the frame is applied. The screen is pushed on top of the game, so the
+/// final position stays visible and the game systems stop.
+fn game_over_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    humans: Res<HumanColors>,
+    game_time: Option<Res<ClockTime>>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    let winner = match &game.0.game_over {
+        Some(winner) => winner.clone(),
+        None => return,
+    };
+    let game_mode = app_state.current().clone();
+
+    // Another state change of the frame wins, e.g. leaving to the menu.
+    if let Err(e) = app_state.push(AppState::GameOver) {
+        warn!("Game over screen not shown: {:?}", e);
+        return;
+    }
+    let times = game.0.clock().zip(game_time).map(|(clock, game_time)| {
+        [TileColor::Black, TileColor::White]
+            .map(|color| clock.remaining(color, game_time.0))
+    });
+    commands.insert_resource(GameResult {
+        winner,
+        reason: game.0.win_reason,
+        game: game_mode,
+        player: humans.0.first().copied().unwrap_or(TileColor::Black),
+        turns: game.0.turn,
+        times,
+    });
+}
+
+/// Load error as shown to the player. Unknown tiles are most likely of a
+/// newer version, not a broken file.
+fn load_error_message(e: &RecordError) -> String {
+    match e {
+        RecordError::UnsupportedContent(_, name) => {
+            format!("This game uses tiles from a newer version or a different ruleset ({}).", name)
         }
-        _ => {
-            panic!("Illegal state.")
+        e => e.to_string(),
+    }
+}
+
+/// Load the record of the `GameFile`. The current game is left as it is, the
+/// record is continued by the next game set up.
+fn load_game_file(file: Res<GameFile>, mut loaded: ResMut<LoadedRecord>, mut load_error: ResMut<LoadError>) {
+    let path = match &file.0 {
+        Some(path) => path,
+        None => return,
+    };
+    match std::fs::read_to_string(path) {
+        Ok(text) => match GameRecord::from_text(&text) {
+            Ok(record) => loaded.0 = Some(record),
+            Err(e) => load_error.0 = Some(format!("Unable to load {}: {}", path.display(), load_error_message(&e))),
+        },
+        Err(e) => load_error.0 = Some(format!("Unable to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Cancel pending AI search, when the game is left.
+fn cancel_opponent_search(mut search: ResMut<OpponentSearch>) {
+    search.0 = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusty_duke_logic::ai::random::RandomAgent;
+
+    fn session(seed: u64) -> GameSession {
+        GameSession::new(Variant::Standard, Some(seed), [Controller::Human, Controller::Remote])
+    }
+
+    #[test]
+    fn search_of_an_earlier_game_is_dropped() {
+        // Start positions hash the same, the earlier game has moved on.
+        let mut earlier = session(1);
+        let action = RandomAgent::new_seeded(TileColor::Black, 1).choose_action(&earlier).unwrap();
+        earlier.submit_action(action).unwrap();
+        let handle = SearchHandle::spawn(&earlier, |state, _| {
+            RandomAgent::new_seeded(state.ply, 2).choose_action(state)
+        });
+
+        // Result arrives after a new game was set up.
+        let mut world = World::new();
+        world.insert_resource(Game(session(2)));
+        world.insert_resource(TurnTracker(Turn::Opponent));
+        world.insert_resource(HumanColors(vec![TileColor::Black]));
+        world.insert_resource(OpponentSearch(Some(handle)));
+        world.insert_resource(LastAction::default());
+        world.insert_resource(Events::<UpdateBoardEvent>::default());
+        let mut stage = SystemStage::single_threaded().with_system(poll_opponent_search);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while world.resource::<OpponentSearch>().0.is_some() {
+            assert!(Instant::now() < deadline, "Search did not end.");
+            stage.run(&mut world);
         }
+
+        assert!(world.resource::<Game>().0.record().is_empty());
+        assert!(world.resource::<LastAction>().0.is_none());
+        assert!(world.resource::<TurnTracker>().0 == Turn::Opponent);
+        assert!(world.resource::<Events<UpdateBoardEvent>>().is_empty());
+    }
+
+    #[test]
+    fn failed_load_leaves_the_game_unchanged() {
+        let mut game = session(1);
+        let action = game.current_legal_actions()[0];
+        game.submit_action(action).unwrap();
+        let record = game.record().clone();
+        let hash = game.hash();
+
+        // Record of a newer version, with a tile this version doesn't know.
+        let path = std::env::temp_dir().join(format!("rusty-duke-bevy-load-{}.txt", std::process::id()));
+        let text = record.to_text();
+        assert!(text.contains(" Duke"), "{}", text);
+        std::fs::write(&path, text.replace("Duke", "Gawain")).unwrap();
+
+        let mut world = World::new();
+        world.insert_resource(Game(game));
+        world.insert_resource(GameFile(Some(path.clone())));
+        world.insert_resource(LoadedRecord::default());
+        world.insert_resource(LoadError::default());
+        SystemStage::single_threaded().with_system(load_game_file).run(&mut world);
+        std::fs::remove_file(&path).unwrap();
+
+        let error = world.resource::<LoadError>().0.clone().unwrap();
+        assert!(error.ends_with("This game uses tiles from a newer version or a different ruleset (Gawain)."), "{}", error);
+        assert!(world.resource::<LoadedRecord>().0.is_none());
+        assert!(*world.resource::<Game>().0.record() == record);
+        assert_eq!(world.resource::<Game>().0.hash(), hash);
     }
-}
\ No newline at end of file
+}
@@ -1,5 +1,7 @@
 use bevy::{prelude::*, winit::WinitSettings};
 
+mod clock;
+mod diagram;
 mod game;
 mod menu;
 
@@ -19,6 +21,8 @@ enum AppState {
     InGameMenu,
     SingleplayerGame,
     MultiplayerGame,
+    /// Result of the last game, see `game::GameResult`.
+    GameOver,
 }
 
 fn main() {
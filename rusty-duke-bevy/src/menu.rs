@@ -1,14 +1,19 @@
 use std::time::Duration;
 
 use crate::*;
+use crate::game::{GameResult, LoadError};
 use bevy::app::AppExit;
-use rusty_duke_logic::logic::{self, TileColor};
+use rusty_duke_logic::ai::alpha_beta::{Agent, MAX_LEVEL, MIN_LEVEL};
+use rusty_duke_logic::ai::calibrate::{self, Calibration, Difficulty, HardwareFingerprint};
+use rusty_duke_logic::logic::{clock::format_time, variant::Variant, TileColor, WinReason, Winner};
 
 
 // Much of the code in this file is derived from the Bevy 0.7 game_menu example.
 const MIN_AI_LEVEL: u8 = 2;
 const MIN_GAME_TIME: u8 = 0;
 const MIN_GAME_TIME_INCREMENT: u8 = 0;
+/// Translucent, the final position shows through the game over screen.
+const GAME_OVER_SHADE_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
 
 // Components
 #[derive(Component)]
@@ -25,6 +30,9 @@ pub enum MenuButtonAction {
     DecreaseGameTime,
     IncreaseGameTimeIncrement,
     DecreaseGameTimeIncrement,
+    SwitchVariant,
+    Rematch,
+    ToggleRotateBoard,
 }
 #[derive(Component)]
 struct OnMainMenuScreen;
@@ -34,6 +42,20 @@ struct OnSingleplayerMenuScreen;
 struct OnMultiplayerMenuScreen;
 #[derive(Component)]
 struct OnInGameMenuScreen;
+#[derive(Component)]
+struct OnGameOverScreen;
+/// Text of the board rotation toggle.
+#[derive(Component)]
+struct RotateBoardText;
+/// Text of the game time setting.
+#[derive(Component)]
+struct GameTimeText;
+/// Text of the game time increment setting.
+#[derive(Component)]
+struct GameTimeIncrementText;
+/// Button of the value a setting has, see `setting_button`.
+#[derive(Component)]
+struct SelectedOption;
 
 // Resources
 #[derive(Debug)]
@@ -42,12 +64,73 @@ pub struct AiLevel(pub u8);
 pub struct GameTime(pub Duration);
 #[derive(Debug)]
 pub struct GameTimeIncrement(pub Duration);
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ColorSetting {
     BLACK,
     WHITE,
     RANDOM
 }
+
+impl ColorSetting {
+    pub fn of(color: TileColor) -> ColorSetting {
+        match color {
+            TileColor::Black => ColorSetting::BLACK,
+            TileColor::White => ColorSetting::WHITE,
+        }
+    }
+
+    /// Color to play. `RANDOM` picks a new one on each call.
+    pub fn tile_color(&self) -> TileColor {
+        match self {
+            ColorSetting::BLACK => TileColor::Black,
+            ColorSetting::WHITE => TileColor::White,
+            ColorSetting::RANDOM => {
+                // Std hashers are randomly seeded, no need for a rand crate.
+                use std::hash::{BuildHasher, Hasher};
+                let random = std::collections::hash_map::RandomState::new().build_hasher().finish();
+                if random & 1 == 0 { TileColor::Black } else { TileColor::White }
+            }
+        }
+    }
+}
+
+/// Color of the player, or of player one in hotseat games. Also the
+/// component of the buttons that set it.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
 pub struct PlayerColor(pub ColorSetting);
+#[derive(Debug)]
+pub struct GameVariant(pub Variant);
+/// Turn the board to the player to go in hotseat games.
+#[derive(Debug)]
+pub struct RotateBoard(pub bool);
+
+/// Normal difficulty depth of the stored calibration. Calibrates on first
+/// run, the benchmark takes a couple of seconds.
+fn default_ai_level() -> u8 {
+    let path = std::path::Path::new(calibrate::DEFAULT_CALIBRATION_FILE);
+    let calibration = match Calibration::load(path) {
+        Ok(Some(calibration)) => {
+            if calibration.needs_recalibration(&HardwareFingerprint::measure()) {
+                warn!("Hardware has changed since AI calibration, delete {} to recalibrate.", path.display());
+            }
+            calibration
+        }
+        Ok(None) | Err(_) => {
+            let calibration = calibrate::calibrate(calibrate::BENCHMARK_BUDGET);
+            if let Err(e) = calibration.save(path) {
+                warn!("Could not save AI calibration: {}", e);
+            }
+            calibration
+        }
+    };
+    // Highest level that searches no deeper than the calibrated depth.
+    let depth = calibration.depth_for_difficulty(Difficulty::Normal);
+    (MIN_LEVEL..=MAX_LEVEL)
+        .rev()
+        .find(|level| Agent::from_level(TileColor::Black, *level).depth <= Some(depth))
+        .unwrap_or(MIN_LEVEL)
+        .max(MIN_AI_LEVEL)
+}
 
 // Plugins
 pub struct MenuPlugin;
@@ -56,10 +139,12 @@ impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app
             // FIXME: Use menu sub-states
-            .insert_resource(AiLevel(6))
+            .insert_resource(AiLevel(default_ai_level()))
             .insert_resource(GameTime(Duration::from_secs(15 * 60)))
             .insert_resource(GameTimeIncrement(Duration::from_secs(0)))
             .insert_resource(PlayerColor(ColorSetting::BLACK))
+            .insert_resource(GameVariant(Variant::Standard))
+            .insert_resource(RotateBoard(true))
 
             // Main menu
             .add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(setup_main_menu))
@@ -87,8 +172,21 @@ impl Plugin for MenuPlugin {
                     .with_system(despawn_screen::<OnSingleplayerMenuScreen>),
             )
             // Multiplayer menu
-            /*.add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(setup_main_menu))
-            .add_system_set(SystemSet::on_exit(AppState::MainMenu).with_system(update_main_menu))*/
+            .add_system_set(
+                SystemSet::on_enter(AppState::MultiplayerMenu).with_system(setup_mp_menu),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::MultiplayerMenu)
+                    .with_system(menu_action)
+                    .with_system(button_system)
+                    .with_system(setting_button::<PlayerColor>)
+                    .with_system(rotate_board_text)
+                    .with_system(game_time_text),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::MultiplayerMenu)
+                    .with_system(despawn_screen::<OnMultiplayerMenuScreen>),
+            )
             // Ingame menu
             .add_system_set(SystemSet::on_enter(AppState::InGameMenu).with_system(setup_main_menu))
             .add_system_set(
@@ -99,11 +197,24 @@ impl Plugin for MenuPlugin {
             .add_system_set(
                 SystemSet::on_exit(AppState::InGameMenu)
                     .with_system(despawn_screen::<OnInGameMenuScreen>),
+            )
+            // Game over
+            .add_system_set(
+                SystemSet::on_enter(AppState::GameOver).with_system(setup_game_over_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::GameOver)
+                    .with_system(menu_action)
+                    .with_system(button_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::GameOver)
+                    .with_system(despawn_screen::<OnGameOverScreen>),
             );
     }
 }
 
-fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>, mut load_error: ResMut<LoadError>) {
     let font = asset_server.load("fonts/FiraSans-Bold.ttf");
 
     // Common style for all buttons on the screen
@@ -188,6 +299,141 @@ fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                 .with_children(|parent| {
                     parent.spawn_bundle(TextBundle::from_section("Quit", button_text_style.clone()));
                 });
+            // Shown once, the menu is set up again after each game.
+            if let Some(error) = load_error.0.take() {
+                parent.spawn_bundle(TextBundle::from_section(
+                    error,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 20.0,
+                        color: TEXT_COLOR,
+                    },
+                ));
+            }
+        });
+}
+
+/// Result as shown to the player, e.g. "White wins by guardmate".
+fn result_text(result: &GameResult) -> String {
+    let winner = match result.winner {
+        Winner::Color(color) => format!("{:?} wins", color),
+        Winner::Draw => "Draw".to_string(),
+    };
+    let reason = match result.reason {
+        Some(WinReason::DukeCaptured) => "Duke captured",
+        Some(WinReason::Guardmate) => "guardmate",
+        Some(WinReason::NoMoves) => "no moves left",
+        Some(WinReason::TurnLimitAdjudication(_)) => "turn limit",
+        Some(WinReason::Repetition) => "repetition",
+        Some(WinReason::NoCaptureLimit) => "no captures",
+        Some(WinReason::Timeout) => "time out",
+        Some(WinReason::Resignation) => "resignation",
+        Some(WinReason::DrawAgreed) => "agreement",
+        Some(WinReason::Adjudicated) => "adjudication",
+        None => return winner,
+    };
+    format!("{} by {}", winner, reason)
+}
+
+/// Turns played and time left, e.g. "42 turns, Black 03:10, White 00:00".
+fn result_details(result: &GameResult) -> String {
+    let mut details = format!("{} turns", result.turns);
+    if let Some(times) = result.times {
+        for color in [TileColor::Black, TileColor::White] {
+            details.push_str(&format!(", {:?} {}", color, format_time(times[color as usize])));
+        }
+    }
+    details
+}
+
+fn setup_game_over_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    result: Res<GameResult>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    let button_style = Style {
+        size: Size::new(Val::Px(250.0), Val::Px(65.0)),
+        margin: UiRect::all(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    let button_text_style = TextStyle {
+        font: font.clone(),
+        font_size: 40.0,
+        color: TEXT_COLOR,
+    };
+
+    // Darkens the final position of the game below.
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: GAME_OVER_SHADE_COLOR.into(),
+            ..default()
+        })
+        .insert(OnGameOverScreen)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Auto),
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    color: Color::CRIMSON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle::from_section(
+                        "Game over",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 80.0,
+                            color: TEXT_COLOR,
+                        },
+                    ));
+                    parent.spawn_bundle(TextBundle::from_section(
+                        result_text(&result),
+                        button_text_style.clone(),
+                    ));
+                    parent.spawn_bundle(TextBundle::from_section(
+                        result_details(&result),
+                        TextStyle {
+                            font_size: 25.0,
+                            ..button_text_style.clone()
+                        },
+                    ));
+                    parent
+                        .spawn_bundle(ButtonBundle {
+                            style: button_style.clone(),
+                            color: NORMAL_BUTTON_COLOR.into(),
+                            ..default()
+                        })
+                        .insert(MenuButtonAction::Rematch)
+                        .with_children(|parent| {
+                            parent.spawn_bundle(TextBundle::from_section("Rematch", button_text_style.clone()));
+                        });
+                    parent
+                        .spawn_bundle(ButtonBundle {
+                            style: button_style.clone(),
+                            color: NORMAL_BUTTON_COLOR.into(),
+                            ..default()
+                        })
+                        .insert(MenuButtonAction::MainMenu)
+                        .with_children(|parent| {
+                            parent.spawn_bundle(TextBundle::from_section("Main Menu", button_text_style.clone()));
+                        });
+                });
         });
 }
 
@@ -197,6 +443,7 @@ fn setup_singleplayer_menu(
     ai_level: Res<AiLevel>,
     game_time: Res<GameTime>,
     game_time_increment: Res<GameTimeIncrement>,
+    variant: Res<GameVariant>,
 ) {
     let font = asset_server.load("fonts/FiraSans-Bold.ttf");
 
@@ -245,6 +492,44 @@ fn setup_singleplayer_menu(
         .insert(OnSingleplayerMenuScreen)
         .with_children(|parent| {
 
+            // Game variant
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Auto),
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    color: Color::CRIMSON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    // Label
+                    parent.spawn_bundle(TextBundle::from_section(
+                        "Variant",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 80.0,
+                            color: TEXT_COLOR,
+                        },
+                    ));
+
+                    // Value, click to switch
+                    parent
+                        .spawn_bundle(ButtonBundle {
+                            style: button_style.clone(),
+                            color: NORMAL_BUTTON_COLOR.into(),
+                            ..default()
+                        })
+                        .insert(MenuButtonAction::SwitchVariant)
+                        .with_children(|parent| {
+                            parent.spawn_bundle(TextBundle::from_section(
+                                format!("{}", variant.0),
+                                button_text_style.clone(),
+                            ));
+                        });
+                });
 
             // Player color
             parent
@@ -536,8 +821,251 @@ fn setup_singleplayer_menu(
         });
 }
 
-fn setup_mp_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
-    todo!();
+/// Text of the board rotation toggle for setting.
+fn rotate_board_label(rotate: &RotateBoard) -> String {
+    format!("Rotate board: {}", if rotate.0 { "On" } else { "Off" })
+}
+
+/// Text of the game time setting, e.g. "Game time 15 min".
+fn game_time_label(game_time: &GameTime) -> String {
+    format!("Game time {} min", game_time.0.as_secs() / 60)
+}
+
+/// Text of the game time increment setting, e.g. "Increment 3 s".
+fn game_time_increment_label(increment: &GameTimeIncrement) -> String {
+    format!("Increment {} s", increment.0.as_secs())
+}
+
+/// Menu of hotseat games. Player one picks a color, player two gets the
+/// other one.
+fn setup_mp_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_time: Res<GameTime>,
+    game_time_increment: Res<GameTimeIncrement>,
+    variant: Res<GameVariant>,
+    rotate: Res<RotateBoard>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    // Common style for all buttons on the screen
+    let button_style = Style {
+        size: Size::new(Val::Px(250.0), Val::Px(65.0)),
+        margin: UiRect::all(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    let button_text_style = TextStyle {
+        font: font.clone(),
+        font_size: 40.0,
+        color: TEXT_COLOR,
+    };
+
+    let row_style = Style {
+        margin: UiRect::all(Val::Auto),
+        flex_direction: FlexDirection::Row,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                margin: UiRect::all(Val::Auto),
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::CRIMSON.into(),
+            ..default()
+        })
+        .insert(OnMultiplayerMenuScreen)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle::from_section(
+                "Hotseat",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 80.0,
+                    color: TEXT_COLOR,
+                },
+            ));
+
+            // Game variant
+            parent
+                .spawn_bundle(ButtonBundle {
+                    style: button_style.clone(),
+                    color: NORMAL_BUTTON_COLOR.into(),
+                    ..default()
+                })
+                .insert(MenuButtonAction::SwitchVariant)
+                .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle::from_section(
+                        format!("{}", variant.0),
+                        button_text_style.clone(),
+                    ));
+                });
+
+            // Color of player one
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: row_style.clone(),
+                    color: Color::CRIMSON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle::from_section(
+                        "Player one",
+                        button_text_style.clone(),
+                    ));
+                    parent.spawn_bundle(ButtonBundle {
+                        style: button_style.clone(),
+                        color: game::BLACK_TILE_COLOR.into(),
+                        ..default()
+                    })
+                    .insert(PlayerColor(ColorSetting::BLACK))
+                    .with_children(|parent| {
+                        parent.spawn_bundle(TextBundle::from_section("BLACK", button_text_style.clone()));
+                    });
+                    parent.spawn_bundle(ButtonBundle {
+                        style: button_style.clone(),
+                        color: game::WHITE_TILE_COLOR.into(),
+                        ..default()
+                    })
+                    .insert(PlayerColor(ColorSetting::WHITE))
+                    .with_children(|parent| {
+                        parent.spawn_bundle(TextBundle::from_section("WHITE", button_text_style.clone()));
+                    });
+                });
+
+            // Game time, 0 is no clock
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: row_style.clone(),
+                    color: Color::CRIMSON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(TextBundle::from_section(
+                            game_time_label(&game_time),
+                            button_text_style.clone(),
+                        ))
+                        .insert(GameTimeText);
+                    for (action, label) in [
+                        (MenuButtonAction::IncreaseGameTime, "Up"),
+                        (MenuButtonAction::DecreaseGameTime, "Down"),
+                    ] {
+                        parent
+                            .spawn_bundle(ButtonBundle {
+                                style: button_style.clone(),
+                                color: NORMAL_BUTTON_COLOR.into(),
+                                ..default()
+                            })
+                            .insert(action)
+                            .with_children(|parent| {
+                                parent.spawn_bundle(TextBundle::from_section(label, button_text_style.clone()));
+                            });
+                    }
+                });
+
+            // Increment, added to the clock after each turn
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: row_style.clone(),
+                    color: Color::CRIMSON.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(TextBundle::from_section(
+                            game_time_increment_label(&game_time_increment),
+                            button_text_style.clone(),
+                        ))
+                        .insert(GameTimeIncrementText);
+                    for (action, label) in [
+                        (MenuButtonAction::IncreaseGameTimeIncrement, "Up"),
+                        (MenuButtonAction::DecreaseGameTimeIncrement, "Down"),
+                    ] {
+                        parent
+                            .spawn_bundle(ButtonBundle {
+                                style: button_style.clone(),
+                                color: NORMAL_BUTTON_COLOR.into(),
+                                ..default()
+                            })
+                            .insert(action)
+                            .with_children(|parent| {
+                                parent.spawn_bundle(TextBundle::from_section(label, button_text_style.clone()));
+                            });
+                    }
+                });
+
+            // Board rotation
+            parent
+                .spawn_bundle(ButtonBundle {
+                    style: button_style.clone(),
+                    color: NORMAL_BUTTON_COLOR.into(),
+                    ..default()
+                })
+                .insert(MenuButtonAction::ToggleRotateBoard)
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(TextBundle::from_section(
+                            rotate_board_label(&rotate),
+                            button_text_style.clone(),
+                        ))
+                        .insert(RotateBoardText);
+                });
+
+            for (action, label) in [
+                (MenuButtonAction::Play, "Play"),
+                (MenuButtonAction::MainMenu, "Main Menu"),
+            ] {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: button_style.clone(),
+                        color: NORMAL_BUTTON_COLOR.into(),
+                        ..default()
+                    })
+                    .insert(action)
+                    .with_children(|parent| {
+                        parent.spawn_bundle(TextBundle::from_section(label, button_text_style.clone()));
+                    });
+            }
+        });
+}
+
+/// Keep the rotation toggle text in sync with the setting.
+fn rotate_board_text(
+    rotate: Res<RotateBoard>,
+    mut texts: Query<&mut Text, With<RotateBoardText>>,
+) {
+    if !rotate.is_changed() {
+        return;
+    }
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = rotate_board_label(&rotate);
+    }
+}
+
+/// Keep the game time texts in sync with the settings.
+fn game_time_text(
+    game_time: Res<GameTime>,
+    game_time_increment: Res<GameTimeIncrement>,
+    mut time_texts: Query<&mut Text, (With<GameTimeText>, Without<GameTimeIncrementText>)>,
+    mut increment_texts: Query<&mut Text, (With<GameTimeIncrementText>, Without<GameTimeText>)>,
+) {
+    if game_time.is_changed() {
+        for mut text in time_texts.iter_mut() {
+            text.sections[0].value = game_time_label(&game_time);
+        }
+    }
+    if game_time_increment.is_changed() {
+        for mut text in increment_texts.iter_mut() {
+            text.sections[0].value = game_time_increment_label(&game_time_increment);
+        }
+    }
 }
 
 fn setup_in_game_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
@@ -662,9 +1190,11 @@ fn setting_button<T: Component + PartialEq + Copy>(
 ) {
     for (interaction, button_setting, entity) in &interaction_query {
         if *interaction == Interaction::Clicked && *setting != *button_setting {
-            let (previous_button, mut previous_color) = selected_query.single_mut();
-            *previous_color = NORMAL_BUTTON.into();
-            commands.entity(previous_button).remove::<SelectedOption>();
+            // No button is marked before the first click.
+            if let Ok((previous_button, mut previous_color)) = selected_query.get_single_mut() {
+                *previous_color = NORMAL_BUTTON_COLOR.into();
+                commands.entity(previous_button).remove::<SelectedOption>();
+            }
             commands.entity(entity).insert(SelectedOption);
             *setting = *button_setting;
         }
@@ -681,18 +1211,24 @@ fn menu_action(
     mut ai_level: ResMut<AiLevel>,
     mut game_time: ResMut<GameTime>,
     mut game_time_increment: ResMut<GameTimeIncrement>,
+    mut variant: ResMut<GameVariant>,
+    mut rotate: ResMut<RotateBoard>,
+    mut player_color: ResMut<PlayerColor>,
+    result: Option<Res<GameResult>>,
 ) {
     for (interaction, menu_button_action) in interaction_query.iter() {
         if *interaction == Interaction::Clicked {
             match menu_button_action {
                 MenuButtonAction::MainMenu => {
-                    game_state.set(AppState::MainMenu).unwrap();
+                    // Game may be paused below the in game menu or the game
+                    // over screen, it ends too.
+                    game_state.replace(AppState::MainMenu).unwrap();
                 }
                 MenuButtonAction::SingleplayerMenu => {
                     game_state.set(AppState::SingleplayerMenu).unwrap();
                 }
                 MenuButtonAction::MultiplayerMenu => {
-                    //game_state.set(AppState::MultiplayerMenu).unwrap();
+                    game_state.set(AppState::MultiplayerMenu).unwrap();
                 }
                 MenuButtonAction::InGameMenu => {
                     game_state.push(AppState::InGameMenu).unwrap();
@@ -713,11 +1249,13 @@ fn menu_action(
                 }
                 MenuButtonAction::Quit => app_exit_events.send(AppExit),
                 MenuButtonAction::IncreaseAI => {
-                    ai_level.0 = ai_level.0 + 1;
+                    if ai_level.0 < MAX_LEVEL {
+                        ai_level.0 += 1;
+                    }
                 }
                 MenuButtonAction::DecreaseAI => {
-                    if ai_level.0 > 0 {
-                        ai_level.0 = ai_level.0 - 1;
+                    if ai_level.0 > MIN_LEVEL {
+                        ai_level.0 -= 1;
                     }
                 }
                 MenuButtonAction::IncreaseGameTime => {
@@ -736,6 +1274,22 @@ fn menu_action(
                         game_time_increment.0 = game_time_increment.0 - Duration::from_secs(1);
                     }
                 }
+                MenuButtonAction::SwitchVariant => {
+                    variant.0 = variant.0.next();
+                }
+                MenuButtonAction::Rematch => {
+                    // New game with the same settings and colors swapped, see
+                    // `setup_game`. The finished game below the game over
+                    // screen is left, and set up again.
+                    let game = result.as_ref().map_or(AppState::SingleplayerGame, |r| r.game.clone());
+                    if let Some(result) = result.as_ref() {
+                        player_color.0 = ColorSetting::of(result.player.opponent());
+                    }
+                    game_state.replace(game).unwrap();
+                }
+                MenuButtonAction::ToggleRotateBoard => {
+                    rotate.0 = !rotate.0;
+                }
             }
         }
     }
@@ -0,0 +1,31 @@
+//! Leaf evaluation and action generation of the alpha beta search, with
+//! criterion. Run with `cargo bench -p rusty-duke-logic --bench eval`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rusty_duke_logic::ai::alpha_beta::{evaluate, Agent};
+use rusty_duke_logic::logic::{compute_attack_map, get_actions, get_actions_into, GameState};
+
+/// Midgame position with many tiles, see `MoveOrdering`.
+const FEN: &str = "2FDPF'/3EF'1/6/2f'3/b5/d'1w'f2 b fpppkelyscagmnr/YPRPKBLWSCAGMN -/- - 16";
+
+fn bench_eval(c: &mut Criterion) {
+    let state = GameState::from_fen(FEN).unwrap();
+    let agent = Agent::new(state.ply, Some(1), None);
+
+    c.bench_function("evaluate", |b| {
+        b.iter(|| evaluate(&agent, black_box(&state)))
+    });
+    // Parts of evaluate, and all actions for comparison.
+    c.bench_function("compute_attack_map", |b| {
+        b.iter(|| compute_attack_map(black_box(&state)))
+    });
+    c.bench_function("get_actions", |b| b.iter(|| get_actions(black_box(&state))));
+    // Same vector every time, as in the search.
+    let mut actions = Vec::new();
+    c.bench_function("get_actions_into", |b| {
+        b.iter(|| get_actions_into(black_box(&state), &mut actions))
+    });
+}
+
+criterion_group!(benches, bench_eval);
+criterion_main!(benches);
@@ -0,0 +1,87 @@
+//! Nodes per second of the alpha beta search.
+//!
+//! Searches midgame positions of seeded games at a fixed depth, single
+//! threaded, and prints nodes, time and nodes per second, with and without
+//! transposition table. Run with
+//! `cargo bench -p rusty-duke-logic --bench search`, optionally with the depth
+//! as argument.
+
+use rusty_duke_logic::ai::alpha_beta::{get_action_with_stats, Agent};
+use rusty_duke_logic::ai::DukeAgent;
+use rusty_duke_logic::logic::{do_unsafe_action, GameState, TileColor};
+use std::time::Instant;
+
+/// Search depth, unless given as argument.
+const DEFAULT_DEPTH: u8 = 5;
+/// Positions are taken every `INTERVAL` plies from games with these seeds.
+const SEEDS: std::ops::Range<u64> = 0..8;
+const INTERVAL: usize = 6;
+const POSITIONS_PER_GAME: usize = 4;
+
+/// Positions of games between shallow agents, the same on every run.
+fn positions() -> Vec<GameState> {
+    let mut positions = Vec::new();
+    for seed in SEEDS {
+        let mut state = GameState::new_seeded(seed);
+        let mut agents = [
+            Agent::new(TileColor::Black, Some(2), None),
+            Agent::new(TileColor::White, Some(2), None),
+        ];
+        let mut taken = 0;
+        for ply in 1.. {
+            if state.game_over.is_some() || taken == POSITIONS_PER_GAME {
+                break;
+            }
+            match agents[state.ply as usize].choose_action(&state) {
+                Some(action) => {
+                    do_unsafe_action(&mut state, &action);
+                }
+                None => break,
+            }
+            if ply > INTERVAL
+                && ply % INTERVAL == 0
+                && state.drawn().is_empty()
+                && state.game_over.is_none()
+            {
+                positions.push(state.clone());
+                taken += 1;
+            }
+        }
+    }
+    positions
+}
+
+fn main() {
+    // Cargo passes "--bench", skip flags.
+    let depth = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(DEFAULT_DEPTH);
+    let positions = positions();
+
+    for transposition_table in [true, false] {
+        let start = Instant::now();
+        let mut nodes = 0;
+        for state in positions.iter() {
+            let mut agent = Agent::new(state.ply, Some(depth), None);
+            agent.transposition_table = transposition_table;
+            let (_, stats) = get_action_with_stats(&agent, state, None);
+            nodes += stats.nodes;
+        }
+        let elapsed = start.elapsed();
+
+        println!(
+            "{} positions at depth {}, {}: {} nodes in {} ms, {:.0} nodes/s.",
+            positions.len(),
+            depth,
+            if transposition_table {
+                "transposition table"
+            } else {
+                "no transposition table"
+            },
+            nodes,
+            elapsed.as_millis(),
+            nodes as f64 / elapsed.as_secs_f64().max(1e-9)
+        );
+    }
+}
@@ -1,3 +1,220 @@
 //! Implements AI agents for the Rusty Duke game.
 
+use crate::logic::clock::GameClock;
+use crate::logic::{Action, GameState, TileColor};
+use crate::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
 pub mod alpha_beta;
+pub mod arena;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod calibrate;
+pub mod opening;
+pub mod random;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tuner;
+
+/// Game playing agent. Implement this to plug in a new AI, UIs only use
+/// agents through this trait.
+pub trait DukeAgent {
+    /// Color the agent plays.
+    fn color(&self) -> TileColor;
+
+    /// Action to do in state, or `None` if there is nothing to do. Called once
+    /// per stage of a turn, i.e. again for `PlaceNew` after `NewFromBag`.
+    fn choose_action(&mut self, state: &GameState) -> Option<Action>;
+
+    /// Same as `choose_action` in a timed game, with the clock at now. Agents
+    /// that can't budget their time ignore the clock.
+    fn choose_action_timed(
+        &mut self,
+        state: &GameState,
+        _clock: &GameClock,
+        _now: Instant,
+    ) -> Option<Action> {
+        self.choose_action(state)
+    }
+
+    /// Same as `choose_action`, but the agent should give its best action so
+    /// far once token is cancelled, e.g. when searching in background. Agents
+    /// that can't stop early ignore the token.
+    fn choose_action_cancellable(
+        &mut self,
+        state: &GameState,
+        _token: &CancellationToken,
+    ) -> Option<Action> {
+        self.choose_action(state)
+    }
+
+    /// Evaluation of state for the color of the agent, positive if the agent
+    /// is better, e.g. the utility of its last search. `None` for agents that
+    /// don't evaluate positions. Used to adjudicate arena games, see
+    /// `arena::Adjudication`.
+    fn evaluation(&self, _state: &GameState) -> Option<i32> {
+        None
+    }
+}
+
+struct CancellationInner {
+    cancelled: AtomicBool,
+    deadline: Option<Instant>,
+    parent: Option<CancellationToken>,
+}
+
+/// Cooperative cancellation of searches. Cheap to clone, clones share state.
+///
+/// Cancelling a token cancels all its children, but not its parent. A token
+/// with a deadline is cancelled when the deadline passes.
+#[derive(Clone)]
+pub struct CancellationToken {
+    inner: Arc<CancellationInner>,
+}
+
+impl CancellationToken {
+    /// New token without parent and deadline.
+    pub fn new() -> CancellationToken {
+        CancellationToken::build(None, None)
+    }
+
+    /// New token, cancelled when deadline passes.
+    pub fn with_deadline(deadline: Instant) -> CancellationToken {
+        CancellationToken::build(None, Some(deadline))
+    }
+
+    /// New child token, cancelled when this token is cancelled.
+    pub fn child(&self) -> CancellationToken {
+        CancellationToken::build(Some(self.clone()), None)
+    }
+
+    /// New child token with deadline, cancelled when this token is cancelled
+    /// or deadline passes.
+    pub fn child_with_deadline(&self, deadline: Instant) -> CancellationToken {
+        CancellationToken::build(Some(self.clone()), Some(deadline))
+    }
+
+    fn build(parent: Option<CancellationToken>, deadline: Option<Instant>) -> CancellationToken {
+        CancellationToken {
+            inner: Arc::new(CancellationInner {
+                cancelled: AtomicBool::new(false),
+                deadline,
+                parent,
+            }),
+        }
+    }
+
+    /// Cancel token and all its children.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// True if token, or any of its parents, is cancelled or past deadline.
+    pub fn is_cancelled(&self) -> bool {
+        let mut token = Some(self);
+        let mut now = None;
+        while let Some(t) = token {
+            if t.inner.cancelled.load(Ordering::Relaxed) {
+                return true;
+            }
+            if let Some(deadline) = t.inner.deadline {
+                if *now.get_or_insert_with(Instant::now) >= deadline {
+                    return true;
+                }
+            }
+            token = t.inner.parent.as_ref();
+        }
+        false
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        CancellationToken::new()
+    }
+}
+
+/// State of a search running in background, see `SearchHandle::poll`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchPoll {
+    /// Search is still running.
+    Pending,
+    /// Search is done, with action for the position it was started from.
+    Done(Option<Action>),
+    /// Search is done, but the position it was started from is not the
+    /// position of the game anymore. Result must not be applied.
+    Stale,
+    /// Search thread ended without result, i.e. it panicked.
+    Lost,
+}
+
+/// Search running on a background thread, on a copy of the position it was
+/// started from. The game can go on while searching, so the result is only
+/// handed out for the same position, see `poll`.
+///
+/// Dropping the handle cancels the search. Handles are `Sync`, so they can
+/// be kept in resources shared between threads, e.g. by Bevy.
+pub struct SearchHandle {
+    hash: u64,
+    token: CancellationToken,
+    /// Only polled, the lock is never contended.
+    result: Mutex<Receiver<Option<Action>>>,
+}
+
+impl SearchHandle {
+    /// Start search on a copy of state. Search gets the copy and a token that
+    /// is cancelled when the handle is cancelled or dropped.
+    pub fn spawn<F>(state: &GameState, search: F) -> SearchHandle
+    where
+        F: FnOnce(&GameState, &CancellationToken) -> Option<Action> + Send + 'static,
+    {
+        let hash = state.hash();
+        let token = CancellationToken::new();
+        let (sender, result) = mpsc::channel();
+        let state = state.clone();
+        let search_token = token.clone();
+        thread::spawn(move || {
+            // Receiver is gone if handle was dropped, result is not needed.
+            let _ = sender.send(search(&state, &search_token));
+        });
+
+        SearchHandle {
+            hash,
+            token,
+            result: Mutex::new(result),
+        }
+    }
+
+    /// Hash of the position search was started from.
+    pub fn position_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Stop search early. A cancelled search still delivers its best action
+    /// so far.
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Check search without blocking. `state` is the current position of the
+    /// game, a result for any other position is `Stale`.
+    ///
+    /// The result is handed out once, later polls are `Lost`. Drop the handle
+    /// when it is not `Pending` anymore.
+    pub fn poll(&self, state: &GameState) -> SearchPoll {
+        let result = self.result.lock().unwrap_or_else(|e| e.into_inner());
+        match result.try_recv() {
+            Ok(_) if state.hash() != self.hash => SearchPoll::Stale,
+            Ok(action) => SearchPoll::Done(action),
+            Err(TryRecvError::Empty) => SearchPoll::Pending,
+            Err(TryRecvError::Disconnected) => SearchPoll::Lost,
+        }
+    }
+}
+
+impl Drop for SearchHandle {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
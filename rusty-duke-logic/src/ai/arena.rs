@@ -0,0 +1,484 @@
+//! Headless games between agents, for tuning and tests.
+//!
+//! Games are deterministic for a seed, as long as the agents are: the seed
+//! decides the tiles drawn from bags, and seeds of agents in tournaments are
+//! derived from it.
+//!
+//! Long runs spend most of their time finishing decided games. With an
+//! `Adjudication`, games end early when both agents evaluate them as won, or
+//! as a dead draw late in the game.
+//!
+//! ```
+//! use rusty_duke_logic::ai::arena::{tournament, Entrant};
+//! use rusty_duke_logic::ai::random::RandomAgent;
+//! use rusty_duke_logic::ai::alpha_beta::Agent;
+//! use rusty_duke_logic::ai::DukeAgent;
+//!
+//! let entrants = vec![
+//!     Entrant::new("random", |color, seed| {
+//!         Box::new(RandomAgent::new_seeded(color, seed)) as Box<dyn DukeAgent>
+//!     }),
+//!     Entrant::new("depth 1", |color, _| {
+//!         Box::new(Agent::new(color, Some(1), None)) as Box<dyn DukeAgent>
+//!     }),
+//! ];
+//! let report = tournament(entrants, 4, 7, 200);
+//!
+//! assert_eq!(report.games, 4);
+//! assert_eq!((report.standings[0].wins, report.standings[0].losses), (0, 4));
+//! assert_eq!((report.standings[1].wins, report.standings[1].losses), (4, 0));
+//! assert_eq!(report.average_plies, 44.25);
+//! ```
+
+use super::DukeAgent;
+use crate::logic::record::GameRecord;
+use crate::logic::rng::GameRng;
+use crate::logic::{do_action, GameState, TileColor, WinReason, Winner};
+use rand::RngCore;
+use std::fmt;
+
+/// Outcome of one game of `play_game`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameResult {
+    /// `Winner::Draw` also when the ply limit was reached, or an agent had no
+    /// action.
+    pub winner: Winner,
+    /// `None` when the ply limit was reached, or an agent had no action.
+    pub reason: Option<WinReason>,
+    /// Actions done, drawing and placing a tile count as two.
+    pub plies: u32,
+    /// Record of the game. Tournaments don't keep records.
+    pub record: Option<GameRecord>,
+    /// Evaluations of the Black and White agent when the game was
+    /// adjudicated, both positive if Black was better.
+    pub adjudicated: Option<[i32; 2]>,
+}
+
+/// Rules for ending decided games early, see `play_game_adjudicated`. Only
+/// games of agents that evaluate positions are adjudicated, see
+/// `DukeAgent::evaluation`. Positions are evaluated after every turn, and
+/// the evaluations of both agents must agree.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Adjudication {
+    /// No game is adjudicated before this many plies.
+    pub min_plies: u32,
+    /// A color wins when both agents evaluate it at least this much better
+    /// for `win_turns` turns in a row.
+    pub win_threshold: i32,
+    pub win_turns: u32,
+    /// The game is a draw when both evaluations are within this of zero for
+    /// `draw_turns` turns in a row, all after `draw_min_plies`.
+    pub draw_band: i32,
+    pub draw_turns: u32,
+    pub draw_min_plies: u32,
+}
+
+/// Defaults are for `alpha_beta::Agent`, a tile is worth about 10 to 40.
+impl Default for Adjudication {
+    fn default() -> Self {
+        Adjudication {
+            min_plies: 40,
+            win_threshold: 100,
+            win_turns: 4,
+            draw_band: 10,
+            draw_turns: 12,
+            draw_min_plies: 80,
+        }
+    }
+}
+
+/// Turns in a row each rule of an `Adjudication` held.
+struct Adjudicator<'a> {
+    rules: &'a Adjudication,
+    black: u32,
+    white: u32,
+    draw: u32,
+}
+
+impl<'a> Adjudicator<'a> {
+    fn new(rules: &'a Adjudication) -> Adjudicator<'a> {
+        Adjudicator {
+            rules,
+            black: 0,
+            white: 0,
+            draw: 0,
+        }
+    }
+
+    /// Winner if the game is decided after plies, by evaluations of the
+    /// Black and White agent, both positive if Black is better. A missing
+    /// evaluation or disagreement starts all counts over.
+    fn update(&mut self, evaluations: [Option<i32>; 2], plies: u32) -> Option<Winner> {
+        let rules = self.rules;
+        let (black, white) = match evaluations {
+            [Some(black), Some(white)] => (black, white),
+            _ => {
+                *self = Adjudicator::new(rules);
+                return None;
+            }
+        };
+        let (lowest, highest) = (black.min(white), black.max(white));
+        let count = |turns: u32, holds: bool| if holds { turns + 1 } else { 0 };
+
+        self.black = count(self.black, lowest >= rules.win_threshold);
+        self.white = count(self.white, highest <= -rules.win_threshold);
+        self.draw = count(
+            self.draw,
+            plies >= rules.draw_min_plies
+                && lowest >= -rules.draw_band
+                && highest <= rules.draw_band,
+        );
+
+        if plies < rules.min_plies {
+            None
+        } else if self.black >= rules.win_turns {
+            Some(Winner::Color(TileColor::Black))
+        } else if self.white >= rules.win_turns {
+            Some(Winner::Color(TileColor::White))
+        } else if self.draw >= rules.draw_turns {
+            Some(Winner::Draw)
+        } else {
+            None
+        }
+    }
+}
+
+/// Play a game from `GameState::new_seeded(seed)`. After `max_plies` actions
+/// the game is a draw, but a drawn tile is always placed first.
+///
+/// Panics if an agent chooses an illegal action, agents are expected to be
+/// correct.
+pub fn play_game(
+    black: &mut dyn DukeAgent,
+    white: &mut dyn DukeAgent,
+    max_plies: u32,
+    seed: u64,
+) -> GameResult {
+    play(black, white, max_plies, seed, None)
+}
+
+/// Same as `play_game`, but decided games end early by the rules of
+/// adjudication, with `WinReason::Adjudicated`.
+pub fn play_game_adjudicated(
+    black: &mut dyn DukeAgent,
+    white: &mut dyn DukeAgent,
+    max_plies: u32,
+    seed: u64,
+    adjudication: &Adjudication,
+) -> GameResult {
+    play(black, white, max_plies, seed, Some(adjudication))
+}
+
+fn play(
+    black: &mut dyn DukeAgent,
+    white: &mut dyn DukeAgent,
+    max_plies: u32,
+    seed: u64,
+    adjudication: Option<&Adjudication>,
+) -> GameResult {
+    debug_assert!(black.color() == TileColor::Black && white.color() == TileColor::White);
+
+    let mut state = GameState::new_seeded(seed);
+    let mut record = GameRecord::new(Some(seed));
+    let mut plies = 0;
+    let mut adjudicator = adjudication.map(Adjudicator::new);
+
+    while state.game_over.is_none() && (plies < max_plies || !state.drawn().is_empty()) {
+        // The same agent chooses again for PlaceNew after NewFromBag.
+        let action = match state.ply {
+            TileColor::Black => black.choose_action(&state),
+            TileColor::White => white.choose_action(&state),
+        };
+        let action = match action {
+            Some(action) => action,
+            None => break,
+        };
+        if let Err(e) = do_action(&mut state, &action) {
+            panic!("{:?} chose {:?}: {}", state.ply, action, e);
+        }
+        record.push(&state, action);
+        plies += 1;
+
+        // Turns end when no tile is drawn.
+        if let Some(adjudicator) = adjudicator.as_mut() {
+            if state.game_over.is_some() || !state.drawn().is_empty() {
+                continue;
+            }
+            let evaluations = [
+                black.evaluation(&state),
+                white.evaluation(&state).map(|e| -e),
+            ];
+            if let Some(winner) = adjudicator.update(evaluations, plies) {
+                return GameResult {
+                    winner,
+                    reason: Some(WinReason::Adjudicated),
+                    plies,
+                    record: Some(record),
+                    adjudicated: Some(evaluations.map(Option::unwrap)),
+                };
+            }
+        }
+    }
+
+    GameResult {
+        winner: state.game_over.clone().unwrap_or(Winner::Draw),
+        reason: state.win_reason,
+        plies,
+        record: Some(record),
+        adjudicated: None,
+    }
+}
+
+/// Agent in a tournament. Entrants play both colors, so the tournament makes
+/// a new agent of the entrant for every game, with color and a seed for
+/// agents that use random numbers.
+pub struct Entrant {
+    pub name: String,
+    make: Box<dyn Fn(TileColor, u64) -> Box<dyn DukeAgent>>,
+}
+
+impl Entrant {
+    pub fn new<F>(name: &str, make: F) -> Entrant
+    where
+        F: Fn(TileColor, u64) -> Box<dyn DukeAgent> + 'static,
+    {
+        Entrant {
+            name: name.to_string(),
+            make: Box::new(make),
+        }
+    }
+}
+
+/// Results of one entrant of a tournament.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Standing {
+    pub name: String,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Standing {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Wins plus half the draws.
+    pub fn score(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+
+    /// Score per game, from 0 to 1. One half without games.
+    pub fn mean_score(&self) -> f64 {
+        match self.games() {
+            0 => 0.5,
+            games => self.score() / games as f64,
+        }
+    }
+
+    /// Interval of `mean_score` z standard errors wide on each side, e.g.
+    /// 1.96 for 95 % confidence. Normal approximation over the outcomes of
+    /// the games, a win scoring 1, a draw 0.5 and a loss 0. Within 0 to 1.
+    ///
+    /// ```
+    /// use rusty_duke_logic::ai::arena::Standing;
+    ///
+    /// let standing = Standing {
+    ///     wins: 30,
+    ///     draws: 0,
+    ///     losses: 10,
+    ///     ..Default::default()
+    /// };
+    /// let (low, high) = standing.confidence_interval(1.96);
+    /// assert!(low > 0.6 && low < 0.75 && high > 0.75 && high < 0.9);
+    /// ```
+    pub fn confidence_interval(&self, z: f64) -> (f64, f64) {
+        let games = self.games();
+        if games == 0 {
+            return (0.0, 1.0);
+        }
+        let mean = self.mean_score();
+        let variance = (self.wins as f64 * (1.0 - mean).powi(2)
+            + self.draws as f64 * (0.5 - mean).powi(2)
+            + self.losses as f64 * mean.powi(2))
+            / games as f64;
+        let margin = z * (variance / games as f64).sqrt();
+        ((mean - margin).max(0.0), (mean + margin).min(1.0))
+    }
+}
+
+/// Game of a tournament that was adjudicated. Has what is needed to play it
+/// out with `play_game`, so results can be recomputed without adjudication.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdjudicatedGame {
+    pub black: String,
+    pub white: String,
+    /// Seed of the game, and seeds the Black and White agent were made with.
+    pub seed: u64,
+    pub agent_seeds: [u64; 2],
+    pub plies: u32,
+    pub winner: Winner,
+    /// See `GameResult::adjudicated`.
+    pub evaluations: [i32; 2],
+}
+
+/// Results of `tournament`. Standings are in the order of the entrants.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TournamentReport {
+    pub standings: Vec<Standing>,
+    pub games: u32,
+    /// Average plies per game.
+    pub average_plies: f64,
+    /// Rules games were adjudicated by, `None` if all were played out.
+    pub adjudication: Option<Adjudication>,
+    /// Adjudicated games, in order of play.
+    pub adjudicated: Vec<AdjudicatedGame>,
+}
+
+impl TournamentReport {
+    /// Report as JSON, with the adjudication rules and every adjudicated
+    /// game.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Reports are always serializable.")
+    }
+}
+
+impl fmt::Display for TournamentReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<20} {:>5} {:>5} {:>5} {:>6}",
+            "Agent", "Win", "Draw", "Loss", "Score"
+        )?;
+        for s in self.standings.iter() {
+            writeln!(
+                f,
+                "{:<20} {:>5} {:>5} {:>5} {:>6.1}",
+                s.name,
+                s.wins,
+                s.draws,
+                s.losses,
+                s.score()
+            )?;
+        }
+        write!(
+            f,
+            "{} games, {:.1} plies on average",
+            self.games, self.average_plies
+        )?;
+        if self.adjudication.is_some() {
+            write!(f, ", {} adjudicated", self.adjudicated.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// Round robin of entrants, `games_per_pair` games for every pair. Pairs
+/// switch colors every game, the first entrant of the pair is Black in the
+/// first game. Game seeds, and seeds of agents, are drawn from seed.
+pub fn tournament(
+    entrants: Vec<Entrant>,
+    games_per_pair: u32,
+    seed: u64,
+    max_plies: u32,
+) -> TournamentReport {
+    run_tournament(entrants, games_per_pair, seed, max_plies, None)
+}
+
+/// Same as `tournament`, but games are adjudicated, see
+/// `play_game_adjudicated`. Same seed gives the same games as `tournament`
+/// until they are adjudicated.
+pub fn tournament_adjudicated(
+    entrants: Vec<Entrant>,
+    games_per_pair: u32,
+    seed: u64,
+    max_plies: u32,
+    adjudication: Adjudication,
+) -> TournamentReport {
+    run_tournament(
+        entrants,
+        games_per_pair,
+        seed,
+        max_plies,
+        Some(adjudication),
+    )
+}
+
+fn run_tournament(
+    entrants: Vec<Entrant>,
+    games_per_pair: u32,
+    seed: u64,
+    max_plies: u32,
+    adjudication: Option<Adjudication>,
+) -> TournamentReport {
+    let mut rng = GameRng::new(seed);
+    let mut report = TournamentReport {
+        standings: entrants
+            .iter()
+            .map(|e| Standing {
+                name: e.name.clone(),
+                ..Default::default()
+            })
+            .collect(),
+        adjudication,
+        ..Default::default()
+    };
+    let mut plies: u64 = 0;
+
+    for i in 0..entrants.len() {
+        for j in i + 1..entrants.len() {
+            for game in 0..games_per_pair {
+                let (b, w) = if game % 2 == 0 { (i, j) } else { (j, i) };
+                let game_seed = rng.next_u64();
+                let agent_seeds = [rng.next_u64(), rng.next_u64()];
+                let mut black = (entrants[b].make)(TileColor::Black, agent_seeds[0]);
+                let mut white = (entrants[w].make)(TileColor::White, agent_seeds[1]);
+
+                let result = play(
+                    black.as_mut(),
+                    white.as_mut(),
+                    max_plies,
+                    game_seed,
+                    adjudication.as_ref(),
+                );
+                if let Some(evaluations) = result.adjudicated {
+                    report.adjudicated.push(AdjudicatedGame {
+                        black: entrants[b].name.clone(),
+                        white: entrants[w].name.clone(),
+                        seed: game_seed,
+                        agent_seeds,
+                        plies: result.plies,
+                        winner: result.winner.clone(),
+                        evaluations,
+                    });
+                }
+                match result.winner {
+                    Winner::Color(TileColor::Black) => {
+                        report.standings[b].wins += 1;
+                        report.standings[w].losses += 1;
+                    }
+                    Winner::Color(TileColor::White) => {
+                        report.standings[w].wins += 1;
+                        report.standings[b].losses += 1;
+                    }
+                    Winner::Draw => {
+                        report.standings[b].draws += 1;
+                        report.standings[w].draws += 1;
+                    }
+                }
+                report.games += 1;
+                plies += result.plies as u64;
+            }
+        }
+    }
+
+    if report.games > 0 {
+        report.average_plies = plies as f64 / report.games as f64;
+    }
+    report
+}
@@ -0,0 +1,325 @@
+//! Calibration of AI search depth to the hardware.
+//!
+//! A short benchmark searches a fixed set of positions deeper and deeper, and
+//! measures nodes per second and the effective branching factor. Single
+//! positions are a bad benchmark, drawing from bag ends search early so the
+//! tree size of one position jumps around with depth. Search time
+//! at any depth is then estimated as
+//! `reference_nodes * branching_factor ^ (depth - reference_depth) / nodes_per_second`,
+//! and each difficulty gets the deepest depth that fits its move time.
+
+use super::alpha_beta::{get_action_with_stats, Agent};
+use super::{CancellationToken, DukeAgent};
+use crate::logic::{do_unsafe_action, GameState, TileColor};
+use crate::persist::{self, PersistError};
+use crate::time::{Duration, Instant};
+use std::fmt;
+use std::path::Path;
+use std::thread;
+
+/// Time spent by `calibrate` in a normal calibration.
+pub const BENCHMARK_BUDGET: Duration = Duration::from_secs(2);
+
+/// Time spent by `HardwareFingerprint::measure`.
+pub const FINGERPRINT_BUDGET: Duration = Duration::from_millis(200);
+
+/// File name of stored calibration, in the working directory.
+pub const DEFAULT_CALIBRATION_FILE: &str = "rusty-duke-calibration.txt";
+
+/// Depths given by calibration are within these limits.
+pub const MIN_DEPTH: u8 = 1;
+pub const MAX_DEPTH: u8 = 12;
+
+/// Benchmark positions are taken every `SUITE_INTERVAL` plies from games
+/// with these seeds, until there are `SUITE_SIZE` positions.
+const SUITE_SEEDS: std::ops::RangeInclusive<u64> = 1..=10;
+const SUITE_INTERVAL: usize = 4;
+const SUITE_SIZE: usize = 16;
+/// Search depth of the agents playing the benchmark games.
+const SUITE_DEPTH: u8 = 2;
+
+/// AI difficulty presets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    /// Longest typical AI move time.
+    pub fn target_move_time(self) -> Duration {
+        match self {
+            Difficulty::Easy => Duration::from_millis(200),
+            Difficulty::Normal => Duration::from_secs(1),
+            Difficulty::Hard => Duration::from_secs(5),
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Rough description of the hardware, to notice when a stored calibration is
+/// from another machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HardwareFingerprint {
+    pub cores: usize,
+    /// Base 2 logarithm of single thread nodes per second, rounded.
+    pub single_thread_score: u32,
+}
+
+impl HardwareFingerprint {
+    pub fn new(cores: usize, nodes_per_second: f64) -> HardwareFingerprint {
+        HardwareFingerprint {
+            cores,
+            single_thread_score: nodes_per_second.max(1.0).log2().round() as u32,
+        }
+    }
+
+    /// Fingerprint of this machine. Runs a benchmark for about
+    /// `FINGERPRINT_BUDGET`.
+    pub fn measure() -> HardwareFingerprint {
+        calibrate(FINGERPRINT_BUDGET).fingerprint
+    }
+
+    /// True if fingerprints are from the same machine. Scores from short
+    /// benchmarks are noisy, so neighbouring scores match.
+    pub fn matches(&self, other: &HardwareFingerprint) -> bool {
+        self.cores == other.cores
+            && self.single_thread_score.abs_diff(other.single_thread_score) <= 1
+    }
+}
+
+/// Result of a calibration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    /// Single thread search speed.
+    pub nodes_per_second: f64,
+    /// Growth of node count per depth.
+    pub branching_factor: f64,
+    /// Deepest fully searched depth, and its node count per position.
+    pub reference_depth: u8,
+    pub reference_nodes: f64,
+    pub fingerprint: HardwareFingerprint,
+}
+
+impl Calibration {
+    /// Estimated number of nodes searched at depth.
+    pub fn estimate_nodes(&self, depth: u8) -> f64 {
+        let exponent = depth as i32 - self.reference_depth as i32;
+        self.reference_nodes * self.branching_factor.powi(exponent)
+    }
+
+    /// Estimated search time at depth.
+    pub fn estimate_time(&self, depth: u8) -> Duration {
+        let seconds = self.estimate_nodes(depth) / self.nodes_per_second.max(1.0);
+        Duration::from_secs_f64(seconds.min(u32::MAX as f64))
+    }
+
+    /// Deepest depth with estimated search time within target.
+    pub fn depth_for(&self, target: Duration) -> u8 {
+        (MIN_DEPTH..=MAX_DEPTH)
+            .rev()
+            .find(|depth| self.estimate_time(*depth) <= target)
+            .unwrap_or(MIN_DEPTH)
+    }
+
+    pub fn depth_for_difficulty(&self, difficulty: Difficulty) -> u8 {
+        self.depth_for(difficulty.target_move_time())
+    }
+
+    /// True if calibration was done on other hardware than fingerprint.
+    pub fn needs_recalibration(&self, current: &HardwareFingerprint) -> bool {
+        !self.fingerprint.matches(current)
+    }
+
+    /// Calibration as text, one `key value` per line.
+    pub fn to_text(&self) -> String {
+        format!(
+            "nodes_per_second {}\nbranching_factor {}\nreference_depth {}\nreference_nodes {}\ncores {}\nsingle_thread_score {}\n",
+            self.nodes_per_second,
+            self.branching_factor,
+            self.reference_depth,
+            self.reference_nodes,
+            self.fingerprint.cores,
+            self.fingerprint.single_thread_score,
+        )
+    }
+
+    /// Calibration from `to_text` output. `None` if a value is missing or
+    /// invalid.
+    pub fn from_text(text: &str) -> Option<Calibration> {
+        let value = |key: &str| {
+            text.lines()
+                .filter_map(|line| line.trim().split_once(' '))
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.trim())
+        };
+
+        Some(Calibration {
+            nodes_per_second: value("nodes_per_second")?.parse().ok()?,
+            branching_factor: value("branching_factor")?.parse().ok()?,
+            reference_depth: value("reference_depth")?.parse().ok()?,
+            reference_nodes: value("reference_nodes")?.parse().ok()?,
+            fingerprint: HardwareFingerprint {
+                cores: value("cores")?.parse().ok()?,
+                single_thread_score: value("single_thread_score")?.parse().ok()?,
+            },
+        })
+    }
+
+    /// Load calibration from file. `None` if file does not exist or is
+    /// invalid.
+    pub fn load(path: &Path) -> Result<Option<Calibration>, PersistError> {
+        let contents = persist::read(path, persist::DEFAULT_LOCK_TIMEOUT)?;
+        Ok(contents.and_then(|c| Calibration::from_text(&String::from_utf8_lossy(&c))))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), PersistError> {
+        persist::write(
+            path,
+            self.to_text().as_bytes(),
+            persist::DEFAULT_LOCK_TIMEOUT,
+        )
+    }
+}
+
+/// Node counts and times per position of a benchmark, one per fully searched
+/// depth starting from depth 1, to calibration. Pure, so it can be checked
+/// with made up numbers.
+///
+/// Panics if there are no measurements.
+pub fn estimate(measurements: &[(u64, Duration)], cores: usize) -> Calibration {
+    assert!(!measurements.is_empty(), "No measurements.");
+
+    let nodes: u64 = measurements.iter().map(|(n, _)| n).sum();
+    let time: Duration = measurements.iter().map(|(_, t)| *t).sum();
+    let nodes_per_second = nodes as f64 / time.as_secs_f64().max(1e-6);
+
+    // Average growth over the two deepest steps. Shallow depths are dominated
+    // by overhead, and growth alternates between odd and even depths.
+    let reference_nodes = measurements.last().unwrap().0.max(1) as f64;
+    let branching_factor = match measurements.len() {
+        1 => reference_nodes,
+        2 => reference_nodes / measurements[0].0.max(1) as f64,
+        n => (reference_nodes / measurements[n - 3].0.max(1) as f64).sqrt(),
+    };
+
+    Calibration {
+        nodes_per_second,
+        // Alpha beta never shrinks the tree with depth, but measurements may
+        // be noisy.
+        branching_factor: branching_factor.max(1.5),
+        reference_depth: measurements.len() as u8,
+        reference_nodes,
+        fingerprint: HardwareFingerprint::new(cores, nodes_per_second),
+    }
+}
+
+/// Positions searched by the benchmark. Midgame positions of games played by
+/// shallow agents, the same on every machine.
+fn suite() -> Vec<GameState> {
+    let mut suite = Vec::new();
+    for seed in SUITE_SEEDS {
+        let mut state = GameState::new_seeded(seed);
+        let mut agents = [
+            Agent::new(TileColor::Black, Some(SUITE_DEPTH), None),
+            Agent::new(TileColor::White, Some(SUITE_DEPTH), None),
+        ];
+        for ply in 1.. {
+            if state.game_over.is_some() || suite.len() == SUITE_SIZE {
+                break;
+            }
+            match agents[state.ply as usize].choose_action(&state) {
+                Some(action) => {
+                    do_unsafe_action(&mut state, &action);
+                }
+                None => break,
+            }
+            if ply > 2 * SUITE_INTERVAL
+                && ply % SUITE_INTERVAL == 0
+                && state.drawn().is_empty()
+                && state.game_over.is_none()
+            {
+                suite.push(state.clone());
+            }
+        }
+    }
+    suite
+}
+
+/// Run benchmark for about budget, and estimate calibration. Searches one
+/// depth deeper at a time, until the next depth would not fit in budget.
+pub fn calibrate(budget: Duration) -> Calibration {
+    calibrate_with_clock(budget, Instant::now)
+}
+
+/// Same as `calibrate`, with times measured by now instead of
+/// `Instant::now`. Node counts are the same on every machine, so a made up
+/// clock gives the same calibration on every run. Searches are cancelled
+/// when `Instant::now` passes the deadline, so a made up clock should not
+/// be behind the real one.
+pub fn calibrate_with_clock<F>(budget: Duration, mut now: F) -> Calibration
+where
+    F: FnMut() -> Instant,
+{
+    let deadline = now() + budget;
+    let token = CancellationToken::with_deadline(deadline);
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    let suite = suite();
+    let positions = suite.len().max(1) as u64;
+
+    // Search every position at depth, single threaded since nodes per second
+    // is per thread. Returns nodes and time per position, or `None` if
+    // cancelled.
+    let mut measure = |depth: u8, token: Option<&CancellationToken>| {
+        let start = now();
+        let mut nodes = 0;
+        for state in suite.iter() {
+            let agent = Agent::new(state.ply, Some(depth), None);
+            let (_, stats) = get_action_with_stats(&agent, state, token);
+            if stats.cancelled {
+                return None;
+            }
+            nodes += stats.nodes;
+        }
+        let elapsed = now().saturating_duration_since(start);
+        Some((
+            nodes / positions,
+            elapsed / positions as u32,
+            start + elapsed,
+        ))
+    };
+
+    let mut measurements: Vec<(u64, Duration)> = Vec::new();
+    for depth in MIN_DEPTH..=MAX_DEPTH {
+        let end = match measure(depth, Some(&token)) {
+            Some((nodes, time, end)) => {
+                measurements.push((nodes, time));
+                end
+            }
+            None => break,
+        };
+
+        // Don't start a depth that can't finish in time.
+        let estimated = estimate(&measurements, cores)
+            .estimate_time(depth + 1)
+            .saturating_mul(positions as u32);
+        if end + estimated > deadline {
+            break;
+        }
+    }
+
+    // Depth 1 is always fast, but budget may be tiny.
+    if measurements.is_empty() {
+        measurements.extend(measure(MIN_DEPTH, None).map(|(nodes, time, _)| (nodes, time)));
+    }
+
+    estimate(&measurements, cores)
+}
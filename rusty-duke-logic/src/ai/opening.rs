@@ -0,0 +1,133 @@
+//! Opening book for AI agents.
+//!
+//! Every game starts with placing the Duke and two Footmen, and search spends
+//! a lot of time on these placements. The book maps positions of the first
+//! turns to actions chosen ahead of time, so agents can skip the search.
+//!
+//! Text format, one entry per line: actions from game start separated by `;`,
+//! then `=>` and the book action, in the notation of the `notation` module.
+//! Empty lines and lines starting with `#` are ignored.
+//!
+//! ```text
+//! => @c1 Duke
+//! @c1 Duke => @c6 Duke
+//! @c1 Duke; @c6 Duke => @d1 Footman
+//! ```
+//!
+//! Positions are keyed by their hash without bags. The first placements don't
+//! depend on what is in the bags, so the same book serves every variant.
+
+use crate::logic::notation::{notation_to_action, NotationError};
+use crate::logic::{do_unsafe_action, get_actions, Action, GameState};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Book of `OpeningBook::standard`, in the text format of the module
+/// documentation.
+pub const STANDARD_BOOK: &str = include_str!("opening_book.txt");
+
+/// Reason why a text book could not be loaded. Line numbers start from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BookError {
+    /// Line has no `=>`.
+    Syntax(usize, String),
+    /// Action could not be parsed, or can't be done.
+    Notation(usize, NotationError),
+    /// Line draws from bag. Book positions must not depend on the draw.
+    Draw(usize),
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BookError::Syntax(line, s) => write!(f, "line {}: missing => in {}", line, s),
+            BookError::Notation(line, e) => write!(f, "line {}: {}", line, e),
+            BookError::Draw(line) => write!(f, "line {}: book can't draw from bag", line),
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+/// Actions for positions of the first turns.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpeningBook {
+    entries: HashMap<u64, Action>,
+    /// Highest turn of any book position. Later positions are not looked up.
+    last_turn: u16,
+}
+
+impl OpeningBook {
+    /// Book shipped with the game. Actions were chosen by a depth 8 search.
+    pub fn standard() -> OpeningBook {
+        OpeningBook::from_text(STANDARD_BOOK).expect("Standard opening book is invalid.")
+    }
+
+    /// Load book from the text format of the module documentation. Actions
+    /// are checked against a replayed standard game.
+    pub fn from_text(text: &str) -> Result<OpeningBook, BookError> {
+        let mut book = OpeningBook::default();
+
+        for (n, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (moves, book_action) = line
+                .split_once("=>")
+                .ok_or_else(|| BookError::Syntax(n, line.to_string()))?;
+
+            let mut state = GameState::new_seeded(0);
+            let parse = |state: &GameState, s: &str| {
+                let action =
+                    notation_to_action(state, s.trim()).map_err(|e| BookError::Notation(n, e))?;
+                if action == Action::NewFromBag {
+                    return Err(BookError::Draw(n));
+                }
+                Ok(action)
+            };
+
+            for s in moves.split(';').filter(|s| !s.trim().is_empty()) {
+                let action = parse(&state, s)?;
+                do_unsafe_action(&mut state, &action);
+            }
+            let action = parse(&state, book_action)?;
+
+            book.last_turn = book.last_turn.max(state.turn);
+            book.entries.insert(book_key(&state), action);
+        }
+
+        Ok(book)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Book action for state, if any. Only actions that can be done in state
+    /// are handed out, so a book for other rules never breaks a game.
+    pub fn action(&self, state: &GameState) -> Option<Action> {
+        if state.turn > self.last_turn || state.game_over.is_some() {
+            return None;
+        }
+        let action = *self.entries.get(&book_key(state))?;
+        if get_actions(state).contains(&action) {
+            Some(action)
+        } else {
+            None
+        }
+    }
+}
+
+/// Hash of position without bags.
+fn book_key(state: &GameState) -> u64 {
+    let mut position = state.clone();
+    position.clear_history();
+    position.bags = Default::default();
+    position.rehash();
+    position.hash()
+}
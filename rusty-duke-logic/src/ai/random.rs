@@ -0,0 +1,45 @@
+//! Implements random agent for the Rusty Duke game. Useful as a baseline, and
+//! for fast games.
+
+use super::DukeAgent;
+use crate::logic::{get_legal_actions, rng::GameRng, Action, GameState, TileColor};
+use rand::Rng;
+
+/// Agent that picks uniformly among legal actions.
+pub struct RandomAgent {
+    color: TileColor,
+    rng: GameRng,
+}
+
+impl RandomAgent {
+    /// New agent, random generator seeded from entropy.
+    pub fn new(color: TileColor) -> RandomAgent {
+        RandomAgent {
+            color,
+            rng: GameRng::from_entropy(),
+        }
+    }
+
+    /// New agent with seeded random generator. Same seed and same states give
+    /// the same actions.
+    pub fn new_seeded(color: TileColor, seed: u64) -> RandomAgent {
+        RandomAgent {
+            color,
+            rng: GameRng::new(seed),
+        }
+    }
+}
+
+impl DukeAgent for RandomAgent {
+    fn color(&self) -> TileColor {
+        self.color
+    }
+
+    fn choose_action(&mut self, state: &GameState) -> Option<Action> {
+        let actions = get_legal_actions(state);
+        if actions.is_empty() {
+            return None;
+        }
+        Some(actions[self.rng.gen_range(0..actions.len())])
+    }
+}
@@ -0,0 +1,340 @@
+//! Tuning of evaluation weights by self play.
+//!
+//! Agents with different `EvalWeights` play round robins on a fixed set of
+//! seeds. Every pair plays each seed twice, once with each color, so both
+//! agents draw the same tiles and the luck of the bag evens out. A local
+//! search changes one weight at a time, and keeps the change if the new
+//! weights score more than half against the best weights so far. Its state
+//! is saved after every step, so long runs can be stopped and resumed.
+//!
+//! Agents search to a fixed depth without time limit, so the games, and the
+//! whole search, are the same on every run and machine.
+
+use super::alpha_beta::{Agent, EvalWeights};
+use super::arena::{play_game, Standing};
+use crate::logic::{TileColor, WinReason, Winner};
+use crate::persist::{self, PersistError};
+use std::path::Path;
+
+/// Names of the weights, in the order of `weights_to_text`.
+pub const WEIGHT_NAMES: [&str; 7] = [
+    "spawn_square",
+    "coordination",
+    "duke_shelter",
+    "mobility",
+    "duke_danger",
+    "position",
+    "duke_home",
+];
+
+/// Columns of `MatchGame::to_csv`.
+pub const CSV_HEADER: &str = "iteration,seed,black,white,winner,reason,plies";
+
+/// Standard errors on each side of confidence intervals, for 95 %.
+pub const CONFIDENCE_Z: f64 = 1.96;
+
+/// Weight i of weights, in the order of `WEIGHT_NAMES`.
+fn weight(weights: &mut EvalWeights, i: usize) -> &mut i32 {
+    match i {
+        0 => &mut weights.spawn_square,
+        1 => &mut weights.coordination,
+        2 => &mut weights.duke_shelter,
+        3 => &mut weights.mobility,
+        4 => &mut weights.duke_danger,
+        5 => &mut weights.position,
+        6 => &mut weights.duke_home,
+        _ => panic!("No weight {}.", i),
+    }
+}
+
+/// Weights as numbers separated by spaces, in the order of `WEIGHT_NAMES`,
+/// e.g. `2 1 2 1 2 2 1` for the defaults.
+pub fn weights_to_text(weights: &EvalWeights) -> String {
+    let mut weights = *weights;
+    (0..WEIGHT_NAMES.len())
+        .map(|i| weight(&mut weights, i).to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Weights from `weights_to_text` output. `None` if a number is missing or
+/// invalid, or there are too many.
+pub fn weights_from_text(text: &str) -> Option<EvalWeights> {
+    let numbers: Vec<i32> = text
+        .split_whitespace()
+        .map(|n| n.parse().ok())
+        .collect::<Option<_>>()?;
+    if numbers.len() != WEIGHT_NAMES.len() {
+        return None;
+    }
+    let mut weights = EvalWeights::default();
+    for (i, n) in numbers.into_iter().enumerate() {
+        *weight(&mut weights, i) = n;
+    }
+    Some(weights)
+}
+
+/// Settings of the games of a tuning run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TunerConfig {
+    /// Search depth of all agents.
+    pub depth: u8,
+    /// Seeds of the games. Every pair of agents plays each twice.
+    pub seeds: Vec<u64>,
+    /// Games are draws after this many actions, see `arena::play_game`.
+    pub max_plies: u32,
+    /// Change of a weight in a step of the local search.
+    pub step: i32,
+}
+
+impl Default for TunerConfig {
+    fn default() -> Self {
+        TunerConfig {
+            depth: 2,
+            seeds: (1..=8).collect(),
+            max_plies: 200,
+            step: 1,
+        }
+    }
+}
+
+/// Game of a round robin. Agents are indices of the entrants.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchGame {
+    pub black: usize,
+    pub white: usize,
+    pub seed: u64,
+    pub winner: Winner,
+    pub reason: Option<WinReason>,
+    pub plies: u32,
+}
+
+impl MatchGame {
+    /// Row of a CSV file, see `CSV_HEADER`. Agents are named by their
+    /// weights.
+    pub fn to_csv(&self, iteration: u32, entrants: &[EvalWeights]) -> String {
+        let winner = match self.winner {
+            Winner::Color(TileColor::Black) => "black",
+            Winner::Color(TileColor::White) => "white",
+            Winner::Draw => "draw",
+        };
+        format!(
+            "{},{},{},{},{},{},{}",
+            iteration,
+            self.seed,
+            weights_to_text(&entrants[self.black]),
+            weights_to_text(&entrants[self.white]),
+            winner,
+            self.reason
+                .map(|reason| format!("{:?}", reason))
+                .unwrap_or_default(),
+            self.plies
+        )
+    }
+}
+
+/// Results of `round_robin`. Standings are in the order of the entrants,
+/// named by their weights.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoundRobin {
+    pub standings: Vec<Standing>,
+    pub games: Vec<MatchGame>,
+}
+
+/// Round robin of agents with entrants as weights. Every pair plays every
+/// seed of config twice, the first of the pair is Black in the first game.
+pub fn round_robin(entrants: &[EvalWeights], config: &TunerConfig) -> RoundRobin {
+    let mut result = RoundRobin {
+        standings: entrants
+            .iter()
+            .map(|weights| Standing {
+                name: weights_to_text(weights),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let agent = |color, i: usize| {
+        let mut agent = Agent::new(color, Some(config.depth), None);
+        agent.weights = entrants[i];
+        agent
+    };
+
+    for i in 0..entrants.len() {
+        for j in i + 1..entrants.len() {
+            for seed in config.seeds.iter() {
+                for (b, w) in [(i, j), (j, i)] {
+                    let mut black = agent(TileColor::Black, b);
+                    let mut white = agent(TileColor::White, w);
+                    let game = play_game(&mut black, &mut white, config.max_plies, *seed);
+                    match game.winner {
+                        Winner::Color(TileColor::Black) => {
+                            result.standings[b].wins += 1;
+                            result.standings[w].losses += 1;
+                        }
+                        Winner::Color(TileColor::White) => {
+                            result.standings[w].wins += 1;
+                            result.standings[b].losses += 1;
+                        }
+                        Winner::Draw => {
+                            result.standings[b].draws += 1;
+                            result.standings[w].draws += 1;
+                        }
+                    }
+                    result.games.push(MatchGame {
+                        black: b,
+                        white: w,
+                        seed: *seed,
+                        winner: game.winner,
+                        reason: game.reason,
+                        plies: game.plies,
+                    });
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Progress of the local search, saved between steps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TunerState {
+    pub config: TunerConfig,
+    /// Best weights so far.
+    pub best: EvalWeights,
+    /// Steps done.
+    pub iteration: u32,
+    /// Steps that changed best.
+    pub accepted: u32,
+}
+
+impl TunerState {
+    pub fn new(config: TunerConfig, start: EvalWeights) -> TunerState {
+        TunerState {
+            config,
+            best: start,
+            iteration: 0,
+            accepted: 0,
+        }
+    }
+
+    /// Index of the weight changed by the next step. Steps go through the
+    /// weights in order, raising and then lowering each.
+    pub fn next_weight(&self) -> usize {
+        (self.iteration as usize / 2) % WEIGHT_NAMES.len()
+    }
+
+    /// Weights tried by the next step: best with one weight changed by
+    /// `TunerConfig::step`. Weights don't go below 0.
+    pub fn candidate(&self) -> EvalWeights {
+        let mut candidate = self.best;
+        let step = match self.iteration % 2 {
+            0 => self.config.step,
+            _ => -self.config.step,
+        };
+        let weight = weight(&mut candidate, self.next_weight());
+        *weight = (*weight + step).max(0);
+        candidate
+    }
+
+    /// State as text, one `key value` per line.
+    pub fn to_text(&self) -> String {
+        format!(
+            "depth {}\nseeds {}\nmax_plies {}\nstep {}\nbest {}\niteration {}\naccepted {}\n",
+            self.config.depth,
+            self.config
+                .seeds
+                .iter()
+                .map(|seed| seed.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            self.config.max_plies,
+            self.config.step,
+            weights_to_text(&self.best),
+            self.iteration,
+            self.accepted,
+        )
+    }
+
+    /// State from `to_text` output. `None` if a value is missing or invalid.
+    pub fn from_text(text: &str) -> Option<TunerState> {
+        let value = |key: &str| {
+            text.lines()
+                .filter_map(|line| line.trim().split_once(' '))
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.trim())
+        };
+
+        Some(TunerState {
+            config: TunerConfig {
+                depth: value("depth")?.parse().ok()?,
+                seeds: value("seeds")?
+                    .split_whitespace()
+                    .map(|seed| seed.parse().ok())
+                    .collect::<Option<_>>()?,
+                max_plies: value("max_plies")?.parse().ok()?,
+                step: value("step")?.parse().ok()?,
+            },
+            best: weights_from_text(value("best")?)?,
+            iteration: value("iteration")?.parse().ok()?,
+            accepted: value("accepted")?.parse().ok()?,
+        })
+    }
+
+    /// Load state from file. `None` if file does not exist or is invalid.
+    pub fn load(path: &Path) -> Result<Option<TunerState>, PersistError> {
+        let contents = persist::read(path, persist::DEFAULT_LOCK_TIMEOUT)?;
+        Ok(contents.and_then(|c| TunerState::from_text(&String::from_utf8_lossy(&c))))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), PersistError> {
+        persist::write(
+            path,
+            self.to_text().as_bytes(),
+            persist::DEFAULT_LOCK_TIMEOUT,
+        )
+    }
+}
+
+/// Outcome of `tune_step`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TunerStep {
+    pub iteration: u32,
+    pub candidate: EvalWeights,
+    /// Standing of candidate against the best weights. No games when the
+    /// candidate is the same as best, a weight of 0 can't be lowered.
+    pub standing: Standing,
+    pub accepted: bool,
+    /// Games of the step. Entrant 0 is best, entrant 1 candidate.
+    pub games: Vec<MatchGame>,
+}
+
+/// Play the candidate of state against the best weights, and make it best
+/// if it scores more than half. Advances state by one iteration.
+pub fn tune_step(state: &mut TunerState) -> TunerStep {
+    let candidate = state.candidate();
+    let mut step = TunerStep {
+        iteration: state.iteration,
+        candidate,
+        standing: Standing {
+            name: weights_to_text(&candidate),
+            ..Default::default()
+        },
+        accepted: false,
+        games: Vec::new(),
+    };
+
+    if candidate != state.best {
+        let result = round_robin(&[state.best, candidate], &state.config);
+        step.standing = result.standings[1].clone();
+        step.games = result.games;
+        step.accepted = step.standing.mean_score() > 0.5;
+    }
+
+    if step.accepted {
+        state.best = candidate;
+        state.accepted += 1;
+    }
+    state.iteration += 1;
+    step
+}
@@ -0,0 +1,155 @@
+//! Engine for GUIs, other engines and scripts. Reads commands from stdin and
+//! answers on stdout, one per line, modeled on UCI.
+//!
+//! | Command                                 | Reply                    |
+//! |-----------------------------------------|--------------------------|
+//! | `isready`                               | `readyok`                |
+//! | `newgame`                               |                          |
+//! | `position startpos [moves <action>...]` |                          |
+//! | `go [depth <n> \| movetime <ms>]`       | `bestmove <action>`      |
+//! | `quit`                                  |                          |
+//!
+//! `newgame` forgets the position and what earlier searches learned. Actions
+//! are in the notation of `logic::notation`, one after the other, e.g.
+//! `position startpos moves @c1 Duke @c6 Duke`. Without limits `go` searches
+//! to depth 3, and answers `bestmove none` when the game is over.
+//!
+//! Tiles are drawn by the caller, so every engine of a game sees the same
+//! bag: draws name the drawn tile, e.g. `draw Footman`. When the engine
+//! answers `bestmove draw`, the caller draws a tile, sends the position with
+//! the draw and asks again for the placement.
+//!
+//! Invalid commands are answered with `error <reason>`, the position stays
+//! as it was.
+
+use rusty_duke_logic::ai::alpha_beta::Agent;
+use rusty_duke_logic::ai::DukeAgent;
+use rusty_duke_logic::logic::notation::action_to_notation;
+use rusty_duke_logic::logic::record::GameRecord;
+use rusty_duke_logic::logic::{GameState, TileColor};
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+/// Search depth of `go` without limits.
+const DEFAULT_DEPTH: u8 = 3;
+
+struct Engine {
+    state: GameState,
+    /// Agents of Black and White, kept between searches of a game.
+    agents: [Agent; 2],
+}
+
+impl Engine {
+    fn new() -> Engine {
+        Engine {
+            state: GameRecord::new(None).replay(),
+            agents: [
+                Agent::new(TileColor::Black, Some(DEFAULT_DEPTH), None),
+                Agent::new(TileColor::White, Some(DEFAULT_DEPTH), None),
+            ],
+        }
+    }
+
+    /// Best action of the color to go, within the limits of `go`.
+    fn go(&mut self, words: &[&str]) -> Result<String, String> {
+        let (depth, duration) = match words {
+            [] => (Some(DEFAULT_DEPTH), None),
+            ["depth", depth] => match depth.parse::<u8>() {
+                Ok(depth) if depth > 0 => (Some(depth), None),
+                _ => return Err(format!("invalid depth: {}", depth)),
+            },
+            ["movetime", ms] => match ms.parse::<u64>() {
+                Ok(ms) if ms > 0 => (None, Some(Duration::from_millis(ms))),
+                _ => return Err(format!("invalid movetime: {}", ms)),
+            },
+            _ => return Err(format!("invalid limits: {}", words.join(" "))),
+        };
+        if self.state.game_over.is_some() {
+            return Ok("bestmove none".to_string());
+        }
+
+        let agent = &mut self.agents[self.state.ply as usize];
+        agent.depth = depth;
+        agent.duration = duration;
+        Ok(match agent.choose_action(&self.state) {
+            Some(action) => format!("bestmove {}", action_to_notation(&self.state, &action)),
+            None => "bestmove none".to_string(),
+        })
+    }
+}
+
+/// Actions of a move list, e.g. `Footman b1-b2` from its two words. Draws,
+/// placements and moves are two words, commands three.
+fn split_actions(words: &[&str]) -> Result<Vec<String>, String> {
+    let mut actions = Vec::new();
+    let mut rest = words;
+    while let Some(first) = rest.first() {
+        let len = match rest.get(1) {
+            // Commander, its square and the move of the commanded tile.
+            Some(second)
+                if *first != "draw" && !first.starts_with('@') && !second.contains(['-', 'x']) =>
+            {
+                3
+            }
+            _ => 2,
+        };
+        if rest.len() < len {
+            return Err(format!("incomplete action: {}", rest.join(" ")));
+        }
+        actions.push(rest[..len].join(" "));
+        rest = &rest[len..];
+    }
+    Ok(actions)
+}
+
+/// State of a `position` command. Actions are checked as in game records.
+fn position(words: &[&str]) -> Result<GameState, String> {
+    let moves = match words {
+        ["startpos"] => &[][..],
+        ["startpos", "moves", moves @ ..] => moves,
+        _ => return Err(format!("invalid position: {}", words.join(" "))),
+    };
+
+    let mut text = "seed -\n".to_string();
+    for action in split_actions(moves)? {
+        text.push_str(&action);
+        text.push('\n');
+    }
+    let record = GameRecord::from_text(&text).map_err(|e| e.to_string())?;
+    Ok(record.replay())
+}
+
+fn main() -> io::Result<()> {
+    let mut engine = Engine::new();
+    let mut stdout = io::stdout();
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let reply = match words.as_slice() {
+            [] => continue,
+            ["quit"] => break,
+            ["isready"] => Ok(Some("readyok".to_string())),
+            ["newgame"] => {
+                engine = Engine::new();
+                Ok(None)
+            }
+            ["position", words @ ..] => position(words).map(|state| {
+                engine.state = state;
+                None
+            }),
+            ["go", words @ ..] => engine.go(words).map(Some),
+            _ => Err(format!("unknown command: {}", line.trim())),
+        };
+
+        let reply = match reply {
+            Ok(reply) => reply,
+            Err(e) => Some(format!("error {}", e)),
+        };
+        if let Some(reply) = reply {
+            writeln!(stdout, "{}", reply)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,176 @@
+//! Tuning of evaluation weights by self play, see `ai::tuner`.
+//!
+//! Without `--weights`, runs `--iterations` steps of the local search from
+//! the default weights, or from where the state file of an earlier run left
+//! off. The state file is saved after every step, and the games of every step
+//! are appended to the CSV file. Depth, seeds, max plies and step of a
+//! resumed run are those of the state file.
+//!
+//! With `--weights` given twice or more, e.g. `--weights "2 1 2 1 2 2 1"
+//! --weights "0 0 0 0 0 0 0"`, plays a round robin of the weights instead,
+//! and appends its games to the CSV file as iteration 0.
+//!
+//! | Option             | Default                                  |
+//! |--------------------|------------------------------------------|
+//! | `--iterations <n>` | 14, every weight raised and lowered once |
+//! | `--depth <n>`      | 2                                        |
+//! | `--seeds <n>`      | 8, games are seeded 1 to n               |
+//! | `--max-plies <n>`  | 200                                      |
+//! | `--step <n>`       | 1                                        |
+//! | `--state <file>`   | `rusty-duke-tuner.txt`                   |
+//! | `--csv <file>`     | `rusty-duke-tuner.csv`                   |
+
+use rusty_duke_logic::ai::alpha_beta::EvalWeights;
+use rusty_duke_logic::ai::arena::Standing;
+use rusty_duke_logic::ai::tuner::{
+    round_robin, tune_step, weights_from_text, weights_to_text, MatchGame, TunerConfig, TunerState,
+    CONFIDENCE_Z, CSV_HEADER, WEIGHT_NAMES,
+};
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+const DEFAULT_STATE_FILE: &str = "rusty-duke-tuner.txt";
+const DEFAULT_CSV_FILE: &str = "rusty-duke-tuner.csv";
+
+/// Values of all occurrences of option, e.g. "--depth".
+fn arg_values(option: &str) -> Vec<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.windows(2)
+        .filter(|pair| pair[0] == option)
+        .map(|pair| pair[1].clone())
+        .collect()
+}
+
+/// Last value of option parsed, or default if not given.
+fn parsed_arg<T>(option: &str, default: T) -> Result<T, Box<dyn Error>>
+where
+    T: std::str::FromStr,
+    T::Err: Error + 'static,
+{
+    match arg_values(option).pop() {
+        Some(value) => Ok(value.parse()?),
+        None => Ok(default),
+    }
+}
+
+/// Config of the options, defaults for those not given.
+fn config_from_args() -> Result<TunerConfig, Box<dyn Error>> {
+    let default = TunerConfig::default();
+    Ok(TunerConfig {
+        depth: parsed_arg("--depth", default.depth)?,
+        seeds: (1..=parsed_arg("--seeds", default.seeds.len() as u64)?).collect(),
+        max_plies: parsed_arg("--max-plies", default.max_plies)?,
+        step: parsed_arg("--step", default.step)?,
+    })
+}
+
+/// Standing with score and confidence interval.
+fn describe(standing: &Standing) -> String {
+    let (low, high) = standing.confidence_interval(CONFIDENCE_Z);
+    format!(
+        "{}-{}-{}, score {:.3} ({:.3} to {:.3})",
+        standing.wins,
+        standing.draws,
+        standing.losses,
+        standing.mean_score(),
+        low,
+        high
+    )
+}
+
+/// Append games to the CSV file, with a header if the file is new.
+fn append_csv(
+    path: &Path,
+    iteration: u32,
+    entrants: &[EvalWeights],
+    games: &[MatchGame],
+) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        writeln!(file, "{}", CSV_HEADER)?;
+    }
+    for game in games {
+        writeln!(file, "{}", game.to_csv(iteration, entrants))?;
+    }
+    file.flush()
+}
+
+fn run_round_robin(weights: &[String], csv: &Path) -> Result<(), Box<dyn Error>> {
+    let entrants: Vec<EvalWeights> = weights
+        .iter()
+        .map(|text| weights_from_text(text).ok_or(format!("Invalid weights: {}", text)))
+        .collect::<Result<_, _>>()?;
+    let result = round_robin(&entrants, &config_from_args()?);
+    append_csv(csv, 0, &entrants, &result.games)?;
+    for standing in result.standings.iter() {
+        println!("{:<20} {}", standing.name, describe(standing));
+    }
+    Ok(())
+}
+
+fn run_local_search(state_path: &Path, csv: &Path) -> Result<(), Box<dyn Error>> {
+    let iterations: u32 = parsed_arg("--iterations", 2 * WEIGHT_NAMES.len() as u32)?;
+    let mut state = match TunerState::load(state_path)? {
+        Some(state) => {
+            println!(
+                "Resuming at iteration {} from {}.",
+                state.iteration,
+                state_path.display()
+            );
+            state
+        }
+        None => TunerState::new(config_from_args()?, EvalWeights::default()),
+    };
+
+    for _ in 0..iterations {
+        let name = WEIGHT_NAMES[state.next_weight()];
+        let best = state.best;
+        let step = tune_step(&mut state);
+
+        // Games first, an interrupted run repeats at most the games of one
+        // step.
+        append_csv(csv, step.iteration, &[best, step.candidate], &step.games)?;
+        state.save(state_path)?;
+
+        println!(
+            "Iteration {}: {} {}, {}{}",
+            step.iteration,
+            name,
+            weights_to_text(&step.candidate),
+            describe(&step.standing),
+            if step.accepted { ", kept" } else { "" }
+        );
+    }
+
+    println!(
+        "Best weights after {} iterations, {} kept: {}",
+        state.iteration,
+        state.accepted,
+        weights_to_text(&state.best)
+    );
+    for (name, value) in WEIGHT_NAMES
+        .iter()
+        .zip(weights_to_text(&state.best).split(' '))
+    {
+        println!("{:<14}{:>4}", name, value);
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let state = arg_values("--state")
+        .pop()
+        .unwrap_or(DEFAULT_STATE_FILE.to_string());
+    let csv = arg_values("--csv")
+        .pop()
+        .unwrap_or(DEFAULT_CSV_FILE.to_string());
+
+    let weights = arg_values("--weights");
+    match weights.len() {
+        0 => run_local_search(Path::new(&state), Path::new(&csv)),
+        1 => Err("A round robin needs at least two --weights.".into()),
+        _ => run_round_robin(&weights, Path::new(&csv)),
+    }
+}
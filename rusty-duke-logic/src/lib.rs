@@ -12,3 +12,11 @@ extern crate lazy_static;
 
 pub mod ai;
 pub mod logic;
+#[cfg(feature = "net")]
+pub mod net;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod persist;
+pub mod session;
+pub mod time;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -1,17 +1,111 @@
 //! Implements logic for the Rusty Duke game.
 
+use crate::ai::alpha_beta::tile_value;
+use rand::Rng;
+use rng::GameRng;
 use std::collections::HashMap;
 use std::fmt;
 pub use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-/// Width of game board in squares.
+pub mod builder;
+pub mod cache;
+pub mod cards;
+pub mod clock;
+pub mod fast;
+pub mod fen;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod notation;
+pub mod perft;
+pub mod record;
+pub mod rng;
+#[cfg(feature = "serde")]
+pub mod save;
+pub mod tiles;
+pub mod validate;
+pub mod variant;
+
+use variant::{adjudicate, BagComposition, Expansion, GameRules, GameSetup, START_TILES};
+
+/// Width of the standard board in squares.
 pub const WIDTH: u8 = 6;
-/// Height of game board in squares.
+/// Height of the standard board in squares.
 pub const HEIGHT: u8 = 6;
+/// Width of the largest board, see `BoardSize`.
+pub const MAX_WIDTH: u8 = 8;
+/// Height of the largest board, see `BoardSize`.
+pub const MAX_HEIGHT: u8 = 8;
+
+/// Width and height of a board in squares. Width is even, so the Duke starts
+/// next to the middle of the back row, see `initial_duke_squares`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoardSize {
+    pub width: u8,
+    pub height: u8,
+}
+
+impl BoardSize {
+    /// Board of the printed game, 6x6.
+    pub const STANDARD: BoardSize = BoardSize {
+        width: WIDTH,
+        height: HEIGHT,
+    };
+    /// Largest board, 8x8. Squares fit in the bits of a `u64`.
+    pub const MAX: BoardSize = BoardSize {
+        width: MAX_WIDTH,
+        height: MAX_HEIGHT,
+    };
+
+    pub fn legal(width: u8, height: u8) -> bool {
+        (2..=MAX_WIDTH).contains(&width)
+            && width.is_multiple_of(2)
+            && (2..=MAX_HEIGHT).contains(&height)
+    }
+
+    pub fn new(width: u8, height: u8) -> BoardSize {
+        assert!(BoardSize::legal(width, height), "Illegal board size.");
+        BoardSize { width, height }
+    }
+
+    /// Size, or `None` if no board can have it.
+    pub fn try_new(width: u8, height: u8) -> Option<BoardSize> {
+        if BoardSize::legal(width, height) {
+            Some(BoardSize { width, height })
+        } else {
+            None
+        }
+    }
+
+    /// True if square is on the board.
+    pub fn contains(&self, cord: Coordinate) -> bool {
+        Coordinate::legal(cord.x, cord.y, *self)
+    }
+
+    /// All squares, row by row from y = 0.
+    pub fn squares(self) -> impl Iterator<Item = Coordinate> {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| Coordinate { x, y }))
+    }
+
+    /// Square mirrored left-right.
+    pub fn mirror(&self, cord: Coordinate) -> Coordinate {
+        Coordinate {
+            x: self.width - 1 - cord.x,
+            y: cord.y,
+        }
+    }
+}
+
+impl Default for BoardSize {
+    fn default() -> BoardSize {
+        BoardSize::STANDARD
+    }
+}
 
 /// Board Coordinate
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Coordinate {
     // FIXME: Use wrapping and/or ranged integers?
     pub x: u8,
@@ -19,25 +113,46 @@ pub struct Coordinate {
 }
 
 impl Coordinate {
-    pub fn legal(x: u8, y: u8) -> bool {
-        x < WIDTH && y < HEIGHT
+    /// True if square is on a board of size.
+    pub fn legal(x: u8, y: u8, size: BoardSize) -> bool {
+        x < size.width && y < size.height
     }
 
+    /// Square of the largest board. Whether it is on the board of a game is
+    /// checked with `GameState::size`.
     // FIXME: Make sure all coordinates are made with new() to avoid bugs.
     pub fn new(x: u8, y: u8) -> Coordinate {
-        assert!(Coordinate::legal(x, y));
+        assert!(Coordinate::legal(x, y, BoardSize::MAX));
         Coordinate { x: x, y: y }
     }
+
+    /// Coordinate, or `None` if it is off a board of size.
+    pub fn try_new(x: u8, y: u8, size: BoardSize) -> Option<Coordinate> {
+        if Coordinate::legal(x, y, size) {
+            Some(Coordinate { x, y })
+        } else {
+            None
+        }
+    }
+
+    /// Coordinate moved by offset, or `None` if that is off a board of size.
+    /// Never wraps, whatever the offset.
+    pub fn offset(&self, off: &Offset, size: BoardSize) -> Option<Coordinate> {
+        let x = u8::try_from(self.x as i16 + off.x as i16).ok()?;
+        let y = u8::try_from(self.y as i16 + off.y as i16).ok()?;
+        Coordinate::try_new(x, y, size)
+    }
 }
 
-/// Direction relative to tile.
-struct Direction {
-    x: i8,
-    y: i8,
+/// Square in notation, e.g. `c1`.
+impl fmt::Display for Coordinate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.x) as char, self.y + 1)
+    }
 }
 
 /// Offset relative to tile.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Offset {
     pub x: i8,
     pub y: i8,
@@ -50,11 +165,12 @@ fn invert_offset(offset: &Offset) -> Offset {
     }
 }
 
-/// Take two coordinates and calculate direction. Only works for straight lines and diagonals.
-fn get_direction(start: Coordinate, end: Coordinate) -> Direction {
+/// Take two coordinates and calculate direction, as offset of one square. Only
+/// works for straight lines and diagonals.
+fn get_direction(start: Coordinate, end: Coordinate) -> Offset {
     debug_assert!(start != end);
 
-    let mut dir = Direction { x: 0, y: 0 };
+    let mut dir = Offset { x: 0, y: 0 };
 
     if start.x < end.x {
         dir.x = 1;
@@ -73,42 +189,136 @@ fn get_direction(start: Coordinate, end: Coordinate) -> Direction {
 
 /// Effect imposed by tile on square.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
 pub enum Effect {
-    Dread,
-    Defence,
+    Dread = 0,
+    Defence = 1,
 }
 
-/// Square on board. Can have a tile and effects.
-#[derive(Debug, Clone)]
-pub struct Square {
-    pub effects: Vec<Effect>,
-    pub tile: Option<Tile>,
+/// Effects on a square, by color of the tiles imposing them. Effects of
+/// several tiles stack, so each effect of each color has a 4 bit count
+/// instead of a single bit. Serialized as a list of effects and colors, one
+/// per tile imposing it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "Vec<(Effect, TileColor)>", into = "Vec<(Effect, TileColor)>")
+)]
+pub struct EffectSet(u16);
+
+impl EffectSet {
+    fn shift(effect: &Effect, color: TileColor) -> u16 {
+        let effect = match effect {
+            Effect::Dread => 0,
+            Effect::Defence => 8,
+        };
+        effect + 4 * color as u16
+    }
+
+    /// Number of tiles of color imposing effect on square.
+    pub fn count_of(self, effect: &Effect, color: TileColor) -> u8 {
+        ((self.0 >> EffectSet::shift(effect, color)) & 0xf) as u8
+    }
+
+    /// Number of tiles of either color imposing effect on square.
+    pub fn count(self, effect: &Effect) -> u8 {
+        self.count_of(effect, TileColor::Black) + self.count_of(effect, TileColor::White)
+    }
+
+    pub fn contains(self, effect: &Effect) -> bool {
+        self.count(effect) > 0
+    }
+
+    /// True if a tile of color imposes effect on square.
+    pub fn contains_of(self, effect: &Effect, color: TileColor) -> bool {
+        self.count_of(effect, color) > 0
+    }
+
+    /// Number of effects, counting stacked effects once per tile.
+    pub fn len(self) -> usize {
+        (self.count(&Effect::Dread) + self.count(&Effect::Defence)) as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Effects and colors, one per tile imposing it. Dread first, Black
+    /// first.
+    pub fn iter(self) -> impl Iterator<Item = (Effect, TileColor)> {
+        [Effect::Dread, Effect::Defence]
+            .into_iter()
+            .flat_map(|effect| {
+                [TileColor::Black, TileColor::White]
+                    .into_iter()
+                    .map(move |color| (effect.clone(), color))
+            })
+            .flat_map(move |(effect, color)| {
+                let count = self.count_of(&effect, color) as usize;
+                std::iter::repeat_n((effect, color), count)
+            })
+    }
+
+    fn add(&mut self, effect: &Effect, color: TileColor) {
+        debug_assert!(self.count_of(effect, color) < 0xf, "Effect count overflow.");
+        self.0 += 1 << EffectSet::shift(effect, color);
+    }
+
+    fn remove(&mut self, effect: &Effect, color: TileColor) {
+        if self.contains_of(effect, color) {
+            self.0 -= 1 << EffectSet::shift(effect, color);
+        }
+    }
+
+    /// Effects as a list, same order as `iter`.
+    pub fn to_vec(self) -> Vec<(Effect, TileColor)> {
+        self.iter().collect()
+    }
 }
 
-impl Default for Square {
-    fn default() -> Self {
-        Square {
-            effects: Vec::new(),
-            tile: None,
+impl From<Vec<(Effect, TileColor)>> for EffectSet {
+    fn from(effects: Vec<(Effect, TileColor)>) -> EffectSet {
+        let mut set = EffectSet::default();
+        for (effect, color) in effects.iter() {
+            set.add(effect, *color);
         }
+        set
     }
 }
 
+impl From<EffectSet> for Vec<(Effect, TileColor)> {
+    fn from(effects: EffectSet) -> Vec<(Effect, TileColor)> {
+        effects.to_vec()
+    }
+}
+
+/// Square on board. Can have a tile and effects.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Square {
+    pub effects: EffectSet,
+    pub tile: Option<Tile>,
+}
+
 /// Action type that a tile can perform.
 #[derive(Debug, Clone, PartialEq)]
+#[repr(u8)]
 pub enum ActionType {
-    NewFromBag,
-    PlaceNew,
-    Move,
-    Jump,
-    JumpSlide,
-    Slide,
-    Command,
-    Strike,
+    NewFromBag = 0,
+    PlaceNew = 1,
+    Move = 2,
+    Jump = 3,
+    JumpSlide = 4,
+    Slide = 5,
+    Command = 6,
+    Strike = 7,
 }
 
 /// Data included with standard tile action.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ActionData {
     pub tile_pos: Coordinate,
     pub target_pos: Coordinate,
@@ -116,7 +326,8 @@ pub struct ActionData {
 }
 
 /// Data included with command tile action.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CommandActionData {
     pub tile_pos: Coordinate,
     pub command_tile_pos: Coordinate,
@@ -125,7 +336,8 @@ pub struct CommandActionData {
 }
 
 /// Action that a tile can perform.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Action {
     NewFromBag,
     PlaceNew(Coordinate),
@@ -139,6 +351,7 @@ pub enum Action {
 
 /// Result that action has on game state.
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ActionResult {
     Move,
     Capture,
@@ -151,6 +364,20 @@ pub struct AvailableAction {
     offset: Offset,
 }
 
+impl AvailableAction {
+    /// Action of kind at offset, as seen by Black. For tile definitions made
+    /// in code, see `tiles::TileDefinitions`.
+    pub fn new(kind: ActionType, offset: Offset) -> AvailableAction {
+        AvailableAction { kind, offset }
+    }
+
+    /// Offset from tile, as seen by Black. For Slide and JumpSlide this is
+    /// the direction.
+    pub fn offset(&self) -> Offset {
+        self.offset.clone()
+    }
+}
+
 /// Specifies an effect of a tile type.
 #[derive(Debug, Clone)]
 pub struct AvailableEffect {
@@ -158,49 +385,123 @@ pub struct AvailableEffect {
     offset: Offset,
 }
 
+impl AvailableEffect {
+    /// Offset from tile, as seen by Black.
+    pub fn offset(&self) -> Offset {
+        self.offset.clone()
+    }
+}
+
 /// Specifies possible tile colors.
 #[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TileColor {
     Black,
     White,
 }
 
+impl TileColor {
+    /// The other color.
+    pub fn opponent(self) -> TileColor {
+        match self {
+            TileColor::Black => TileColor::White,
+            TileColor::White => TileColor::Black,
+        }
+    }
+}
+
 /// Contains winner of game.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Winner {
     Color(TileColor),
-    //Draw, Draw does not exist in duke?
+    /// Not in the printed rules, only with the draw rules of `GameRules` or
+    /// by agreement.
+    Draw,
+}
+
+/// Why the game ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WinReason {
+    /// Loser's Duke was captured.
+    DukeCaptured,
+    /// Loser's Duke was in guard and could not escape.
+    Guardmate,
+    /// Loser could not do any action without leaving the Duke in guard.
+    NoMoves,
+    /// Turn limit was reached, and winner was adjudicated. Has the
+    /// adjudication score, positive if Black was better. See `variant`.
+    TurnLimitAdjudication(i32),
+    /// Draw, same position was repeated too often. See
+    /// `GameRules::repetition_limit`.
+    Repetition,
+    /// Draw, too many turns without capture or placing a tile. See
+    /// `GameRules::no_capture_limit`.
+    NoCaptureLimit,
+    /// Loser ran out of time. See `clock` and `flag_fall`.
+    Timeout,
+    /// Loser resigned. See `resign`.
+    Resignation,
+    /// Draw, offered by one player and accepted by the other. See
+    /// `offer_draw`.
+    DrawAgreed,
+    /// Game was ended early by the arena, both agents evaluated it as
+    /// decided. See `ai::arena::Adjudication`.
+    Adjudicated,
 }
 
 /// Tile type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
 pub enum TileType {
+    // Tile ids are stable, they are used in files and over the network.
+    // Never reuse or renumber an id. New tiles get the next free id, and
+    // TILE_ID_TABLE_VERSION is bumped.
+
     // Basic tiles
-    Duke,
-    Footman,
-    Pikeman,
-    Knight,
-    Bowman,
-    LightHorse,
-    Wizard,
-    Seer,
-    Champion,
-    Arbalist,
-    General,
-    Marshall,
-    Countess,
-    Ranger,
-    Sage,
-    RoyalAssassin,
+    Duke = 0,
+    Footman = 1,
+    Pikeman = 2,
+    Knight = 3,
+    Bowman = 4,
+    LightHorse = 5,
+    Wizard = 6,
+    Seer = 7,
+    Champion = 8,
+    Arbalist = 9,
+    General = 10,
+    Marshall = 11,
+    Countess = 12,
+    Ranger = 13,
+    Sage = 14,
+    RoyalAssassin = 15,
     // Arthurian legends tiles
-    /*Arthur,
-    Guinevere,
-    Lancelot,
-    Perceval,
-    Merlin,
-    Camelot,
-    Morgana,
-    Mordred,*/
+    Arthur = 16,
+    Guinevere = 17,
+    Lancelot = 18,
+    Perceval = 19,
+    Merlin = 20,
+    Camelot = 21,
+    Morgana = 22,
+    Mordred = 23,
+}
+
+/// Version of the tile id table. Binary formats should store this and refuse
+/// to load data written with another version.
+pub const TILE_ID_TABLE_VERSION: u32 = 2;
+
+impl TileType {
+    /// Stable id of tile type.
+    pub fn to_id(self) -> u8 {
+        self as u8
+    }
+
+    /// Tile type from stable id. None if id is unknown.
+    pub fn from_id(id: u8) -> Option<TileType> {
+        TileType::iter().find(|kind| kind.to_id() == id)
+    }
 }
 
 impl fmt::Display for TileType {
@@ -209,20 +510,46 @@ impl fmt::Display for TileType {
     }
 }
 
+/// Tile type name that is not a `TileType`, e.g. a tile of a newer version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownTileType(pub String);
+
+impl fmt::Display for UnknownTileType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown tile type {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownTileType {}
+
+impl std::str::FromStr for TileType {
+    type Err = UnknownTileType;
+
+    /// Tile type from its name, as written by `Display`.
+    fn from_str(s: &str) -> Result<TileType, UnknownTileType> {
+        TileType::iter()
+            .find(|kind| kind.to_string() == s)
+            .ok_or_else(|| UnknownTileType(s.to_string()))
+    }
+}
+
 /// Actions that a tile type can perform.
+#[derive(Debug, Clone)]
 pub struct AvailableActions {
     pub front: Vec<AvailableAction>,
     pub back: Vec<AvailableAction>,
 }
 
 /// Effects that a tile type can perform.
+#[derive(Debug, Clone)]
 pub struct AvailableEffects {
     pub front: Vec<AvailableEffect>,
     pub back: Vec<AvailableEffect>,
 }
 
 /// Tile that can be played. Will be owned by bag, board or graveyard.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tile {
     pub kind: TileType,
     pub flipped: bool,
@@ -237,915 +564,17 @@ impl Tile {
 
 lazy_static! {
 
-    /// This is where tile types are defined.
-    pub static ref TILE_ACTIONS: HashMap<TileType, AvailableActions> = {
-
-        // FIXME: Specify tiles and rules in configuration file.
-        // FIXME: Use more suitable hashing algorithm.
-
-        // For future reference:
-        // for some reason HashMap method `from` does not work here.
-        // Maybe related to lazy static.
-
-        let mut m = HashMap::new();
-        m.insert(
-            TileType::Duke, /* Type */
-            AvailableActions{
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Slide,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Slide,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Slide,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Slide,
-                        offset: Offset { x: 0, y: -1 },
-                    },
-                ],
-            }
-        );
-        m.insert(
-            TileType::Footman, /* Type */
-            AvailableActions {
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: 1 },
-                    },
-                ],
-            }
-        );
-        m.insert(
-            TileType::Pikeman, /* Type */
-            AvailableActions{
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 2, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -2, y: 2 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Strike,
-                        offset: Offset { x: 1, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Strike,
-                        offset: Offset { x: -1, y: 2 },
-                    },
-                ],
-            }
-        );
-        m.insert(
-            TileType::Knight, /* Type */
-            AvailableActions{
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 1, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -1, y: 2 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Slide,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 2, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -2, y: -2 },
-                    },
-                ],
-            }
-        );
-        m.insert(
-            TileType::Bowman, /* Type */
-            AvailableActions{
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 0, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -2, y: 0 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Strike,
-                        offset: Offset { x: 0, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Strike,
-                        offset: Offset { x: 1, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Strike,
-                        offset: Offset { x: -1, y: 1 },
-                    },
-                ],
-            }
-        );
-        m.insert(
-            TileType::LightHorse, /* Type */
-            AvailableActions{
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Slide,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: -1 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Strike,
-                        offset: Offset { x: 1, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 2, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -2, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Strike,
-                        offset: Offset { x: -1, y: 2 },
-                    },
-                ],
-            }
-        );
-        m.insert(
-            TileType::Wizard, /* Type */
-            AvailableActions{
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: 1 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 0, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 2, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 2, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 0, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -2, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -2, y: 2 },
-                    },
-                ],
-            }
-        );
-        m.insert(
-            TileType::Seer, /* Type */
-            AvailableActions{
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 0, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 0, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: 1 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 2, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 2, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -2, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -2, y: 2 },
-                    },
-                ],
-            }
-        );
-        m.insert(
-            TileType::Champion, /* Type */
-            AvailableActions{
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 0, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 0, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -2, y: 0 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Strike,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 0, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Strike,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Strike,
-                        offset: Offset { x: 0, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 0, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Strike,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -2, y: 0 },
-                    },
-                ],
-            }
-        );
-        m.insert(
-            TileType::Arbalist, /* Type */
-            AvailableActions{
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Strike,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Strike,
-                        offset: Offset { x: 0, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 1, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -1, y: -2 },
-                    },
-                ],
-            }
-        );
-        m.insert(
-            TileType::General, /* Type */
-            AvailableActions{
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 1, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -1, y: 2 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 1, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -1, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Command,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Command,
-                        offset: Offset { x: 1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Command,
-                        offset: Offset { x: 0, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Command,
-                        offset: Offset { x: -1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Command,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                ],
-            }
-        );
-        m.insert(
-            TileType::Marshall, /* Type */
-            AvailableActions{
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 2, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Slide,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 0, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Slide,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -2, y: 2 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Command,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Command,
-                        offset: Offset { x: 1, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Command,
-                        offset: Offset { x: -1, y: 1 },
-                    },
-                ],
-            }
-        );
-        m.insert(
-            TileType::Countess, /* Type */
-            AvailableActions{
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Command,
-                        offset: Offset { x: 2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Command,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Command,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Command,
-                        offset: Offset { x: -2, y: 0 },
-                    },
-                ],
-            }
-        );
-        m.insert(
-            TileType::Ranger, /* Type */
-            AvailableActions{
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Slide,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 1, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 2, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -2, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -1 , y: 2 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Slide,
-                        offset: Offset { x: 1, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: 1, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Jump,
-                        offset: Offset { x: -1, y: -2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Slide,
-                        offset: Offset { x: -1, y: 1 },
-                    },
-                ],
-            }
-        );
-        m.insert(
-            TileType::Sage, /* Type */
-            AvailableActions{
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 1, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -1, y: -1 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: 2 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 2, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: -2, y: 0 },
-                    },
-                ],
-            }
-        );
-        m.insert(
-            TileType::RoyalAssassin, /* Type */
-            AvailableActions{
-                front: vec![
-                    /* Front side */
-                    AvailableAction {
-                        kind: ActionType::Move,
-                        offset: Offset { x: 0, y: -1 },
-                    },
-                ],
-                back: vec![
-                    /* Back side */
-                    AvailableAction {
-                        kind: ActionType::Slide,
-                        offset: Offset { x: 0, y: 1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Slide,
-                        offset: Offset { x: 1, y: 0 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Slide,
-                        offset: Offset { x: 0, y: -1 },
-                    },
-                    AvailableAction {
-                        kind: ActionType::Slide,
-                        offset: Offset { x: -1, y: 0 },
-                    },
-                ],
-        }
-        );
-        m
-    };
+    /// Actions of tile types, as seen by Black. Defined in `tiles.toml`, or
+    /// in a custom file, see `tiles`.
+    // FIXME: Use more suitable hashing algorithm.
+    pub static ref TILE_ACTIONS: HashMap<TileType, AvailableActions> =
+        tiles::definitions_in_use().actions;
 
     pub static ref NO_EFFECTS: AvailableEffects = AvailableEffects{front: vec![], back: vec![]};
 
-    pub static ref TILE_EFFECTS: HashMap<TileType, AvailableEffects>  = {
-        let m = HashMap::new();
-            // Effect tiles will be added.
-            m
-    };
+    /// Effects of tile types. Only tile types with effects are here.
+    pub static ref TILE_EFFECTS: HashMap<TileType, AvailableEffects> =
+        tiles::definitions_in_use().effects;
 
     /// Same as `TILE_ACTIONS` but inverted offsets. (For white player.)
     static ref INVERTED_TILE_ACTIONS: HashMap<TileType, AvailableActions> = {
@@ -1217,18 +646,17 @@ impl Tile {
         }
     }
 
+    /// Actions of tile. Every tile type has actions, built in definitions
+    /// are complete and `tiles::load_tile_definitions_from` rejects custom
+    /// definitions that are not. Unknown tile names are rejected when parsed,
+    /// see `UnknownTileType`.
     fn actions(&self) -> &'static AvailableActions {
-        if self.color == TileColor::Black {
-            return TILE_ACTIONS
-                .get(&self.kind)
-                .as_ref()
-                .expect("Illegal tile type.");
+        let actions = if self.color == TileColor::Black {
+            TILE_ACTIONS.get(&self.kind)
         } else {
-            return INVERTED_TILE_ACTIONS
-                .get(&self.kind)
-                .as_ref()
-                .expect("Illegal tile type.");
-        }
+            INVERTED_TILE_ACTIONS.get(&self.kind)
+        };
+        actions.expect("Tile type without actions.")
     }
 
     fn effects(&self) -> &'static AvailableEffects {
@@ -1250,149 +678,469 @@ impl Tile {
     }
 }
 
+/// Actions of one side of tile type, with offsets inverted for White. For
+/// UIs drawing the movement diagram of a tile.
+pub fn tile_movement_grid(
+    kind: TileType,
+    flipped: bool,
+    color: TileColor,
+) -> Vec<(Offset, ActionType)> {
+    let actions = Tile::new(kind, color).actions();
+    let side = if flipped {
+        &actions.back
+    } else {
+        &actions.front
+    };
+    side.iter()
+        .map(|a| (a.offset.clone(), a.kind.clone()))
+        .collect()
+}
+
+/// Effects of one side of tile type, like `tile_movement_grid`.
+pub fn tile_effect_grid(kind: TileType, flipped: bool, color: TileColor) -> Vec<(Offset, Effect)> {
+    let effects = Tile::new(kind, color).effects();
+    let side = if flipped {
+        &effects.back
+    } else {
+        &effects.front
+    };
+    side.iter()
+        .map(|e| (e.offset.clone(), e.kind.clone()))
+        .collect()
+}
+
+/// Tile in graveyard, with who captured it, when and where.
+///
+/// ```
+/// use rusty_duke_logic::ai::alpha_beta::tile_value;
+/// use rusty_duke_logic::logic::builder::GameStateBuilder;
+/// use rusty_duke_logic::logic::notation::notation_to_action;
+/// use rusty_duke_logic::logic::{do_action, undo_action, Coordinate, TileColor, TileType};
+///
+/// let mut state = GameStateBuilder::new()
+///     .tile_at(Coordinate::new(0, 0), TileType::Duke, TileColor::Black, false)
+///     .tile_at(Coordinate::new(0, 5), TileType::Duke, TileColor::White, false)
+///     .tile_at(Coordinate::new(2, 1), TileType::Bowman, TileColor::Black, true)
+///     .tile_at(Coordinate::new(2, 3), TileType::Footman, TileColor::White, false)
+///     .tile_at(Coordinate::new(1, 1), TileType::Footman, TileColor::White, false)
+///     .tile_at(Coordinate::new(4, 3), TileType::General, TileColor::Black, true)
+///     .tile_at(Coordinate::new(3, 3), TileType::Footman, TileColor::Black, false)
+///     .tile_at(Coordinate::new(5, 2), TileType::Pikeman, TileColor::White, false)
+///     .build()
+///     .unwrap();
+///
+/// // Strike, capture by move, and capture by Command.
+/// for notation in ["Bowman c2xc4!", "Footman b2xc2", "General e4 d4xf3"] {
+///     let action = notation_to_action(&state, notation).unwrap();
+///     do_action(&mut state, &action).unwrap();
+/// }
+///
+/// let captures: Vec<_> = state
+///     .graveyard
+///     .iter()
+///     .map(|c| (c.tile.kind, c.by, c.at_ply, c.square.to_string()))
+///     .collect();
+/// assert_eq!(
+///     captures,
+///     vec![
+///         (TileType::Footman, TileColor::Black, 0, "c4".to_string()),
+///         (TileType::Bowman, TileColor::White, 1, "c2".to_string()),
+///         (TileType::Pikeman, TileColor::Black, 2, "f3".to_string()),
+///     ]
+/// );
+/// assert_eq!(state.ply_count, 3);
+/// assert_eq!(state.captures_by(TileColor::Black).len(), 2);
+/// assert_eq!(
+///     state.material_balance(),
+///     tile_value(TileType::Footman) + tile_value(TileType::Pikeman)
+///         - tile_value(TileType::Bowman)
+/// );
+///
+/// undo_action(&mut state).unwrap();
+/// assert_eq!(state.graveyard.len(), 2);
+/// assert_eq!(state.ply_count, 2);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CapturedTile {
+    pub tile: Tile,
+    /// Color of the capturing player.
+    pub by: TileColor,
+    /// `GameState::ply_count` before the capturing action, i.e. its index
+    /// among the actions of the game.
+    pub at_ply: u32,
+    /// Square the tile was captured on.
+    pub square: Coordinate,
+}
+
+impl CapturedTile {
+    /// Captured tile when only the tile is known, e.g. from text formats.
+    /// Captured by the opponent of tile, at ply 0 on a1.
+    pub fn from_tile(tile: Tile) -> CapturedTile {
+        CapturedTile {
+            tile,
+            by: tile.color.opponent(),
+            at_ply: 0,
+            square: Coordinate::new(0, 0),
+        }
+    }
+}
+
 /// Complete state of a duke game. Bag, board and graveyard are owner of tiles.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GameState {
-    /// Game board.
-    pub board: [[Square; WIDTH as usize]; HEIGHT as usize],
+    /// Game board, with its size.
+    board: Board,
     /// Tiles go here before they are deployed to board. One bag per player.
     pub bags: [Vec<Tile>; 2],
     /// When one draws a new tile it is placed here in limbo. One queue for each player.
     pub drawn_tiles: [Vec<Tile>; 2],
-    /// Dead tiles go here
-    pub graveyard: Vec<Tile>,
+    /// Dead tiles go here, in order of capture.
+    pub graveyard: Vec<CapturedTile>,
     /// Specifies color of current player.
     pub ply: TileColor,
     /// Stores winner if any.
     pub game_over: Option<Winner>,
+    /// Why the game ended, set together with game_over.
+    pub win_reason: Option<WinReason>,
+    /// Turns done by both players. Drawing and placing a tile is one turn.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub turn: u16,
+    /// Game is adjudicated after this many turns.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub turn_limit: Option<u16>,
+    /// See `GameRules::strict_draw`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub strict_draw: bool,
+    /// See `GameRules::repetition_limit`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub repetition_limit: Option<u8>,
+    /// See `GameRules::no_capture_limit`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub no_capture_limit: Option<u16>,
+    /// Turns done since the last capture or placed tile.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub quiet_turns: u16,
+    /// Actions done since the game started, or since the state was built.
+    /// Unlike `turn`, drawing and placing a tile count as two.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ply_count: u32,
+    /// Color that offered a draw, see `offer_draw`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub draw_offer: Option<TileColor>,
+    /// Changed by every change of the state through this module, see
+    /// `version`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    version: u64,
+    /// Zobrist hash of the position, updated by actions and undo. See
+    /// `hash`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    hash: u64,
     /// Put duke positions here to avoid extra search
     dukes: [Option<Coordinate>; 2],
+    /// Done actions, with what is needed to undo them.
+    history: Vec<HistoryEntry>,
+    /// Undone actions, the last undone last, with the bag index of drawn
+    /// tiles. Cleared by any other action.
+    #[cfg_attr(feature = "serde", serde(default))]
+    redo: Vec<(Action, Option<usize>)>,
+    /// Hashes of positions after each turn, only kept with a repetition
+    /// limit. Positions before the last `quiet_turns` can't repeat.
+    #[cfg_attr(feature = "serde", serde(default))]
+    positions: Vec<u64>,
+    /// Used to draw tiles from bag.
+    rng: GameRng,
 }
 
-impl GameState {
-    /// Initialize bags
-    fn init_tiles(color: TileColor) -> Vec<Tile> {
-        let mut tiles = Vec::new();
-
-        // Add footmen
-
-        tiles.push(Tile::new(TileType::Footman, color));
-
-        // Add pikemen
-        tiles.push(Tile::new(TileType::Pikeman, color));
-        tiles.push(Tile::new(TileType::Pikeman, color));
-        tiles.push(Tile::new(TileType::Pikeman, color));
-
-        // Add bowmen
-        tiles.push(Tile::new(TileType::Knight, color));
-
-        // Add knights
-        tiles.push(Tile::new(TileType::Bowman, color));
+/// Everything needed to undo an action.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct HistoryEntry {
+    action: Action,
+    ply: TileColor,
+    captured: Option<Tile>,
+    /// Bag index of drawn tile, for NewFromBag.
+    bag_index: Option<usize>,
+    dukes: [Option<Coordinate>; 2],
+    game_over: Option<Winner>,
+    win_reason: Option<WinReason>,
+    rng: GameRng,
+    #[cfg_attr(feature = "serde", serde(default))]
+    quiet_turns: u16,
+    #[cfg_attr(feature = "serde", serde(default))]
+    positions_len: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    draw_offer: Option<TileColor>,
+}
 
-        // Add light horses
-        tiles.push(Tile::new(TileType::LightHorse, color));
+impl HistoryEntry {
+    /// Entry with state before action.
+    fn new(state: &GameState, action: &Action) -> HistoryEntry {
+        HistoryEntry {
+            action: *action,
+            ply: state.ply,
+            captured: None,
+            bag_index: None,
+            dukes: state.dukes,
+            game_over: state.game_over.clone(),
+            win_reason: state.win_reason,
+            rng: state.rng,
+            quiet_turns: state.quiet_turns,
+            positions_len: state.positions.len(),
+            draw_offer: state.draw_offer,
+        }
+    }
+}
 
-        // Add wizards
-        tiles.push(Tile::new(TileType::Wizard, color));
+/// Reason why `undo_action` failed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UndoError {
+    /// No actions in history.
+    NothingToUndo,
+    /// No undone actions, or an action was done after the last undo.
+    NothingToRedo,
+}
 
-        // Add Seer
-        tiles.push(Tile::new(TileType::Seer, color));
+impl fmt::Display for UndoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UndoError::NothingToUndo => write!(f, "nothing to undo"),
+            UndoError::NothingToRedo => write!(f, "nothing to redo"),
+        }
+    }
+}
 
-        // Add Champion
-        tiles.push(Tile::new(TileType::Champion, color));
+impl std::error::Error for UndoError {}
 
-        // Add Arbalist
-        tiles.push(Tile::new(TileType::Arbalist, color));
+impl GameState {
+    /// Initialize bags
+    fn init_tiles(kinds: &[TileType], color: TileColor) -> Vec<Tile> {
+        kinds.iter().map(|kind| Tile::new(*kind, color)).collect()
+    }
 
-        // Add General
-        tiles.push(Tile::new(TileType::General, color));
+    /// New game, random generator seeded from entropy.
+    pub fn new() -> GameState {
+        GameState::new_with(&GameRules::standard(), GameRng::from_entropy())
+    }
 
-        // Add Marshall
-        tiles.push(Tile::new(TileType::Marshall, color));
+    /// New game with seeded random generator. Same seed and same actions give
+    /// the same game.
+    pub fn new_seeded(seed: u64) -> GameState {
+        GameState::new_with(&GameRules::standard(), GameRng::new(seed))
+    }
 
-        // Add Countess
-        tiles.push(Tile::new(TileType::Countess, color));
+    /// New standard game with expansion tiles, random generator seeded from
+    /// entropy.
+    pub fn with_setup(setup: &GameSetup) -> GameState {
+        GameState::new_with(&setup.rules(), GameRng::from_entropy())
+    }
 
-        // Add Ranger
-        tiles.push(Tile::new(TileType::Ranger, color));
+    /// New standard game with the tiles of an expansion added to the bags.
+    /// Random generator is seeded from entropy if seed is `None`.
+    pub fn new_with_expansion(expansion: Expansion, seed: Option<u64>) -> GameState {
+        let setup = GameSetup {
+            expansions: vec![expansion],
+            ..GameSetup::default()
+        };
+        GameState::new_with_rules(&setup.rules(), seed)
+    }
 
-        // Add Sage
-        tiles.push(Tile::new(TileType::Sage, color));
+    /// New standard game with bags of composition, e.g. of a variant with
+    /// more or other tiles. Random generator is seeded from entropy if seed is
+    /// `None`.
+    pub fn new_with_bag(composition: &BagComposition, seed: Option<u64>) -> GameState {
+        let rules = GameRules {
+            bag: composition.tiles(),
+            ..GameRules::standard()
+        };
+        GameState::new_with_rules(&rules, seed)
+    }
 
-        // Add RoyalAssassin
-        tiles.push(Tile::new(TileType::RoyalAssassin, color));
+    /// New game of a variant, e.g. a quick game. Random generator is seeded
+    /// from entropy if seed is `None`.
+    pub fn new_with_rules(rules: &GameRules, seed: Option<u64>) -> GameState {
+        let rng = match seed {
+            Some(seed) => GameRng::new(seed),
+            None => GameRng::from_entropy(),
+        };
+        GameState::new_with(rules, rng)
+    }
 
-        // Arthurian legends tiles
+    fn new_with(rules: &GameRules, rng: GameRng) -> GameState {
+        // These are the first three tiles that will be deployed. In the right
+        // order.
+        let new_black_tiles = GameState::init_tiles(&START_TILES, TileColor::Black);
+        let new_white_tiles = GameState::init_tiles(&START_TILES, TileColor::White);
 
-        /*    if arthurian_legends {
-            todo!();
+        let mut state = GameState {
+            board: Board::new(rules.size),
+            bags: [
+                GameState::init_tiles(&rules.bag, TileColor::Black),
+                GameState::init_tiles(&rules.bag, TileColor::White),
+            ],
+            drawn_tiles: [new_black_tiles, new_white_tiles],
+            graveyard: Vec::new(),
+            ply: TileColor::Black, // Black always start
+            game_over: None,
+            win_reason: None,
+            turn: 0,
+            turn_limit: rules.turn_limit,
+            strict_draw: rules.strict_draw,
+            repetition_limit: rules.repetition_limit,
+            no_capture_limit: rules.no_capture_limit,
+            quiet_turns: 0,
+            ply_count: 0,
+            draw_offer: None,
+            version: 0,
+            hash: 0,
+            dukes: [None; 2], // Duke board positions, to decrease amount of search.
+            history: Vec::new(),
+            redo: Vec::new(),
+            positions: Vec::new(),
+            rng,
+        };
+        state.rehash();
+        state
+    }
 
-            // Add Arthur
-            tiles.push(Tile::new(TileType::RoyalAssassin, color));
+    /// Counter that changes whenever the state is changed by an action, an
+    /// undo, the end of the game or a `mut_` method. Direct changes to
+    /// public fields don't change it. See `cache::CachedGameState`.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
 
-            // Add Guinevere
-            tiles.push(Tile::new(TileType::RoyalAssassin, color));
+    fn changed(&mut self) {
+        self.version = self.version.wrapping_add(1);
+    }
 
-            // Add Lancelot
-            tiles.push(Tile::new(TileType::RoyalAssassin, color));
+    /// True if there are actions to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
 
-            // Add Perceval
-            tiles.push(Tile::new(TileType::RoyalAssassin, color));
+    /// True if there are undone actions to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
 
-            // Add Merlin
-            tiles.push(Tile::new(TileType::RoyalAssassin, color));
+    /// Number of actions that can be undone. Less than `ply_count` after
+    /// `clear_history`.
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
 
-            // Add Camelot
-            tiles.push(Tile::new(TileType::RoyalAssassin, color));
+    /// Forget all actions, they can no longer be undone. Makes clones cheaper.
+    /// Positions that can still repeat are kept.
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+        let repeatable = self.positions.len().min(self.quiet_turns as usize + 1);
+        self.positions.drain(..self.positions.len() - repeatable);
+    }
 
-            // Add Morgana
-            tiles.push(Tile::new(TileType::RoyalAssassin, color));
+    /// Forget all but the last keep actions, older ones can no longer be
+    /// undone. Unlike `clear_history`, positions are kept.
+    pub fn trim_history(&mut self, keep: usize) {
+        let forgotten = self.history.len().saturating_sub(keep);
+        self.history.drain(..forgotten);
+    }
 
-            // Add Mordred
-            tiles.push(Tile::new(TileType::RoyalAssassin, color));
-        }*/
+    /// Copy of state without history, same as `clone` and `clear_history`
+    /// but the history is never copied. Undo and redo are not possible on the
+    /// copy.
+    pub fn clone_without_history(&self) -> GameState {
+        let repeatable = self.positions.len().min(self.quiet_turns as usize + 1);
+        GameState {
+            board: self.board,
+            bags: self.bags.clone(),
+            drawn_tiles: self.drawn_tiles.clone(),
+            graveyard: self.graveyard.clone(),
+            ply: self.ply,
+            game_over: self.game_over.clone(),
+            win_reason: self.win_reason,
+            turn: self.turn,
+            turn_limit: self.turn_limit,
+            strict_draw: self.strict_draw,
+            repetition_limit: self.repetition_limit,
+            no_capture_limit: self.no_capture_limit,
+            quiet_turns: self.quiet_turns,
+            ply_count: self.ply_count,
+            draw_offer: self.draw_offer,
+            version: self.version,
+            hash: self.hash,
+            dukes: self.dukes,
+            history: Vec::new(),
+            redo: Vec::new(),
+            positions: self.positions[self.positions.len() - repeatable..].to_vec(),
+            rng: self.rng,
+        }
+    }
 
-        tiles
+    /// How many times the current position has occurred with the same player
+    /// to go. Only counted with a repetition limit, 0 without.
+    pub fn repetitions(&self) -> usize {
+        let current = match self.positions.last() {
+            Some(hash) => *hash,
+            None => return 0,
+        };
+        self.positions
+            .iter()
+            .rev()
+            .take(self.quiet_turns as usize + 1)
+            .filter(|hash| **hash == current)
+            .count()
     }
 
-    pub fn new() -> GameState {
-        // These are the first three tiles that will be deployed. In the right
-        // order.
-        let mut new_black_tiles: Vec<Tile> = Vec::new();
-        new_black_tiles.push(Tile::new(TileType::Footman, TileColor::Black));
-        new_black_tiles.push(Tile::new(TileType::Footman, TileColor::Black));
-        new_black_tiles.push(Tile::new(TileType::Duke, TileColor::Black));
+    /// Tiles in graveyard, in order of capture.
+    pub fn graveyard_tiles(&self) -> Vec<Tile> {
+        self.graveyard.iter().map(|c| c.tile).collect()
+    }
 
-        let mut new_white_tiles: Vec<Tile> = Vec::new();
-        new_white_tiles.push(Tile::new(TileType::Footman, TileColor::White));
-        new_white_tiles.push(Tile::new(TileType::Footman, TileColor::White));
-        new_white_tiles.push(Tile::new(TileType::Duke, TileColor::White));
+    /// Captures by color, in order of capture.
+    pub fn captures_by(&self, color: TileColor) -> Vec<&CapturedTile> {
+        self.graveyard.iter().filter(|c| c.by == color).collect()
+    }
 
-        GameState {
-            /*board: [(); HEIGHT as usize]
-            .map(|_| [(); WIDTH as usize]
-                .map(|_| Square{effects: Vec::new(), tile: None})),*/
-            board: Default::default(),
-            bags: [
-                GameState::init_tiles(TileColor::Black),
-                GameState::init_tiles(TileColor::White),
-            ],
-            drawn_tiles: [new_black_tiles, new_white_tiles],
-            graveyard: Vec::new(),
-            ply: TileColor::Black, // Black always start
-            game_over: None,
-            dukes: [None; 2], // Duke board positions, to decrease amount of search.
-        }
+    /// Value of tiles captured by Black minus value of tiles captured by
+    /// White, in tile values of the AI. Positive when Black is ahead.
+    pub fn material_balance(&self) -> i32 {
+        self.graveyard
+            .iter()
+            .map(|c| match c.by {
+                TileColor::Black => tile_value(c.tile.kind),
+                TileColor::White => -tile_value(c.tile.kind),
+            })
+            .sum()
     }
 
     /// Borrow of bag for current ply
     pub fn bag(&self) -> &Vec<Tile> {
-        &self.bags[self.ply as usize]
+        self.bag_of(self.ply)
+    }
+
+    /// Bag of color.
+    pub fn bag_of(&self, color: TileColor) -> &Vec<Tile> {
+        &self.bags[color as usize]
     }
 
     /// Borrow of  drawn tile(s) for current ply
     pub fn drawn(&self) -> &Vec<Tile> {
-        &self.drawn_tiles[self.ply as usize]
+        self.drawn_of(self.ply)
+    }
+
+    /// Drawn tiles of color, the last is placed first.
+    pub fn drawn_of(&self, color: TileColor) -> &Vec<Tile> {
+        &self.drawn_tiles[color as usize]
+    }
+
+    /// Captured tiles of color, in order of capture. Same as `captures_by`
+    /// of the opponent.
+    pub fn graveyard_of(&self, color: TileColor) -> Vec<&CapturedTile> {
+        self.graveyard
+            .iter()
+            .filter(|c| c.tile.color == color)
+            .collect()
     }
 
     /// Borrow of players duke board position for current ply
@@ -1400,6 +1148,11 @@ impl GameState {
         &self.dukes[self.ply as usize]
     }
 
+    /// Duke board position of color.
+    pub fn duke_pos(&self, color: TileColor) -> Option<Coordinate> {
+        self.dukes[color as usize]
+    }
+
     /// Borrow of opponent duke board position for current ply
     pub fn opponent_duke_pos(&self) -> &Option<Coordinate> {
         if self.ply == TileColor::Black {
@@ -1409,28 +1162,74 @@ impl GameState {
         }
     }
 
+    /// Size of the board, standard unless the rules say otherwise. Squares
+    /// off the board are always empty.
+    pub fn size(&self) -> BoardSize {
+        self.board.size
+    }
+
     /// Borrow of square
     pub fn square(&self, cord: Coordinate) -> &Square {
-        &self.board[cord.y as usize][cord.x as usize]
+        &self.board.squares[square_index(cord)]
+    }
+
+    /// Tile on square, `None` if square is empty or off board.
+    pub fn tile_at(&self, cord: Coordinate) -> Option<&Tile> {
+        if !self.size().contains(cord) {
+            return None;
+        }
+        self.square(cord).tile.as_ref()
+    }
+
+    /// All squares, row by row from y = 0.
+    pub fn iter_squares(&self) -> impl Iterator<Item = (Coordinate, &Square)> {
+        self.size()
+            .squares()
+            .map(|cord| (cord, &self.board.squares[square_index(cord)]))
+    }
+
+    /// Tiles on board of color, or of both colors if `None`. Same order as
+    /// `iter_squares`.
+    pub fn iter_tiles(
+        &self,
+        color: Option<TileColor>,
+    ) -> impl Iterator<Item = (Coordinate, &Tile)> {
+        self.iter_squares()
+            .filter_map(move |(cord, square)| match &square.tile {
+                Some(tile) if color.is_none_or(|color| tile.color == color) => Some((cord, tile)),
+                _ => None,
+            })
+    }
+
+    /// Squares of tiles of kind and color on board.
+    pub fn find_tiles(&self, kind: TileType, color: TileColor) -> Vec<Coordinate> {
+        self.iter_tiles(Some(color))
+            .filter(|(_, tile)| tile.kind == kind)
+            .map(|(cord, _)| cord)
+            .collect()
     }
 
     /// Mut borrow of bag for current ply
     pub fn mut_bag(&mut self) -> &mut Vec<Tile> {
+        self.changed();
         &mut self.bags[self.ply as usize]
     }
 
     /// Mut borrow of  drawn tile(s) for current ply
     pub fn mut_drawn(&mut self) -> &mut Vec<Tile> {
+        self.changed();
         &mut self.drawn_tiles[self.ply as usize]
     }
 
     /// Mut borrow of players duke board position for current ply
     pub fn mut_own_duke_pos(&mut self) -> &mut Option<Coordinate> {
+        self.changed();
         &mut self.dukes[self.ply as usize]
     }
 
     /// Mut borrow of opponent duke board position for current ply
     pub fn mut_opponent_duke_pos(&mut self) -> &mut Option<Coordinate> {
+        self.changed();
         if self.ply == TileColor::Black {
             &mut self.dukes[TileColor::White as usize]
         } else {
@@ -1440,28 +1239,264 @@ impl GameState {
 
     /// Mut borrow of square
     pub fn mut_square(&mut self, cord: Coordinate) -> &mut Square {
-        &mut self.board[cord.y as usize][cord.x as usize]
+        self.changed();
+        &mut self.board.squares[square_index(cord)]
     }
-}
-
-/// Check if square effects prevent tile from doing anything at all.
-fn tile_can_act(state: &GameState, tile: (Coordinate, &Tile)) -> bool {
-    let square = &state.board[tile.0.y as usize][tile.0.x as usize];
 
-    for effect in &square.effects {
-        if *effect == Effect::Dread && tile.1.kind != TileType::Duke {
-            return false;
+    /// Put tile on square, or clear square with `None`, and update hash.
+    /// Returns the tile that was on the square.
+    fn set_tile(&mut self, cord: Coordinate, tile: Option<Tile>) -> Option<Tile> {
+        let keys = &*ZOBRIST;
+        let old = std::mem::replace(&mut self.mut_square(cord).tile, tile);
+        for tile in old.iter().chain(tile.iter()) {
+            self.hash ^= keys.square(square_index(cord), tile);
         }
+        old
     }
 
-    true
-}
+    /// Flip tile on square and update hash.
+    fn flip_tile(&mut self, cord: Coordinate) {
+        let mut tile = self.square(cord).tile.expect("Flip, but no tile.");
+        tile.flip();
+        self.set_tile(cord, Some(tile));
+    }
 
-/// Check if path between two coordinates is straight
+    /// Add tile last to drawn tiles of current ply and update hash.
+    fn push_drawn(&mut self, tile: Tile) {
+        let position = self.drawn().len();
+        self.hash ^= ZOBRIST.drawn(&tile, position);
+        self.mut_drawn().push(tile);
+    }
+
+    /// Remove last drawn tile of current ply and update hash, the reverse of
+    /// `push_drawn`.
+    fn pop_drawn(&mut self) -> Tile {
+        let tile = self.mut_drawn().pop().expect("No drawn tile.");
+        let position = self.drawn().len();
+        self.hash ^= ZOBRIST.drawn(&tile, position);
+        tile
+    }
+
+    /// Take tile at index from bag of current ply and update hash. The last
+    /// tile of the bag takes its place.
+    fn take_from_bag(&mut self, index: usize) -> Tile {
+        let tile = self.mut_bag().swap_remove(index);
+        let copy = self.bag().iter().filter(|t| **t == tile).count();
+        self.hash ^= ZOBRIST.bag(&tile, copy);
+        tile
+    }
+
+    /// Put tile back at index in bag of current ply and update hash, the
+    /// reverse of `take_from_bag`.
+    fn put_in_bag(&mut self, tile: Tile, index: usize) {
+        let copy = self.bag().iter().filter(|t| **t == tile).count();
+        self.hash ^= ZOBRIST.bag(&tile, copy);
+        let bag = self.mut_bag();
+        bag.push(tile);
+        let last = bag.len() - 1;
+        bag.swap(index, last);
+    }
+
+    /// Set player to go and update hash.
+    fn set_ply(&mut self, ply: TileColor) {
+        if ply != self.ply {
+            self.hash ^= ZOBRIST.white_ply;
+        }
+        self.ply = ply;
+    }
+
+    /// True if state is the exact left-right mirror of other state. Only true
+    /// if all tiles on board act the same when mirrored, so mirrored states
+    /// are equivalent.
+    pub fn is_mirror_of(&self, other: &GameState) -> bool {
+        if self.ply != other.ply
+            || self.rng != other.rng
+            || self.game_over != other.game_over
+            || self.bags != other.bags
+            || self.drawn_tiles != other.drawn_tiles
+            || self.graveyard_tiles() != other.graveyard_tiles()
+        {
+            return false;
+        }
+
+        if self.size() != other.size() {
+            return false;
+        }
+        for cord in self.size().squares() {
+            let square = self.square(cord);
+            let mirrored = other.square(self.size().mirror(cord));
+
+            if square.tile != mirrored.tile {
+                return false;
+            }
+            if let Some(tile) = square.tile {
+                if !mirror_symmetric(tile.kind) {
+                    return false;
+                }
+            }
+            if square.effects != mirrored.effects {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// True if tile type has the same actions and effects when mirrored left-right.
+fn mirror_symmetric(kind: TileType) -> bool {
+    let actions = |actions: &Vec<AvailableAction>| {
+        actions.iter().all(|a| {
+            actions
+                .iter()
+                .any(|b| a.kind == b.kind && a.offset.x == -b.offset.x && a.offset.y == b.offset.y)
+        })
+    };
+    let effects = |effects: &Vec<AvailableEffect>| {
+        effects.iter().all(|a| {
+            effects
+                .iter()
+                .any(|b| a.kind == b.kind && a.offset.x == -b.offset.x && a.offset.y == b.offset.y)
+        })
+    };
+
+    let actions_symmetric = match TILE_ACTIONS.get(&kind) {
+        Some(a) => actions(&a.front) && actions(&a.back),
+        None => true,
+    };
+    let effects_symmetric = match TILE_EFFECTS.get(&kind) {
+        Some(e) => effects(&e.front) && effects(&e.back),
+        None => true,
+    };
+
+    actions_symmetric && effects_symmetric
+}
+
+/// Random keys for `GameState::hash`.
+struct ZobristKeys {
+    /// Tile on square, indexed by `square_key`.
+    squares: Vec<u64>,
+    /// Drawn tiles, indexed by `tile_key`.
+    drawn: Vec<u64>,
+    /// Tiles in bag, indexed by `tile_key`.
+    bag: Vec<u64>,
+    /// XOR:ed in when it is White's turn.
+    white_ply: u64,
+    /// Number of keys per square, one for each tile kind, color and side.
+    tile_keys: usize,
+}
+
+fn tile_key(tile: &Tile) -> usize {
+    (tile.kind.to_id() as usize * 2 + tile.color as usize) * 2 + tile.flipped as usize
+}
+
+impl ZobristKeys {
+    /// Key of tile on square with index.
+    fn square(&self, square: usize, tile: &Tile) -> u64 {
+        self.squares[square * self.tile_keys + tile_key(tile)]
+    }
+
+    /// Key of tile at position in a drawn queue. Order of drawn tiles
+    /// matters, so the key is rotated by position.
+    fn drawn(&self, tile: &Tile, position: usize) -> u64 {
+        self.drawn[tile_key(tile)].rotate_left(position as u32 * 8)
+    }
+
+    /// Key of the nth copy of tile in a bag. Order in bag does not matter,
+    /// but bags may hold several equal tiles, so the key is rotated by the
+    /// number of equal tiles before it.
+    fn bag(&self, tile: &Tile, copy: usize) -> u64 {
+        self.bag[tile_key(tile)].rotate_left(copy as u32 * 7)
+    }
+}
+
+lazy_static! {
+    static ref ZOBRIST: ZobristKeys = {
+        // Fixed seed, so hashes are the same between runs.
+        let mut rng = GameRng::new(0x0D0C_E5EE_D000_0001);
+        let mut keys = |n: usize| (0..n).map(|_| rng.gen()).collect::<Vec<u64>>();
+        let ids = TileType::iter().map(|kind| kind.to_id() as usize).max().unwrap() + 1;
+        let tile_keys = ids * 2 * 2;
+        ZobristKeys {
+            squares: keys(SQUARES * tile_keys),
+            drawn: keys(tile_keys),
+            bag: keys(tile_keys),
+            white_ply: keys(1)[0],
+            tile_keys,
+        }
+    };
+}
+
+impl GameState {
+    /// Zobrist hash of position: tiles on board, drawn tiles, bags and ply.
+    /// Equal positions have equal hashes, history and graveyard are ignored.
+    ///
+    /// The hash is kept up to date by actions and undo. After direct changes
+    /// to `bags`, `drawn_tiles`, `ply` or tiles of `mut_square`, call
+    /// `rehash`.
+    pub fn hash(&self) -> u64 {
+        debug_assert_eq!(
+            self.hash,
+            self.compute_hash(),
+            "Hash out of sync with position."
+        );
+        self.hash
+    }
+
+    /// Recompute hash after direct changes to the position, see `hash`.
+    pub fn rehash(&mut self) {
+        self.hash = self.compute_hash();
+    }
+
+    /// Hash of position computed from scratch. Same as `hash`, unless the
+    /// position was changed without `rehash`.
+    pub fn compute_hash(&self) -> u64 {
+        let keys = &*ZOBRIST;
+        let mut hash = 0;
+
+        for (square, tile) in self.board.squares.iter().enumerate() {
+            if let Some(tile) = &tile.tile {
+                hash ^= keys.square(square, tile);
+            }
+        }
+
+        for drawn in self.drawn_tiles.iter() {
+            for (i, tile) in drawn.iter().enumerate() {
+                hash ^= keys.drawn(tile, i);
+            }
+        }
+
+        for bag in self.bags.iter() {
+            let mut copies = vec![0; keys.tile_keys];
+            for tile in bag.iter() {
+                hash ^= keys.bag(tile, copies[tile_key(tile)]);
+                copies[tile_key(tile)] += 1;
+            }
+        }
+
+        if self.ply == TileColor::White {
+            hash ^= keys.white_ply;
+        }
+
+        hash
+    }
+}
+
+/// Check if square effects prevent tile from acting. Dread of the opponent
+/// keeps tiles other than the Duke from acting, Dread of own tiles doesn't.
+/// Tiles on Dread squares can still be commanded, see
+/// `for_each_command_action`.
+fn tile_can_act(state: &GameState, tile: (Coordinate, &Tile)) -> bool {
+    let square = state.square(tile.0);
+    !square
+        .effects
+        .contains_of(&Effect::Dread, tile.1.color.opponent())
+        || tile.1.kind == TileType::Duke
+}
+
+/// Check if path between two coordinates is straight
 fn straight_path(start: &Coordinate, end: &Coordinate) -> bool {
     debug_assert!(start != end);
-    debug_assert!(Coordinate::legal(start.x, start.y));
-    debug_assert!(Coordinate::legal(end.x, end.y));
 
     // Vertical or horizontal
     if start.x == end.x || start.y == end.y {
@@ -1482,138 +1517,76 @@ fn straight_path(start: &Coordinate, end: &Coordinate) -> bool {
     false
 }
 
-/// Check if path between two coordinates is blocked. Check from square
-/// adjacent to start to and including end square.
-fn path_blocked(
+/// True if square has Defence. Defence blocks every action onto the square,
+/// also Command.
+fn defended(state: &GameState, cord: Coordinate) -> bool {
+    state.square(cord).effects.contains(&Effect::Defence)
+}
+
+/// Check if move from start to end is blocked. Moves only go along straight
+/// lines (horizontal, vertical or diagonal). Every square in between must be
+/// empty and without Defence. The end square is blocked by Defence, and by a
+/// tile of `tile_color` if given.
+///
+/// Moves along other offsets, e.g. L-shaped, have no path and are always
+/// blocked. Such offsets have to be jumps, see `validate`.
+fn move_path_blocked(
     state: &GameState,
-    tile_color: TileColor,
-    action_type: ActionType,
+    tile_color: Option<TileColor>,
     start: Coordinate,
     end: Coordinate,
 ) -> bool {
-    debug_assert!(Coordinate::legal(start.x, start.y));
-    debug_assert!(Coordinate::legal(end.x, end.y));
+    debug_assert!(state.size().contains(start));
+    debug_assert!(state.size().contains(end));
 
-    let board = &state.board;
-
-    // FIXME: Create lookup table? That might be more efficient and more readable.
+    if !straight_path(&start, &end) {
+        return true;
+    }
 
     let dir = get_direction(start, end);
+    let mut cord = start;
+    loop {
+        cord = match cord.offset(&dir, state.size()) {
+            Some(next) => next,
+            None => return true,
+        };
 
-    // Straight path?
-    if straight_path(&start, &end) {
-        let mut cord = Coordinate::new(start.x, start.y);
-        loop {
-            cord.x = (cord.x as i8 + dir.x) as u8;
-            cord.y = (cord.y as i8 + dir.y) as u8;
-
-            let square = state.square(cord);
-
-            // Check if path is blocked by defence
-            for effect in &square.effects {
-                if *effect == Effect::Defence {
-                    return true;
-                }
-            }
-
-            if cord != end {
-                // Move is blocked by any tile in way.
-                if action_type == ActionType::Move && square.tile.is_some() {
-                    return true;
-                }
-            } else {
-                // Any action is blocked by tile of same color on final square.
-                if square.tile.is_some() {
-                    let t = square.tile.as_ref().unwrap();
-                    if tile_color == t.color {
-                        return true;
-                    }
-                }
-                break;
-            }
+        if cord == end {
+            return target_blocked_for_jump(state, tile_color, end);
         }
-    } else {
-        // Non-straight path
-
-        // Closure to remove some redundant code. Hope it's not more confusing.
-        let non_straight_blocked = |x_first: bool| -> bool {
-            let mut cord = start.clone();
-
-            // First axis
-            loop {
-                if x_first {
-                    cord.x = (cord.x as i8 + dir.x) as u8;
-                } else {
-                    cord.y = (cord.y as i8 + dir.y) as u8;
-                }
-
-                let square = state.square(cord);
-
-                // Check if path is blocked by defence
-                for effect in &square.effects {
-                    if *effect == Effect::Defence {
-                        return true;
-                    }
-                }
-
-                // Move is blocked by any tile in way.
-                if action_type == ActionType::Move && square.tile.is_some() {
-                    return true;
-                }
-
-                if x_first {
-                    if cord.x == end.x {
-                        break;
-                    }
-                } else {
-                    if cord.y == end.y {
-                        break;
-                    }
-                }
-            }
-
-            // Second axis
-            loop {
-                if x_first {
-                    cord.y = (cord.y as i8 + dir.y) as u8;
-                } else {
-                    cord.x = (cord.x as i8 + dir.x) as u8;
-                }
 
-                let square = &board[cord.y as usize][cord.x as usize];
-
-                // Check if path is blocked by defence
-                for effect in &square.effects {
-                    if *effect == Effect::Defence {
-                        return true;
-                    }
-                }
+        if defended(state, cord) || state.square(cord).tile.is_some() {
+            return true;
+        }
+    }
+}
 
-                if x_first && (cord.y != end.y) || (!x_first) && (cord.x != end.x) {
-                    // Move is blocked by any tile in way.
-                    if action_type == ActionType::Move && square.tile.is_some() {
-                        return true;
-                    }
-                } else {
-                    // Any action is blocked by tile of same color on final square.
-                    if square.tile.is_some() {
-                        let t = square.tile.as_ref().unwrap();
-                        if tile_color == t.color {
-                            return true;
-                        }
-                    }
+/// Check if jump onto target is blocked. Jumps ignore everything in between,
+/// only the target matters: it is blocked by Defence, and by a tile of
+/// `tile_color` if given.
+fn target_blocked_for_jump(
+    state: &GameState,
+    tile_color: Option<TileColor>,
+    target: Coordinate,
+) -> bool {
+    debug_assert!(state.size().contains(target));
 
-                    return false;
-                }
-            }
-        };
+    if defended(state, target) {
+        return true;
+    }
 
-        if non_straight_blocked(true) && non_straight_blocked(false) {
-            return true;
-        }
+    match (tile_color, state.square(target).tile) {
+        (Some(color), Some(tile)) => tile.color == color,
+        _ => false,
     }
+}
 
-    false
+/// Check if strike on target is blocked. Strikes ignore everything in between,
+/// as jumps, and are only blocked by Defence on the target.
+fn target_blocked_for_strike(state: &GameState, target: Coordinate) -> bool {
+    debug_assert!(state.size().contains(target));
+
+    defended(state, target)
 }
 
 /// Get legal move action if any. Only valid coordinates.
@@ -1622,13 +1595,13 @@ fn get_move_action(
     tile: (Coordinate, &Tile),
     target: Coordinate,
 ) -> Option<Action> {
-    debug_assert!(Coordinate::legal(target.x, target.y));
+    debug_assert!(state.size().contains(target));
 
-    if path_blocked(state, tile.1.color, ActionType::Move, tile.0, target) {
+    if move_path_blocked(state, Some(tile.1.color), tile.0, target) {
         return None;
     }
 
-    let square = &state.board[target.y as usize][target.x as usize];
+    let square = state.square(target);
 
     if square.tile.is_some() {
         let blocking_tile = square.tile.as_ref().unwrap();
@@ -1650,37 +1623,28 @@ fn get_move_action(
     }));
 }
 
-/// Get slide or jumpslide action(s). Each square in path generate one action.
-/// Only valid coordinates.
-fn get_slide_actions(
+/// Slide or jumpslide action(s), each passed to f. Each square in path
+/// generate one action. Only valid coordinates.
+fn for_each_slide_action(
     state: &GameState,
     tile: (Coordinate, &Tile),
     jumpslide: bool,
     start: Coordinate,
-) -> Vec<Action> {
-    debug_assert!(Coordinate::legal(start.x, start.y));
+    f: &mut impl FnMut(Action),
+) {
+    debug_assert!(state.size().contains(start));
 
-    let board = &state.board;
-    let mut x = start.x;
-    let mut y = start.y;
     let dir = get_direction(tile.0, start);
-    let mut actions: Vec<Action> = Vec::new();
-
-    // Check if jump is blocked.
-    if jumpslide {
-        if path_blocked(state, tile.1.color, ActionType::Jump, tile.0, start) {
-            return actions;
-        }
-    }
+    let mut cord = Some(start);
 
-    while x < WIDTH && y < HEIGHT {
-        let square = &board[y as usize][x as usize];
+    // Jump slide jumps to start, ignoring everything in between. Start is
+    // then checked as any other square of the slide.
+    while let Some(target) = cord {
+        let square = state.square(target);
 
         // Check if path is blocked by defence
-        for effect in &square.effects {
-            if *effect == Effect::Defence {
-                return actions;
-            }
+        if square.effects.contains(&Effect::Defence) {
+            return;
         }
 
         // If tile in path, stop or capture.
@@ -1688,41 +1652,38 @@ fn get_slide_actions(
             let blocking_tile = square.tile.as_ref().unwrap();
             if tile.1.color != blocking_tile.color {
                 if jumpslide {
-                    actions.push(Action::JumpSlide(ActionData {
+                    f(Action::JumpSlide(ActionData {
                         tile_pos: tile.0,
-                        target_pos: Coordinate { x: x, y: y },
+                        target_pos: target,
                         result: ActionResult::Capture,
                     }));
                 } else {
-                    actions.push(Action::Slide(ActionData {
+                    f(Action::Slide(ActionData {
                         tile_pos: tile.0,
-                        target_pos: Coordinate { x: x, y: y },
+                        target_pos: target,
                         result: ActionResult::Capture,
                     }));
                 }
             }
-            return actions;
+            return;
         }
 
         if jumpslide {
-            actions.push(Action::JumpSlide(ActionData {
+            f(Action::JumpSlide(ActionData {
                 tile_pos: tile.0,
-                target_pos: Coordinate { x: x, y: y },
+                target_pos: target,
                 result: ActionResult::Move,
             }));
         } else {
-            actions.push(Action::Slide(ActionData {
+            f(Action::Slide(ActionData {
                 tile_pos: tile.0,
-                target_pos: Coordinate { x: x, y: y },
+                target_pos: target,
                 result: ActionResult::Move,
             }));
         }
 
-        x = (x as i8 + dir.x) as u8;
-        y = (y as i8 + dir.y) as u8;
+        cord = target.offset(&dir, state.size());
     }
-
-    actions
 }
 
 /// Get legal jump action, if any. Only valid coordinates.
@@ -1731,13 +1692,13 @@ fn get_jump_action(
     tile: (Coordinate, &Tile),
     target: Coordinate,
 ) -> Option<Action> {
-    debug_assert!(Coordinate::legal(target.x, target.y));
+    debug_assert!(state.size().contains(target));
 
-    if path_blocked(state, tile.1.color, ActionType::Jump, tile.0, target) {
+    if target_blocked_for_jump(state, Some(tile.1.color), target) {
         return None;
     }
 
-    let square = &state.board[target.y as usize][target.x as usize];
+    let square = state.square(target);
 
     if square.tile.is_some() {
         let blocking_tile = square.tile.as_ref().unwrap();
@@ -1759,19 +1720,19 @@ fn get_jump_action(
     }));
 }
 
-/// Get legal jump action, if any. Only valid coordinates.
+/// Get legal strike action, if any. Only valid coordinates.
 fn get_strike_action(
     state: &GameState,
     tile: (Coordinate, &Tile),
     target: Coordinate,
 ) -> Option<Action> {
-    debug_assert!(Coordinate::legal(target.x, target.y));
+    debug_assert!(state.size().contains(target));
 
-    if path_blocked(state, tile.1.color, ActionType::Jump, tile.0, target) {
+    if target_blocked_for_strike(state, target) {
         return None;
     }
 
-    let square = &state.board[target.y as usize][target.x as usize];
+    let square = state.square(target);
 
     if square.tile.is_some() {
         let blocking_tile = square.tile.as_ref().unwrap();
@@ -1789,50 +1750,47 @@ fn get_strike_action(
     None
 }
 
-/// Get command actions. Only valid coordinates.
-fn get_command_actions(
+/// Command actions, each passed to f. Only valid coordinates.
+fn for_each_command_action(
     state: &GameState,
     tile: (Coordinate, &Tile),
     target: Coordinate,
-) -> Vec<Action> {
-    debug_assert!(Coordinate::legal(target.x, target.y));
+    f: &mut impl FnMut(Action),
+) {
+    debug_assert!(state.size().contains(target));
 
-    let mut actions: Vec<Action> = Vec::new();
     let command_square = state.square(target);
 
     if command_square.tile.is_none() {
-        return actions;
+        return;
     }
 
     if command_square.tile.as_ref().unwrap().color != tile.1.color {
-        return actions;
+        return;
     }
 
-    // Command actions can't be blocked.
+    // Command actions can't be blocked on the way, only by Defence on the
+    // target.
 
-    // Get all command squares
-    let mut command_squares: Vec<Coordinate> = Vec::new();
-    let mut push_cord = |a: &AvailableAction| {
-        if a.kind == ActionType::Command {
-            let x = (tile.0.x as i8 + a.offset.x) as u8;
-            let y = (tile.0.y as i8 + a.offset.y) as u8;
-            if Coordinate::legal(x, y) {
-                command_squares.push(Coordinate::new(x, y));
-            }
-        }
+    // All command squares
+    let avail_actions = if tile.1.flipped {
+        &tile.1.actions().back
+    } else {
+        &tile.1.actions().front
     };
+    let command_squares = avail_actions
+        .iter()
+        .filter(|a| a.kind == ActionType::Command)
+        .filter_map(|a| tile.0.offset(&a.offset, state.size()));
 
-    if tile.1.flipped {
-        for a in tile.1.actions().back.iter() {
-            push_cord(&a);
-        }
-    } else {
-        for a in tile.1.actions().front.iter() {
-            push_cord(&a);
+    for cord in command_squares {
+        // Commanded tile has to move, and can't go onto its commander. Own
+        // tiles are not captured either, but offsets of custom tiles may
+        // point anywhere.
+        if cord == target || cord == tile.0 || defended(state, cord) {
+            continue;
         }
-    }
 
-    for cord in command_squares {
         let square = state.square(cord);
         if square.tile.is_some() {
             let t = square.tile.as_ref().unwrap();
@@ -1840,7 +1798,7 @@ fn get_command_actions(
             // Will not move to own square or to one occupied by same color.
             // This avoids multiple checks for target cord in push_cord.
             if t.color != tile.1.color {
-                actions.push(Action::Command(CommandActionData {
+                f(Action::Command(CommandActionData {
                     tile_pos: tile.0,
                     command_tile_pos: target,
                     target_pos: cord,
@@ -1848,7 +1806,7 @@ fn get_command_actions(
                 }));
             }
         } else {
-            actions.push(Action::Command(CommandActionData {
+            f(Action::Command(CommandActionData {
                 tile_pos: tile.0,
                 command_tile_pos: target,
                 target_pos: cord,
@@ -1856,84 +1814,107 @@ fn get_command_actions(
             }));
         }
     }
+}
 
-    actions
+/// Squares where the Duke can be deployed at game start, on a board of given
+/// size. Width has to be even.
+///
+/// Rules: "Place your Duke on one of the two center squares of the row closest
+/// to you." Black's home row is y = 0 and White's is y = height - 1.
+pub fn initial_duke_squares(color: TileColor, width: u8, height: u8) -> [Coordinate; 2] {
+    assert!(
+        width >= 2 && width.is_multiple_of(2) && height >= 1,
+        "Board width has to be even."
+    );
+
+    let y = if color == TileColor::Black {
+        0
+    } else {
+        height - 1
+    };
+
+    [
+        Coordinate {
+            x: width / 2 - 1,
+            y,
+        },
+        Coordinate { x: width / 2, y },
+    ]
 }
 
+/// Squares where drawn tile can be placed. Empty if the player has no Duke
+/// on board and none to place.
 pub fn get_spawn_squares(state: &GameState) -> Vec<Coordinate> {
     let mut squares: Vec<Coordinate> = Vec::new();
+    for_each_spawn_square(state, |cord| squares.push(cord));
+    squares
+}
 
+/// Call f with each square of `get_spawn_squares`, in the same order.
+fn for_each_spawn_square(state: &GameState, mut f: impl FnMut(Coordinate)) {
     if state.game_over.is_some() {
-        return squares;
+        return;
     }
 
-    // If there is no duke, return initial spawn squares. Assume init.
-    if state.own_duke_pos().is_none() {
-        if !state.drawn().is_empty() && state.drawn().last().unwrap().kind == TileType::Duke {
-            if state.ply == TileColor::Black {
-                return vec![Coordinate { x: 2, y: 0 }, Coordinate { x: 3, y: 0 }];
+    // If there is no duke, use initial spawn squares. Assume init. Otherwise
+    // the Duke was captured, and there is nowhere to spawn, e.g. when asked
+    // before `game_over` is set.
+    let duke_pos = match *state.own_duke_pos() {
+        Some(duke_pos) => duke_pos,
+        None => {
+            if state.drawn().last().map(|tile| tile.kind) == Some(TileType::Duke) {
+                initial_duke_squares(state.ply, state.size().width, state.size().height)
+                    .into_iter()
+                    .for_each(f);
             } else {
-                return vec![
-                    Coordinate {
-                        x: 2,
-                        y: HEIGHT - 1,
-                    },
-                    Coordinate {
-                        x: 3,
-                        y: HEIGHT - 1,
-                    },
-                ];
+                debug_assert!(
+                    state.drawn().is_empty(),
+                    "Drawn tile other than the Duke, but no Duke."
+                );
             }
-        } else {
-            panic!("Should be game over.");
+            return;
         }
-    }
-
-    let duke_pos = state.own_duke_pos().unwrap();
+    };
     let mut check_n_add = |x: i8, y: i8| {
-        if Coordinate::legal(x as u8, y as u8) {
-            let cord = Coordinate::new(x as u8, y as u8);
+        if let Some(cord) = duke_pos.offset(&Offset { x, y }, state.size()) {
             if state.square(cord).tile.is_none() {
-                squares.push(cord);
+                f(cord);
             }
         }
     };
 
     // Check tile on right
-    check_n_add(duke_pos.x as i8 + 1, duke_pos.y as i8);
+    check_n_add(1, 0);
     // Check tile on left
-    check_n_add(duke_pos.x as i8 - 1, duke_pos.y as i8);
+    check_n_add(-1, 0);
     // Check tile up
-    check_n_add(duke_pos.x as i8, duke_pos.y as i8 + 1);
+    check_n_add(0, 1);
     // Check tile down
-    check_n_add(duke_pos.x as i8, duke_pos.y as i8 - 1);
-
-    squares
+    check_n_add(0, -1);
 }
 
 /// Get tile actions. Tile has to be in play. Also shows actions for who can not
 /// play this ply.
 pub fn get_tile_actions(state: &GameState, tile_pos: Coordinate) -> Vec<Action> {
     let mut actions = Vec::new();
+    for_each_tile_action(state, tile_pos, &mut |action| actions.push(action));
+    actions
+}
 
+/// Actions of tile on tile_pos, each passed to f, see `get_tile_actions`.
+fn for_each_tile_action(state: &GameState, tile_pos: Coordinate, f: &mut impl FnMut(Action)) {
     if state.game_over.is_some() {
-        return actions;
+        return;
     }
 
-    if state.board[tile_pos.y as usize][tile_pos.x as usize]
-        .tile
-        .is_none()
-    {
-        return actions;
-    }
-    let tile = state.board[tile_pos.y as usize][tile_pos.x as usize]
-        .tile
-        .as_ref()
-        .unwrap();
+    let tile = match state.square(tile_pos).tile.as_ref() {
+        Some(tile) => tile,
+        None => return,
+    };
 
     // Check if tile is inhibited by some effect.
     if !tile_can_act(state, (tile_pos, tile)) {
-        return actions;
+        return;
     }
 
     // Let's get available actions for this tile.
@@ -1950,52 +1931,35 @@ pub fn get_tile_actions(state: &GameState, tile_pos: Coordinate) -> Vec<Action>
     // like slide, can result in many actual actions. And some available actions,
     // does not produce any actual actions.
     for action in avail_actions {
-        let x = (tile_pos.x as i8 + action.offset.x) as u8;
-        let y = (tile_pos.y as i8 + action.offset.y) as u8;
-
         // Skip if cordinate is illegal.
-        if !Coordinate::legal(x, y) {
-            continue;
-        }
-
-        let target = Coordinate::new(x, y);
+        let target = match tile_pos.offset(&action.offset, state.size()) {
+            Some(target) => target,
+            None => continue,
+        };
 
         match action.kind {
             ActionType::Move => {
-                let action = get_move_action(state, (tile_pos, tile), target);
-                if action.is_some() {
-                    actions.push(action.unwrap());
+                if let Some(action) = get_move_action(state, (tile_pos, tile), target) {
+                    f(action);
                 }
             }
             ActionType::Jump => {
-                let action = get_jump_action(state, (tile_pos, tile), target);
-                if action.is_some() {
-                    actions.push(action.unwrap());
+                if let Some(action) = get_jump_action(state, (tile_pos, tile), target) {
+                    f(action);
                 }
             }
             ActionType::JumpSlide => {
-                actions.append(&mut get_slide_actions(
-                    state,
-                    (tile_pos, tile),
-                    true,
-                    target,
-                ));
+                for_each_slide_action(state, (tile_pos, tile), true, target, f);
             }
             ActionType::Slide => {
-                actions.append(&mut get_slide_actions(
-                    state,
-                    (tile_pos, tile),
-                    false,
-                    target,
-                ));
+                for_each_slide_action(state, (tile_pos, tile), false, target, f);
             }
             ActionType::Command => {
-                actions.append(&mut get_command_actions(state, (tile_pos, tile), target));
+                for_each_command_action(state, (tile_pos, tile), target, f);
             }
             ActionType::Strike => {
-                let action = get_strike_action(state, (tile_pos, tile), target);
-                if action.is_some() {
-                    actions.push(action.unwrap());
+                if let Some(action) = get_strike_action(state, (tile_pos, tile), target) {
+                    f(action);
                 }
             }
             _ => {
@@ -2003,142 +1967,1158 @@ pub fn get_tile_actions(state: &GameState, tile_pos: Coordinate) -> Vec<Action>
             }
         }
     }
+}
 
-    actions
+/// Squares the tile on tile_pos can move to, strike or command a tile to,
+/// each once and in the order of `get_tile_actions`. For previews of what a
+/// tile can do, like `get_tile_actions` it ignores whose turn it is.
+pub fn get_tile_targets(state: &GameState, tile_pos: Coordinate) -> Vec<Coordinate> {
+    let mut targets = Vec::new();
+    for action in get_tile_actions(state, tile_pos) {
+        let target = match action {
+            Action::Move(ad)
+            | Action::Jump(ad)
+            | Action::JumpSlide(ad)
+            | Action::Slide(ad)
+            | Action::Strike(ad) => ad.target_pos,
+            Action::Command(cd) => cd.target_pos,
+            Action::NewFromBag | Action::PlaceNew(_) => continue,
+        };
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+    targets
+}
+
+/// True if current player may draw a tile, which can be placed on a spawn
+/// square. Placing a tile never puts the own Duke in check, but it may get it
+/// out of check by blocking a path or by dreading the attacker. So when in
+/// check, drawing is only allowed if some tile in the bag has a placement that
+/// gets the Duke out of check, or every tile with `strict_draw`.
+fn draw_allowed(state: &GameState) -> bool {
+    if !is_in_check(state, state.ply) {
+        return true;
+    }
+
+    let spawn_squares = get_spawn_squares(state);
+    let root = state.clone_without_history();
+
+    let escapes = |kind: TileType| {
+        spawn_squares.iter().any(|cord| {
+            let mut placed = root.clone();
+            placed.set_tile(*cord, Some(Tile::new(kind, state.ply)));
+            add_tile_effects(&mut placed, *cord);
+            !is_in_check(&placed, state.ply)
+        })
+    };
+
+    let mut kinds: Vec<TileType> = state.bag().iter().map(|tile| tile.kind).collect();
+    kinds.sort_by_key(|kind| kind.to_id());
+    kinds.dedup();
+
+    if state.strict_draw {
+        kinds.into_iter().all(escapes)
+    } else {
+        kinds.into_iter().any(escapes)
+    }
 }
 
-/// Get possible actions for a given game state.
+/// Get possible actions for a given game state. Drawing is only offered if a
+/// drawn tile can be placed without leaving the Duke in check, see
+/// `draw_allowed`. Other actions may still leave it in check, see
+/// `get_legal_actions`.
 pub fn get_actions(state: &GameState) -> Vec<Action> {
     let mut actions: Vec<Action> = Vec::new();
+    get_actions_into(state, &mut actions);
+    actions
+}
+
+/// Same as `get_actions`, but into actions, which is cleared first. Reusing
+/// the same vector doesn't allocate once it has grown large enough, except
+/// to check whether drawing is allowed when in check.
+///
+/// ```
+/// use rusty_duke_logic::logic::{get_actions, get_actions_into, GameState};
+///
+/// let state = GameState::new_seeded(1);
+/// let mut actions = Vec::with_capacity(64);
+/// get_actions_into(&state, &mut actions);
+/// assert_eq!(actions, get_actions(&state));
+/// ```
+pub fn get_actions_into(state: &GameState, actions: &mut Vec<Action>) {
+    actions.clear();
 
     if state.game_over.is_some() {
-        return actions;
+        return;
     }
 
-    // Get squares where tiles can be spawned.
-    let spawn_squares = get_spawn_squares(state);
-
     // Place drawn tile if any
     if !state.drawn().is_empty() {
-        // If there is a drawn tile and no spawn squares, we have a bug.
-        debug_assert!(
-            !spawn_squares.is_empty(),
-            "Drawn tile but no spawn squares."
-        );
-
-        for square in spawn_squares {
-            actions.push(Action::PlaceNew(square));
-        }
+        for_each_spawn_square(state, |square| actions.push(Action::PlaceNew(square)));
 
-        return actions;
+        // If there is a drawn tile and no spawn squares, we have a bug.
+        debug_assert!(!actions.is_empty(), "Drawn tile but no spawn squares.");
+        return;
     }
 
     // Add any potential spawn actions first.
-    if !spawn_squares.is_empty() && !state.bag().is_empty() {
+    let mut can_spawn = false;
+    for_each_spawn_square(state, |_| can_spawn = true);
+    if can_spawn && !state.bag().is_empty() && draw_allowed(state) {
         actions.push(Action::NewFromBag);
     }
 
-    // Check each cordinate for available actions.
-    for y in 0..HEIGHT {
-        for x in 0..WIDTH {
-            let cord = Coordinate::new(x, y);
-            if state.square(cord).tile.is_some() {
-                let tile = state.square(cord).tile.as_ref().unwrap();
-                if tile.color == state.ply {
-                    actions.append(&mut get_tile_actions(
-                        state,
-                        Coordinate {
-                            x: x as u8,
-                            y: y as u8,
-                        },
-                    ));
-                }
-            }
-        }
+    // Check each tile for available actions.
+    for (cord, _) in state.iter_tiles(Some(state.ply)) {
+        for_each_tile_action(state, cord, &mut |action| actions.push(action));
     }
-
-    actions
 }
 
-fn add_tile_effects(state: &mut GameState, tile_pos: Coordinate) {
-    let tile = &state.board[tile_pos.y as usize][tile_pos.x as usize]
-        .tile
-        .expect("Add effects, but no tile.");
-    let effects: &Vec<AvailableEffect>;
+/// Number of squares of the largest board, the length of board arrays.
+pub const SQUARES: usize = MAX_WIDTH as usize * MAX_HEIGHT as usize;
 
-    if !tile.flipped {
-        effects = &tile.effects().front;
-    } else {
-        effects = &tile.effects().back;
-    }
+/// Index of square in board arrays, `y * MAX_WIDTH + x` whatever the size of
+/// the board.
+fn square_index(cord: Coordinate) -> usize {
+    (cord.y * MAX_WIDTH + cord.x) as usize
+}
 
-    // Each effect position has to be calculated.
-    // FIXME: Use effect references, again?
-    for effect in effects {
-        let effect_x = (tile_pos.x as i8 + effect.offset.x) as u8;
-        let effect_y = (tile_pos.y as i8 + effect.offset.y) as u8;
-        if Coordinate::legal(effect_x, effect_y) {
-            state.board[effect_y as usize][effect_x as usize]
-                .effects
-                .push(effect.kind.clone());
+/// Squares of a board, indexed by `square_index`. Squares are `Copy`, so
+/// cloning the board allocates nothing. Squares off the board stay empty.
+#[derive(Clone, Copy, Debug)]
+struct Board {
+    squares: [Square; SQUARES],
+    size: BoardSize,
+}
+
+impl Board {
+    fn new(size: BoardSize) -> Board {
+        Board {
+            squares: [Square::default(); SQUARES],
+            size,
         }
     }
 }
 
-fn clear_tile_effects(state: &mut GameState, tile_pos: Coordinate) {
-    let tile = &state.board[tile_pos.y as usize][tile_pos.x as usize]
-        .tile
-        .expect("Clear effects, but no tile.");
-    let effects: &Vec<AvailableEffect>;
-
-    if !tile.flipped {
-        effects = &tile.effects().front;
-    } else {
-        effects = &tile.effects().back;
+/// Board serialized as rows of squares from y = 0, as many as the board has,
+/// so standard boards are the same as before boards had sizes.
+#[cfg(feature = "serde")]
+mod board_rows {
+    use super::{square_index, Board, BoardSize, Coordinate, Square};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    impl Serialize for Board {
+        fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+            let rows: Vec<&[Square]> = (0..self.size.height)
+                .map(|y| {
+                    let start = square_index(Coordinate { x: 0, y });
+                    &self.squares[start..start + self.size.width as usize]
+                })
+                .collect();
+            rows.serialize(s)
+        }
     }
 
-    // Each effect position has to be calculated.
-    // FIXME: Use effect references, again?
-    for effect in effects {
-        let effect_x = (tile_pos.x as i8 + effect.offset.x) as u8;
-        let effect_y = (tile_pos.y as i8 + effect.offset.y) as u8;
-        if Coordinate::legal(effect_x, effect_y) {
-            let square_effects = &mut state.board[effect_y as usize][effect_x as usize].effects;
-            let mut effect_idx: Option<usize> = None;
-            for i in 0..square_effects.len() {
-                if square_effects[i] == effect.kind {
-                    effect_idx = Some(i);
-                    break;
+    impl<'de> Deserialize<'de> for Board {
+        fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Board, D::Error> {
+            let rows = Vec::<Vec<Square>>::deserialize(d)?;
+            let width = rows.first().map_or(0, Vec::len);
+            let size = match (u8::try_from(width), u8::try_from(rows.len())) {
+                (Ok(width), Ok(height)) if rows.iter().all(|row| row.len() == width as usize) => {
+                    BoardSize::try_new(width, height)
                 }
+                _ => None,
             }
+            .ok_or_else(|| D::Error::custom("board rows are not of a board size"))?;
 
-            if effect_idx.is_some() {
-                square_effects.swap_remove(effect_idx.unwrap());
+            let mut board = Board::new(size);
+            for (y, row) in rows.iter().enumerate() {
+                let start = square_index(Coordinate { x: 0, y: y as u8 });
+                board.squares[start..start + row.len()].copy_from_slice(row);
             }
+            Ok(board)
         }
     }
 }
 
-/// This function assumes that the action is legal. Only provide an action
-/// returned by `get_actions` or `get_tile_actions` on the same state or bad
-/// things will happen.
-pub fn do_unsafe_action(state: &mut GameState, action: &Action) {
-    let mut standard_action = |data: &ActionData| {
-        let mut tile = state.square(data.tile_pos).tile.unwrap(); // Copy
+/// Bit of square in attack maps.
+pub fn square_bit(cord: Coordinate) -> u64 {
+    1 << square_index(cord)
+}
 
-        assert!(tile.color == state.ply);
+/// Which tiles bear on which squares. A tile bears on a square if it could
+/// capture an enemy tile there, so squares of friendly tiles are included.
+/// Build with `compute_attack_map`.
+#[derive(Debug, Clone)]
+pub struct AttackMap {
+    /// Bits of tiles bearing on each square, per color.
+    attackers: [[u64; SQUARES]; 2],
+    /// Number of capturing actions on each square, per color. A tile can have
+    /// more than one action on the same square.
+    actions: [[u8; SQUARES]; 2],
+    /// Bits of squares borne on, per color.
+    squares: [u64; 2],
+}
 
-        // Clear effects
-        clear_tile_effects(state, data.tile_pos);
+impl AttackMap {
+    fn add(&mut self, color: TileColor, attacker: Coordinate, target: Coordinate, actions: u8) {
+        let c = color as usize;
+        let i = square_index(target);
+        self.attackers[c][i] |= square_bit(attacker);
+        self.actions[c][i] += actions;
+        self.squares[c] |= square_bit(target);
+    }
+
+    /// Tiles of color bearing on square.
+    pub fn attackers_of(&self, cord: Coordinate, color: TileColor) -> Vec<Coordinate> {
+        let bits = self.attackers[color as usize][square_index(cord)];
+        (0..SQUARES as u8)
+            .filter(|i| bits & (1 << i) != 0)
+            .map(|i| Coordinate::new(i % MAX_WIDTH, i / MAX_WIDTH))
+            .collect()
+    }
+
+    /// Squares of tiles of color bearing on square, as bits from `square_bit`.
+    pub fn attacker_squares(&self, cord: Coordinate, color: TileColor) -> u64 {
+        self.attackers[color as usize][square_index(cord)]
+    }
+
+    /// True if any tile of color bears on square.
+    pub fn is_attacked(&self, cord: Coordinate, color: TileColor) -> bool {
+        self.attackers[color as usize][square_index(cord)] != 0
+    }
+
+    /// Number of actions of color that would capture an enemy tile on square.
+    /// Same as the number of capturing actions from `get_actions`, when
+    /// square has an enemy tile and it is the turn of color.
+    pub fn attack_count(&self, cord: Coordinate, color: TileColor) -> u32 {
+        self.actions[color as usize][square_index(cord)] as u32
+    }
+
+    /// Squares color bears on, as bits from `square_bit`.
+    pub fn squares(&self, color: TileColor) -> u64 {
+        self.squares[color as usize]
+    }
+}
+
+/// Build attack map of both colors in one pass over the board.
+pub fn compute_attack_map(state: &GameState) -> AttackMap {
+    let mut map = AttackMap {
+        attackers: [[0; SQUARES]; 2],
+        actions: [[0; SQUARES]; 2],
+        squares: [0; 2],
+    };
+
+    for tile_pos in state.size().squares() {
+        let tile = match &state.square(tile_pos).tile {
+            Some(tile) => tile,
+            None => continue,
+        };
+        if !tile_can_act(state, (tile_pos, tile)) {
+            continue;
+        }
+
+        let avail_actions = if tile.flipped {
+            &tile.actions().back
+        } else {
+            &tile.actions().front
+        };
+
+        // Own tiles on command squares, i.e. tiles that can be commanded.
+        let mut commanded = 0u8;
+        let mut command_squares: u64 = 0;
+
+        for action in avail_actions {
+            let target = match tile_pos.offset(&action.offset, state.size()) {
+                Some(target) => target,
+                None => continue,
+            };
+
+            match action.kind {
+                ActionType::Move if !move_path_blocked(state, None, tile_pos, target) => {
+                    map.add(tile.color, tile_pos, target, 1);
+                }
+                ActionType::Jump if !target_blocked_for_jump(state, None, target) => {
+                    map.add(tile.color, tile_pos, target, 1);
+                }
+                ActionType::Strike if !target_blocked_for_strike(state, target) => {
+                    map.add(tile.color, tile_pos, target, 1);
+                }
+                ActionType::Slide | ActionType::JumpSlide => {
+                    // Same as get_slide_actions.
+                    let dir = get_direction(tile_pos, target);
+                    let mut next = Some(target);
+                    while let Some(cord) = next {
+                        let square = state.square(cord);
+                        if square.effects.contains(&Effect::Defence) {
+                            break;
+                        }
+                        map.add(tile.color, tile_pos, cord, 1);
+                        if square.tile.is_some() {
+                            break;
+                        }
+                        next = cord.offset(&dir, state.size());
+                    }
+                }
+                ActionType::Command => {
+                    command_squares |= square_bit(target);
+                    if let Some(t) = state.square(target).tile {
+                        if t.color == tile.color {
+                            commanded += 1;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Command is only blocked by Defence, same as get_command_actions.
+        // Any commanded tile, other than one on the square itself, can
+        // capture there.
+        for i in 0..SQUARES as u8 {
+            if command_squares & (1 << i) == 0 {
+                continue;
+            }
+            let cord = Coordinate::new(i % MAX_WIDTH, i / MAX_WIDTH);
+            if defended(state, cord) {
+                continue;
+            }
+            let own = match state.square(cord).tile {
+                Some(t) if t.color == tile.color => 1,
+                _ => 0,
+            };
+            if commanded > own {
+                map.add(tile.color, tile_pos, cord, commanded - own);
+            }
+        }
+    }
+
+    map
+}
+
+/// Tiles under attack, from the view of one color. For hints in UIs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThreatReport {
+    /// Own tiles the opponent bears on, with the squares of the tiles bearing
+    /// on them. For Command, the square of the commanding tile.
+    pub attacked: Vec<(Coordinate, Vec<Coordinate>)>,
+    /// Opponent tiles that own tiles bear on, and no opponent tile defends.
+    pub hanging: Vec<Coordinate>,
+}
+
+impl ThreatReport {
+    /// True if own tile on square is attacked.
+    pub fn is_attacked(&self, cord: Coordinate) -> bool {
+        self.attacked.iter().any(|(c, _)| *c == cord)
+    }
+
+    /// True if opponent tile on square is hanging.
+    pub fn is_hanging(&self, cord: Coordinate) -> bool {
+        self.hanging.contains(&cord)
+    }
+}
+
+/// Threats to and by tiles of color, see `ThreatReport`. Uses the attack
+/// map, so it does not depend on whose turn it is. A tile commanded onto a
+/// square counts as an attack on it. See `threatened_squares` for only the
+/// captures the tiles have.
+///
+/// The Black General commands the Footman to d2, which the General can't
+/// reach itself:
+///
+/// ```
+/// use rusty_duke_logic::logic::builder::GameStateBuilder;
+/// use rusty_duke_logic::logic::{get_threats, Coordinate, TileColor, TileType};
+///
+/// let general = Coordinate::new(2, 2);
+/// let pikeman = Coordinate::new(3, 1);
+/// let builder = GameStateBuilder::new()
+///     .tile_at(Coordinate::new(0, 0), TileType::Duke, TileColor::Black, false)
+///     .tile_at(Coordinate::new(5, 5), TileType::Duke, TileColor::White, false)
+///     .tile_at(general, TileType::General, TileColor::Black, true)
+///     .tile_at(Coordinate::new(1, 2), TileType::Footman, TileColor::Black, false)
+///     .tile_at(pikeman, TileType::Pikeman, TileColor::White, false);
+///
+/// let state = builder.clone().build().unwrap();
+/// let black = get_threats(&state, TileColor::Black);
+/// assert!(black.attacked.is_empty());
+/// assert_eq!(black.hanging, vec![pikeman]);
+/// let white = get_threats(&state, TileColor::White);
+/// assert_eq!(white.attacked, vec![(pikeman, vec![general])]);
+/// assert!(white.hanging.is_empty());
+///
+/// // A White Footman next to the Pikeman defends it.
+/// let state = builder
+///     .tile_at(Coordinate::new(4, 1), TileType::Footman, TileColor::White, false)
+///     .build()
+///     .unwrap();
+/// assert!(get_threats(&state, TileColor::Black).hanging.is_empty());
+/// assert!(get_threats(&state, TileColor::White).is_attacked(pikeman));
+/// ```
+pub fn get_threats(state: &GameState, color: TileColor) -> ThreatReport {
+    let map = compute_attack_map(state);
+    let opponent = color.opponent();
+    let mut report = ThreatReport::default();
+
+    for (cord, square) in state.iter_squares() {
+        let tile = match square.tile {
+            Some(tile) => tile,
+            None => continue,
+        };
+        if tile.color == color {
+            if map.is_attacked(cord, opponent) {
+                report
+                    .attacked
+                    .push((cord, map.attackers_of(cord, opponent)));
+            }
+        } else if map.is_attacked(cord, color) && !map.is_attacked(cord, opponent) {
+            report.hanging.push(cord);
+        }
+    }
+
+    report
+}
+
+/// Opponent tiles color could capture were it its turn, with the squares of
+/// the capturing tiles. For Command, the square of the commanding tile.
+/// Squares row by row from y = 0, attackers too.
+///
+/// Unlike `get_threats`, from the actions of the tiles, so exactly the
+/// captures of `get_actions`, with Dread and Defence respected. The ply of
+/// state is not changed nor looked at. Empty when the game is over.
+///
+/// ```
+/// use rusty_duke_logic::logic::builder::GameStateBuilder;
+/// use rusty_duke_logic::logic::{threatened_squares, Coordinate, TileColor, TileType};
+///
+/// // Black to go, the White Footman on b2 can capture the Black one on b1.
+/// let footman = Coordinate::new(1, 0);
+/// let attacker = Coordinate::new(1, 1);
+/// let state = GameStateBuilder::new()
+///     .tile_at(Coordinate::new(3, 0), TileType::Duke, TileColor::Black, false)
+///     .tile_at(Coordinate::new(3, 5), TileType::Duke, TileColor::White, false)
+///     .tile_at(footman, TileType::Footman, TileColor::Black, false)
+///     .tile_at(attacker, TileType::Footman, TileColor::White, false)
+///     .build()
+///     .unwrap();
+/// assert_eq!(
+///     threatened_squares(&state, TileColor::White),
+///     vec![(footman, vec![attacker])]
+/// );
+/// assert_eq!(
+///     threatened_squares(&state, TileColor::Black),
+///     vec![(attacker, vec![footman])]
+/// );
+/// ```
+pub fn threatened_squares(
+    state: &GameState,
+    by_color: TileColor,
+) -> Vec<(Coordinate, Vec<Coordinate>)> {
+    let mut threatened: Vec<(Coordinate, Vec<Coordinate>)> = Vec::new();
+    for (cord, _) in state.iter_tiles(Some(by_color)) {
+        for_each_tile_action(state, cord, &mut |action| {
+            let target = match capture_target(&action) {
+                Some(target) => target,
+                None => return,
+            };
+            match threatened.iter_mut().find(|(c, _)| *c == target) {
+                Some((_, attackers)) if attackers.contains(&cord) => {}
+                Some((_, attackers)) => attackers.push(cord),
+                None => threatened.push((target, vec![cord])),
+            }
+        });
+    }
+    threatened.sort_by_key(|(c, _)| square_index(*c));
+    threatened
+}
+
+/// Square of the tile action captures, if it captures.
+fn capture_target(action: &Action) -> Option<Coordinate> {
+    match action {
+        Action::Move(data)
+        | Action::Jump(data)
+        | Action::JumpSlide(data)
+        | Action::Slide(data)
+        | Action::Strike(data)
+            if data.result == ActionResult::Capture =>
+        {
+            Some(data.target_pos)
+        }
+        Action::Command(data) if data.result == ActionResult::Capture => Some(data.target_pos),
+        _ => None,
+    }
+}
+
+/// True if action captures the tile on pos.
+fn captures_at(action: &Action, pos: Coordinate) -> bool {
+    capture_target(action) == Some(pos)
+}
+
+/// Actions of opponent tiles that would capture the tile on pos, were it
+/// the opponent's turn. Has the square of the acting tile with each action,
+/// the commander for Command. Empty if pos has no tile.
+///
+/// ```
+/// use rusty_duke_logic::logic::builder::GameStateBuilder;
+/// use rusty_duke_logic::logic::{attackers_of, is_in_check, Action, Coordinate, TileColor, TileType};
+///
+/// // White Pikeman, flipped, strikes two squares ahead and one aside.
+/// let state = GameStateBuilder::new()
+///     .tile_at(Coordinate::new(2, 0), TileType::Duke, TileColor::Black, false)
+///     .tile_at(Coordinate::new(3, 5), TileType::Duke, TileColor::White, false)
+///     .tile_at(Coordinate::new(3, 2), TileType::Pikeman, TileColor::White, true)
+///     .build()
+///     .unwrap();
+/// let attackers = attackers_of(&state, Coordinate::new(2, 0));
+/// assert_eq!(attackers.len(), 1);
+/// assert_eq!(attackers[0].0, Coordinate::new(3, 2));
+/// assert!(matches!(attackers[0].1, Action::Strike(_)));
+/// assert!(is_in_check(&state, TileColor::Black));
+/// ```
+pub fn attackers_of(state: &GameState, pos: Coordinate) -> Vec<(Coordinate, Action)> {
+    let color = match state.square(pos).tile {
+        Some(tile) => tile.color,
+        None => return Vec::new(),
+    };
+
+    state
+        .iter_tiles(Some(color.opponent()))
+        .flat_map(|(cord, _)| {
+            get_tile_actions(state, cord)
+                .into_iter()
+                .map(move |action| (cord, action))
+        })
+        .filter(|(_, action)| captures_at(action, pos))
+        .collect()
+}
+
+/// True if Duke of color can be captured by the opponent, i.e. if the
+/// opponent could capture it were it the opponent's turn. Same as non-empty
+/// `attackers_of` on the Duke, but stops at the first attacker.
+pub fn is_in_check(state: &GameState, color: TileColor) -> bool {
+    let duke_pos = match state.dukes[color as usize] {
+        Some(pos) => pos,
+        None => return false,
+    };
+
+    for cord in state.size().squares() {
+        match state.square(cord).tile {
+            Some(tile) if tile.color != color => {}
+            _ => continue,
+        }
+
+        let mut captures_duke = false;
+        for_each_tile_action(state, cord, &mut |a| {
+            captures_duke |= captures_at(&a, duke_pos)
+        });
+        if captures_duke {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// True if action doesn't leave own Duke in check. Capturing the opponent
+/// Duke is always safe, the game is won. Game over is not evaluated here,
+/// that would recurse.
+fn is_safe(state: &mut GameState, action: &Action) -> bool {
+    match action {
+        // Only offered if the drawn tile can be placed safely.
+        Action::NewFromBag => true,
+        _ => {
+            // Make and unmake action, copying the state is slower.
+            let color = state.ply;
+            let delta = apply_action(state, action);
+            let safe = delta
+                .captured
+                .is_some_and(|captured| captured.tile.kind == TileType::Duke)
+                || !is_in_check(state, color);
+            undo_action(state).expect("Applied action can be undone.");
+            safe
+        }
+    }
+}
+
+/// Actions that don't leave own Duke in check.
+fn safe_actions(state: &GameState) -> Vec<Action> {
+    let mut root = state.clone_without_history();
+    get_actions(&root)
+        .into_iter()
+        .filter(|action| is_safe(&mut root, action))
+        .collect()
+}
+
+/// True if current player has any action that doesn't leave own Duke in
+/// check. Stops at the first one, cheaper than `safe_actions`.
+fn has_safe_action(state: &GameState) -> bool {
+    let mut root = state.clone_without_history();
+    get_actions(&root)
+        .iter()
+        .any(|action| is_safe(&mut root, action))
+}
+
+/// Get legal actions for a given game state. Same as `get_actions`, but
+/// actions that leave own Duke in check are removed. Drawing is already
+/// checked by `get_actions`.
+///
+/// Leaving the Duke in check is never legal. If every action does, there are
+/// no legal actions and the game is over, see `WinReason::Guardmate` and
+/// `WinReason::NoMoves`. `get_actions` is cheaper and meant for search.
+pub fn get_legal_actions(state: &GameState) -> Vec<Action> {
+    safe_actions(state)
+}
+
+/// True if Duke of current player is in check and can't get out of it.
+pub fn is_checkmate(state: &GameState) -> bool {
+    is_in_check(state, state.ply) && !has_safe_action(state)
+}
+
+/// Winner and reason, if game is over for current ply.
+fn game_over_with_reason(state: &GameState) -> Option<(Winner, WinReason)> {
+    let opponent = if state.ply == TileColor::Black {
+        Winner::Color(TileColor::White)
+    } else {
+        Winner::Color(TileColor::Black)
+    };
+
+    if state.own_duke_pos().is_none() {
+        // No Duke is fine only when it is about to be deployed.
+        let new_tile = state.drawn().last();
+        if new_tile.is_some_and(|t| t.kind == TileType::Duke) {
+            return None;
+        }
+        return Some((opponent, WinReason::DukeCaptured));
+    }
+
+    // A player without legal actions loses, whether in check or not.
+    if !has_safe_action(state) {
+        let reason = if is_in_check(state, state.ply) {
+            WinReason::Guardmate
+        } else {
+            WinReason::NoMoves
+        };
+        return Some((opponent, reason));
+    }
+
+    if state
+        .repetition_limit
+        .is_some_and(|limit| state.repetitions() >= limit as usize)
+    {
+        return Some((Winner::Draw, WinReason::Repetition));
+    }
+    if state
+        .no_capture_limit
+        .is_some_and(|limit| state.quiet_turns >= limit)
+    {
+        return Some((Winner::Draw, WinReason::NoCaptureLimit));
+    }
+
+    if state.turn_limit.is_some_and(|limit| state.turn >= limit) {
+        return Some(adjudicate(state));
+    }
+
+    None
+}
+
+/// Color ran out of time, the opponent wins by `WinReason::Timeout`. Nothing
+/// happens if the game is already over.
+pub fn flag_fall(state: &mut GameState, color: TileColor) {
+    if state.game_over.is_some() {
+        return;
+    }
+    state.game_over = Some(Winner::Color(color.opponent()));
+    state.win_reason = Some(WinReason::Timeout);
+    state.redo.clear();
+    state.changed();
+}
+
+/// Color resigns, the opponent wins by `WinReason::Resignation`. Possible in
+/// any turn. Nothing happens if the game is already over.
+///
+/// Resigning is not an action, `get_actions` never has it. There are no
+/// actions after it.
+///
+/// ```
+/// use rusty_duke_logic::logic::{get_actions, resign, GameState, TileColor, Winner};
+///
+/// let mut state = GameState::new_seeded(1);
+/// resign(&mut state, TileColor::White);
+/// assert_eq!(state.game_over, Some(Winner::Color(TileColor::Black)));
+/// assert!(get_actions(&state).is_empty());
+/// ```
+pub fn resign(state: &mut GameState, color: TileColor) {
+    if state.game_over.is_some() {
+        return;
+    }
+    state.game_over = Some(Winner::Color(color.opponent()));
+    state.win_reason = Some(WinReason::Resignation);
+    state.redo.clear();
+    state.changed();
+}
+
+/// Color offers a draw, in its own turn. The offer stands until the end of
+/// the next turn of the opponent, who can accept or decline it with
+/// `respond_draw`. Offering is not an action, the turn goes on.
+///
+/// ```
+/// use rusty_duke_logic::logic::{
+///     do_action, get_actions, offer_draw, respond_draw, ActionError, GameState, TileColor,
+///     WinReason, Winner,
+/// };
+///
+/// let mut state = GameState::new_seeded(1);
+/// offer_draw(&mut state, TileColor::Black).unwrap();
+///
+/// // Offer stands in the turn of White.
+/// let action = get_actions(&state)[0];
+/// do_action(&mut state, &action).unwrap();
+/// assert_eq!(state.draw_offer, Some(TileColor::Black));
+///
+/// // White played on without accepting, the offer expired.
+/// let action = get_actions(&state)[0];
+/// do_action(&mut state, &action).unwrap();
+/// assert_eq!(state.draw_offer, None);
+/// assert_eq!(
+///     respond_draw(&mut state, TileColor::White, true),
+///     Err(ActionError::NoDrawOffer)
+/// );
+///
+/// offer_draw(&mut state, TileColor::Black).unwrap();
+/// respond_draw(&mut state, TileColor::White, true).unwrap();
+/// assert_eq!(state.game_over, Some(Winner::Draw));
+/// assert_eq!(state.win_reason, Some(WinReason::DrawAgreed));
+/// ```
+pub fn offer_draw(state: &mut GameState, color: TileColor) -> Result<(), ActionError> {
+    if state.game_over.is_some() {
+        return Err(ActionError::GameAlreadyOver);
+    }
+    if state.ply != color {
+        return Err(ActionError::DrawOfferOutOfTurn);
+    }
+    state.draw_offer = Some(color);
+    state.changed();
+    Ok(())
+}
+
+/// Color accepts or declines the draw offer of the opponent. Accepting ends
+/// the game in a `WinReason::DrawAgreed` draw.
+pub fn respond_draw(
+    state: &mut GameState,
+    color: TileColor,
+    accept: bool,
+) -> Result<(), ActionError> {
+    if state.game_over.is_some() {
+        return Err(ActionError::GameAlreadyOver);
+    }
+    if state.draw_offer != Some(color.opponent()) {
+        return Err(ActionError::NoDrawOffer);
+    }
+    state.draw_offer = None;
+    if accept {
+        state.game_over = Some(Winner::Draw);
+        state.win_reason = Some(WinReason::DrawAgreed);
+        state.redo.clear();
+    }
+    state.changed();
+    Ok(())
+}
+
+/// Winner, if game is over for current ply. `do_unsafe_action` stores this in
+/// game_over, and why in win_reason.
+pub fn evaluate_game_over(state: &GameState) -> Option<Winner> {
+    game_over_with_reason(state).map(|(winner, _)| winner)
+}
+
+fn add_tile_effects(state: &mut GameState, tile_pos: Coordinate) {
+    let tile = &state
+        .square(tile_pos)
+        .tile
+        .expect("Add effects, but no tile.");
+    let effects: &Vec<AvailableEffect>;
+
+    if !tile.flipped {
+        effects = &tile.effects().front;
+    } else {
+        effects = &tile.effects().back;
+    }
+
+    // Each effect position has to be calculated.
+    // FIXME: Use effect references, again?
+    for effect in effects {
+        if let Some(cord) = tile_pos.offset(&effect.offset, state.size()) {
+            state.mut_square(cord).effects.add(&effect.kind, tile.color);
+        }
+    }
+}
+
+fn clear_tile_effects(state: &mut GameState, tile_pos: Coordinate) {
+    let tile = &state
+        .square(tile_pos)
+        .tile
+        .expect("Clear effects, but no tile.");
+    let effects: &Vec<AvailableEffect>;
+
+    if !tile.flipped {
+        effects = &tile.effects().front;
+    } else {
+        effects = &tile.effects().back;
+    }
+
+    // Each effect position has to be calculated.
+    // FIXME: Use effect references, again?
+    for effect in effects {
+        if let Some(cord) = tile_pos.offset(&effect.offset, state.size()) {
+            state
+                .mut_square(cord)
+                .effects
+                .remove(&effect.kind, tile.color);
+        }
+    }
+}
+
+/// Tile moved by an action, see `StateDelta`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileMove {
+    pub from: Coordinate,
+    pub to: Coordinate,
+    pub kind: TileType,
+    /// Side of the tile after the move.
+    pub flipped: bool,
+}
+
+/// What changed when an action was done, so front-ends and other observers
+/// can update without comparing states.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateDelta {
+    /// Tile that moved: the acting tile, or the commanded tile of a Command.
+    /// None for Strike, which captures without moving.
+    pub moved: Option<TileMove>,
+    /// Positions, after the action, of tiles that were flipped. Only the acting
+    /// tile is flipped: mover, striker or commander.
+    pub flipped: Vec<Coordinate>,
+    /// Tile captured by the action, if any. Same as the new last entry of
+    /// `GameState::graveyard`.
+    pub captured: Option<CapturedTile>,
+    /// Effects that squares gained, with the color of the tile imposing
+    /// them. Effects of a tile that moved, flipped or was captured are
+    /// removed from their old squares and added to the new. Effects that
+    /// stayed on a square are in neither list.
+    pub effects_added: Vec<(Coordinate, Effect, TileColor)>,
+    pub effects_removed: Vec<(Coordinate, Effect, TileColor)>,
+    /// Tile drawn by `NewFromBag`.
+    pub drawn: Option<Tile>,
+    /// Tile placed by `PlaceNew`, and its square.
+    pub placed: Option<(Coordinate, Tile)>,
+    /// Player to go after the action. None if the turn goes on, after
+    /// `NewFromBag`.
+    pub next_ply: Option<TileColor>,
+    /// Winner and reason, if the action ended the game.
+    pub game_over: Option<(Winner, WinReason)>,
+}
+
+impl StateDelta {
+    /// Effects imposed by tile on square, about to be cleared.
+    fn clearing_effects(&mut self, state: &GameState, tile_pos: Coordinate) {
+        self.effects_removed
+            .extend(imposed_effects(state, tile_pos));
+    }
+
+    /// Effects imposed by tile on square, just added.
+    fn added_effects(&mut self, state: &GameState, tile_pos: Coordinate) {
+        self.effects_added.extend(imposed_effects(state, tile_pos));
+    }
+
+    /// Drop effects that were removed and added back to the same square.
+    fn net_effects(&mut self) {
+        let mut i = 0;
+        while i < self.effects_added.len() {
+            match self
+                .effects_removed
+                .iter()
+                .position(|e| *e == self.effects_added[i])
+            {
+                Some(j) => {
+                    self.effects_removed.swap_remove(j);
+                    self.effects_added.swap_remove(i);
+                }
+                None => i += 1,
+            }
+        }
+    }
+
+    /// The changes as a sequence of events, in the order they happen on the
+    /// board: draw or deploy, capture, move, flip, effects and game end.
+    pub fn events(&self) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        if let Some(tile) = self.drawn {
+            events.push(GameEvent::TileDrawn { tile });
+        }
+        if let Some((at, tile)) = self.placed {
+            events.push(GameEvent::TileDeployed { at, tile });
+        }
+        if let Some(captured) = self.captured {
+            events.push(GameEvent::TileCaptured {
+                at: captured.square,
+                tile: captured.tile,
+            });
+        }
+        if let Some(moved) = self.moved {
+            events.push(GameEvent::TileMoved {
+                from: moved.from,
+                to: moved.to,
+            });
+        }
+        for at in self.flipped.iter() {
+            events.push(GameEvent::TileFlipped { at: *at });
+        }
+        for (at, effect, color) in self.effects_removed.iter() {
+            events.push(GameEvent::EffectRemoved {
+                at: *at,
+                effect: effect.clone(),
+                color: *color,
+            });
+        }
+        for (at, effect, color) in self.effects_added.iter() {
+            events.push(GameEvent::EffectAdded {
+                at: *at,
+                effect: effect.clone(),
+                color: *color,
+            });
+        }
+        if let Some((winner, reason)) = &self.game_over {
+            events.push(GameEvent::GameEnded {
+                winner: winner.clone(),
+                reason: *reason,
+            });
+        }
+        events
+    }
+}
+
+/// Something that happened on the board during an action, for animations
+/// and logs. See `StateDelta::events`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    /// Tile went from one square to another, by Move, Jump, Slide, JumpSlide
+    /// or being commanded.
+    TileMoved { from: Coordinate, to: Coordinate },
+    /// Tile was removed from the board, by a capture or a Strike.
+    TileCaptured { at: Coordinate, tile: Tile },
+    /// Tile now on square was flipped.
+    TileFlipped { at: Coordinate },
+    /// Drawn tile was placed on the board.
+    TileDeployed { at: Coordinate, tile: Tile },
+    /// Tile was drawn from the bag of the player to go.
+    TileDrawn { tile: Tile },
+    /// Square gained an effect from a tile of color.
+    EffectAdded {
+        at: Coordinate,
+        effect: Effect,
+        color: TileColor,
+    },
+    /// Square lost an effect from a tile of color.
+    EffectRemoved {
+        at: Coordinate,
+        effect: Effect,
+        color: TileColor,
+    },
+    /// Action ended the game.
+    GameEnded { winner: Winner, reason: WinReason },
+}
+
+/// Effects imposed by tile on square, with the squares they are on and the
+/// color of the tile.
+fn imposed_effects(
+    state: &GameState,
+    tile_pos: Coordinate,
+) -> Vec<(Coordinate, Effect, TileColor)> {
+    let tile = state.square(tile_pos).tile.expect("Effects, but no tile.");
+    let effects = if tile.flipped {
+        &tile.effects().back
+    } else {
+        &tile.effects().front
+    };
+    effects
+        .iter()
+        .filter_map(|effect| {
+            tile_pos
+                .offset(&effect.offset, state.size())
+                .map(|cord| (cord, effect.kind.clone(), tile.color))
+        })
+        .collect()
+}
+
+/// True if effects on every square are exactly the effects of the tiles on
+/// board. Effects are kept up to date as tiles move, flip and are captured,
+/// this recalculates them to catch an action or undo that forgot to clear or
+/// add them.
+fn effects_consistent(state: &GameState) -> bool {
+    let mut expected = [EffectSet::default(); SQUARES];
+    for (cord, tile) in state.iter_tiles(None) {
+        let effects = if !tile.flipped {
+            &tile.effects().front
+        } else {
+            &tile.effects().back
+        };
+        for effect in effects {
+            if let Some(target) = cord.offset(&effect.offset, state.size()) {
+                expected[square_index(target)].add(&effect.kind, tile.color);
+            }
+        }
+    }
+
+    state
+        .board
+        .squares
+        .iter()
+        .zip(expected.iter())
+        .all(|(square, expected)| square.effects == *expected)
+}
+
+/// This function assumes that the action is legal. Only provide an action
+/// returned by `get_actions` or `get_tile_actions` on the same state or bad
+/// things will happen.
+pub fn do_unsafe_action(state: &mut GameState, action: &Action) -> StateDelta {
+    let mut delta = apply_action(state, action);
+
+    // Drawing a tile is the first stage of a turn, game can't end here.
+    if *action != Action::NewFromBag {
+        if delta.captured.is_some() || matches!(action, Action::PlaceNew(_)) {
+            state.quiet_turns = 0;
+        } else {
+            state.quiet_turns += 1;
+        }
+        if state.repetition_limit.is_some() {
+            let hash = state.hash();
+            state.positions.push(hash);
+        }
+
+        match game_over_with_reason(state) {
+            Some((winner, reason)) => {
+                state.game_over = Some(winner.clone());
+                state.win_reason = Some(reason);
+                delta.game_over = Some((winner, reason));
+            }
+            None => {
+                state.game_over = None;
+                state.win_reason = None;
+            }
+        }
+    }
+
+    delta
+}
+
+/// Possible result of `NewFromBag`, see `draw_outcomes`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawOutcome {
+    /// Index in `bag()` of a tile of the type, for `draw_from_bag`.
+    pub index: usize,
+    pub kind: TileType,
+    /// Tiles of the type in the bag. The chance of the outcome is this
+    /// divided by the size of the bag.
+    pub count: usize,
+}
+
+/// Every tile type `NewFromBag` can draw, in order of first appearance in
+/// the bag. Drawing any tile of a type gives the same position, so searches
+/// can go through the outcomes with `draw_from_bag` instead of drawing at
+/// random.
+///
+/// ```
+/// use rusty_duke_logic::logic::builder::GameStateBuilder;
+/// use rusty_duke_logic::logic::{draw_outcomes, Coordinate, TileColor, TileType};
+///
+/// let state = GameStateBuilder::new()
+///     .tile_at(Coordinate::new(2, 0), TileType::Duke, TileColor::Black, false)
+///     .tile_at(Coordinate::new(3, 5), TileType::Duke, TileColor::White, false)
+///     .bag(
+///         TileColor::Black,
+///         vec![TileType::Pikeman, TileType::Knight, TileType::Pikeman],
+///     )
+///     .build()
+///     .unwrap();
+/// let outcomes = draw_outcomes(&state);
+/// assert_eq!(outcomes.len(), 2);
+/// assert_eq!((outcomes[0].kind, outcomes[0].count), (TileType::Pikeman, 2));
+/// assert_eq!((outcomes[1].index, outcomes[1].count), (1, 1));
+/// ```
+pub fn draw_outcomes(state: &GameState) -> Vec<DrawOutcome> {
+    let mut outcomes: Vec<DrawOutcome> = Vec::new();
+    for (index, tile) in state.bag().iter().enumerate() {
+        match outcomes.iter_mut().find(|o| o.kind == tile.kind) {
+            Some(outcome) => outcome.count += 1,
+            None => outcomes.push(DrawOutcome {
+                index,
+                kind: tile.kind,
+                count: 1,
+            }),
+        }
+    }
+    outcomes
+}
+
+/// Same as `do_unsafe_action` with `NewFromBag`, but draws the tile at index
+/// in `bag()` instead of a random one. For replaying recorded games.
+///
+/// The random generator is advanced as for a random draw, so a replay from the
+/// original seed continues with the original random stream.
+pub fn draw_from_bag(state: &mut GameState, index: usize) {
+    let entry = HistoryEntry::new(state, &Action::NewFromBag);
+    state.rng.gen_range(0..state.bag().len());
+    draw_tile(state, index, entry);
+}
+
+fn draw_tile(state: &mut GameState, index: usize, mut entry: HistoryEntry) -> Tile {
+    let tile = state.take_from_bag(index);
+    state.push_drawn(tile);
+    state.ply_count += 1;
+    state.changed();
+
+    entry.bag_index = Some(index);
+    state.history.push(entry);
+    state.redo.clear();
+    tile
+}
+
+/// Put tile captured on square by current player in graveyard.
+fn capture(state: &mut GameState, tile: Tile, square: Coordinate) -> CapturedTile {
+    let captured = CapturedTile {
+        tile,
+        by: state.ply,
+        at_ply: state.ply_count,
+        square,
+    };
+    state.graveyard.push(captured);
+    captured
+}
+
+/// Do action and update ply, but don't check if game is over.
+fn apply_action(state: &mut GameState, action: &Action) -> StateDelta {
+    let mut delta = StateDelta::default();
+    let mut entry = HistoryEntry::new(state, action);
+
+    let standard_action = |state: &mut GameState, delta: &mut StateDelta, data: &ActionData| {
+        let mut tile = state.square(data.tile_pos).tile.unwrap(); // Copy
+
+        assert!(tile.color == state.ply);
+
+        // Clear effects
+        delta.clearing_effects(state, data.tile_pos);
+        clear_tile_effects(state, data.tile_pos);
 
         // Flip
         tile.flip();
 
         // Clear source square
-        state.mut_square(data.tile_pos).tile = None;
+        state.set_tile(data.tile_pos, None);
 
         // And do transfer of tile ownership. This is a bit messy due to ownership.
         if data.result == ActionResult::Capture {
+            delta.clearing_effects(state, data.target_pos);
             clear_tile_effects(state, data.target_pos);
 
             let captured = state.square(data.target_pos).tile.unwrap();
@@ -2147,137 +3127,380 @@ pub fn do_unsafe_action(state: &mut GameState, action: &Action) {
             }
 
             // Put captured in graveyard
-            state.graveyard.push(captured);
-
-            // Put action tile on square
-            state.mut_square(data.target_pos).tile = Some(tile);
-        } else {
-            // Put action tile on square
-            state.mut_square(data.target_pos).tile = Some(tile);
+            delta.captured = Some(capture(state, captured, data.target_pos));
         }
+        // Put action tile on square
+        state.set_tile(data.target_pos, Some(tile));
 
         // Add effects
         add_tile_effects(state, data.target_pos);
+        delta.added_effects(state, data.target_pos);
 
         // If Duke, save Duke pos
         if tile.kind == TileType::Duke {
             *state.mut_own_duke_pos() = Some(data.target_pos.clone());
         }
+
+        delta.moved = Some(TileMove {
+            from: data.tile_pos,
+            to: data.target_pos,
+            kind: tile.kind,
+            flipped: tile.flipped,
+        });
+        delta.flipped.push(data.target_pos);
     };
 
     // Do action on new state
     match action {
         Action::NewFromBag => {
-            let index = (rand::random::<f32>() * state.bag().len() as f32).floor() as usize;
-            let tile = state.mut_bag().swap_remove(index);
-            state.mut_drawn().push(tile);
+            // gen_range is unbiased, every tile in bag is equally likely.
+            let index = state.rng.gen_range(0..state.bag().len());
+            delta.drawn = Some(draw_tile(state, index, entry));
 
             // Don't update ply or game over. Just return. This is a special case.
-            return;
+            return delta;
         }
         Action::PlaceNew(cord) => {
-            let tile = state.mut_drawn().pop().unwrap();
+            let tile = state.pop_drawn();
 
             assert!(tile.color == state.ply);
 
             if tile.kind == TileType::Duke {
                 *state.mut_own_duke_pos() = Some(cord.clone());
             }
-            state.mut_square(*cord).tile = Some(tile);
+            state.set_tile(*cord, Some(tile));
+            delta.placed = Some((*cord, tile));
 
             // Add effects
             add_tile_effects(state, *cord);
+            delta.added_effects(state, *cord);
         }
         Action::Move(data) | Action::Jump(data) | Action::JumpSlide(data) | Action::Slide(data) => {
-            standard_action(data);
+            standard_action(state, &mut delta, data);
         }
         Action::Command(data) => {
             assert!(state.square(data.tile_pos).tile.as_ref().unwrap().color == state.ply);
 
-            let tile = state.square(data.command_tile_pos).tile.unwrap(); // Copy
+            let commanded = state.square(data.command_tile_pos).tile.unwrap(); // Copy
 
-            // Clear commander effects
+            // Clear commander effects, on its squares before the flip.
+            delta.clearing_effects(state, data.tile_pos);
             clear_tile_effects(state, data.tile_pos);
 
-            // Clear commanded effects
-            clear_tile_effects(state, data.tile_pos);
+            // Clear commanded effects, on its squares before the move.
+            delta.clearing_effects(state, data.command_tile_pos);
+            clear_tile_effects(state, data.command_tile_pos);
 
-            state.mut_square(data.command_tile_pos).tile = None;
+            state.set_tile(data.command_tile_pos, None);
 
+            // Captured tile goes to the graveyard, with its effects cleared.
             if data.result == ActionResult::Capture {
+                delta.clearing_effects(state, data.target_pos);
                 clear_tile_effects(state, data.target_pos);
                 let captured = state.square(data.target_pos).tile.unwrap();
                 if captured.kind == TileType::Duke {
                     *state.mut_opponent_duke_pos() = None;
                 }
-                state.graveyard.push(captured);
-                state.mut_square(data.target_pos).tile = Some(tile);
-            } else {
-                state.mut_square(data.target_pos).tile = Some(tile);
+                delta.captured = Some(capture(state, captured, data.target_pos));
             }
+            state.set_tile(data.target_pos, Some(commanded));
 
-            // Flip
-            let commander = state.mut_square(data.tile_pos).tile.as_mut().unwrap();
-            commander.flip();
+            // Commanded tile is not flipped, but may be the Duke.
+            if commanded.kind == TileType::Duke {
+                *state.mut_own_duke_pos() = Some(data.target_pos);
+            }
+            delta.moved = Some(TileMove {
+                from: data.command_tile_pos,
+                to: data.target_pos,
+                kind: commanded.kind,
+                flipped: commanded.flipped,
+            });
+
+            // Flip commander. Its effects were cleared above, so effects of
+            // the new side are added below.
+            state.flip_tile(data.tile_pos);
+            delta.flipped.push(data.tile_pos);
 
             // Add effects
             add_tile_effects(state, data.tile_pos);
+            delta.added_effects(state, data.tile_pos);
             add_tile_effects(state, data.target_pos);
+            delta.added_effects(state, data.target_pos);
         }
         Action::Strike(data) => {
             assert!(state.square(data.tile_pos).tile.as_ref().unwrap().color == state.ply);
 
+            delta.clearing_effects(state, data.target_pos);
             clear_tile_effects(state, data.target_pos);
             let captured = state.square(data.target_pos).tile.unwrap();
             if captured.kind == TileType::Duke {
                 *state.mut_opponent_duke_pos() = None;
             }
-            state.graveyard.push(captured);
-            state.mut_square(data.target_pos).tile = None;
+            delta.captured = Some(capture(state, captured, data.target_pos));
+            state.set_tile(data.target_pos, None);
 
             // Flip
+            delta.clearing_effects(state, data.tile_pos);
             clear_tile_effects(state, data.tile_pos);
-            let tile = state.mut_square(data.tile_pos).tile.as_mut().unwrap();
-            tile.flip();
+            state.flip_tile(data.tile_pos);
+            delta.flipped.push(data.tile_pos);
 
             // Add effects
             add_tile_effects(state, data.tile_pos);
+            delta.added_effects(state, data.tile_pos);
         }
     }
 
     // Update ply
-    if state.ply == TileColor::Black {
-        state.ply = TileColor::White;
-    } else {
-        state.ply = TileColor::Black;
+    state.set_ply(state.ply.opponent());
+    delta.next_ply = Some(state.ply);
+    // The opponent had its turn to accept.
+    if state.draw_offer == Some(state.ply) {
+        state.draw_offer = None;
+    }
+    state.turn += 1;
+    state.ply_count += 1;
+    state.changed();
+
+    entry.captured = delta.captured.map(|captured| captured.tile);
+    state.history.push(entry);
+    state.redo.clear();
+
+    delta.net_effects();
+    debug_assert!(
+        effects_consistent(state),
+        "Effects out of sync after action."
+    );
+    delta
+}
+
+/// Reason why an action was rejected by `do_action`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ActionError {
+    /// Game is already over.
+    GameAlreadyOver,
+    /// A drawn tile has to be placed before anything else.
+    MustDeployDrawnTile,
+    /// Tried to place a tile, but no tile is drawn.
+    NoDrawnTile,
+    /// Tried to draw from an empty bag, or without a free spawn square.
+    CannotDraw,
+    /// No tile on action source square, or square is not on board.
+    NoTileAtSource,
+    /// Tile on source square belongs to the opponent.
+    NotYourTurn,
+    /// Tile can't do the action, e.g. the target is not reachable.
+    IllegalTarget,
+    /// Draws can only be offered in own turn.
+    DrawOfferOutOfTurn,
+    /// No draw offer of the opponent to respond to.
+    NoDrawOffer,
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match self {
+            ActionError::GameAlreadyOver => "game is already over",
+            ActionError::MustDeployDrawnTile => "drawn tile must be placed first",
+            ActionError::NoDrawnTile => "no tile is drawn",
+            ActionError::CannotDraw => "can't draw a tile",
+            ActionError::NoTileAtSource => "no tile at source square",
+            ActionError::NotYourTurn => "tile belongs to the opponent",
+            ActionError::IllegalTarget => "tile can't do that action",
+            ActionError::DrawOfferOutOfTurn => "draws can only be offered in own turn",
+            ActionError::NoDrawOffer => "no draw offer to respond to",
+        };
+        write!(f, "{}", msg)
     }
+}
 
-    /* let set_win = || {
-        if state.ply == TileColor::Black {
-            state.game_over = Some(Winner::Color(TileColor::White));
-        } else {
-            state.game_over = Some(Winner::Color(TileColor::Black));
-        }
-    };*/
+impl std::error::Error for ActionError {}
 
-    // Check if game over for new ply.
-    if state.own_duke_pos().is_none() {
-        let new_tile = state.drawn().last();
-        if !(new_tile.is_some() && new_tile.unwrap().kind == TileType::Duke) {
-            if state.ply == TileColor::Black {
-                state.game_over = Some(Winner::Color(TileColor::White));
-            } else {
-                state.game_over = Some(Winner::Color(TileColor::Black));
+/// Check that action is legal in state. See `do_action`.
+pub fn validate_action(state: &GameState, action: &Action) -> Result<(), ActionError> {
+    if state.game_over.is_some() {
+        return Err(ActionError::GameAlreadyOver);
+    }
+
+    let tile_pos = match action {
+        Action::PlaceNew(_) => {
+            if state.drawn().is_empty() {
+                return Err(ActionError::NoDrawnTile);
             }
+            None
         }
-    } else if get_actions(state).is_empty() {
-        // Cant implement this with closure because of mut borrow rules.
-        if state.ply == TileColor::Black {
-            state.game_over = Some(Winner::Color(TileColor::White));
-        } else {
-            state.game_over = Some(Winner::Color(TileColor::Black));
+        _ if !state.drawn().is_empty() => {
+            return Err(ActionError::MustDeployDrawnTile);
+        }
+        Action::NewFromBag => None,
+        Action::Move(data)
+        | Action::Jump(data)
+        | Action::JumpSlide(data)
+        | Action::Slide(data)
+        | Action::Strike(data) => Some(data.tile_pos),
+        Action::Command(data) => Some(data.tile_pos),
+    };
+
+    if let Some(tile_pos) = tile_pos {
+        if !state.size().contains(tile_pos) {
+            return Err(ActionError::NoTileAtSource);
+        }
+        match state.square(tile_pos).tile {
+            None => return Err(ActionError::NoTileAtSource),
+            Some(tile) if tile.color != state.ply => return Err(ActionError::NotYourTurn),
+            _ => {}
         }
     }
+
+    if get_actions(state).contains(action) {
+        return Ok(());
+    }
+
+    match action {
+        Action::NewFromBag => Err(ActionError::CannotDraw),
+        _ => Err(ActionError::IllegalTarget),
+    }
+}
+
+/// Safe counterpart of `do_unsafe_action`. Action is validated against the
+/// state before it is done. State is left unchanged if action is illegal.
+pub fn do_action(state: &mut GameState, action: &Action) -> Result<StateDelta, ActionError> {
+    validate_action(state, action)?;
+    Ok(do_unsafe_action(state, action))
+}
+
+/// Same as `do_action`, but returns what happened as events instead of a
+/// delta.
+pub fn apply_action_with_events(
+    state: &mut GameState,
+    action: &Action,
+) -> Result<Vec<GameEvent>, ActionError> {
+    do_action(state, action).map(|delta| delta.events())
+}
+
+/// Undo last action done with `do_unsafe_action` or `do_action`. The action
+/// can be done again with `redo_action`.
+pub fn undo_action(state: &mut GameState) -> Result<(), UndoError> {
+    let entry = state.history.pop().ok_or(UndoError::NothingToUndo)?;
+    state.redo.push((entry.action, entry.bag_index));
+
+    // Restore ply first, so own bag and drawn tiles are the ones of the actor.
+    state.set_ply(entry.ply);
+
+    // Move tile back from target to source, unflip it and put back any
+    // captured tile.
+    let unmove = |state: &mut GameState, from: Coordinate, to: Coordinate, unflip: bool| {
+        clear_tile_effects(state, from);
+        let mut tile = state.set_tile(from, None).unwrap();
+        if unflip {
+            tile.flip();
+        }
+        state.set_tile(to, Some(tile));
+        if let Some(captured) = entry.captured {
+            state.graveyard.pop();
+            state.set_tile(from, Some(captured));
+            add_tile_effects(state, from);
+        }
+        add_tile_effects(state, to);
+    };
+
+    match &entry.action {
+        Action::NewFromBag => {
+            let tile = state.pop_drawn();
+            state.put_in_bag(tile, entry.bag_index.unwrap());
+        }
+        Action::PlaceNew(cord) => {
+            clear_tile_effects(state, *cord);
+            let tile = state.set_tile(*cord, None).unwrap();
+            state.push_drawn(tile);
+        }
+        Action::Move(data) | Action::Jump(data) | Action::JumpSlide(data) | Action::Slide(data) => {
+            unmove(state, data.target_pos, data.tile_pos, true);
+        }
+        Action::Command(data) => {
+            unmove(state, data.target_pos, data.command_tile_pos, false);
+
+            clear_tile_effects(state, data.tile_pos);
+            state.flip_tile(data.tile_pos);
+            add_tile_effects(state, data.tile_pos);
+        }
+        Action::Strike(data) => {
+            state.graveyard.pop();
+            state.set_tile(data.target_pos, entry.captured);
+            add_tile_effects(state, data.target_pos);
+
+            clear_tile_effects(state, data.tile_pos);
+            state.flip_tile(data.tile_pos);
+            add_tile_effects(state, data.tile_pos);
+        }
+    }
+
+    if entry.action != Action::NewFromBag {
+        state.turn -= 1;
+    }
+    state.ply_count -= 1;
+    state.changed();
+    state.dukes = entry.dukes;
+    state.game_over = entry.game_over;
+    state.win_reason = entry.win_reason;
+    state.rng = entry.rng;
+    state.quiet_turns = entry.quiet_turns;
+    state.positions.truncate(entry.positions_len);
+    state.draw_offer = entry.draw_offer;
+
+    debug_assert!(effects_consistent(state), "Effects out of sync after undo.");
+    Ok(())
+}
+
+/// Do the last undone action again. Redone `NewFromBag` draws the same tile
+/// as before the undo. Any other action, or the end of the game, clears the
+/// actions to redo.
+///
+/// ```
+/// use rusty_duke_logic::logic::{
+///     do_action, get_actions, redo_action, undo_action, Action, GameState, UndoError,
+/// };
+///
+/// let mut state = GameState::new_seeded(1);
+/// while !get_actions(&state).contains(&Action::NewFromBag) {
+///     let action = get_actions(&state)[0];
+///     do_action(&mut state, &action).unwrap();
+/// }
+/// do_action(&mut state, &Action::NewFromBag).unwrap();
+/// let drawn = *state.drawn().last().unwrap();
+/// let hash = state.hash();
+///
+/// undo_action(&mut state).unwrap();
+/// undo_action(&mut state).unwrap();
+/// assert!(state.can_redo());
+/// redo_action(&mut state).unwrap();
+/// let delta = redo_action(&mut state).unwrap();
+/// assert_eq!(delta.drawn, Some(drawn));
+/// assert_eq!(state.hash(), hash);
+///
+/// // A new action replaces the undone ones.
+/// undo_action(&mut state).unwrap();
+/// let action = get_actions(&state)[0];
+/// do_action(&mut state, &action).unwrap();
+/// assert_eq!(redo_action(&mut state), Err(UndoError::NothingToRedo));
+/// ```
+pub fn redo_action(state: &mut GameState) -> Result<StateDelta, UndoError> {
+    let (action, bag_index) = state.redo.pop().ok_or(UndoError::NothingToRedo)?;
+
+    // Doing the action clears the actions to redo, keep the rest.
+    let redo = std::mem::take(&mut state.redo);
+    let delta = match bag_index {
+        Some(index) => {
+            draw_from_bag(state, index);
+            StateDelta {
+                drawn: state.drawn().last().copied(),
+                ..Default::default()
+            }
+        }
+        None => do_unsafe_action(state, &action),
+    };
+    state.redo = redo;
+    Ok(delta)
 }
 
 /// Same as `do_unsafe_action` but returns copy of new state. For recursive AI search.
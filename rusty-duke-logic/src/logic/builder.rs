@@ -0,0 +1,212 @@
+//! Game states from arbitrary positions, for puzzles and variants.
+//!
+//! Black to go and guardmate in one: the Footman moves two forward to a5 and
+//! flips to its front side, so it attacks the White Duke. The only escape is
+//! capturing the Pikeman, which the Black Duke guards.
+//!
+//! ```
+//! use rusty_duke_logic::ai::{alpha_beta::Agent, DukeAgent};
+//! use rusty_duke_logic::logic::builder::GameStateBuilder;
+//! use rusty_duke_logic::logic::{do_action, Coordinate, TileColor, TileType, WinReason};
+//!
+//! let mut state = GameStateBuilder::new()
+//!     .tile_at(Coordinate::new(0, 5), TileType::Duke, TileColor::White, false)
+//!     .tile_at(Coordinate::new(1, 0), TileType::Duke, TileColor::Black, true)
+//!     .tile_at(Coordinate::new(1, 5), TileType::Pikeman, TileColor::Black, false)
+//!     .tile_at(Coordinate::new(0, 2), TileType::Footman, TileColor::Black, true)
+//!     .ply(TileColor::Black)
+//!     .build()
+//!     .unwrap();
+//!
+//! let mut agent = Agent::new(TileColor::Black, Some(2), None);
+//! let action = agent.choose_action(&state).unwrap();
+//! do_action(&mut state, &action).unwrap();
+//! assert_eq!(state.win_reason, Some(WinReason::Guardmate));
+//! ```
+
+use super::rng::GameRng;
+use super::variant::GameRules;
+use super::{
+    add_tile_effects, Board, BoardSize, CapturedTile, Coordinate, GameState, Tile, TileColor,
+    TileType,
+};
+use std::fmt;
+
+/// Reason why `GameStateBuilder::build` failed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetupError {
+    /// Square is not on the board.
+    IllegalSquare(Coordinate),
+    /// More than one tile on square.
+    DuplicateSquare(Coordinate),
+    /// More than one Duke of a color on the board or drawn.
+    DuplicateDuke(TileColor),
+    /// Color has no Duke on the board, and no Duke to place next.
+    MissingDuke(TileColor),
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SetupError::IllegalSquare(c) => write!(f, "({}, {}) is not on board", c.x, c.y),
+            SetupError::DuplicateSquare(c) => write!(f, "{} already has a tile", c),
+            SetupError::DuplicateDuke(c) => write!(f, "more than one {:?} Duke", c),
+            SetupError::MissingDuke(c) => write!(f, "no {:?} Duke", c),
+        }
+    }
+}
+
+impl std::error::Error for SetupError {}
+
+/// Builds a game state from tiles, bags, drawn tiles and graveyard. Starts from an
+/// empty board with empty bags, Black to go. Duke positions and square
+/// effects are calculated by `build`.
+#[derive(Debug, Clone, Default)]
+pub struct GameStateBuilder {
+    tiles: Vec<(Coordinate, Tile)>,
+    bags: [Vec<Tile>; 2],
+    drawn_tiles: [Vec<Tile>; 2],
+    graveyard: Vec<CapturedTile>,
+    ply: Option<TileColor>,
+    turn: u16,
+    seed: Option<u64>,
+    size: BoardSize,
+}
+
+impl GameStateBuilder {
+    pub fn new() -> GameStateBuilder {
+        GameStateBuilder::default()
+    }
+
+    /// Put tile on square.
+    pub fn tile_at(
+        mut self,
+        cord: Coordinate,
+        kind: TileType,
+        color: TileColor,
+        flipped: bool,
+    ) -> GameStateBuilder {
+        self.tiles.push((
+            cord,
+            Tile {
+                kind,
+                flipped,
+                color,
+            },
+        ));
+        self
+    }
+
+    /// Replace bag of color.
+    pub fn bag(mut self, color: TileColor, kinds: Vec<TileType>) -> GameStateBuilder {
+        self.bags[color as usize] = GameState::init_tiles(&kinds, color);
+        self
+    }
+
+    /// Add drawn tile for color. The last drawn tile is placed first, like
+    /// after `NewFromBag`.
+    pub fn drawn(mut self, color: TileColor, kind: TileType) -> GameStateBuilder {
+        self.drawn_tiles[color as usize].push(Tile::new(kind, color));
+        self
+    }
+
+    /// Add captured tile to graveyard, see `CapturedTile::from_tile`.
+    pub fn graveyard(
+        mut self,
+        kind: TileType,
+        color: TileColor,
+        flipped: bool,
+    ) -> GameStateBuilder {
+        self.graveyard.push(CapturedTile::from_tile(Tile {
+            kind,
+            flipped,
+            color,
+        }));
+        self
+    }
+
+    /// Player to go.
+    pub fn ply(mut self, color: TileColor) -> GameStateBuilder {
+        self.ply = Some(color);
+        self
+    }
+
+    /// Turns done so far, 0 if not set.
+    pub fn turn(mut self, turn: u16) -> GameStateBuilder {
+        self.turn = turn;
+        self
+    }
+
+    /// Size of the board, standard if not set.
+    pub fn size(mut self, size: BoardSize) -> GameStateBuilder {
+        self.size = size;
+        self
+    }
+
+    /// Seed of the random generator that draws from bags. Seeded from
+    /// entropy if not set.
+    pub fn seed(mut self, seed: u64) -> GameStateBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Game state of the position, with standard rules on a board of the
+    /// size and no history.
+    pub fn build(self) -> Result<GameState, SetupError> {
+        let rng = match self.seed {
+            Some(seed) => GameRng::new(seed),
+            None => GameRng::from_entropy(),
+        };
+        let rules = GameRules {
+            size: self.size,
+            ..GameRules::standard()
+        };
+        let mut state = GameState::new_with(&rules, rng);
+        state.board = Board::new(self.size);
+        state.bags = self.bags;
+        state.drawn_tiles = self.drawn_tiles;
+        state.graveyard = self.graveyard;
+        state.ply = self.ply.unwrap_or(TileColor::Black);
+        state.turn = self.turn;
+
+        for (cord, tile) in self.tiles.iter() {
+            if !self.size.contains(*cord) {
+                return Err(SetupError::IllegalSquare(*cord));
+            }
+            if state.square(*cord).tile.is_some() {
+                return Err(SetupError::DuplicateSquare(*cord));
+            }
+
+            if tile.kind == TileType::Duke {
+                let duke = &mut state.dukes[tile.color as usize];
+                if duke.is_some() {
+                    return Err(SetupError::DuplicateDuke(tile.color));
+                }
+                *duke = Some(*cord);
+            }
+            state.mut_square(*cord).tile = Some(*tile);
+        }
+
+        // A Duke not on the board must be the next tile to place, see
+        // `get_spawn_squares`.
+        for color in [TileColor::Black, TileColor::White] {
+            let drawn = &state.drawn_tiles[color as usize];
+            let drawn_dukes = drawn.iter().filter(|t| t.kind == TileType::Duke).count();
+            let on_board = state.dukes[color as usize].is_some() as usize;
+            if drawn_dukes + on_board > 1 {
+                return Err(SetupError::DuplicateDuke(color));
+            }
+            if on_board == 0 && drawn.last().map(|t| t.kind) != Some(TileType::Duke) {
+                return Err(SetupError::MissingDuke(color));
+            }
+        }
+
+        // Effects are added when all tiles are on the board.
+        for (cord, _) in self.tiles.iter() {
+            add_tile_effects(&mut state, *cord);
+        }
+        state.rehash();
+
+        Ok(state)
+    }
+}
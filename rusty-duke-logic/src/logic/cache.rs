@@ -0,0 +1,109 @@
+//! Legal actions cached between changes of a game state.
+//!
+//! Front-ends ask for the legal actions every frame, while the state changes
+//! only a few times a minute. `CachedGameState` owns a state and generates its
+//! actions again only when `GameState::version` has changed, i.e. after an
+//! action, undo, draw or end of game.
+//!
+//! ```
+//! use rusty_duke_logic::logic::cache::CachedGameState;
+//! use rusty_duke_logic::logic::{do_action, undo_action, Action, GameState};
+//!
+//! let mut game = CachedGameState::new(GameState::new_seeded(1));
+//! let first = game.actions().to_vec();
+//! let pointer = game.actions().as_ptr();
+//! assert_eq!(game.actions().as_ptr(), pointer);
+//! assert_eq!(game.generated(), 1);
+//!
+//! do_action(&mut game, &first[0]).unwrap();
+//! assert_ne!(game.actions(), &first[..]);
+//! assert_eq!(game.generated(), 2);
+//!
+//! // Never stale after undo.
+//! undo_action(&mut game).unwrap();
+//! assert_eq!(game.actions(), &first[..]);
+//! assert_eq!(game.generated(), 3);
+//!
+//! // Drawing a tile leaves only placements.
+//! while !game.actions().contains(&Action::NewFromBag) {
+//!     let action = game.actions()[0];
+//!     do_action(&mut game, &action).unwrap();
+//! }
+//! do_action(&mut game, &Action::NewFromBag).unwrap();
+//! assert!(game.actions().iter().all(|a| matches!(a, Action::PlaceNew(_))));
+//! ```
+
+use super::{get_legal_actions, Action, GameState};
+use std::ops::{Deref, DerefMut};
+
+/// Game state with cached legal actions. Derefs to the state, so it can be
+/// passed to `do_action` and the other functions of `logic`.
+///
+/// Direct changes to public fields of the state, or replacing the whole
+/// state, don't change `GameState::version`. Call `invalidate` after them.
+#[derive(Debug, Clone)]
+pub struct CachedGameState {
+    state: GameState,
+    /// Actions, and the version of the state they were generated for.
+    actions: Option<(u64, Vec<Action>)>,
+    generated: u64,
+}
+
+impl CachedGameState {
+    pub fn new(state: GameState) -> CachedGameState {
+        CachedGameState {
+            state,
+            actions: None,
+            generated: 0,
+        }
+    }
+
+    /// Legal actions, same as `get_legal_actions`. Only generated if the state
+    /// changed since the last call.
+    pub fn actions(&mut self) -> &[Action] {
+        let version = self.state.version();
+        if !matches!(&self.actions, Some((v, _)) if *v == version) {
+            self.actions = Some((version, get_legal_actions(&self.state)));
+            self.generated += 1;
+        }
+        &self
+            .actions
+            .as_ref()
+            .expect("Actions were just generated.")
+            .1
+    }
+
+    /// Forget cached actions.
+    pub fn invalidate(&mut self) {
+        self.actions = None;
+    }
+
+    /// How many times actions were generated.
+    pub fn generated(&self) -> u64 {
+        self.generated
+    }
+
+    pub fn into_inner(self) -> GameState {
+        self.state
+    }
+}
+
+impl From<GameState> for CachedGameState {
+    fn from(state: GameState) -> CachedGameState {
+        CachedGameState::new(state)
+    }
+}
+
+impl Deref for CachedGameState {
+    type Target = GameState;
+
+    fn deref(&self) -> &GameState {
+        &self.state
+    }
+}
+
+impl DerefMut for CachedGameState {
+    fn deref_mut(&mut self) -> &mut GameState {
+        &mut self.state
+    }
+}
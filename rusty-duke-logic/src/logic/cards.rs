@@ -0,0 +1,400 @@
+//! Movement grids of the printed tile cards, and a check that `TILE_ACTIONS`
+//! matches them.
+//!
+//! Each card is two 5x5 grids, front and back side by side, as seen by Black:
+//! the top row is forward, two squares ahead. Symbols:
+//!
+//! ```text
+//! T  the tile itself     M  Move        J  Jump        S  Slide
+//! L  JumpSlide           X  Strike      C  Command     B  Move and Command
+//! .  nothing
+//! ```
+//!
+//! Effects are not drawn, see `TILE_EFFECTS`.
+//!
+//! When adding or changing a tile, draw it here from the printed card first.
+//! `check` reports every definition that differs from its card, it is run by
+//! `rusty-duke-terminal --validate-tiles` and by the tests of the crate.
+
+use super::validate::Side;
+use super::{ActionType, AvailableAction, AvailableActions, TileType};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Width and height of a card grid.
+const GRID: usize = 5;
+
+/// Movement grids of a printed tile card.
+pub struct Card {
+    pub kind: TileType,
+    /// Front grid, space, back grid. Top row first.
+    pub rows: [&'static str; GRID],
+}
+
+/// Cards of all tile types, in tile id order.
+pub const CARDS: &[Card] = &[
+    Card {
+        kind: TileType::Duke,
+        rows: [
+            "..... .....",
+            "..... ..S..",
+            ".STS. ..T..",
+            "..... ..S..",
+            "..... .....",
+        ],
+    },
+    Card {
+        kind: TileType::Footman,
+        rows: [
+            "..... ..M..",
+            "..M.. .M.M.",
+            ".MTM. ..T..",
+            "..M.. .M.M.",
+            "..... .....",
+        ],
+    },
+    Card {
+        kind: TileType::Pikeman,
+        rows: [
+            "M...M .X.X.",
+            ".M.M. ..M..",
+            "..T.. ..T..",
+            "..... ..M..",
+            "..... ..M..",
+        ],
+    },
+    Card {
+        kind: TileType::Knight,
+        rows: [
+            ".J.J. .....",
+            "..... ..S..",
+            ".MTM. ..T..",
+            "..M.. .M.M.",
+            "..M.. M...M",
+        ],
+    },
+    Card {
+        kind: TileType::Bowman,
+        rows: [
+            "..... ..X..",
+            "..M.. .XMX.",
+            "JMTMJ ..T..",
+            "..... .M.M.",
+            "..J.. .....",
+        ],
+    },
+    Card {
+        kind: TileType::LightHorse,
+        rows: [
+            "..... .X.X.",
+            "..S.. J...J",
+            "..T.. ..T..",
+            ".M.M. .....",
+            "..... .....",
+        ],
+    },
+    Card {
+        kind: TileType::Wizard,
+        rows: [
+            "..... J.J.J",
+            ".MMM. .....",
+            ".MTM. J.T.J",
+            ".MMM. .....",
+            "..... J.J.J",
+        ],
+    },
+    Card {
+        kind: TileType::Seer,
+        rows: [
+            "..J.. J...J",
+            ".M.M. ..M..",
+            "J.T.J .MTM.",
+            ".M.M. ..M..",
+            "..J.. J...J",
+        ],
+    },
+    Card {
+        kind: TileType::Champion,
+        rows: [
+            "..J.. ..J..",
+            "..M.. ..X..",
+            "JMTMJ JXTXJ",
+            "..M.. ..X..",
+            "..J.. ..J..",
+        ],
+    },
+    Card {
+        kind: TileType::Arbalist,
+        rows: [
+            "..M.. ..X..",
+            "..... ..X..",
+            ".MTM. ..T..",
+            ".M.M. ..M..",
+            "..... .J.J.",
+        ],
+    },
+    Card {
+        kind: TileType::General,
+        rows: [
+            ".J.J. .J.J.",
+            "..M.. ..M..",
+            "M.T.M MBTBM",
+            "..M.. .CCC.",
+            "..... .....",
+        ],
+    },
+    Card {
+        kind: TileType::Marshall,
+        rows: [
+            "J...J .....",
+            "..... .BBB.",
+            ".STS. MMTMM",
+            "..... .M.M.",
+            "..J.. .....",
+        ],
+    },
+    Card {
+        kind: TileType::Countess,
+        rows: [
+            "..... .....",
+            "..M.. ..M..",
+            "B.T.B .BTB.",
+            "..M.. .....",
+            "..... ..M..",
+        ],
+    },
+    Card {
+        kind: TileType::Ranger,
+        rows: [
+            ".J.J. .....",
+            "J.S.J .S.S.",
+            "..T.. ..T..",
+            "..... .....",
+            "..... .J.J.",
+        ],
+    },
+    Card {
+        kind: TileType::Sage,
+        rows: [
+            "..... ..M..",
+            "..M.. .....",
+            "..T.. M.T.M",
+            ".M.M. ..M..",
+            "..... .....",
+        ],
+    },
+    Card {
+        kind: TileType::RoyalAssassin,
+        rows: [
+            "..... .....",
+            "..... ..S..",
+            "..T.. .STS.",
+            "..M.. ..S..",
+            "..... .....",
+        ],
+    },
+    Card {
+        kind: TileType::Arthur,
+        rows: [
+            "..... .....",
+            ".CBC. .SMS.",
+            ".BTB. ..T..",
+            "..M.. .CBC.",
+            "..... .....",
+        ],
+    },
+    Card {
+        kind: TileType::Guinevere,
+        rows: [
+            "..J.. .....",
+            "..... .M.M.",
+            ".STS. ..T..",
+            "..... .M.M.",
+            "..... ..J..",
+        ],
+    },
+    Card {
+        kind: TileType::Lancelot,
+        rows: [
+            ".XLX. .....",
+            "..M.. .S.S.",
+            "..T.. J.T.J",
+            "..... .....",
+            "..... .....",
+        ],
+    },
+    Card {
+        kind: TileType::Perceval,
+        rows: [
+            "..J.. .....",
+            ".M.M. ..S..",
+            "..T.. ..T..",
+            "..... .M.M.",
+            "..... .....",
+        ],
+    },
+    Card {
+        kind: TileType::Merlin,
+        rows: [
+            "J...J ..J..",
+            "..M.. .....",
+            "..T.. J.T.J",
+            "..... .....",
+            "J...J ..J..",
+        ],
+    },
+    Card {
+        kind: TileType::Camelot,
+        rows: [
+            "..... .....",
+            "..C.. ..M..",
+            ".CTC. .MTM.",
+            "..C.. ..M..",
+            "..... .....",
+        ],
+    },
+    Card {
+        kind: TileType::Morgana,
+        rows: [
+            "..... ..X..",
+            ".M.M. .....",
+            "..T.. .MTM.",
+            "..... .....",
+            "..J.. .....",
+        ],
+    },
+    Card {
+        kind: TileType::Mordred,
+        rows: [
+            "..X.. .....",
+            "..... .XSX.",
+            ".MTM. ..T..",
+            "..... .....",
+            "..... .....",
+        ],
+    },
+];
+
+/// Difference between a card and the tile definitions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CardMismatch {
+    /// Tile type is defined, but has no card.
+    MissingCard(TileType),
+    /// Square of card differs from definition. Has symbols of card and
+    /// definition.
+    Square {
+        kind: TileType,
+        side: Side,
+        x: i8,
+        y: i8,
+        card: char,
+        defined: char,
+    },
+}
+
+impl fmt::Display for CardMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CardMismatch::MissingCard(kind) => write!(f, "{}: no card", kind),
+            CardMismatch::Square {
+                kind,
+                side,
+                x,
+                y,
+                card,
+                defined,
+            } => write!(
+                f,
+                "{}, {:?}, offset ({}, {}): card '{}', defined '{}'",
+                kind, side, x, y, card, defined
+            ),
+        }
+    }
+}
+
+/// Symbol of the action kinds on one square. `?` if no symbol exists for the
+/// combination, it never matches a card.
+fn symbol(kinds: &[ActionType]) -> char {
+    let has = |kind: ActionType| kinds.contains(&kind);
+    match kinds.len() {
+        0 => '.',
+        1 if has(ActionType::Move) => 'M',
+        1 if has(ActionType::Jump) => 'J',
+        1 if has(ActionType::Slide) => 'S',
+        1 if has(ActionType::JumpSlide) => 'L',
+        1 if has(ActionType::Strike) => 'X',
+        1 if has(ActionType::Command) => 'C',
+        2 if has(ActionType::Move) && has(ActionType::Command) => 'B',
+        _ => '?',
+    }
+}
+
+/// Grid of one side of a tile definition, in the same format as a card.
+/// Actions out of grid are drawn as `?` on the tile square, so they never
+/// match.
+pub fn draw(actions: &[AvailableAction]) -> [String; GRID] {
+    let half = (GRID / 2) as i8;
+    let mut squares: Vec<Vec<Vec<ActionType>>> = vec![vec![Vec::new(); GRID]; GRID];
+    let mut out_of_grid = false;
+    for action in actions {
+        let (x, y) = (action.offset.x, action.offset.y);
+        if x.abs() > half || y.abs() > half || (x == 0 && y == 0) {
+            out_of_grid = true;
+            continue;
+        }
+        squares[(half - y) as usize][(x + half) as usize].push(action.kind.clone());
+    }
+
+    let mut rows: [String; GRID] = Default::default();
+    for (row, squares) in rows.iter_mut().zip(squares.iter()) {
+        *row = squares.iter().map(|kinds| symbol(kinds)).collect();
+    }
+    let center = if out_of_grid { "?" } else { "T" };
+    rows[half as usize].replace_range(half as usize..half as usize + 1, center);
+    rows
+}
+
+/// Compare definitions with `CARDS`. Returns every difference, and every
+/// defined tile type without a card.
+pub fn check(actions: &HashMap<TileType, AvailableActions>) -> Vec<CardMismatch> {
+    let mut mismatches = Vec::new();
+    let half = (GRID / 2) as i8;
+
+    let mut kinds: Vec<TileType> = actions.keys().copied().collect();
+    kinds.sort_by_key(|kind| *kind as usize);
+
+    for kind in kinds {
+        let card = match CARDS.iter().find(|card| card.kind == kind) {
+            Some(card) => card,
+            None => {
+                mismatches.push(CardMismatch::MissingCard(kind));
+                continue;
+            }
+        };
+
+        let sides = [
+            (Side::Front, &actions[&kind].front, 0),
+            (Side::Back, &actions[&kind].back, GRID + 1),
+        ];
+        for (side, side_actions, start) in sides {
+            let defined = draw(side_actions);
+            for (row, (card_row, defined_row)) in card.rows.iter().zip(defined.iter()).enumerate() {
+                let card_squares = card_row.chars().skip(start).take(GRID);
+                for (column, (card, defined)) in card_squares.zip(defined_row.chars()).enumerate() {
+                    if card != defined {
+                        mismatches.push(CardMismatch::Square {
+                            kind,
+                            side,
+                            x: column as i8 - half,
+                            y: half - row as i8,
+                            card,
+                            defined,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    mismatches
+}
@@ -0,0 +1,117 @@
+//! Chess clock for timed games.
+//!
+//! Each color has a remaining time, which runs during its turns and grows by
+//! the increment after each of them. The clock never reads the time itself,
+//! callers pass in `now`. UIs pass `Instant::now()`, tests and replays can
+//! pass made up instants and need no sleeping.
+//!
+//! ```
+//! use rusty_duke_logic::logic::clock::GameClock;
+//! use rusty_duke_logic::logic::TileColor;
+//! use std::time::{Duration, Instant};
+//!
+//! let start = Instant::now();
+//! let mut clock = GameClock::new(Duration::from_secs(60), Duration::from_secs(2));
+//!
+//! clock.start_turn(TileColor::Black, start);
+//! clock.end_turn(TileColor::Black, start + Duration::from_secs(10));
+//! assert_eq!(clock.remaining(TileColor::Black, start), Duration::from_secs(52));
+//!
+//! let white_start = start + Duration::from_secs(10);
+//! clock.start_turn(TileColor::White, white_start);
+//! assert_eq!(clock.flagged(white_start + Duration::from_secs(59)), None);
+//! assert_eq!(
+//!     clock.flagged(white_start + Duration::from_secs(60)),
+//!     Some(TileColor::White)
+//! );
+//! ```
+
+use super::TileColor;
+use crate::time::{Duration, Instant};
+
+/// Remaining time of both colors, with increment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameClock {
+    remaining: [Duration; 2],
+    increment: Duration,
+    /// Color whose clock runs, and when its turn started.
+    running: Option<(TileColor, Instant)>,
+}
+
+impl GameClock {
+    /// Clock with initial time for both colors, and increment added after
+    /// each turn.
+    pub fn new(initial: Duration, increment: Duration) -> GameClock {
+        GameClock {
+            remaining: [initial; 2],
+            increment,
+            running: None,
+        }
+    }
+
+    pub fn increment(&self) -> Duration {
+        self.increment
+    }
+
+    /// Color whose clock runs, if any.
+    pub fn running(&self) -> Option<TileColor> {
+        self.running.map(|(color, _)| color)
+    }
+
+    /// Start clock of color. A clock that already runs is stopped first,
+    /// see `end_turn`.
+    pub fn start_turn(&mut self, color: TileColor, now: Instant) {
+        if let Some((running, _)) = self.running {
+            self.end_turn(running, now);
+        }
+        self.running = Some((color, now));
+    }
+
+    /// Stop clock of color, and charge the time of the turn to it. The
+    /// increment is added unless the time ran out. Nothing happens if the
+    /// clock of color does not run.
+    pub fn end_turn(&mut self, color: TileColor, now: Instant) {
+        if self.running() != Some(color) {
+            return;
+        }
+        let remaining = self.remaining(color, now);
+        self.remaining[color as usize] = if remaining.is_zero() {
+            remaining
+        } else {
+            remaining + self.increment
+        };
+        self.running = None;
+    }
+
+    /// Time left of color at now, zero if it ran out.
+    pub fn remaining(&self, color: TileColor, now: Instant) -> Duration {
+        let remaining = self.remaining[color as usize];
+        match self.running {
+            Some((running, start)) if running == color => {
+                remaining.saturating_sub(now.saturating_duration_since(start))
+            }
+            _ => remaining,
+        }
+    }
+
+    /// Color whose time ran out at now, if any.
+    pub fn flagged(&self, now: Instant) -> Option<TileColor> {
+        [TileColor::Black, TileColor::White]
+            .into_iter()
+            .find(|color| self.remaining(*color, now).is_zero())
+    }
+}
+
+/// Time as "mm:ss", seconds rounded down. Minutes don't wrap at an hour.
+///
+/// ```
+/// use rusty_duke_logic::logic::clock::format_time;
+/// use std::time::Duration;
+///
+/// assert_eq!(format_time(Duration::from_millis(65_900)), "01:05");
+/// assert_eq!(format_time(Duration::from_secs(90 * 60)), "90:00");
+/// ```
+pub fn format_time(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
@@ -0,0 +1,135 @@
+//! Compact copy of the board.
+//!
+//! `GameState` also owns the history of the game, so copying it still
+//! allocates. `FastBoard` holds only the tiles and effects of the board in
+//! flat arrays of `Copy` values, and is `Copy` itself.
+//!
+//! Actions are not done on `FastBoard`. Move generation, game over and
+//! evaluation work on `GameState`, so alpha beta search makes and unmakes
+//! actions there with `do_unsafe_action` and `undo_action`. It keeps
+//! `FastBoard` copies to compare positions, e.g. to skip mirrored placements.
+//!
+//! Bags, graveyard and random generator are not part of `FastBoard`.
+
+use super::{
+    mirror_symmetric, square_index, BoardSize, Coordinate, EffectSet, GameState, Tile, TileColor,
+    SQUARES,
+};
+
+/// Most drawn tiles a player can have. Only at game start there are more than
+/// one.
+pub const MAX_DRAWN: usize = 3;
+
+/// Board, drawn tiles, Duke positions and ply of a game, without heap memory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FastBoard {
+    /// Tiles on board, indexed by `square_index`.
+    pub tiles: [Option<Tile>; SQUARES],
+    pub effects: [EffectSet; SQUARES],
+    /// Drawn tiles of each player, first `drawn_len` are used.
+    drawn: [[Option<Tile>; MAX_DRAWN]; 2],
+    drawn_len: [u8; 2],
+    dukes: [Option<Coordinate>; 2],
+    pub ply: TileColor,
+    size: BoardSize,
+}
+
+impl FastBoard {
+    /// Copy of the board part of state.
+    ///
+    /// Panics if a player has more than `MAX_DRAWN` drawn tiles.
+    pub fn from_state(state: &GameState) -> FastBoard {
+        let mut board = FastBoard {
+            tiles: [None; SQUARES],
+            effects: [EffectSet::default(); SQUARES],
+            drawn: [[None; MAX_DRAWN]; 2],
+            drawn_len: [0; 2],
+            dukes: state.dukes,
+            ply: state.ply,
+            size: state.size(),
+        };
+
+        for cord in state.size().squares() {
+            let square = state.square(cord);
+            let i = square_index(cord);
+            board.tiles[i] = square.tile;
+            board.effects[i] = square.effects;
+        }
+
+        for (color, drawn) in state.drawn_tiles.iter().enumerate() {
+            assert!(drawn.len() <= MAX_DRAWN, "Too many drawn tiles.");
+            for (i, tile) in drawn.iter().enumerate() {
+                board.drawn[color][i] = Some(*tile);
+            }
+            board.drawn_len[color] = drawn.len() as u8;
+        }
+
+        board
+    }
+
+    /// Write board, drawn tiles, Duke positions and ply to state. Bags,
+    /// graveyard, game over and history of state are left as they are.
+    pub fn write_to(&self, state: &mut GameState) {
+        for cord in self.size.squares() {
+            let i = square_index(cord);
+            let square = state.mut_square(cord);
+            square.tile = self.tiles[i];
+            square.effects = self.effects[i];
+        }
+
+        for color in [TileColor::Black, TileColor::White] {
+            let drawn = &mut state.drawn_tiles[color as usize];
+            drawn.clear();
+            drawn.extend(self.drawn(color).iter().map(|t| t.unwrap()));
+        }
+
+        state.dukes = self.dukes;
+        state.ply = self.ply;
+        state.rehash();
+    }
+
+    pub fn tile(&self, cord: Coordinate) -> Option<Tile> {
+        self.tiles[square_index(cord)]
+    }
+
+    pub fn effects(&self, cord: Coordinate) -> EffectSet {
+        self.effects[square_index(cord)]
+    }
+
+    pub fn duke_pos(&self, color: TileColor) -> Option<Coordinate> {
+        self.dukes[color as usize]
+    }
+
+    /// Drawn tiles of player, last is placed first.
+    pub fn drawn(&self, color: TileColor) -> &[Option<Tile>] {
+        &self.drawn[color as usize][..self.drawn_len[color as usize] as usize]
+    }
+
+    /// True if board is the exact left-right mirror of other board. See
+    /// `GameState::is_mirror_of`.
+    pub fn is_mirror_of(&self, other: &FastBoard) -> bool {
+        if self.size != other.size
+            || self.ply != other.ply
+            || self.drawn != other.drawn
+            || self.drawn_len != other.drawn_len
+        {
+            return false;
+        }
+
+        for cord in self.size.squares() {
+            let mirrored = self.size.mirror(cord);
+
+            let tile = self.tile(cord);
+            if tile != other.tile(mirrored) || self.effects(cord) != other.effects(mirrored) {
+                return false;
+            }
+            if let Some(tile) = tile {
+                if !mirror_symmetric(tile.kind) {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+}
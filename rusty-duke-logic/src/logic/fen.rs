@@ -0,0 +1,414 @@
+//! Position as one line of text, like FEN in chess. For bug reports and test
+//! positions.
+//!
+//! Fields are separated by single spaces:
+//!
+//! | Field     | Example        | Contents                                   |
+//! |-----------|----------------|--------------------------------------------|
+//! | Board     | `6/6/6/6/6/6`  | Rows from the last rank to rank 1          |
+//! | Ply       | `b`            | `b` or `w`, player to go                   |
+//! | Bags      | `pkb/PKB`      | Black bag, then White bag                  |
+//! | Drawn     | `ffd/FFD`      | Black drawn tiles, then White drawn tiles  |
+//! | Graveyard | `-`            | Captured tiles of both colors              |
+//! | Turn      | `0`            | Turns done, optional                       |
+//!
+//! Tiles are letters, upper case for White and lower case for Black, see
+//! `tile_letter`. A `'` after the letter means the tile shows its back side.
+//! In board rows, digits count empty squares. The number of rows and their
+//! length give the board size, see `BoardSize`. Empty lists are written `-`.
+//! Tiles are listed in the order of the state, the last drawn tile is placed
+//! first.
+//!
+//! Rules, history, the random generator and where and when graveyard tiles
+//! were captured are not part of the position.
+//! Parsed states have standard rules and a generator seeded from entropy.
+//! Positions where a Duke was captured can't be parsed, the game is over.
+//!
+//! ```
+//! use rusty_duke_logic::logic::{do_action, get_actions, GameState};
+//! use rand::seq::SliceRandom;
+//! use rand::SeedableRng;
+//!
+//! let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+//! for seed in 0..20 {
+//!     let mut state = GameState::new_seeded(seed);
+//!     while state.game_over.is_none() && state.turn < 60 {
+//!         let fen = state.to_fen();
+//!         let parsed = GameState::from_fen(&fen).unwrap();
+//!
+//!         assert_eq!(parsed.to_fen(), fen);
+//!         assert_eq!(parsed.hash(), state.hash());
+//!         assert_eq!(parsed.graveyard_tiles(), state.graveyard_tiles());
+//!         assert_eq!(parsed.bags, state.bags);
+//!         assert_eq!(get_actions(&parsed), get_actions(&state));
+//!
+//!         let action = *get_actions(&state).choose(&mut rng).unwrap();
+//!         do_action(&mut state, &action).unwrap();
+//!     }
+//! }
+//!
+//! assert!(GameState::from_fen("6/6/6/6/6/6 b -/- -/- -").is_err());
+//! ```
+
+use super::builder::{GameStateBuilder, SetupError};
+use super::{BoardSize, Coordinate, GameState, Tile, TileColor, TileType, MAX_HEIGHT, MAX_WIDTH};
+use std::fmt;
+
+/// Letter of each tile type, upper case. Letters are stable, never reuse one.
+const TILE_LETTERS: [(TileType, char); 24] = [
+    (TileType::Duke, 'D'),
+    (TileType::Footman, 'F'),
+    (TileType::Pikeman, 'P'),
+    (TileType::Knight, 'K'),
+    (TileType::Bowman, 'B'),
+    (TileType::LightHorse, 'L'),
+    (TileType::Wizard, 'W'),
+    (TileType::Seer, 'S'),
+    (TileType::Champion, 'C'),
+    (TileType::Arbalist, 'A'),
+    (TileType::General, 'G'),
+    (TileType::Marshall, 'M'),
+    (TileType::Countess, 'N'),
+    (TileType::Ranger, 'R'),
+    (TileType::Sage, 'E'),
+    (TileType::RoyalAssassin, 'Y'),
+    (TileType::Arthur, 'T'),
+    (TileType::Guinevere, 'Q'),
+    (TileType::Lancelot, 'X'),
+    (TileType::Perceval, 'V'),
+    (TileType::Merlin, 'J'),
+    (TileType::Camelot, 'H'),
+    (TileType::Morgana, 'O'),
+    (TileType::Mordred, 'Z'),
+];
+
+const FLIPPED: char = '\'';
+const EMPTY: &str = "-";
+
+/// Reason why a position string could not be parsed. Columns count from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FenError {
+    /// Character at column does not belong there.
+    Unexpected(usize, char),
+    /// Something is missing at column, e.g. a field.
+    Missing(usize, &'static str),
+    /// Board does not have 2 to `MAX_HEIGHT` rows.
+    RowCount(usize),
+    /// Row starting at column is not as long as the first row, or no board
+    /// has rows of its length.
+    RowLength(usize),
+    /// Tile at column is in a bag or drawn tiles of the other color.
+    WrongColor(usize),
+    /// Tile at column is flipped, but not on the board or in the graveyard.
+    Flipped(usize),
+    /// Turn at column is not a number.
+    Turn(usize),
+    /// Position can't be played.
+    Setup(SetupError),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::Unexpected(col, c) => write!(f, "column {}: unexpected '{}'", col, c),
+            FenError::Missing(col, s) => write!(f, "column {}: missing {}", col, s),
+            FenError::RowCount(n) => {
+                write!(f, "{} rows, board has 2 to {}", n, MAX_HEIGHT)
+            }
+            FenError::RowLength(col) => write!(f, "column {}: illegal row length", col),
+            FenError::WrongColor(col) => write!(f, "column {}: tile of other color", col),
+            FenError::Flipped(col) => write!(f, "column {}: tile can't be flipped", col),
+            FenError::Turn(col) => write!(f, "column {}: invalid turn", col),
+            FenError::Setup(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+impl From<SetupError> for FenError {
+    fn from(e: SetupError) -> FenError {
+        FenError::Setup(e)
+    }
+}
+
+/// Letter of tile, upper case for White.
+pub fn tile_letter(tile: &Tile) -> char {
+    let letter = TILE_LETTERS
+        .iter()
+        .find(|(kind, _)| *kind == tile.kind)
+        .map(|(_, letter)| *letter)
+        .expect("Letter of every tile type.");
+    match tile.color {
+        TileColor::White => letter,
+        TileColor::Black => letter.to_ascii_lowercase(),
+    }
+}
+
+/// Tile type and color of letter.
+fn letter_tile(letter: char) -> Option<(TileType, TileColor)> {
+    let color = if letter.is_ascii_uppercase() {
+        TileColor::White
+    } else {
+        TileColor::Black
+    };
+    TILE_LETTERS
+        .iter()
+        .find(|(_, l)| *l == letter.to_ascii_uppercase())
+        .map(|(kind, _)| (*kind, color))
+}
+
+fn write_tile(fen: &mut String, tile: &Tile) {
+    fen.push(tile_letter(tile));
+    if tile.flipped {
+        fen.push(FLIPPED);
+    }
+}
+
+fn write_tiles(fen: &mut String, tiles: &[Tile]) {
+    if tiles.is_empty() {
+        fen.push_str(EMPTY);
+    }
+    for tile in tiles {
+        write_tile(fen, tile);
+    }
+}
+
+/// Tiles of field and their columns. Field starts at byte offset start, all
+/// text before an error is ASCII, so it is also the column.
+fn parse_tiles(field: &str, start: usize) -> Result<Vec<(usize, Tile)>, FenError> {
+    let mut tiles: Vec<(usize, Tile)> = Vec::new();
+    if field == EMPTY {
+        return Ok(tiles);
+    }
+
+    for (i, c) in field.char_indices() {
+        let col = start + i + 1;
+        if c == FLIPPED {
+            match tiles.last_mut() {
+                Some((_, tile)) if !tile.flipped => tile.flipped = true,
+                _ => return Err(FenError::Unexpected(col, c)),
+            }
+            continue;
+        }
+        let (kind, color) = letter_tile(c).ok_or(FenError::Unexpected(col, c))?;
+        tiles.push((
+            col,
+            Tile {
+                kind,
+                flipped: false,
+                color,
+            },
+        ));
+    }
+
+    if tiles.is_empty() {
+        return Err(FenError::Missing(start + 1, "tiles"));
+    }
+    Ok(tiles)
+}
+
+/// Black and White tiles of a `black/white` field. Tiles must have the color
+/// of their list, and can't be flipped.
+fn parse_pair(field: &str, start: usize) -> Result<[Vec<TileType>; 2], FenError> {
+    let (black, white) = field
+        .split_once('/')
+        .ok_or(FenError::Missing(start + field.len() + 1, "'/'"))?;
+
+    let mut pair: [Vec<TileType>; 2] = Default::default();
+    let lists = [
+        (TileColor::Black, black, start),
+        (TileColor::White, white, start + black.len() + 1),
+    ];
+    for (color, list, start) in lists {
+        for (col, tile) in parse_tiles(list, start)? {
+            if tile.color != color {
+                return Err(FenError::WrongColor(col));
+            }
+            if tile.flipped {
+                return Err(FenError::Flipped(col));
+            }
+            pair[color as usize].push(tile.kind);
+        }
+    }
+    Ok(pair)
+}
+
+fn parse_board(
+    mut builder: GameStateBuilder,
+    field: &str,
+    start: usize,
+) -> Result<GameStateBuilder, FenError> {
+    let rows: Vec<&str> = field.split('/').collect();
+    if !(2..=MAX_HEIGHT as usize).contains(&rows.len()) {
+        return Err(FenError::RowCount(rows.len()));
+    }
+    let height = rows.len() as u8;
+
+    // First row gives the width.
+    let mut width = None;
+    let mut row_start = start;
+    for (i, row) in rows.iter().enumerate() {
+        let y = height - 1 - i as u8;
+        let mut x = 0;
+        let mut last: Option<(Coordinate, TileType, TileColor)> = None;
+        for (j, c) in row.char_indices() {
+            let col = row_start + j + 1;
+            if let Some(empty) = c
+                .to_digit(10)
+                .filter(|d| (1..=MAX_WIDTH as u32).contains(d))
+            {
+                if let Some((cord, kind, color)) = last.take() {
+                    builder = builder.tile_at(cord, kind, color, false);
+                }
+                x += empty as u8;
+            } else if c == FLIPPED {
+                let (cord, kind, color) = last.take().ok_or(FenError::Unexpected(col, c))?;
+                builder = builder.tile_at(cord, kind, color, true);
+            } else {
+                let (kind, color) = letter_tile(c).ok_or(FenError::Unexpected(col, c))?;
+                if let Some((cord, kind, color)) = last.take() {
+                    builder = builder.tile_at(cord, kind, color, false);
+                }
+                if x >= MAX_WIDTH {
+                    return Err(FenError::RowLength(row_start + 1));
+                }
+                last = Some((Coordinate::new(x, y), kind, color));
+                x += 1;
+            }
+            if x > MAX_WIDTH {
+                return Err(FenError::RowLength(row_start + 1));
+            }
+        }
+        if let Some((cord, kind, color)) = last {
+            builder = builder.tile_at(cord, kind, color, false);
+        }
+        let legal = match width {
+            None => BoardSize::legal(x, height),
+            Some(width) => x == width,
+        };
+        if !legal {
+            return Err(FenError::RowLength(row_start + 1));
+        }
+        width = Some(x);
+        row_start += row.len() + 1;
+    }
+
+    let size = BoardSize::new(width.unwrap(), height);
+    Ok(builder.size(size))
+}
+
+impl GameState {
+    /// Position as text, see module documentation.
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+
+        let size = self.size();
+        for y in (0..size.height).rev() {
+            let mut empty = 0;
+            for x in 0..size.width {
+                match &self.square(Coordinate::new(x, y)).tile {
+                    Some(tile) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        write_tile(&mut fen, tile);
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if y > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push(' ');
+        fen.push(match self.ply {
+            TileColor::Black => 'b',
+            TileColor::White => 'w',
+        });
+
+        for pair in [&self.bags, &self.drawn_tiles] {
+            fen.push(' ');
+            write_tiles(&mut fen, &pair[TileColor::Black as usize]);
+            fen.push('/');
+            write_tiles(&mut fen, &pair[TileColor::White as usize]);
+        }
+
+        fen.push(' ');
+        write_tiles(&mut fen, &self.graveyard_tiles());
+
+        fen.push_str(&format!(" {}", self.turn));
+        fen
+    }
+
+    /// Game state of position text, see module documentation. Fails on text
+    /// that does not follow the format, and on positions that
+    /// `GameStateBuilder` rejects.
+    pub fn from_fen(fen: &str) -> Result<GameState, FenError> {
+        let fen = fen.trim_end();
+        let mut fields: Vec<(usize, &str)> = Vec::new();
+        let mut start = 0;
+        for field in fen.split(' ') {
+            if field.is_empty() {
+                return Err(FenError::Missing(start + 1, "field"));
+            }
+            fields.push((start, field));
+            start += field.len() + 1;
+        }
+
+        let names = ["board", "ply", "bags", "drawn tiles", "graveyard"];
+        if fields.len() < names.len() {
+            return Err(FenError::Missing(fen.len() + 2, names[fields.len()]));
+        }
+        if let Some((start, field)) = fields.get(names.len() + 1) {
+            let c = field.chars().next().unwrap_or(' ');
+            return Err(FenError::Unexpected(start + 1, c));
+        }
+
+        let (start, board) = fields[0];
+        let mut builder = parse_board(GameStateBuilder::new(), board, start)?;
+
+        let (start, ply) = fields[1];
+        builder = match ply {
+            "b" => builder.ply(TileColor::Black),
+            "w" => builder.ply(TileColor::White),
+            _ => {
+                let c = ply.chars().next().unwrap_or(' ');
+                return Err(FenError::Unexpected(start + 1, c));
+            }
+        };
+
+        let (start, bags) = fields[2];
+        let [black, white] = parse_pair(bags, start)?;
+        builder = builder
+            .bag(TileColor::Black, black)
+            .bag(TileColor::White, white);
+
+        let (start, drawn) = fields[3];
+        for (color, kinds) in [TileColor::Black, TileColor::White]
+            .into_iter()
+            .zip(parse_pair(drawn, start)?)
+        {
+            for kind in kinds {
+                builder = builder.drawn(color, kind);
+            }
+        }
+
+        let (start, graveyard) = fields[4];
+        for (_, tile) in parse_tiles(graveyard, start)? {
+            builder = builder.graveyard(tile.kind, tile.color, tile.flipped);
+        }
+
+        if let Some((start, turn)) = fields.get(5) {
+            let turn = turn.parse().map_err(|_| FenError::Turn(start + 1))?;
+            builder = builder.turn(turn);
+        }
+
+        Ok(builder.build()?)
+    }
+}
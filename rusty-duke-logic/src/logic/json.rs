@@ -0,0 +1,273 @@
+//! Positions as self-describing JSON, for third-party tools.
+//!
+//! Schema version 1:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "ply": "Black",
+//!   "game_over": null,
+//!   "win_reason": null,
+//!   "turn": 0,
+//!   "size": { "width": 6, "height": 6 },
+//!   "board": [
+//!     { "square": { "x": 2, "y": 0 }, "tile": { "kind": "Duke", "color": "Black", "flipped": false } }
+//!   ],
+//!   "bags": {
+//!     "black": [ { "kind": "Pikeman", "count": 3 } ],
+//!     "white": []
+//!   },
+//!   "drawn": { "black": [], "white": [] },
+//!   "graveyard": []
+//! }
+//! ```
+//!
+//! Square effects are not stored, they are recalculated from the tiles on the
+//! board. Unknown fields are ignored, so documents from newer versions with
+//! only added fields still load.
+//!
+//! ```
+//! use rusty_duke_logic::logic::{json, GameState};
+//!
+//! let state = GameState::new();
+//! let position = json::to_json_position(&state);
+//! let loaded = json::from_json_position(&position).unwrap();
+//! assert_eq!(loaded.hash(), state.hash());
+//! ```
+
+use super::{
+    add_tile_effects, Board, BoardSize, CapturedTile, Coordinate, GameState, Tile, TileColor,
+    TileType, WinReason, Winner,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Current schema version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct JsonPosition {
+    version: u32,
+    ply: TileColor,
+    game_over: Option<Winner>,
+    /// Added after version 1 documents were written, hence the default.
+    #[serde(default)]
+    win_reason: Option<WinReason>,
+    /// Turns done so far. Added after version 1 too, 0 if missing.
+    #[serde(default)]
+    turn: u16,
+    /// Added with boards of other sizes, standard if missing.
+    #[serde(default)]
+    size: BoardSize,
+    board: Vec<JsonSquare>,
+    bags: PerColor<Vec<TileCount>>,
+    drawn: PerColor<Vec<JsonTile>>,
+    graveyard: Vec<Tile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonSquare {
+    square: Coordinate,
+    tile: Tile,
+}
+
+/// Tile with known color.
+#[derive(Serialize, Deserialize)]
+struct JsonTile {
+    kind: TileType,
+    flipped: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TileCount {
+    kind: TileType,
+    count: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PerColor<T> {
+    black: T,
+    white: T,
+}
+
+/// Reason why a JSON position could not be loaded.
+#[derive(Debug)]
+pub enum PositionError {
+    /// Not valid JSON, or a field has wrong type. Has line and column.
+    Json(serde_json::Error),
+    /// Document is from an unsupported schema version.
+    UnsupportedVersion(u32),
+    /// No board has this size, see `BoardSize::legal`.
+    IllegalSize(BoardSize),
+    /// Square is not on the board. Has JSON pointer to the square.
+    IllegalSquare(String),
+    /// More than one tile on square. Has JSON pointer to the square.
+    DuplicateSquare(String),
+    /// More than one Duke of a color on the board.
+    DuplicateDuke(TileColor),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionError::Json(e) => write!(f, "invalid position: {}", e),
+            PositionError::UnsupportedVersion(v) => {
+                write!(f, "unsupported schema version {}", v)
+            }
+            PositionError::IllegalSize(s) => {
+                write!(f, "illegal board size {}x{}", s.width, s.height)
+            }
+            PositionError::IllegalSquare(p) => write!(f, "{}: square is not on board", p),
+            PositionError::DuplicateSquare(p) => write!(f, "{}: square already has a tile", p),
+            PositionError::DuplicateDuke(c) => write!(f, "more than one {:?} Duke", c),
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+impl From<serde_json::Error> for PositionError {
+    fn from(e: serde_json::Error) -> Self {
+        PositionError::Json(e)
+    }
+}
+
+fn count_tiles(tiles: &[Tile]) -> Vec<TileCount> {
+    let mut counts: Vec<TileCount> = Vec::new();
+    for tile in tiles {
+        match counts.iter_mut().find(|c| c.kind == tile.kind) {
+            Some(c) => c.count += 1,
+            None => counts.push(TileCount {
+                kind: tile.kind,
+                count: 1,
+            }),
+        }
+    }
+    counts.sort_by_key(|c| c.kind.to_id());
+    counts
+}
+
+fn to_json_tiles(tiles: &[Tile]) -> Vec<JsonTile> {
+    tiles
+        .iter()
+        .map(|t| JsonTile {
+            kind: t.kind,
+            flipped: t.flipped,
+        })
+        .collect()
+}
+
+fn from_json_tiles(tiles: &[JsonTile], color: TileColor) -> Vec<Tile> {
+    tiles
+        .iter()
+        .map(|t| Tile {
+            kind: t.kind,
+            flipped: t.flipped,
+            color,
+        })
+        .collect()
+}
+
+fn from_tile_counts(counts: &[TileCount], color: TileColor) -> Vec<Tile> {
+    counts
+        .iter()
+        .flat_map(|c| (0..c.count).map(move |_| Tile::new(c.kind, color)))
+        .collect()
+}
+
+/// Position as pretty printed JSON. See module documentation for the schema.
+pub fn to_json_position(state: &GameState) -> String {
+    let mut board = Vec::new();
+    for (square, tile) in state.iter_tiles(None) {
+        board.push(JsonSquare {
+            square,
+            tile: *tile,
+        });
+    }
+
+    let black = TileColor::Black as usize;
+    let white = TileColor::White as usize;
+    let position = JsonPosition {
+        version: SCHEMA_VERSION,
+        ply: state.ply,
+        game_over: state.game_over.clone(),
+        win_reason: state.win_reason,
+        turn: state.turn,
+        size: state.size(),
+        board,
+        bags: PerColor {
+            black: count_tiles(&state.bags[black]),
+            white: count_tiles(&state.bags[white]),
+        },
+        drawn: PerColor {
+            black: to_json_tiles(&state.drawn_tiles[black]),
+            white: to_json_tiles(&state.drawn_tiles[white]),
+        },
+        graveyard: state.graveyard_tiles(),
+    };
+
+    serde_json::to_string_pretty(&position).expect("Position is always serializable.")
+}
+
+/// Load position from JSON. Effects and Duke positions are recalculated, the
+/// loaded state has no history.
+pub fn from_json_position(json: &str) -> Result<GameState, PositionError> {
+    let position: JsonPosition = serde_json::from_str(json)?;
+
+    if position.version > SCHEMA_VERSION {
+        return Err(PositionError::UnsupportedVersion(position.version));
+    }
+    let size = position.size;
+    if !BoardSize::legal(size.width, size.height) {
+        return Err(PositionError::IllegalSize(size));
+    }
+
+    let mut state = GameState::new();
+    state.board = Board::new(size);
+    state.dukes = [None; 2];
+    state.ply = position.ply;
+    state.game_over = position.game_over;
+    state.win_reason = position.win_reason;
+    state.turn = position.turn;
+    state.graveyard = position
+        .graveyard
+        .into_iter()
+        .map(CapturedTile::from_tile)
+        .collect();
+
+    let black = TileColor::Black as usize;
+    let white = TileColor::White as usize;
+    state.bags[black] = from_tile_counts(&position.bags.black, TileColor::Black);
+    state.bags[white] = from_tile_counts(&position.bags.white, TileColor::White);
+    state.drawn_tiles[black] = from_json_tiles(&position.drawn.black, TileColor::Black);
+    state.drawn_tiles[white] = from_json_tiles(&position.drawn.white, TileColor::White);
+
+    for (i, square) in position.board.iter().enumerate() {
+        let pointer = format!("/board/{}/square", i);
+        let cord = square.square;
+        if !size.contains(cord) {
+            return Err(PositionError::IllegalSquare(pointer));
+        }
+        if state.square(cord).tile.is_some() {
+            return Err(PositionError::DuplicateSquare(pointer));
+        }
+
+        let tile = square.tile;
+        if tile.kind == TileType::Duke {
+            let duke = &mut state.dukes[tile.color as usize];
+            if duke.is_some() {
+                return Err(PositionError::DuplicateDuke(tile.color));
+            }
+            *duke = Some(cord);
+        }
+        state.mut_square(cord).tile = Some(tile);
+    }
+
+    // Effects are added when all tiles are on the board.
+    for square in position.board.iter() {
+        add_tile_effects(&mut state, square.square);
+    }
+    state.rehash();
+
+    Ok(state)
+}
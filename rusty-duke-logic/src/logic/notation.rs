@@ -0,0 +1,339 @@
+//! Text notation of actions, for game logs.
+//!
+//! Squares are written `a1` to `f6`, file from x and rank from y + 1.
+//!
+//! | Action      | Notation             |
+//! |-------------|----------------------|
+//! | NewFromBag  | `draw`               |
+//! | PlaceNew    | `@c1 Knight`         |
+//! | Move etc.   | `Footman b2-b3`      |
+//! | Capture     | `Footman b2xb3`      |
+//! | Strike      | `Pikeman c3xd5!`     |
+//! | Command     | `General d2 c2>c4`   |
+//! | Command cap | `General d2 c2xc4`   |
+//!
+//! Move, Jump, Slide and JumpSlide share notation. Parsing picks the action
+//! the tile can do, and fails if it can do more than one. Errors for illegal
+//! actions list what the tile can do instead.
+//!
+//! ```
+//! use rusty_duke_logic::logic::notation::{
+//!     action_to_notation, notation_to_action, NotationError,
+//! };
+//! use rusty_duke_logic::logic::{do_action, GameState};
+//!
+//! let mut state = GameState::new_seeded(1);
+//! let setup = [
+//!     "@c1 Duke", "@c6 Duke", "@b1 Footman", "@d6 Footman", "@d1 Footman", "@b6 Footman",
+//! ];
+//! for text in setup {
+//!     let action = notation_to_action(&state, text).unwrap();
+//!     assert_eq!(action_to_notation(&state, &action), text);
+//!     do_action(&mut state, &action).unwrap();
+//! }
+//!
+//! let error = notation_to_action(&state, "Footman b1-b3").unwrap_err();
+//! assert_eq!(
+//!     error,
+//!     NotationError::IllegalAction(
+//!         "Footman b1-b3, tile can do Footman b1-b2, Footman b1-a1".to_string()
+//!     )
+//! );
+//! ```
+
+use super::{
+    get_actions, Action, ActionResult, Coordinate, GameState, TileType, UnknownTileType,
+    MAX_HEIGHT, MAX_WIDTH,
+};
+use std::fmt;
+
+/// Reason why notation could not be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotationError {
+    /// Text does not follow the notation.
+    Syntax(String),
+    /// Unknown tile name, see `UnknownTileType`.
+    UnknownTileType(String),
+    /// Square is not on the largest board.
+    IllegalSquare(String),
+    /// Named tile is not the tile on the square, or the drawn tile.
+    WrongTile(String),
+    /// Action can't be done in this state.
+    IllegalAction(String),
+    /// Notation matches more than one action.
+    Ambiguous(String),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotationError::Syntax(s) => write!(f, "invalid notation: {}", s),
+            NotationError::UnknownTileType(s) => write!(f, "unknown tile: {}", s),
+            NotationError::IllegalSquare(s) => write!(f, "square not on board: {}", s),
+            NotationError::WrongTile(s) => write!(f, "wrong tile: {}", s),
+            NotationError::IllegalAction(s) => write!(f, "illegal action: {}", s),
+            NotationError::Ambiguous(s) => write!(f, "ambiguous action: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+/// Square as text, e.g. `c1`.
+pub fn square_to_notation(cord: Coordinate) -> String {
+    cord.to_string()
+}
+
+/// Square from text, e.g. `c1`.
+pub fn notation_to_square(s: &str) -> Result<Coordinate, NotationError> {
+    let mut chars = s.chars();
+    let file = chars.next();
+    let rank: String = chars.collect();
+
+    let x = match file {
+        Some(c) if c.is_ascii_lowercase() => c as u8 - b'a',
+        _ => return Err(NotationError::Syntax(s.to_string())),
+    };
+    let y = match rank.parse::<u8>() {
+        Ok(rank) if rank >= 1 => rank - 1,
+        _ => return Err(NotationError::Syntax(s.to_string())),
+    };
+
+    if x >= MAX_WIDTH || y >= MAX_HEIGHT {
+        return Err(NotationError::IllegalSquare(s.to_string()));
+    }
+    Ok(Coordinate::new(x, y))
+}
+
+pub(super) fn parse_tile(s: &str) -> Result<TileType, NotationError> {
+    s.parse()
+        .map_err(|UnknownTileType(name)| NotationError::UnknownTileType(name))
+}
+
+fn tile_name(state: &GameState, cord: Coordinate) -> String {
+    match state.square(cord).tile {
+        Some(tile) => tile.kind.to_string(),
+        None => "?".to_string(),
+    }
+}
+
+/// Action as text. Action should be from `get_actions` on the same state.
+pub fn action_to_notation(state: &GameState, action: &Action) -> String {
+    match action {
+        Action::NewFromBag => "draw".to_string(),
+        Action::PlaceNew(cord) => {
+            let name = match state.drawn().last() {
+                Some(tile) => tile.kind.to_string(),
+                None => "?".to_string(),
+            };
+            format!("@{} {}", square_to_notation(*cord), name)
+        }
+        Action::Move(data) | Action::Jump(data) | Action::JumpSlide(data) | Action::Slide(data) => {
+            let sep = if data.result == ActionResult::Capture {
+                'x'
+            } else {
+                '-'
+            };
+            format!(
+                "{} {}{}{}",
+                tile_name(state, data.tile_pos),
+                square_to_notation(data.tile_pos),
+                sep,
+                square_to_notation(data.target_pos)
+            )
+        }
+        Action::Strike(data) => format!(
+            "{} {}x{}!",
+            tile_name(state, data.tile_pos),
+            square_to_notation(data.tile_pos),
+            square_to_notation(data.target_pos)
+        ),
+        Action::Command(data) => {
+            let sep = if data.result == ActionResult::Capture {
+                'x'
+            } else {
+                '>'
+            };
+            format!(
+                "{} {} {}{}{}",
+                tile_name(state, data.tile_pos),
+                square_to_notation(data.tile_pos),
+                square_to_notation(data.command_tile_pos),
+                sep,
+                square_to_notation(data.target_pos)
+            )
+        }
+    }
+}
+
+/// Split e.g. `b2-b3` at separator. Returns (from, separator, to).
+fn split_squares(s: &str, separators: &[char]) -> Result<(String, char, String), NotationError> {
+    match s.find(|c| separators.contains(&c)) {
+        Some(i) => {
+            let sep = s[i..].chars().next().unwrap();
+            Ok((s[..i].to_string(), sep, s[i + 1..].to_string()))
+        }
+        None => Err(NotationError::Syntax(s.to_string())),
+    }
+}
+
+/// Action from text. Only actions returned by `get_actions` on the state are
+/// accepted.
+pub fn notation_to_action(state: &GameState, s: &str) -> Result<Action, NotationError> {
+    let actions = get_actions(state);
+    let words: Vec<&str> = s.split_whitespace().collect();
+    let illegal = || NotationError::IllegalAction(s.to_string());
+
+    // Draw
+    if words == ["draw"] {
+        return actions
+            .into_iter()
+            .find(|a| *a == Action::NewFromBag)
+            .ok_or_else(illegal);
+    }
+
+    // Deploy
+    if words.len() == 2 && words[0].starts_with('@') {
+        let cord = notation_to_square(&words[0][1..])?;
+        let kind = parse_tile(words[1])?;
+        match state.drawn().last() {
+            Some(tile) if tile.kind == kind => {}
+            _ => return Err(NotationError::WrongTile(s.to_string())),
+        }
+        return actions
+            .into_iter()
+            .find(|a| *a == Action::PlaceNew(cord))
+            .ok_or_else(illegal);
+    }
+
+    if words.len() < 2 {
+        return Err(NotationError::Syntax(s.to_string()));
+    }
+
+    let kind = parse_tile(words[0])?;
+    let check_tile = |cord: Coordinate| match state.square(cord).tile {
+        Some(tile) if tile.kind == kind => Ok(()),
+        _ => Err(NotationError::WrongTile(s.to_string())),
+    };
+
+    let (tile_pos, candidates): (Coordinate, Vec<Action>) = if words.len() == 3 {
+        // Command
+        let tile_pos = notation_to_square(words[1])?;
+        check_tile(tile_pos)?;
+        let (from, sep, to) = split_squares(words[2], &['>', 'x'])?;
+        let command_tile_pos = notation_to_square(&from)?;
+        let target_pos = notation_to_square(&to)?;
+        let result = if sep == 'x' {
+            ActionResult::Capture
+        } else {
+            ActionResult::Move
+        };
+
+        let candidates = actions
+            .iter()
+            .copied()
+            .filter(|a| match a {
+                Action::Command(data) => {
+                    data.tile_pos == tile_pos
+                        && data.command_tile_pos == command_tile_pos
+                        && data.target_pos == target_pos
+                        && data.result == result
+                }
+                _ => false,
+            })
+            .collect();
+        (tile_pos, candidates)
+    } else if words.len() == 2 {
+        let strike = words[1].ends_with('!');
+        let squares = words[1].trim_end_matches('!');
+        let (from, sep, to) = split_squares(squares, &['-', 'x'])?;
+        let tile_pos = notation_to_square(&from)?;
+        let target_pos = notation_to_square(&to)?;
+        check_tile(tile_pos)?;
+        if strike && sep != 'x' {
+            return Err(NotationError::Syntax(s.to_string()));
+        }
+        let result = if sep == 'x' {
+            ActionResult::Capture
+        } else {
+            ActionResult::Move
+        };
+
+        let candidates = actions
+            .iter()
+            .copied()
+            .filter(|a| match a {
+                Action::Strike(data) => {
+                    strike && data.tile_pos == tile_pos && data.target_pos == target_pos
+                }
+                Action::Move(data)
+                | Action::Jump(data)
+                | Action::JumpSlide(data)
+                | Action::Slide(data) => {
+                    !strike
+                        && data.tile_pos == tile_pos
+                        && data.target_pos == target_pos
+                        && data.result == result
+                }
+                _ => false,
+            })
+            .collect();
+        (tile_pos, candidates)
+    } else {
+        return Err(NotationError::Syntax(s.to_string()));
+    };
+
+    match candidates.len() {
+        0 => Err(NotationError::IllegalAction(format!(
+            "{}, {}",
+            s,
+            tile_actions_hint(state, &actions, tile_pos)
+        ))),
+        1 => Ok(candidates[0]),
+        _ => {
+            let kinds: Vec<&str> = candidates.iter().map(action_kind).collect();
+            Err(NotationError::Ambiguous(format!(
+                "{}, could be {}",
+                s,
+                kinds.join(" or ")
+            )))
+        }
+    }
+}
+
+/// Name of the kind of action, e.g. `Jump`.
+fn action_kind(action: &Action) -> &'static str {
+    match action {
+        Action::NewFromBag => "NewFromBag",
+        Action::PlaceNew(_) => "PlaceNew",
+        Action::Move(_) => "Move",
+        Action::Jump(_) => "Jump",
+        Action::JumpSlide(_) => "JumpSlide",
+        Action::Slide(_) => "Slide",
+        Action::Command(_) => "Command",
+        Action::Strike(_) => "Strike",
+    }
+}
+
+/// What the tile on square can do instead, for errors.
+fn tile_actions_hint(state: &GameState, actions: &[Action], tile_pos: Coordinate) -> String {
+    let possible: Vec<String> = actions
+        .iter()
+        .filter(|a| match a {
+            Action::Move(data)
+            | Action::Jump(data)
+            | Action::JumpSlide(data)
+            | Action::Slide(data)
+            | Action::Strike(data) => data.tile_pos == tile_pos,
+            Action::Command(data) => data.tile_pos == tile_pos,
+            _ => false,
+        })
+        .map(|a| action_to_notation(state, a))
+        .collect();
+
+    if possible.is_empty() {
+        "tile has no actions".to_string()
+    } else {
+        format!("tile can do {}", possible.join(", "))
+    }
+}
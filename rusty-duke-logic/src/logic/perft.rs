@@ -0,0 +1,143 @@
+//! Node counting of the action tree, like perft in chess. Counts from known
+//! positions catch changes to action generation, and timing them measures
+//! its speed.
+//!
+//! Every action of `get_actions` is a branch, also actions that leave the Duke
+//! in check. A node counts as a leaf when depth runs out. Nodes without
+//! actions before that, e.g. game over, are not counted.
+//!
+//! Counts from the start position of the standard game. The first six turns
+//! place Dukes and Footmen, so both draw modes agree until depth 7.
+//!
+//! | Depth | `Exclude` | `Expand` |
+//! |-------|-----------|----------|
+//! | 1     | 2         | 2        |
+//! | 2     | 4         | 4        |
+//! | 3     | 12        | 12       |
+//! | 4     | 36        | 36       |
+//! | 5     | 72        | 72       |
+//! | 6     | 144       | 144      |
+//! | 7     | 912       | 3072     |
+//! | 8     | 5776      | 21616    |
+//! | 9     | 54184     | 200584   |
+//!
+//! `perft` counts the tree of legal actions only, see `get_legal_actions`.
+//! From the same position:
+//!
+//! | Depth | `Exclude` | `Expand` | `Leaf` |
+//! |-------|-----------|----------|--------|
+//! | 6     | 144       | 144      | 144    |
+//! | 7     | 912       | 3072     | 1056   |
+//! | 8     | 5640      | 21480    | 6696   |
+//! | 9     | 49968     | 194008   | 56664  |
+
+use super::{
+    do_unsafe_action, draw_from_bag, get_actions, get_legal_actions, undo_action, Action, GameState,
+};
+
+/// Actions of a state that are branches of the tree.
+type ActionsFn = fn(&GameState) -> Vec<Action>;
+
+/// How drawing from the bag is counted. Which tile is drawn is chance, not a
+/// choice of the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawMode {
+    /// `NewFromBag` is not counted and not searched.
+    Exclude,
+    /// `NewFromBag` has a branch for each kind of tile in the bag. Equal
+    /// tiles are one branch.
+    Expand,
+    /// `NewFromBag` is a single leaf node, whatever the depth left.
+    Leaf,
+}
+
+/// Number of leaf nodes of the action tree of state, depth actions deep.
+pub fn count_action_nodes(state: &GameState, depth: u8, draws: DrawMode) -> u64 {
+    let mut state = state.clone();
+    state.clear_history();
+    count(&mut state, depth, draws, get_actions)
+}
+
+/// Same as `count_action_nodes`, but only legal actions are branches. Any
+/// difference between the two is actions that leave the own Duke in check.
+///
+/// ```
+/// use rusty_duke_logic::logic::perft::{count_action_nodes, perft, DrawMode};
+/// use rusty_duke_logic::logic::GameState;
+///
+/// let state = GameState::new_seeded(0);
+/// assert_eq!(perft(&state, 6, DrawMode::Leaf), 144);
+/// assert_eq!(perft(&state, 7, DrawMode::Expand), count_action_nodes(&state, 7, DrawMode::Expand));
+/// ```
+pub fn perft(state: &GameState, depth: u8, draws: DrawMode) -> u64 {
+    let mut state = state.clone();
+    state.clear_history();
+    count(&mut state, depth, draws, get_legal_actions)
+}
+
+/// Same as `count_action_nodes`, but per action of state, like divide in
+/// chess. Actions without nodes are listed too. With `DrawMode::Expand`,
+/// `NewFromBag` is listed once with the nodes of all drawn tiles.
+pub fn divide(state: &GameState, depth: u8, draws: DrawMode) -> Vec<(Action, u64)> {
+    let mut state = state.clone();
+    state.clear_history();
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    get_actions(&state)
+        .into_iter()
+        .filter(|action| *action != Action::NewFromBag || draws != DrawMode::Exclude)
+        .map(|action| {
+            let nodes = count_action(&mut state, &action, depth, draws, get_actions);
+            (action, nodes)
+        })
+        .collect()
+}
+
+fn count(state: &mut GameState, depth: u8, draws: DrawMode, actions: ActionsFn) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let mut nodes = 0;
+    for action in actions(state) {
+        if action == Action::NewFromBag && draws == DrawMode::Exclude {
+            continue;
+        }
+        nodes += count_action(state, &action, depth, draws, actions);
+    }
+    nodes
+}
+
+/// Nodes below action, which is done at depth.
+fn count_action(
+    state: &mut GameState,
+    action: &Action,
+    depth: u8,
+    draws: DrawMode,
+    actions: ActionsFn,
+) -> u64 {
+    if *action != Action::NewFromBag {
+        do_unsafe_action(state, action);
+        let nodes = count(state, depth - 1, draws, actions);
+        undo_action(state).expect("Undo of counted action.");
+        return nodes;
+    }
+    if draws == DrawMode::Leaf {
+        return 1;
+    }
+
+    // Draw the first tile of each kind.
+    let mut nodes = 0;
+    for index in 0..state.bag().len() {
+        let kind = state.bag()[index].kind;
+        if state.bag()[..index].iter().any(|tile| tile.kind == kind) {
+            continue;
+        }
+        draw_from_bag(state, index);
+        nodes += count(state, depth - 1, draws, actions);
+        undo_action(state).expect("Undo of counted draw.");
+    }
+    nodes
+}
@@ -0,0 +1,431 @@
+//! Records of full games, for saving and replaying.
+//!
+//! A record is the seed of the game and the actions played. The tile drawn by
+//! each `NewFromBag` is recorded too, so replay gives the same game even when
+//! the seed is not known.
+//!
+//! Text format, one action per line in the notation of the `notation` module.
+//! Draws also name the drawn tile. Empty lines and lines starting with `#` are
+//! ignored.
+//!
+//! ```text
+//! seed 1234
+//! @c1 Duke
+//! @c2 Footman
+//! @d1 Footman
+//! ...
+//! draw Knight
+//! @c3 Knight
+//! Footman c2-c3
+//! ```
+//!
+//! A game that ended by resignation or agreed draw has a last line
+//! `resign Black`, `resign White` or `draw agreed`.
+//!
+//! A game without known seed has `seed -`. Games of other variants than
+//! standard have a variant line right after the seed line, e.g.
+//! `variant quick`.
+
+use super::notation::{action_to_notation, notation_to_action, parse_tile, NotationError};
+use super::variant::Variant;
+use super::{
+    do_unsafe_action, draw_from_bag, offer_draw, resign, respond_draw, Action, GameState,
+    TileColor, TileType, WinReason, Winner,
+};
+use rand::Rng;
+use std::fmt;
+
+pub mod binary;
+
+/// Action in a record.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordedAction {
+    pub action: Action,
+    /// Drawn tile, for `NewFromBag`.
+    pub drawn: Option<TileType>,
+}
+
+/// End of a game that is not an action, see `logic::resign` and
+/// `logic::offer_draw`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RecordedEnd {
+    /// Color resigned.
+    Resignation(TileColor),
+    DrawAgreed,
+}
+
+/// Reason why a text record could not be loaded. Line numbers start from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordError {
+    /// Missing or invalid seed line.
+    Seed(String),
+    /// Unknown variant.
+    Variant(usize, String),
+    /// Action could not be parsed, or can't be done.
+    Notation(usize, NotationError),
+    /// Drawn tile is not in the bag.
+    NotInBag(usize, TileType),
+    /// Invalid resign line.
+    Resign(usize, String),
+    /// Line after the game is over.
+    AfterEnd(usize),
+    /// Tile name this version doesn't know, e.g. of a newer version or
+    /// another ruleset.
+    UnsupportedContent(usize, String),
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::Seed(s) => write!(f, "invalid seed line: {}", s),
+            RecordError::Variant(line, name) => {
+                write!(f, "line {}: unknown variant {}", line, name)
+            }
+            RecordError::Notation(line, e) => write!(f, "line {}: {}", line, e),
+            RecordError::NotInBag(line, kind) => {
+                write!(f, "line {}: {} is not in bag", line, kind)
+            }
+            RecordError::Resign(line, s) => write!(f, "line {}: invalid resign {}", line, s),
+            RecordError::AfterEnd(line) => write!(f, "line {}: game is already over", line),
+            RecordError::UnsupportedContent(line, name) => {
+                write!(f, "line {}: tile {} is not supported", line, name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+/// Error of action notation on line. Unknown tile names are unsupported
+/// content, not typos.
+fn notation_error(line: usize, e: NotationError) -> RecordError {
+    match e {
+        NotationError::UnknownTileType(name) => RecordError::UnsupportedContent(line, name),
+        e => RecordError::Notation(line, e),
+    }
+}
+
+/// Seed, variant and actions of a game.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameRecord {
+    /// Seed given to `GameState::new_seeded`, if known.
+    pub seed: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub variant: Variant,
+    pub actions: Vec<RecordedAction>,
+    /// Resignation or agreed draw after the last action.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub end: Option<RecordedEnd>,
+}
+
+impl GameRecord {
+    /// Empty record of a game started with `GameState::new_seeded(seed)`, or
+    /// with `GameState::new()` if seed is `None`.
+    pub fn new(seed: Option<u64>) -> GameRecord {
+        GameRecord::new_variant(seed, Variant::Standard)
+    }
+
+    /// Empty record of a game started with
+    /// `GameState::new_with_rules(&variant.rules(), seed)`.
+    pub fn new_variant(seed: Option<u64>, variant: Variant) -> GameRecord {
+        GameRecord {
+            seed,
+            variant,
+            actions: Vec::new(),
+            end: None,
+        }
+    }
+
+    /// Number of recorded actions.
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Record action. State is the state right after the action was done, it
+    /// tells which tile was drawn by `NewFromBag`.
+    pub fn push(&mut self, state: &GameState, action: Action) {
+        let drawn = match action {
+            Action::NewFromBag => Some(
+                state
+                    .drawn()
+                    .last()
+                    .expect("NewFromBag without drawn tile.")
+                    .kind,
+            ),
+            _ => None,
+        };
+        self.actions.push(RecordedAction { action, drawn });
+    }
+
+    /// Record how the game ended, if by resignation or agreed draw. Call
+    /// after `logic::resign` or accepting with `logic::respond_draw`.
+    pub fn push_end(&mut self, state: &GameState) {
+        self.end = match state.win_reason {
+            Some(WinReason::Resignation) => match state.game_over {
+                Some(Winner::Color(winner)) => Some(RecordedEnd::Resignation(winner.opponent())),
+                _ => None,
+            },
+            Some(WinReason::DrawAgreed) => Some(RecordedEnd::DrawAgreed),
+            _ => None,
+        };
+    }
+
+    /// Remove last action, e.g. after undo. Also removes the end.
+    pub fn pop(&mut self) -> Option<RecordedAction> {
+        self.end = None;
+        self.actions.pop()
+    }
+
+    fn initial_state(&self) -> GameState {
+        // Draws are replayed from the record, so any seed works when the
+        // original is not known.
+        GameState::new_with_rules(&self.variant.rules(), Some(self.seed.unwrap_or(0)))
+    }
+
+    /// State after all recorded actions.
+    pub fn replay(&self) -> GameState {
+        self.state_at(self.actions.len())
+    }
+
+    /// State after the first `ply_index` actions. Index 0 is the initial state.
+    /// The state after all actions has the end of the record too.
+    ///
+    /// Panics if the index is past the end of the record.
+    pub fn state_at(&self, ply_index: usize) -> GameState {
+        let mut state = self.initial_state();
+        for recorded in self.actions[..ply_index].iter() {
+            replay_action(&mut state, recorded);
+        }
+        if ply_index == self.actions.len() {
+            if let Some(end) = self.end {
+                replay_end(&mut state, end);
+            }
+        }
+        state
+    }
+
+    /// Winner of the recorded game, None if it was not over.
+    pub fn winner(&self) -> Option<Winner> {
+        self.replay().game_over
+    }
+
+    /// Iterate over recorded actions, with the state each action was done in.
+    pub fn iter(&self) -> ReplayIter<'_> {
+        ReplayIter {
+            state: self.initial_state(),
+            actions: self.actions.iter(),
+        }
+    }
+
+    /// Iterate over all states of the game, same as `state_at` for every index
+    /// from 0 to `len()`. Replays the game once.
+    pub fn positions(&self) -> Positions<'_> {
+        let mut state = self.initial_state();
+        if let (true, Some(end)) = (self.actions.is_empty(), self.end) {
+            replay_end(&mut state, end);
+        }
+        Positions {
+            next: Some(state),
+            actions: self.actions.iter(),
+            end: self.end,
+        }
+    }
+
+    /// Record in the text format of the module documentation.
+    pub fn to_text(&self) -> String {
+        let mut text = match self.seed {
+            Some(seed) => format!("seed {}\n", seed),
+            None => "seed -\n".to_string(),
+        };
+        if self.variant != Variant::Standard {
+            text.push_str(&format!("variant {}\n", self.variant.name()));
+        }
+        for (state, recorded) in self.iter() {
+            let line = match recorded.drawn {
+                Some(kind) => format!("draw {}", kind),
+                None => action_to_notation(&state, &recorded.action),
+            };
+            text.push_str(&line);
+            text.push('\n');
+        }
+        match self.end {
+            Some(RecordedEnd::Resignation(color)) => {
+                text.push_str(&format!("resign {}\n", color_name(color)))
+            }
+            Some(RecordedEnd::DrawAgreed) => text.push_str("draw agreed\n"),
+            None => (),
+        }
+        text
+    }
+
+    /// Load record from the text format of the module documentation. Every
+    /// action is checked against the replayed game.
+    pub fn from_text(text: &str) -> Result<GameRecord, RecordError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        let seed = match lines.next() {
+            Some((_, line)) => match line.strip_prefix("seed ").map(str::trim) {
+                Some("-") => None,
+                Some(seed) => Some(
+                    seed.parse::<u64>()
+                        .map_err(|_| RecordError::Seed(line.to_string()))?,
+                ),
+                None => return Err(RecordError::Seed(line.to_string())),
+            },
+            None => return Err(RecordError::Seed(String::new())),
+        };
+
+        let mut lines = lines.peekable();
+        let mut variant = Variant::Standard;
+        if let Some((n, name)) = lines.peek().and_then(|(n, line)| {
+            line.strip_prefix("variant ")
+                .map(|name| (*n, name.trim().to_string()))
+        }) {
+            variant = Variant::from_name(&name).ok_or(RecordError::Variant(n, name))?;
+            lines.next();
+        }
+
+        let mut record = GameRecord::new_variant(seed, variant);
+        let mut state = record.initial_state();
+        for (n, line) in lines {
+            if record.end.is_some() {
+                return Err(RecordError::AfterEnd(n));
+            }
+            let end = if line == "draw agreed" {
+                Some(RecordedEnd::DrawAgreed)
+            } else if let Some(name) = line.strip_prefix("resign ") {
+                match name.trim() {
+                    "Black" => Some(RecordedEnd::Resignation(TileColor::Black)),
+                    "White" => Some(RecordedEnd::Resignation(TileColor::White)),
+                    _ => return Err(RecordError::Resign(n, line.to_string())),
+                }
+            } else {
+                None
+            };
+            if let Some(end) = end {
+                if state.game_over.is_some() {
+                    return Err(RecordError::AfterEnd(n));
+                }
+                replay_end(&mut state, end);
+                record.end = Some(end);
+                continue;
+            }
+            let recorded = match line.strip_prefix("draw ") {
+                Some(name) => {
+                    // Checks that drawing is legal.
+                    let action =
+                        notation_to_action(&state, "draw").map_err(|e| notation_error(n, e))?;
+                    let kind = parse_tile(name.trim()).map_err(|e| notation_error(n, e))?;
+                    if !state.bag().iter().any(|t| t.kind == kind) {
+                        return Err(RecordError::NotInBag(n, kind));
+                    }
+                    RecordedAction {
+                        action,
+                        drawn: Some(kind),
+                    }
+                }
+                None => RecordedAction {
+                    action: notation_to_action(&state, line).map_err(|e| notation_error(n, e))?,
+                    drawn: None,
+                },
+            };
+            replay_action(&mut state, &recorded);
+            record.actions.push(recorded);
+        }
+
+        Ok(record)
+    }
+}
+
+/// Iterator over (state, action) pairs of a record. See `GameRecord::iter`.
+pub struct ReplayIter<'a> {
+    state: GameState,
+    actions: std::slice::Iter<'a, RecordedAction>,
+}
+
+impl<'a> Iterator for ReplayIter<'a> {
+    type Item = (GameState, RecordedAction);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let recorded = self.actions.next()?;
+        let before = self.state.clone();
+        replay_action(&mut self.state, recorded);
+        Some((before, *recorded))
+    }
+}
+
+/// Iterator over the states of a record. See `GameRecord::positions`.
+pub struct Positions<'a> {
+    next: Option<GameState>,
+    actions: std::slice::Iter<'a, RecordedAction>,
+    end: Option<RecordedEnd>,
+}
+
+impl<'a> Iterator for Positions<'a> {
+    type Item = GameState;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+        if let Some(recorded) = self.actions.next() {
+            let mut state = current.clone();
+            replay_action(&mut state, recorded);
+            if let (0, Some(end)) = (self.actions.len(), self.end) {
+                replay_end(&mut state, end);
+            }
+            self.next = Some(state);
+        }
+        Some(current)
+    }
+}
+
+fn color_name(color: TileColor) -> &'static str {
+    match color {
+        TileColor::Black => "Black",
+        TileColor::White => "White",
+    }
+}
+
+fn replay_end(state: &mut GameState, end: RecordedEnd) {
+    match end {
+        RecordedEnd::Resignation(color) => resign(state, color),
+        RecordedEnd::DrawAgreed => {
+            let color = state.ply;
+            offer_draw(state, color).expect("Recorded draw after game over.");
+            respond_draw(state, color.opponent(), true).expect("Draw offer was just made.");
+        }
+    }
+}
+
+fn replay_action(state: &mut GameState, recorded: &RecordedAction) {
+    match (recorded.action, recorded.drawn) {
+        (Action::NewFromBag, Some(kind)) => {
+            // With the original seed, the random draw is the recorded tile.
+            // Drawing that one keeps the order of the bag as in the game.
+            let mut rng = state.rng;
+            let random = rng.gen_range(0..state.bag().len());
+            let index = match state.bag()[random].kind == kind {
+                true => random,
+                false => state
+                    .bag()
+                    .iter()
+                    .position(|t| t.kind == kind)
+                    .expect("Recorded tile is not in bag."),
+            };
+            draw_from_bag(state, index);
+        }
+        (action, _) => {
+            do_unsafe_action(state, &action);
+        }
+    }
+}
@@ -0,0 +1,608 @@
+//! Compact binary format for large archives of game records, e.g. self-play
+//! data.
+//!
+//! Positions are not stored, they are reconstructed by replaying the actions.
+//! Integers are little endian, varints are unsigned LEB128.
+//!
+//! ```text
+//! header:  "RDGR", varint format version, varint tile id table version
+//! game:    0x01, varint body length, body
+//! body:    flags (bit 0: has seed, bit 1: has variant, bit 2: has end),
+//!          [u64 seed], [u8 variant id], varint action count, actions,
+//!          [u8 end]
+//! action:  varint PackedAction, [varint length, annotation bytes]
+//! footer:  0x00, u64 offset of each game, u64 game count, "RDGI"
+//! ```
+//!
+//! Game offsets in the footer are from the start of the file, so a reader can
+//! seek straight to any game. A file without footer, e.g. from an interrupted
+//! writer, can still be read from start to end.
+//!
+//! Games without variant byte are standard games. The end byte is 0 if Black
+//! resigned, 1 if White resigned and 2 for an agreed draw.
+//!
+//! Annotation blocks are reserved for analysis data. Readers skip them and
+//! writers don't write them yet.
+
+use super::super::variant::Variant;
+use super::super::TileColor;
+use super::super::{
+    Action, ActionData, ActionResult, CommandActionData, Coordinate, TileType, HEIGHT,
+    TILE_ID_TABLE_VERSION, WIDTH,
+};
+use super::{GameRecord, RecordError, RecordedAction, RecordedEnd};
+use std::fmt;
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+
+/// Current format version.
+pub const FORMAT_VERSION: u32 = 1;
+
+const MAGIC: &[u8; 4] = b"RDGR";
+const FOOTER_MAGIC: &[u8; 4] = b"RDGI";
+const GAME_TAG: u8 = 1;
+const FOOTER_TAG: u8 = 0;
+const HAS_SEED: u8 = 1;
+const HAS_VARIANT: u8 = 2;
+const HAS_END: u8 = 4;
+/// Footer ends with game count and magic.
+const FOOTER_END_LEN: u64 = 8 + 4;
+
+/// Reason why an archive could not be read or written.
+#[derive(Debug)]
+pub enum ArchiveError {
+    Io(io::Error),
+    /// Not a binary record archive.
+    BadMagic,
+    /// Archive is from an unsupported format version.
+    UnsupportedVersion(u32),
+    /// Archive uses another tile id table.
+    UnsupportedTileIds(u32),
+    /// Archive has invalid data. Has byte offset of the game.
+    Corrupt(u64),
+    /// Archive has no footer, so games can't be found by index.
+    NoIndex,
+    /// Game index past the last game.
+    NoSuchGame(u64),
+    /// Game in text archive could not be loaded.
+    Record(RecordError),
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArchiveError::Io(e) => write!(f, "{}", e),
+            ArchiveError::BadMagic => write!(f, "not a game record archive"),
+            ArchiveError::UnsupportedVersion(v) => write!(f, "unsupported format version {}", v),
+            ArchiveError::UnsupportedTileIds(v) => {
+                write!(f, "unsupported tile id table version {}", v)
+            }
+            ArchiveError::Corrupt(offset) => write!(f, "corrupt game at byte {}", offset),
+            ArchiveError::NoIndex => write!(f, "archive has no index"),
+            ArchiveError::NoSuchGame(i) => write!(f, "no game {} in archive", i),
+            ArchiveError::Record(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+impl From<io::Error> for ArchiveError {
+    fn from(e: io::Error) -> Self {
+        ArchiveError::Io(e)
+    }
+}
+
+impl From<RecordError> for ArchiveError {
+    fn from(e: RecordError) -> Self {
+        ArchiveError::Record(e)
+    }
+}
+
+/// Recorded action packed in 23 bits.
+///
+/// | Bits  | Content                                         |
+/// |-------|-------------------------------------------------|
+/// | 0-2   | Action kind                                     |
+/// | 3     | Capture                                         |
+/// | 4     | Annotation block follows                        |
+/// | 5-10  | Tile square, or drawn tile id for `NewFromBag`  |
+/// | 11-16 | Target square                                   |
+/// | 17-22 | Commanded tile square                           |
+///
+/// Squares are `y * WIDTH + x`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackedAction(pub u32);
+
+const KIND_NEW_FROM_BAG: u32 = 0;
+const KIND_PLACE_NEW: u32 = 1;
+const KIND_MOVE: u32 = 2;
+const KIND_JUMP: u32 = 3;
+const KIND_JUMP_SLIDE: u32 = 4;
+const KIND_SLIDE: u32 = 5;
+const KIND_COMMAND: u32 = 6;
+const KIND_STRIKE: u32 = 7;
+
+const CAPTURE_BIT: u32 = 1 << 3;
+const ANNOTATION_BIT: u32 = 1 << 4;
+
+fn pack_square(cord: Coordinate) -> u32 {
+    (cord.y * WIDTH + cord.x) as u32
+}
+
+fn unpack_square(bits: u32) -> Option<Coordinate> {
+    let bits = bits & 0x3f;
+    if bits >= (WIDTH * HEIGHT) as u32 {
+        return None;
+    }
+    Some(Coordinate::new(bits as u8 % WIDTH, bits as u8 / WIDTH))
+}
+
+impl PackedAction {
+    pub fn pack(recorded: &RecordedAction) -> PackedAction {
+        let capture = |result: ActionResult| match result {
+            ActionResult::Capture => CAPTURE_BIT,
+            ActionResult::Move => 0,
+        };
+        let standard = |kind: u32, data: &ActionData| {
+            kind | capture(data.result)
+                | pack_square(data.tile_pos) << 5
+                | pack_square(data.target_pos) << 11
+        };
+
+        let bits = match &recorded.action {
+            Action::NewFromBag => {
+                let id = recorded
+                    .drawn
+                    .expect("NewFromBag without drawn tile.")
+                    .to_id() as u32;
+                KIND_NEW_FROM_BAG | id << 5
+            }
+            Action::PlaceNew(cord) => KIND_PLACE_NEW | pack_square(*cord) << 5,
+            Action::Move(data) => standard(KIND_MOVE, data),
+            Action::Jump(data) => standard(KIND_JUMP, data),
+            Action::JumpSlide(data) => standard(KIND_JUMP_SLIDE, data),
+            Action::Slide(data) => standard(KIND_SLIDE, data),
+            Action::Strike(data) => standard(KIND_STRIKE, data),
+            Action::Command(data) => {
+                KIND_COMMAND
+                    | capture(data.result)
+                    | pack_square(data.tile_pos) << 5
+                    | pack_square(data.target_pos) << 11
+                    | pack_square(data.command_tile_pos) << 17
+            }
+        };
+        PackedAction(bits)
+    }
+
+    /// Unpacked action, or `None` if bits are invalid.
+    pub fn unpack(self) -> Option<RecordedAction> {
+        let bits = self.0;
+        let result = if bits & CAPTURE_BIT != 0 {
+            ActionResult::Capture
+        } else {
+            ActionResult::Move
+        };
+        let data = || -> Option<ActionData> {
+            Some(ActionData {
+                tile_pos: unpack_square(bits >> 5)?,
+                target_pos: unpack_square(bits >> 11)?,
+                result,
+            })
+        };
+
+        let action = match bits & 0x7 {
+            KIND_NEW_FROM_BAG => {
+                let drawn = TileType::from_id(((bits >> 5) & 0x3f) as u8)?;
+                return Some(RecordedAction {
+                    action: Action::NewFromBag,
+                    drawn: Some(drawn),
+                });
+            }
+            KIND_PLACE_NEW => Action::PlaceNew(unpack_square(bits >> 5)?),
+            KIND_MOVE => Action::Move(data()?),
+            KIND_JUMP => Action::Jump(data()?),
+            KIND_JUMP_SLIDE => Action::JumpSlide(data()?),
+            KIND_SLIDE => Action::Slide(data()?),
+            KIND_STRIKE => Action::Strike(data()?),
+            _ => Action::Command(CommandActionData {
+                tile_pos: unpack_square(bits >> 5)?,
+                command_tile_pos: unpack_square(bits >> 17)?,
+                target_pos: unpack_square(bits >> 11)?,
+                result,
+            }),
+        };
+        Some(RecordedAction {
+            action,
+            drawn: None,
+        })
+    }
+
+    /// True if an annotation block follows the action.
+    pub fn has_annotation(self) -> bool {
+        self.0 & ANNOTATION_BIT != 0
+    }
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Read varint from slice at position. Returns `None` if slice ends or the
+/// value does not fit in u64.
+fn read_varint_slice(bytes: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn read_varint<R: Read>(r: &mut R) -> Result<u64, ArchiveError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8];
+        r.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(ArchiveError::Io(io::ErrorKind::InvalidData.into()))
+}
+
+/// Streaming writer. Holds one game in memory at a time, and the offsets of
+/// written games for the footer.
+pub struct BinaryWriter<W: Write> {
+    writer: W,
+    written: u64,
+    offsets: Vec<u64>,
+}
+
+impl<W: Write> BinaryWriter<W> {
+    /// Writes the header.
+    pub fn new(mut writer: W) -> io::Result<BinaryWriter<W>> {
+        let mut header = MAGIC.to_vec();
+        write_varint(&mut header, FORMAT_VERSION as u64);
+        write_varint(&mut header, TILE_ID_TABLE_VERSION as u64);
+        writer.write_all(&header)?;
+
+        Ok(BinaryWriter {
+            writer,
+            written: header.len() as u64,
+            offsets: Vec::new(),
+        })
+    }
+
+    pub fn write_game(&mut self, record: &GameRecord) -> io::Result<()> {
+        let mut flags = 0;
+        if record.seed.is_some() {
+            flags |= HAS_SEED;
+        }
+        if record.variant != Variant::Standard {
+            flags |= HAS_VARIANT;
+        }
+        if record.end.is_some() {
+            flags |= HAS_END;
+        }
+
+        let mut body = vec![flags];
+        if let Some(seed) = record.seed {
+            body.extend_from_slice(&seed.to_le_bytes());
+        }
+        if record.variant != Variant::Standard {
+            body.push(record.variant.to_id());
+        }
+        write_varint(&mut body, record.actions.len() as u64);
+        for recorded in record.actions.iter() {
+            write_varint(&mut body, PackedAction::pack(recorded).0 as u64);
+        }
+        match record.end {
+            Some(RecordedEnd::Resignation(TileColor::Black)) => body.push(0),
+            Some(RecordedEnd::Resignation(TileColor::White)) => body.push(1),
+            Some(RecordedEnd::DrawAgreed) => body.push(2),
+            None => (),
+        }
+
+        let mut game = vec![GAME_TAG];
+        write_varint(&mut game, body.len() as u64);
+        game.extend_from_slice(&body);
+        self.writer.write_all(&game)?;
+
+        self.offsets.push(self.written);
+        self.written += game.len() as u64;
+        Ok(())
+    }
+
+    /// Writes the footer and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let mut footer = vec![FOOTER_TAG];
+        for offset in self.offsets.iter() {
+            footer.extend_from_slice(&offset.to_le_bytes());
+        }
+        footer.extend_from_slice(&(self.offsets.len() as u64).to_le_bytes());
+        footer.extend_from_slice(FOOTER_MAGIC);
+        self.writer.write_all(&footer)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Streaming reader. Iterates over games, holding one game in memory at a
+/// time.
+pub struct BinaryReader<R: Read> {
+    reader: R,
+    /// Offset of next byte to read.
+    position: u64,
+    done: bool,
+}
+
+impl<R: Read> BinaryReader<R> {
+    /// Reads and checks the header.
+    pub fn new(mut reader: R) -> Result<BinaryReader<R>, ArchiveError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(ArchiveError::BadMagic);
+        }
+
+        // Varints are counted to know the offset of the first game.
+        let mut counted = CountingReader {
+            reader: &mut reader,
+            count: magic.len() as u64,
+        };
+        let version = read_varint(&mut counted)?;
+        if version != FORMAT_VERSION as u64 {
+            return Err(ArchiveError::UnsupportedVersion(version as u32));
+        }
+        let tile_ids = read_varint(&mut counted)?;
+        if tile_ids != TILE_ID_TABLE_VERSION as u64 {
+            return Err(ArchiveError::UnsupportedTileIds(tile_ids as u32));
+        }
+        let position = counted.count;
+
+        Ok(BinaryReader {
+            reader,
+            position,
+            done: false,
+        })
+    }
+
+    /// Read next game. Returns `None` at the footer or end of file.
+    pub fn read_game(&mut self) -> Result<Option<GameRecord>, ArchiveError> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let offset = self.position;
+        let mut tag = [0u8];
+        match self.reader.read_exact(&mut tag) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                self.done = true;
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        }
+        if tag[0] == FOOTER_TAG {
+            self.done = true;
+            return Ok(None);
+        }
+        if tag[0] != GAME_TAG {
+            return Err(ArchiveError::Corrupt(offset));
+        }
+
+        let mut counted = CountingReader {
+            reader: &mut self.reader,
+            count: 1,
+        };
+        let len = read_varint(&mut counted)?;
+        let header_len = counted.count;
+        let mut body = vec![0u8; len as usize];
+        self.reader.read_exact(&mut body)?;
+        self.position += header_len + len;
+
+        parse_body(&body)
+            .map(Some)
+            .ok_or(ArchiveError::Corrupt(offset))
+    }
+}
+
+impl<R: Read + Seek> BinaryReader<R> {
+    /// Offsets of all games, from the footer.
+    fn index(&mut self) -> Result<Vec<u64>, ArchiveError> {
+        let end = self.reader.seek(SeekFrom::End(0))?;
+        if end < FOOTER_END_LEN {
+            return Err(ArchiveError::NoIndex);
+        }
+        self.reader.seek(SeekFrom::Start(end - FOOTER_END_LEN))?;
+        let mut count = [0u8; 8];
+        let mut magic = [0u8; 4];
+        self.reader.read_exact(&mut count)?;
+        self.reader.read_exact(&mut magic)?;
+        if &magic != FOOTER_MAGIC {
+            return Err(ArchiveError::NoIndex);
+        }
+
+        let count = u64::from_le_bytes(count);
+        let start = count
+            .checked_mul(8)
+            .and_then(|len| (end - FOOTER_END_LEN).checked_sub(len))
+            .ok_or(ArchiveError::NoIndex)?;
+        self.reader.seek(SeekFrom::Start(start))?;
+        let mut offsets = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let mut offset = [0u8; 8];
+            self.reader.read_exact(&mut offset)?;
+            offsets.push(u64::from_le_bytes(offset));
+        }
+        Ok(offsets)
+    }
+
+    /// Number of games, from the footer.
+    pub fn game_count(&mut self) -> Result<u64, ArchiveError> {
+        let count = self.index()?.len() as u64;
+        self.seek_to(self.position)?;
+        Ok(count)
+    }
+
+    /// Read game by index, using the footer. Reading continues from the game
+    /// after it.
+    pub fn read_game_at(&mut self, index: u64) -> Result<GameRecord, ArchiveError> {
+        let offsets = self.index()?;
+        let offset = *offsets
+            .get(index as usize)
+            .ok_or(ArchiveError::NoSuchGame(index))?;
+        self.seek_to(offset)?;
+        self.read_game()?.ok_or(ArchiveError::Corrupt(offset))
+    }
+
+    fn seek_to(&mut self, offset: u64) -> Result<(), ArchiveError> {
+        self.reader.seek(SeekFrom::Start(offset))?;
+        self.position = offset;
+        self.done = false;
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for BinaryReader<R> {
+    type Item = Result<GameRecord, ArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_game().transpose()
+    }
+}
+
+struct CountingReader<'a, R: Read> {
+    reader: &'a mut R,
+    count: u64,
+}
+
+impl<'a, R: Read> Read for CountingReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+fn parse_body(body: &[u8]) -> Option<GameRecord> {
+    let mut pos = 0;
+    let flags = *body.first()?;
+    pos += 1;
+
+    let seed = if flags & HAS_SEED != 0 {
+        let bytes = body.get(pos..pos + 8)?;
+        pos += 8;
+        Some(u64::from_le_bytes(bytes.try_into().ok()?))
+    } else {
+        None
+    };
+
+    let variant = if flags & HAS_VARIANT != 0 {
+        let id = *body.get(pos)?;
+        pos += 1;
+        Variant::from_id(id)?
+    } else {
+        Variant::Standard
+    };
+
+    let count = read_varint_slice(body, &mut pos)?;
+    let mut record = GameRecord::new_variant(seed, variant);
+    for _ in 0..count {
+        let packed = PackedAction(u32::try_from(read_varint_slice(body, &mut pos)?).ok()?);
+        if packed.has_annotation() {
+            let len = read_varint_slice(body, &mut pos)? as usize;
+            pos = pos.checked_add(len).filter(|end| *end <= body.len())?;
+        }
+        record.actions.push(packed.unpack()?);
+    }
+    if flags & HAS_END != 0 {
+        record.end = Some(match *body.get(pos)? {
+            0 => RecordedEnd::Resignation(TileColor::Black),
+            1 => RecordedEnd::Resignation(TileColor::White),
+            2 => RecordedEnd::DrawAgreed,
+            _ => return None,
+        });
+    }
+    Some(record)
+}
+
+/// Streaming reader of text archives, i.e. text records one after another.
+/// Each game starts at its `seed` line.
+pub struct TextArchiveReader<R: BufRead> {
+    lines: io::Lines<R>,
+    /// Seed line of next game, already read.
+    next_seed: Option<String>,
+}
+
+impl<R: BufRead> TextArchiveReader<R> {
+    pub fn new(reader: R) -> TextArchiveReader<R> {
+        TextArchiveReader {
+            lines: reader.lines(),
+            next_seed: None,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for TextArchiveReader<R> {
+    type Item = Result<GameRecord, ArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut text = self.next_seed.take().unwrap_or_default();
+        loop {
+            match self.lines.next() {
+                Some(Ok(line)) => {
+                    if line.trim_start().starts_with("seed") && !text.trim().is_empty() {
+                        self.next_seed = Some(line + "\n");
+                        break;
+                    }
+                    text.push_str(&line);
+                    text.push('\n');
+                }
+                Some(Err(e)) => return Some(Err(e.into())),
+                None => break,
+            }
+        }
+
+        if text.trim().is_empty() {
+            return None;
+        }
+        Some(GameRecord::from_text(&text).map_err(ArchiveError::from))
+    }
+}
+
+/// Convert text archive to binary. Returns number of games.
+pub fn text_to_binary<R: BufRead, W: Write>(reader: R, writer: W) -> Result<u64, ArchiveError> {
+    let mut binary = BinaryWriter::new(writer)?;
+    let mut games = 0;
+    for record in TextArchiveReader::new(reader) {
+        binary.write_game(&record?)?;
+        games += 1;
+    }
+    binary.finish()?;
+    Ok(games)
+}
+
+/// Convert binary archive to text, games separated by an empty line. Returns
+/// number of games.
+pub fn binary_to_text<R: Read, W: Write>(reader: R, mut writer: W) -> Result<u64, ArchiveError> {
+    let mut games = 0;
+    for record in BinaryReader::new(reader)? {
+        if games > 0 {
+            writer.write_all(b"\n")?;
+        }
+        writer.write_all(record?.to_text().as_bytes())?;
+        games += 1;
+    }
+    writer.flush()?;
+    Ok(games)
+}
@@ -0,0 +1,140 @@
+//! Saved games, the whole `GameState` in a versioned envelope.
+//!
+//! Unlike `json` positions, saves keep everything needed to resume the game
+//! exactly: order of the bags, state of the random generator, effects, and
+//! the history for `undo_action`. Saves of another `SAVE_VERSION` are
+//! rejected instead of being loaded wrong. Bump it whenever the serialized
+//! form of `GameState` changes in a way `serde(default)` can't cover.
+//!
+//! ```
+//! use rusty_duke_logic::logic::save::{from_save_json, to_save_json};
+//! use rusty_duke_logic::logic::{do_action, get_actions, GameState};
+//!
+//! let mut state = GameState::new_seeded(1);
+//! let action = get_actions(&state)[0];
+//! do_action(&mut state, &action).unwrap();
+//!
+//! let loaded = from_save_json(&to_save_json(&state)).unwrap();
+//! assert_eq!(get_actions(&loaded), get_actions(&state));
+//! ```
+
+use super::GameState;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Current version of saves. Version 2 stores the color of the tile imposing
+/// each square effect.
+pub const SAVE_VERSION: u8 = 2;
+
+/// Game state with the version of the save.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub version: u8,
+    pub state: GameState,
+}
+
+/// Only the version, read before the rest of a save.
+#[cfg(feature = "json")]
+#[derive(Deserialize)]
+struct Version {
+    version: u8,
+}
+
+/// Reason why a save could not be loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveError {
+    /// Save is not valid, or not a save at all.
+    Malformed(String),
+    /// Save is from another version, see `SAVE_VERSION`.
+    UnsupportedVersion(u8),
+    /// Tile type this version doesn't know, e.g. of a newer version or
+    /// another ruleset.
+    UnsupportedContent(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Malformed(e) => write!(f, "invalid save: {}", e),
+            SaveError::UnsupportedVersion(v) => {
+                write!(
+                    f,
+                    "save version {} is not supported, expected {}",
+                    v, SAVE_VERSION
+                )
+            }
+            SaveError::UnsupportedContent(name) => write!(f, "tile {} is not supported", name),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl SavedGame {
+    pub fn new(state: GameState) -> SavedGame {
+        SavedGame {
+            version: SAVE_VERSION,
+            state,
+        }
+    }
+
+    /// State of the save, if it has the current version. The hash is
+    /// recomputed, saves don't need to have it.
+    pub fn into_state(self) -> Result<GameState, SaveError> {
+        if self.version != SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion(self.version));
+        }
+        let mut state = self.state;
+        state.rehash();
+        Ok(state)
+    }
+}
+
+/// Save game state as JSON.
+#[cfg(feature = "json")]
+pub fn to_save_json(state: &GameState) -> String {
+    serde_json::to_string(&SavedGame::new(state.clone())).expect("Saves are always serializable.")
+}
+
+/// Load game state saved by `to_save_json`. The version is checked first, so
+/// saves of other versions fail with `UnsupportedVersion` even if the rest
+/// doesn't parse.
+#[cfg(feature = "json")]
+pub fn from_save_json(json: &str) -> Result<GameState, SaveError> {
+    let malformed = |e: serde_json::Error| SaveError::Malformed(e.to_string());
+    let version: Version = serde_json::from_str(json).map_err(malformed)?;
+    if version.version != SAVE_VERSION {
+        return Err(SaveError::UnsupportedVersion(version.version));
+    }
+
+    let saved: SavedGame =
+        serde_json::from_str(json).map_err(|e| match unknown_tile_type(json) {
+            Some(name) => SaveError::UnsupportedContent(name),
+            None => malformed(e),
+        })?;
+    saved.into_state()
+}
+
+/// First tile type in json that is not a `TileType`. Tiles are the objects
+/// with a kind and a color. Only looked for when a save doesn't load, to tell
+/// saves of newer versions from broken ones.
+#[cfg(feature = "json")]
+fn unknown_tile_type(json: &str) -> Option<String> {
+    use super::TileType;
+    use serde_json::Value;
+
+    fn find(value: &Value) -> Option<String> {
+        match value {
+            Value::Object(fields) => match (fields.get("kind"), fields.get("color")) {
+                (Some(Value::String(kind)), Some(_)) if kind.parse::<TileType>().is_err() => {
+                    Some(kind.clone())
+                }
+                _ => fields.values().find_map(find),
+            },
+            Value::Array(values) => values.iter().find_map(find),
+            _ => None,
+        }
+    }
+
+    find(&serde_json::from_str(json).ok()?)
+}
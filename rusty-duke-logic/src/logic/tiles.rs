@@ -0,0 +1,356 @@
+//! Tile definitions, from the data file `tiles.toml` embedded in the crate or
+//! from a custom file loaded with `load_tile_definitions_from`.
+//!
+//! See `tiles.toml` for the format. Definitions are checked when parsed:
+//! unknown tile types and action kinds, offsets more than `MAX_OFFSET` squares
+//! away and actions declared twice on a side are errors, reported with the
+//! tile name. `load_tile_definitions_from` also runs
+//! `validate::validate_ruleset`, and rejects definitions with errors, e.g.
+//! tiles that can't be played.
+//!
+//! Files of newer versions may define tile types this version doesn't know.
+//! `parse_tile_definitions` fails on them with
+//! `TileDefinitionError::UnknownTileType`, the compatibility shims
+//! `parse_tile_definitions_compat` and `load_tile_definitions_compat_from`
+//! skip them with a warning instead.
+//!
+//! ```
+//! use rusty_duke_logic::logic::tiles::{parse_tile_definitions, TileDefinitionError};
+//! use rusty_duke_logic::logic::TileType;
+//!
+//! let definitions = parse_tile_definitions(
+//!     r#"
+//!     [Footman]
+//!     front = [{ kind = "Move", x = 0, y = 1 }]
+//!     back = [{ kind = "Strike", x = 0, y = 2 }]
+//!     "#,
+//! )
+//! .unwrap();
+//! assert_eq!(definitions.actions[&TileType::Footman].back.len(), 1);
+//!
+//! let error = parse_tile_definitions(
+//!     r#"
+//!     [Pikeman]
+//!     front = [{ kind = "Move", x = 0, y = 6 }]
+//!     back = []
+//!     "#,
+//! )
+//! .unwrap_err();
+//! assert!(matches!(error, TileDefinitionError::Tile { tile, .. } if tile == "Pikeman"));
+//! ```
+
+use super::validate::{self, Diagnostic, RulesetSource};
+use super::variant::{Expansion, GameRules};
+use super::{
+    ActionType, AvailableAction, AvailableActions, AvailableEffect, AvailableEffects, Effect,
+    Offset, TileType, UnknownTileType,
+};
+use log::warn;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+use strum::IntoEnumIterator;
+
+/// Built in tile definitions.
+pub const BUILTIN: &str = include_str!("tiles.toml");
+
+/// Offsets further away than this, in x or y, are errors.
+pub const MAX_OFFSET: i8 = 5;
+
+/// Actions and effects of tile types, as seen by Black.
+#[derive(Debug, Clone, Default)]
+pub struct TileDefinitions {
+    pub actions: HashMap<TileType, AvailableActions>,
+    /// Only tile types with effects are here.
+    pub effects: HashMap<TileType, AvailableEffects>,
+}
+
+/// Reason why tile definitions could not be loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TileDefinitionError {
+    /// File could not be read.
+    Io(String),
+    /// File is not valid TOML.
+    Syntax(String),
+    /// Tile type of a definition is not a `TileType`, see
+    /// `parse_tile_definitions_compat`.
+    UnknownTileType(String),
+    /// Definition of tile is invalid.
+    Tile { tile: String, reason: String },
+    /// Definitions parse, but `validate::validate_ruleset` found errors.
+    /// Holds all diagnostics, warnings too.
+    Invalid(Vec<Diagnostic>),
+    /// Definitions are in use already, see `load_tile_definitions_from`.
+    InUse,
+}
+
+impl fmt::Display for TileDefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TileDefinitionError::Io(e) => write!(f, "{}", e),
+            TileDefinitionError::Syntax(e) => write!(f, "{}", e),
+            TileDefinitionError::UnknownTileType(tile) => write!(f, "unknown tile type {}", tile),
+            TileDefinitionError::Tile { tile, reason } => write!(f, "tile {}: {}", tile, reason),
+            TileDefinitionError::Invalid(diagnostics) => {
+                let errors: Vec<String> = diagnostics
+                    .iter()
+                    .filter(|d| d.severity() == validate::Severity::Error)
+                    .map(|d| d.to_string())
+                    .collect();
+                write!(f, "{}", errors.join("\n"))
+            }
+            TileDefinitionError::InUse => write!(f, "tile definitions are already in use"),
+        }
+    }
+}
+
+impl std::error::Error for TileDefinitionError {}
+
+/// Definitions loaded by `load_tile_definitions_from`, and whether
+/// definitions were handed out to `TILE_ACTIONS` or `TILE_EFFECTS`.
+struct Installed {
+    custom: Option<TileDefinitions>,
+    in_use: bool,
+}
+
+static INSTALLED: Mutex<Installed> = Mutex::new(Installed {
+    custom: None,
+    in_use: false,
+});
+
+/// Parse tile definitions in the format of `tiles.toml`.
+pub fn parse_tile_definitions(text: &str) -> Result<TileDefinitions, TileDefinitionError> {
+    parse(text, false).map(|(definitions, _)| definitions)
+}
+
+/// Same as `parse_tile_definitions`, but definitions of unknown tile types,
+/// e.g. from a newer version, are skipped with a warning. Also returns the
+/// names of the skipped tile types.
+pub fn parse_tile_definitions_compat(
+    text: &str,
+) -> Result<(TileDefinitions, Vec<String>), TileDefinitionError> {
+    parse(text, true)
+}
+
+fn parse(
+    text: &str,
+    skip_unknown: bool,
+) -> Result<(TileDefinitions, Vec<String>), TileDefinitionError> {
+    let table: toml::Table = text
+        .parse()
+        .map_err(|e: toml::de::Error| TileDefinitionError::Syntax(e.to_string()))?;
+
+    let mut definitions = TileDefinitions::default();
+    let mut skipped = Vec::new();
+    for (name, value) in table.iter() {
+        let error = |reason: String| TileDefinitionError::Tile {
+            tile: name.clone(),
+            reason,
+        };
+        let kind = match name.parse::<TileType>() {
+            Ok(kind) => kind,
+            Err(UnknownTileType(name)) if skip_unknown => {
+                warn!("Skipped definition of unknown tile type {}.", name);
+                skipped.push(name);
+                continue;
+            }
+            Err(UnknownTileType(name)) => return Err(TileDefinitionError::UnknownTileType(name)),
+        };
+        let tile = value
+            .as_table()
+            .ok_or_else(|| error("not a table".to_string()))?;
+        if let Some(key) = tile
+            .keys()
+            .find(|key| !["front", "back", "effects"].contains(&key.as_str()))
+        {
+            return Err(error(format!("unknown key {}", key)));
+        }
+
+        let front = parse_side(tile, "front", action_type).map_err(error)?;
+        let back = parse_side(tile, "back", action_type).map_err(error)?;
+        let action = |(kind, offset)| AvailableAction { kind, offset };
+        definitions.actions.insert(
+            kind,
+            AvailableActions {
+                front: front.into_iter().map(action).collect(),
+                back: back.into_iter().map(action).collect(),
+            },
+        );
+
+        if let Some(effects) = tile.get("effects") {
+            let effects = effects
+                .as_table()
+                .ok_or_else(|| error("effects is not a table".to_string()))?;
+            let effects_error = |reason| error(format!("effects: {}", reason));
+            let front = parse_side(effects, "front", effect).map_err(effects_error)?;
+            let back = parse_side(effects, "back", effect).map_err(effects_error)?;
+            let effect = |(kind, offset)| AvailableEffect { kind, offset };
+            definitions.effects.insert(
+                kind,
+                AvailableEffects {
+                    front: front.into_iter().map(effect).collect(),
+                    back: back.into_iter().map(effect).collect(),
+                },
+            );
+        }
+    }
+
+    Ok((definitions, skipped))
+}
+
+/// Load tile definitions from file, to be used instead of the built in ones
+/// by every game of the process, e.g. games from `builder::GameStateBuilder`
+/// or `GameState::with_setup`.
+///
+/// Definitions are validated with the tile set of the full game and every
+/// expansion, and rejected with `TileDefinitionError::Invalid` on any error.
+/// Warnings are not reported, see `validate::validate_ruleset`.
+///
+/// Has to be called before the first game is created. Fails with
+/// `TileDefinitionError::InUse` once `TILE_ACTIONS` or `TILE_EFFECTS` is used.
+pub fn load_tile_definitions_from<P: AsRef<Path>>(path: P) -> Result<(), TileDefinitionError> {
+    let text = std::fs::read_to_string(path).map_err(|e| TileDefinitionError::Io(e.to_string()))?;
+    install(parse_tile_definitions(&text)?)
+}
+
+/// Same as `load_tile_definitions_from`, but definitions of unknown tile
+/// types are skipped, see `parse_tile_definitions_compat`. Returns the names
+/// of the skipped tile types.
+pub fn load_tile_definitions_compat_from<P: AsRef<Path>>(
+    path: P,
+) -> Result<Vec<String>, TileDefinitionError> {
+    let text = std::fs::read_to_string(path).map_err(|e| TileDefinitionError::Io(e.to_string()))?;
+    let (definitions, skipped) = parse_tile_definitions_compat(&text)?;
+    install(definitions)?;
+    Ok(skipped)
+}
+
+/// Validate definitions, and use them instead of the built in ones.
+fn install(definitions: TileDefinitions) -> Result<(), TileDefinitionError> {
+    let diagnostics = validate::validate_ruleset(&RulesetSource::for_definitions(
+        &definitions,
+        &all_tiles_rules(),
+    ));
+    if validate::has_errors(&diagnostics) {
+        return Err(TileDefinitionError::Invalid(diagnostics));
+    }
+
+    let mut installed = INSTALLED.lock().expect("Tile definitions lock poisoned.");
+    if installed.in_use {
+        return Err(TileDefinitionError::InUse);
+    }
+    installed.custom = Some(definitions);
+    Ok(())
+}
+
+/// Standard rules, with the tiles of every expansion in the bag.
+fn all_tiles_rules() -> GameRules {
+    let mut rules = GameRules::standard();
+    rules
+        .bag
+        .extend(Expansion::ArthurianLegends.tiles().iter().copied());
+    rules
+}
+
+/// Built in tile definitions, from `BUILTIN`.
+pub fn builtin() -> TileDefinitions {
+    let definitions = parse_tile_definitions(BUILTIN).expect("Invalid built in tile definitions.");
+
+    // Every tile type has to be defined, otherwise lookups will fail.
+    debug_assert!(
+        TileType::iter().all(|kind| definitions.actions.contains_key(&kind)),
+        "Tile type without actions."
+    );
+
+    definitions
+}
+
+/// Definitions for `TILE_ACTIONS` and `TILE_EFFECTS`. Custom definitions if
+/// loaded, built in otherwise.
+pub(super) fn definitions_in_use() -> TileDefinitions {
+    let mut installed = INSTALLED.lock().expect("Tile definitions lock poisoned.");
+    installed.in_use = true;
+    match &installed.custom {
+        Some(definitions) => definitions.clone(),
+        None => builtin(),
+    }
+}
+
+fn action_type(name: &str) -> Option<ActionType> {
+    match name {
+        "Move" => Some(ActionType::Move),
+        "Jump" => Some(ActionType::Jump),
+        "JumpSlide" => Some(ActionType::JumpSlide),
+        "Slide" => Some(ActionType::Slide),
+        "Command" => Some(ActionType::Command),
+        "Strike" => Some(ActionType::Strike),
+        _ => None,
+    }
+}
+
+fn effect(name: &str) -> Option<Effect> {
+    match name {
+        "Dread" => Some(Effect::Dread),
+        "Defence" => Some(Effect::Defence),
+        _ => None,
+    }
+}
+
+/// Side of a tile table, as (kind, offset) pairs in file order. Error is the
+/// reason only, the caller adds the tile.
+fn parse_side<K: PartialEq + fmt::Debug>(
+    table: &toml::Table,
+    side: &str,
+    parse_kind: fn(&str) -> Option<K>,
+) -> Result<Vec<(K, Offset)>, String> {
+    let entries = table
+        .get(side)
+        .ok_or_else(|| format!("missing {}", side))?
+        .as_array()
+        .ok_or_else(|| format!("{} is not an array", side))?;
+
+    let mut parsed: Vec<(K, Offset)> = Vec::new();
+    for entry in entries.iter() {
+        let entry = entry
+            .as_table()
+            .ok_or_else(|| format!("{}: entry is not a table", side))?;
+        if let Some(key) = entry
+            .keys()
+            .find(|key| !["kind", "x", "y"].contains(&key.as_str()))
+        {
+            return Err(format!("{}: unknown key {}", side, key));
+        }
+
+        let name = entry
+            .get("kind")
+            .and_then(|kind| kind.as_str())
+            .ok_or_else(|| format!("{}: missing kind", side))?;
+        let kind = parse_kind(name).ok_or_else(|| format!("{}: unknown kind {}", side, name))?;
+        let coordinate = |axis: &str| {
+            entry
+                .get(axis)
+                .and_then(|value| value.as_integer())
+                .ok_or_else(|| format!("{}: missing {}", side, axis))
+        };
+        let (x, y) = (coordinate("x")?, coordinate("y")?);
+        let limit = MAX_OFFSET as i64;
+        if x.abs() > limit || y.abs() > limit {
+            return Err(format!("{}: offset ({}, {}) out of range", side, x, y));
+        }
+
+        let offset = Offset {
+            x: x as i8,
+            y: y as i8,
+        };
+        if parsed.iter().any(|(k, o)| *k == kind && *o == offset) {
+            return Err(format!(
+                "{}: {:?} at ({}, {}) declared twice",
+                side, kind, x, y
+            ));
+        }
+        parsed.push((kind, offset));
+    }
+
+    Ok(parsed)
+}
@@ -0,0 +1,368 @@
+//! Validation of tile sets (rulesets).
+//!
+//! Custom tile definitions are easy to get wrong. `validate_ruleset` checks a
+//! tile set for definitions that can't work (errors) or that are legal but
+//! suspicious (warnings).
+
+use super::tiles::TileDefinitions;
+use super::variant::{GameRules, START_TILES};
+use super::{
+    ActionType, AvailableAction, AvailableActions, AvailableEffects, Offset, TileType, MAX_HEIGHT,
+    MAX_WIDTH, NO_EFFECTS, TILE_ACTIONS, TILE_EFFECTS,
+};
+use std::collections::HashMap;
+use std::fmt;
+
+/// How bad a diagnostic is. Errors make the ruleset unplayable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Side of a tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Front,
+    Back,
+}
+
+/// Kind of problem found in a ruleset. See `code()` for the stable codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// Tile in set, but no actions are defined for its type.
+    UndefinedTile,
+    /// Side without any actions.
+    EmptySide,
+    /// Offset (0, 0) points at the tile itself.
+    ZeroOffset,
+    /// Offset can never be reached, not even on the largest board.
+    OffsetOutOfRange,
+    /// Same action kind and offset declared twice on one side.
+    DuplicateOffset,
+    /// Slide offset is not a single step, or jump slide is not a straight line.
+    SlideNotRay,
+    /// Side declares Command, but has less than two command offsets.
+    CommandWithoutOffsets,
+    /// Tile set has no Duke, or more than one.
+    DukeCount,
+    /// Tile set lacks the two Footmen deployed at game start.
+    MissingStartingTiles,
+    /// Move offset is not on a straight line, so the move has no path.
+    MoveNotStraight,
+    /// Two different non-command action kinds on the same offset.
+    ConflictingOffset,
+    /// Back side can do strictly less than front side.
+    BackStrictlyWorse,
+}
+
+impl DiagnosticKind {
+    /// Stable code. Never reuse or renumber codes.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DiagnosticKind::UndefinedTile => "E001",
+            DiagnosticKind::EmptySide => "E002",
+            DiagnosticKind::ZeroOffset => "E003",
+            DiagnosticKind::OffsetOutOfRange => "E004",
+            DiagnosticKind::DuplicateOffset => "E005",
+            DiagnosticKind::SlideNotRay => "E006",
+            DiagnosticKind::CommandWithoutOffsets => "E007",
+            DiagnosticKind::DukeCount => "E008",
+            DiagnosticKind::MissingStartingTiles => "E009",
+            DiagnosticKind::MoveNotStraight => "E010",
+            DiagnosticKind::ConflictingOffset => "W001",
+            DiagnosticKind::BackStrictlyWorse => "W002",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            DiagnosticKind::ConflictingOffset | DiagnosticKind::BackStrictlyWorse => {
+                Severity::Warning
+            }
+            _ => Severity::Error,
+        }
+    }
+
+    /// Suggestion on how to fix the problem.
+    pub fn suggestion(&self) -> &'static str {
+        match self {
+            DiagnosticKind::UndefinedTile => "Define actions for the tile type.",
+            DiagnosticKind::EmptySide => "Add at least one action to the side.",
+            DiagnosticKind::ZeroOffset => "Remove the action.",
+            DiagnosticKind::OffsetOutOfRange => "Use an offset smaller than the board.",
+            DiagnosticKind::DuplicateOffset => "Remove one of the actions.",
+            DiagnosticKind::SlideNotRay => {
+                "Use a single step for slides, or a straight line for jump slides."
+            }
+            DiagnosticKind::CommandWithoutOffsets => {
+                "Add command offsets, the commanded tile needs somewhere to go."
+            }
+            DiagnosticKind::DukeCount => "Include exactly one Duke.",
+            DiagnosticKind::MissingStartingTiles => "Include at least two Footmen.",
+            DiagnosticKind::MoveNotStraight => "Use Jump for offsets off straight lines.",
+            DiagnosticKind::ConflictingOffset => "Use one action kind per offset.",
+            DiagnosticKind::BackStrictlyWorse => "Check that the sides are not swapped.",
+        }
+    }
+}
+
+/// A problem found in a ruleset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub tile: Option<TileType>,
+    pub side: Option<Side>,
+    pub offset: Option<Offset>,
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity() {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}[{}]: {:?}", severity, self.kind.code(), self.kind)?;
+        if let Some(tile) = self.tile {
+            write!(f, ", tile: {}", tile)?;
+        }
+        if let Some(side) = self.side {
+            write!(f, ", side: {:?}", side)?;
+        }
+        if let Some(offset) = &self.offset {
+            write!(f, ", offset: ({}, {})", offset.x, offset.y)?;
+        }
+        write!(f, ". {}", self.kind.suggestion())
+    }
+}
+
+/// Everything needed to validate a ruleset.
+pub struct RulesetSource<'a> {
+    pub actions: &'a HashMap<TileType, AvailableActions>,
+    pub effects: &'a HashMap<TileType, AvailableEffects>,
+    /// All tiles of one player, i.e. initial tiles and bag.
+    pub tiles: Vec<TileType>,
+}
+
+impl Default for RulesetSource<'static> {
+    /// Built in tile set.
+    fn default() -> Self {
+        RulesetSource::for_rules(&GameRules::standard())
+    }
+}
+
+impl RulesetSource<'static> {
+    /// Built in tile definitions, with the tile set of a variant.
+    pub fn for_rules(rules: &GameRules) -> RulesetSource<'static> {
+        RulesetSource {
+            actions: &TILE_ACTIONS,
+            effects: &TILE_EFFECTS,
+            tiles: tile_set(rules),
+        }
+    }
+}
+
+impl<'a> RulesetSource<'a> {
+    /// Tile definitions, e.g. from a custom file, with the tile set of a
+    /// variant. Unlike `for_rules`, does not use `TILE_ACTIONS`.
+    pub fn for_definitions(
+        definitions: &'a TileDefinitions,
+        rules: &GameRules,
+    ) -> RulesetSource<'a> {
+        RulesetSource {
+            actions: &definitions.actions,
+            effects: &definitions.effects,
+            tiles: tile_set(rules),
+        }
+    }
+}
+
+/// All tiles of one player at game start.
+fn tile_set(rules: &GameRules) -> Vec<TileType> {
+    START_TILES
+        .iter()
+        .chain(rules.bag.iter())
+        .copied()
+        .collect()
+}
+
+/// Validate ruleset. Returns all diagnostics, errors and warnings.
+pub fn validate_ruleset(source: &RulesetSource) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    // Tile set
+    let dukes = source
+        .tiles
+        .iter()
+        .filter(|kind| **kind == TileType::Duke)
+        .count();
+    if dukes != 1 {
+        diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::DukeCount,
+            tile: None,
+            side: None,
+            offset: None,
+        });
+    }
+
+    let footmen = source
+        .tiles
+        .iter()
+        .filter(|kind| **kind == TileType::Footman)
+        .count();
+    if footmen < 2 {
+        diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::MissingStartingTiles,
+            tile: None,
+            side: None,
+            offset: None,
+        });
+    }
+
+    // Tile definitions. Check each type once, in a stable order.
+    let mut kinds: Vec<TileType> = source.tiles.clone();
+    kinds.extend(source.actions.keys());
+    kinds.sort_by_key(|kind| *kind as usize);
+    kinds.dedup();
+
+    for kind in kinds {
+        let actions = match source.actions.get(&kind) {
+            Some(actions) => actions,
+            None => {
+                diagnostics.push(Diagnostic {
+                    kind: DiagnosticKind::UndefinedTile,
+                    tile: Some(kind),
+                    side: None,
+                    offset: None,
+                });
+                continue;
+            }
+        };
+        let effects = source.effects.get(&kind).unwrap_or(&NO_EFFECTS);
+
+        validate_side(
+            &mut diagnostics,
+            kind,
+            Side::Front,
+            &actions.front,
+            effects.front.is_empty(),
+        );
+        validate_side(
+            &mut diagnostics,
+            kind,
+            Side::Back,
+            &actions.back,
+            effects.back.is_empty(),
+        );
+
+        // Back side that can only do a subset of the front side.
+        let back_subset = actions
+            .back
+            .iter()
+            .all(|b| actions.front.iter().any(|f| same_action(f, b)));
+        if !actions.back.is_empty()
+            && back_subset
+            && actions.back.len() < actions.front.len()
+            && effects.back.len() <= effects.front.len()
+        {
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::BackStrictlyWorse,
+                tile: Some(kind),
+                side: Some(Side::Back),
+                offset: None,
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// True if any diagnostic is an error.
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity() == Severity::Error)
+}
+
+fn same_action(a: &AvailableAction, b: &AvailableAction) -> bool {
+    a.kind == b.kind && a.offset.x == b.offset.x && a.offset.y == b.offset.y
+}
+
+fn validate_side(
+    diagnostics: &mut Vec<Diagnostic>,
+    kind: TileType,
+    side: Side,
+    actions: &[AvailableAction],
+    no_effects: bool,
+) {
+    let mut push = |diagnostic_kind: DiagnosticKind, offset: Option<&Offset>| {
+        diagnostics.push(Diagnostic {
+            kind: diagnostic_kind,
+            tile: Some(kind),
+            side: Some(side),
+            offset: offset.cloned(),
+        });
+    };
+
+    // Tiles with effects may be useful without any actions.
+    if actions.is_empty() && no_effects {
+        push(DiagnosticKind::EmptySide, None);
+    }
+
+    let commands = actions
+        .iter()
+        .filter(|a| a.kind == ActionType::Command)
+        .count();
+    if commands == 1 {
+        push(DiagnosticKind::CommandWithoutOffsets, None);
+    }
+
+    for (i, a) in actions.iter().enumerate() {
+        let offset = &a.offset;
+
+        if offset.x == 0 && offset.y == 0 {
+            push(DiagnosticKind::ZeroOffset, Some(offset));
+            continue;
+        }
+
+        if offset.x.unsigned_abs() >= MAX_WIDTH || offset.y.unsigned_abs() >= MAX_HEIGHT {
+            push(DiagnosticKind::OffsetOutOfRange, Some(offset));
+        }
+
+        let not_ray = match a.kind {
+            ActionType::Slide => offset.x.abs() > 1 || offset.y.abs() > 1,
+            ActionType::JumpSlide => {
+                offset.x != 0 && offset.y != 0 && offset.x.abs() != offset.y.abs()
+            }
+            _ => false,
+        };
+        if not_ray {
+            push(DiagnosticKind::SlideNotRay, Some(offset));
+        }
+
+        if a.kind == ActionType::Move
+            && offset.x != 0
+            && offset.y != 0
+            && offset.x.abs() != offset.y.abs()
+        {
+            push(DiagnosticKind::MoveNotStraight, Some(offset));
+        }
+
+        // Only compare with earlier actions, to report each pair once.
+        for earlier in actions[..i].iter() {
+            if earlier.offset.x != offset.x || earlier.offset.y != offset.y {
+                continue;
+            }
+
+            if earlier.kind == a.kind {
+                push(DiagnosticKind::DuplicateOffset, Some(offset));
+            } else if earlier.kind != ActionType::Command && a.kind != ActionType::Command {
+                push(DiagnosticKind::ConflictingOffset, Some(offset));
+            }
+        }
+    }
+}
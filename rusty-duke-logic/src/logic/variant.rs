@@ -0,0 +1,366 @@
+//! Game variants: which tiles are in the bags, an optional turn limit and
+//! optional draw rules.
+//!
+//! A game that reaches its turn limit is adjudicated by `adjudication_score`,
+//! material plus position. The score is frozen: it must give the same result
+//! for the same position in every version, so adjudicated games keep their
+//! results. Never change it, add a new scoring function instead.
+
+use super::{
+    compute_attack_map, BoardSize, GameState, IntoEnumIterator, TileColor, TileType, WinReason,
+    Winner,
+};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Turn limit of quick games. Placements of the first tiles count too.
+pub const QUICK_TURN_LIMIT: u16 = 60;
+
+/// Tiles deployed at game start, not drawn from bag. Last is placed first.
+pub const START_TILES: [TileType; 3] = [TileType::Footman, TileType::Footman, TileType::Duke];
+
+/// Number of tiles of each type in a bag.
+///
+/// ```
+/// use rusty_duke_logic::logic::variant::BagComposition;
+/// use rusty_duke_logic::logic::TileType;
+///
+/// let bag = BagComposition::default().with(TileType::Pikeman, 1);
+/// assert_eq!(bag.len(), 15);
+/// assert_eq!(bag.tiles()[..2], [TileType::Footman, TileType::Pikeman]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BagComposition {
+    /// Only tile types in the bag are here.
+    counts: HashMap<TileType, u8>,
+}
+
+impl BagComposition {
+    /// Bag without tiles.
+    pub fn empty() -> BagComposition {
+        BagComposition {
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Same bag with count tiles of kind. Count 0 removes kind.
+    pub fn with(mut self, kind: TileType, count: u8) -> BagComposition {
+        if count == 0 {
+            self.counts.remove(&kind);
+        } else {
+            self.counts.insert(kind, count);
+        }
+        self
+    }
+
+    /// Number of tiles of kind in bag.
+    pub fn count(&self, kind: TileType) -> u8 {
+        self.counts.get(&kind).copied().unwrap_or(0)
+    }
+
+    /// Number of tiles in bag.
+    pub fn len(&self) -> usize {
+        self.counts.values().map(|count| *count as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Tiles of bag in tile id order, as in `GameRules::bag`.
+    pub fn tiles(&self) -> Vec<TileType> {
+        TileType::iter()
+            .flat_map(|kind| std::iter::repeat_n(kind, self.count(kind) as usize))
+            .collect()
+    }
+}
+
+/// Bag of the base game: one Footman, three Pikemen and one of each other
+/// troop tile, 17 tiles. Dukes and the two Footmen on the board at game start
+/// are not in the bag, see `START_TILES`.
+impl Default for BagComposition {
+    fn default() -> BagComposition {
+        let troops = [
+            TileType::Knight,
+            TileType::Bowman,
+            TileType::LightHorse,
+            TileType::Wizard,
+            TileType::Seer,
+            TileType::Champion,
+            TileType::Arbalist,
+            TileType::General,
+            TileType::Marshall,
+            TileType::Countess,
+            TileType::Ranger,
+            TileType::Sage,
+            TileType::RoyalAssassin,
+        ];
+        troops.into_iter().fold(
+            BagComposition::empty()
+                .with(TileType::Footman, 1)
+                .with(TileType::Pikeman, 3),
+            |bag, kind| bag.with(kind, 1),
+        )
+    }
+}
+
+impl From<&[TileType]> for BagComposition {
+    fn from(tiles: &[TileType]) -> BagComposition {
+        let mut bag = BagComposition::empty();
+        for kind in tiles {
+            *bag.counts.entry(*kind).or_insert(0) += 1;
+        }
+        bag
+    }
+}
+
+/// Rules of a game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRules {
+    /// Tiles in the bag of each player at game start. `START_TILES` are not
+    /// in bag. Order matters, the same seed draws different tiles from bags
+    /// in different order.
+    pub bag: Vec<TileType>,
+    /// Game ends when this many turns are done, and winner is adjudicated.
+    /// Turns of both players count, drawing and placing a tile is one turn.
+    pub turn_limit: Option<u16>,
+    /// House rule: drawing while in check is only allowed if every tile that
+    /// could be drawn can be placed to get the Duke out of check. Without it,
+    /// one such tile is enough.
+    pub strict_draw: bool,
+    /// Game is a draw when the same position, with the same player to go,
+    /// has occurred this many times.
+    pub repetition_limit: Option<u8>,
+    /// Game is a draw after this many turns without a capture or a placed
+    /// tile. Turns of both players count.
+    pub no_capture_limit: Option<u16>,
+    /// Size of the board. Dukes start in the middle of the first rows.
+    pub size: BoardSize,
+}
+
+impl GameRules {
+    /// Rules of the full game.
+    pub fn standard() -> GameRules {
+        GameRules {
+            bag: BagComposition::default().tiles(),
+            turn_limit: None,
+            strict_draw: false,
+            repetition_limit: None,
+            no_capture_limit: None,
+            size: BoardSize::STANDARD,
+        }
+    }
+
+    /// Rules of a quick game, small bags and a turn limit.
+    pub fn quick() -> GameRules {
+        GameRules {
+            bag: vec![
+                TileType::Pikeman,
+                TileType::Knight,
+                TileType::Bowman,
+                TileType::LightHorse,
+            ],
+            turn_limit: Some(QUICK_TURN_LIMIT),
+            strict_draw: false,
+            repetition_limit: None,
+            no_capture_limit: None,
+            size: BoardSize::STANDARD,
+        }
+    }
+}
+
+/// Tile set expansions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Expansion {
+    ArthurianLegends,
+}
+
+impl Expansion {
+    /// Tiles added to the bag of each player.
+    pub fn tiles(self) -> &'static [TileType] {
+        match self {
+            Expansion::ArthurianLegends => &[
+                TileType::Arthur,
+                TileType::Guinevere,
+                TileType::Lancelot,
+                TileType::Perceval,
+                TileType::Merlin,
+                TileType::Camelot,
+                TileType::Morgana,
+                TileType::Mordred,
+            ],
+        }
+    }
+}
+
+/// Setup of a standard game with expansions. See `GameState::with_setup`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GameSetup {
+    pub expansions: Vec<Expansion>,
+    /// See `GameRules::strict_draw`.
+    pub strict_draw: bool,
+    /// See `GameRules::repetition_limit`.
+    pub repetition_limit: Option<u8>,
+    /// See `GameRules::no_capture_limit`.
+    pub no_capture_limit: Option<u16>,
+}
+
+impl GameSetup {
+    /// Standard rules, with tiles of the expansions added to the bags.
+    pub fn rules(&self) -> GameRules {
+        let mut rules = GameRules::standard();
+        for expansion in self.expansions.iter() {
+            rules.bag.extend_from_slice(expansion.tiles());
+        }
+        rules.strict_draw = self.strict_draw;
+        rules.repetition_limit = self.repetition_limit;
+        rules.no_capture_limit = self.no_capture_limit;
+        rules
+    }
+}
+
+/// Preset game variants. Records store the variant, so games can be replayed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Variant {
+    #[default]
+    Standard,
+    Quick,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 2] = [Variant::Standard, Variant::Quick];
+
+    pub fn rules(self) -> GameRules {
+        match self {
+            Variant::Standard => GameRules::standard(),
+            Variant::Quick => GameRules::quick(),
+        }
+    }
+
+    /// Name used in files, e.g. "quick".
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Standard => "standard",
+            Variant::Quick => "quick",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Variant> {
+        Variant::ALL.iter().copied().find(|v| v.name() == name)
+    }
+
+    /// Stable id, for binary files. Never reuse or renumber an id.
+    pub fn to_id(self) -> u8 {
+        match self {
+            Variant::Standard => 0,
+            Variant::Quick => 1,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Variant> {
+        Variant::ALL.iter().copied().find(|v| v.to_id() == id)
+    }
+
+    /// Next variant, for cycling through variants in menus.
+    pub fn next(self) -> Variant {
+        match self {
+            Variant::Standard => Variant::Quick,
+            Variant::Quick => Variant::Standard,
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Variant::Standard => write!(f, "Standard"),
+            Variant::Quick => write!(f, "Quick ({} turns)", QUICK_TURN_LIMIT),
+        }
+    }
+}
+
+/// Material value of tile for adjudication. Frozen copy of the AI tile
+/// utility at the time the tile was added. Dukes are never counted, both are
+/// on board while the game goes on.
+fn material_value(kind: TileType) -> i32 {
+    match kind {
+        TileType::Duke => 0,
+        TileType::Footman => 9,
+        TileType::Pikeman => 13,
+        TileType::Knight => 16,
+        TileType::Bowman => 24,
+        TileType::LightHorse => 16,
+        TileType::Wizard => 32,
+        TileType::Seer => 32,
+        TileType::Champion => 40,
+        TileType::Arbalist => 18,
+        TileType::General => 31,
+        TileType::Marshall => 28,
+        TileType::Countess => 16,
+        TileType::Ranger => 24,
+        TileType::Sage => 7,
+        TileType::RoyalAssassin => 9,
+        TileType::Arthur => 26,
+        TileType::Guinevere => 20,
+        TileType::Lancelot => 21,
+        TileType::Perceval => 9,
+        TileType::Merlin => 28,
+        TileType::Camelot => 30,
+        TileType::Morgana => 14,
+        TileType::Mordred => 13,
+    }
+}
+
+/// Score of position for adjudication, positive if Black is better.
+///
+/// Each player gets the material value of own tiles on board, plus one per
+/// square own tiles bear on, as in the attack map used by the AI. Tiles in
+/// bag or drawn are not counted.
+pub fn adjudication_score(state: &GameState) -> i32 {
+    let map = compute_attack_map(state);
+    let mut score = map.squares(TileColor::Black).count_ones() as i32
+        - map.squares(TileColor::White).count_ones() as i32;
+
+    for cord in state.size().squares() {
+        if let Some(tile) = state.square(cord).tile {
+            match tile.color {
+                TileColor::Black => score += material_value(tile.kind),
+                TileColor::White => score -= material_value(tile.kind),
+            }
+        }
+    }
+
+    score
+}
+
+/// Winner of game at turn limit, and the adjudication score.
+///
+/// Higher score wins. On equal score the player with more tiles on board
+/// wins. If that is equal too, White wins, since Black had the first turn.
+pub(super) fn adjudicate(state: &GameState) -> (Winner, WinReason) {
+    let score = adjudication_score(state);
+    let winner = if score > 0 {
+        TileColor::Black
+    } else if score < 0 {
+        TileColor::White
+    } else {
+        let mut tiles = [0; 2];
+        for cord in state.size().squares() {
+            if let Some(tile) = state.square(cord).tile {
+                tiles[tile.color as usize] += 1;
+            }
+        }
+        if tiles[TileColor::Black as usize] > tiles[TileColor::White as usize] {
+            TileColor::Black
+        } else {
+            TileColor::White
+        }
+    };
+
+    (
+        Winner::Color(winner),
+        WinReason::TurnLimitAdjudication(score),
+    )
+}
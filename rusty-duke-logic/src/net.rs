@@ -0,0 +1,513 @@
+//! Protocol for remote multiplayer, enabled by the `net` feature. The async
+//! sessions in `asynchronous` need the `net-async` feature, and tokio.
+//!
+//! Peers send messages as JSON, one per line, over any byte stream, e.g. a
+//! `TcpStream`. The host chooses its color and the seed of the game, both
+//! peers then play `GameState::new_seeded(seed)`, so bags draw the same
+//! tiles on both sides.
+//!
+//! Only the peer in turn starts an exchange, the other peer answers:
+//!
+//! | Peer in turn | Answer                                     |
+//! |--------------|--------------------------------------------|
+//! | `Action`     | `Ack` with hash of the new state, `Reject` |
+//! | `OfferDraw`  | `AcceptDraw`, `DeclineDraw`                |
+//! | `Resign`     | `GameOver`                                 |
+//!
+//! Every received action is checked with `do_action` before it is done, and
+//! illegal actions are rejected. The peer that receives the message ending
+//! the game, i.e. the last action, a resignation or an accepted draw offer,
+//! confirms the result with `GameOver`. Differing hashes or results are
+//! reported as `NetError::Desync`.
+//!
+//! Messages are small, and each waits for the answer to the one before, so
+//! TCP streams should have Nagle's algorithm off with `set_nodelay(true)`.
+//! Otherwise every message may be held back for the delayed ACK of the peer.
+//!
+//! ```no_run
+//! use rusty_duke_logic::logic::TileColor;
+//! use rusty_duke_logic::net::{ClientSession, Event, HostSession};
+//! use std::net::{TcpListener, TcpStream};
+//!
+//! // Host
+//! let (stream, _) = TcpListener::bind("0.0.0.0:7878").unwrap().accept().unwrap();
+//! stream.set_nodelay(true).unwrap();
+//! let mut host = HostSession::accept(stream, TileColor::Black, 42).unwrap();
+//!
+//! // Client, in another process
+//! let stream = TcpStream::connect("192.168.1.2:7878").unwrap();
+//! stream.set_nodelay(true).unwrap();
+//! let mut client = ClientSession::connect(stream).unwrap();
+//! if let Event::Action(action) = client.receive().unwrap() {
+//!     println!("Host did {:?}", action);
+//! }
+//! ```
+
+#[cfg(feature = "net-async")]
+pub mod asynchronous;
+
+use crate::logic::{self, Action, ActionError, GameState, TileColor, Winner};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::ops::{Deref, DerefMut};
+
+/// Version of the protocol. Peers of other versions are refused.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Message between peers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Message {
+    /// First message of both peers, with the color of the sender. Host sends
+    /// the seed of the game.
+    Hello {
+        version: u32,
+        color: TileColor,
+        seed: Option<u64>,
+    },
+    /// Action of the peer in turn.
+    Action(Action),
+    /// Action was done, with the hash of the state after it.
+    Ack {
+        hash: u64,
+    },
+    /// Action was illegal and not done.
+    Reject {
+        reason: String,
+    },
+    OfferDraw,
+    AcceptDraw,
+    DeclineDraw,
+    Resign,
+    /// Result of the game, see module documentation.
+    GameOver {
+        winner: Winner,
+    },
+}
+
+/// Reason why a session failed, or refused to do something.
+#[derive(Debug)]
+pub enum NetError {
+    Io(io::Error),
+    /// Connection was closed by the peer.
+    Closed,
+    /// Message could not be parsed.
+    Malformed(String),
+    /// Peer uses another protocol version, has version of peer.
+    Version(u32),
+    /// Message was not expected at this point of the protocol.
+    Unexpected(Message),
+    /// Session can't do that now, e.g. act in turn of the peer, or receive
+    /// before answering a draw offer.
+    Turn,
+    /// Game is already over.
+    GameOver,
+    /// Own action is illegal, it was not sent.
+    Illegal(ActionError),
+    /// Action of peer is illegal, it was rejected.
+    PeerIllegal(Action, ActionError),
+    /// Peer rejected own action, it was undone. Has reason given by peer.
+    Rejected(String),
+    /// There is no draw offer to answer.
+    NoDrawOffer,
+    /// Peers disagree on the state or result of the game.
+    Desync,
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetError::Io(e) => write!(f, "{}", e),
+            NetError::Closed => write!(f, "connection closed by peer"),
+            NetError::Malformed(e) => write!(f, "malformed message: {}", e),
+            NetError::Version(v) => write!(
+                f,
+                "peer uses protocol version {}, expected {}",
+                v, PROTOCOL_VERSION
+            ),
+            NetError::Unexpected(m) => write!(f, "unexpected message {:?}", m),
+            NetError::Turn => write!(f, "not possible in this turn"),
+            NetError::GameOver => write!(f, "game is already over"),
+            NetError::Illegal(e) => write!(f, "illegal action: {}", e),
+            NetError::PeerIllegal(a, e) => write!(f, "peer did illegal action {:?}: {}", a, e),
+            NetError::Rejected(reason) => write!(f, "peer rejected action: {}", reason),
+            NetError::NoDrawOffer => write!(f, "no draw offer to answer"),
+            NetError::Desync => write!(f, "peers disagree on the game"),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+impl From<io::Error> for NetError {
+    fn from(e: io::Error) -> Self {
+        NetError::Io(e)
+    }
+}
+
+/// What the peer did, see `Session::receive`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// Action of peer, it is done on the state of the session. The game may
+    /// be over after it, see `Session::winner`.
+    Action(Action),
+    /// Peer offers a draw, answer with `Session::answer_draw`.
+    DrawOffered,
+    /// Peer resigned, the session won.
+    Resigned,
+}
+
+fn encode(message: &Message) -> String {
+    let mut line = serde_json::to_string(message).expect("Messages are always serializable.");
+    line.push('\n');
+    line
+}
+
+fn decode(line: &str) -> Result<Message, NetError> {
+    serde_json::from_str(line).map_err(|e| NetError::Malformed(e.to_string()))
+}
+
+fn hello(color: TileColor, seed: Option<u64>) -> Message {
+    Message::Hello {
+        version: PROTOCOL_VERSION,
+        color,
+        seed,
+    }
+}
+
+/// Check hello of client, for host of color.
+fn check_client_hello(message: Message, color: TileColor) -> Result<(), NetError> {
+    match message {
+        Message::Hello { version, .. } if version != PROTOCOL_VERSION => {
+            Err(NetError::Version(version))
+        }
+        Message::Hello { color: client, .. } if client == color.opponent() => Ok(()),
+        m => Err(NetError::Unexpected(m)),
+    }
+}
+
+/// Version, color of client and seed from hello of host.
+fn read_host_hello(message: Message) -> Result<(u32, TileColor, u64), NetError> {
+    match message {
+        Message::Hello {
+            version,
+            color,
+            seed: Some(seed),
+        } => Ok((version, color.opponent(), seed)),
+        m => Err(NetError::Unexpected(m)),
+    }
+}
+
+/// Game of a session, and what was agreed on with the peer. Does no IO, so
+/// it is shared by blocking and async sessions.
+struct Peer {
+    state: GameState,
+    color: TileColor,
+}
+
+impl Peer {
+    fn new(color: TileColor, seed: u64) -> Peer {
+        Peer {
+            state: GameState::new_seeded(seed),
+            color,
+        }
+    }
+
+    fn winner(&self) -> Option<Winner> {
+        self.state.game_over.clone()
+    }
+
+    /// Check that the game goes on, and that it is own turn, or the turn of
+    /// the peer.
+    fn expect_turn(&self, own: bool) -> Result<(), NetError> {
+        if self.winner().is_some() {
+            return Err(NetError::GameOver);
+        }
+        let draw_offered = self.state.draw_offer == Some(self.color.opponent());
+        if (self.state.ply == self.color) != own || draw_offered {
+            return Err(NetError::Turn);
+        }
+        Ok(())
+    }
+
+    fn game_over(&self) -> Message {
+        Message::GameOver {
+            winner: self.winner().expect("Game is over."),
+        }
+    }
+
+    /// Do own action, returns message to send.
+    fn act(&mut self, action: Action) -> Result<Message, NetError> {
+        self.expect_turn(true)?;
+        logic::do_action(&mut self.state, &action).map_err(NetError::Illegal)?;
+        Ok(Message::Action(action))
+    }
+
+    /// Handle answer to own action. Rejected action is undone.
+    fn acknowledged(&mut self, answer: Message) -> Result<(), NetError> {
+        match answer {
+            Message::Ack { hash } if hash == self.state.hash() => Ok(()),
+            Message::Ack { .. } => Err(NetError::Desync),
+            Message::Reject { reason } => {
+                logic::undo_action(&mut self.state).expect("Own action can be undone.");
+                Err(NetError::Rejected(reason))
+            }
+            m => Err(NetError::Unexpected(m)),
+        }
+    }
+
+    /// Check result sent by peer against own.
+    fn confirm(&self, message: Message) -> Result<(), NetError> {
+        match message {
+            Message::GameOver { winner } if Some(&winner) == self.winner().as_ref() => Ok(()),
+            Message::GameOver { .. } => Err(NetError::Desync),
+            m => Err(NetError::Unexpected(m)),
+        }
+    }
+
+    /// Handle message of peer in turn. Answers to send are pushed to
+    /// answers, also when the message is illegal.
+    fn receive(&mut self, message: Message, answers: &mut Vec<Message>) -> Result<Event, NetError> {
+        match message {
+            Message::Action(action) => match logic::do_action(&mut self.state, &action) {
+                Ok(_) => {
+                    answers.push(Message::Ack {
+                        hash: self.state.hash(),
+                    });
+                    if self.winner().is_some() {
+                        answers.push(self.game_over());
+                    }
+                    Ok(Event::Action(action))
+                }
+                Err(e) => {
+                    answers.push(Message::Reject {
+                        reason: e.to_string(),
+                    });
+                    Err(NetError::PeerIllegal(action, e))
+                }
+            },
+            Message::OfferDraw => {
+                logic::offer_draw(&mut self.state, self.color.opponent())
+                    .map_err(|_| NetError::Unexpected(Message::OfferDraw))?;
+                Ok(Event::DrawOffered)
+            }
+            Message::Resign => {
+                logic::resign(&mut self.state, self.color.opponent());
+                answers.push(self.game_over());
+                Ok(Event::Resigned)
+            }
+            m => Err(NetError::Unexpected(m)),
+        }
+    }
+
+    fn offer_draw(&mut self) -> Result<Message, NetError> {
+        self.expect_turn(true)?;
+        logic::offer_draw(&mut self.state, self.color).map_err(NetError::Illegal)?;
+        Ok(Message::OfferDraw)
+    }
+
+    /// Handle answer to own draw offer, true if accepted.
+    fn draw_answered(&mut self, answer: Message) -> Result<bool, NetError> {
+        let accept = match answer {
+            Message::AcceptDraw => true,
+            Message::DeclineDraw => false,
+            m => return Err(NetError::Unexpected(m)),
+        };
+        logic::respond_draw(&mut self.state, self.color.opponent(), accept)
+            .expect("Own draw offer stands.");
+        Ok(accept)
+    }
+
+    fn answer_draw(&mut self, accept: bool) -> Result<Message, NetError> {
+        logic::respond_draw(&mut self.state, self.color, accept)
+            .map_err(|_| NetError::NoDrawOffer)?;
+        if accept {
+            Ok(Message::AcceptDraw)
+        } else {
+            Ok(Message::DeclineDraw)
+        }
+    }
+
+    fn resign(&mut self) -> Result<Message, NetError> {
+        self.expect_turn(true)?;
+        logic::resign(&mut self.state, self.color);
+        Ok(Message::Resign)
+    }
+}
+
+/// Messages as lines on a blocking stream.
+struct Connection<S> {
+    stream: BufReader<S>,
+}
+
+impl<S: Read + Write> Connection<S> {
+    fn new(stream: S) -> Connection<S> {
+        Connection {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    fn send(&mut self, message: &Message) -> Result<(), NetError> {
+        let stream = self.stream.get_mut();
+        stream.write_all(encode(message).as_bytes())?;
+        stream.flush()?;
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<Message, NetError> {
+        let mut line = String::new();
+        if self.stream.read_line(&mut line)? == 0 {
+            return Err(NetError::Closed);
+        }
+        decode(&line)
+    }
+}
+
+/// Game with a peer, after the handshake. Calls block until the peer has
+/// answered.
+pub struct Session<S> {
+    connection: Connection<S>,
+    peer: Peer,
+}
+
+impl<S: Read + Write> Session<S> {
+    /// Color played by this session.
+    pub fn color(&self) -> TileColor {
+        self.peer.color
+    }
+
+    /// State of the game, the same on both peers.
+    pub fn state(&self) -> &GameState {
+        &self.peer.state
+    }
+
+    /// Winner, also by resignation or agreed draw. None while the game goes
+    /// on.
+    pub fn winner(&self) -> Option<Winner> {
+        self.peer.winner()
+    }
+
+    /// Do own action, and send it to the peer. Returns when the peer has
+    /// done it too.
+    pub fn send_action(&mut self, action: Action) -> Result<(), NetError> {
+        let message = self.peer.act(action)?;
+        self.connection.send(&message)?;
+        let answer = self.connection.receive()?;
+        self.peer.acknowledged(answer)?;
+        if self.peer.winner().is_some() {
+            let result = self.connection.receive()?;
+            self.peer.confirm(result)?;
+        }
+        Ok(())
+    }
+
+    /// Wait for the next message of the peer in turn, and answer it. An
+    /// illegal action is rejected, and the peer may try again.
+    pub fn receive(&mut self) -> Result<Event, NetError> {
+        self.peer.expect_turn(false)?;
+        let message = self.connection.receive()?;
+        let mut answers = Vec::new();
+        let event = self.peer.receive(message, &mut answers);
+        for answer in answers.iter() {
+            self.connection.send(answer)?;
+        }
+        event
+    }
+
+    /// Offer a draw in own turn. Returns true if the peer accepted, and the
+    /// game is drawn. The turn goes on if not.
+    pub fn offer_draw(&mut self) -> Result<bool, NetError> {
+        let message = self.peer.offer_draw()?;
+        self.connection.send(&message)?;
+        let answer = self.connection.receive()?;
+        let accepted = self.peer.draw_answered(answer)?;
+        if accepted {
+            self.connection.send(&self.peer.game_over())?;
+        }
+        Ok(accepted)
+    }
+
+    /// Answer draw offer of the peer.
+    pub fn answer_draw(&mut self, accept: bool) -> Result<(), NetError> {
+        let message = self.peer.answer_draw(accept)?;
+        self.connection.send(&message)?;
+        if accept {
+            let result = self.connection.receive()?;
+            self.peer.confirm(result)?;
+        }
+        Ok(())
+    }
+
+    /// Resign in own turn.
+    pub fn resign(&mut self) -> Result<(), NetError> {
+        let message = self.peer.resign()?;
+        self.connection.send(&message)?;
+        let result = self.connection.receive()?;
+        self.peer.confirm(result)
+    }
+}
+
+/// Session of the peer that chose color and seed.
+pub struct HostSession<S>(Session<S>);
+
+impl<S: Read + Write> HostSession<S> {
+    /// Handshake with a connected client. Host plays color, the game is
+    /// `GameState::new_seeded(seed)`.
+    pub fn accept(stream: S, color: TileColor, seed: u64) -> Result<HostSession<S>, NetError> {
+        let mut connection = Connection::new(stream);
+        connection.send(&hello(color, Some(seed)))?;
+        check_client_hello(connection.receive()?, color)?;
+        Ok(HostSession(Session {
+            connection,
+            peer: Peer::new(color, seed),
+        }))
+    }
+}
+
+impl<S> Deref for HostSession<S> {
+    type Target = Session<S>;
+
+    fn deref(&self) -> &Session<S> {
+        &self.0
+    }
+}
+
+impl<S> DerefMut for HostSession<S> {
+    fn deref_mut(&mut self) -> &mut Session<S> {
+        &mut self.0
+    }
+}
+
+/// Session of the peer that joined a host. Plays the other color.
+pub struct ClientSession<S>(Session<S>);
+
+impl<S: Read + Write> ClientSession<S> {
+    /// Handshake with host on stream.
+    pub fn connect(stream: S) -> Result<ClientSession<S>, NetError> {
+        let mut connection = Connection::new(stream);
+        let (version, color, seed) = read_host_hello(connection.receive()?)?;
+        // Answered also on version mismatch, so the host knows why.
+        connection.send(&hello(color, None))?;
+        if version != PROTOCOL_VERSION {
+            return Err(NetError::Version(version));
+        }
+        Ok(ClientSession(Session {
+            connection,
+            peer: Peer::new(color, seed),
+        }))
+    }
+}
+
+impl<S> Deref for ClientSession<S> {
+    type Target = Session<S>;
+
+    fn deref(&self) -> &Session<S> {
+        &self.0
+    }
+}
+
+impl<S> DerefMut for ClientSession<S> {
+    fn deref_mut(&mut self) -> &mut Session<S> {
+        &mut self.0
+    }
+}
@@ -0,0 +1,192 @@
+//! Sessions of the `net` protocol for tokio, e.g. on a
+//! `tokio::net::TcpStream`. Same as the blocking sessions, except that calls
+//! are async.
+
+use super::{
+    check_client_hello, decode, encode, hello, read_host_hello, Event, Message, NetError, Peer,
+    PROTOCOL_VERSION,
+};
+use crate::logic::{Action, GameState, TileColor, Winner};
+use std::ops::{Deref, DerefMut};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Messages as lines on an async stream.
+struct Connection<S> {
+    stream: BufReader<S>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Connection<S> {
+    fn new(stream: S) -> Connection<S> {
+        Connection {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    async fn send(&mut self, message: &Message) -> Result<(), NetError> {
+        let stream = self.stream.get_mut();
+        stream.write_all(encode(message).as_bytes()).await?;
+        stream.flush().await?;
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> Result<Message, NetError> {
+        let mut line = String::new();
+        if self.stream.read_line(&mut line).await? == 0 {
+            return Err(NetError::Closed);
+        }
+        decode(&line)
+    }
+}
+
+/// Game with a peer, after the handshake. See `net::Session`.
+pub struct Session<S> {
+    connection: Connection<S>,
+    peer: Peer,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<S> {
+    /// Color played by this session.
+    pub fn color(&self) -> TileColor {
+        self.peer.color
+    }
+
+    /// State of the game, the same on both peers.
+    pub fn state(&self) -> &GameState {
+        &self.peer.state
+    }
+
+    /// Winner, also by resignation or agreed draw. None while the game goes
+    /// on.
+    pub fn winner(&self) -> Option<Winner> {
+        self.peer.winner()
+    }
+
+    /// Do own action, and send it to the peer. Returns when the peer has
+    /// done it too.
+    pub async fn send_action(&mut self, action: Action) -> Result<(), NetError> {
+        let message = self.peer.act(action)?;
+        self.connection.send(&message).await?;
+        let answer = self.connection.receive().await?;
+        self.peer.acknowledged(answer)?;
+        if self.peer.winner().is_some() {
+            let result = self.connection.receive().await?;
+            self.peer.confirm(result)?;
+        }
+        Ok(())
+    }
+
+    /// Wait for the next message of the peer in turn, and answer it. An
+    /// illegal action is rejected, and the peer may try again.
+    pub async fn receive(&mut self) -> Result<Event, NetError> {
+        self.peer.expect_turn(false)?;
+        let message = self.connection.receive().await?;
+        let mut answers = Vec::new();
+        let event = self.peer.receive(message, &mut answers);
+        for answer in answers.iter() {
+            self.connection.send(answer).await?;
+        }
+        event
+    }
+
+    /// Offer a draw in own turn. Returns true if the peer accepted, and the
+    /// game is drawn. The turn goes on if not.
+    pub async fn offer_draw(&mut self) -> Result<bool, NetError> {
+        let message = self.peer.offer_draw()?;
+        self.connection.send(&message).await?;
+        let answer = self.connection.receive().await?;
+        let accepted = self.peer.draw_answered(answer)?;
+        if accepted {
+            let result = self.peer.game_over();
+            self.connection.send(&result).await?;
+        }
+        Ok(accepted)
+    }
+
+    /// Answer draw offer of the peer.
+    pub async fn answer_draw(&mut self, accept: bool) -> Result<(), NetError> {
+        let message = self.peer.answer_draw(accept)?;
+        self.connection.send(&message).await?;
+        if accept {
+            let result = self.connection.receive().await?;
+            self.peer.confirm(result)?;
+        }
+        Ok(())
+    }
+
+    /// Resign in own turn.
+    pub async fn resign(&mut self) -> Result<(), NetError> {
+        let message = self.peer.resign()?;
+        self.connection.send(&message).await?;
+        let result = self.connection.receive().await?;
+        self.peer.confirm(result)
+    }
+}
+
+/// Session of the peer that chose color and seed.
+pub struct HostSession<S>(Session<S>);
+
+impl<S: AsyncRead + AsyncWrite + Unpin> HostSession<S> {
+    /// Handshake with a connected client. Host plays color, the game is
+    /// `GameState::new_seeded(seed)`.
+    pub async fn accept(
+        stream: S,
+        color: TileColor,
+        seed: u64,
+    ) -> Result<HostSession<S>, NetError> {
+        let mut connection = Connection::new(stream);
+        connection.send(&hello(color, Some(seed))).await?;
+        check_client_hello(connection.receive().await?, color)?;
+        Ok(HostSession(Session {
+            connection,
+            peer: Peer::new(color, seed),
+        }))
+    }
+}
+
+impl<S> Deref for HostSession<S> {
+    type Target = Session<S>;
+
+    fn deref(&self) -> &Session<S> {
+        &self.0
+    }
+}
+
+impl<S> DerefMut for HostSession<S> {
+    fn deref_mut(&mut self) -> &mut Session<S> {
+        &mut self.0
+    }
+}
+
+/// Session of the peer that joined a host. Plays the other color.
+pub struct ClientSession<S>(Session<S>);
+
+impl<S: AsyncRead + AsyncWrite + Unpin> ClientSession<S> {
+    /// Handshake with host on stream.
+    pub async fn connect(stream: S) -> Result<ClientSession<S>, NetError> {
+        let mut connection = Connection::new(stream);
+        let (version, color, seed) = read_host_hello(connection.receive().await?)?;
+        // Answered also on version mismatch, so the host knows why.
+        connection.send(&hello(color, None)).await?;
+        if version != PROTOCOL_VERSION {
+            return Err(NetError::Version(version));
+        }
+        Ok(ClientSession(Session {
+            connection,
+            peer: Peer::new(color, seed),
+        }))
+    }
+}
+
+impl<S> Deref for ClientSession<S> {
+    type Target = Session<S>;
+
+    fn deref(&self) -> &Session<S> {
+        &self.0
+    }
+}
+
+impl<S> DerefMut for ClientSession<S> {
+    fn deref_mut(&mut self) -> &mut Session<S> {
+        &mut self.0
+    }
+}
@@ -0,0 +1,150 @@
+//! Safe writing of files shared by several running instances, e.g. the
+//! terminal and Bevy apps at the same time.
+//!
+//! Every write holds an advisory lock on a `<file>.lock` file next to the
+//! file, and replaces the file atomically by writing a temporary file and
+//! renaming it. Readers never see a half written file, and concurrent writers
+//! never interleave. The lock file is separate because rename replaces the
+//! locked file.
+
+use fs2::FileExt;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait for another instance to release a lock.
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+const FIRST_RETRY_DELAY: Duration = Duration::from_millis(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Reason why a file could not be read or written.
+#[derive(Debug)]
+pub enum PersistError {
+    Io(io::Error),
+    /// Lock was not acquired within timeout. Has path of the locked file.
+    Locked(PathBuf),
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PersistError::Io(e) => write!(f, "{}", e),
+            PersistError::Locked(path) => write!(
+                f,
+                "another instance holds the lock on {}, try again later",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PersistError {}
+
+impl From<io::Error> for PersistError {
+    fn from(e: io::Error) -> Self {
+        PersistError::Io(e)
+    }
+}
+
+/// Path with suffix added to the file name.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name: OsString = path.file_name().unwrap_or_default().to_owned();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Exclusive lock on a file, released when dropped.
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// Lock file, retrying with backoff until timeout.
+    pub fn acquire(path: &Path, timeout: Duration) -> Result<FileLock, PersistError> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(with_suffix(path, ".lock"))?;
+
+        let deadline = Instant::now() + timeout;
+        let mut delay = FIRST_RETRY_DELAY;
+        loop {
+            match file.try_lock_exclusive() {
+                Ok(()) => return Ok(FileLock { file }),
+                Err(e) if e.kind() == fs2::lock_contended_error().kind() => {}
+                Err(e) => return Err(e.into()),
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(PersistError::Locked(path.to_path_buf()));
+            }
+            thread::sleep(delay.min(deadline - now));
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // Closing the file releases the lock too, this just makes it explicit.
+        let _ = self.file.unlock();
+    }
+}
+
+/// Replace file contents. Caller must hold the lock.
+fn replace(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let temp_path = with_suffix(path, &format!(".tmp.{}", std::process::id()));
+    let result = (|| {
+        let mut temp = File::create(&temp_path)?;
+        temp.write_all(contents)?;
+        temp.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Read file under lock. Returns `None` if file does not exist.
+pub fn read(path: &Path, timeout: Duration) -> Result<Option<Vec<u8>>, PersistError> {
+    let _lock = FileLock::acquire(path, timeout)?;
+    read_unlocked(path)
+}
+
+fn read_unlocked(path: &Path) -> Result<Option<Vec<u8>>, PersistError> {
+    match fs::read(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Replace file contents atomically.
+pub fn write(path: &Path, contents: &[u8], timeout: Duration) -> Result<(), PersistError> {
+    let _lock = FileLock::acquire(path, timeout)?;
+    replace(path, contents)?;
+    Ok(())
+}
+
+/// Read, modify and write file atomically. Update gets current contents, or
+/// `None` if file does not exist, and returns new contents. No other instance
+/// can write the file in between.
+pub fn update<F>(path: &Path, timeout: Duration, update: F) -> Result<(), PersistError>
+where
+    F: FnOnce(Option<Vec<u8>>) -> Vec<u8>,
+{
+    let _lock = FileLock::acquire(path, timeout)?;
+    let contents = update(read_unlocked(path)?);
+    replace(path, &contents)?;
+    Ok(())
+}
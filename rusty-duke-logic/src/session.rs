@@ -0,0 +1,233 @@
+//! Game session shared by the front-ends.
+//!
+//! A `GameSession` owns the game, who plays each color, the clock and the
+//! record, and runs the turns: actions of humans come in through
+//! `submit_action`, agents are asked by `poll_ai`. Front-ends only show the
+//! session and turn input into actions.
+//!
+//! ```
+//! use rusty_duke_logic::ai::random::RandomAgent;
+//! use rusty_duke_logic::logic::variant::Variant;
+//! use rusty_duke_logic::logic::TileColor;
+//! use rusty_duke_logic::session::{Controller, GameSession};
+//!
+//! let agent = RandomAgent::new_seeded(TileColor::White, 1);
+//! let mut session = GameSession::new(
+//!     Variant::Standard,
+//!     Some(1),
+//!     [Controller::Human, Controller::Agent(Box::new(agent))],
+//! );
+//!
+//! // Black is human, the agent waits for its turn.
+//! assert_eq!(session.poll_ai(), None);
+//! let action = session.current_legal_actions()[0];
+//! let outcome = session.submit_action(action).unwrap();
+//! assert_eq!(outcome.to_go, TileColor::White);
+//!
+//! // White plays until it is Black's turn again.
+//! while session.ply == TileColor::White {
+//!     assert!(session.poll_ai().is_some());
+//! }
+//! assert_eq!(session.record().actions.len() as u16, session.turn);
+//! ```
+
+use crate::ai::DukeAgent;
+use crate::logic::cache::CachedGameState;
+use crate::logic::clock::GameClock;
+use crate::logic::record::GameRecord;
+use crate::logic::variant::Variant;
+use crate::logic::{
+    self, Action, ActionError, GameState, StateDelta, TileColor, WinReason, Winner,
+};
+use crate::time::Instant;
+use std::ops::Deref;
+
+/// Who plays a color.
+pub enum Controller {
+    /// Human on this device. Actions come from the front-end.
+    Human,
+    /// Agent asked for its actions by `GameSession::poll_ai`. Sessions are
+    /// shared with other threads, e.g. as a resource of a Bevy app.
+    Agent(Box<dyn DukeAgent + Send + Sync>),
+    /// Plays outside the session, e.g. an agent searching in background or a
+    /// network peer. Actions come from the front-end, like for humans.
+    Remote,
+}
+
+/// What an action did.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnOutcome {
+    /// Changes of the action, e.g. the captured tile.
+    pub delta: StateDelta,
+    /// Color to go next. The same color after drawing a tile, it places the
+    /// tile next.
+    pub to_go: TileColor,
+    /// Set if the action ended the game.
+    pub game_over: Option<Winner>,
+}
+
+/// Game with its players, clock and record. Derefs to the state, all
+/// changes go through the session.
+pub struct GameSession {
+    game: CachedGameState,
+    record: GameRecord,
+    /// Controllers of Black and White.
+    controllers: [Controller; 2],
+    clock: Option<GameClock>,
+    /// Most actions that can be undone, see `set_history_limit`.
+    history_limit: Option<usize>,
+}
+
+impl GameSession {
+    /// New game of variant. Random generator is seeded from entropy without
+    /// seed.
+    pub fn new(variant: Variant, seed: Option<u64>, controllers: [Controller; 2]) -> GameSession {
+        GameSession::from_state(
+            GameState::new_with_rules(&variant.rules(), seed),
+            GameRecord::new_variant(seed, variant),
+            controllers,
+        )
+    }
+
+    /// Game at the end of record, e.g. from a save file.
+    pub fn from_record(record: GameRecord, controllers: [Controller; 2]) -> GameSession {
+        GameSession::from_state(record.replay(), record, controllers)
+    }
+
+    /// Game of state, with the record that leads to it. For rules that are
+    /// not a variant, e.g. with another turn limit.
+    pub fn from_state(
+        state: GameState,
+        record: GameRecord,
+        controllers: [Controller; 2],
+    ) -> GameSession {
+        GameSession {
+            game: CachedGameState::new(state),
+            record,
+            controllers,
+            clock: None,
+            history_limit: None,
+        }
+    }
+
+    /// Make the game timed. The clock starts with `update_clock`.
+    pub fn set_clock(&mut self, clock: GameClock) {
+        self.clock = Some(clock);
+    }
+
+    /// Keep at most limit actions that can be undone, for long games, e.g.
+    /// of agents playing each other. Older actions are forgotten by the
+    /// state, the record still has every action.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = Some(limit);
+        self.game.trim_history(limit);
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.game
+    }
+
+    pub fn record(&self) -> &GameRecord {
+        &self.record
+    }
+
+    pub fn clock(&self) -> Option<&GameClock> {
+        self.clock.as_ref()
+    }
+
+    pub fn controller(&self, color: TileColor) -> &Controller {
+        &self.controllers[color as usize]
+    }
+
+    pub fn is_human(&self, color: TileColor) -> bool {
+        matches!(self.controller(color), Controller::Human)
+    }
+
+    /// Legal actions of the color to go, generated once per change.
+    pub fn current_legal_actions(&mut self) -> &[Action] {
+        self.game.actions()
+    }
+
+    /// Do and record action of the color to go, whoever plays it. Illegal
+    /// actions are rejected and change nothing.
+    pub fn submit_action(&mut self, action: Action) -> Result<TurnOutcome, ActionError> {
+        let delta = logic::do_action(&mut self.game, &action)?;
+        self.record.push(&self.game, action);
+        if let Some(limit) = self.history_limit {
+            self.game.trim_history(limit);
+        }
+        Ok(TurnOutcome {
+            delta,
+            to_go: self.game.ply,
+            game_over: self.game.game_over.clone(),
+        })
+    }
+
+    /// If the color to go is played by an agent, let it choose and do one
+    /// stage of its turn. Returns the action and what it did, None if a human
+    /// or remote player is to go, the game is over or the agent has no action.
+    pub fn poll_ai(&mut self) -> Option<(Action, TurnOutcome)> {
+        if self.game.game_over.is_some() {
+            return None;
+        }
+        let now = Instant::now();
+        let agent = match &mut self.controllers[self.game.ply as usize] {
+            Controller::Agent(agent) => agent,
+            _ => return None,
+        };
+        let action = match &self.clock {
+            Some(clock) => agent.choose_action_timed(&self.game, clock, now),
+            None => agent.choose_action(&self.game),
+        }?;
+        match self.submit_action(action) {
+            Ok(outcome) => Some((action, outcome)),
+            Err(e) => {
+                log::error!("Agent action {:?} rejected: {}", action, e);
+                None
+            }
+        }
+    }
+
+    /// Color resigns, see `logic::resign`.
+    pub fn resign(&mut self, color: TileColor) {
+        logic::resign(&mut self.game, color);
+        self.record.push_end(&self.game);
+    }
+
+    /// Start the clock of the color to go when the turn changed, and end the
+    /// game if a time ran out. Clocks stop at game over. Call often, with the
+    /// time of the front-end.
+    pub fn update_clock(&mut self, now: Instant) {
+        let clock = match self.clock.as_mut() {
+            Some(clock) => clock,
+            None => return,
+        };
+
+        if let Some(color) = clock.flagged(now) {
+            logic::flag_fall(&mut self.game, color);
+        }
+        if self.game.game_over.is_some() {
+            if let Some(color) = clock.running() {
+                clock.end_turn(color, now);
+            }
+        } else if clock.running() != Some(self.game.ply) {
+            clock.start_turn(self.game.ply, now);
+        }
+    }
+
+    /// Winner and why, once the game is over.
+    pub fn result(&self) -> Option<(Winner, Option<WinReason>)> {
+        self.game
+            .game_over
+            .clone()
+            .map(|winner| (winner, self.game.win_reason))
+    }
+}
+
+impl Deref for GameSession {
+    type Target = GameState;
+
+    fn deref(&self) -> &GameState {
+        &self.game
+    }
+}
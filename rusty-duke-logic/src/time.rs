@@ -0,0 +1,32 @@
+//! Clock of the library. Search deadlines, game clocks and calibration read
+//! the time only through `Instant` of this module.
+//!
+//! Natively `Instant` is `std::time::Instant`, so nothing changes for native
+//! callers. On wasm32 `std::time::Instant::now` panics, there `Instant` is
+//! `web_time::Instant`, which reads `performance.now()` of the JavaScript
+//! host. Searches that must not depend on the clock at all can be limited by
+//! depth and searched nodes instead, see `Agent::max_nodes`.
+//!
+//! ```
+//! use rusty_duke_logic::ai::alpha_beta::Agent;
+//! use rusty_duke_logic::ai::DukeAgent;
+//! use rusty_duke_logic::logic::{GameState, TileColor};
+//! use rusty_duke_logic::time::{Duration, Instant};
+//!
+//! // Same type as before, callers passing std instants still compile.
+//! let now: std::time::Instant = Instant::now();
+//!
+//! // Timed searches still stop at their deadline.
+//! let state = GameState::new_seeded(3);
+//! let mut agent = Agent::new(TileColor::Black, Some(30), Some(Duration::from_millis(100)));
+//! assert!(agent.choose_action(&state).is_some());
+//! assert!(now.elapsed() < Duration::from_secs(5));
+//! ```
+
+pub use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+
+#[cfg(target_arch = "wasm32")]
+pub use web_time::Instant;
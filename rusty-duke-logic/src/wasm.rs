@@ -0,0 +1,78 @@
+//! JavaScript bindings, for playing in a web page. Enabled by the `wasm`
+//! feature, build with e.g.
+//! `wasm-pack build rusty-duke-logic --target web -- --features wasm`.
+//!
+//! Actions are passed as JSON in the serde form of `Action`, positions as
+//! JSON of the `json` module.
+//!
+//! ```js
+//! const game = new Game(42);
+//! const actions = JSON.parse(game.legalActions());
+//! game.applyAction(JSON.stringify(actions[0]));
+//! const reply = game.aiAction(3, 20000);
+//! if (reply !== undefined) game.applyAction(reply);
+//! const position = JSON.parse(game.state());
+//! ```
+//!
+//! The AI searches synchronously on the calling thread, limited by depth and
+//! searched nodes, so it runs the same in every host, whatever its clock.
+
+use crate::ai::alpha_beta::{self, Agent};
+use crate::logic::{self, json, Action, GameState};
+use wasm_bindgen::prelude::*;
+
+/// Game played from JavaScript.
+#[wasm_bindgen]
+pub struct Game {
+    state: GameState,
+}
+
+#[wasm_bindgen]
+impl Game {
+    /// New game, tiles are drawn from the bags in an order decided by seed.
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: u32) -> Game {
+        Game {
+            state: GameState::new_seeded(seed as u64),
+        }
+    }
+
+    /// Game from a position of `state`.
+    #[wasm_bindgen(js_name = fromState)]
+    pub fn from_state(position: &str) -> Result<Game, JsError> {
+        Ok(Game {
+            state: json::from_json_position(position)?,
+        })
+    }
+
+    /// Legal actions of the color to play, as JSON array.
+    #[wasm_bindgen(js_name = legalActions)]
+    pub fn legal_actions(&self) -> String {
+        serde_json::to_string(&logic::get_legal_actions(&self.state))
+            .expect("Actions are always serializable.")
+    }
+
+    /// Do action, given as JSON. The game is unchanged if the action is
+    /// illegal.
+    #[wasm_bindgen(js_name = applyAction)]
+    pub fn apply_action(&mut self, action: &str) -> Result<(), JsError> {
+        let action: Action = serde_json::from_str(action)?;
+        logic::do_action(&mut self.state, &action)?;
+        Ok(())
+    }
+
+    /// Position as JSON, including whose turn it is and the winner.
+    pub fn state(&self) -> String {
+        json::to_json_position(&self.state)
+    }
+
+    /// Action of AI for the color to play as JSON, or undefined if there is
+    /// none. Search stops at depth or after max_nodes searched nodes.
+    #[wasm_bindgen(js_name = aiAction)]
+    pub fn ai_action(&self, depth: u8, max_nodes: u32) -> Option<String> {
+        let mut agent = Agent::new(self.state.ply, Some(depth), None);
+        agent.max_nodes = Some(max_nodes as u64);
+        alpha_beta::get_action(&agent, &self.state)
+            .map(|a| serde_json::to_string(&a).expect("Actions are always serializable."))
+    }
+}
@@ -0,0 +1,79 @@
+//! Accessors by color agree with scans of the board and with the accessors
+//! of the player to go.
+
+use rusty_duke_logic::ai::random::RandomAgent;
+use rusty_duke_logic::ai::DukeAgent;
+use rusty_duke_logic::logic::{
+    do_action, Coordinate, GameState, TileColor, TileType, HEIGHT, WIDTH,
+};
+
+/// Random game with seed, after plies actions, or when over.
+fn midgame(seed: u64, plies: usize) -> GameState {
+    let mut state = GameState::new_seeded(seed);
+    let mut agents = [
+        RandomAgent::new_seeded(TileColor::Black, seed),
+        RandomAgent::new_seeded(TileColor::White, seed + 100),
+    ];
+    for _ in 0..plies {
+        if state.game_over.is_some() {
+            break;
+        }
+        let action = match agents[state.ply as usize].choose_action(&state) {
+            Some(action) => action,
+            None => break,
+        };
+        do_action(&mut state, &action).unwrap();
+    }
+    state
+}
+
+#[test]
+fn accessors_agree() {
+    let mut captures = 0;
+    for seed in 0..8 {
+        let state = midgame(seed, 60);
+        let mut graveyards = 0;
+        for color in [TileColor::Black, TileColor::White] {
+            // Tiles of color, column by column within each row.
+            let mut scanned = Vec::new();
+            for y in 0..HEIGHT {
+                for x in 0..WIDTH {
+                    let cord = Coordinate::new(x, y);
+                    if let Some(tile) = state.tile_at(cord) {
+                        if tile.color == color {
+                            scanned.push((cord, tile));
+                        }
+                    }
+                }
+            }
+            assert_eq!(state.iter_tiles(Some(color)).collect::<Vec<_>>(), scanned);
+
+            let dukes: Vec<_> = scanned
+                .iter()
+                .filter(|(_, tile)| tile.kind == TileType::Duke)
+                .map(|(cord, _)| *cord)
+                .collect();
+            assert_eq!(state.duke_pos(color).into_iter().collect::<Vec<_>>(), dukes);
+
+            let graveyard = state.graveyard_of(color);
+            assert!(graveyard.iter().all(|c| c.tile.color == color));
+            assert_eq!(graveyard, state.captures_by(color.opponent()));
+            graveyards += graveyard.len();
+
+            assert_eq!(state.bag_of(color), &state.bags[color as usize]);
+            assert_eq!(state.drawn_of(color), &state.drawn_tiles[color as usize]);
+        }
+
+        assert_eq!(graveyards, state.graveyard.len());
+        captures += graveyards;
+
+        assert_eq!(state.bag(), state.bag_of(state.ply));
+        assert_eq!(state.drawn(), state.drawn_of(state.ply));
+        assert_eq!(*state.own_duke_pos(), state.duke_pos(state.ply));
+        assert_eq!(
+            *state.opponent_duke_pos(),
+            state.duke_pos(state.ply.opponent())
+        );
+    }
+    assert!(captures > 0);
+}
@@ -0,0 +1,237 @@
+//! Arena games ended early by adjudication, with agents of scripted
+//! evaluations.
+
+use rusty_duke_logic::ai::alpha_beta::Agent;
+use rusty_duke_logic::ai::arena::{
+    play_game, play_game_adjudicated, tournament, tournament_adjudicated, Adjudication, Entrant,
+    GameResult,
+};
+use rusty_duke_logic::ai::random::RandomAgent;
+use rusty_duke_logic::ai::DukeAgent;
+use rusty_duke_logic::logic::{Action, GameState, TileColor, WinReason, Winner};
+use std::time::Instant;
+
+/// Random agent with evaluations given by a script of the plies played, for
+/// its own color.
+struct Scripted {
+    agent: RandomAgent,
+    script: fn(u32) -> Option<i32>,
+}
+
+impl Scripted {
+    fn new(color: TileColor, seed: u64, script: fn(u32) -> Option<i32>) -> Scripted {
+        Scripted {
+            agent: RandomAgent::new_seeded(color, seed),
+            script,
+        }
+    }
+}
+
+impl DukeAgent for Scripted {
+    fn color(&self) -> TileColor {
+        self.agent.color()
+    }
+
+    fn choose_action(&mut self, state: &GameState) -> Option<Action> {
+        self.agent.choose_action(state)
+    }
+
+    fn evaluation(&self, state: &GameState) -> Option<i32> {
+        (self.script)(state.ply_count)
+    }
+}
+
+const RULES: Adjudication = Adjudication {
+    min_plies: 10,
+    win_threshold: 300,
+    win_turns: 3,
+    draw_band: 20,
+    draw_turns: 5,
+    draw_min_plies: 30,
+};
+const SEED: u64 = 0;
+const MAX_PLIES: u32 = 400;
+
+/// Game of scripted agents, adjudicated by rules.
+fn scripted_game(
+    black: fn(u32) -> Option<i32>,
+    white: fn(u32) -> Option<i32>,
+    rules: &Adjudication,
+) -> GameResult {
+    play_game_adjudicated(
+        &mut Scripted::new(TileColor::Black, 1, black),
+        &mut Scripted::new(TileColor::White, 2, white),
+        MAX_PLIES,
+        SEED,
+        rules,
+    )
+}
+
+/// Same game played out.
+fn full_game() -> GameResult {
+    play_game(
+        &mut RandomAgent::new_seeded(TileColor::Black, 1),
+        &mut RandomAgent::new_seeded(TileColor::White, 2),
+        MAX_PLIES,
+        SEED,
+    )
+}
+
+#[test]
+fn games_are_long_enough() {
+    let full = full_game();
+    assert!(full.plies > 60, "{}", full.plies);
+    assert_ne!(full.reason, Some(WinReason::Adjudicated));
+    assert_eq!(full.adjudicated, None);
+}
+
+#[test]
+fn agreed_wins_are_adjudicated() {
+    let result = scripted_game(|_| Some(500), |_| Some(-500), &RULES);
+    assert_eq!(result.winner, Winner::Color(TileColor::Black));
+    assert_eq!(result.reason, Some(WinReason::Adjudicated));
+    assert_eq!(result.adjudicated, Some([500, 500]));
+    // Three turns agreed long before, but not before the first ply allowed.
+    assert!(result.plies >= RULES.min_plies && result.plies <= RULES.min_plies + 2);
+
+    let result = scripted_game(|_| Some(-400), |_| Some(400), &RULES);
+    assert_eq!(result.winner, Winner::Color(TileColor::White));
+    assert_eq!(result.reason, Some(WinReason::Adjudicated));
+    assert_eq!(result.adjudicated, Some([-400, -400]));
+
+    // The actions are the same as in the game played out.
+    let record = result.record.unwrap();
+    let full = full_game().record.unwrap();
+    assert_eq!(record.actions[..], full.actions[..record.actions.len()]);
+}
+
+#[test]
+fn disagreements_are_not_adjudicated() {
+    // Both think they are winning.
+    let result = scripted_game(|_| Some(500), |_| Some(500), &RULES);
+    let full = full_game();
+    assert_eq!((result.winner, result.reason), (full.winner, full.reason));
+    assert_eq!(result.plies, full.plies);
+    assert_eq!(result.adjudicated, None);
+
+    // Beyond the threshold for one only.
+    let result = scripted_game(|_| Some(500), |_| Some(-200), &RULES);
+    assert_eq!(result.plies, full.plies);
+
+    // White only agrees from ply 40, three turns are needed from there.
+    let result = scripted_game(
+        |_| Some(500),
+        |plies| Some(if plies >= 40 { -500 } else { 500 }),
+        &RULES,
+    );
+    assert_eq!(result.winner, Winner::Color(TileColor::Black));
+    assert!(result.plies >= 42 && result.plies <= 46, "{}", result.plies);
+
+    // No evaluation is no agreement.
+    let result = scripted_game(|_| Some(500), |_| None, &RULES);
+    assert_eq!(result.plies, full.plies);
+}
+
+#[test]
+fn late_even_games_are_drawn() {
+    let result = scripted_game(|_| Some(10), |_| Some(-15), &RULES);
+    assert_eq!(result.winner, Winner::Draw);
+    assert_eq!(result.reason, Some(WinReason::Adjudicated));
+    assert_eq!(result.adjudicated, Some([10, 15]));
+    // Five turns after the late phase starts.
+    assert!(result.plies >= 30 + 5 && result.plies <= 30 + 10);
+
+    // Even, but not within the band.
+    let result = scripted_game(|_| Some(25), |_| Some(-25), &RULES);
+    assert_eq!(result.plies, full_game().plies);
+}
+
+#[test]
+fn agents_without_evaluation_play_out() {
+    let result = play_game_adjudicated(
+        &mut RandomAgent::new_seeded(TileColor::Black, 1),
+        &mut RandomAgent::new_seeded(TileColor::White, 2),
+        MAX_PLIES,
+        SEED,
+        &RULES,
+    );
+    assert_eq!(result, full_game());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn report_has_the_adjudicated_games() {
+    use rusty_duke_logic::ai::arena::TournamentReport;
+
+    let entrants = || {
+        vec![
+            Entrant::new("winning", |color, seed| {
+                Box::new(Scripted::new(color, seed, |_| Some(500))) as Box<dyn DukeAgent>
+            }),
+            Entrant::new("losing", |color, seed| {
+                Box::new(Scripted::new(color, seed, |_| Some(-500))) as Box<dyn DukeAgent>
+            }),
+        ]
+    };
+    let report = tournament_adjudicated(entrants(), 4, 9, MAX_PLIES, RULES);
+    assert_eq!(report.adjudication, Some(RULES));
+    assert_eq!(report.adjudicated.len(), 4);
+    assert_eq!(report.standings[0].wins, 4);
+    assert!(report.to_string().ends_with(", 4 adjudicated"));
+
+    let json = report.to_json();
+    assert!(json.contains("\"win_threshold\": 300"), "{}", json);
+    let parsed: TournamentReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, report);
+
+    // Games can be played out from the report.
+    let game = &report.adjudicated[0];
+    assert_eq!(
+        (game.black.as_str(), game.white.as_str()),
+        ("winning", "losing")
+    );
+    let full = play_game(
+        &mut RandomAgent::new_seeded(TileColor::Black, game.agent_seeds[0]),
+        &mut RandomAgent::new_seeded(TileColor::White, game.agent_seeds[1]),
+        MAX_PLIES,
+        game.seed,
+    );
+    assert!(full.plies > game.plies);
+
+    // Without adjudication, nothing is reported.
+    let report = tournament(entrants(), 2, 9, MAX_PLIES);
+    assert_eq!(report.adjudication, None);
+    assert!(report.adjudicated.is_empty());
+}
+
+/// Weakened agents fall behind early, but take long to lose.
+#[test]
+fn adjudication_saves_time() {
+    let entrants = || {
+        vec![
+            Entrant::new("level 3", |color, seed| {
+                Box::new(Agent::from_level(color, 3).with_seed(seed)) as Box<dyn DukeAgent>
+            }),
+            Entrant::new("level 5", |color, seed| {
+                Box::new(Agent::from_level(color, 5).with_seed(seed)) as Box<dyn DukeAgent>
+            }),
+        ]
+    };
+
+    let start = Instant::now();
+    let full = tournament(entrants(), 6, 11, 300);
+    let full_time = start.elapsed();
+
+    let start = Instant::now();
+    let adjudicated = tournament_adjudicated(entrants(), 6, 11, 300, Adjudication::default());
+    let adjudicated_time = start.elapsed();
+
+    assert!(!adjudicated.adjudicated.is_empty());
+    assert!(adjudicated.average_plies < full.average_plies);
+    assert!(
+        adjudicated_time < full_time,
+        "{:?} adjudicated, {:?} played out",
+        adjudicated_time,
+        full_time
+    );
+}
@@ -0,0 +1,114 @@
+//! Actions into a reused vector, without allocating.
+
+use rusty_duke_logic::ai::random::RandomAgent;
+use rusty_duke_logic::ai::DukeAgent;
+use rusty_duke_logic::logic::perft::{count_action_nodes, DrawMode};
+use rusty_duke_logic::logic::{
+    do_action, get_actions, get_actions_into, is_in_check, GameState, TileColor,
+};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Counts allocations per thread, tests run in parallel.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // Thread locals are gone while a thread exits.
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Allocations of this thread while running f.
+fn allocations(f: impl FnOnce()) -> u64 {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+/// Positions of random games, with and without drawn tiles.
+fn positions() -> Vec<GameState> {
+    let mut positions = Vec::new();
+    for seed in 0..4 {
+        let mut state = GameState::new_seeded(seed);
+        let mut agents = [
+            RandomAgent::new_seeded(TileColor::Black, seed),
+            RandomAgent::new_seeded(TileColor::White, seed + 100),
+        ];
+        while state.game_over.is_none() && positions.len() < 100 * (seed as usize + 1) {
+            positions.push(state.clone());
+            let action = match agents[state.ply as usize].choose_action(&state) {
+                Some(action) => action,
+                None => break,
+            };
+            do_action(&mut state, &action).unwrap();
+        }
+    }
+    positions
+}
+
+#[test]
+fn reused_vector_does_not_allocate() {
+    let mut actions = Vec::with_capacity(256);
+    let mut reused = 0;
+    let mut fresh = 0;
+    for state in positions() {
+        // Whether drawing gets the Duke out of check is tried on copies.
+        if is_in_check(&state, state.ply) {
+            continue;
+        }
+        reused += allocations(|| get_actions_into(&state, &mut actions));
+        assert_eq!(actions, get_actions(&state));
+        fresh += allocations(|| drop(get_actions(&state)));
+    }
+
+    assert_eq!(reused, 0);
+    assert!(fresh > 100, "{}", fresh);
+}
+
+#[test]
+fn same_actions_as_before() {
+    // Counts of the action tree from before actions were pushed into a
+    // reused vector.
+    let counts = [
+        (
+            "2FDPF'/3EF'1/6/2f'3/b5/d'1w'f2 b fpppkelyscagmnr/YPRPKBLWSCAGMN -/- - 16",
+            2852,
+            14126,
+        ),
+        (
+            "d'3PA'/4F'1/4f1/6/3f'2/1D'4 b fpppkblwscyemnr/FEPPKBLWSCYGMNR -/- gFa' 28",
+            4908,
+            19270,
+        ),
+        (
+            "6/6/6/6/2D2d'/1f4 b fpppkblwscagynre/FEPPKBYWSCAGMNR -/- Ff'L'mF'P' 32",
+            1912,
+            1288,
+        ),
+    ];
+    for (fen, expand, exclude) in counts {
+        let state = GameState::from_fen(fen).unwrap();
+        assert_eq!(count_action_nodes(&state, 3, DrawMode::Expand), expand);
+        assert_eq!(count_action_nodes(&state, 4, DrawMode::Exclude), exclude);
+    }
+
+    // Vector isn't cleared by the caller.
+    let mut actions = Vec::new();
+    for state in positions() {
+        get_actions_into(&state, &mut actions);
+        assert_eq!(actions, get_actions(&state));
+    }
+}
@@ -0,0 +1,165 @@
+//! Actions of the Arthurian Legends tiles, and games with the expansion.
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::variant::{Expansion, GameSetup};
+use rusty_duke_logic::logic::{
+    get_tile_actions, Action, Coordinate, GameState, TileColor, TileType,
+};
+
+/// Black tile on c3, Dukes out of its reach, plus Footmen. Black to go.
+fn board(kind: TileType, flipped: bool, footmen: &[(Coordinate, TileColor)]) -> GameState {
+    let mut builder = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(5, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(0, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+        .tile_at(Coordinate::new(2, 2), kind, TileColor::Black, flipped);
+    for (cord, color) in footmen.iter() {
+        builder = builder.tile_at(*cord, TileType::Footman, *color, false);
+    }
+    builder.ply(TileColor::Black).build().unwrap()
+}
+
+/// Actions of the tile on c3 as card symbol and target, command actions
+/// with the commanded square too, e.g. "Cb4c4". Sorted.
+fn actions(state: &GameState) -> Vec<String> {
+    let mut actions: Vec<String> = get_tile_actions(state, Coordinate::new(2, 2))
+        .into_iter()
+        .map(|action| match action {
+            Action::Move(data) => format!("M{}", data.target_pos),
+            Action::Jump(data) => format!("J{}", data.target_pos),
+            Action::Slide(data) => format!("S{}", data.target_pos),
+            Action::JumpSlide(data) => format!("L{}", data.target_pos),
+            Action::Strike(data) => format!("X{}", data.target_pos),
+            Action::Command(data) => format!("C{}{}", data.command_tile_pos, data.target_pos),
+            action => panic!("{:?}", action),
+        })
+        .collect();
+    actions.sort();
+    actions
+}
+
+#[test]
+fn actions_on_empty_board() {
+    let expected: [(TileType, [&[&str]; 2]); 8] = [
+        (
+            TileType::Arthur,
+            [
+                &["Mb3", "Mc2", "Mc4", "Md3"],
+                &["Mc2", "Mc4", "Sa5", "Sb4", "Sd4", "Se5", "Sf6"],
+            ],
+        ),
+        // Defence on b3 and d3 blocks the slides of the front.
+        (
+            TileType::Guinevere,
+            [&["Jc5"], &["Jc1", "Mb2", "Mb4", "Md2", "Md4"]],
+        ),
+        (
+            TileType::Lancelot,
+            [
+                &["Lc5", "Lc6", "Mc4"],
+                &["Ja3", "Je3", "Sa5", "Sb4", "Sd4", "Se5", "Sf6"],
+            ],
+        ),
+        (
+            TileType::Perceval,
+            [&["Jc5", "Mb4", "Md4"], &["Mb2", "Md2", "Sc4", "Sc5", "Sc6"]],
+        ),
+        (
+            TileType::Merlin,
+            [
+                &["Ja1", "Ja5", "Je1", "Je5", "Mc4"],
+                &["Ja3", "Jc1", "Jc5", "Je3"],
+            ],
+        ),
+        // Nothing to command on the front, Defence on c4 blocks the move of
+        // the back.
+        (TileType::Camelot, [&[], &["Mb3", "Mc2", "Md3"]]),
+        (TileType::Morgana, [&["Jc1", "Mb4", "Md4"], &["Mb3", "Md3"]]),
+        (TileType::Mordred, [&["Mb3", "Md3"], &["Sc4", "Sc5", "Sc6"]]),
+    ];
+    for (kind, sides) in expected {
+        for (flipped, side) in [false, true].into_iter().zip(sides) {
+            assert_eq!(
+                actions(&board(kind, flipped, &[])),
+                side,
+                "{} flipped {}",
+                kind,
+                flipped
+            );
+        }
+    }
+}
+
+#[test]
+fn strikes() {
+    let white = |x, y| (Coordinate::new(x, y), TileColor::White);
+
+    // JumpSlide captures on c5 and stops there.
+    let state = board(
+        TileType::Lancelot,
+        false,
+        &[white(1, 4), white(2, 4), white(3, 4)],
+    );
+    assert_eq!(actions(&state), ["Lc5", "Mc4", "Xb5", "Xd5"]);
+
+    let state = board(TileType::Morgana, true, &[white(2, 4)]);
+    assert_eq!(actions(&state), ["Mb3", "Md3", "Xc5"]);
+    let state = board(TileType::Mordred, false, &[white(2, 4)]);
+    assert_eq!(actions(&state), ["Mb3", "Md3", "Xc5"]);
+    let state = board(TileType::Mordred, true, &[white(1, 3), white(3, 3)]);
+    assert_eq!(actions(&state), ["Sc4", "Sc5", "Sc6", "Xb4", "Xd4"]);
+}
+
+#[test]
+fn commands() {
+    let black = |x, y| (Coordinate::new(x, y), TileColor::Black);
+
+    // Move and Command squares are command squares too.
+    let state = board(TileType::Arthur, false, &[black(1, 3)]);
+    assert_eq!(
+        actions(&state),
+        ["Cb4b3", "Cb4c4", "Cb4d3", "Cb4d4", "Mb3", "Mc2", "Mc4", "Md3"]
+    );
+    let state = board(TileType::Arthur, true, &[black(1, 1)]);
+    assert_eq!(
+        actions(&state),
+        ["Cb2c2", "Cb2d2", "Mc2", "Mc4", "Sa5", "Sb4", "Sd4", "Se5", "Sf6"]
+    );
+
+    // Camelot defends every command square but c2.
+    let state = board(TileType::Camelot, false, &[black(1, 2)]);
+    assert_eq!(actions(&state), ["Cb3c2"]);
+}
+
+#[test]
+fn expansion_tiles_are_in_bags() {
+    let state = GameState::new_with_expansion(Expansion::ArthurianLegends, Some(1));
+    let standard = GameState::new_seeded(1);
+    for (bag, standard_bag) in state.bags.iter().zip(standard.bags.iter()) {
+        assert_eq!(
+            bag.len(),
+            standard_bag.len() + Expansion::ArthurianLegends.tiles().len()
+        );
+        for kind in Expansion::ArthurianLegends.tiles() {
+            assert_eq!(bag.iter().filter(|tile| tile.kind == *kind).count(), 1);
+        }
+    }
+
+    let setup = GameSetup {
+        expansions: vec![Expansion::ArthurianLegends],
+        ..GameSetup::default()
+    };
+    assert_eq!(
+        GameState::new_with_rules(&setup.rules(), Some(1)).bags,
+        state.bags
+    );
+}
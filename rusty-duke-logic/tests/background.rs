@@ -0,0 +1,85 @@
+//! Searches in background, and results that arrive after the game moved on.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use rusty_duke_logic::ai::random::RandomAgent;
+use rusty_duke_logic::ai::{DukeAgent, SearchHandle, SearchPoll};
+use rusty_duke_logic::logic::{do_action, GameState, TileColor};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Poll handle until the search is done.
+fn wait(handle: &SearchHandle, state: &GameState) -> SearchPoll {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        match handle.poll(state) {
+            SearchPoll::Pending if Instant::now() < deadline => {
+                std::thread::sleep(Duration::from_millis(1))
+            }
+            poll => return poll,
+        }
+    }
+}
+
+/// Search with a random agent that waits for go before answering.
+fn spawn(state: &GameState) -> (SearchHandle, mpsc::Sender<()>) {
+    let (go, wait_for_go) = mpsc::channel();
+    let handle = SearchHandle::spawn(state, move |state, _| {
+        let _ = wait_for_go.recv();
+        RandomAgent::new_seeded(state.ply, 1).choose_action(state)
+    });
+    (handle, go)
+}
+
+#[test]
+fn result_for_the_same_position_is_done() {
+    let state = GameState::new_seeded(3);
+    let (handle, go) = spawn(&state);
+    assert_eq!(handle.poll(&state), SearchPoll::Pending);
+    go.send(()).unwrap();
+
+    match wait(&handle, &state) {
+        SearchPoll::Done(Some(action)) => {
+            let mut state = state.clone();
+            do_action(&mut state, &action).unwrap();
+        }
+        poll => panic!("{:?}", poll),
+    }
+    // Handed out once.
+    assert_eq!(handle.poll(&state), SearchPoll::Lost);
+}
+
+#[test]
+fn result_after_reset_is_stale() {
+    // Search starts, the game is reset, then the result arrives. Start
+    // positions are the same in every game, so the search starts later.
+    let mut game = GameState::new_seeded(3);
+    let action = RandomAgent::new_seeded(TileColor::Black, 2)
+        .choose_action(&game)
+        .unwrap();
+    do_action(&mut game, &action).unwrap();
+    let (handle, go) = spawn(&game);
+    game = GameState::new_seeded(4);
+    go.send(()).unwrap();
+
+    assert_eq!(wait(&handle, &game), SearchPoll::Stale);
+}
+
+#[test]
+fn result_after_a_move_is_stale() {
+    let mut game = GameState::new_seeded(3);
+    let (handle, go) = spawn(&game);
+    let action = RandomAgent::new_seeded(TileColor::Black, 2)
+        .choose_action(&game)
+        .unwrap();
+    do_action(&mut game, &action).unwrap();
+    go.send(()).unwrap();
+
+    assert_eq!(wait(&handle, &game), SearchPoll::Stale);
+}
+
+#[test]
+fn handles_can_be_shared_between_threads() {
+    fn shared<T: Send + Sync>() {}
+    shared::<SearchHandle>();
+}
@@ -0,0 +1,62 @@
+//! Bag composition of new games.
+
+use rusty_duke_logic::logic::variant::{BagComposition, GameRules};
+use rusty_duke_logic::logic::{
+    do_action, get_legal_actions, Action, GameState, IntoEnumIterator, TileType,
+};
+
+#[test]
+fn default_bag_is_base_game() {
+    let bag = BagComposition::default();
+    assert_eq!(bag.len(), 17);
+    assert_eq!(bag.count(TileType::Footman), 1);
+    assert_eq!(bag.count(TileType::Pikeman), 3);
+    assert_eq!(bag.count(TileType::Duke), 0);
+    for kind in TileType::iter()
+        .skip_while(|kind| *kind != TileType::Knight)
+        .take_while(|kind| *kind != TileType::Arthur)
+    {
+        assert_eq!(bag.count(kind), 1, "{}", kind);
+    }
+    for kind in TileType::iter().skip_while(|kind| *kind != TileType::Arthur) {
+        assert_eq!(bag.count(kind), 0, "{}", kind);
+    }
+
+    assert_eq!(GameRules::standard().bag, bag.tiles());
+    assert_eq!(BagComposition::from(&bag.tiles()[..]), bag);
+    for bag in GameState::new_seeded(1).bags.iter() {
+        assert_eq!(bag.len(), 17);
+    }
+}
+
+#[test]
+fn custom_bag_is_drawn_from() {
+    let bag = BagComposition::empty().with(TileType::Knight, 1);
+    let mut state = GameState::new_with_bag(&bag, Some(1));
+    for bag in state.bags.iter() {
+        assert_eq!(bag.len(), 1);
+        assert_eq!(bag[0].kind, TileType::Knight);
+    }
+
+    // Dukes and Footmen are placed first.
+    while !get_legal_actions(&state).contains(&Action::NewFromBag) {
+        let action = get_legal_actions(&state)[0];
+        do_action(&mut state, &action).unwrap();
+    }
+    let player = state.ply;
+    do_action(&mut state, &Action::NewFromBag).unwrap();
+    assert_eq!(state.drawn()[0].kind, TileType::Knight);
+    let place = get_legal_actions(&state)[0];
+    do_action(&mut state, &place).unwrap();
+    assert!(state.bags[player as usize].is_empty());
+
+    // Opponent still has its Knight, player has none left to draw.
+    assert!(get_legal_actions(&state).contains(&Action::NewFromBag));
+    let action = *get_legal_actions(&state)
+        .iter()
+        .find(|action| **action != Action::NewFromBag)
+        .unwrap();
+    do_action(&mut state, &action).unwrap();
+    assert_eq!(state.ply, player);
+    assert!(!get_legal_actions(&state).contains(&Action::NewFromBag));
+}
@@ -0,0 +1,200 @@
+//! Binary game record archives: conversion to and from text archives, random
+//! access by the footer, and games the reader skips over or rejects.
+
+use rusty_duke_logic::ai::random::RandomAgent;
+use rusty_duke_logic::ai::DukeAgent;
+use rusty_duke_logic::logic::record::binary::{
+    binary_to_text, text_to_binary, ArchiveError, BinaryReader, BinaryWriter, PackedAction,
+};
+use rusty_duke_logic::logic::record::GameRecord;
+use rusty_duke_logic::logic::variant::Variant;
+use rusty_duke_logic::logic::{
+    do_unsafe_action, offer_draw, resign, respond_draw, GameState, TileColor, TileType,
+};
+use std::io::Cursor;
+
+/// Random game of seed. Odd seeds are quick games, and games that last too
+/// long end by resignation or agreed draw.
+fn random_game(seed: u64, max_actions: usize) -> GameRecord {
+    let variant = match seed % 2 {
+        0 => Variant::Standard,
+        _ => Variant::Quick,
+    };
+    let mut state = GameState::new_with_rules(&variant.rules(), Some(seed));
+    let mut record = GameRecord::new_variant(Some(seed), variant);
+    let mut agents = [
+        RandomAgent::new_seeded(TileColor::Black, seed),
+        RandomAgent::new_seeded(TileColor::White, seed + 1000),
+    ];
+    while state.game_over.is_none() {
+        if record.len() >= max_actions && state.drawn().is_empty() {
+            let color = state.ply;
+            if seed.is_multiple_of(3) {
+                offer_draw(&mut state, color).unwrap();
+                respond_draw(&mut state, color.opponent(), true).unwrap();
+            } else {
+                resign(&mut state, color);
+            }
+            record.push_end(&state);
+            break;
+        }
+        let action = match agents[state.ply as usize].choose_action(&state) {
+            Some(action) => action,
+            None => break,
+        };
+        do_unsafe_action(&mut state, &action);
+        record.push(&state, action);
+    }
+    record
+}
+
+/// Text archive of records, as written by `binary_to_text`.
+fn text_archive(records: &[GameRecord]) -> String {
+    records
+        .iter()
+        .map(|record| record.to_text())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn binary_archive(records: &[GameRecord]) -> Vec<u8> {
+    let mut writer = BinaryWriter::new(Vec::new()).unwrap();
+    for record in records {
+        writer.write_game(record).unwrap();
+    }
+    writer.finish().unwrap()
+}
+
+fn records(count: u64) -> Vec<GameRecord> {
+    (0..count).map(|seed| random_game(seed, 80)).collect()
+}
+
+#[test]
+fn archives_convert_both_ways() {
+    let records = records(30);
+    assert!(records.iter().any(|r| r.end.is_some()));
+    let text = text_archive(&records);
+
+    let mut binary = Vec::new();
+    assert_eq!(text_to_binary(text.as_bytes(), &mut binary).unwrap(), 30);
+    assert_eq!(binary, binary_archive(&records));
+
+    let mut back = Vec::new();
+    assert_eq!(binary_to_text(binary.as_slice(), &mut back).unwrap(), 30);
+    assert_eq!(String::from_utf8(back).unwrap(), text);
+
+    let read: Vec<GameRecord> = BinaryReader::new(binary.as_slice())
+        .unwrap()
+        .map(|record| record.unwrap())
+        .collect();
+    assert_eq!(read, records);
+}
+
+#[test]
+fn games_are_found_by_the_footer() {
+    let records = records(12);
+    let mut reader = BinaryReader::new(Cursor::new(binary_archive(&records))).unwrap();
+    assert_eq!(reader.game_count().unwrap(), 12);
+    for index in (0..12).rev() {
+        assert_eq!(reader.read_game_at(index).unwrap(), records[index as usize]);
+    }
+    // Reading goes on from the game after.
+    reader.read_game_at(4).unwrap();
+    assert_eq!(reader.read_game().unwrap().as_ref(), Some(&records[5]));
+    assert!(matches!(
+        reader.read_game_at(12),
+        Err(ArchiveError::NoSuchGame(12))
+    ));
+}
+
+#[test]
+fn archive_without_footer_is_read_to_the_end() {
+    // Writer interrupted before finish, the footer is missing.
+    let records = records(3);
+    let full = binary_archive(&records);
+    // Footer is a tag, offsets of the games, game count and magic.
+    let bytes = full[..full.len() - (1 + 3 * 8 + 8 + 4)].to_vec();
+
+    let read: Vec<GameRecord> = BinaryReader::new(bytes.as_slice())
+        .unwrap()
+        .map(|record| record.unwrap())
+        .collect();
+    assert_eq!(read, records);
+    let mut reader = BinaryReader::new(Cursor::new(bytes)).unwrap();
+    assert!(matches!(reader.game_count(), Err(ArchiveError::NoIndex)));
+}
+
+#[test]
+fn annotations_are_skipped_and_corrupt_games_rejected() {
+    let record = random_game(2, 6);
+    let header_len = 6;
+    let empty = binary_archive(&[]);
+    let mut bytes = empty[..header_len].to_vec();
+
+    // Game with an annotation block after its first action.
+    let mut body = vec![0x01];
+    body.extend_from_slice(&2u64.to_le_bytes());
+    body.push(record.len() as u8);
+    for (i, action) in record.actions.iter().enumerate() {
+        let mut packed = PackedAction::pack(action).0;
+        if i == 0 {
+            packed |= 1 << 4;
+        }
+        let mut value = packed as u64;
+        while value >= 0x80 {
+            body.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        body.push(value as u8);
+        if i == 0 {
+            body.extend_from_slice(&[3, 0xaa, 0xbb, 0xcc]);
+        }
+    }
+    bytes.push(0x01);
+    bytes.push(body.len() as u8);
+    bytes.extend_from_slice(&body);
+
+    let mut expected = record.clone();
+    expected.end = None;
+    let mut reader = BinaryReader::new(bytes.as_slice()).unwrap();
+    assert_eq!(reader.read_game().unwrap(), Some(expected));
+    assert_eq!(reader.read_game().unwrap(), None);
+
+    // Unknown tag where the next game should start.
+    bytes.push(0x07);
+    let mut reader = BinaryReader::new(bytes.as_slice()).unwrap();
+    reader.read_game().unwrap();
+    let offset = (bytes.len() - 1) as u64;
+    assert!(matches!(reader.read_game(), Err(ArchiveError::Corrupt(o)) if o == offset));
+}
+
+#[test]
+fn packed_actions_round_trip() {
+    for record in records(10) {
+        for action in record.actions.iter() {
+            let packed = PackedAction::pack(action);
+            assert!(packed.0 < 1 << 23);
+            assert!(!packed.has_annotation());
+            assert_eq!(packed.unpack().as_ref(), Some(action));
+        }
+    }
+    // Drawn tile id that no tile has.
+    assert_eq!(PackedAction(63 << 5).unpack(), None);
+    assert!(TileType::from_id(63).is_none());
+}
+
+#[test]
+fn size_of_a_large_archive() {
+    // Report only, sizes depend on the random games. Random play is slow in
+    // debug builds, so 50 games are repeated.
+    let games: Vec<GameRecord> = (0..50).map(|seed| random_game(seed, 120)).collect();
+    let records: Vec<GameRecord> = games.iter().cycle().take(1000).cloned().collect();
+    let text = text_archive(&records);
+    let binary = binary_archive(&records);
+    println!(
+        "1000 games: text {} bytes, binary {} bytes, {:.1} times smaller",
+        text.len(),
+        binary.len(),
+        text.len() as f64 / binary.len() as f64
+    );
+}
@@ -0,0 +1,153 @@
+//! Strikes and jumps go over tiles and Defence in between, only the target
+//! matters.
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::{
+    get_tile_actions, is_in_check, validate_action, Action, ActionData, ActionResult, Coordinate,
+    GameState, TileColor, TileType,
+};
+
+const FROM: Coordinate = Coordinate { x: 2, y: 1 };
+const BETWEEN: Coordinate = Coordinate { x: 2, y: 2 };
+const TARGET: Coordinate = Coordinate { x: 2, y: 3 };
+
+/// Black Duke on a1, White Duke on f6, plus tiles. Black to go.
+fn state(tiles: &[(Coordinate, TileType, TileColor, bool)]) -> GameState {
+    let mut builder = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(5, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        );
+    for (cord, kind, color, flipped) in tiles.iter() {
+        builder = builder.tile_at(*cord, *kind, *color, *flipped);
+    }
+    builder.ply(TileColor::Black).build().unwrap()
+}
+
+/// Jump or strike of the tile on `FROM` onto `TARGET`.
+fn action_onto_target(state: &GameState) -> Option<Action> {
+    get_tile_actions(state, FROM)
+        .into_iter()
+        .find(|action| match action {
+            Action::Jump(data) | Action::Strike(data) => data.target_pos == TARGET,
+            _ => false,
+        })
+}
+
+/// Tiles put between `FROM` and `TARGET`: nothing, a Footman of either
+/// color, and Defence of a White Guinevere on b3.
+fn in_between() -> Vec<Vec<(Coordinate, TileType, TileColor, bool)>> {
+    vec![
+        vec![],
+        vec![(BETWEEN, TileType::Footman, TileColor::Black, false)],
+        vec![(BETWEEN, TileType::Footman, TileColor::White, false)],
+        vec![(
+            Coordinate::new(1, 2),
+            TileType::Guinevere,
+            TileColor::White,
+            false,
+        )],
+    ]
+}
+
+#[test]
+fn strike_goes_over_tiles() {
+    // Bowman back side strikes two ahead.
+    for between in in_between() {
+        let mut tiles = between.clone();
+        tiles.push((FROM, TileType::Bowman, TileColor::Black, true));
+        tiles.push((TARGET, TileType::Pikeman, TileColor::White, false));
+        let state = state(&tiles);
+
+        let action = action_onto_target(&state);
+        assert!(matches!(action, Some(Action::Strike(_))), "{:?}", between);
+        assert_eq!(validate_action(&state, &action.unwrap()), Ok(()));
+    }
+}
+
+#[test]
+fn strike_checks_over_tiles() {
+    let state = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(TARGET, TileType::Duke, TileColor::White, false)
+        .tile_at(FROM, TileType::Bowman, TileColor::Black, true)
+        .tile_at(BETWEEN, TileType::Footman, TileColor::White, false)
+        .ply(TileColor::White)
+        .build()
+        .unwrap();
+    assert!(is_in_check(&state, TileColor::White));
+}
+
+#[test]
+fn strike_is_blocked_by_defence_on_target() {
+    // Guinevere on d4 defends c4.
+    let state = state(&[
+        (FROM, TileType::Bowman, TileColor::Black, true),
+        (TARGET, TileType::Pikeman, TileColor::White, false),
+        (
+            Coordinate::new(3, 3),
+            TileType::Guinevere,
+            TileColor::White,
+            false,
+        ),
+    ]);
+    assert_eq!(action_onto_target(&state), None);
+
+    let strike = Action::Strike(ActionData {
+        tile_pos: FROM,
+        target_pos: TARGET,
+        result: ActionResult::Capture,
+    });
+    assert!(validate_action(&state, &strike).is_err());
+}
+
+#[test]
+fn jump_goes_over_tiles() {
+    // Champion front side jumps two ahead.
+    for between in in_between() {
+        let mut tiles = between.clone();
+        tiles.push((FROM, TileType::Champion, TileColor::Black, false));
+        assert!(
+            matches!(action_onto_target(&state(&tiles)), Some(Action::Jump(_))),
+            "{:?}",
+            between
+        );
+
+        // Target decides: own tile or Defence block, enemy tile is captured.
+        let mut own = tiles.clone();
+        own.push((TARGET, TileType::Footman, TileColor::Black, false));
+        assert_eq!(action_onto_target(&state(&own)), None);
+
+        let mut enemy = tiles.clone();
+        enemy.push((TARGET, TileType::Footman, TileColor::White, false));
+        assert!(matches!(
+            action_onto_target(&state(&enemy)),
+            Some(Action::Jump(ActionData {
+                result: ActionResult::Capture,
+                ..
+            }))
+        ));
+
+        let mut defended = tiles.clone();
+        defended.push((
+            Coordinate::new(3, 3),
+            TileType::Guinevere,
+            TileColor::White,
+            false,
+        ));
+        assert_eq!(action_onto_target(&state(&defended)), None);
+    }
+}
@@ -0,0 +1,176 @@
+//! Games on boards of other sizes than the standard 6x6.
+
+use rusty_duke_logic::ai::alpha_beta::Agent;
+use rusty_duke_logic::ai::random::RandomAgent;
+use rusty_duke_logic::ai::DukeAgent;
+use rusty_duke_logic::logic::builder::{GameStateBuilder, SetupError};
+use rusty_duke_logic::logic::fen::FenError;
+use rusty_duke_logic::logic::variant::GameRules;
+use rusty_duke_logic::logic::{
+    do_action, get_legal_actions, Action, BoardSize, Coordinate, GameState, TileColor, TileType,
+};
+
+fn rules(size: BoardSize) -> GameRules {
+    GameRules {
+        size,
+        turn_limit: Some(120),
+        ..GameRules::standard()
+    }
+}
+
+/// Squares an action moves from or to.
+fn action_squares(action: &Action) -> Vec<Coordinate> {
+    match action {
+        Action::NewFromBag => vec![],
+        Action::PlaceNew(cord) => vec![*cord],
+        Action::Move(data)
+        | Action::Jump(data)
+        | Action::JumpSlide(data)
+        | Action::Slide(data)
+        | Action::Strike(data) => vec![data.tile_pos, data.target_pos],
+        Action::Command(data) => vec![data.command_tile_pos, data.tile_pos, data.target_pos],
+    }
+}
+
+#[test]
+fn dukes_start_in_the_middle_of_the_home_rows() {
+    let mut state = GameState::new_with_rules(&rules(BoardSize::new(8, 8)), Some(1));
+    assert_eq!(state.size(), BoardSize::new(8, 8));
+
+    // d1 and e1 for Black, d8 and e8 for White.
+    for (color, y) in [(TileColor::Black, 0), (TileColor::White, 7)] {
+        assert_eq!(state.ply, color);
+        let actions = get_legal_actions(&state);
+        assert_eq!(
+            actions,
+            vec![
+                Action::PlaceNew(Coordinate::new(3, y)),
+                Action::PlaceNew(Coordinate::new(4, y))
+            ]
+        );
+        do_action(&mut state, &actions[0]).unwrap();
+    }
+}
+
+#[test]
+fn random_games_stay_on_the_board() {
+    for size in [BoardSize::new(8, 8), BoardSize::new(4, 5)] {
+        for seed in 0..4 {
+            let mut state = GameState::new_with_rules(&rules(size), Some(seed));
+            let mut agents = [
+                RandomAgent::new_seeded(TileColor::Black, seed),
+                RandomAgent::new_seeded(TileColor::White, seed + 100),
+            ];
+            while state.game_over.is_none() {
+                let actions = get_legal_actions(&state);
+                for action in actions.iter() {
+                    for cord in action_squares(action) {
+                        assert!(size.contains(cord), "{:?} off {:?}", action, size);
+                    }
+                }
+
+                let action = agents[state.ply as usize].choose_action(&state).unwrap();
+                assert!(actions.contains(&action));
+                do_action(&mut state, &action).unwrap();
+
+                let parsed = GameState::from_fen(&state.to_fen()).unwrap();
+                assert_eq!(parsed.size(), size);
+                assert_eq!(parsed.to_fen(), state.to_fen());
+                assert_eq!(parsed.hash(), state.hash());
+            }
+            for (cord, _) in state.iter_tiles(None) {
+                assert!(size.contains(cord));
+            }
+        }
+    }
+}
+
+#[test]
+fn agent_plays_on_a_large_board() {
+    let mut state = GameState::new_with_rules(&rules(BoardSize::MAX), Some(3));
+    let mut agents = [
+        Agent::new(TileColor::Black, Some(2), None),
+        Agent::new(TileColor::White, Some(2), None),
+    ];
+    for _ in 0..20 {
+        if state.game_over.is_some() {
+            break;
+        }
+        let action = agents[state.ply as usize].choose_action(&state).unwrap();
+        assert!(get_legal_actions(&state).contains(&action));
+        do_action(&mut state, &action).unwrap();
+    }
+    assert!(state.turn >= 2);
+}
+
+#[test]
+fn squares_beyond_the_standard_board() {
+    let corner = Coordinate::new(7, 7);
+    assert!(BoardSize::MAX.contains(corner));
+    assert!(!BoardSize::STANDARD.contains(corner));
+    assert_eq!(
+        BoardSize::MAX.mirror(Coordinate::new(1, 2)),
+        Coordinate::new(6, 2)
+    );
+    assert_eq!(BoardSize::STANDARD.squares().count(), 36);
+
+    // Width is even, and no side is longer than 8.
+    assert_eq!(BoardSize::try_new(7, 8), None);
+    assert_eq!(BoardSize::try_new(10, 8), None);
+    assert_eq!(BoardSize::try_new(8, 9), None);
+
+    let built = GameStateBuilder::new()
+        .tile_at(corner, TileType::Duke, TileColor::White, false)
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        );
+    assert_eq!(
+        built.clone().build().unwrap_err(),
+        SetupError::IllegalSquare(corner)
+    );
+    assert_eq!(
+        built.size(BoardSize::MAX).build().unwrap().size(),
+        BoardSize::MAX
+    );
+}
+
+#[test]
+fn fen_gives_the_board_size() {
+    let state = GameState::from_fen("d7/8/8/8/8/7D b -/- -/- -").unwrap();
+    assert_eq!(state.size(), BoardSize::new(8, 6));
+    assert_eq!(
+        state.duke_pos(TileColor::White),
+        Some(Coordinate::new(7, 0))
+    );
+
+    assert_eq!(
+        GameState::from_fen("d4/5/5/5/4D b -/- -/- -").unwrap_err(),
+        FenError::RowLength(1)
+    );
+    assert_eq!(
+        GameState::from_fen("d5/6/8/5D b -/- -/- -").unwrap_err(),
+        FenError::RowLength(6)
+    );
+    assert_eq!(
+        GameState::from_fen("d1/2/2/2/2/2/2/2/1D b -/- -/- -").unwrap_err(),
+        FenError::RowCount(9)
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_position_keeps_the_board_size() {
+    use rusty_duke_logic::logic::json;
+
+    let mut state = GameState::new_with_rules(&rules(BoardSize::new(8, 4)), Some(5));
+    for _ in 0..6 {
+        let action = get_legal_actions(&state)[0];
+        do_action(&mut state, &action).unwrap();
+    }
+    let loaded = json::from_json_position(&json::to_json_position(&state)).unwrap();
+    assert_eq!(loaded.size(), state.size());
+    assert_eq!(loaded.hash(), state.hash());
+}
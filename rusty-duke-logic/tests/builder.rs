@@ -0,0 +1,190 @@
+//! Positions built by `GameStateBuilder`, and positions it rejects.
+
+use rusty_duke_logic::ai::{alpha_beta::Agent, DukeAgent};
+use rusty_duke_logic::logic::builder::{GameStateBuilder, SetupError};
+use rusty_duke_logic::logic::{
+    do_action, get_legal_actions, get_spawn_squares, Action, Coordinate, Effect, TileColor,
+    TileType, WinReason, Winner, HEIGHT,
+};
+
+/// Black Duke on a1 and White Duke on f6.
+fn dukes() -> GameStateBuilder {
+    GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(5, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+}
+
+#[test]
+fn duke_positions_and_effects_are_set() {
+    let state = dukes()
+        .tile_at(
+            Coordinate::new(2, 2),
+            TileType::Guinevere,
+            TileColor::White,
+            false,
+        )
+        .bag(TileColor::Black, vec![TileType::Knight, TileType::Pikeman])
+        .ply(TileColor::White)
+        .build()
+        .unwrap();
+
+    assert_eq!(state.ply, TileColor::White);
+    assert_eq!(*state.own_duke_pos(), Some(Coordinate::new(5, 5)));
+    assert_eq!(*state.opponent_duke_pos(), Some(Coordinate::new(0, 0)));
+    assert_eq!(state.bags[TileColor::Black as usize].len(), 2);
+    assert!(state.bags[TileColor::White as usize].is_empty());
+
+    // Spawn squares are next to the White Duke.
+    let mut spawn = get_spawn_squares(&state);
+    spawn.sort_by_key(|c| (c.y, c.x));
+    assert_eq!(spawn, [Coordinate::new(5, 4), Coordinate::new(4, 5)]);
+
+    // Guinevere defends the squares left and right of her.
+    for x in 0..6 {
+        let effects = state.square(Coordinate::new(x, 2)).effects;
+        let defended = x == 1 || x == 3;
+        assert_eq!(
+            effects.count_of(&Effect::Defence, TileColor::White),
+            defended as u8,
+            "{}",
+            x
+        );
+    }
+}
+
+#[test]
+fn duke_to_place_replaces_duke_on_board() {
+    let state = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(5, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+        .drawn(TileColor::Black, TileType::Duke)
+        .build()
+        .unwrap();
+    assert_eq!(*state.own_duke_pos(), None);
+    assert!(get_spawn_squares(&state)
+        .iter()
+        .all(|c| c.y == 0 || c.y == HEIGHT - 1));
+}
+
+#[test]
+fn invalid_positions_are_rejected() {
+    let off_board = Coordinate { x: 6, y: 0 };
+    assert_eq!(
+        dukes()
+            .tile_at(off_board, TileType::Footman, TileColor::Black, false)
+            .build()
+            .unwrap_err(),
+        SetupError::IllegalSquare(off_board)
+    );
+
+    let taken = Coordinate::new(5, 5);
+    assert_eq!(
+        dukes()
+            .tile_at(taken, TileType::Footman, TileColor::Black, false)
+            .build()
+            .unwrap_err(),
+        SetupError::DuplicateSquare(taken)
+    );
+
+    assert_eq!(
+        dukes()
+            .tile_at(
+                Coordinate::new(3, 3),
+                TileType::Duke,
+                TileColor::White,
+                false
+            )
+            .build()
+            .unwrap_err(),
+        SetupError::DuplicateDuke(TileColor::White)
+    );
+    assert_eq!(
+        dukes()
+            .drawn(TileColor::Black, TileType::Duke)
+            .build()
+            .unwrap_err(),
+        SetupError::DuplicateDuke(TileColor::Black)
+    );
+
+    // No Duke on board, and the drawn tile to place next isn't one.
+    assert_eq!(
+        GameStateBuilder::new()
+            .tile_at(
+                Coordinate::new(0, 0),
+                TileType::Duke,
+                TileColor::Black,
+                false,
+            )
+            .drawn(TileColor::White, TileType::Duke)
+            .drawn(TileColor::White, TileType::Footman)
+            .build()
+            .unwrap_err(),
+        SetupError::MissingDuke(TileColor::White)
+    );
+}
+
+#[test]
+fn ai_finds_the_mating_move() {
+    // Guardmate in one, see the module doc of builder. Only the Footman
+    // moving from a3 to a5 mates.
+    let footman = Coordinate::new(0, 2);
+    let mate = Coordinate::new(0, 4);
+    let state = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(1, 0),
+            TileType::Duke,
+            TileColor::Black,
+            true,
+        )
+        .tile_at(
+            Coordinate::new(1, 5),
+            TileType::Pikeman,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(footman, TileType::Footman, TileColor::Black, true)
+        .ply(TileColor::Black)
+        .build()
+        .unwrap();
+
+    let mating: Vec<Action> = get_legal_actions(&state)
+        .into_iter()
+        .filter(|action| {
+            let mut state = state.clone();
+            do_action(&mut state, action).unwrap();
+            state.win_reason == Some(WinReason::Guardmate)
+        })
+        .collect();
+    assert!(matches!(
+        mating.as_slice(),
+        [Action::Move(data)] if data.tile_pos == footman && data.target_pos == mate
+    ));
+
+    let mut agent = Agent::new(TileColor::Black, Some(2), None);
+    let action = agent.choose_action(&state).unwrap();
+    assert_eq!(action, mating[0]);
+    let mut state = state;
+    do_action(&mut state, &action).unwrap();
+    assert_eq!(state.game_over, Some(Winner::Color(TileColor::Black)));
+    assert_eq!(state.win_reason, Some(WinReason::Guardmate));
+}
@@ -0,0 +1,112 @@
+//! Calibration of search depth, from made up measurements and from a
+//! benchmark timed by a made up clock.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use rusty_duke_logic::ai::calibrate::{
+    calibrate_with_clock, estimate, Calibration, Difficulty, HardwareFingerprint, MAX_DEPTH,
+    MIN_DEPTH,
+};
+use std::time::{Duration, Instant};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn estimate_from_made_up_measurements() {
+    // Tree grows 4 times per depth, 10 000 nodes per second.
+    let measurements = [(10, ms(1)), (40, ms(4)), (160, ms(16)), (640, ms(64))];
+    let calibration = estimate(&measurements, 4);
+    assert!((calibration.nodes_per_second - 10_000.0).abs() < 1e-6);
+    assert!((calibration.branching_factor - 4.0).abs() < 1e-9);
+    assert_eq!(calibration.reference_depth, 4);
+    assert_eq!(calibration.reference_nodes, 640.0);
+    assert_eq!(
+        calibration.fingerprint,
+        HardwareFingerprint::new(4, 10_000.0)
+    );
+
+    assert_eq!(calibration.estimate_nodes(5), 2560.0);
+    assert_eq!(calibration.estimate_time(6), ms(1024));
+    // 256 ms at depth 5, 1 s at 6, 4 s at 7.
+    assert_eq!(calibration.depth_for_difficulty(Difficulty::Easy), 4);
+    assert_eq!(calibration.depth_for(ms(1024)), 6);
+    assert_eq!(calibration.depth_for_difficulty(Difficulty::Hard), 7);
+    assert_eq!(calibration.depth_for(Duration::ZERO), MIN_DEPTH);
+    assert_eq!(
+        calibration.depth_for(Duration::from_secs(1 << 40)),
+        MAX_DEPTH
+    );
+
+    // Noisy measurements never shrink the tree.
+    let noisy = estimate(&[(100, ms(1)), (50, ms(1))], 1);
+    assert_eq!(noisy.branching_factor, 1.5);
+}
+
+#[test]
+fn faster_hardware_searches_deeper() {
+    let slow = estimate(&[(10, ms(10)), (100, ms(100)), (1000, ms(1000))], 1);
+    let fast = estimate(&[(10, ms(1)), (100, ms(10)), (1000, ms(100))], 1);
+    for difficulty in Difficulty::ALL {
+        assert!(fast.depth_for_difficulty(difficulty) > slow.depth_for_difficulty(difficulty));
+    }
+}
+
+#[test]
+fn fingerprints_of_other_hardware_need_recalibration() {
+    let calibration = estimate(&[(100, ms(10))], 8);
+    assert!(!calibration.needs_recalibration(&HardwareFingerprint::new(8, 10_000.0)));
+    // Neighbouring scores are noise.
+    assert!(!calibration.needs_recalibration(&HardwareFingerprint::new(8, 20_000.0)));
+    assert!(calibration.needs_recalibration(&HardwareFingerprint::new(8, 40_000.0)));
+    assert!(calibration.needs_recalibration(&HardwareFingerprint::new(4, 10_000.0)));
+}
+
+#[test]
+fn calibration_text() {
+    let calibration = estimate(&[(10, ms(1)), (45, ms(3)), (170, ms(9))], 2);
+    assert_eq!(
+        Calibration::from_text(&calibration.to_text()),
+        Some(calibration)
+    );
+    assert_eq!(
+        Calibration::from_text(&calibration.to_text().replace("cores 2", "cores x")),
+        None
+    );
+}
+
+/// Clock that moves by step every time it is read. Starts well ahead of the
+/// real clock, so searches are never cancelled.
+fn made_up_clock(step: Duration) -> impl FnMut() -> Instant {
+    let mut now = Instant::now() + Duration::from_secs(3600);
+    move || {
+        now += step;
+        now
+    }
+}
+
+#[test]
+fn calibration_with_a_made_up_clock_repeats() {
+    // Every depth takes a step, so deeper searches seem faster per node.
+    let budget = ms(200);
+    let calibration = calibrate_with_clock(budget, made_up_clock(ms(12)));
+    assert_eq!(
+        calibrate_with_clock(budget, made_up_clock(ms(12))),
+        calibration
+    );
+    assert_eq!(calibration.reference_depth, 3);
+
+    // Slower hardware, the same nodes in more time.
+    let slow = calibrate_with_clock(budget, made_up_clock(ms(100)));
+    assert_eq!(slow.reference_depth, 1);
+    assert!(slow.nodes_per_second < calibration.nodes_per_second);
+    assert!(
+        slow.depth_for_difficulty(Difficulty::Normal)
+            < calibration.depth_for_difficulty(Difficulty::Normal)
+    );
+
+    // Depth 1 is measured even if budget is gone at once.
+    let tiny = calibrate_with_clock(Duration::ZERO, made_up_clock(ms(12)));
+    assert_eq!(tiny.reference_depth, 1);
+}
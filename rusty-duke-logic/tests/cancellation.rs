@@ -0,0 +1,105 @@
+//! Cancellation tokens, and searches stopped by them.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use rusty_duke_logic::ai::alpha_beta::{get_action_with_stats, Agent};
+use rusty_duke_logic::ai::CancellationToken;
+use rusty_duke_logic::logic::{validate_action, GameState};
+use std::time::{Duration, Instant};
+
+#[test]
+fn cancelling_a_parent_cancels_its_children() {
+    let parent = CancellationToken::new();
+    let child = parent.child();
+    let grandchild = child.child();
+    let timed_child = parent.child_with_deadline(Instant::now() + Duration::from_secs(3600));
+    let sibling = CancellationToken::new();
+
+    parent.cancel();
+    assert!(parent.is_cancelled());
+    assert!(child.is_cancelled());
+    assert!(grandchild.is_cancelled());
+    assert!(timed_child.is_cancelled());
+    assert!(!sibling.is_cancelled());
+}
+
+#[test]
+fn cancelling_a_child_leaves_its_parent() {
+    let parent = CancellationToken::new();
+    let child = parent.child();
+    let other_child = parent.child();
+
+    child.cancel();
+    assert!(child.is_cancelled());
+    assert!(!parent.is_cancelled());
+    assert!(!other_child.is_cancelled());
+}
+
+#[test]
+fn clones_share_cancellation() {
+    let token = CancellationToken::new();
+    let clone = token.clone();
+    clone.cancel();
+    assert!(token.is_cancelled());
+}
+
+#[test]
+fn expired_deadline_reads_as_cancelled() {
+    let now = Instant::now();
+    assert!(CancellationToken::with_deadline(now).is_cancelled());
+    assert!(!CancellationToken::with_deadline(now + Duration::from_secs(3600)).is_cancelled());
+
+    // Deadline of a parent applies to its children.
+    let parent = CancellationToken::with_deadline(now);
+    assert!(parent.child().is_cancelled());
+    // Deadline of a child does not apply to its parent.
+    let parent = CancellationToken::new();
+    assert!(parent.child_with_deadline(now).is_cancelled());
+    assert!(!parent.is_cancelled());
+
+    let soon = CancellationToken::with_deadline(Instant::now() + Duration::from_millis(20));
+    assert!(!soon.is_cancelled());
+    std::thread::sleep(Duration::from_millis(30));
+    assert!(soon.is_cancelled());
+}
+
+#[test]
+fn tokens_are_not_cancelled_by_themselves() {
+    let token = CancellationToken::default();
+    let child = token.child();
+    for _ in 0..10_000 {
+        assert!(!token.is_cancelled() && !child.is_cancelled());
+    }
+}
+
+#[test]
+fn cancelled_search_returns_best_action_so_far() {
+    // Far too deep to finish, only cancellation stops the search. Midgame
+    // position of tests/deepening.rs.
+    let fen = "2FDPF'/3EF'1/6/2f'3/b5/d'1w'f2 b fpppkelyscagmnr/YPRPKBLWSCAGMN -/- - 16";
+    let state = GameState::from_fen(fen).unwrap();
+    let mut agent = Agent::new(state.ply, Some(30), None);
+    agent.transposition_table = false;
+    let token = CancellationToken::new();
+
+    let canceller = {
+        let token = token.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            token.cancel();
+            Instant::now()
+        })
+    };
+    let (action, stats) = get_action_with_stats(&agent, &state, Some(&token));
+    let returned = Instant::now();
+    let cancelled = canceller.join().unwrap();
+
+    assert!(stats.cancelled, "{:?}", stats);
+    assert!(stats.depth >= 1 && stats.depth < 30, "{:?}", stats);
+    assert_eq!(validate_action(&state, &action.unwrap()), Ok(()));
+    assert!(
+        returned.duration_since(cancelled) < Duration::from_millis(500),
+        "Search returned {:?} after cancel.",
+        returned.duration_since(cancelled)
+    );
+}
@@ -0,0 +1,67 @@
+//! Positions whose Duke was captured, queried as if the game went on, e.g. by
+//! a search before it checks `game_over`.
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::{
+    do_action, get_actions, get_legal_actions, get_spawn_squares, get_tile_actions, is_in_check,
+    Action, Coordinate, GameState, TileColor, TileType,
+};
+
+/// Black Lancelot has struck the White Duke. White to go, game not over.
+fn without_white_duke() -> GameState {
+    let lancelot = Coordinate::new(2, 1);
+    let mut state = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(3, 3),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+        .tile_at(lancelot, TileType::Lancelot, TileColor::Black, false)
+        .tile_at(
+            Coordinate::new(5, 5),
+            TileType::Footman,
+            TileColor::White,
+            false,
+        )
+        .bag(TileColor::White, vec![TileType::Pikeman])
+        .ply(TileColor::Black)
+        .seed(1)
+        .build()
+        .unwrap();
+    let strike = get_tile_actions(&state, lancelot)
+        .into_iter()
+        .find(|action| matches!(action, Action::Strike(_)))
+        .unwrap();
+    do_action(&mut state, &strike).unwrap();
+    assert!(state.game_over.is_some());
+
+    state.game_over = None;
+    state.win_reason = None;
+    state
+}
+
+#[test]
+fn nowhere_to_spawn() {
+    let state = without_white_duke();
+    assert_eq!(state.ply, TileColor::White);
+    assert!(get_spawn_squares(&state).is_empty());
+
+    // Tiles can still act, but can't draw without a Duke.
+    let actions = get_actions(&state);
+    assert!(!actions.is_empty());
+    assert!(!actions.contains(&Action::NewFromBag));
+    assert!(!is_in_check(&state, TileColor::White));
+    assert_eq!(get_legal_actions(&state), actions);
+
+    let mut state = state;
+    assert!(do_action(&mut state, &Action::NewFromBag).is_err());
+    let place = Action::PlaceNew(Coordinate::new(4, 5));
+    assert!(do_action(&mut state, &place).is_err());
+}
@@ -0,0 +1,66 @@
+//! Built-in tile definitions match the printed cards in `cards::CARDS`, front
+//! and back of every tile type.
+
+use rusty_duke_logic::logic::cards::{self, CardMismatch, CARDS};
+use rusty_duke_logic::logic::tiles::{builtin, parse_tile_definitions};
+use rusty_duke_logic::logic::{IntoEnumIterator, TileType};
+
+/// Mismatches, one per line.
+fn report(mismatches: &[CardMismatch]) -> String {
+    mismatches
+        .iter()
+        .map(|mismatch| mismatch.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn builtin_tiles_match_cards() {
+    let definitions = builtin();
+    for kind in TileType::iter() {
+        assert!(definitions.actions.contains_key(&kind), "{}", kind);
+        assert!(CARDS.iter().any(|card| card.kind == kind), "{}", kind);
+    }
+
+    let mismatches = cards::check(&definitions.actions);
+    assert!(mismatches.is_empty(), "{}", report(&mismatches));
+}
+
+#[test]
+fn transcription_error_is_found() {
+    // Countess as it once was, with one command offset of each side swapped.
+    let countess = parse_tile_definitions(
+        r#"
+        [Countess]
+        front = [
+            { kind = "Move", x = 0, y = 1 },
+            { kind = "Move", x = 2, y = 0 },
+            { kind = "Move", x = 0, y = -1 },
+            { kind = "Move", x = -2, y = 0 },
+            { kind = "Command", x = 2, y = 0 },
+            { kind = "Command", x = -1, y = 0 },
+        ]
+        back = [
+            { kind = "Move", x = 0, y = 1 },
+            { kind = "Move", x = 1, y = 0 },
+            { kind = "Move", x = 0, y = -2 },
+            { kind = "Move", x = -1, y = 0 },
+            { kind = "Command", x = 1, y = 0 },
+            { kind = "Command", x = -2, y = 0 },
+        ]
+        "#,
+    )
+    .unwrap();
+    let mut actions = builtin().actions;
+    actions.extend(countess.actions);
+
+    let mismatches = cards::check(&actions);
+    assert_eq!(mismatches.len(), 4, "{}", report(&mismatches));
+    assert!(mismatches.iter().all(|mismatch| matches!(
+        mismatch,
+        CardMismatch::Square {
+            kind: TileType::Countess,
+            ..
+        }
+    )));
+}
@@ -0,0 +1,107 @@
+//! Dukes in check by slides, strikes and commands.
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::{
+    attackers_of, is_in_check, Action, Coordinate, GameState, TileColor, TileType,
+};
+
+/// Black Duke on duke, White Duke in a corner, plus White tiles. Black to go.
+fn state(duke: Coordinate, tiles: &[(Coordinate, TileType, bool)]) -> GameState {
+    let mut builder = GameStateBuilder::new()
+        .tile_at(duke, TileType::Duke, TileColor::Black, false)
+        .tile_at(
+            Coordinate::new(0, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        );
+    for (cord, kind, flipped) in tiles.iter() {
+        builder = builder.tile_at(*cord, *kind, TileColor::White, *flipped);
+    }
+    builder.ply(TileColor::Black).build().unwrap()
+}
+
+#[test]
+fn slide_down_file() {
+    // Knight back side slides ahead, down the board for White.
+    let duke = Coordinate::new(2, 1);
+    let knight = Coordinate::new(2, 5);
+    let state = state(duke, &[(knight, TileType::Knight, true)]);
+
+    let attackers = attackers_of(&state, duke);
+    assert_eq!(attackers.len(), 1);
+    assert_eq!(attackers[0].0, knight);
+    assert!(matches!(attackers[0].1, Action::Slide(data) if data.target_pos == duke));
+    assert!(is_in_check(&state, TileColor::Black));
+    assert!(!is_in_check(&state, TileColor::White));
+}
+
+#[test]
+fn blocked_slide_is_no_check() {
+    let duke = Coordinate::new(2, 1);
+    let state = GameStateBuilder::new()
+        .tile_at(duke, TileType::Duke, TileColor::Black, false)
+        .tile_at(
+            Coordinate::new(2, 3),
+            TileType::Footman,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(0, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(2, 5),
+            TileType::Knight,
+            TileColor::White,
+            true,
+        )
+        .build()
+        .unwrap();
+
+    assert!(attackers_of(&state, duke).is_empty());
+    assert!(!is_in_check(&state, TileColor::Black));
+}
+
+#[test]
+fn strike_from_two_squares_away() {
+    // Pikeman back side strikes two ahead and one aside.
+    let duke = Coordinate::new(2, 1);
+    let pikeman = Coordinate::new(3, 3);
+    let state = state(duke, &[(pikeman, TileType::Pikeman, true)]);
+
+    let attackers = attackers_of(&state, duke);
+    assert_eq!(attackers.len(), 1);
+    assert_eq!(attackers[0].0, pikeman);
+    assert!(matches!(attackers[0].1, Action::Strike(data) if data.target_pos == duke));
+    assert!(is_in_check(&state, TileColor::Black));
+}
+
+#[test]
+fn command_capture() {
+    // General back side commands the Footman onto the Duke. Neither can
+    // capture the Duke by itself.
+    let duke = Coordinate::new(4, 4);
+    let general = Coordinate::new(3, 3);
+    let footman = Coordinate::new(2, 4);
+    let state = state(
+        duke,
+        &[
+            (general, TileType::General, true),
+            (footman, TileType::Footman, false),
+        ],
+    );
+
+    let attackers = attackers_of(&state, duke);
+    assert_eq!(attackers.len(), 1);
+    assert_eq!(attackers[0].0, general);
+    assert!(matches!(attackers[0].1, Action::Command(data)
+        if data.command_tile_pos == footman && data.target_pos == duke));
+    assert!(is_in_check(&state, TileColor::Black));
+
+    // Empty squares have no attackers.
+    assert!(attackers_of(&state, Coordinate::new(5, 0)).is_empty());
+}
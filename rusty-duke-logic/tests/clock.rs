@@ -0,0 +1,113 @@
+//! Chess clocks of timed games, flag fall and the time budget of the AI.
+//! Clocks are driven by made up instants, nothing sleeps.
+
+use rusty_duke_logic::ai::alpha_beta::{turn_budget, Agent};
+use rusty_duke_logic::ai::DukeAgent;
+use rusty_duke_logic::logic::clock::GameClock;
+use rusty_duke_logic::logic::{self, do_action, GameState, TileColor, WinReason, Winner};
+use std::time::{Duration, Instant};
+
+fn secs(s: u64) -> Duration {
+    Duration::from_secs(s)
+}
+
+#[test]
+fn increment_is_added_after_each_turn() {
+    let start = Instant::now();
+    let mut clock = GameClock::new(secs(60), secs(2));
+    assert_eq!(clock.running(), None);
+
+    clock.start_turn(TileColor::Black, start);
+    assert_eq!(clock.running(), Some(TileColor::Black));
+    assert_eq!(clock.remaining(TileColor::Black, start + secs(5)), secs(55));
+    // Only the clock of the color to go runs.
+    assert_eq!(clock.remaining(TileColor::White, start + secs(5)), secs(60));
+
+    // Starting White stops Black.
+    clock.start_turn(TileColor::White, start + secs(5));
+    assert_eq!(clock.running(), Some(TileColor::White));
+    assert_eq!(
+        clock.remaining(TileColor::Black, start + secs(100)),
+        secs(57)
+    );
+
+    clock.end_turn(TileColor::White, start + secs(8));
+    assert_eq!(clock.running(), None);
+    assert_eq!(
+        clock.remaining(TileColor::White, start + secs(100)),
+        secs(59)
+    );
+    // Not running, nothing happens.
+    clock.end_turn(TileColor::White, start + secs(20));
+    assert_eq!(
+        clock.remaining(TileColor::White, start + secs(100)),
+        secs(59)
+    );
+}
+
+#[test]
+fn no_increment_after_time_ran_out() {
+    let start = Instant::now();
+    let mut clock = GameClock::new(secs(10), secs(5));
+    clock.start_turn(TileColor::White, start);
+    assert_eq!(clock.flagged(start + secs(9)), None);
+    assert_eq!(clock.flagged(start + secs(10)), Some(TileColor::White));
+    assert_eq!(
+        clock.remaining(TileColor::White, start + secs(30)),
+        Duration::ZERO
+    );
+
+    clock.end_turn(TileColor::White, start + secs(30));
+    assert_eq!(clock.remaining(TileColor::White, start), Duration::ZERO);
+    assert_eq!(clock.flagged(start), Some(TileColor::White));
+}
+
+#[test]
+fn flag_fall_is_a_timeout_win_of_the_opponent() {
+    let mut state = GameState::new_seeded(1);
+    logic::flag_fall(&mut state, TileColor::White);
+    assert_eq!(state.game_over, Some(Winner::Color(TileColor::Black)));
+    assert_eq!(state.win_reason, Some(WinReason::Timeout));
+
+    // Game over already, the result stays.
+    logic::flag_fall(&mut state, TileColor::Black);
+    assert_eq!(state.game_over, Some(Winner::Color(TileColor::Black)));
+}
+
+#[test]
+fn turn_budget_splits_remaining_time() {
+    let start = Instant::now();
+    let clock = GameClock::new(secs(60), secs(2));
+
+    // 40 own turns left, plus three quarters of the increment.
+    assert_eq!(
+        turn_budget(&clock, TileColor::Black, 0, start),
+        Duration::from_millis(3000)
+    );
+    // Late in the game at least 10 turns are expected.
+    assert_eq!(
+        turn_budget(&clock, TileColor::Black, 200, start),
+        Duration::from_millis(7500)
+    );
+
+    // Never more than half the remaining time.
+    let mut short = GameClock::new(secs(2), secs(2));
+    assert_eq!(turn_budget(&short, TileColor::White, 200, start), secs(1));
+    short.start_turn(TileColor::White, start);
+    assert_eq!(
+        turn_budget(&short, TileColor::White, 200, start + secs(2)),
+        Duration::ZERO
+    );
+}
+
+#[test]
+fn timed_search_keeps_the_agent_duration() {
+    let start = Instant::now();
+    let state = GameState::new_seeded(1);
+    let mut agent = Agent::new(TileColor::Black, Some(2), Some(secs(5)));
+    let clock = GameClock::new(secs(60), Duration::ZERO);
+
+    let action = agent.choose_action_timed(&state, &clock, start).unwrap();
+    do_action(&mut state.clone(), &action).unwrap();
+    assert_eq!(agent.duration, Some(secs(5)));
+}
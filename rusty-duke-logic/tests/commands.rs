@@ -0,0 +1,105 @@
+//! Command actions where command offsets overlap the squares of the tiles
+//! involved.
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::{
+    get_tile_actions, validate_action, Action, Coordinate, GameState, TileColor, TileType,
+};
+
+/// Black commander, flipped to its back side, on c3. Black Footman on the
+/// commanded square.
+fn state(commander: TileType, commanded: Coordinate, camelot: bool) -> GameState {
+    let mut builder = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(5, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+        .tile_at(Coordinate::new(2, 2), commander, TileColor::Black, true)
+        .tile_at(commanded, TileType::Footman, TileColor::Black, false);
+    if camelot {
+        // Defence on c3, d3, e3, c4 and e4. d3 is a command target of the
+        // General.
+        builder = builder.tile_at(
+            Coordinate::new(3, 3),
+            TileType::Camelot,
+            TileColor::White,
+            false,
+        );
+    }
+    builder.ply(TileColor::Black).build().unwrap()
+}
+
+/// Targets of command actions that move the tile on commanded.
+fn command_targets(state: &GameState, commanded: Coordinate) -> Vec<Coordinate> {
+    let mut targets: Vec<Coordinate> = get_tile_actions(state, Coordinate::new(2, 2))
+        .into_iter()
+        .filter_map(|action| match action {
+            Action::Command(data) if data.command_tile_pos == commanded => Some(data.target_pos),
+            _ => None,
+        })
+        .collect();
+    targets.sort_by_key(|c| (c.y, c.x));
+    targets
+}
+
+#[test]
+fn general_back_commands_to_other_command_squares() {
+    // Footman on b3 stands on a command square of the General, which also
+    // has a Move to b3.
+    let commanded = Coordinate::new(1, 2);
+    let state = state(TileType::General, commanded, false);
+    let targets = command_targets(&state, commanded);
+
+    assert_eq!(
+        targets,
+        vec![
+            Coordinate::new(1, 1),
+            Coordinate::new(2, 1),
+            Coordinate::new(3, 1),
+            Coordinate::new(3, 2),
+        ]
+    );
+    assert!(!targets.contains(&commanded));
+    assert!(!targets.contains(&Coordinate::new(2, 2)));
+}
+
+#[test]
+fn marshall_back_does_not_command_in_place() {
+    let commanded = Coordinate::new(2, 3);
+    let state = state(TileType::Marshall, commanded, false);
+
+    assert_eq!(
+        command_targets(&state, commanded),
+        vec![Coordinate::new(1, 3), Coordinate::new(3, 3)]
+    );
+}
+
+#[test]
+fn defence_blocks_command_target() {
+    let commanded = Coordinate::new(1, 2);
+    let state = state(TileType::General, commanded, true);
+    let targets = command_targets(&state, commanded);
+
+    assert!(!targets.contains(&Coordinate::new(3, 2)));
+    assert_eq!(targets.len(), 3);
+
+    let blocked = get_tile_actions(
+        &self::state(TileType::General, commanded, false),
+        Coordinate::new(2, 2),
+    )
+    .into_iter()
+    .find(|action| {
+        matches!(action, Action::Command(data)
+            if data.command_tile_pos == commanded && data.target_pos == Coordinate::new(3, 2))
+    })
+    .unwrap();
+    assert!(validate_action(&state, &blocked).is_err());
+}
@@ -0,0 +1,69 @@
+//! The Countess commands through the squares of its card: two squares to
+//! the side on the front, one square to the side on the back.
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::{
+    get_tile_actions, Action, Coordinate, GameState, TileColor, TileType,
+};
+
+const COUNTESS: Coordinate = Coordinate { x: 2, y: 2 };
+
+/// Black Countess on c3, Black Footmen on a3 and b3.
+fn state(flipped: bool) -> GameState {
+    GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(5, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+        .tile_at(COUNTESS, TileType::Countess, TileColor::Black, flipped)
+        .tile_at(
+            Coordinate::new(0, 2),
+            TileType::Footman,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(1, 2),
+            TileType::Footman,
+            TileColor::Black,
+            false,
+        )
+        .ply(TileColor::Black)
+        .build()
+        .unwrap()
+}
+
+/// Commanded tile and target of every command of the Countess.
+fn commands(state: &GameState) -> Vec<(Coordinate, Coordinate)> {
+    get_tile_actions(state, COUNTESS)
+        .into_iter()
+        .filter_map(|action| match action {
+            Action::Command(data) => Some((data.command_tile_pos, data.target_pos)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn front_commands_two_squares_to_the_side() {
+    assert_eq!(
+        commands(&state(false)),
+        vec![(Coordinate::new(0, 2), Coordinate::new(4, 2))]
+    );
+}
+
+#[test]
+fn back_commands_one_square_to_the_side() {
+    assert_eq!(
+        commands(&state(true)),
+        vec![(Coordinate::new(1, 2), Coordinate::new(3, 2))]
+    );
+}
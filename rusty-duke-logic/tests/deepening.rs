@@ -0,0 +1,64 @@
+//! Timed searches deepen iteratively and choose from a fully searched depth.
+
+use rusty_duke_logic::ai::alpha_beta::{get_action_with_stats, Agent, Duration};
+use rusty_duke_logic::ai::CancellationToken;
+use rusty_duke_logic::logic::{get_actions, GameState};
+
+/// Midgame positions, see `MoveOrdering`.
+const POSITIONS: [&str; 3] = [
+    "2FDPF'/3EF'1/6/2f'3/b5/d'1w'f2 b fpppkelyscagmnr/YPRPKBLWSCAGMN -/- - 16",
+    "d'3PA'/4F'1/4f1/6/3f'2/1D'4 b fpppkblwscyemnr/FEPPKBLWSCYGMNR -/- gFa' 28",
+    "6/6/6/6/2D2d'/1f4 b fpppkblwscagynre/FEPPKBYWSCAGMNR -/- Ff'L'mF'P' 32",
+];
+
+/// Agent without transposition table, so utilities don't depend on what
+/// earlier depths left in the table.
+fn agent(state: &GameState, depth: Option<u8>, duration: Option<Duration>) -> Agent {
+    let mut agent = Agent::new(state.ply, depth, duration);
+    agent.transposition_table = false;
+    agent
+}
+
+#[test]
+fn generous_duration_matches_fixed_depth() {
+    for fen in POSITIONS {
+        let state = GameState::from_fen(fen).unwrap();
+        let fixed = get_action_with_stats(&agent(&state, Some(4), None), &state, None).1;
+        let timed = agent(&state, Some(4), Some(Duration::from_secs(60)));
+        let (action, stats) = get_action_with_stats(&timed, &state, None);
+
+        assert!(get_actions(&state).contains(&action.unwrap()));
+        assert!(!stats.cancelled);
+        assert_eq!(stats.depth, 4);
+        assert_eq!(stats.utility, fixed.utility, "{}", fen);
+        // Shallower depths are searched too.
+        assert!(stats.nodes > fixed.nodes / 2);
+    }
+}
+
+#[test]
+fn duration_without_depth_keeps_deepest_complete_depth() {
+    let state = GameState::from_fen(POSITIONS[0]).unwrap();
+    let timed = agent(&state, None, Some(Duration::from_millis(300)));
+    let (action, stats) = get_action_with_stats(&timed, &state, None);
+
+    assert!(stats.cancelled);
+    assert!(stats.depth >= 2);
+    assert!(get_actions(&state).contains(&action.unwrap()));
+    let fixed = get_action_with_stats(&agent(&state, Some(stats.depth), None), &state, None).1;
+    assert_eq!(stats.utility, fixed.utility);
+}
+
+#[test]
+fn cancelled_search_still_chooses() {
+    let state = GameState::from_fen(POSITIONS[1]).unwrap();
+    let token = CancellationToken::new();
+    token.cancel();
+    let (action, stats) =
+        get_action_with_stats(&agent(&state, Some(6), None), &state, Some(&token));
+
+    // Token is polled every few nodes, so the first depths may finish.
+    assert!(stats.cancelled);
+    assert!(stats.depth >= 1 && stats.depth < 6);
+    assert!(get_actions(&state).contains(&action.unwrap()));
+}
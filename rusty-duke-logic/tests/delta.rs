@@ -0,0 +1,385 @@
+//! State deltas returned by `do_action`, for every kind of action.
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::{
+    do_action, get_tile_actions, undo_action, Action, Coordinate, Effect, GameState, StateDelta,
+    Tile, TileColor, TileMove, TileType, WinReason, Winner,
+};
+
+/// Dukes in opposite corners, Black to go, plus tiles.
+fn state(tiles: &[(Coordinate, TileType, TileColor, bool)]) -> GameState {
+    let mut builder = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(5, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        );
+    for (cord, kind, color, flipped) in tiles.iter() {
+        builder = builder.tile_at(*cord, *kind, *color, *flipped);
+    }
+    builder.ply(TileColor::Black).seed(1).build().unwrap()
+}
+
+/// Do the action of tile on from that ends on target, and matches kind.
+fn act(
+    state: &mut GameState,
+    from: Coordinate,
+    target: Coordinate,
+    kind: fn(&Action) -> bool,
+) -> StateDelta {
+    let action = get_tile_actions(state, from)
+        .into_iter()
+        .find(|action| {
+            kind(action)
+                && match action {
+                    Action::Move(data)
+                    | Action::Jump(data)
+                    | Action::JumpSlide(data)
+                    | Action::Slide(data)
+                    | Action::Strike(data) => data.target_pos == target,
+                    Action::Command(data) => data.target_pos == target,
+                    _ => false,
+                }
+        })
+        .unwrap();
+    do_action(state, &action).unwrap()
+}
+
+fn black(kind: TileType, flipped: bool) -> Tile {
+    Tile {
+        kind,
+        flipped,
+        color: TileColor::Black,
+    }
+}
+
+#[test]
+fn draw_and_place() {
+    let mut state = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(5, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+        .bag(TileColor::Black, vec![TileType::Footman])
+        .ply(TileColor::Black)
+        .seed(1)
+        .build()
+        .unwrap();
+
+    let delta = do_action(&mut state, &Action::NewFromBag).unwrap();
+    assert_eq!(
+        delta,
+        StateDelta {
+            drawn: Some(black(TileType::Footman, false)),
+            ..Default::default()
+        }
+    );
+
+    let square = Coordinate::new(1, 0);
+    let delta = do_action(&mut state, &Action::PlaceNew(square)).unwrap();
+    assert_eq!(
+        delta,
+        StateDelta {
+            placed: Some((square, black(TileType::Footman, false))),
+            next_ply: Some(TileColor::White),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn move_flips_mover() {
+    let from = Coordinate::new(2, 2);
+    let to = Coordinate::new(2, 3);
+    let mut state = state(&[(from, TileType::Footman, TileColor::Black, false)]);
+
+    let delta = act(&mut state, from, to, |a| matches!(a, Action::Move(_)));
+    assert_eq!(
+        delta,
+        StateDelta {
+            moved: Some(TileMove {
+                from,
+                to,
+                kind: TileType::Footman,
+                flipped: true,
+            }),
+            flipped: vec![to],
+            next_ply: Some(TileColor::White),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn jump_captures() {
+    let from = Coordinate::new(2, 2);
+    let to = Coordinate::new(3, 4);
+    let mut state = state(&[
+        (from, TileType::Knight, TileColor::Black, false),
+        (to, TileType::Footman, TileColor::White, false),
+    ]);
+
+    let delta = act(&mut state, from, to, |a| matches!(a, Action::Jump(_)));
+    assert_eq!(
+        delta.moved,
+        Some(TileMove {
+            from,
+            to,
+            kind: TileType::Knight,
+            flipped: true,
+        })
+    );
+    let captured = delta.captured.unwrap();
+    assert_eq!(captured.tile.kind, TileType::Footman);
+    assert_eq!(captured.tile.color, TileColor::White);
+    assert_eq!(captured.by, TileColor::Black);
+    assert_eq!(captured.square, to);
+    assert_eq!(state.graveyard.last(), Some(&captured));
+    assert_eq!(delta.game_over, None);
+}
+
+#[test]
+fn jump_slide_and_slide() {
+    let lancelot = Coordinate::new(2, 1);
+    let mut state = state(&[(lancelot, TileType::Lancelot, TileColor::Black, false)]);
+
+    let to = Coordinate::new(2, 4);
+    let delta = act(&mut state, lancelot, to, |a| {
+        matches!(a, Action::JumpSlide(_))
+    });
+    assert_eq!(
+        delta.moved,
+        Some(TileMove {
+            from: lancelot,
+            to,
+            kind: TileType::Lancelot,
+            flipped: true,
+        })
+    );
+    assert_eq!(delta.flipped, vec![to]);
+
+    // White passes the turn back with a Duke slide.
+    let delta = act(
+        &mut state,
+        Coordinate::new(5, 5),
+        Coordinate::new(3, 5),
+        |a| matches!(a, Action::Slide(_)),
+    );
+    assert_eq!(delta.moved.unwrap().kind, TileType::Duke);
+    assert_eq!(delta.next_ply, Some(TileColor::Black));
+
+    let from = Coordinate::new(0, 0);
+    let to = Coordinate::new(3, 0);
+    let delta = act(&mut state, from, to, |a| matches!(a, Action::Slide(_)));
+    assert_eq!(
+        delta.moved,
+        Some(TileMove {
+            from,
+            to,
+            kind: TileType::Duke,
+            flipped: true,
+        })
+    );
+    assert_eq!(delta.next_ply, Some(TileColor::White));
+}
+
+#[test]
+fn command_moves_commanded_tile() {
+    let general = Coordinate::new(2, 2);
+    let from = Coordinate::new(1, 2);
+    let to = Coordinate::new(1, 1);
+    let mut state = state(&[
+        (general, TileType::General, TileColor::Black, true),
+        (from, TileType::Footman, TileColor::Black, false),
+    ]);
+
+    let delta = act(
+        &mut state,
+        general,
+        to,
+        |a| matches!(a, Action::Command(data) if data.command_tile_pos == Coordinate::new(1, 2)),
+    );
+    // Commanded tile moves without flipping, commander flips in place.
+    assert_eq!(
+        delta,
+        StateDelta {
+            moved: Some(TileMove {
+                from,
+                to,
+                kind: TileType::Footman,
+                flipped: false,
+            }),
+            flipped: vec![general],
+            next_ply: Some(TileColor::White),
+            ..Default::default()
+        }
+    );
+    assert!(!state.tile_at(general).unwrap().flipped);
+}
+
+#[test]
+fn command_moves_effects_of_commanded_tile() {
+    // Guinevere on b3 defends a3 and c3, on b2 she defends a2 and c2.
+    let general = Coordinate::new(2, 2);
+    let from = Coordinate::new(1, 2);
+    let to = Coordinate::new(1, 1);
+    let mut state = state(&[
+        (general, TileType::General, TileColor::Black, true),
+        (from, TileType::Guinevere, TileColor::Black, false),
+    ]);
+    let before = state.clone();
+
+    let delta = act(
+        &mut state,
+        general,
+        to,
+        |a| matches!(a, Action::Command(data) if data.command_tile_pos == Coordinate::new(1, 2)),
+    );
+    let mut removed = delta.effects_removed.clone();
+    removed.sort_by_key(|(c, _, _)| (c.y, c.x));
+    assert_eq!(
+        removed,
+        vec![
+            (Coordinate::new(0, 2), Effect::Defence, TileColor::Black),
+            (general, Effect::Defence, TileColor::Black),
+        ]
+    );
+    let mut added = delta.effects_added.clone();
+    added.sort_by_key(|(c, _, _)| (c.y, c.x));
+    assert_eq!(
+        added,
+        vec![
+            (Coordinate::new(0, 1), Effect::Defence, TileColor::Black),
+            (Coordinate::new(2, 1), Effect::Defence, TileColor::Black),
+        ]
+    );
+    for cord in [Coordinate::new(0, 2), general] {
+        assert!(state.square(cord).effects.is_empty(), "{}", cord);
+    }
+    for cord in [Coordinate::new(0, 1), Coordinate::new(2, 1)] {
+        assert_eq!(state.square(cord).effects.count(&Effect::Defence), 1);
+    }
+
+    undo_action(&mut state).unwrap();
+    for cord in [Coordinate::new(0, 2), general] {
+        assert_eq!(
+            state.square(cord).effects,
+            before.square(cord).effects,
+            "{}",
+            cord
+        );
+    }
+    for cord in [Coordinate::new(0, 1), Coordinate::new(2, 1)] {
+        assert!(state.square(cord).effects.is_empty(), "{}", cord);
+    }
+}
+
+#[test]
+fn strike_captures_without_moving() {
+    let lancelot = Coordinate::new(2, 1);
+    let target = Coordinate::new(3, 3);
+    let mut state = state(&[
+        (lancelot, TileType::Lancelot, TileColor::Black, false),
+        (target, TileType::Pikeman, TileColor::White, true),
+    ]);
+
+    let delta = act(&mut state, lancelot, target, |a| {
+        matches!(a, Action::Strike(_))
+    });
+    assert_eq!(delta.moved, None);
+    assert_eq!(delta.flipped, vec![lancelot]);
+    let captured = delta.captured.unwrap();
+    assert_eq!(captured.tile.kind, TileType::Pikeman);
+    assert!(captured.tile.flipped);
+    assert_eq!(captured.square, target);
+    assert!(state.tile_at(target).is_none());
+}
+
+#[test]
+fn capturing_duke_ends_game() {
+    let lancelot = Coordinate::new(2, 1);
+    let mut state = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(3, 3),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+        .tile_at(lancelot, TileType::Lancelot, TileColor::Black, false)
+        .ply(TileColor::Black)
+        .seed(1)
+        .build()
+        .unwrap();
+
+    let delta = act(&mut state, lancelot, Coordinate::new(3, 3), |a| {
+        matches!(a, Action::Strike(_))
+    });
+    assert_eq!(delta.captured.unwrap().tile.kind, TileType::Duke);
+    assert_eq!(
+        delta.game_over,
+        Some((Winner::Color(TileColor::Black), WinReason::DukeCaptured))
+    );
+}
+
+#[test]
+fn effects_follow_tile_and_side() {
+    // Guinevere defends left and right on the front side, nothing on the back.
+    let from = Coordinate::new(2, 2);
+    let to = Coordinate::new(3, 3);
+    let mut state = state(&[(from, TileType::Guinevere, TileColor::Black, true)]);
+
+    let delta = act(&mut state, from, to, |a| matches!(a, Action::Move(_)));
+    assert!(delta.effects_removed.is_empty());
+    let mut added = delta.effects_added.clone();
+    added.sort_by_key(|(c, _, _)| (c.y, c.x));
+    assert_eq!(
+        added,
+        vec![
+            (Coordinate::new(2, 3), Effect::Defence, TileColor::Black),
+            (Coordinate::new(4, 3), Effect::Defence, TileColor::Black),
+        ]
+    );
+
+    act(
+        &mut state,
+        Coordinate::new(5, 5),
+        Coordinate::new(4, 5),
+        |a| matches!(a, Action::Slide(_)),
+    );
+
+    // Jumps away and flips, effects go away.
+    let delta = act(&mut state, to, Coordinate::new(3, 5), |a| {
+        matches!(a, Action::Jump(_))
+    });
+    assert!(delta.effects_added.is_empty());
+    let mut removed = delta.effects_removed.clone();
+    removed.sort_by_key(|(c, _, _)| (c.y, c.x));
+    assert_eq!(
+        removed,
+        vec![
+            (Coordinate::new(2, 3), Effect::Defence, TileColor::Black),
+            (Coordinate::new(4, 3), Effect::Defence, TileColor::Black),
+        ]
+    );
+}
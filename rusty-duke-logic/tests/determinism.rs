@@ -0,0 +1,41 @@
+//! Seeded games between AIs are reproducible, draws included.
+
+use rusty_duke_logic::ai::alpha_beta::Agent;
+use rusty_duke_logic::ai::DukeAgent;
+use rusty_duke_logic::logic::record::GameRecord;
+use rusty_duke_logic::logic::{do_action, Action, GameState, TileColor};
+
+/// Actions played per game.
+const MAX_ACTIONS: usize = 40;
+
+/// Game between weakened agents, which choose at random among good actions.
+fn play(seed: u64) -> GameRecord {
+    let mut state = GameState::new_seeded(seed);
+    let mut record = GameRecord::new(Some(seed));
+    let mut agents = [
+        Agent::from_level(TileColor::Black, 1).with_seed(seed),
+        Agent::from_level(TileColor::White, 2).with_seed(seed + 1),
+    ];
+
+    while state.game_over.is_none() && record.len() < MAX_ACTIONS {
+        let action = match agents[state.ply as usize].choose_action(&state) {
+            Some(action) => action,
+            None => break,
+        };
+        do_action(&mut state, &action).unwrap();
+        record.push(&state, action);
+    }
+    record
+}
+
+#[test]
+fn same_seed_same_game() {
+    for seed in [3, 7] {
+        let first = play(seed);
+        assert!(first
+            .actions
+            .iter()
+            .any(|recorded| recorded.action == Action::NewFromBag));
+        assert_eq!(play(seed), first);
+    }
+}
@@ -0,0 +1,96 @@
+//! `Agent::from_level`: levels out of range, seeded weakening, and level 8
+//! against level 2.
+
+use rusty_duke_logic::ai::alpha_beta::{Agent, AgentConfig, MAX_LEVEL, MIN_LEVEL};
+use rusty_duke_logic::ai::random::RandomAgent;
+use rusty_duke_logic::ai::DukeAgent;
+use rusty_duke_logic::logic::variant::Variant;
+use rusty_duke_logic::logic::{do_action, Action, GameState, TileColor, Winner};
+
+/// Agent of level with a fixed seed, comparable with `==`.
+fn seeded(level: u8) -> Agent {
+    Agent::from_level(TileColor::Black, level).with_seed(1)
+}
+
+#[test]
+fn levels_out_of_range_are_clamped() {
+    assert_eq!(seeded(0), seeded(MIN_LEVEL));
+    assert_eq!(seeded(MAX_LEVEL + 1), seeded(MAX_LEVEL));
+    assert_eq!(seeded(u8::MAX), seeded(MAX_LEVEL));
+    assert_eq!(AgentConfig::level(0), AgentConfig::level(MIN_LEVEL));
+    assert_eq!(AgentConfig::level(u8::MAX), AgentConfig::level(MAX_LEVEL));
+}
+
+#[test]
+fn only_low_levels_are_weakened() {
+    let mut depth = Some(0);
+    for level in MIN_LEVEL..=MAX_LEVEL {
+        let agent = Agent::from_level(TileColor::White, level);
+        assert_eq!(agent.color, TileColor::White);
+        assert_eq!(agent.weakening.is_some(), level <= 4, "level {}", level);
+        assert!(agent.depth >= depth, "level {}", level);
+        depth = agent.depth;
+    }
+    assert!(Agent::from_level(TileColor::Black, MAX_LEVEL)
+        .duration
+        .is_some());
+}
+
+/// Actions of agents of level with seed, in positions of a random game.
+fn choices(level: u8, seed: u64) -> Vec<Option<Action>> {
+    let mut state = GameState::new_seeded(5);
+    let mut random = [
+        RandomAgent::new_seeded(TileColor::Black, 5),
+        RandomAgent::new_seeded(TileColor::White, 6),
+    ];
+    let mut choices = Vec::new();
+    while state.game_over.is_none() && state.turn < 24 {
+        let mut agent = Agent::from_level(state.ply, level).with_seed(seed);
+        choices.push(agent.choose_action(&state));
+        let action = random[state.ply as usize].choose_action(&state).unwrap();
+        do_action(&mut state, &action).unwrap();
+    }
+    choices
+}
+
+#[test]
+fn seeded_weakening_is_reproducible() {
+    assert_eq!(choices(1, 3), choices(1, 3));
+    assert_ne!(choices(1, 3), choices(1, 4));
+    // Full strength agents don't use the seed.
+    assert_eq!(choices(5, 3), choices(5, 4));
+}
+
+/// Agent of level, searches cut to depth 3 to keep the games fast.
+fn agent(color: TileColor, level: u8, seed: u64) -> Agent {
+    let config = AgentConfig {
+        depth: AgentConfig::level(level).depth.min(Some(3)),
+        duration: None,
+        ..AgentConfig::level(level)
+    };
+    Agent::from_config(color, &config).with_seed(seed)
+}
+
+#[test]
+fn level_8_beats_level_2() {
+    let games: u64 = 8;
+    let mut wins = 0;
+    for seed in 0..games {
+        let strong = if seed.is_multiple_of(2) {
+            TileColor::Black
+        } else {
+            TileColor::White
+        };
+        let mut state = GameState::new_with_rules(&Variant::Quick.rules(), Some(seed));
+        let mut agents = [TileColor::Black, TileColor::White]
+            .map(|color| agent(color, if color == strong { 8 } else { 2 }, seed));
+        while state.game_over.is_none() {
+            let action = agents[state.ply as usize].choose_action(&state).unwrap();
+            do_action(&mut state, &action).unwrap();
+        }
+        if state.game_over == Some(Winner::Color(strong)) {
+            wins += 1;
+        }
+    }
+    assert!(wins > games / 2, "{} of {}", wins, games);
+}
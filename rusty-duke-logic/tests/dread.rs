@@ -0,0 +1,111 @@
+//! Dread keeps enemy tiles from acting, but not own tiles, the Duke, or
+//! commands moving a tile off the square.
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::{
+    do_action, get_tile_actions, Action, Coordinate, Effect, GameState, TileColor, TileType,
+};
+
+/// Square dreaded by Morgana on c3 and c5, front side up.
+const DREADED: Coordinate = Coordinate { x: 2, y: 3 };
+
+/// Dukes in corners unless given, plus tiles front side up. Player of color
+/// to go.
+fn state(tiles: &[(Coordinate, TileType, TileColor)], color: TileColor) -> GameState {
+    let mut builder = GameStateBuilder::new();
+    for (duke, duke_color) in [
+        (Coordinate::new(0, 0), TileColor::Black),
+        (Coordinate::new(5, 5), TileColor::White),
+    ] {
+        if !tiles
+            .iter()
+            .any(|(_, kind, color)| *kind == TileType::Duke && *color == duke_color)
+        {
+            builder = builder.tile_at(duke, TileType::Duke, duke_color, false);
+        }
+    }
+    for (cord, kind, color) in tiles.iter() {
+        builder = builder.tile_at(*cord, *kind, *color, false);
+    }
+    builder.ply(color).build().unwrap()
+}
+
+/// Black Morgana on c3, dreading c4.
+fn black_morgana() -> (Coordinate, TileType, TileColor) {
+    (Coordinate::new(2, 2), TileType::Morgana, TileColor::Black)
+}
+
+/// White Morgana on c5, dreading c4.
+fn white_morgana() -> (Coordinate, TileType, TileColor) {
+    (Coordinate::new(2, 4), TileType::Morgana, TileColor::White)
+}
+
+#[test]
+fn effects_record_color() {
+    let one = state(&[black_morgana()], TileColor::Black);
+    let effects = one.square(DREADED).effects;
+    assert_eq!(effects.count_of(&Effect::Dread, TileColor::Black), 1);
+    assert_eq!(effects.count_of(&Effect::Dread, TileColor::White), 0);
+
+    let both = state(&[black_morgana(), white_morgana()], TileColor::Black);
+    assert_eq!(
+        both.square(DREADED).effects.to_vec(),
+        [
+            (Effect::Dread, TileColor::Black),
+            (Effect::Dread, TileColor::White)
+        ]
+    );
+}
+
+#[test]
+fn enemy_tile_is_frozen() {
+    let footman = (DREADED, TileType::Footman, TileColor::White);
+    let dreaded = state(&[black_morgana(), footman], TileColor::White);
+    assert!(get_tile_actions(&dreaded, DREADED).is_empty());
+
+    // Without Morgana, the Footman can act.
+    let free = state(&[footman], TileColor::White);
+    assert!(!get_tile_actions(&free, DREADED).is_empty());
+}
+
+#[test]
+fn own_tile_and_duke_can_act() {
+    let footman = (DREADED, TileType::Footman, TileColor::Black);
+    let own = state(&[black_morgana(), footman], TileColor::Black);
+    assert!(!get_tile_actions(&own, DREADED).is_empty());
+
+    let duke = (DREADED, TileType::Duke, TileColor::White);
+    let enemy_duke = state(&[black_morgana(), duke], TileColor::White);
+    assert!(!get_tile_actions(&enemy_duke, DREADED).is_empty());
+
+    // Own Dread doesn't help against the opponent's.
+    let both = state(
+        &[black_morgana(), white_morgana(), footman],
+        TileColor::Black,
+    );
+    assert!(get_tile_actions(&both, DREADED).is_empty());
+}
+
+#[test]
+fn frozen_tile_can_be_commanded() {
+    // Arthur on c3 commands c4, front side up.
+    let arthur = Coordinate::new(2, 2);
+    let mut state = state(
+        &[
+            white_morgana(),
+            (arthur, TileType::Arthur, TileColor::Black),
+            (DREADED, TileType::Footman, TileColor::Black),
+        ],
+        TileColor::Black,
+    );
+    assert!(get_tile_actions(&state, DREADED).is_empty());
+
+    let command = get_tile_actions(&state, arthur)
+        .into_iter()
+        .find(|action| matches!(action, Action::Command(data) if data.command_tile_pos == DREADED))
+        .unwrap();
+    let delta = do_action(&mut state, &command).unwrap();
+    let moved = delta.moved.unwrap();
+    assert_eq!(moved.from, DREADED);
+    assert_eq!(state.tile_at(moved.to).unwrap().kind, TileType::Footman);
+}
@@ -0,0 +1,135 @@
+//! Dread and Defence of the Arthurian tiles: effects follow their tile, Dread
+//! keeps an enemy tile from moving and commanding, Defence keeps a tile from
+//! being captured.
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::{
+    do_action, get_tile_actions, validate_action, Action, ActionResult, Coordinate, Effect,
+    GameState, TileColor, TileType,
+};
+
+/// Dukes in corners, plus tiles front side up. Black to go.
+fn state(tiles: &[(Coordinate, TileType, TileColor)]) -> GameState {
+    let mut builder = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(5, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        );
+    for (cord, kind, color) in tiles.iter() {
+        builder = builder.tile_at(*cord, *kind, *color, false);
+    }
+    builder.ply(TileColor::Black).build().unwrap()
+}
+
+/// Action of tile on from that ends on to.
+fn action_to(state: &GameState, from: Coordinate, to: Coordinate) -> Option<Action> {
+    get_tile_actions(state, from)
+        .into_iter()
+        .find(|action| match action {
+            Action::Move(data)
+            | Action::Jump(data)
+            | Action::JumpSlide(data)
+            | Action::Slide(data)
+            | Action::Strike(data) => data.target_pos == to,
+            _ => false,
+        })
+}
+
+fn dreaded_by(state: &GameState, cord: Coordinate, color: TileColor) -> bool {
+    state.square(cord).effects.count_of(&Effect::Dread, color) > 0
+}
+
+/// White Guinevere on c5 defends b5 and d5. White Footman on d5, Black
+/// Footman on d4 below it.
+fn state_with_guinevere() -> GameState {
+    state(&[
+        (Coordinate::new(2, 4), TileType::Guinevere, TileColor::White),
+        (Coordinate::new(3, 4), TileType::Footman, TileColor::White),
+        (Coordinate::new(3, 3), TileType::Footman, TileColor::Black),
+    ])
+}
+
+#[test]
+fn effects_follow_their_tile() {
+    // Morgana front side dreads the square ahead, back side three squares.
+    let c3 = Coordinate::new(2, 2);
+    let d4 = Coordinate::new(3, 3);
+    let mut state = state(&[(c3, TileType::Morgana, TileColor::Black)]);
+    assert!(dreaded_by(&state, Coordinate::new(2, 3), TileColor::Black));
+    assert!(!dreaded_by(&state, Coordinate::new(3, 4), TileColor::Black));
+
+    let action = action_to(&state, c3, d4).unwrap();
+    do_action(&mut state, &action).unwrap();
+    assert!(!dreaded_by(&state, Coordinate::new(2, 3), TileColor::Black));
+    for x in 2..=4 {
+        assert!(dreaded_by(&state, Coordinate::new(x, 4), TileColor::Black));
+    }
+
+    // Guinevere defends the squares on both sides.
+    let guinevere = state_with_guinevere();
+    assert!(guinevere
+        .square(Coordinate::new(1, 4))
+        .effects
+        .contains(&Effect::Defence));
+    assert!(guinevere
+        .square(Coordinate::new(3, 4))
+        .effects
+        .contains(&Effect::Defence));
+    assert!(guinevere.square(Coordinate::new(2, 3)).effects.is_empty());
+}
+
+#[test]
+fn dread_stops_moves_and_commands() {
+    // White Morgana on c5 dreads c4, where Black Arthur could move and
+    // command the Footman on d4.
+    let arthur = Coordinate::new(2, 3);
+    let footman = Coordinate::new(3, 3);
+    let tiles = [
+        (arthur, TileType::Arthur, TileColor::Black),
+        (footman, TileType::Footman, TileColor::Black),
+    ];
+    let free = state(&tiles);
+    let actions = get_tile_actions(&free, arthur);
+    assert!(actions.iter().any(|a| matches!(a, Action::Move(_))));
+    assert!(actions
+        .iter()
+        .any(|a| matches!(a, Action::Command(data) if data.command_tile_pos == footman)));
+
+    let mut tiles = tiles.to_vec();
+    tiles.push((Coordinate::new(2, 4), TileType::Morgana, TileColor::White));
+    let dreaded = state(&tiles);
+    assert!(dreaded_by(&dreaded, arthur, TileColor::White));
+    assert!(get_tile_actions(&dreaded, arthur).is_empty());
+    for action in actions {
+        assert!(validate_action(&dreaded, &action).is_err(), "{:?}", action);
+    }
+    // Footman next to it is not dreaded.
+    assert!(!get_tile_actions(&dreaded, footman).is_empty());
+}
+
+#[test]
+fn defence_stops_captures() {
+    let black = Coordinate::new(3, 3);
+    let white = Coordinate::new(3, 4);
+    let undefended = state(&[
+        (white, TileType::Footman, TileColor::White),
+        (black, TileType::Footman, TileColor::Black),
+    ]);
+    let capture = action_to(&undefended, black, white).unwrap();
+    assert!(matches!(capture, Action::Move(data) if data.result == ActionResult::Capture));
+
+    let defended = state_with_guinevere();
+    assert!(defended.square(white).effects.contains(&Effect::Defence));
+    assert_eq!(action_to(&defended, black, white), None);
+    assert!(validate_action(&defended, &capture).is_err());
+    // The Footman can still go elsewhere.
+    assert!(!get_tile_actions(&defended, black).is_empty());
+}
@@ -0,0 +1,114 @@
+//! Games against the engine binary over its stdin and stdout.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use rusty_duke_logic::logic::notation::notation_to_action;
+use rusty_duke_logic::logic::record::GameRecord;
+use rusty_duke_logic::logic::{do_action, get_legal_actions, Action, GameState};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+/// Actions of the scripted game if it does not end before, drawing and
+/// placing a tile count as two.
+const ACTIONS: usize = 24;
+
+struct Engine {
+    child: Child,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Engine {
+    fn spawn() -> Engine {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_engine"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Engine { child, stdout }
+    }
+
+    fn send(&mut self, command: &str) {
+        let stdin = self.child.stdin.as_mut().unwrap();
+        writeln!(stdin, "{}", command).unwrap();
+        stdin.flush().unwrap();
+    }
+
+    /// Send command and read the reply.
+    fn ask(&mut self, command: &str) -> String {
+        self.send(command);
+        let mut line = String::new();
+        self.stdout.read_line(&mut line).unwrap();
+        line.trim_end().to_string()
+    }
+
+    fn quit(mut self) {
+        self.send("quit");
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+
+#[test]
+fn scripted_game_gets_legal_answers() {
+    let mut engine = Engine::spawn();
+    assert_eq!(engine.ask("isready"), "readyok");
+
+    // The test draws the tiles, with a seed of its own.
+    let mut state: GameState = GameRecord::new(Some(11)).replay();
+    let mut moves: Vec<String> = Vec::new();
+    for i in 0..ACTIONS {
+        engine.send(&format!("position startpos moves {}", moves.join(" ")));
+        let limits = if i % 2 == 0 { "depth 1" } else { "movetime 20" };
+        let reply = engine.ask(&format!("go {}", limits));
+        if state.game_over.is_some() {
+            assert_eq!(reply, "bestmove none");
+            break;
+        }
+        let notation = reply
+            .strip_prefix("bestmove ")
+            .unwrap_or_else(|| panic!("{}", reply));
+
+        let action = notation_to_action(&state, notation).unwrap();
+        assert!(get_legal_actions(&state).contains(&action), "{}", notation);
+        do_action(&mut state, &action).unwrap();
+        moves.push(match action {
+            Action::NewFromBag => format!("draw {}", state.drawn().last().unwrap().kind),
+            _ => notation.to_string(),
+        });
+    }
+    assert!(moves.iter().any(|action| action.starts_with("draw ")));
+
+    engine.quit();
+}
+
+#[test]
+fn invalid_commands_are_answered() {
+    let mut engine = Engine::spawn();
+
+    assert!(engine.ask("fly").starts_with("error "));
+    assert!(engine.ask("go depth 0").starts_with("error "));
+    // Duke is placed first, and draws name the tile.
+    for moves in ["@b1 Footman", "@c1 Duke @c6 Duke draw"] {
+        let reply = engine.ask(&format!("position startpos moves {}", moves));
+        assert!(reply.starts_with("error "), "{}: {}", moves, reply);
+    }
+
+    // Position is kept after an error.
+    engine.send("position startpos moves @c1 Duke");
+    assert!(engine
+        .ask("position startpos moves Duke c1-c9")
+        .starts_with("error "));
+    assert!(engine.ask("go depth 1").starts_with("bestmove @"));
+
+    // Black Duke is in guard and can't escape.
+    engine.send(concat!(
+        "position startpos moves @c1 Duke @d6 Duke @c2 Footman @d5 Footman @d1 Footman ",
+        "@c6 Footman draw Bowman @b1 Bowman draw Knight @e6 Knight Bowman b1-b2 ",
+        "Knight e6-d4 draw Arbalist @b1 Arbalist Knight d4-d3"
+    ));
+    assert_eq!(engine.ask("go"), "bestmove none");
+
+    engine.send("newgame");
+    assert!(engine.ask("go depth 1").ends_with(" Duke"));
+    engine.quit();
+}
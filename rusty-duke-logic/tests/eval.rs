@@ -0,0 +1,296 @@
+//! Positional terms of the evaluation.
+
+use rusty_duke_logic::ai::alpha_beta::{evaluate, Agent, EvalWeights};
+use rusty_duke_logic::ai::DukeAgent;
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::{do_action, Coordinate, GameState, TileColor, TileType, Winner};
+
+/// Black Duke on (2, 0), White Duke on (3, 5), plus tiles front side up.
+/// Black to go.
+fn state(tiles: &[(Coordinate, TileType, TileColor)]) -> GameState {
+    let mut builder = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(2, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(3, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        );
+    for (cord, kind, color) in tiles.iter() {
+        builder = builder.tile_at(*cord, *kind, *color, false);
+    }
+    builder.ply(TileColor::Black).build().unwrap()
+}
+
+#[test]
+fn centralised_wizard_is_better() {
+    let agent = Agent::new(TileColor::Black, Some(1), None);
+    let wizard = |cord| state(&[(cord, TileType::Wizard, TileColor::Black)]);
+
+    let center = evaluate(&agent, &wizard(Coordinate::new(2, 2)));
+    for corner in [Coordinate::new(0, 5), Coordinate::new(5, 5)] {
+        assert!(center > evaluate(&agent, &wizard(corner)), "{}", corner);
+    }
+
+    // Material only can't tell them apart.
+    let mut material = agent.clone();
+    material.weights = EvalWeights::material_only();
+    assert_eq!(
+        evaluate(&material, &wizard(Coordinate::new(2, 2))),
+        evaluate(&material, &wizard(Coordinate::new(0, 5)))
+    );
+}
+
+#[test]
+fn exposed_duke_is_worse() {
+    let agent = Agent::new(TileColor::Black, Some(1), None);
+    let mut careless = agent.clone();
+    careless.weights.duke_danger = 0;
+    careless.weights.duke_shelter = 0;
+
+    // White Knight bearing on squares next to the Black Duke.
+    let knight = |cord| state(&[(cord, TileType::Knight, TileColor::White)]);
+    let near = knight(Coordinate::new(0, 1));
+    let far = knight(Coordinate::new(5, 5));
+    assert!(evaluate(&agent, &near) < evaluate(&agent, &far));
+    assert!(evaluate(&agent, &near) < evaluate(&careless, &near));
+    assert_eq!(evaluate(&agent, &far), evaluate(&careless, &far));
+
+    // Black Footman shielding the Duke.
+    let footman = |cord| state(&[(cord, TileType::Footman, TileColor::Black)]);
+    let shelter = footman(Coordinate::new(2, 1));
+    let away = footman(Coordinate::new(5, 3));
+    assert!(evaluate(&agent, &shelter) > evaluate(&agent, &away));
+    assert!(evaluate(&agent, &shelter) > evaluate(&careless, &shelter));
+    assert_eq!(evaluate(&agent, &away), evaluate(&careless, &away));
+}
+
+#[test]
+fn duke_stays_home_early() {
+    let agent = Agent::new(TileColor::Black, Some(1), None);
+    let mut careless = agent.clone();
+    careless.weights.duke_home = 0;
+    let duke = |y| {
+        GameStateBuilder::new()
+            .tile_at(
+                Coordinate::new(2, y),
+                TileType::Duke,
+                TileColor::Black,
+                false,
+            )
+            .tile_at(
+                Coordinate::new(3, 5),
+                TileType::Duke,
+                TileColor::White,
+                false,
+            )
+            .build()
+            .unwrap()
+    };
+    let (mut home, mut out) = (duke(0), duke(2));
+    let cost = |agent: &Agent, home: &GameState, out: &GameState| {
+        evaluate(agent, home) - evaluate(agent, out)
+    };
+
+    assert_eq!(
+        cost(&agent, &home, &out),
+        cost(&careless, &home, &out) + 2 * agent.weights.duke_home
+    );
+
+    // Later the Duke may roam.
+    home.turn = 40;
+    out.turn = 40;
+    assert_eq!(cost(&agent, &home, &out), cost(&careless, &home, &out));
+}
+
+/// Material only, plus weight set by f.
+fn only(f: impl FnOnce(&mut EvalWeights)) -> Agent {
+    let mut agent = Agent::new(TileColor::Black, Some(1), None);
+    agent.weights = EvalWeights::material_only();
+    f(&mut agent.weights);
+    agent
+}
+
+#[test]
+fn covered_tiles_are_better() {
+    // Same material and square bonus, Footmen on b3 and b4 cover each other.
+    let footmen = |a, b| {
+        state(&[
+            (a, TileType::Footman, TileColor::Black),
+            (b, TileType::Footman, TileColor::Black),
+        ])
+    };
+    let together = footmen(Coordinate::new(1, 2), Coordinate::new(1, 3));
+    let apart = footmen(Coordinate::new(1, 2), Coordinate::new(4, 3));
+
+    let agent = only(|w| w.coordination = 3);
+    assert_eq!(
+        evaluate(&agent, &together) - evaluate(&agent, &apart),
+        2 * 3
+    );
+    let mut position = only(|w| w.position = 1);
+    assert_eq!(evaluate(&position, &together), evaluate(&position, &apart));
+
+    // Covered White tiles count for White.
+    position.weights.coordination = 3;
+    let white = |a, b| {
+        state(&[
+            (a, TileType::Footman, TileColor::White),
+            (b, TileType::Footman, TileColor::White),
+        ])
+    };
+    let together = white(Coordinate::new(1, 2), Coordinate::new(1, 3));
+    let apart = white(Coordinate::new(1, 2), Coordinate::new(4, 3));
+    assert_eq!(
+        evaluate(&position, &together) - evaluate(&position, &apart),
+        -2 * 3
+    );
+}
+
+#[test]
+fn shelter_counts_half_when_the_enemy_bears_on_it() {
+    // Black Footman on c2 next to the Duke. A White Knight on b4 jumps to c2,
+    // on f5 it is out of reach.
+    let knight = |cord| {
+        state(&[
+            (Coordinate::new(2, 1), TileType::Footman, TileColor::Black),
+            (cord, TileType::Knight, TileColor::White),
+        ])
+    };
+    let attacked = knight(Coordinate::new(1, 3));
+    let safe = knight(Coordinate::new(5, 4));
+
+    let agent = only(|w| w.duke_shelter = 4);
+    let none = only(|_| ());
+    let term = |state: &GameState| evaluate(&agent, state) - evaluate(&none, state);
+    assert_eq!(term(&safe), 4);
+    assert_eq!(term(&attacked), 2);
+
+    // Tiles diagonal to the Duke shelter it too, tiles two squares away don't.
+    let footman = |cord| state(&[(cord, TileType::Footman, TileColor::Black)]);
+    assert_eq!(term(&footman(Coordinate::new(3, 1))), 4);
+    assert_eq!(term(&footman(Coordinate::new(2, 2))), 0);
+}
+
+#[test]
+fn mobility_counts_free_squares() {
+    // Footman on c3 bears on four free squares, on a6 on two.
+    let footman = |cord| state(&[(cord, TileType::Footman, TileColor::Black)]);
+    let agent = only(|w| w.mobility = 3);
+    let none = only(|_| ());
+    let term = |state: &GameState| evaluate(&agent, state) - evaluate(&none, state);
+    assert_eq!(
+        term(&footman(Coordinate::new(2, 2))) - term(&footman(Coordinate::new(0, 5))),
+        2 * 3
+    );
+
+    // Squares of own tiles are cover, not mobility.
+    let pair = |b| {
+        state(&[
+            (Coordinate::new(2, 2), TileType::Footman, TileColor::Black),
+            (b, TileType::Footman, TileColor::Black),
+        ])
+    };
+    assert_eq!(
+        term(&pair(Coordinate::new(4, 4))) - term(&pair(Coordinate::new(2, 3))),
+        2 * 3
+    );
+}
+
+#[test]
+fn duke_danger_counts_each_enemy_tile_once() {
+    // White Knight on c3 jumps to b1 and d1, both next to the Black Duke.
+    // On a2 it moves to b2.
+    let knights = |cords: &[Coordinate]| {
+        let tiles: Vec<(Coordinate, TileType, TileColor)> = cords
+            .iter()
+            .map(|cord| (*cord, TileType::Knight, TileColor::White))
+            .collect();
+        state(&tiles)
+    };
+    let agent = only(|w| w.duke_danger = 3);
+    let none = only(|_| ());
+    let term = |state: &GameState| evaluate(&agent, state) - evaluate(&none, state);
+    let (c3, a2, f5) = (
+        Coordinate::new(2, 2),
+        Coordinate::new(0, 1),
+        Coordinate::new(5, 4),
+    );
+    assert_eq!(term(&knights(&[f5])), 0);
+    assert_eq!(term(&knights(&[c3])), -3);
+    assert_eq!(term(&knights(&[a2])), -3);
+    assert_eq!(term(&knights(&[c3, a2])), -6);
+}
+
+#[test]
+fn footmen_advance_and_others_centralise() {
+    let tile = |kind, color, cord| state(&[(cord, kind, color)]);
+    let agent = only(|w| w.position = 1);
+    let none = only(|_| ());
+    let term = |state: &GameState| evaluate(&agent, state) - evaluate(&none, state);
+    let (black, white) = (TileColor::Black, TileColor::White);
+    let footman = TileType::Footman;
+    let wizard = TileType::Wizard;
+
+    // Column c gets 2, row 3 gets 1 for Footmen and 2 for others, row 5
+    // gets 2 for Footmen and 1 for others.
+    let (c3, c5) = (Coordinate::new(2, 2), Coordinate::new(2, 4));
+    assert_eq!(term(&tile(footman, black, c3)), 3);
+    assert_eq!(term(&tile(footman, black, c5)), 4);
+    assert_eq!(term(&tile(wizard, black, c3)), 4);
+    // Wizard on c5 would bear on the White Duke, b5 is one column out.
+    assert_eq!(term(&tile(wizard, black, Coordinate::new(1, 4))), 2);
+    // White advances down the board, and counts against Black.
+    assert_eq!(term(&tile(footman, white, c3)), -3);
+    assert_eq!(term(&tile(footman, white, c5)), -2);
+    assert_eq!(term(&tile(footman, black, Coordinate::new(0, 1))), 0);
+}
+
+const DEPTH: u8 = 3;
+
+/// Winner of a game between agents with weights of Black and White, at
+/// most 150 turns.
+fn play(weights: [EvalWeights; 2], seed: u64) -> Winner {
+    let mut state = GameState::new_seeded(seed);
+    let mut agents = [TileColor::Black, TileColor::White].map(|color| {
+        let mut agent = Agent::new(color, Some(DEPTH), None);
+        agent.weights = weights[color as usize];
+        agent
+    });
+    while state.game_over.is_none() && state.turn < 150 {
+        let action = agents[state.ply as usize].choose_action(&state).unwrap();
+        do_action(&mut state, &action).unwrap();
+    }
+    state.game_over.unwrap_or(Winner::Draw)
+}
+
+#[test]
+fn default_weights_beat_material_only() {
+    let (default, material) = (EvalWeights::default(), EvalWeights::material_only());
+    let (mut wins, mut losses) = (0, 0);
+    for seed in 0..10 {
+        for (weights, color) in [
+            ([default, material], TileColor::Black),
+            ([material, default], TileColor::White),
+        ] {
+            match play(weights, seed) {
+                Winner::Color(winner) if winner == color => wins += 1,
+                Winner::Color(_) => losses += 1,
+                _ => {}
+            }
+        }
+    }
+    // Won 12 and lost 5 at the time of writing, the rest were drawn at the
+    // turn limit.
+    assert!(
+        wins >= 10 && wins > 2 * losses,
+        "won {}, lost {} of 20",
+        wins,
+        losses
+    );
+}
@@ -0,0 +1,195 @@
+//! Event sequences of actions, as front-ends see them.
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::{
+    apply_action_with_events, get_tile_actions, Action, Coordinate, Effect, GameEvent, GameState,
+    Tile, TileColor, TileType, WinReason, Winner,
+};
+
+/// Black Duke on a1, White Duke on duke, Black to go, plus tiles.
+fn state(duke: Coordinate, tiles: &[(Coordinate, TileType, TileColor, bool)]) -> GameState {
+    let mut builder = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(duke, TileType::Duke, TileColor::White, false);
+    for (cord, kind, color, flipped) in tiles.iter() {
+        builder = builder.tile_at(*cord, *kind, *color, *flipped);
+    }
+    builder.ply(TileColor::Black).seed(1).build().unwrap()
+}
+
+/// Events of the action of tile on from that ends on target, and matches
+/// kind.
+fn act(
+    state: &mut GameState,
+    from: Coordinate,
+    target: Coordinate,
+    kind: fn(&Action) -> bool,
+) -> Vec<GameEvent> {
+    let action = get_tile_actions(state, from)
+        .into_iter()
+        .find(|action| {
+            kind(action)
+                && match action {
+                    Action::Strike(data) => data.target_pos == target,
+                    Action::Command(data) => data.target_pos == target,
+                    _ => false,
+                }
+        })
+        .unwrap();
+    apply_action_with_events(state, &action).unwrap()
+}
+
+fn white(kind: TileType, flipped: bool) -> Tile {
+    Tile {
+        kind,
+        flipped,
+        color: TileColor::White,
+    }
+}
+
+#[test]
+fn command_capture() {
+    // General back side on c3 commands the Footman on b3 onto the Pikeman
+    // on d2.
+    let general = Coordinate::new(2, 2);
+    let footman = Coordinate::new(1, 2);
+    let pikeman = Coordinate::new(3, 1);
+    let mut state = state(
+        Coordinate::new(5, 5),
+        &[
+            (general, TileType::General, TileColor::Black, true),
+            (footman, TileType::Footman, TileColor::Black, false),
+            (pikeman, TileType::Pikeman, TileColor::White, false),
+        ],
+    );
+
+    let events = act(
+        &mut state,
+        general,
+        pikeman,
+        |a| matches!(a, Action::Command(data) if data.command_tile_pos == Coordinate::new(1, 2)),
+    );
+    assert_eq!(
+        events,
+        vec![
+            GameEvent::TileCaptured {
+                at: pikeman,
+                tile: white(TileType::Pikeman, false),
+            },
+            GameEvent::TileMoved {
+                from: footman,
+                to: pikeman,
+            },
+            GameEvent::TileFlipped { at: general },
+        ]
+    );
+}
+
+#[test]
+fn strike() {
+    // Bowman back side strikes two ahead, from c2 onto c4.
+    let bowman = Coordinate::new(2, 1);
+    let pikeman = Coordinate::new(2, 3);
+    let mut state = state(
+        Coordinate::new(5, 5),
+        &[
+            (bowman, TileType::Bowman, TileColor::Black, true),
+            (pikeman, TileType::Pikeman, TileColor::White, true),
+        ],
+    );
+
+    let events = act(&mut state, bowman, pikeman, |a| {
+        matches!(a, Action::Strike(_))
+    });
+    assert_eq!(
+        events,
+        vec![
+            GameEvent::TileCaptured {
+                at: pikeman,
+                tile: white(TileType::Pikeman, true),
+            },
+            GameEvent::TileFlipped { at: bowman },
+        ]
+    );
+}
+
+#[test]
+fn strike_on_duke_ends_game() {
+    let bowman = Coordinate::new(2, 1);
+    let duke = Coordinate::new(2, 3);
+    let mut state = state(duke, &[(bowman, TileType::Bowman, TileColor::Black, true)]);
+
+    let events = act(&mut state, bowman, duke, |a| matches!(a, Action::Strike(_)));
+    assert_eq!(
+        events,
+        vec![
+            GameEvent::TileCaptured {
+                at: duke,
+                tile: white(TileType::Duke, false),
+            },
+            GameEvent::TileFlipped { at: bowman },
+            GameEvent::GameEnded {
+                winner: Winner::Color(TileColor::Black),
+                reason: WinReason::DukeCaptured,
+            },
+        ]
+    );
+}
+
+#[test]
+fn draw_and_deploy() {
+    let mut state = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(5, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+        .bag(TileColor::Black, vec![TileType::Guinevere])
+        .ply(TileColor::Black)
+        .seed(1)
+        .build()
+        .unwrap();
+    let guinevere = Tile {
+        kind: TileType::Guinevere,
+        flipped: false,
+        color: TileColor::Black,
+    };
+
+    let events = apply_action_with_events(&mut state, &Action::NewFromBag).unwrap();
+    assert_eq!(events, vec![GameEvent::TileDrawn { tile: guinevere }]);
+
+    // Guinevere on b1 defends c1 and a1, in the order of her effects.
+    let at = Coordinate::new(1, 0);
+    let events = apply_action_with_events(&mut state, &Action::PlaceNew(at)).unwrap();
+    assert_eq!(
+        events,
+        vec![
+            GameEvent::TileDeployed {
+                at,
+                tile: guinevere,
+            },
+            GameEvent::EffectAdded {
+                at: Coordinate::new(2, 0),
+                effect: Effect::Defence,
+                color: TileColor::Black,
+            },
+            GameEvent::EffectAdded {
+                at: Coordinate::new(0, 0),
+                effect: Effect::Defence,
+                color: TileColor::Black,
+            },
+        ]
+    );
+}
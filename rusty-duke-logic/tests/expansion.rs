@@ -0,0 +1,225 @@
+//! Arthurian Legends tiles: their card offsets and effects, and games that
+//! deal them only when asked to.
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::tiles::builtin;
+use rusty_duke_logic::logic::variant::{Expansion, GameSetup};
+use rusty_duke_logic::logic::{
+    ActionType, AvailableAction, AvailableEffect, Coordinate, Effect, GameState, Offset, TileColor,
+    TileType,
+};
+
+const ARTHURIAN: [TileType; 8] = [
+    TileType::Arthur,
+    TileType::Guinevere,
+    TileType::Lancelot,
+    TileType::Perceval,
+    TileType::Merlin,
+    TileType::Camelot,
+    TileType::Morgana,
+    TileType::Mordred,
+];
+
+/// Offset as kind letter and offset, e.g. "J0,2". Sorted.
+fn cards<T>(entries: &[T], entry: impl Fn(&T) -> (char, Offset)) -> Vec<String> {
+    let mut cards: Vec<String> = entries
+        .iter()
+        .map(|e| {
+            let (kind, offset) = entry(e);
+            format!("{}{},{}", kind, offset.x, offset.y)
+        })
+        .collect();
+    cards.sort();
+    cards
+}
+
+fn letter(kind: &ActionType) -> char {
+    match kind {
+        ActionType::Move => 'M',
+        ActionType::Jump => 'J',
+        ActionType::Slide => 'S',
+        ActionType::JumpSlide => 'L',
+        ActionType::Strike => 'X',
+        ActionType::Command => 'C',
+        kind => panic!("{:?}", kind),
+    }
+}
+
+#[test]
+fn card_offsets() {
+    let expected: [(TileType, [&[&str]; 2]); 8] = [
+        (
+            TileType::Arthur,
+            [
+                &[
+                    "C-1,0", "C-1,1", "C0,1", "C1,0", "C1,1", "M-1,0", "M0,-1", "M0,1", "M1,0",
+                ],
+                &["C-1,-1", "C0,-1", "C1,-1", "M0,-1", "M0,1", "S-1,1", "S1,1"],
+            ],
+        ),
+        (
+            TileType::Guinevere,
+            [
+                &["J0,2", "S-1,0", "S1,0"],
+                &["J0,-2", "M-1,-1", "M-1,1", "M1,-1", "M1,1"],
+            ],
+        ),
+        (
+            TileType::Lancelot,
+            [
+                &["L0,2", "M0,1", "X-1,2", "X1,2"],
+                &["J-2,0", "J2,0", "S-1,1", "S1,1"],
+            ],
+        ),
+        (
+            TileType::Perceval,
+            [&["J0,2", "M-1,1", "M1,1"], &["M-1,-1", "M1,-1", "S0,1"]],
+        ),
+        (
+            TileType::Merlin,
+            [
+                &["J-2,-2", "J-2,2", "J2,-2", "J2,2", "M0,1"],
+                &["J-2,0", "J0,-2", "J0,2", "J2,0"],
+            ],
+        ),
+        (
+            TileType::Camelot,
+            [
+                &["C-1,0", "C0,-1", "C0,1", "C1,0"],
+                &["M-1,0", "M0,-1", "M0,1", "M1,0"],
+            ],
+        ),
+        (
+            TileType::Morgana,
+            [&["J0,-2", "M-1,1", "M1,1"], &["M-1,0", "M1,0", "X0,2"]],
+        ),
+        (
+            TileType::Mordred,
+            [&["M-1,0", "M1,0", "X0,2"], &["S0,1", "X-1,1", "X1,1"]],
+        ),
+    ];
+    let definitions = builtin();
+    for (kind, [front, back]) in expected {
+        let actions = &definitions.actions[&kind];
+        let card = |side: &[AvailableAction]| {
+            cards(side, |a: &AvailableAction| (letter(&a.kind), a.offset()))
+        };
+        assert_eq!(card(&actions.front), front, "{} front", kind);
+        assert_eq!(card(&actions.back), back, "{} back", kind);
+    }
+}
+
+#[test]
+fn card_effects() {
+    let expected: [(TileType, [&[&str]; 2]); 4] = [
+        (TileType::Guinevere, [&["D-1,0", "D1,0"], &[]]),
+        (TileType::Merlin, [&[], &["R-1,1", "R0,1", "R1,1"]]),
+        (
+            TileType::Camelot,
+            [&["D-1,0", "D-1,1", "D0,1", "D1,0", "D1,1"], &["D0,1"]],
+        ),
+        (TileType::Morgana, [&["R0,1"], &["R-1,1", "R0,1", "R1,1"]]),
+    ];
+    let definitions = builtin();
+    let mut with_effects: Vec<TileType> = definitions.effects.keys().copied().collect();
+    with_effects.sort_by_key(|kind| kind.to_id());
+    assert_eq!(with_effects, expected.map(|(kind, _)| kind));
+
+    for (kind, [front, back]) in expected {
+        let effects = &definitions.effects[&kind];
+        let card = |side: &[AvailableEffect]| {
+            cards(side, |e: &AvailableEffect| {
+                let letter = match e.kind {
+                    Effect::Dread => 'R',
+                    Effect::Defence => 'D',
+                };
+                (letter, e.offset())
+            })
+        };
+        assert_eq!(card(&effects.front), front, "{} front", kind);
+        assert_eq!(card(&effects.back), back, "{} back", kind);
+    }
+}
+
+/// Squares with effect of color, tile on c3.
+fn effect_squares(kind: TileType, color: TileColor, flipped: bool, effect: Effect) -> Vec<String> {
+    let state = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(5, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(0, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+        .tile_at(Coordinate::new(2, 2), kind, color, flipped)
+        .build()
+        .unwrap();
+    let mut squares: Vec<String> = state
+        .size()
+        .squares()
+        .filter(|cord| state.square(*cord).effects.count_of(&effect, color) > 0)
+        .map(|cord| cord.to_string())
+        .collect();
+    squares.sort();
+    squares
+}
+
+#[test]
+fn effects_are_seen_from_the_owner() {
+    let black = TileColor::Black;
+    let white = TileColor::White;
+    assert_eq!(
+        effect_squares(TileType::Merlin, black, true, Effect::Dread),
+        ["b4", "c4", "d4"]
+    );
+    assert_eq!(
+        effect_squares(TileType::Merlin, white, true, Effect::Dread),
+        ["b2", "c2", "d2"]
+    );
+    assert!(effect_squares(TileType::Merlin, black, false, Effect::Dread).is_empty());
+    assert_eq!(
+        effect_squares(TileType::Morgana, white, false, Effect::Dread),
+        ["c2"]
+    );
+    assert_eq!(
+        effect_squares(TileType::Camelot, white, false, Effect::Defence),
+        ["b2", "b3", "c2", "d2", "d3"]
+    );
+}
+
+#[test]
+fn expansion_tiles_only_when_asked() {
+    let has = |state: &GameState, kind: TileType| {
+        state
+            .bags
+            .iter()
+            .map(|bag| bag.iter().filter(|tile| tile.kind == kind).count())
+            .collect::<Vec<usize>>()
+    };
+
+    let base = GameState::new();
+    let default = GameState::with_setup(&GameSetup::default());
+    let setup = GameSetup {
+        expansions: vec![Expansion::ArthurianLegends],
+        ..GameSetup::default()
+    };
+    let expansion = GameState::with_setup(&setup);
+    for kind in ARTHURIAN {
+        assert_eq!(has(&base, kind), [0, 0], "{}", kind);
+        assert_eq!(has(&default, kind), [0, 0], "{}", kind);
+        assert_eq!(has(&expansion, kind), [1, 1], "{}", kind);
+        assert_eq!(
+            kind.to_id(),
+            16 + ARTHURIAN.iter().position(|k| *k == kind).unwrap() as u8
+        );
+    }
+    assert_eq!(Expansion::ArthurianLegends.tiles(), ARTHURIAN);
+    for (bag, base_bag) in expansion.bags.iter().zip(base.bags.iter()) {
+        assert_eq!(bag.len(), base_bag.len() + ARTHURIAN.len());
+    }
+}
@@ -0,0 +1,77 @@
+//! `FastBoard` against `GameState`, over random games.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use proptest::prelude::*;
+use rusty_duke_logic::logic::fast::FastBoard;
+use rusty_duke_logic::logic::{do_action, get_legal_actions, Action, GameState, Tile, TileColor};
+
+/// Seeded game, with the i-th legal action chosen by choices, modulo the
+/// number of legal actions. Calls f with the state before each action.
+fn play<F: FnMut(&GameState, &Action)>(seed: u64, choices: &[usize], mut f: F) {
+    let mut state = GameState::new_seeded(seed);
+    for choice in choices {
+        if state.game_over.is_some() {
+            break;
+        }
+        let actions = get_legal_actions(&state);
+        let action = actions[choice % actions.len()];
+        f(&state, &action);
+        do_action(&mut state, &action).unwrap();
+    }
+}
+
+proptest! {
+    // Every case plays a game, fewer cases keep the test fast.
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn fast_board_agrees_with_game_state(
+        seed: u64,
+        choices in prop::collection::vec(any::<usize>(), 1..80),
+    ) {
+        play(seed, &choices, |state, action| {
+            let board = FastBoard::from_state(state);
+            let mut written = state.clone();
+            board.write_to(&mut written);
+            assert_eq!(FastBoard::from_state(&written), board);
+
+            for cord in state.size().squares() {
+                assert_eq!(board.tile(cord), state.square(cord).tile);
+                assert_eq!(board.effects(cord), state.square(cord).effects);
+            }
+            for color in [TileColor::Black, TileColor::White] {
+                let drawn: Vec<Tile> = board.drawn(color).iter().map(|t| t.unwrap()).collect();
+                assert_eq!(&drawn, state.drawn_of(color));
+                assert_eq!(board.duke_pos(color), state.duke_pos(color));
+            }
+            assert_eq!(board.ply, state.ply, "{:?}", action);
+        });
+    }
+
+    #[test]
+    fn mirrored_placements_agree_with_game_state(
+        seed: u64,
+        choices in prop::collection::vec(any::<usize>(), 1..80),
+    ) {
+        play(seed, &choices, |state, _| {
+            let placed: Vec<GameState> = get_legal_actions(state)
+                .iter()
+                .filter(|action| matches!(action, Action::PlaceNew(_)))
+                .map(|action| {
+                    let mut placed = state.clone();
+                    do_action(&mut placed, action).unwrap();
+                    placed
+                })
+                .collect();
+            for a in placed.iter() {
+                for b in placed.iter() {
+                    assert_eq!(
+                        FastBoard::from_state(a).is_mirror_of(&FastBoard::from_state(b)),
+                        a.is_mirror_of(b)
+                    );
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,179 @@
+//! Every action flips exactly one tile, the acting one, and effects of the
+//! flipped tile are those of its new side.
+
+use rand::Rng;
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::rng::GameRng;
+use rusty_duke_logic::logic::variant::Expansion;
+use rusty_duke_logic::logic::{
+    do_action, get_legal_actions, get_tile_actions, Action, Coordinate, Effect, GameState,
+    TileColor, TileType,
+};
+
+/// Acting tile of action, and where it is after the action.
+fn acting(action: &Action) -> Option<(Coordinate, Coordinate)> {
+    match action {
+        Action::Move(data) | Action::Jump(data) | Action::Slide(data) | Action::JumpSlide(data) => {
+            Some((data.tile_pos, data.target_pos))
+        }
+        Action::Command(data) => Some((data.tile_pos, data.tile_pos)),
+        Action::Strike(data) => Some((data.tile_pos, data.tile_pos)),
+        Action::NewFromBag | Action::PlaceNew(_) => None,
+    }
+}
+
+/// Square of each tile after action, for tiles that stay on the board.
+fn after(action: &Action, cord: Coordinate) -> Option<Coordinate> {
+    match action {
+        Action::Move(data) | Action::Jump(data) | Action::Slide(data) | Action::JumpSlide(data) => {
+            if cord == data.tile_pos {
+                Some(data.target_pos)
+            } else if cord == data.target_pos {
+                None
+            } else {
+                Some(cord)
+            }
+        }
+        Action::Command(data) => {
+            if cord == data.command_tile_pos {
+                Some(data.target_pos)
+            } else if cord == data.target_pos {
+                None
+            } else {
+                Some(cord)
+            }
+        }
+        Action::Strike(data) if cord == data.target_pos => None,
+        _ => Some(cord),
+    }
+}
+
+/// Same position built from scratch, so effects are calculated from the
+/// tiles only.
+fn rebuilt(state: &GameState) -> GameState {
+    let mut builder = GameStateBuilder::new().size(state.size()).ply(state.ply);
+    for cord in state.size().squares() {
+        if let Some(tile) = state.square(cord).tile {
+            builder = builder.tile_at(cord, tile.kind, tile.color, tile.flipped);
+        }
+    }
+    for color in [TileColor::Black, TileColor::White] {
+        for tile in state.drawn_tiles[color as usize].iter() {
+            builder = builder.drawn(color, tile.kind);
+        }
+    }
+    builder.build().unwrap()
+}
+
+#[test]
+fn only_the_acting_tile_flips() {
+    for seed in 0..20 {
+        let mut state = GameState::new_with_expansion(Expansion::ArthurianLegends, Some(seed));
+        let mut rng = GameRng::new(seed);
+        while state.game_over.is_none() && state.turn < 150 {
+            let actions = get_legal_actions(&state);
+            // Drawn tile without a safe square, see `get_actions`.
+            if actions.is_empty() {
+                break;
+            }
+            let action = actions[rng.gen_range(0..actions.len())];
+            let before = state.clone();
+            let delta = do_action(&mut state, &action).unwrap();
+
+            let acting = acting(&action);
+            assert_eq!(
+                delta.flipped,
+                acting.map(|(_, to)| vec![to]).unwrap_or_default(),
+                "{:?}",
+                action
+            );
+            for cord in before.size().squares() {
+                let tile = match before.square(cord).tile {
+                    Some(tile) => tile,
+                    None => continue,
+                };
+                let to = match after(&action, cord) {
+                    Some(to) => to,
+                    None => continue,
+                };
+                let moved = state.square(to).tile.unwrap();
+                assert_eq!(moved.kind, tile.kind, "{:?}", action);
+                let flips = acting.map(|(from, _)| from) == Some(cord);
+                assert_eq!(
+                    moved.flipped,
+                    tile.flipped != flips,
+                    "{:?} {}",
+                    action,
+                    cord
+                );
+            }
+
+            if state.game_over.is_none() {
+                let rebuilt = rebuilt(&state);
+                for cord in state.size().squares() {
+                    assert_eq!(
+                        state.square(cord).effects,
+                        rebuilt.square(cord).effects,
+                        "{:?} {}",
+                        action,
+                        cord
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn commander_gets_effects_of_its_new_side() {
+    // Camelot on c3 defends b3, b4, c4, d4 and d3 on its front side, only
+    // c4 on its back side. It commands the Footman on b3 to c2.
+    let camelot = Coordinate::new(2, 2);
+    let footman = Coordinate::new(1, 2);
+    let target = Coordinate::new(2, 1);
+    let mut state = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(5, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+        .tile_at(camelot, TileType::Camelot, TileColor::Black, false)
+        .tile_at(footman, TileType::Footman, TileColor::Black, false)
+        .ply(TileColor::Black)
+        .build()
+        .unwrap();
+    let action = get_tile_actions(&state, camelot)
+        .into_iter()
+        .find(|action| {
+            matches!(action, Action::Command(data)
+                if data.command_tile_pos == footman && data.target_pos == target)
+        })
+        .unwrap();
+
+    let delta = do_action(&mut state, &action).unwrap();
+    assert_eq!(delta.flipped, [camelot]);
+    assert!(state.square(camelot).tile.unwrap().flipped);
+    assert!(!state.square(target).tile.unwrap().flipped);
+    for x in 0..6 {
+        for y in 0..6 {
+            let cord = Coordinate::new(x, y);
+            let defended = cord == Coordinate::new(2, 3);
+            assert_eq!(
+                state
+                    .square(cord)
+                    .effects
+                    .count_of(&Effect::Defence, TileColor::Black),
+                defended as u8,
+                "{}",
+                cord
+            );
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Movement and effect grids for UIs: offsets of every tile side, turned
+//! for White.
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::tiles::builtin;
+use rusty_duke_logic::logic::{
+    get_tile_actions, tile_effect_grid, tile_movement_grid, Action, ActionType, Coordinate,
+    IntoEnumIterator, Offset, TileColor, TileType,
+};
+
+fn negated(offset: &Offset) -> Offset {
+    Offset {
+        x: -offset.x,
+        y: -offset.y,
+    }
+}
+
+#[test]
+fn duke_front_slides_sideways() {
+    for color in [TileColor::Black, TileColor::White] {
+        let mut grid = tile_movement_grid(TileType::Duke, false, color);
+        grid.sort_by_key(|(offset, _)| offset.x);
+        assert_eq!(
+            grid,
+            [
+                (Offset { x: -1, y: 0 }, ActionType::Slide),
+                (Offset { x: 1, y: 0 }, ActionType::Slide),
+            ]
+        );
+    }
+}
+
+#[test]
+fn black_grids_are_the_definitions() {
+    let definitions = builtin();
+    for kind in TileType::iter() {
+        let actions = &definitions.actions[&kind];
+        for (flipped, side) in [(false, &actions.front), (true, &actions.back)] {
+            let defined: Vec<(Offset, ActionType)> =
+                side.iter().map(|a| (a.offset(), a.kind.clone())).collect();
+            assert_eq!(
+                tile_movement_grid(kind, flipped, TileColor::Black),
+                defined,
+                "{}",
+                kind
+            );
+        }
+    }
+}
+
+#[test]
+fn white_grids_mirror_black() {
+    for kind in TileType::iter() {
+        for flipped in [false, true] {
+            let black = tile_movement_grid(kind, flipped, TileColor::Black);
+            let white = tile_movement_grid(kind, flipped, TileColor::White);
+            let mirrored: Vec<(Offset, ActionType)> = black
+                .iter()
+                .map(|(offset, kind)| (negated(offset), kind.clone()))
+                .collect();
+            assert_eq!(white, mirrored, "{} flipped {}", kind, flipped);
+
+            let black = tile_effect_grid(kind, flipped, TileColor::Black);
+            let white = tile_effect_grid(kind, flipped, TileColor::White);
+            assert_eq!(white.len(), black.len());
+            for ((white, white_effect), (black, black_effect)) in white.iter().zip(black.iter()) {
+                assert_eq!(*white, negated(black), "{} flipped {}", kind, flipped);
+                assert_eq!(white_effect, black_effect);
+            }
+        }
+    }
+}
+
+#[test]
+fn grids_match_moves_on_the_board() {
+    // Pikeman front moves diagonally forward only.
+    let tile_pos = Coordinate::new(2, 2);
+    for color in [TileColor::Black, TileColor::White] {
+        let state = GameStateBuilder::new()
+            .tile_at(
+                Coordinate::new(2, 0),
+                TileType::Duke,
+                TileColor::Black,
+                false,
+            )
+            .tile_at(
+                Coordinate::new(2, 5),
+                TileType::Duke,
+                TileColor::White,
+                false,
+            )
+            .tile_at(tile_pos, TileType::Pikeman, color, false)
+            .ply(color)
+            .build()
+            .unwrap();
+
+        let mut expected: Vec<Coordinate> = tile_movement_grid(TileType::Pikeman, false, color)
+            .iter()
+            .map(|(offset, _)| tile_pos.offset(offset, state.size()).unwrap())
+            .collect();
+        let mut targets: Vec<Coordinate> = get_tile_actions(&state, tile_pos)
+            .into_iter()
+            .map(|action| match action {
+                Action::Move(data) => data.target_pos,
+                action => panic!("{:?}", action),
+            })
+            .collect();
+        expected.sort_by_key(|c| (c.y, c.x));
+        targets.sort_by_key(|c| (c.y, c.x));
+        assert_eq!(targets, expected, "{:?}", color);
+    }
+}
@@ -0,0 +1,114 @@
+//! Position hashes through the action tree, like perft.
+
+use rusty_duke_logic::logic::variant::Expansion;
+use rusty_duke_logic::logic::{
+    do_unsafe_action, do_unsafe_action_copy, get_actions, get_legal_actions, undo_action,
+    GameState, TileColor,
+};
+use std::collections::HashMap;
+
+/// Everything the hash covers: tiles on board, drawn tiles, bags in any
+/// order and ply.
+fn position_key(state: &GameState) -> String {
+    let mut bags = state.bags.clone();
+    for bag in bags.iter_mut() {
+        bag.sort_by_key(|tile| tile.kind.to_id());
+    }
+    let tiles: Vec<_> = state.iter_tiles(None).collect();
+    format!(
+        "{:?} {:?} {:?} {:?}",
+        tiles, state.drawn_tiles, bags, state.ply
+    )
+}
+
+/// Walk the action tree depth actions deep. Every action is done in place
+/// and on a copy, and undone. Collects the key and visits of every position
+/// by hash.
+fn walk(state: &mut GameState, depth: u8, seen: &mut HashMap<u64, (String, u32)>) {
+    let hash = state.hash();
+    assert_eq!(hash, state.compute_hash());
+    let key = position_key(state);
+    let entry = seen.entry(hash).or_insert_with(|| (key.clone(), 0));
+    assert_eq!(entry.0, key, "hash collision");
+    entry.1 += 1;
+    if depth == 0 {
+        return;
+    }
+
+    for action in get_actions(state) {
+        let copy = do_unsafe_action_copy(state, &action);
+        do_unsafe_action(state, &action);
+        assert_eq!(state.hash(), copy.hash(), "{:?}", action);
+        walk(state, depth - 1, seen);
+        undo_action(state).unwrap();
+        assert_eq!(state.hash(), hash, "{:?} undone", action);
+    }
+}
+
+#[test]
+fn start_position_tree() {
+    let mut state = GameState::new_seeded(1);
+    let mut seen = HashMap::new();
+    walk(&mut state, 7, &mut seen);
+
+    // Same positions are reached by other orders of actions.
+    assert!(seen.values().any(|(_, visits)| *visits > 1));
+}
+
+#[test]
+fn midgame_tree() {
+    let fen = "2FDPF'/3EF'1/6/2f'3/b5/d'1w'f2 b fpppkelyscagmnr/YPRPKBLWSCAGMN -/- - 16";
+    let mut state = GameState::from_fen(fen).unwrap();
+    let mut seen = HashMap::new();
+    walk(&mut state, 3, &mut seen);
+    assert!(seen.len() > 1000);
+    assert!(seen.values().any(|(_, visits)| *visits > 1));
+
+    // Ply is part of the hash.
+    let mut white = state.clone();
+    white.ply = TileColor::White;
+    white.rehash();
+    assert_ne!(white.hash(), state.hash());
+}
+
+/// Count positions depth actions deep, checking the stored hash against the
+/// recomputed one in every position, after actions and after undo.
+fn perft(state: &mut GameState, depth: u8) -> u64 {
+    assert_eq!(state.hash(), state.compute_hash());
+    if depth == 0 || state.game_over.is_some() {
+        return 1;
+    }
+
+    let mut nodes = 0;
+    for action in get_actions(state) {
+        do_unsafe_action(state, &action);
+        nodes += perft(state, depth - 1);
+        undo_action(state).unwrap();
+        assert_eq!(state.hash(), state.compute_hash(), "{:?} undone", action);
+    }
+    nodes
+}
+
+#[test]
+fn stored_hash_matches_recomputed() {
+    // Arthurian tiles command and strike, every fifth legal action is taken
+    // to get to a middle game.
+    for seed in 0..4 {
+        let mut state = GameState::new_with_expansion(Expansion::ArthurianLegends, Some(seed));
+        for i in 0..30 {
+            let legal = get_legal_actions(&state);
+            if state.game_over.is_some() || legal.is_empty() {
+                break;
+            }
+            do_unsafe_action(&mut state, &legal[(i * 5) % legal.len()]);
+            assert_eq!(state.hash(), state.compute_hash());
+        }
+        assert!(perft(&mut state, 3) > 1);
+    }
+
+    // Rehash after direct changes.
+    let mut state = GameState::new_seeded(2);
+    state.bags[TileColor::Black as usize].pop();
+    state.rehash();
+    assert_eq!(state.hash(), state.compute_hash());
+}
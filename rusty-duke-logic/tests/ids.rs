@@ -0,0 +1,113 @@
+//! Stable ids of tile types, action types and effects, and archives written
+//! with another tile id table.
+
+use rusty_duke_logic::logic::record::binary::{ArchiveError, BinaryReader, BinaryWriter};
+use rusty_duke_logic::logic::record::GameRecord;
+use rusty_duke_logic::logic::{
+    do_action, get_legal_actions, ActionType, Effect, GameState, IntoEnumIterator, TileType,
+    TILE_ID_TABLE_VERSION,
+};
+
+/// Every id ever given out. Ids are never reused or renumbered, so entries
+/// are only ever added here.
+const TILE_IDS: [(TileType, u8); 24] = [
+    (TileType::Duke, 0),
+    (TileType::Footman, 1),
+    (TileType::Pikeman, 2),
+    (TileType::Knight, 3),
+    (TileType::Bowman, 4),
+    (TileType::LightHorse, 5),
+    (TileType::Wizard, 6),
+    (TileType::Seer, 7),
+    (TileType::Champion, 8),
+    (TileType::Arbalist, 9),
+    (TileType::General, 10),
+    (TileType::Marshall, 11),
+    (TileType::Countess, 12),
+    (TileType::Ranger, 13),
+    (TileType::Sage, 14),
+    (TileType::RoyalAssassin, 15),
+    (TileType::Arthur, 16),
+    (TileType::Guinevere, 17),
+    (TileType::Lancelot, 18),
+    (TileType::Perceval, 19),
+    (TileType::Merlin, 20),
+    (TileType::Camelot, 21),
+    (TileType::Morgana, 22),
+    (TileType::Mordred, 23),
+];
+
+#[test]
+fn tile_ids_are_pinned() {
+    assert_eq!(TileType::iter().count(), TILE_IDS.len());
+    for (kind, id) in TILE_IDS {
+        assert_eq!(kind.to_id(), id, "{}", kind);
+        assert_eq!(TileType::from_id(id), Some(kind));
+    }
+    for id in TILE_IDS.len() as u8..=u8::MAX {
+        assert_eq!(TileType::from_id(id), None, "{}", id);
+    }
+    // Arthurian tiles got ids 16 to 23, the table was bumped for them.
+    assert_eq!(TILE_ID_TABLE_VERSION, 2);
+}
+
+#[test]
+fn action_and_effect_ids_are_pinned() {
+    let actions = [
+        (ActionType::NewFromBag, 0),
+        (ActionType::PlaceNew, 1),
+        (ActionType::Move, 2),
+        (ActionType::Jump, 3),
+        (ActionType::JumpSlide, 4),
+        (ActionType::Slide, 5),
+        (ActionType::Command, 6),
+        (ActionType::Strike, 7),
+    ];
+    for (kind, id) in actions {
+        assert_eq!(kind.clone() as u8, id, "{:?}", kind);
+    }
+    assert_eq!(Effect::Dread as u8, 0);
+    assert_eq!(Effect::Defence as u8, 1);
+}
+
+/// Archive with one game. Drawn tiles are stored by tile id.
+fn archive() -> (Vec<u8>, GameRecord) {
+    let mut state = GameState::new_seeded(3);
+    let mut record = GameRecord::new(Some(3));
+    for i in 0..60 {
+        let actions = get_legal_actions(&state);
+        if state.game_over.is_some() || actions.is_empty() {
+            break;
+        }
+        let action = actions[(i * 3) % actions.len()];
+        do_action(&mut state, &action).unwrap();
+        record.push(&state, action);
+    }
+
+    let mut writer = BinaryWriter::new(Vec::new()).unwrap();
+    writer.write_game(&record).unwrap();
+    (writer.finish().unwrap(), record)
+}
+
+#[test]
+fn archive_with_same_tile_ids_is_read() {
+    let (bytes, record) = archive();
+    let mut reader = BinaryReader::new(bytes.as_slice()).unwrap();
+    assert_eq!(reader.read_game().unwrap(), Some(record));
+}
+
+#[test]
+fn archive_with_other_tile_ids_is_rejected() {
+    // Header is the magic, then the format version and the tile id table
+    // version, both one byte varints.
+    let (mut bytes, _) = archive();
+    assert_eq!(bytes[5] as u32, TILE_ID_TABLE_VERSION);
+    for version in [TILE_ID_TABLE_VERSION - 1, TILE_ID_TABLE_VERSION + 1] {
+        bytes[5] = version as u8;
+        match BinaryReader::new(bytes.as_slice()) {
+            Err(ArchiveError::UnsupportedTileIds(v)) => assert_eq!(v, version),
+            Err(error) => panic!("{}", error),
+            Ok(_) => panic!("Archive with tile ids {} was read.", version),
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Positions as JSON for third-party tools: round trips, documents of schema
+//! version 1, and errors of broken documents.
+
+#![cfg(feature = "json")]
+
+use rusty_duke_logic::logic::json::{from_json_position, to_json_position, PositionError};
+use rusty_duke_logic::logic::variant::Expansion;
+use rusty_duke_logic::logic::{
+    do_action, get_actions, get_legal_actions, BoardSize, Coordinate, GameState, TileColor,
+    TileType, WinReason, Winner,
+};
+
+/// Positions of seeded games, standard and with the Arthurian tiles, every
+/// few actions until the game ends.
+fn positions() -> Vec<GameState> {
+    let mut positions = Vec::new();
+    for seed in 0..6 {
+        let mut state = match seed % 2 {
+            0 => GameState::new_seeded(seed),
+            _ => GameState::new_with_expansion(Expansion::ArthurianLegends, Some(seed)),
+        };
+        for i in 0..200 {
+            if i % 7 == 0 {
+                positions.push(state.clone());
+            }
+            let actions = get_legal_actions(&state);
+            if state.game_over.is_some() || actions.is_empty() {
+                break;
+            }
+            do_action(
+                &mut state,
+                &actions[(i * 11 + seed as usize) % actions.len()],
+            )
+            .unwrap();
+        }
+        positions.push(state);
+    }
+    positions
+}
+
+fn sorted_bags(state: &GameState) -> Vec<Vec<TileType>> {
+    state
+        .bags
+        .iter()
+        .map(|bag| {
+            let mut kinds: Vec<TileType> = bag.iter().map(|tile| tile.kind).collect();
+            kinds.sort_by_key(|kind| kind.to_id());
+            kinds
+        })
+        .collect()
+}
+
+#[test]
+fn positions_round_trip() {
+    for state in positions() {
+        let json = to_json_position(&state);
+        let loaded = from_json_position(&json).unwrap();
+
+        // Bags are stored as counts, so their order is not kept.
+        assert_eq!(sorted_bags(&loaded), sorted_bags(&state));
+        assert_eq!(loaded.drawn_tiles, state.drawn_tiles);
+        assert_eq!(loaded.ply, state.ply);
+        assert_eq!(loaded.turn, state.turn);
+        assert_eq!(loaded.hash(), state.hash());
+        assert_eq!(loaded.game_over, state.game_over);
+        assert_eq!(loaded.win_reason, state.win_reason);
+        assert_eq!(loaded.graveyard_tiles(), state.graveyard_tiles());
+        assert_eq!(loaded.own_duke_pos(), state.own_duke_pos());
+        assert_eq!(loaded.opponent_duke_pos(), state.opponent_duke_pos());
+        for cord in state.size().squares() {
+            assert_eq!(loaded.square(cord).tile, state.square(cord).tile);
+            assert_eq!(loaded.square(cord).effects, state.square(cord).effects);
+        }
+        if state.game_over.is_none() {
+            assert_eq!(get_actions(&loaded), get_actions(&state));
+        }
+        assert_eq!(to_json_position(&loaded), json);
+    }
+}
+
+#[test]
+fn version_1_document_loads() {
+    // Written before win_reason, turn and size were added, with a field of a later
+    // version that is ignored.
+    let json = r#"{
+        "version": 1,
+        "ply": "White",
+        "game_over": null,
+        "board": [
+            { "square": { "x": 2, "y": 0 }, "tile": { "kind": "Duke", "color": "Black", "flipped": false } },
+            { "square": { "x": 3, "y": 5 }, "tile": { "kind": "Duke", "color": "White", "flipped": true } },
+            { "square": { "x": 2, "y": 1 }, "tile": { "kind": "Guinevere", "color": "Black", "flipped": false } }
+        ],
+        "bags": {
+            "black": [ { "kind": "Pikeman", "count": 3 } ],
+            "white": [ { "kind": "Knight", "count": 1 } ]
+        },
+        "drawn": { "black": [], "white": [ { "kind": "Footman", "flipped": false } ] },
+        "graveyard": [ { "kind": "Seer", "color": "White", "flipped": true } ],
+        "clocks": { "black": 60000, "white": 60000 }
+    }"#;
+    let state = from_json_position(json).unwrap();
+
+    assert_eq!(state.size(), BoardSize::STANDARD);
+    assert_eq!(state.ply, TileColor::White);
+    assert_eq!(state.win_reason, None);
+    assert_eq!(state.turn, 0);
+    assert_eq!(*state.own_duke_pos(), Some(Coordinate::new(3, 5)));
+    assert_eq!(*state.opponent_duke_pos(), Some(Coordinate::new(2, 0)));
+    assert_eq!(state.bags[TileColor::Black as usize].len(), 3);
+    assert_eq!(state.drawn()[0].kind, TileType::Footman);
+    assert_eq!(state.graveyard_tiles()[0].kind, TileType::Seer);
+    // Effects are calculated from the tiles, Guinevere defends b2 and d2.
+    for x in 0..6 {
+        let defended = x == 1 || x == 3;
+        assert_eq!(
+            state.square(Coordinate::new(x, 1)).effects.is_empty(),
+            !defended
+        );
+    }
+}
+
+#[test]
+fn game_over_and_size_round_trip() {
+    let mut state = GameState::from_fen("d7/8/8/8/8/7D b -/- -/- -").unwrap();
+    state.game_over = Some(Winner::Color(TileColor::White));
+    state.win_reason = Some(WinReason::Resignation);
+    let loaded = from_json_position(&to_json_position(&state)).unwrap();
+    assert_eq!(loaded.size(), BoardSize::new(8, 6));
+    assert_eq!(loaded.game_over, state.game_over);
+    assert_eq!(loaded.win_reason, state.win_reason);
+}
+
+/// Standard start position as JSON, with text replaced.
+fn edited(from: &str, to: &str) -> Result<GameState, PositionError> {
+    let json = to_json_position(&GameState::new_seeded(1));
+    assert!(json.contains(from), "{}", json);
+    from_json_position(&json.replacen(from, to, 1))
+}
+
+#[test]
+fn broken_documents_are_rejected() {
+    match edited("\"version\": 1", "\"version\": 2") {
+        Err(PositionError::UnsupportedVersion(2)) => {}
+        result => panic!("{:?}", result.map(|s| s.to_fen())),
+    }
+    match edited("\"ply\": \"Black\"", "\"ply\": 3") {
+        Err(PositionError::Json(error)) => assert!(error.line() > 1, "{}", error),
+        result => panic!("{:?}", result.map(|s| s.to_fen())),
+    }
+    match edited("\"width\": 6", "\"width\": 5") {
+        Err(PositionError::IllegalSize(size)) => assert_eq!(size.width, 5),
+        result => panic!("{:?}", result.map(|s| s.to_fen())),
+    }
+
+    let duke = |x: u8, y: u8, color: &str| {
+        format!(
+            r#"{{ "square": {{ "x": {}, "y": {} }}, "tile": {{ "kind": "Duke", "color": "{}", "flipped": false }} }}"#,
+            x, y, color
+        )
+    };
+    let board = |squares: &[String]| {
+        format!(
+            r#"{{ "version": 1, "ply": "Black", "game_over": null, "board": [{}],
+               "bags": {{ "black": [], "white": [] }}, "drawn": {{ "black": [], "white": [] }},
+               "graveyard": [] }}"#,
+            squares.join(",")
+        )
+    };
+    match from_json_position(&board(&[duke(0, 0, "Black"), duke(6, 0, "White")])) {
+        Err(PositionError::IllegalSquare(pointer)) => assert_eq!(pointer, "/board/1/square"),
+        result => panic!("{:?}", result.map(|s| s.to_fen())),
+    }
+    match from_json_position(&board(&[
+        duke(0, 0, "Black"),
+        duke(5, 5, "White"),
+        duke(5, 5, "Black"),
+    ])) {
+        Err(PositionError::DuplicateSquare(pointer)) => assert_eq!(pointer, "/board/2/square"),
+        result => panic!("{:?}", result.map(|s| s.to_fen())),
+    }
+    match from_json_position(&board(&[
+        duke(0, 0, "Black"),
+        duke(5, 5, "White"),
+        duke(3, 3, "White"),
+    ])) {
+        Err(PositionError::DuplicateDuke(color)) => assert_eq!(color, TileColor::White),
+        result => panic!("{:?}", result.map(|s| s.to_fen())),
+    }
+}
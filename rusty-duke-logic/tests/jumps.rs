@@ -0,0 +1,205 @@
+//! Jumps and strikes ignore what is between the tile and the target, only
+//! the target square can block them. Moves are blocked by tiles in between.
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::{
+    get_tile_actions, validate_action, Action, ActionResult, Coordinate, GameState, TileColor,
+    TileType,
+};
+
+/// Black Duke on a1, White Duke on f6, plus tiles. Black to go.
+fn state(tiles: &[(Coordinate, TileType, TileColor, bool)]) -> GameState {
+    let mut builder = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(5, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        );
+    for (cord, kind, color, flipped) in tiles.iter() {
+        builder = builder.tile_at(*cord, *kind, *color, *flipped);
+    }
+    builder.ply(TileColor::Black).build().unwrap()
+}
+
+/// Action of the tile on from onto to, also checked by `validate_action`.
+fn action(state: &GameState, from: Coordinate, to: Coordinate) -> Option<Action> {
+    let action = get_tile_actions(state, from)
+        .into_iter()
+        .find(|action| match action {
+            Action::Move(data) | Action::Jump(data) | Action::Strike(data) => data.target_pos == to,
+            _ => false,
+        })?;
+    assert_eq!(validate_action(state, &action), Ok(()));
+    Some(action)
+}
+
+/// Footmen of both colors, front side up.
+fn footmen(cords: &[Coordinate]) -> Vec<(Coordinate, TileType, TileColor, bool)> {
+    let mut tiles = Vec::new();
+    for (i, cord) in cords.iter().enumerate() {
+        let color = match i % 2 {
+            0 => TileColor::Black,
+            _ => TileColor::White,
+        };
+        tiles.push((*cord, TileType::Footman, color, false));
+    }
+    tiles
+}
+
+#[test]
+fn knight_jumps_over_tiles_of_either_color() {
+    // Knight on c2 jumps to d4, every square around the way is taken.
+    let knight = Coordinate::new(2, 1);
+    let target = Coordinate::new(3, 3);
+    let between = [
+        Coordinate::new(2, 2),
+        Coordinate::new(3, 2),
+        Coordinate::new(3, 1),
+    ];
+    for colors in 0..2 {
+        let mut tiles = footmen(&between);
+        if colors == 1 {
+            for tile in tiles.iter_mut() {
+                tile.2 = tile.2.opponent();
+            }
+        }
+        tiles.push((knight, TileType::Knight, TileColor::Black, false));
+        let state = state(&tiles);
+        assert!(matches!(
+            action(&state, knight, target),
+            Some(Action::Jump(data)) if data.result == ActionResult::Move
+        ));
+    }
+}
+
+#[test]
+fn straight_jump_goes_over_tiles_and_defence() {
+    // Champion on c2 jumps to c4 over c3.
+    let champion = (
+        Coordinate::new(2, 1),
+        TileType::Champion,
+        TileColor::Black,
+        false,
+    );
+    let over = [
+        vec![(
+            Coordinate::new(2, 2),
+            TileType::Footman,
+            TileColor::Black,
+            false,
+        )],
+        vec![(
+            Coordinate::new(2, 2),
+            TileType::Footman,
+            TileColor::White,
+            false,
+        )],
+        // White Guinevere on b3 defends c3.
+        vec![(
+            Coordinate::new(1, 2),
+            TileType::Guinevere,
+            TileColor::White,
+            false,
+        )],
+    ];
+    for mut tiles in over {
+        tiles.push(champion);
+        let state = state(&tiles);
+        assert!(
+            matches!(
+                action(&state, champion.0, Coordinate::new(2, 3)),
+                Some(Action::Jump(_))
+            ),
+            "{:?}",
+            tiles
+        );
+    }
+}
+
+#[test]
+fn jump_target_decides() {
+    // Flipped Wizard on c2 jumps to c4.
+    let wizard = (
+        Coordinate::new(2, 1),
+        TileType::Wizard,
+        TileColor::Black,
+        true,
+    );
+    let target = Coordinate::new(2, 3);
+    let jump = |others: &[(Coordinate, TileType, TileColor, bool)]| {
+        let mut tiles = others.to_vec();
+        tiles.push(wizard);
+        action(&state(&tiles), wizard.0, target)
+    };
+
+    assert!(matches!(jump(&[]), Some(Action::Jump(data)) if data.result == ActionResult::Move));
+    let enemy = (target, TileType::Footman, TileColor::White, false);
+    assert!(matches!(
+        jump(&[enemy]),
+        Some(Action::Jump(data)) if data.result == ActionResult::Capture
+    ));
+    let own = (target, TileType::Footman, TileColor::Black, false);
+    assert_eq!(jump(&[own]), None);
+
+    // White Guinevere on b4 defends c4, empty or not.
+    let guinevere = (
+        Coordinate::new(1, 3),
+        TileType::Guinevere,
+        TileColor::White,
+        false,
+    );
+    assert_eq!(jump(&[guinevere]), None);
+    assert_eq!(jump(&[guinevere, enemy]), None);
+}
+
+#[test]
+fn strike_goes_over_tiles() {
+    // Flipped Bowman on c2 strikes c4 over an enemy on c3.
+    let bowman = (
+        Coordinate::new(2, 1),
+        TileType::Bowman,
+        TileColor::Black,
+        true,
+    );
+    let between = (
+        Coordinate::new(2, 2),
+        TileType::Footman,
+        TileColor::White,
+        false,
+    );
+    let target = (
+        Coordinate::new(2, 3),
+        TileType::Footman,
+        TileColor::White,
+        false,
+    );
+    let state = state(&[bowman, between, target]);
+    assert!(matches!(
+        action(&state, bowman.0, target.0),
+        Some(Action::Strike(_))
+    ));
+}
+
+#[test]
+fn move_is_blocked_by_tiles_in_between() {
+    // Flipped Knight on c4 moves two squares diagonally to e2, over d3.
+    let knight = (
+        Coordinate::new(2, 3),
+        TileType::Knight,
+        TileColor::Black,
+        true,
+    );
+    let target = Coordinate::new(4, 1);
+    assert!(action(&state(&[knight]), knight.0, target).is_some());
+    for color in [TileColor::Black, TileColor::White] {
+        let between = (Coordinate::new(3, 2), TileType::Footman, color, false);
+        assert_eq!(action(&state(&[knight, between]), knight.0, target), None);
+    }
+}
@@ -0,0 +1,97 @@
+//! Actions may not leave own Duke in guard.
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::{
+    do_action, get_actions, get_legal_actions, get_tile_actions, is_checkmate, is_in_check, Action,
+    Coordinate, TileColor, TileType, WinReason, Winner,
+};
+
+/// Action among actions that ends on target.
+fn action_to(actions: &[Action], target: Coordinate) -> Option<Action> {
+    actions.iter().copied().find(|action| match action {
+        Action::Move(data) | Action::Slide(data) | Action::Jump(data) => data.target_pos == target,
+        _ => false,
+    })
+}
+
+#[test]
+fn pinned_tile_stays_on_file() {
+    // Footman between the Black Duke and the sliding White Knight.
+    let footman = Coordinate::new(2, 3);
+    let state = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(2, 1),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(footman, TileType::Footman, TileColor::Black, false)
+        .tile_at(
+            Coordinate::new(0, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(2, 5),
+            TileType::Knight,
+            TileColor::White,
+            true,
+        )
+        .ply(TileColor::Black)
+        .build()
+        .unwrap();
+    assert!(!is_in_check(&state, TileColor::Black));
+
+    let possible = get_tile_actions(&state, footman);
+    let legal = get_legal_actions(&state);
+    for x in [1, 3] {
+        let aside = action_to(&possible, Coordinate::new(x, 3)).unwrap();
+        assert!(get_actions(&state).contains(&aside));
+        assert!(!legal.contains(&aside));
+    }
+    for y in [2, 4] {
+        let along = action_to(&possible, Coordinate::new(2, y)).unwrap();
+        assert!(legal.contains(&along));
+    }
+}
+
+#[test]
+fn no_legal_actions_loses() {
+    // Black can only move the Footman, which shields the Duke from the White
+    // Duke once it slides onto the same rank.
+    let mut state = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(1, 0),
+            TileType::Footman,
+            TileColor::Black,
+            true,
+        )
+        .tile_at(
+            Coordinate::new(4, 2),
+            TileType::Duke,
+            TileColor::White,
+            true,
+        )
+        .bag(TileColor::Black, vec![])
+        .bag(TileColor::White, vec![])
+        .ply(TileColor::White)
+        .build()
+        .unwrap();
+
+    let actions = get_tile_actions(&state, Coordinate::new(4, 2));
+    let slide = action_to(&actions, Coordinate::new(4, 0)).unwrap();
+    do_action(&mut state, &slide).unwrap();
+
+    assert!(!is_in_check(&state, TileColor::Black));
+    assert!(!is_checkmate(&state));
+    assert_eq!(state.game_over, Some(Winner::Color(TileColor::White)));
+    assert_eq!(state.win_reason, Some(WinReason::NoMoves));
+    assert!(get_legal_actions(&state).is_empty());
+}
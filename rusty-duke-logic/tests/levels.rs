@@ -0,0 +1,83 @@
+//! Difficulty levels: low levels blunder, and lose to high levels.
+
+use rusty_duke_logic::ai::alpha_beta::{Agent, AgentConfig, MAX_LEVEL, MIN_LEVEL};
+use rusty_duke_logic::ai::random::RandomAgent;
+use rusty_duke_logic::ai::DukeAgent;
+use rusty_duke_logic::logic::variant::Variant;
+use rusty_duke_logic::logic::{do_action, GameState, TileColor, Winner};
+
+/// Games of the batch, each level plays both colors.
+const GAMES: u64 = 20;
+
+/// Agent of level. Searches of high levels are cut to depth 3, to keep the
+/// games fast. Blunders and noise are those of the level.
+fn agent(color: TileColor, level: u8, seed: u64) -> Agent {
+    let config = AgentConfig {
+        depth: AgentConfig::level(level).depth.min(Some(3)),
+        duration: None,
+        ..AgentConfig::level(level)
+    };
+    Agent::from_config(color, &config).with_seed(seed)
+}
+
+/// Winner of a quick game between levels of Black and White.
+fn play(levels: [u8; 2], seed: u64) -> Winner {
+    let mut state = GameState::new_with_rules(&Variant::Quick.rules(), Some(seed));
+    let mut agents = [
+        agent(TileColor::Black, levels[0], seed),
+        agent(TileColor::White, levels[1], seed + 1),
+    ];
+    while state.game_over.is_none() {
+        let action = agents[state.ply as usize].choose_action(&state).unwrap();
+        do_action(&mut state, &action).unwrap();
+    }
+    state.game_over.unwrap()
+}
+
+#[test]
+fn high_level_beats_low_level() {
+    let mut wins = 0;
+    for seed in 0..GAMES {
+        let strong = if seed % 2 == 0 {
+            TileColor::Black
+        } else {
+            TileColor::White
+        };
+        let levels = match strong {
+            TileColor::Black => [MAX_LEVEL, MIN_LEVEL],
+            TileColor::White => [MIN_LEVEL, MAX_LEVEL],
+        };
+        if play(levels, seed) == Winner::Color(strong) {
+            wins += 1;
+        }
+    }
+    assert!(wins >= GAMES * 3 / 4, "{} of {}", wins, GAMES);
+}
+
+#[test]
+fn only_weakened_levels_blunder() {
+    let mut blunders = 0;
+    for seed in 0..4 {
+        let mut state = GameState::new_seeded(seed);
+        let mut random = [
+            RandomAgent::new_seeded(TileColor::Black, seed),
+            RandomAgent::new_seeded(TileColor::White, seed + 100),
+        ];
+        while state.game_over.is_none() && state.turn < 30 {
+            // Level 1 searches depth 1, level 5 depth 3 without weakening.
+            let mut weak = Agent::from_level(state.ply, 1).with_seed(seed);
+            let mut best = Agent::new(state.ply, Some(1), None);
+            if weak.choose_action(&state) != best.choose_action(&state) {
+                blunders += 1;
+            }
+            let mut level = Agent::from_level(state.ply, 5);
+            let mut full = Agent::new(state.ply, Some(3), None);
+            assert_eq!(level.weakening, None);
+            assert_eq!(level.choose_action(&state), full.choose_action(&state));
+
+            let action = random[state.ply as usize].choose_action(&state).unwrap();
+            do_action(&mut state, &action).unwrap();
+        }
+    }
+    assert!(blunders > 0);
+}
@@ -0,0 +1,138 @@
+//! Games between two sessions of the `net` protocol in one process.
+
+#![cfg(feature = "net")]
+
+use rusty_duke_logic::ai::random::RandomAgent;
+use rusty_duke_logic::ai::DukeAgent;
+use rusty_duke_logic::logic::{Action, Coordinate, TileColor, Winner};
+use rusty_duke_logic::net::{ClientSession, Event, HostSession, NetError, Session};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+/// Plies after which the side in turn resigns.
+const MAX_PLIES: usize = 400;
+
+/// Play until the game is over, with seeded random actions. Returns hash of
+/// the final state and the winner.
+fn play<S: Read + Write>(session: &mut Session<S>, seed: u64) -> (u64, Winner) {
+    let mut agent = RandomAgent::new_seeded(session.color(), seed);
+    let mut plies = 0;
+    while session.winner().is_none() {
+        if session.state().ply == session.color() {
+            if plies >= MAX_PLIES {
+                session.resign().unwrap();
+                break;
+            }
+            let action = agent.choose_action(session.state()).unwrap();
+            session.send_action(action).unwrap();
+        } else {
+            session.receive().unwrap();
+        }
+        plies += 1;
+    }
+    (session.state().hash(), session.winner().unwrap())
+}
+
+fn connect() -> (TcpStream, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (host, _) = listener.accept().unwrap();
+    host.set_nodelay(true).unwrap();
+    client.set_nodelay(true).unwrap();
+    (host, client)
+}
+
+#[test]
+fn scripted_game_over_loopback() {
+    for seed in 0..4 {
+        let (host_stream, client_stream) = connect();
+        let host = thread::spawn(move || {
+            let mut host = HostSession::accept(host_stream, TileColor::White, seed).unwrap();
+            play(&mut host, seed)
+        });
+        let mut client = ClientSession::connect(client_stream).unwrap();
+        assert_eq!(client.color(), TileColor::Black);
+        let client_result = play(&mut client, seed + 100);
+
+        assert_eq!(host.join().unwrap(), client_result);
+    }
+}
+
+#[test]
+fn illegal_action_is_rejected_and_draw_agreed() {
+    let (host_stream, client_stream) = connect();
+    let host = thread::spawn(move || {
+        let mut host = HostSession::accept(host_stream, TileColor::Black, 1).unwrap();
+        // Duke can't be placed in a corner. Nothing is sent.
+        let corner = Action::PlaceNew(Coordinate::new(0, 0));
+        assert!(matches!(
+            host.send_action(corner),
+            Err(NetError::Illegal(_))
+        ));
+        host.send_action(Action::PlaceNew(Coordinate::new(2, 0)))
+            .unwrap();
+        assert_eq!(host.receive().unwrap(), Event::DrawOffered);
+        host.answer_draw(true).unwrap();
+        host.winner()
+    });
+
+    let mut client = ClientSession::connect(client_stream).unwrap();
+    assert!(matches!(
+        client.send_action(Action::PlaceNew(Coordinate::new(2, 5))),
+        Err(NetError::Turn)
+    ));
+    assert!(matches!(client.receive(), Ok(Event::Action(_))));
+    assert!(client.offer_draw().unwrap());
+
+    assert_eq!(client.winner(), Some(Winner::Draw));
+    assert_eq!(host.join().unwrap(), Some(Winner::Draw));
+}
+
+#[cfg(feature = "net-async")]
+#[tokio::test]
+async fn async_sessions_over_duplex() {
+    use rusty_duke_logic::net::asynchronous;
+
+    let (host_stream, client_stream) = tokio::io::duplex(4096);
+    let host = async {
+        let mut host = asynchronous::HostSession::accept(host_stream, TileColor::Black, 5)
+            .await
+            .unwrap();
+        let mut agent = RandomAgent::new_seeded(TileColor::Black, 5);
+        let mut plies = 0;
+        while host.winner().is_none() {
+            if host.state().ply == host.color() {
+                if plies >= 20 {
+                    host.resign().await.unwrap();
+                    break;
+                }
+                let action = agent.choose_action(host.state()).unwrap();
+                host.send_action(action).await.unwrap();
+            } else {
+                host.receive().await.unwrap();
+            }
+            plies += 1;
+        }
+        (host.state().hash(), host.winner())
+    };
+    let client = async {
+        let mut client = asynchronous::ClientSession::connect(client_stream)
+            .await
+            .unwrap();
+        let mut agent = RandomAgent::new_seeded(TileColor::White, 6);
+        while client.winner().is_none() {
+            if client.state().ply == client.color() {
+                let action = agent.choose_action(client.state()).unwrap();
+                client.send_action(action).await.unwrap();
+            } else {
+                client.receive().await.unwrap();
+            }
+        }
+        (client.state().hash(), client.winner())
+    };
+
+    let (host_result, client_result) = tokio::join!(host, client);
+    assert_eq!(host_result, client_result);
+    assert_eq!(client_result.1, Some(Winner::Color(TileColor::White)));
+}
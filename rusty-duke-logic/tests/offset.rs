@@ -0,0 +1,47 @@
+//! `Coordinate::offset` against a reference in wide integers, for any
+//! coordinate and offset, on board or not, and any board size.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use proptest::prelude::*;
+use rusty_duke_logic::logic::{BoardSize, Coordinate, Offset, HEIGHT, WIDTH};
+
+/// Square at cord plus offset, computed without any chance to wrap.
+fn reference(x: u8, y: u8, dx: i8, dy: i8, size: BoardSize) -> Option<(u8, u8)> {
+    let x = x as i32 + dx as i32;
+    let y = y as i32 + dy as i32;
+    if (0..size.width as i32).contains(&x) && (0..size.height as i32).contains(&y) {
+        Some((x as u8, y as u8))
+    } else {
+        None
+    }
+}
+
+fn board_size() -> impl Strategy<Value = BoardSize> {
+    (1..=4u8, 2..=8u8).prop_map(|(half, height)| BoardSize::new(half * 2, height))
+}
+
+proptest! {
+    #[test]
+    fn offset_matches_reference(x: u8, y: u8, dx: i8, dy: i8, size in board_size()) {
+        let cord = Coordinate { x, y };
+        let moved = cord.offset(&Offset { x: dx, y: dy }, size);
+
+        prop_assert_eq!(moved.map(|c| (c.x, c.y)), reference(x, y, dx, dy, size));
+        if let Some(moved) = moved {
+            prop_assert!(Coordinate::legal(moved.x, moved.y, size));
+        }
+    }
+
+    #[test]
+    fn offset_on_board_matches_reference(
+        x in 0..WIDTH,
+        y in 0..HEIGHT,
+        dx in -(WIDTH as i8)..=WIDTH as i8,
+        dy in -(HEIGHT as i8)..=HEIGHT as i8,
+    ) {
+        let size = BoardSize::STANDARD;
+        let moved = Coordinate::new(x, y).offset(&Offset { x: dx, y: dy }, size);
+        prop_assert_eq!(moved.map(|c| (c.x, c.y)), reference(x, y, dx, dy, size));
+    }
+}
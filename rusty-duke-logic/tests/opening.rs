@@ -0,0 +1,136 @@
+//! Opening book: the agent plays the first turns without search, and only
+//! hands out actions that can be done.
+
+use rusty_duke_logic::ai::alpha_beta::{get_action_with_stats, Agent};
+use rusty_duke_logic::ai::opening::{BookError, OpeningBook};
+use rusty_duke_logic::logic::notation::{notation_to_action, NotationError};
+use rusty_duke_logic::logic::variant::{GameRules, Variant};
+use rusty_duke_logic::logic::{do_action, get_actions, Action, BoardSize, GameState, TileColor};
+use std::time::{Duration, Instant};
+
+fn agent(color: TileColor, book: bool) -> Agent {
+    let agent = Agent::new(color, Some(2), None);
+    if book {
+        agent.with_opening_book(OpeningBook::standard())
+    } else {
+        agent
+    }
+}
+
+/// Agents with book play until the book runs out. Actions and their turns.
+fn play_from_book(mut state: GameState) -> Vec<(u16, Action)> {
+    let mut played = Vec::new();
+    loop {
+        let color = state.ply;
+        let started = Instant::now();
+        let (action, stats) = get_action_with_stats(&agent(color, true), &state, None);
+        if stats.nodes > 0 {
+            return played;
+        }
+        assert!(started.elapsed() < Duration::from_millis(50));
+        let action = action.unwrap();
+        assert!(get_actions(&state).contains(&action));
+        played.push((state.turn, action));
+        do_action(&mut state, &action).unwrap();
+    }
+}
+
+#[test]
+fn first_turns_come_from_the_book() {
+    let book = OpeningBook::standard();
+    assert_eq!(book.len(), 19);
+
+    for state in [
+        GameState::new(),
+        GameState::new_seeded(7),
+        GameState::new_with_rules(&Variant::Quick.rules(), Some(7)),
+    ] {
+        let first = state.clone();
+        let played = play_from_book(state);
+        assert_eq!(
+            played.iter().map(|(turn, _)| *turn).collect::<Vec<u16>>(),
+            [0, 1, 2, 3]
+        );
+        assert_eq!(
+            Some(played[0].1),
+            notation_to_action(&first, "@c1 Duke").ok()
+        );
+    }
+}
+
+#[test]
+fn agent_without_book_searches() {
+    let state = GameState::new_seeded(7);
+    let (action, stats) = get_action_with_stats(&agent(TileColor::Black, false), &state, None);
+    assert!(action.is_some());
+    assert!(stats.nodes > 0);
+}
+
+#[test]
+fn book_replies_to_other_placements() {
+    let book = OpeningBook::standard();
+    let mut state = GameState::new_seeded(3);
+    // Book plays c1, d1 is the other choice.
+    for s in ["@d1 Duke", "@d6 Duke", "@c1 Footman"] {
+        let action = notation_to_action(&state, s).unwrap();
+        do_action(&mut state, &action).unwrap();
+    }
+    assert_eq!(
+        book.action(&state),
+        notation_to_action(&state, "@e6 Footman").ok()
+    );
+}
+
+#[test]
+fn book_actions_that_cant_be_done_are_not_handed_out() {
+    let book = OpeningBook::standard();
+    // Same position as the standard start, Black to place the Duke, but the
+    // Duke goes to d1 or e1 on the larger board. Book says c1.
+    let rules = GameRules {
+        size: BoardSize::new(8, 8),
+        ..GameRules::standard()
+    };
+    let state = GameState::new_with_rules(&rules, Some(3));
+    assert_eq!(book.action(&state), None);
+
+    let agent = Agent::new(TileColor::Black, Some(1), None).with_opening_book(book);
+    let (action, stats) = get_action_with_stats(&agent, &state, None);
+    assert!(stats.nodes > 0);
+    assert!(get_actions(&state).contains(&action.unwrap()));
+}
+
+#[test]
+fn positions_after_the_book_are_not_looked_up() {
+    let book = OpeningBook::standard();
+    let mut state = GameState::new_seeded(3);
+    while let Some(action) = book.action(&state) {
+        do_action(&mut state, &action).unwrap();
+    }
+    assert_eq!(state.turn, 4);
+}
+
+#[test]
+fn broken_books_report_the_line() {
+    let text = |line: &str| format!("# Comment\n\n=> @c1 Duke\n{}\n", line);
+    assert_eq!(
+        OpeningBook::from_text(&text("@c1 Duke @c6 Duke")),
+        Err(BookError::Syntax(4, "@c1 Duke @c6 Duke".to_string()))
+    );
+    assert!(matches!(
+        OpeningBook::from_text(&text("@c1 Duke => @c3 Duke")),
+        Err(BookError::Notation(4, NotationError::IllegalAction(_)))
+    ));
+    assert!(matches!(
+        OpeningBook::from_text(&text("@c1 Duke => @c6 Dragon")),
+        Err(BookError::Notation(4, NotationError::UnknownTileType(_)))
+    ));
+    let error = OpeningBook::from_text(&text("@c1 Duke => @c6 Dragon")).unwrap_err();
+    assert!(error.to_string().starts_with("line 4: "), "{}", error);
+
+    // Placements are done, the next action draws from the bag.
+    let placements = "@c1 Duke; @c6 Duke; @d1 Footman; @d6 Footman; @b1 Footman; @b6 Footman";
+    assert_eq!(
+        OpeningBook::from_text(&text(&format!("{} => draw", placements))),
+        Err(BookError::Draw(4))
+    );
+}
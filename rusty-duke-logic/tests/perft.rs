@@ -0,0 +1,33 @@
+//! Node counts of the legal action tree, as regression values. A change to
+//! action generation that changes them has to explain why.
+
+use rusty_duke_logic::logic::perft::{count_action_nodes, perft, DrawMode};
+use rusty_duke_logic::logic::GameState;
+
+/// Seed 0 after the six placements of Dukes and Footmen, each on the first
+/// square offered.
+const PLACED: &str = "1FDF2/6/6/6/6/1fdf2 b fpppkblwscagmnrey/FPPPKBLWSCAGMNREY -/- - 6";
+
+#[test]
+fn after_placements() {
+    let state = GameState::from_fen(PLACED).unwrap();
+
+    // (Exclude, Expand, Leaf) by depth.
+    let counts = [(4, 19, 5), (16, 91, 21), (112, 689, 133)];
+    for (depth, (exclude, expand, leaf)) in (1..).zip(counts) {
+        assert_eq!(perft(&state, depth, DrawMode::Exclude), exclude);
+        assert_eq!(perft(&state, depth, DrawMode::Expand), expand);
+        assert_eq!(perft(&state, depth, DrawMode::Leaf), leaf);
+    }
+
+    // Also counting actions that leave the own Duke in check.
+    assert_eq!(count_action_nodes(&state, 3, DrawMode::Expand), 697);
+}
+
+#[test]
+fn from_start() {
+    let state = GameState::new_seeded(0);
+    assert_eq!(perft(&state, 6, DrawMode::Expand), 144);
+    assert_eq!(perft(&state, 7, DrawMode::Leaf), 1056);
+    assert_eq!(perft(&state, 8, DrawMode::Exclude), 5640);
+}
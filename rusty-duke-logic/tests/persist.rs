@@ -0,0 +1,159 @@
+//! Files written by several threads and processes at once through the
+//! persistence layer.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use rusty_duke_logic::persist::{self, FileLock, PersistError, DEFAULT_LOCK_TIMEOUT};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+/// Environment variable with the file the child process updates.
+const CHILD_FILE: &str = "RUSTY_DUKE_PERSIST_CHILD_FILE";
+const UPDATES: u32 = 50;
+
+/// Empty directory for test.
+fn directory(test: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!(
+        "rusty-duke-persist-{}-{}",
+        std::process::id(),
+        test
+    ));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// Contents written by writer, long enough to take several writes.
+fn contents(writer: usize) -> Vec<u8> {
+    format!("writer {}\n", writer)
+        .repeat(2000 + 100 * writer)
+        .into_bytes()
+}
+
+/// Add one to the counter in file.
+fn increment(path: &Path) {
+    persist::update(path, DEFAULT_LOCK_TIMEOUT, |contents| {
+        let count: u32 = contents
+            .map(|c| String::from_utf8(c).unwrap().parse().unwrap())
+            .unwrap_or(0);
+        (count + 1).to_string().into_bytes()
+    })
+    .unwrap();
+}
+
+fn count(path: &Path) -> u32 {
+    String::from_utf8(persist::read(path, DEFAULT_LOCK_TIMEOUT).unwrap().unwrap())
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn concurrent_writes_are_never_interleaved() {
+    let path = directory("writes").join("archive.txt");
+    let valid: Vec<Vec<u8>> = (0..4).map(contents).collect();
+
+    thread::scope(|scope| {
+        for writer in 0..4 {
+            let path = &path;
+            scope.spawn(move || {
+                for _ in 0..20 {
+                    persist::write(path, &contents(writer), DEFAULT_LOCK_TIMEOUT).unwrap();
+                }
+            });
+        }
+        let (path, valid) = (&path, &valid);
+        scope.spawn(move || {
+            for _ in 0..50 {
+                if let Some(read) = persist::read(path, DEFAULT_LOCK_TIMEOUT).unwrap() {
+                    assert!(valid.contains(&read), "{} bytes", read.len());
+                }
+            }
+        });
+    });
+
+    let read = persist::read(&path, DEFAULT_LOCK_TIMEOUT).unwrap().unwrap();
+    assert!(valid.contains(&read));
+    // Only the file and its lock are left, no temporary files.
+    let mut names: Vec<String> = std::fs::read_dir(path.parent().unwrap())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["archive.txt", "archive.txt.lock"]);
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn concurrent_updates_are_not_lost() {
+    let path = directory("updates").join("count.txt");
+    assert_eq!(persist::read(&path, DEFAULT_LOCK_TIMEOUT).unwrap(), None);
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..UPDATES {
+                    increment(&path);
+                }
+            });
+        }
+    });
+    assert_eq!(count(&path), 4 * UPDATES);
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn held_lock_times_out_with_clear_error() {
+    let path = directory("locked").join("settings.txt");
+    persist::write(&path, b"old", DEFAULT_LOCK_TIMEOUT).unwrap();
+
+    let lock = FileLock::acquire(&path, DEFAULT_LOCK_TIMEOUT).unwrap();
+    let timeout = Duration::from_millis(50);
+    match persist::write(&path, b"new", timeout) {
+        Err(PersistError::Locked(locked)) => assert_eq!(locked, path),
+        result => panic!("{:?}", result),
+    }
+    let error = persist::read(&path, timeout).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("another instance holds the lock"),
+        "{}",
+        error
+    );
+
+    drop(lock);
+    assert_eq!(
+        persist::read(&path, timeout).unwrap(),
+        Some(b"old".to_vec())
+    );
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+/// Updates of the other process in `two_processes_update_one_file`. Does
+/// nothing when run as a normal test.
+#[test]
+fn child_process_updates() {
+    if let Some(path) = std::env::var_os(CHILD_FILE) {
+        for _ in 0..UPDATES {
+            increment(Path::new(&path));
+        }
+    }
+}
+
+#[test]
+fn two_processes_update_one_file() {
+    let path = directory("processes").join("count.txt");
+    let mut child = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "child_process_updates", "--test-threads", "1"])
+        .env(CHILD_FILE, &path)
+        .spawn()
+        .unwrap();
+    for _ in 0..UPDATES {
+        increment(&path);
+    }
+    assert!(child.wait().unwrap().success());
+    assert_eq!(count(&path), 2 * UPDATES);
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
@@ -0,0 +1,78 @@
+//! Placements of a drawn tile that mirror each other, and how the search
+//! skips them.
+
+use rusty_duke_logic::ai::alpha_beta::{get_action_with_stats, Agent};
+use rusty_duke_logic::logic::{
+    do_action, get_legal_actions, Action, Coordinate, GameState, TileColor,
+};
+
+/// States after each legal action.
+fn children(state: &GameState) -> Vec<(Action, GameState)> {
+    get_legal_actions(state)
+        .into_iter()
+        .map(|action| {
+            let mut child = state.clone();
+            do_action(&mut child, &action).unwrap();
+            (action, child)
+        })
+        .collect()
+}
+
+fn agent(depth: u8) -> Agent {
+    let mut agent = Agent::new(TileColor::Black, Some(depth), None);
+    agent.transposition_table = false;
+    agent.threads = 1;
+    agent
+}
+
+#[test]
+fn first_duke_placements_mirror_each_other() {
+    let state = GameState::new_seeded(1);
+    let placements = children(&state);
+    assert_eq!(placements.len(), 2);
+    let (left, right) = (&placements[0].1, &placements[1].1);
+    assert!(left.is_mirror_of(right));
+    assert!(right.is_mirror_of(left));
+    // Duke is not on the middle column, a placement is no mirror of itself.
+    assert!(!left.is_mirror_of(left));
+
+    // Black Duke is on one side now, White placements are not equivalent.
+    let replies = children(left);
+    assert_eq!(replies.len(), 2);
+    assert!(!replies[0].1.is_mirror_of(&replies[1].1));
+}
+
+#[test]
+fn search_skips_mirrored_placements() {
+    let state = GameState::new_seeded(1);
+    let placements = children(&state);
+
+    // Both subtrees are the same size, searching both would double the
+    // nodes.
+    let mut nodes = Vec::new();
+    for (_, child) in placements.iter() {
+        let mut agent = agent(3);
+        agent.color = TileColor::White;
+        let (_, stats) = get_action_with_stats(&agent, child, None);
+        nodes.push(stats.nodes);
+    }
+    assert_eq!(nodes[0], nodes[1]);
+    assert!(nodes[0] > 1);
+
+    // Only one placement is left to search, so it is forced and nothing
+    // below it is searched.
+    let (action, stats) = get_action_with_stats(&agent(4), &state, None);
+    assert!(stats.nodes < nodes[0], "{:?} {:?}", stats, nodes);
+    let allowed = [
+        Action::PlaceNew(Coordinate::new(2, 0)),
+        Action::PlaceNew(Coordinate::new(3, 0)),
+    ];
+    assert!(allowed.contains(&action.unwrap()), "{:?}", action);
+
+    // Placements of White are not equivalent, both are searched.
+    let mut agent = agent(3);
+    agent.color = TileColor::White;
+    let (action, stats) = get_action_with_stats(&agent, &placements[0].1, None);
+    assert!(stats.nodes > 1, "{:?}", stats);
+    assert!(get_legal_actions(&placements[0].1).contains(&action.unwrap()));
+}
@@ -0,0 +1,79 @@
+//! Recorded random games replay to the same positions.
+
+use rusty_duke_logic::ai::random::RandomAgent;
+use rusty_duke_logic::ai::DukeAgent;
+use rusty_duke_logic::logic::record::GameRecord;
+use rusty_duke_logic::logic::{do_unsafe_action, resign, GameState, TileColor};
+
+/// Actions after which the player to go resigns.
+const MAX_ACTIONS: usize = 120;
+
+/// Play a random game. Returns the record, the final state and the hash of
+/// every position.
+fn random_game(seed: u64) -> (GameRecord, GameState, Vec<u64>) {
+    let mut state = GameState::new_seeded(seed);
+    let mut record = GameRecord::new(Some(seed));
+    let mut agents = [
+        RandomAgent::new_seeded(TileColor::Black, seed),
+        RandomAgent::new_seeded(TileColor::White, seed + 1000),
+    ];
+    let mut hashes = vec![state.hash()];
+
+    while state.game_over.is_none() {
+        if record.len() >= MAX_ACTIONS {
+            let color = state.ply;
+            resign(&mut state, color);
+            record.push_end(&state);
+            *hashes.last_mut().unwrap() = state.hash();
+            break;
+        }
+        let action = match agents[state.ply as usize].choose_action(&state) {
+            Some(action) => action,
+            None => break,
+        };
+        do_unsafe_action(&mut state, &action);
+        record.push(&state, action);
+        hashes.push(state.hash());
+    }
+
+    (record, state, hashes)
+}
+
+#[test]
+fn random_games_round_trip() {
+    for seed in 0..100 {
+        let (record, state, hashes) = random_game(seed);
+
+        let positions: Vec<GameState> = record.positions().collect();
+        assert_eq!(positions.len(), record.len() + 1);
+        let replayed: Vec<u64> = positions.iter().map(|p| p.hash()).collect();
+        assert_eq!(replayed, hashes, "seed {}", seed);
+
+        let last = positions.last().unwrap();
+        assert_eq!(last.bag(), state.bag());
+        assert_eq!(last.graveyard, state.graveyard);
+        assert_eq!(last.game_over, state.game_over);
+        assert_eq!(record.winner(), state.game_over);
+
+        let loaded = GameRecord::from_text(&record.to_text()).unwrap();
+        assert_eq!(loaded, record);
+
+        // Draws are in the record, the seed is not needed.
+        let mut unseeded = record.clone();
+        unseeded.seed = None;
+        let replayed = unseeded.replay();
+        assert_eq!(replayed.hash(), state.hash());
+        assert_eq!(replayed.graveyard, state.graveyard);
+    }
+}
+
+#[test]
+fn positions_match_state_at() {
+    let (record, _, _) = random_game(7);
+    for (index, position) in record.positions().enumerate().step_by(13) {
+        assert_eq!(position.hash(), record.state_at(index).hash());
+    }
+
+    let empty = GameRecord::new(Some(7));
+    assert_eq!(empty.positions().count(), 1);
+}
@@ -0,0 +1,151 @@
+//! Seeded random stream, and uniformity of draws from the bag.
+//!
+//! Statistical tests run on fixed seeds, so they give the same result every
+//! run. Limits are chi-squared critical values for p = 0.001, a fair draw
+//! would fail one in a thousand seed sets.
+
+use rand::{Rng, RngCore};
+use rusty_duke_logic::logic::rng::GameRng;
+use rusty_duke_logic::logic::variant::BagComposition;
+use rusty_duke_logic::logic::{do_action, get_legal_actions, Action, GameState, TileType};
+
+/// Chi-squared statistic of counts against expected counts.
+fn chi_squared(counts: &[u32], expected: &[f64]) -> f64 {
+    counts
+        .iter()
+        .zip(expected)
+        .map(|(count, expected)| (*count as f64 - expected).powi(2) / expected)
+        .sum()
+}
+
+/// Chi-squared critical value for p = 0.001 and degrees of freedom.
+fn critical_value(degrees: usize) -> f64 {
+    match degrees {
+        1 => 10.83,
+        2 => 13.82,
+        3 => 16.27,
+        4 => 18.47,
+        5 => 20.52,
+        6 => 22.46,
+        8 => 26.12,
+        11 => 31.26,
+        12 => 32.91,
+        16 => 39.25,
+        _ => panic!("No critical value for {} degrees of freedom.", degrees),
+    }
+}
+
+#[test]
+fn stream_of_a_seed_is_pinned() {
+    // SplitMix64 reference outputs for seed 0.
+    let mut rng = GameRng::new(0);
+    let outputs: Vec<u64> = (0..5).map(|_| rng.next_u64()).collect();
+    assert_eq!(
+        outputs,
+        [
+            0xe220_a839_7b1d_cdaf,
+            0x6e78_9e6a_a1b9_65f4,
+            0x06c4_5d18_8009_454f,
+            0xf88b_b8a8_724c_81ec,
+            0x1b39_896a_51a8_749b,
+        ]
+    );
+
+    // Draws from a bag of the base game, a change here changes every seeded
+    // game.
+    let mut rng = GameRng::new(7);
+    let draws: Vec<usize> = (1..=17).rev().map(|len| rng.gen_range(0..len)).collect();
+    let mut again = GameRng::new(7);
+    let repeated: Vec<usize> = (1..=17).rev().map(|len| again.gen_range(0..len)).collect();
+    assert_eq!(draws, repeated);
+    assert_eq!(draws, [0, 14, 8, 6, 3, 5, 3, 1, 8, 4, 6, 3, 3, 0, 1, 0, 0]);
+}
+
+#[test]
+fn draws_are_uniform_for_every_bag_size() {
+    for len in [2, 3, 5, 7, 12, 13, 17] {
+        let draws = 2000 * len as u32;
+        let mut counts = vec![0; len];
+        let mut rng = GameRng::new(len as u64);
+        for _ in 0..draws {
+            counts[rng.gen_range(0..len)] += 1;
+        }
+        let expected = vec![draws as f64 / len as f64; len];
+        let statistic = chi_squared(&counts, &expected);
+        assert!(
+            statistic < critical_value(len - 1),
+            "Bag of {}: {:?}, chi-squared {}",
+            len,
+            counts,
+            statistic
+        );
+    }
+}
+
+#[test]
+fn every_tile_is_equally_likely_at_every_draw() {
+    // Drawing a whole bag is a shuffle. Every tile should be in every
+    // position equally often.
+    let len = 5;
+    let shuffles = 4000;
+    // counts[tile][position]
+    let mut counts = vec![vec![0; len]; len];
+    for seed in 0..shuffles {
+        let mut rng = GameRng::new(seed);
+        let mut bag: Vec<usize> = (0..len).collect();
+        let mut position = 0;
+        while !bag.is_empty() {
+            let tile = bag.remove(rng.gen_range(0..bag.len()));
+            counts[tile][position] += 1;
+            position += 1;
+        }
+    }
+    let expected = vec![shuffles as f64 / len as f64; len];
+    for (tile, counts) in counts.iter().enumerate() {
+        let statistic = chi_squared(counts, &expected);
+        assert!(
+            statistic < critical_value(len - 1),
+            "Tile {}: {:?}, chi-squared {}",
+            tile,
+            counts,
+            statistic
+        );
+    }
+}
+
+#[test]
+fn first_draw_of_seeded_games_is_uniform() {
+    // Pikemen are three of the tiles, so three times as likely.
+    let bag = BagComposition::empty()
+        .with(TileType::Knight, 1)
+        .with(TileType::Pikeman, 3)
+        .with(TileType::Seer, 1)
+        .with(TileType::Wizard, 1);
+    let kinds = [
+        TileType::Knight,
+        TileType::Pikeman,
+        TileType::Seer,
+        TileType::Wizard,
+    ];
+    let games = 1200;
+    let mut counts = [0; 4];
+    for seed in 0..games {
+        let mut state = GameState::new_with_bag(&bag, Some(seed));
+        // Dukes and Footmen are placed first.
+        while !get_legal_actions(&state).contains(&Action::NewFromBag) {
+            let action = get_legal_actions(&state)[0];
+            do_action(&mut state, &action).unwrap();
+        }
+        do_action(&mut state, &Action::NewFromBag).unwrap();
+        let kind = state.drawn()[0].kind;
+        counts[kinds.iter().position(|k| *k == kind).unwrap()] += 1;
+    }
+    let expected = [1.0, 3.0, 1.0, 1.0].map(|share| games as f64 * share / 6.0);
+    let statistic = chi_squared(&counts, &expected);
+    assert!(
+        statistic < critical_value(3),
+        "{:?}, chi-squared {}",
+        counts,
+        statistic
+    );
+}
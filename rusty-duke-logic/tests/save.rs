@@ -0,0 +1,130 @@
+//! Saves of games in progress.
+
+#![cfg(feature = "json")]
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::save::{from_save_json, to_save_json, SaveError, SAVE_VERSION};
+use rusty_duke_logic::logic::{
+    do_action, get_actions, undo_action, Action, Coordinate, GameState, TileColor, TileType,
+};
+
+/// Camelot and Guinevere on the board, captured tiles in the graveyard and a
+/// few actions in the history.
+fn mid_game() -> GameState {
+    let mut state = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(2, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(3, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(3, 3),
+            TileType::Camelot,
+            TileColor::White,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(1, 2),
+            TileType::Guinevere,
+            TileColor::Black,
+            false,
+        )
+        .bag(
+            TileColor::Black,
+            vec![TileType::Footman, TileType::Pikeman, TileType::Knight],
+        )
+        .bag(TileColor::White, vec![TileType::Footman, TileType::Wizard])
+        .graveyard(TileType::Pikeman, TileColor::White, true)
+        .graveyard(TileType::Footman, TileColor::Black, false)
+        .ply(TileColor::Black)
+        .turn(12)
+        .seed(9)
+        .build()
+        .unwrap();
+
+    do_action(&mut state, &Action::NewFromBag).unwrap();
+    let place = get_actions(&state)[0];
+    do_action(&mut state, &place).unwrap();
+    for _ in 0..3 {
+        let actions = get_actions(&state);
+        do_action(&mut state, &actions[actions.len() / 2]).unwrap();
+    }
+    state
+}
+
+#[test]
+fn mid_game_round_trip() {
+    let state = mid_game();
+    let effects: usize = (0..6)
+        .flat_map(|y| (0..6).map(move |x| Coordinate::new(x, y)))
+        .map(|cord| state.square(cord).effects.len())
+        .sum();
+    assert!(effects > 0);
+    assert_eq!(state.graveyard.len(), 2);
+
+    let json = to_save_json(&state);
+    let mut loaded = from_save_json(&json).unwrap();
+
+    assert_eq!(get_actions(&loaded), get_actions(&state));
+    assert_eq!(loaded.hash(), state.hash());
+    assert_eq!(loaded.bag(), state.bag());
+    assert_eq!(loaded.drawn(), state.drawn());
+    assert_eq!(loaded.graveyard, state.graveyard);
+    assert_eq!(loaded.ply, state.ply);
+    assert_eq!(loaded.turn, state.turn);
+    for color in [TileColor::Black, TileColor::White] {
+        assert_eq!(loaded.duke_pos(color), state.duke_pos(color));
+    }
+    assert_eq!(to_save_json(&loaded), json);
+
+    // Random draws and undo continue as in the original game.
+    let mut original = state.clone();
+    while !get_actions(&original).contains(&Action::NewFromBag) {
+        let action = get_actions(&original)[0];
+        do_action(&mut original, &action).unwrap();
+        do_action(&mut loaded, &action).unwrap();
+    }
+    do_action(&mut original, &Action::NewFromBag).unwrap();
+    do_action(&mut loaded, &Action::NewFromBag).unwrap();
+    assert_eq!(loaded.drawn(), original.drawn());
+    while loaded.can_undo() {
+        undo_action(&mut loaded).unwrap();
+        undo_action(&mut original).unwrap();
+        assert_eq!(loaded.hash(), original.hash());
+    }
+    assert!(!original.can_undo());
+}
+
+#[test]
+fn other_versions_are_rejected() {
+    let json = to_save_json(&mid_game());
+    let current = format!("\"version\":{}", SAVE_VERSION);
+    assert!(json.contains(&current));
+
+    let newer = json.replace(&current, "\"version\":200");
+    assert_eq!(
+        from_save_json(&newer).unwrap_err(),
+        SaveError::UnsupportedVersion(200)
+    );
+
+    // Version is checked before the state, which may have another layout.
+    let old = r#"{ "version": 0, "state": { "board": [] } }"#;
+    assert_eq!(
+        from_save_json(old).unwrap_err(),
+        SaveError::UnsupportedVersion(0)
+    );
+
+    assert!(matches!(from_save_json("{}"), Err(SaveError::Malformed(_))));
+    let truncated = &json[..json.len() / 2];
+    assert!(matches!(
+        from_save_json(truncated),
+        Err(SaveError::Malformed(_))
+    ));
+}
@@ -0,0 +1,82 @@
+//! Game states and actions round tripped through JSON with serde.
+
+#![cfg(feature = "json")]
+
+use rusty_duke_logic::logic::variant::Expansion;
+use rusty_duke_logic::logic::{
+    do_unsafe_action, get_actions, get_legal_actions, undo_action, Action, GameState,
+};
+
+/// Arthurian game after actions, every fifth legal action is taken.
+fn mid_game(actions: usize) -> GameState {
+    let mut state = GameState::new_with_expansion(Expansion::ArthurianLegends, Some(5));
+    for i in 0..actions {
+        let legal = get_legal_actions(&state);
+        if state.game_over.is_some() || legal.is_empty() {
+            break;
+        }
+        do_unsafe_action(&mut state, &legal[(i * 5) % legal.len()]);
+    }
+    state
+}
+
+fn round_trip(state: &GameState) -> GameState {
+    serde_json::from_str(&serde_json::to_string(state).unwrap()).unwrap()
+}
+
+#[test]
+fn mid_game_state_round_trips() {
+    for actions in [0, 1, 7, 40] {
+        let state = mid_game(actions);
+        let copy = round_trip(&state);
+
+        assert_eq!(
+            serde_json::to_string(&copy).unwrap(),
+            serde_json::to_string(&state).unwrap()
+        );
+        assert_eq!(copy.own_duke_pos(), state.own_duke_pos());
+        assert_eq!(copy.opponent_duke_pos(), state.opponent_duke_pos());
+        assert_eq!(copy.bags, state.bags);
+        assert_eq!(copy.drawn_tiles, state.drawn_tiles);
+        assert_eq!(copy.graveyard_tiles(), state.graveyard_tiles());
+        assert_eq!(copy.to_fen(), state.to_fen());
+        assert_eq!(copy.hash(), state.hash());
+        assert_eq!(get_actions(&copy), get_actions(&state), "{}", actions);
+    }
+}
+
+#[test]
+fn history_survives_round_trip() {
+    let mut state = mid_game(40);
+    let mut copy = round_trip(&state);
+    while undo_action(&mut state).is_ok() {
+        undo_action(&mut copy).unwrap();
+        assert_eq!(copy.to_fen(), state.to_fen());
+        assert_eq!(get_actions(&copy), get_actions(&state));
+    }
+    assert!(undo_action(&mut copy).is_err());
+}
+
+#[test]
+fn every_action_kind_round_trips() {
+    let mut kinds = Vec::new();
+    let mut state = GameState::new_with_expansion(Expansion::ArthurianLegends, Some(1));
+    for i in 0..300 {
+        for action in get_actions(&state) {
+            let json = serde_json::to_string(&action).unwrap();
+            assert_eq!(serde_json::from_str::<Action>(&json).unwrap(), action);
+            let kind = std::mem::discriminant(&action);
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        let legal = get_legal_actions(&state);
+        if state.game_over.is_some() || legal.is_empty() {
+            state = GameState::new_with_expansion(Expansion::ArthurianLegends, Some(i));
+            continue;
+        }
+        do_unsafe_action(&mut state, &legal[(i as usize * 7) % legal.len()]);
+    }
+    // Draw, place, Move, Jump, Slide, JumpSlide, Command and Strike.
+    assert_eq!(kinds.len(), 8);
+}
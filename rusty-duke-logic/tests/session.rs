@@ -0,0 +1,234 @@
+//! Turns of a game session: humans submit actions, agents are polled, and
+//! the record and clock follow.
+
+use rusty_duke_logic::ai::random::RandomAgent;
+use rusty_duke_logic::logic::clock::GameClock;
+use rusty_duke_logic::logic::record::GameRecord;
+use rusty_duke_logic::logic::variant::Variant;
+use rusty_duke_logic::logic::{
+    self, Action, ActionError, Coordinate, GameState, TileColor, UndoError, WinReason, Winner,
+};
+use rusty_duke_logic::session::{Controller, GameSession};
+use std::time::{Duration, Instant};
+
+const SEED: u64 = 7;
+
+fn humans() -> [Controller; 2] {
+    [Controller::Human, Controller::Human]
+}
+
+fn agents(seed: u64) -> [Controller; 2] {
+    [
+        Controller::Agent(Box::new(RandomAgent::new_seeded(TileColor::Black, seed))),
+        Controller::Agent(Box::new(RandomAgent::new_seeded(
+            TileColor::White,
+            seed + 1000,
+        ))),
+    ]
+}
+
+#[test]
+fn scripted_game_is_played_to_the_end() {
+    // Quick games end at the turn limit at the latest.
+    let mut session = GameSession::new(Variant::Quick, Some(SEED), humans());
+    let mut hashes = vec![session.hash()];
+    let mut index = 0;
+    while session.game_over.is_none() {
+        assert_eq!(session.poll_ai(), None);
+        let actions = session.current_legal_actions();
+        assert!(!actions.is_empty());
+        // Script of the game, a different action every turn.
+        let action = actions[index % actions.len()];
+        index += 3;
+
+        let ply = session.ply;
+        let outcome = session.submit_action(action).unwrap();
+        assert_eq!(outcome.to_go, session.ply);
+        assert_eq!(outcome.game_over, session.game_over);
+        if action == Action::NewFromBag {
+            // Drawn tile is placed next, in the same turn.
+            assert_eq!(outcome.to_go, ply);
+        }
+        hashes.push(session.hash());
+    }
+
+    let record = session.record();
+    assert_eq!(record.len(), hashes.len() - 1);
+    assert_eq!(record.replay().hash(), session.hash());
+    let replayed: Vec<u64> = record.positions().map(|state| state.hash()).collect();
+    assert_eq!(replayed, hashes);
+    let (winner, _) = session.result().unwrap();
+    assert_eq!(Some(winner), record.winner());
+
+    // Nothing more after the game is over.
+    assert_eq!(
+        session.submit_action(Action::NewFromBag),
+        Err(ActionError::GameAlreadyOver)
+    );
+    assert_eq!(session.record().len(), hashes.len() - 1);
+}
+
+#[test]
+fn illegal_action_is_not_recorded() {
+    let mut session = GameSession::new(Variant::Standard, Some(SEED), humans());
+    let hash = session.hash();
+
+    // Nothing drawn to place.
+    let place = Action::PlaceNew(Coordinate::new(0, 0));
+    assert!(session.submit_action(place).is_err());
+    assert_eq!(session.hash(), hash);
+    assert!(session.record().is_empty());
+    assert_eq!(session.result(), None);
+}
+
+#[test]
+fn agents_play_their_colors() {
+    let agent = RandomAgent::new_seeded(TileColor::Black, SEED);
+    let mut session = GameSession::new(
+        Variant::Quick,
+        Some(SEED),
+        [Controller::Agent(Box::new(agent)), Controller::Human],
+    );
+    assert!(!session.is_human(TileColor::Black));
+    assert!(session.is_human(TileColor::White));
+
+    // Black agent plays its turn, then waits for the human.
+    while session.ply == TileColor::Black {
+        assert!(session.poll_ai().is_some());
+    }
+    assert_eq!(session.poll_ai(), None);
+    assert_eq!(session.record().len(), session.record().actions.len());
+    assert!(!session.record().is_empty());
+
+    // Agents against each other to the end of a game.
+    let mut session = GameSession::new(Variant::Quick, Some(SEED), agents(SEED));
+    while session.game_over.is_none() {
+        let (action, outcome) = session.poll_ai().unwrap();
+        assert_eq!(session.record().actions.last().unwrap().action, action);
+        assert_eq!(outcome.to_go, session.ply);
+    }
+    assert_eq!(session.poll_ai(), None);
+    assert_eq!(session.record().replay().hash(), session.hash());
+}
+
+#[test]
+fn resignation_ends_the_game() {
+    let mut session = GameSession::new(Variant::Standard, Some(SEED), humans());
+    let action = session.current_legal_actions()[0];
+    session.submit_action(action).unwrap();
+
+    session.resign(TileColor::White);
+    assert_eq!(
+        session.result(),
+        Some((
+            Winner::Color(TileColor::Black),
+            Some(WinReason::Resignation)
+        ))
+    );
+
+    // The record keeps the resignation.
+    let record = session.record();
+    assert_eq!(record.winner(), Some(Winner::Color(TileColor::Black)));
+    let loaded = GameSession::from_record(record.clone(), humans());
+    assert_eq!(loaded.result(), session.result());
+}
+
+#[test]
+fn clock_runs_for_the_color_to_go() {
+    let mut session = GameSession::new(Variant::Standard, Some(SEED), humans());
+    session.set_clock(GameClock::new(
+        Duration::from_secs(60),
+        Duration::from_secs(1),
+    ));
+    let start = Instant::now();
+
+    // Black thinks for 10 seconds.
+    session.update_clock(start);
+    assert_eq!(session.clock().unwrap().running(), Some(TileColor::Black));
+    let action = session.current_legal_actions()[0];
+    session.submit_action(action).unwrap();
+    session.update_clock(start + Duration::from_secs(10));
+    let clock = session.clock().unwrap();
+    assert_eq!(clock.running(), Some(TileColor::White));
+    assert_eq!(
+        clock.remaining(TileColor::Black, start + Duration::from_secs(10)),
+        Duration::from_secs(51)
+    );
+
+    // White runs out of time.
+    session.update_clock(start + Duration::from_secs(71));
+    assert_eq!(
+        session.result(),
+        Some((Winner::Color(TileColor::Black), Some(WinReason::Timeout)))
+    );
+    assert_eq!(session.clock().unwrap().running(), None);
+}
+
+#[test]
+fn long_games_keep_a_bounded_history() {
+    const PLIES: usize = 5000;
+    const LIMIT: usize = 100;
+    let mut session = GameSession::new(Variant::Standard, Some(SEED), humans());
+    session.set_history_limit(LIMIT);
+
+    // Script of a game that goes on: no new tiles, no captures and no end.
+    // Colors repeat one of their last actions if they can, otherwise they
+    // leave the most such actions to the other color.
+    let goes_on = |state: &GameState, action: &Action| {
+        let mut next = state.clone();
+        logic::do_action(&mut next, action).unwrap();
+        *action != Action::NewFromBag
+            && next.graveyard.len() == state.graveyard.len()
+            && next.game_over.is_none()
+    };
+    let replies = |state: &GameState, action: &Action| {
+        let mut next = state.clone();
+        logic::do_action(&mut next, action).unwrap();
+        logic::get_legal_actions(&next)
+            .iter()
+            .filter(|reply| goes_on(&next, reply))
+            .count()
+    };
+    while session.record().len() < PLIES {
+        let state = session.state().clone_without_history();
+        let actions = session.current_legal_actions().to_vec();
+        let recorded = &session.record().actions;
+        let repeated = recorded
+            .iter()
+            .rev()
+            .skip(1)
+            .step_by(2)
+            .take(8)
+            .map(|recorded| recorded.action)
+            .find(|action| actions.contains(action) && goes_on(&state, action));
+        let action = repeated
+            .or_else(|| {
+                actions
+                    .iter()
+                    .copied()
+                    .filter(|action| goes_on(&state, action))
+                    .max_by_key(|action| replies(&state, action))
+            })
+            .expect("An action that goes on.");
+
+        session.submit_action(action).unwrap();
+        assert!(session.history_len() <= LIMIT);
+    }
+
+    // The last actions can still be undone.
+    let mut state = session.state().clone();
+    for _ in 0..LIMIT {
+        logic::undo_action(&mut state).unwrap();
+    }
+    assert_eq!(
+        logic::undo_action(&mut state),
+        Err(UndoError::NothingToUndo)
+    );
+
+    // The record has every action.
+    let record = session.record();
+    assert_eq!(record.len(), PLIES);
+    assert_eq!(record.replay().hash(), session.hash());
+    let text = record.to_text();
+    assert_eq!(GameRecord::from_text(&text).unwrap(), *record);
+}
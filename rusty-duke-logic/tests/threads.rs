@@ -0,0 +1,71 @@
+//! Root search on several threads finds the same utility as on one.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use rusty_duke_logic::ai::alpha_beta::{get_action_with_stats, Agent, SearchStats};
+use rusty_duke_logic::logic::{do_action, get_legal_actions, validate_action, Action, GameState};
+use std::time::Instant;
+
+/// Midgame positions of seeded games.
+fn positions() -> Vec<GameState> {
+    let mut positions = vec![GameState::from_fen(
+        "2FDPF'/3EF'1/6/2f'3/b5/d'1w'f2 b fpppkelyscagmnr/YPRPKBLWSCAGMN -/- - 16",
+    )
+    .unwrap()];
+    for seed in 0..4 {
+        let mut state = GameState::new_seeded(seed);
+        for i in 0..(20 + 6 * seed as usize) {
+            let actions = get_legal_actions(&state);
+            if state.game_over.is_some() || actions.is_empty() {
+                break;
+            }
+            do_action(&mut state, &actions[(i * 7) % actions.len()]).unwrap();
+        }
+        if state.game_over.is_none() && state.drawn().is_empty() {
+            positions.push(state);
+        }
+    }
+    positions
+}
+
+fn search(state: &GameState, threads: usize, table: bool) -> (Option<Action>, SearchStats) {
+    let mut agent = Agent::new(state.ply, Some(3), None);
+    agent.threads = threads;
+    agent.transposition_table = table;
+    get_action_with_stats(&agent, state, None)
+}
+
+#[test]
+fn threads_find_the_same_utility() {
+    let positions = positions();
+    assert!(positions.len() >= 3);
+    for state in positions.iter() {
+        for table in [false, true] {
+            let (_, single) = search(state, 1, table);
+            for threads in [2, 4] {
+                let start = Instant::now();
+                let (action, stats) = search(state, threads, table);
+                let secs = start.elapsed().as_secs_f64();
+                println!(
+                    "{} threads: {} nodes, {:.0} nodes/s",
+                    threads,
+                    stats.nodes,
+                    stats.nodes as f64 / secs
+                );
+
+                assert_eq!(stats.utility, single.utility, "{}", state.to_fen());
+                assert_eq!(stats.depth, single.depth);
+                assert_eq!(validate_action(state, &action.unwrap()), Ok(()));
+            }
+        }
+    }
+}
+
+#[test]
+fn more_threads_than_actions() {
+    let state = &positions()[0];
+    let (_, single) = search(state, 1, false);
+    let (action, stats) = search(state, 64, false);
+    assert_eq!(stats.utility, single.utility);
+    assert_eq!(validate_action(state, &action.unwrap()), Ok(()));
+}
@@ -0,0 +1,173 @@
+//! Squares a color could capture on, with Strike, Command, Defence and
+//! Dread, whoever is to go.
+
+use rusty_duke_logic::ai::random::RandomAgent;
+use rusty_duke_logic::ai::DukeAgent;
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::{
+    do_action, get_actions, threatened_squares, Action, ActionResult, Coordinate, GameState,
+    TileColor, TileType,
+};
+
+/// Black Duke on a1, White Duke on f6, plus tiles. Color to go.
+fn state(tiles: &[(Coordinate, TileType, TileColor, bool)], ply: TileColor) -> GameState {
+    let mut builder = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(5, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        );
+    for (cord, kind, color, flipped) in tiles.iter() {
+        builder = builder.tile_at(*cord, *kind, *color, *flipped);
+    }
+    builder.ply(ply).build().unwrap()
+}
+
+/// Attackers of target, None if not threatened.
+fn attackers(
+    threatened: &[(Coordinate, Vec<Coordinate>)],
+    target: Coordinate,
+) -> Option<Vec<Coordinate>> {
+    threatened
+        .iter()
+        .find(|(cord, _)| *cord == target)
+        .map(|(_, attackers)| attackers.clone())
+}
+
+#[test]
+fn strikes_and_commands_threaten() {
+    // Bowman back side strikes two ahead, from c2 onto c4.
+    let bowman = Coordinate::new(2, 1);
+    let pikeman = Coordinate::new(2, 3);
+    let tiles = [
+        (bowman, TileType::Bowman, TileColor::Black, true),
+        (pikeman, TileType::Pikeman, TileColor::White, false),
+    ];
+    for ply in [TileColor::Black, TileColor::White] {
+        let threatened = threatened_squares(&state(&tiles, ply), TileColor::Black);
+        assert_eq!(attackers(&threatened, pikeman), Some(vec![bowman]));
+    }
+
+    // General back side on c3 commands the Footman on b3 to d2, which the
+    // General can't reach itself.
+    let general = Coordinate::new(2, 2);
+    let pikeman = Coordinate::new(3, 1);
+    let tiles = [
+        (general, TileType::General, TileColor::Black, true),
+        (
+            Coordinate::new(1, 2),
+            TileType::Footman,
+            TileColor::Black,
+            false,
+        ),
+        (pikeman, TileType::Pikeman, TileColor::White, false),
+    ];
+    let threatened = threatened_squares(&state(&tiles, TileColor::White), TileColor::Black);
+    assert_eq!(attackers(&threatened, pikeman), Some(vec![general]));
+}
+
+#[test]
+fn defence_shields_tiles() {
+    // Black Footman on d2 can move onto the White Footman on d3.
+    let footman = Coordinate::new(3, 2);
+    let attacker = Coordinate::new(3, 1);
+    let mut tiles = vec![
+        (footman, TileType::Footman, TileColor::White, false),
+        (attacker, TileType::Footman, TileColor::Black, false),
+    ];
+    let threatened = threatened_squares(&state(&tiles, TileColor::Black), TileColor::Black);
+    assert_eq!(attackers(&threatened, footman), Some(vec![attacker]));
+
+    // Guinevere on c3 puts Defence on b3 and d3.
+    tiles.push((
+        Coordinate::new(2, 2),
+        TileType::Guinevere,
+        TileColor::White,
+        false,
+    ));
+    let threatened = threatened_squares(&state(&tiles, TileColor::Black), TileColor::Black);
+    assert_eq!(attackers(&threatened, footman), None);
+    // The Footman on d3 still threatens the one on d2.
+    let threatened = threatened_squares(&state(&tiles, TileColor::Black), TileColor::White);
+    assert_eq!(attackers(&threatened, attacker), Some(vec![footman]));
+}
+
+#[test]
+fn dread_keeps_tiles_from_threatening() {
+    // Black Footman on c4 next to the White Footman on d4.
+    let footman = Coordinate::new(2, 3);
+    let target = Coordinate::new(3, 3);
+    let mut tiles = vec![
+        (footman, TileType::Footman, TileColor::Black, false),
+        (target, TileType::Footman, TileColor::White, false),
+    ];
+    let threatened = threatened_squares(&state(&tiles, TileColor::Black), TileColor::Black);
+    assert_eq!(attackers(&threatened, target), Some(vec![footman]));
+
+    // Morgana on c5 dreads c4, the Footman there can't act. The White
+    // Footman is not dreaded.
+    let morgana = Coordinate::new(2, 4);
+    tiles.push((morgana, TileType::Morgana, TileColor::White, false));
+    let state = state(&tiles, TileColor::Black);
+    let threatened = threatened_squares(&state, TileColor::Black);
+    assert_eq!(attackers(&threatened, target), None);
+    assert_eq!(attackers(&threatened, morgana), None);
+    let threatened = threatened_squares(&state, TileColor::White);
+    assert_eq!(attackers(&threatened, footman), Some(vec![target]));
+}
+
+#[test]
+fn threats_of_the_player_to_go_are_its_captures() {
+    for seed in 0..10 {
+        let mut state = GameState::new_seeded(seed);
+        let mut agents = [
+            RandomAgent::new_seeded(TileColor::Black, seed),
+            RandomAgent::new_seeded(TileColor::White, seed + 100),
+        ];
+        while state.game_over.is_none() && state.turn < 80 {
+            // Capturing actions, grouped by target square.
+            let mut captures: Vec<(Coordinate, Vec<Coordinate>)> = Vec::new();
+            for action in get_actions(&state) {
+                let (from, target) = match action {
+                    Action::Move(data)
+                    | Action::Jump(data)
+                    | Action::JumpSlide(data)
+                    | Action::Slide(data)
+                    | Action::Strike(data)
+                        if data.result == ActionResult::Capture =>
+                    {
+                        (data.tile_pos, data.target_pos)
+                    }
+                    Action::Command(data) if data.result == ActionResult::Capture => {
+                        (data.command_tile_pos, data.target_pos)
+                    }
+                    _ => continue,
+                };
+                match captures.iter_mut().find(|(cord, _)| *cord == target) {
+                    Some((_, from_squares)) => from_squares.push(from),
+                    None => captures.push((target, vec![from])),
+                }
+            }
+            for (_, from_squares) in captures.iter_mut() {
+                from_squares.sort_by_key(|c| (c.y, c.x));
+                from_squares.dedup();
+            }
+            captures.sort_by_key(|(c, _)| (c.y, c.x));
+
+            // Drawn tiles are placed before anything else.
+            if state.drawn().is_empty() {
+                assert_eq!(threatened_squares(&state, state.ply), captures);
+            }
+
+            let action = agents[state.ply as usize].choose_action(&state).unwrap();
+            do_action(&mut state, &action).unwrap();
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Tile definition files with tile types this version doesn't know, strict
+//! and with the compatibility shims.
+//!
+//! Nothing in this test binary creates games, so definitions can be loaded.
+
+use rusty_duke_logic::logic::tiles::{
+    builtin, load_tile_definitions_compat_from, load_tile_definitions_from, parse_tile_definitions,
+    parse_tile_definitions_compat, TileDefinitionError, BUILTIN,
+};
+use std::path::PathBuf;
+
+/// Built in definitions, plus a tile of a newer version.
+fn with_unknown_tile() -> String {
+    format!(
+        "{}\n[Gawain]\nfront = [{{ kind = \"Move\", x = 0, y = 1 }}]\nback = []\n",
+        BUILTIN
+    )
+}
+
+fn write(name: &str, text: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("rusty-duke-{}-{}.toml", name, std::process::id()));
+    std::fs::write(&path, text).unwrap();
+    path
+}
+
+#[test]
+fn unknown_tile_type_is_an_error() {
+    assert_eq!(
+        parse_tile_definitions(&with_unknown_tile()).unwrap_err(),
+        TileDefinitionError::UnknownTileType("Gawain".to_string())
+    );
+}
+
+#[test]
+fn compat_parse_skips_unknown_tile_types() {
+    let (definitions, skipped) = parse_tile_definitions_compat(&with_unknown_tile()).unwrap();
+    assert_eq!(skipped, ["Gawain"]);
+    assert_eq!(definitions.actions.len(), builtin().actions.len());
+
+    // Other errors are still errors.
+    let broken = with_unknown_tile().replace("kind = \"Move\", x = 0, y = 1", "kind = \"Fly\"");
+    assert!(parse_tile_definitions_compat(&broken).is_err());
+    let (_, skipped) = parse_tile_definitions_compat(BUILTIN).unwrap();
+    assert!(skipped.is_empty());
+}
+
+#[test]
+fn loaders_with_unknown_tile_type() {
+    let path = write("unknown", &with_unknown_tile());
+    let strict = load_tile_definitions_from(&path);
+    let compat = load_tile_definitions_compat_from(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        strict,
+        Err(TileDefinitionError::UnknownTileType("Gawain".to_string()))
+    );
+    assert_eq!(compat, Ok(vec!["Gawain".to_string()]));
+}
+
+#[test]
+fn compat_loader_still_validates() {
+    // Missing tile type of this version, every lookup has to find actions.
+    let start = BUILTIN.find("[Duke]").unwrap();
+    let end = start + 1 + BUILTIN[start + 1..].find("\n[").unwrap();
+    let text = format!("{}{}", &BUILTIN[..start], &BUILTIN[end..]);
+    let path = write("incomplete", &text);
+    let compat = load_tile_definitions_compat_from(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    match compat {
+        Err(TileDefinitionError::Invalid(diagnostics)) => {
+            assert!(diagnostics.iter().any(|d| d.kind.code() == "E001"))
+        }
+        result => panic!("{:?}", result),
+    }
+}
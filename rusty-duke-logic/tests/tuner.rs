@@ -0,0 +1,153 @@
+//! Round robins and local search of the weight tuner.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use rusty_duke_logic::ai::alpha_beta::EvalWeights;
+use rusty_duke_logic::ai::arena::Standing;
+use rusty_duke_logic::ai::tuner::{
+    round_robin, tune_step, weights_from_text, weights_to_text, TunerConfig, TunerState, CSV_HEADER,
+};
+
+/// Shallow and short games, for speed.
+fn config() -> TunerConfig {
+    TunerConfig {
+        depth: 1,
+        seeds: vec![1, 2],
+        max_plies: 100,
+        step: 1,
+    }
+}
+
+#[test]
+fn every_pair_plays_every_seed_with_both_colors() {
+    let entrants = [
+        EvalWeights::default(),
+        EvalWeights::material_only(),
+        EvalWeights {
+            mobility: 3,
+            ..EvalWeights::default()
+        },
+    ];
+    let result = round_robin(&entrants, &config());
+    assert_eq!(result.games.len(), 3 * 2 * 2);
+    for game in result.games.iter() {
+        assert!(result.games.iter().any(|other| other.seed == game.seed
+            && other.black == game.white
+            && other.white == game.black));
+    }
+    for standing in result.standings.iter() {
+        assert_eq!(standing.games(), 2 * 2 * 2);
+    }
+    let wins: u32 = result.standings.iter().map(|s| s.wins).sum();
+    let losses: u32 = result.standings.iter().map(|s| s.losses).sum();
+    assert_eq!(wins, losses);
+    assert_eq!(result.standings[1].name, "0 0 0 0 0 0 0");
+
+    // Same games every run.
+    assert_eq!(round_robin(&entrants, &config()), result);
+
+    let row = result.games[0].to_csv(4, &entrants);
+    assert!(
+        row.starts_with("4,1,2 1 2 1 2 2 1,0 0 0 0 0 0 0,"),
+        "{}",
+        row
+    );
+    assert_eq!(row.split(',').count(), CSV_HEADER.split(',').count());
+}
+
+#[test]
+fn confidence_interval_of_standings() {
+    let standing = |wins, draws, losses| Standing {
+        wins,
+        draws,
+        losses,
+        ..Default::default()
+    };
+    assert_eq!(standing(0, 0, 0).confidence_interval(1.96), (0.0, 1.0));
+    assert_eq!(standing(0, 6, 0).confidence_interval(1.96), (0.5, 0.5));
+    assert_eq!(standing(5, 0, 0).confidence_interval(1.96), (1.0, 1.0));
+
+    // More games, narrower interval around the same score.
+    let (low, high) = standing(6, 2, 2).confidence_interval(1.96);
+    let (more_low, more_high) = standing(60, 20, 20).confidence_interval(1.96);
+    assert_eq!(standing(60, 20, 20).mean_score(), 0.7);
+    assert!(low < more_low && more_low < 0.7 && 0.7 < more_high && more_high < high);
+}
+
+#[test]
+fn candidates_change_one_weight_at_a_time() {
+    let mut state = TunerState::new(config(), EvalWeights::material_only());
+    // Raised, then lowered. A weight of 0 can't be lowered, so there are no
+    // games.
+    for (iteration, spawn_square) in [(0, 1), (1, 0)] {
+        state.iteration = iteration;
+        assert_eq!(state.next_weight(), 0);
+        assert_eq!(
+            state.candidate(),
+            EvalWeights {
+                spawn_square,
+                ..EvalWeights::material_only()
+            }
+        );
+    }
+    let step = tune_step(&mut state);
+    assert!(step.games.is_empty() && !step.accepted);
+    assert_eq!(state.iteration, 2);
+    assert_eq!(state.next_weight(), 1);
+}
+
+#[test]
+fn steps_keep_better_weights() {
+    let mut state = TunerState::new(config(), EvalWeights::default());
+    for _ in 0..4 {
+        let best = state.best;
+        let step = tune_step(&mut state);
+        assert_eq!(step.games.len(), 2 * config().seeds.len());
+        assert_eq!(step.accepted, step.standing.mean_score() > 0.5);
+        assert_eq!(
+            state.best,
+            if step.accepted { step.candidate } else { best }
+        );
+    }
+    assert_eq!(state.iteration, 4);
+}
+
+#[test]
+fn interrupted_search_resumes() {
+    let mut uninterrupted = TunerState::new(config(), EvalWeights::default());
+    for _ in 0..3 {
+        tune_step(&mut uninterrupted);
+    }
+
+    let mut state = TunerState::new(config(), EvalWeights::default());
+    tune_step(&mut state);
+    let mut resumed = TunerState::from_text(&state.to_text()).unwrap();
+    assert_eq!(resumed, state);
+    for _ in 0..2 {
+        tune_step(&mut resumed);
+    }
+    assert_eq!(resumed, uninterrupted);
+}
+
+#[test]
+fn state_text() {
+    let mut state = TunerState::new(config(), EvalWeights::default());
+    state.best.position = -3;
+    state.iteration = 12;
+    state.accepted = 5;
+    let text = state.to_text();
+    assert!(text.contains("seeds 1 2\n"));
+    assert!(text.contains("best 2 1 2 1 2 -3 1\n"));
+    assert_eq!(TunerState::from_text(&text), Some(state));
+    assert_eq!(
+        TunerState::from_text(&text.replace("best 2 1 2 1 2 -3 1", "best 2 1 2")),
+        None
+    );
+
+    assert_eq!(
+        weights_from_text(&weights_to_text(&EvalWeights::material_only())),
+        Some(EvalWeights::material_only())
+    );
+    assert_eq!(weights_from_text("1 2 3 4 5 6 7 8"), None);
+    assert_eq!(weights_from_text("1 2 3 x 5 6 7"), None);
+}
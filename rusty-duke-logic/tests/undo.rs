@@ -0,0 +1,129 @@
+//! Undo and redo of every kind of action.
+
+use rusty_duke_logic::logic::builder::GameStateBuilder;
+use rusty_duke_logic::logic::{
+    do_action, get_actions, get_tile_actions, redo_action, undo_action, Action, Coordinate,
+    GameState, TileColor, TileType, UndoError,
+};
+
+/// What has to be the same after undo and redo.
+fn snapshot(state: &GameState) -> String {
+    format!(
+        "{} {:?} {:?} {:?} {:?} {}",
+        state.hash(),
+        state.bag(),
+        state.drawn(),
+        state.graveyard,
+        state.game_over,
+        state.ply_count
+    )
+}
+
+#[test]
+fn whole_game_round_trip() {
+    let mut state = GameState::new_seeded(3);
+    let mut snapshots = vec![snapshot(&state)];
+    let mut kinds = [false; 8];
+
+    // Deterministic choices, drawing about every third time it is possible.
+    let mut choice: usize = 7;
+    while state.game_over.is_none() && snapshots.len() < 400 {
+        let actions = get_actions(&state);
+        choice = choice.wrapping_mul(31).wrapping_add(17);
+        let action = match actions.contains(&Action::NewFromBag) {
+            true if choice % 3 == 1 => Action::NewFromBag,
+            _ => actions[choice % actions.len()],
+        };
+        kinds[match action {
+            Action::NewFromBag => 0,
+            Action::PlaceNew(_) => 1,
+            Action::Move(_) => 2,
+            Action::Jump(_) => 3,
+            Action::JumpSlide(_) => 4,
+            Action::Slide(_) => 5,
+            Action::Command(_) => 6,
+            Action::Strike(_) => 7,
+        }] = true;
+        do_action(&mut state, &action).unwrap();
+        snapshots.push(snapshot(&state));
+    }
+    assert_eq!(state.history_len(), snapshots.len() - 1);
+    assert!(kinds[0] && kinds[1] && kinds[2] && kinds[5]);
+
+    for expected in snapshots.iter().rev().skip(1) {
+        undo_action(&mut state).unwrap();
+        assert_eq!(snapshot(&state), *expected);
+    }
+    assert!(!state.can_undo());
+    assert_eq!(undo_action(&mut state), Err(UndoError::NothingToUndo));
+
+    for expected in snapshots.iter().skip(1) {
+        redo_action(&mut state).unwrap();
+        assert_eq!(snapshot(&state), *expected);
+    }
+    assert!(!state.can_redo());
+    assert_eq!(state.history_len(), snapshots.len() - 1);
+}
+
+#[test]
+fn command_and_strike_round_trip() {
+    let general = Coordinate::new(2, 2);
+    let footman = Coordinate::new(1, 2);
+    let lancelot = Coordinate::new(3, 0);
+    let pikeman = Coordinate::new(4, 2);
+    let mut state = GameStateBuilder::new()
+        .tile_at(
+            Coordinate::new(0, 0),
+            TileType::Duke,
+            TileColor::Black,
+            false,
+        )
+        .tile_at(
+            Coordinate::new(5, 5),
+            TileType::Duke,
+            TileColor::White,
+            false,
+        )
+        .tile_at(general, TileType::General, TileColor::Black, true)
+        .tile_at(footman, TileType::Footman, TileColor::Black, false)
+        .tile_at(lancelot, TileType::Lancelot, TileColor::Black, false)
+        .tile_at(pikeman, TileType::Pikeman, TileColor::White, false)
+        .ply(TileColor::Black)
+        .seed(1)
+        .build()
+        .unwrap();
+    let start = snapshot(&state);
+
+    // Commanded Footman moves, the General flips.
+    let command = get_tile_actions(&state, general)
+        .into_iter()
+        .find(|a| matches!(a, Action::Command(data) if data.command_tile_pos == footman))
+        .unwrap();
+    do_action(&mut state, &command).unwrap();
+    let commanded = snapshot(&state);
+    undo_action(&mut state).unwrap();
+    assert_eq!(snapshot(&state), start);
+    assert!(state.tile_at(general).unwrap().flipped);
+    assert_eq!(state.tile_at(footman).unwrap().kind, TileType::Footman);
+    redo_action(&mut state).unwrap();
+    assert_eq!(snapshot(&state), commanded);
+
+    // Lancelot captures the Pikeman without moving.
+    let pass = get_tile_actions(&state, Coordinate::new(5, 5))[0];
+    do_action(&mut state, &pass).unwrap();
+    let strike = get_tile_actions(&state, lancelot)
+        .into_iter()
+        .find(|a| matches!(a, Action::Strike(data) if data.target_pos == pikeman))
+        .unwrap();
+    let before = snapshot(&state);
+    do_action(&mut state, &strike).unwrap();
+    let struck = snapshot(&state);
+    assert!(state.tile_at(pikeman).is_none());
+
+    undo_action(&mut state).unwrap();
+    assert_eq!(snapshot(&state), before);
+    assert_eq!(state.tile_at(pikeman).unwrap().kind, TileType::Pikeman);
+    assert!(!state.tile_at(lancelot).unwrap().flipped);
+    redo_action(&mut state).unwrap();
+    assert_eq!(snapshot(&state), struck);
+}
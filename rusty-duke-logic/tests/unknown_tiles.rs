@@ -0,0 +1,92 @@
+//! Records and saves naming tile types this version doesn't know, e.g. from
+//! a newer version, are rejected with an error instead of a panic.
+
+use rusty_duke_logic::logic::record::{GameRecord, RecordError};
+use rusty_duke_logic::logic::{
+    do_action, get_legal_actions, Action, GameState, IntoEnumIterator, TileType, UnknownTileType,
+};
+
+/// Record of a seeded game, up to and including the placement of the first
+/// tile drawn from the bag.
+fn record_with_draw() -> GameRecord {
+    let mut state = GameState::new_seeded(5);
+    let mut record = GameRecord::new(Some(5));
+    let mut drawn = false;
+    while !drawn || !state.drawn().is_empty() {
+        let actions = get_legal_actions(&state);
+        let action = match actions.contains(&Action::NewFromBag) {
+            true if !drawn => Action::NewFromBag,
+            _ => actions[0],
+        };
+        drawn |= action == Action::NewFromBag;
+        do_action(&mut state, &action).unwrap();
+        record.push(&state, action);
+    }
+    record
+}
+
+#[test]
+fn tile_types_parse_from_their_names() {
+    for kind in TileType::iter() {
+        assert_eq!(kind.to_string().parse::<TileType>(), Ok(kind));
+    }
+    assert_eq!(
+        "Gawain".parse::<TileType>(),
+        Err(UnknownTileType("Gawain".to_string()))
+    );
+    assert_eq!(
+        "footman".parse::<TileType>(),
+        Err(UnknownTileType("footman".to_string()))
+    );
+}
+
+#[test]
+fn record_with_unknown_drawn_tile_is_rejected() {
+    let record = record_with_draw();
+    let text = record.to_text();
+    assert_eq!(GameRecord::from_text(&text), Ok(record));
+
+    let (n, line) = text
+        .lines()
+        .enumerate()
+        .find(|(_, line)| line.starts_with("draw "))
+        .unwrap();
+    let text = text.replace(line, "draw Gawain");
+    assert_eq!(
+        GameRecord::from_text(&text),
+        Err(RecordError::UnsupportedContent(n + 1, "Gawain".to_string()))
+    );
+}
+
+#[test]
+fn record_with_unknown_placed_tile_is_rejected() {
+    // First line after the seed places the Duke.
+    let text = record_with_draw().to_text();
+    let line = text.lines().nth(1).unwrap();
+    assert!(line.ends_with(" Duke"), "{}", line);
+    let text = text.replace(line, &line.replace("Duke", "Gawain"));
+    assert_eq!(
+        GameRecord::from_text(&text),
+        Err(RecordError::UnsupportedContent(2, "Gawain".to_string()))
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn save_with_unknown_tile_is_rejected() {
+    use rusty_duke_logic::logic::save::{from_save_json, to_save_json, SaveError};
+
+    let state = record_with_draw().replay();
+    let json = to_save_json(&state);
+    assert!(json.contains("\"Footman\""));
+    match from_save_json(&json.replace("\"Footman\"", "\"Gawain\"")) {
+        Err(SaveError::UnsupportedContent(name)) => assert_eq!(name, "Gawain"),
+        result => panic!("{:?}", result.map(|_| ())),
+    }
+
+    // Broken saves of known tiles are still malformed.
+    match from_save_json(&json.replace("\"Footman\"", "7")) {
+        Err(SaveError::Malformed(_)) => {}
+        result => panic!("{:?}", result.map(|_| ())),
+    }
+}
@@ -0,0 +1,182 @@
+//! Ruleset validation, one broken tile set per diagnostic, and rejection of
+//! broken tile definition files.
+
+use rusty_duke_logic::logic::tiles::{
+    builtin, load_tile_definitions_from, TileDefinitionError, TileDefinitions, BUILTIN,
+};
+use rusty_duke_logic::logic::validate::{validate_ruleset, RulesetSource, Severity};
+use rusty_duke_logic::logic::variant::{Expansion, GameRules, Variant};
+use rusty_duke_logic::logic::{ActionType, AvailableAction, Offset, TileType};
+
+/// Codes of the diagnostics of definitions, with the standard tile set.
+fn codes(definitions: &TileDefinitions) -> Vec<&'static str> {
+    tile_set_codes(
+        definitions,
+        RulesetSource::for_definitions(definitions, &GameRules::standard()).tiles,
+    )
+}
+
+/// Codes of the diagnostics of definitions, with tiles as tile set.
+fn tile_set_codes(definitions: &TileDefinitions, tiles: Vec<TileType>) -> Vec<&'static str> {
+    let source = RulesetSource {
+        actions: &definitions.actions,
+        effects: &definitions.effects,
+        tiles,
+    };
+    validate_ruleset(&source)
+        .iter()
+        .map(|d| d.kind.code())
+        .collect()
+}
+
+/// Codes of the built in definitions, with edit made to the Footman.
+fn footman_codes<F: FnOnce(&mut Vec<AvailableAction>, &mut Vec<AvailableAction>)>(
+    edit: F,
+) -> Vec<&'static str> {
+    let mut definitions = builtin();
+    let footman = definitions.actions.get_mut(&TileType::Footman).unwrap();
+    edit(&mut footman.front, &mut footman.back);
+    codes(&definitions)
+}
+
+fn action(kind: ActionType, x: i8, y: i8) -> AvailableAction {
+    AvailableAction::new(kind, Offset { x, y })
+}
+
+#[test]
+fn builtin_definitions_have_no_diagnostics() {
+    let definitions = builtin();
+    for variant in Variant::ALL {
+        let source = RulesetSource::for_definitions(&definitions, &variant.rules());
+        assert!(validate_ruleset(&source).is_empty(), "{}", variant);
+    }
+    let mut rules = GameRules::standard();
+    rules.bag.extend(Expansion::ArthurianLegends.tiles());
+    let source = RulesetSource::for_definitions(&definitions, &rules);
+    assert!(validate_ruleset(&source).is_empty());
+}
+
+#[test]
+fn undefined_tile() {
+    let mut definitions = builtin();
+    definitions.actions.remove(&TileType::Pikeman);
+    assert_eq!(codes(&definitions), ["E001"]);
+}
+
+#[test]
+fn empty_side() {
+    assert_eq!(footman_codes(|_, back| back.clear()), ["E002"]);
+}
+
+#[test]
+fn zero_offset() {
+    let codes = footman_codes(|front, _| front.push(action(ActionType::Move, 0, 0)));
+    assert_eq!(codes, ["E003"]);
+}
+
+#[test]
+fn offset_out_of_range() {
+    let codes = footman_codes(|front, _| front.push(action(ActionType::Jump, 0, 8)));
+    assert_eq!(codes, ["E004"]);
+}
+
+#[test]
+fn duplicate_offset() {
+    let codes = footman_codes(|front, _| front.push(front[0].clone()));
+    assert_eq!(codes, ["E005"]);
+}
+
+#[test]
+fn slide_not_ray() {
+    let codes = footman_codes(|front, _| front.push(action(ActionType::Slide, 0, 2)));
+    assert_eq!(codes, ["E006"]);
+    let codes = footman_codes(|front, _| front.push(action(ActionType::JumpSlide, 1, 2)));
+    assert_eq!(codes, ["E006"]);
+}
+
+#[test]
+fn command_without_offsets() {
+    // Command on an offset the tile moves to, that's no conflict.
+    let codes = footman_codes(|front, _| front.push(action(ActionType::Command, 1, 0)));
+    assert_eq!(codes, ["E007"]);
+}
+
+#[test]
+fn duke_count() {
+    let definitions = builtin();
+    let mut tiles = RulesetSource::for_definitions(&definitions, &GameRules::standard()).tiles;
+    tiles.push(TileType::Duke);
+    assert_eq!(tile_set_codes(&definitions, tiles.clone()), ["E008"]);
+    tiles.retain(|kind| *kind != TileType::Duke);
+    assert_eq!(tile_set_codes(&definitions, tiles), ["E008"]);
+}
+
+#[test]
+fn missing_starting_tiles() {
+    let definitions = builtin();
+    let mut tiles = RulesetSource::for_definitions(&definitions, &GameRules::standard()).tiles;
+    // Footmen are in the bag too, keep only one.
+    tiles.retain(|kind| *kind != TileType::Footman);
+    tiles.push(TileType::Footman);
+    assert_eq!(tile_set_codes(&definitions, tiles), ["E009"]);
+}
+
+#[test]
+fn move_not_straight() {
+    let codes = footman_codes(|front, _| front.push(action(ActionType::Move, 1, 2)));
+    assert_eq!(codes, ["E010"]);
+}
+
+#[test]
+fn conflicting_offset() {
+    // Footman moves to (0, 1) already.
+    let codes = footman_codes(|front, _| front.push(action(ActionType::Strike, 0, 1)));
+    assert_eq!(codes, ["W001"]);
+}
+
+#[test]
+fn back_strictly_worse() {
+    let codes = footman_codes(|front, back| *back = vec![front[0].clone()]);
+    assert_eq!(codes, ["W002"]);
+}
+
+/// Built in definitions with the back side of the Footman replaced, loaded
+/// from a file.
+fn load_with_footman_back(back: &str) -> Result<(), TileDefinitionError> {
+    let start = BUILTIN.find("[Footman]").unwrap();
+    let from = start + BUILTIN[start..].find("back = [").unwrap();
+    let to = from + BUILTIN[from..].find("\n]").unwrap() + 2;
+    let text = format!("{}back = [{}]{}", &BUILTIN[..from], back, &BUILTIN[to..]);
+
+    let path = std::env::temp_dir().join(format!(
+        "rusty-duke-tiles-{}-{}.toml",
+        std::process::id(),
+        back.len()
+    ));
+    std::fs::write(&path, text).unwrap();
+    let result = load_tile_definitions_from(&path);
+    std::fs::remove_file(&path).unwrap();
+    result
+}
+
+#[test]
+fn loader_rejects_definitions_with_errors() {
+    let error = load_with_footman_back("").unwrap_err();
+    assert!(error.to_string().contains("error[E002]"), "{}", error);
+    match error {
+        TileDefinitionError::Invalid(diagnostics) => {
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].kind.code(), "E002");
+            assert_eq!(diagnostics[0].severity(), Severity::Error);
+        }
+        error => panic!("{:?}", error),
+    }
+}
+
+#[test]
+fn loader_accepts_warnings() {
+    // Back side only does one of the front moves. Nothing in this test binary
+    // creates games, so definitions are not in use yet.
+    let back = r#"{ kind = "Move", x = 0, y = 1 }"#;
+    assert_eq!(load_with_footman_back(back), Ok(()));
+}
@@ -0,0 +1,35 @@
+//! Tests of the JavaScript bindings. Run with
+//! `wasm-pack test --node rusty-duke-logic -- --features wasm`.
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use rusty_duke_logic::wasm::Game;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn ai_actions_are_legal() {
+    let mut game = Game::new(7);
+    for _ in 0..12 {
+        let action = game.ai_action(2, 5_000).expect("AI has an action.");
+        assert!(game.legal_actions().contains(&action));
+        assert!(game.apply_action(&action).is_ok());
+    }
+}
+
+#[wasm_bindgen_test]
+fn illegal_action_is_rejected() {
+    let mut game = Game::new(7);
+    let before = game.state();
+    assert!(game.apply_action("{}").is_err());
+    assert!(game.apply_action(r#"{"PlaceNew":{"x":0,"y":0}}"#).is_err());
+    assert_eq!(game.state(), before);
+}
+
+#[wasm_bindgen_test]
+fn state_round_trips() {
+    let mut game = Game::new(11);
+    let action = game.ai_action(1, 1_000).unwrap();
+    game.apply_action(&action).unwrap();
+    let loaded = Game::from_state(&game.state()).unwrap();
+    assert_eq!(loaded.state(), game.state());
+}
@@ -1,23 +1,46 @@
-//! Terminal interface for Rusty Duke game. Only supports single player vs. AI and AI vs. AI.
+//! Terminal interface for Rusty Duke game. Supports single player vs. AI, AI vs. AI and hotseat
+//! games, which can be saved and loaded.
 //!
 //! Very basic for ad hoc manual testing. If you want something more fancy, feel free to contribute.
 
 use crossterm::{
     cursor::{self, MoveTo, MoveToNextLine, RestorePosition, SavePosition},
-    event::{read, Event, KeyCode},
+    event::{poll, read, Event, KeyCode},
     execute, queue,
     style::{self, Color, Colors, Print, ResetColor, Stylize},
     terminal::{self, SetTitle},
     Result,
 };
-use flexi_logger::{self, FileSpec, Logger};
+use flexi_logger::{
+    self, Cleanup, Criterion, FileSpec, FlexiLoggerError, Logger, LoggerHandle, Naming,
+};
 use rusty_duke_logic::{
-    ai::alpha_beta::{self, Agent},
-    logic::{self, Action, Coordinate, GameState, Tile, TileColor},
+    ai::{
+        alpha_beta::{Agent, AgentConfig, MAX_LEVEL, MIN_LEVEL},
+        calibrate::{self, Calibration, Difficulty, HardwareFingerprint},
+        opening::OpeningBook,
+        DukeAgent,
+    },
+    logic::{
+        self, cards,
+        clock::{format_time, GameClock},
+        notation::action_to_notation,
+        perft::{self, DrawMode},
+        record::{GameRecord, RecordError},
+        validate::{self, RulesetSource},
+        variant::{GameRules, Variant},
+        Action, Coordinate, Effect, EffectSet, GameState, StateDelta, Tile, TileColor, TileType,
+        WinReason, Winner,
+    },
+    persist,
+    session::{Controller, GameSession},
 };
 use std::{
+    cmp::Ordering,
+    fs::File,
     io::{self, stdin, Write},
-    time::Duration,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 /// (X,Y)
@@ -25,6 +48,31 @@ const SQUARE_SIZE: (u16, u16) = (16, 6);
 const TILE_SIZE: (u16, u16) = (15, 5);
 const TERM_WIDTH: u16 = SQUARE_SIZE.0 * logic::WIDTH as u16;
 const TERM_HEIGHT: u16 = SQUARE_SIZE.1 * (logic::HEIGHT) as u16 + TILE_SIZE.1 + 5;
+const DEFAULT_LOG_LEVEL: &str = "debug";
+/// Log file is rotated when it reaches this size in bytes.
+const LOG_FILE_SIZE: u64 = 10_000_000;
+const LOG_FILES_KEPT: usize = 5;
+/// Log settings in the working directory, see `LogConfig`.
+const LOG_CONFIG_FILE: &str = "duke-log.txt";
+/// Minimum time an AI turn takes, so the player can follow what changed.
+/// Set in milliseconds with `--ai-min-turn`.
+const DEFAULT_MIN_AI_TURN: Duration = Duration::from_millis(500);
+/// Clocks are redrawn this often while waiting for a key.
+const CLOCK_REFRESH: Duration = Duration::from_millis(200);
+/// Actions kept for undo in games of AI against AI, which can go on for
+/// thousands of plies. Saves have every action.
+const SPECTATOR_HISTORY: usize = 200;
+
+/// Save files are `duke-save-<unix time>.txt` in the working directory.
+const SAVE_PREFIX: &str = "duke-save-";
+const SAVE_EXTENSION: &str = ".txt";
+/// Number of newest saves listed in the load menu.
+const LOAD_MENU_SIZE: usize = 9;
+/// Search depth of self play agents, unless given.
+const SELFPLAY_DEPTH: u8 = 3;
+/// Self play games of variants without turn limit are adjudicated after this
+/// many turns, so every game ends.
+const SELFPLAY_TURN_LIMIT: u16 = 400;
 
 const BLACK_COLORS: Colors = Colors {
     foreground: Some(Color::White),
@@ -50,6 +98,14 @@ const COMMANDED_BLACK_COLORS: Colors = Colors {
     foreground: Some(Color::White),
     background: Some(Color::DarkGreen),
 };
+const THREATENED_BLACK_COLORS: Colors = Colors {
+    foreground: Some(Color::White),
+    background: Some(Color::Red),
+};
+const HANGING_BLACK_COLORS: Colors = Colors {
+    foreground: Some(Color::Black),
+    background: Some(Color::Yellow),
+};
 
 const WHITE_COLORS: Colors = Colors {
     foreground: Some(Color::DarkGrey),
@@ -75,6 +131,14 @@ const COMMANDED_WHITE_COLORS: Colors = Colors {
     foreground: Some(Color::White),
     background: Some(Color::DarkGreen),
 };
+const THREATENED_WHITE_COLORS: Colors = Colors {
+    foreground: Some(Color::White),
+    background: Some(Color::Red),
+};
+const HANGING_WHITE_COLORS: Colors = Colors {
+    foreground: Some(Color::Black),
+    background: Some(Color::Yellow),
+};
 
 const BOARD_COLORS: Colors = Colors {
     foreground: Some(Color::Magenta),
@@ -86,6 +150,22 @@ const SELECTED_SQUARE: Colors = Colors {
     background: Some(Color::Magenta),
 };
 
+/// Effect markers on the top row of a square.
+const DREAD_COLORS: Colors = Colors {
+    foreground: Some(Color::White),
+    background: Some(Color::DarkMagenta),
+};
+const DEFENCE_COLORS: Colors = Colors {
+    foreground: Some(Color::Black),
+    background: Some(Color::Cyan),
+};
+
+/// Banner over the board when the game is over.
+const GAME_OVER_COLORS: Colors = Colors {
+    foreground: Some(Color::Black),
+    background: Some(Color::White),
+};
+
 /*const MOVE_SQUARE_COLORS: Colors = Colors {
     foreground: Some(Color::White),
     background: Some(Color::Green),
@@ -103,22 +183,337 @@ enum TileState {
     Attacked,
     Striked,
     Commanded,
+    /// Own tile the opponent can capture, with threats shown.
+    Threatened,
+    /// Opponent tile that can be captured and is not defended.
+    Hanging,
+}
+
+/// Settings of the AI players of a game, kept to create them again when a
+/// saved game is loaded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AiSettings {
+    /// Difficulty level, see `AgentConfig::level`.
+    Level(u8),
+    /// Full strength search with limits.
+    Search {
+        depth: Option<u8>,
+        duration: Option<Duration>,
+    },
+}
+
+impl AiSettings {
+    fn agent(self, color: TileColor) -> Box<dyn DukeAgent + Send + Sync> {
+        let config = match self {
+            AiSettings::Level(level) => AgentConfig::level(level),
+            AiSettings::Search { depth, duration } => AgentConfig::new(depth, duration),
+        };
+        Box::new(Agent::from_config(color, &config).with_opening_book(OpeningBook::standard()))
+    }
+}
+
+/// Controllers of Black and White: humans for their colors, agents of the AI
+/// settings for the others.
+fn controllers(
+    player_color: Option<TileColor>,
+    player2_color: Option<TileColor>,
+    ai: Option<AiSettings>,
+) -> [Controller; 2] {
+    [TileColor::Black, TileColor::White].map(|color| {
+        if player_color == Some(color) || player2_color == Some(color) {
+            Controller::Human
+        } else {
+            Controller::Agent(ai.expect("No AI settings.").agent(color))
+        }
+    })
 }
 
 struct PlayState {
-    state: GameState,
+    session: GameSession,
     player_color: Option<TileColor>,
-    agent: Agent,
-    agent2: Option<Agent>,
+    /// Second human player, in hotseat games.
+    player2_color: Option<TileColor>,
+    ai: Option<AiSettings>,
     focus: Coordinate,
     selected: Option<Coordinate>,
     selected_command: Option<Coordinate>,
+    /// Shown under the board until the next key, e.g. where game was saved.
+    message: Option<String>,
+    /// Show movement grid of the focused tile until the next key.
+    inspect: bool,
+    /// Tint and mark with '!' own tiles the opponent can capture, and tint
+    /// hanging opponent tiles, toggled by 't'.
+    show_threats: bool,
+    /// Blank screen between turns of hotseat games, see
+    /// `hotseat_interstitial`.
+    pass_screen: bool,
+}
+
+impl PlayState {
+    /// New game of variant. Colors not played by a human are played by AI,
+    /// which needs settings.
+    fn new(
+        variant: Variant,
+        player_color: Option<TileColor>,
+        player2_color: Option<TileColor>,
+        ai: Option<AiSettings>,
+    ) -> PlayState {
+        let controllers = controllers(player_color, player2_color, ai);
+        PlayState::with_session(
+            GameSession::new(variant, None, controllers),
+            player_color,
+            player2_color,
+            ai,
+        )
+    }
+
+    /// Game of session, the controllers of the session agree with the
+    /// colors and AI settings. Games of AI against AI keep only the last
+    /// `SPECTATOR_HISTORY` actions for undo.
+    fn with_session(
+        mut session: GameSession,
+        player_color: Option<TileColor>,
+        player2_color: Option<TileColor>,
+        ai: Option<AiSettings>,
+    ) -> PlayState {
+        if player_color.is_none() {
+            session.set_history_limit(SPECTATOR_HISTORY);
+        }
+        PlayState {
+            session,
+            player_color,
+            player2_color,
+            ai,
+            focus: Coordinate {
+                x: logic::WIDTH / 2,
+                y: 0,
+            },
+            selected: None,
+            selected_command: None,
+            message: None,
+            inspect: false,
+            show_threats: false,
+            pass_screen: true,
+        }
+    }
+
+    /// Game as save file text: a line per human color, then AI settings if
+    /// there is AI, "ai level <level>" or "ai <depth> <duration>", then the
+    /// game record.
+    fn to_save_text(&self) -> String {
+        let mut text = String::new();
+        for color in [self.player_color, self.player2_color].iter().flatten() {
+            text.push_str(&format!("human {:?}\n", color));
+        }
+        let value = |v: Option<u128>| v.map_or("-".to_string(), |v| v.to_string());
+        match self.ai {
+            Some(AiSettings::Level(level)) => text.push_str(&format!("ai level {}\n", level)),
+            Some(AiSettings::Search { depth, duration }) => text.push_str(&format!(
+                "ai {} {}\n",
+                value(depth.map(u128::from)),
+                value(duration.map(|d| d.as_millis()))
+            )),
+            None => {}
+        }
+        text.push_str(&self.session.record().to_text());
+        text
+    }
+
+    /// Game from `to_save_text` output. The error is shown to the user.
+    fn from_save_text(text: &str) -> std::result::Result<PlayState, String> {
+        let mut humans = Vec::new();
+        let mut ai = None;
+        let mut lines = text.lines();
+        let mut record_text = String::new();
+        for line in lines.by_ref() {
+            if let Some(color) = line.strip_prefix("human ") {
+                humans.push(match color.trim() {
+                    "Black" => TileColor::Black,
+                    "White" => TileColor::White,
+                    _ => return Err(format!("Unknown color: {}", color)),
+                });
+            } else if let Some(settings) = line.strip_prefix("ai ") {
+                let invalid = || format!("Invalid AI settings: {}", settings);
+                let values: Vec<&str> = settings.split_whitespace().collect();
+                if values.len() != 2 {
+                    return Err(invalid());
+                }
+                if values[0] == "level" {
+                    let level = values[1].parse::<u8>().map_err(|_| invalid())?;
+                    ai = Some(AiSettings::Level(level));
+                    continue;
+                }
+                let depth = match values[0] {
+                    "-" => None,
+                    v => Some(v.parse::<u8>().map_err(|_| invalid())?),
+                };
+                let duration = match values[1] {
+                    "-" => None,
+                    v => Some(Duration::from_millis(
+                        v.parse::<u64>().map_err(|_| invalid())?,
+                    )),
+                };
+                ai = Some(AiSettings::Search { depth, duration });
+            } else {
+                record_text.push_str(line);
+                record_text.push('\n');
+                break;
+            }
+        }
+        for line in lines {
+            record_text.push_str(line);
+            record_text.push('\n');
+        }
+
+        if humans.len() < 2 && ai.is_none() {
+            return Err("AI settings missing.".to_string());
+        }
+        let record = GameRecord::from_text(&record_text).map_err(|e| record_error_message(&e))?;
+        let (player_color, player2_color) = (humans.first().copied(), humans.get(1).copied());
+        Ok(PlayState::with_session(
+            GameSession::from_record(record, controllers(player_color, player2_color, ai)),
+            player_color,
+            player2_color,
+            ai,
+        ))
+    }
+
+    /// New game of the same variant and AI settings, colors of the humans
+    /// swapped. Agents are created for their new colors.
+    fn rematch(&self) -> PlayState {
+        let mut rematch = PlayState::new(
+            self.session.record().variant,
+            self.player_color.map(TileColor::opponent),
+            self.player2_color.map(TileColor::opponent),
+            self.ai,
+        );
+        rematch.pass_screen = self.pass_screen;
+        rematch
+    }
+
+    fn is_human(&self, color: TileColor) -> bool {
+        self.session.is_human(color)
+    }
+
+    /// Let the agent of the player to go do its turn, drawing and placing a
+    /// tile being one turn.
+    fn ai_turn(&mut self) {
+        let ply = self.session.ply;
+        let mut delta = None;
+        while self.session.ply == ply {
+            match self.session.poll_ai() {
+                Some((_, outcome)) => {
+                    log_events(&outcome.delta);
+                    delta = Some(outcome.delta);
+                }
+                // This means game over. But don't do anything now.
+                None => break,
+            }
+        }
+
+        // Keep the announcement of the human's capture, if any.
+        if let Some(captured) = delta.as_ref().and_then(capture_message) {
+            self.message = Some(match self.message.take() {
+                Some(message) => format!("{} {}", message, captured),
+                None => captured,
+            });
+        }
+    }
+
+    /// Let the agent to go do its turn once pacing allows it at now. Returns
+    /// the time left to wait, zero if the turn was played.
+    fn paced_ai_turn(&mut self, pacing: &mut AiPacing, now: Instant) -> Duration {
+        let wait = pacing.wait(now);
+        if wait.is_zero() {
+            pacing.done();
+            self.ai_turn();
+        }
+        wait
+    }
+
+    /// See `GameSession::update_clock`.
+    fn update_clock(&mut self) {
+        self.session.update_clock(Instant::now());
+    }
+
+    fn clock_runs(&self) -> bool {
+        self.session
+            .clock()
+            .is_some_and(|clock| clock.running().is_some())
+    }
+
+    /// Color the board is seen from. In hotseat games the board turns to the
+    /// player to go, AI vs AI games are seen from Black.
+    fn view_color(&self) -> TileColor {
+        if self.player2_color.is_some() {
+            self.session.ply
+        } else {
+            self.player_color.unwrap_or(TileColor::Black)
+        }
+    }
+}
+
+/// What a key did in `human_turn`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HumanInput {
+    /// Turn goes on, e.g. focus moved or tile selected.
+    Continue,
+    /// Action done and turn passed to the opponent.
+    TurnDone,
+    /// Player wants to resign, not confirmed yet.
+    Resign,
+    Quit,
+}
+
+/// Holds AI turns back until they have taken a minimum time, fast replies
+/// are jarring. The game loop keeps handling keys until the wait is over, so
+/// the board can be looked at meanwhile. Callers pass in `now`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AiPacing {
+    min_turn: Duration,
+    /// Start of the AI turn waiting to be played.
+    started: Option<Instant>,
+}
+
+impl AiPacing {
+    fn new(min_turn: Duration) -> AiPacing {
+        AiPacing {
+            min_turn,
+            started: None,
+        }
+    }
+
+    /// Time left at now until the AI turn may be played. The turn starts at
+    /// the first call after `done`.
+    fn wait(&mut self, now: Instant) -> Duration {
+        let started = *self.started.get_or_insert(now);
+        (started + self.min_turn).saturating_duration_since(now)
+    }
+
+    /// AI turn was played.
+    fn done(&mut self) {
+        self.started = None;
+    }
+}
+
+/// Error of a saved record as shown to the user. Unknown tiles are most
+/// likely of a newer version, not a broken save.
+fn record_error_message(e: &RecordError) -> String {
+    match e {
+        RecordError::UnsupportedContent(_, name) => format!(
+            "This game uses tiles from a newer version or a different ruleset ({}).",
+            name
+        ),
+        e => e.to_string(),
+    }
 }
 
 enum State {
     MainMenu,
     AiMenu(Option<TileColor>),
-    Play(PlayState),
+    /// Saved games to load, with error of the last load if it failed.
+    LoadMenu(Option<String>),
+    Play(Box<PlayState>),
     Exit,
 }
 
@@ -159,6 +554,14 @@ where
                 fg_color = COMMANDED_BLACK_COLORS.foreground.unwrap();
                 bg_color = COMMANDED_BLACK_COLORS.background.unwrap();
             }
+            TileState::Threatened => {
+                fg_color = THREATENED_BLACK_COLORS.foreground.unwrap();
+                bg_color = THREATENED_BLACK_COLORS.background.unwrap();
+            }
+            TileState::Hanging => {
+                fg_color = HANGING_BLACK_COLORS.foreground.unwrap();
+                bg_color = HANGING_BLACK_COLORS.background.unwrap();
+            }
         }
     } else {
         match state {
@@ -190,21 +593,200 @@ where
                 fg_color = COMMANDED_WHITE_COLORS.foreground.unwrap();
                 bg_color = COMMANDED_WHITE_COLORS.background.unwrap();
             }
+            TileState::Threatened => {
+                fg_color = THREATENED_WHITE_COLORS.foreground.unwrap();
+                bg_color = THREATENED_WHITE_COLORS.background.unwrap();
+            }
+            TileState::Hanging => {
+                fg_color = HANGING_WHITE_COLORS.foreground.unwrap();
+                bg_color = HANGING_WHITE_COLORS.background.unwrap();
+            }
         }
     }
 
+    // Threats are marked, not only tinted.
+    let name = match state {
+        TileState::Threatened => format!("{}!", tile.kind),
+        _ => tile.kind.to_string(),
+    };
     print_square(
         w,
         cursor,
         (cursor.0 + TILE_SIZE.0, cursor.1 + TILE_SIZE.1),
         fg_color,
         bg_color,
-        Some(tile.kind.to_string()),
+        Some(name),
+    )?;
+
+    // Side under the name
+    let side = if tile.flipped { "back" } else { "front" };
+    queue!(
+        w,
+        SavePosition,
+        MoveTo(cursor.0, cursor.1 + SQUARE_SIZE.1 / 2 + 1),
+        style::PrintStyledContent(
+            format!("{: ^width$}", side, width = TILE_SIZE.0 as usize)
+                .with(fg_color)
+                .on(bg_color)
+        ),
+        RestorePosition
     )?;
 
     Ok(())
 }
 
+/// Mark Dread and Defence on the top row of square at cursor.
+fn print_effects<W>(w: &mut W, cursor: (u16, u16), effects: EffectSet) -> Result<()>
+where
+    W: Write,
+{
+    queue!(w, SavePosition)?;
+    if effects.contains(&Effect::Dread) {
+        queue!(
+            w,
+            MoveTo(cursor.0, cursor.1),
+            style::PrintStyledContent(
+                "Dread"
+                    .with(DREAD_COLORS.foreground.unwrap())
+                    .on(DREAD_COLORS.background.unwrap())
+            )
+        )?;
+    }
+    if effects.contains(&Effect::Defence) {
+        let text = "Defence";
+        queue!(
+            w,
+            MoveTo(cursor.0 + TILE_SIZE.0 - text.len() as u16, cursor.1),
+            style::PrintStyledContent(
+                text.with(DEFENCE_COLORS.foreground.unwrap())
+                    .on(DEFENCE_COLORS.background.unwrap())
+            )
+        )?;
+    }
+    queue!(w, RestorePosition)?;
+    Ok(())
+}
+
+/// Movement grid of the current side of tile, in `cards` symbols, turned the
+/// way tile moves on the board as seen by view color.
+fn inspector_grid(tile: &Tile, view: TileColor) -> Vec<String> {
+    let actions = match logic::TILE_ACTIONS.get(&tile.kind) {
+        Some(actions) if tile.flipped => &actions.back,
+        Some(actions) => &actions.front,
+        None => return Vec::new(),
+    };
+
+    // Cards are drawn forward up for Black, White tiles move the other way.
+    let mut rows: Vec<Vec<char>> = cards::draw(actions)
+        .iter()
+        .map(|row| row.chars().collect())
+        .collect();
+    if tile.color == TileColor::White {
+        rows.reverse();
+        rows.iter_mut().for_each(|row| row.reverse());
+    }
+    // Board is mirrored left to right for White, see `square_cursor`.
+    if view == TileColor::White {
+        rows.iter_mut().for_each(|row| row.reverse());
+    }
+
+    rows.into_iter()
+        .map(|row| row.into_iter().collect())
+        .collect()
+}
+
+const INSPECTOR_LEGEND: [&str; 3] = [
+    "T tile  M Move  J Jump  S Slide",
+    "L JumpSlide  X Strike  C Command",
+    "B Move and Command",
+];
+
+/// Captured tiles of both colors, with counts of equal tiles, material
+/// balance and tiles left in the bags.
+fn captured_lines(state: &GameState) -> Vec<String> {
+    let mut lines = vec!["Captured".to_string()];
+    for color in [TileColor::Black, TileColor::White] {
+        let mut counts: Vec<(TileType, usize)> = Vec::new();
+        for captured in state.captures_by(color) {
+            match counts
+                .iter_mut()
+                .find(|(kind, _)| *kind == captured.tile.kind)
+            {
+                Some((_, count)) => *count += 1,
+                None => counts.push((captured.tile.kind, 1)),
+            }
+        }
+        let tiles: Vec<String> = counts
+            .iter()
+            .map(|(kind, count)| match count {
+                1 => kind.to_string(),
+                _ => format!("{} x{}", kind, count),
+            })
+            .collect();
+        let tiles = if tiles.is_empty() {
+            "-".to_string()
+        } else {
+            tiles.join(", ")
+        };
+        lines.push(format!("by {:?}: {}", color, tiles));
+    }
+
+    let balance = state.material_balance();
+    lines.push(match balance.cmp(&0) {
+        Ordering::Greater => format!("Material: Black +{}", balance),
+        Ordering::Less => format!("Material: White +{}", -balance),
+        Ordering::Equal => "Material: even".to_string(),
+    });
+    lines.push(format!(
+        "In bag: Black {}, White {}",
+        state.bag_of(TileColor::Black).len(),
+        state.bag_of(TileColor::White).len()
+    ));
+    lines
+}
+
+/// Print movement grid of tile right of the drawn tile, or captured tiles and
+/// bags if there is no tile. Lines are cut to the width of the board.
+fn print_inspector<W>(
+    w: &mut W,
+    state: &GameState,
+    tile: Option<&Tile>,
+    view: TileColor,
+) -> Result<()>
+where
+    W: Write,
+{
+    let x = TILE_SIZE.0 + 3;
+    let top = TERM_HEIGHT - SQUARE_SIZE.1 - 2;
+    let width = (TERM_WIDTH - x) as usize;
+
+    let mut lines = Vec::new();
+    if tile.is_none() {
+        lines = captured_lines(state);
+    }
+    if let Some(tile) = tile {
+        let side = if tile.flipped { "back" } else { "front" };
+        lines.push(format!("{:?} {}, {}:", tile.color, tile.kind, side));
+        for (i, row) in inspector_grid(tile, view).iter().enumerate() {
+            let squares: Vec<String> = row.chars().map(|c| c.to_string()).collect();
+            let legend = INSPECTOR_LEGEND.get(i).copied().unwrap_or("");
+            lines.push(format!("{}    {}", squares.join(" "), legend));
+        }
+    }
+
+    queue!(w, ResetColor, SavePosition)?;
+    for y in 0..(SQUARE_SIZE.1) {
+        let line = lines.get(y as usize).map_or("", |l| l.as_str());
+        queue!(
+            w,
+            MoveTo(x, top + y),
+            Print(format!("{: <width$.width$}", line, width = width))
+        )?;
+    }
+    queue!(w, RestorePosition)?;
+    Ok(())
+}
+
 fn print_square<W>(
     w: &mut W,
     start: (u16, u16),
@@ -247,19 +829,11 @@ fn print_board<W>(w: &mut W, state: &PlayState) -> Result<()>
 where
     W: Write,
 {
-    let player_color;
-    let game_state = &state.state;
-    let board = &game_state.board;
+    let player_color = state.view_color();
+    let game_state = state.session.state();
     let focus = state.focus;
     let selected = state.selected;
 
-    if state.player_color.is_some() {
-        player_color = state.player_color.unwrap();
-    } else {
-        // Not pretyy, but works.
-        player_color = state.agent.color;
-    }
-
     let fg = BOARD_COLORS.foreground.unwrap();
     let bg = BOARD_COLORS.background.unwrap();
 
@@ -335,105 +909,121 @@ where
         actions = logic::get_tile_actions(game_state, focus);
     }
 
+    let (threatened, hanging): (Vec<Coordinate>, Vec<Coordinate>) = if state.show_threats {
+        (
+            logic::threatened_squares(game_state, player_color.opponent())
+                .into_iter()
+                .map(|(cord, _)| cord)
+                .collect(),
+            logic::get_threats(game_state, player_color).hanging,
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
     // Print them tiles
-    for y in 0..logic::HEIGHT {
-        for x in 0..logic::WIDTH {
-            let mut tile_state = TileState::Normal;
-            let tile = &board[y as usize][x as usize].tile;
-            let cord = Coordinate { x: x, y: y };
-            let cursor = square_cursor(cord, player_color);
-            let mut square_text: Option<String> = None;
-
-            for a in actions.iter() {
-                match a {
-                    Action::PlaceNew(c) if *c == cord => {
-                        square_text = Some("Deploy".to_string());
+    for (cord, square) in game_state.iter_squares() {
+        let mut tile_state = TileState::Normal;
+        if threatened.contains(&cord) {
+            tile_state = TileState::Threatened;
+        } else if hanging.contains(&cord) {
+            tile_state = TileState::Hanging;
+        }
+        let tile = &square.tile;
+        let cursor = square_cursor(cord, player_color);
+        let mut square_text: Option<String> = None;
+
+        for a in actions.iter() {
+            match a {
+                Action::PlaceNew(c) if *c == cord => {
+                    square_text = Some("Deploy".to_string());
+                }
+                Action::Move(ad) if ad.target_pos == cord => {
+                    if tile.is_some() {
+                        tile_state = TileState::Attacked;
+                    } else {
+                        square_text = Some("Move".to_string());
                     }
-                    Action::Move(ad) if ad.target_pos == cord => {
-                        if tile.is_some() {
-                            tile_state = TileState::Attacked;
-                        } else {
-                            square_text = Some("Move".to_string());
-                        }
+                }
+                Action::Jump(ad) if ad.target_pos == cord => {
+                    if tile.is_some() {
+                        tile_state = TileState::Attacked;
+                    } else {
+                        square_text = Some("Jump".to_string());
                     }
-                    Action::Jump(ad) if ad.target_pos == cord => {
-                        if tile.is_some() {
-                            tile_state = TileState::Attacked;
-                        } else {
-                            square_text = Some("Jump".to_string());
-                        }
+                }
+                Action::Slide(ad) if ad.target_pos == cord => {
+                    if tile.is_some() {
+                        tile_state = TileState::Attacked;
+                    } else {
+                        square_text = Some("Slide".to_string());
                     }
-                    Action::Slide(ad) if ad.target_pos == cord => {
-                        if tile.is_some() {
-                            tile_state = TileState::Attacked;
-                        } else {
-                            square_text = Some("Slide".to_string());
-                        }
+                }
+                Action::JumpSlide(ad) if ad.target_pos == cord => {
+                    if tile.is_some() {
+                        tile_state = TileState::Attacked;
+                    } else {
+                        square_text = Some("Jumpslide".to_string());
                     }
-                    Action::JumpSlide(ad) if ad.target_pos == cord => {
-                        if tile.is_some() {
+                }
+                Action::Command(cd) => {
+                    if tile.is_some() {
+                        if cd.target_pos == cord {
                             tile_state = TileState::Attacked;
-                        } else {
-                            square_text = Some("Jumpslide".to_string());
-                        }
-                    }
-                    Action::Command(cd) => {
-                        if tile.is_some() {
-                            if cd.target_pos == cord {
-                                tile_state = TileState::Attacked;
-                            } else if cd.command_tile_pos == cord {
-                                tile_state = TileState::Commanded;
-                            }
-                        } else if cd.target_pos == cord {
-                            square_text = Some("Command Move".to_string());
+                        } else if cd.command_tile_pos == cord {
+                            tile_state = TileState::Commanded;
                         }
+                    } else if cd.target_pos == cord {
+                        square_text = Some("Command Move".to_string());
                     }
-                    Action::Strike(ad) if ad.target_pos == cord => {
-                        if tile.is_some() {
-                            tile_state = TileState::Striked;
-                        } else {
-                            square_text = Some("Strike".to_string());
-                        }
+                }
+                Action::Strike(ad) if ad.target_pos == cord => {
+                    if tile.is_some() {
+                        tile_state = TileState::Striked;
+                    } else {
+                        square_text = Some("Strike".to_string());
                     }
-                    _ => {}
                 }
+                _ => {}
             }
+        }
 
-            // Selected looks like focused
-            if selected.is_some() && selected.unwrap() == cord {
-                tile_state = TileState::Selected;
-            }
-
-            if tile.is_some() {
-                // Focus override any state
-                if focus == cord {
-                    tile_state = TileState::Focused;
-                }
-                print_tile(
-                    w,
-                    square_cursor(cord, player_color),
-                    tile_state,
-                    &tile.unwrap(),
-                )?;
-            } else {
-                let mut square_fg = fg;
-                let mut square_bg = bg;
+        // Selected looks like focused
+        if selected.is_some() && selected.unwrap() == cord {
+            tile_state = TileState::Selected;
+        }
 
-                if focus == cord {
-                    square_fg = SELECTED_SQUARE.foreground.unwrap();
-                    square_bg = SELECTED_SQUARE.background.unwrap();
-                }
+        if tile.is_some() {
+            // Focus override any state
+            if focus == cord {
+                tile_state = TileState::Focused;
+            }
+            print_tile(
+                w,
+                square_cursor(cord, player_color),
+                tile_state,
+                &tile.unwrap(),
+            )?;
+        } else {
+            let mut square_fg = fg;
+            let mut square_bg = bg;
 
-                print_square(
-                    w,
-                    cursor,
-                    (cursor.0 + TILE_SIZE.0, cursor.1 + TILE_SIZE.1),
-                    square_fg,
-                    square_bg,
-                    square_text,
-                )?;
+            if focus == cord {
+                square_fg = SELECTED_SQUARE.foreground.unwrap();
+                square_bg = SELECTED_SQUARE.background.unwrap();
             }
+
+            print_square(
+                w,
+                cursor,
+                (cursor.0 + TILE_SIZE.0, cursor.1 + TILE_SIZE.1),
+                square_fg,
+                square_bg,
+                square_text,
+            )?;
         }
+
+        print_effects(w, cursor, square.effects)?;
     }
 
     // Print drawn tile if any.
@@ -456,19 +1046,110 @@ where
         )?;
     }
 
-    // Print ply info
+    let inspected = if state.inspect {
+        game_state.tile_at(focus)
+    } else {
+        None
+    };
+    print_inspector(w, game_state, inspected, player_color)?;
+
+    print_status(w, state)
+}
+
+/// Print message and ply info under the board.
+fn print_status<W>(w: &mut W, state: &PlayState) -> Result<()>
+where
+    W: Write,
+{
+    let game_state = state.session.state();
+    let mut info = match (&game_state.game_over, game_state.win_reason) {
+        (Some(winner), Some(reason)) => format!("Game over: {:?}, {:?}", winner, reason),
+        _ => format!("Player to go: {:?}", game_state.ply),
+    };
+    // AI vs AI games wait for a key instead.
+    if game_state.game_over.is_none()
+        && state.player_color.is_some()
+        && !state.is_human(game_state.ply)
+    {
+        info.push_str(" (AI thinking)");
+    }
+    if let (None, Some(duke)) = (&game_state.game_over, game_state.duke_pos(game_state.ply)) {
+        let mut attackers: Vec<String> = logic::attackers_of(game_state, duke)
+            .iter()
+            .map(|(cord, _)| cord.to_string())
+            .collect();
+        attackers.dedup();
+        if !attackers.is_empty() {
+            info.push_str(&format!(", Duke in guard from {}", attackers.join(" ")));
+        }
+    }
+    if let Some(limit) = game_state.turn_limit {
+        info.push_str(&format!(", turn {}/{}", game_state.turn, limit));
+    }
+    if let Some(clock) = state.session.clock() {
+        let now = Instant::now();
+        for color in [TileColor::Black, TileColor::White] {
+            let remaining = format_time(clock.remaining(color, now));
+            info.push_str(&format!(", {:?} {}", color, remaining));
+        }
+    }
+    let message = state.message.as_deref().unwrap_or("");
     execute!(
         w,
         ResetColor,
         MoveTo(0, TERM_HEIGHT - 2),
-        Print(" ".repeat(TERM_WIDTH as usize)),
+        Print(format!("{: <width$}", message, width = TERM_WIDTH as usize)),
         MoveTo(0, TERM_HEIGHT - 1),
-        Print(format!("Player to go: {:?}", state.state.ply)),
+        Print(format!("{: <width$}", info, width = TERM_WIDTH as usize)),
     )?;
 
     Ok(())
 }
 
+/// Winner and reason of a finished game, e.g. "Black wins, Guardmate".
+fn game_over_text(state: &GameState) -> Option<String> {
+    let winner = match state.game_over.as_ref()? {
+        Winner::Color(color) => format!("{:?} wins", color),
+        Winner::Draw => "Draw".to_string(),
+    };
+    Some(match state.win_reason {
+        Some(reason) => format!("{}, {:?}", winner, reason),
+        None => winner,
+    })
+}
+
+/// Banner with the result across the middle of the board, and the keys
+/// that still work.
+fn print_game_over<W>(w: &mut W, state: &GameState) -> Result<()>
+where
+    W: Write,
+{
+    let text = match game_over_text(state) {
+        Some(text) => text,
+        None => return Ok(()),
+    };
+    let lines = [
+        String::new(),
+        format!("Game over: {}.", text),
+        "'r' - rematch with colors swapped, 'm' - main menu".to_string(),
+        String::new(),
+    ];
+    let top = SQUARE_SIZE.1 * logic::HEIGHT as u16 / 2 - 1;
+    for (i, line) in lines.iter().enumerate() {
+        queue!(
+            w,
+            MoveTo(0, top + i as u16),
+            style::PrintStyledContent(
+                format!("{: ^width$}", line, width = TERM_WIDTH as usize)
+                    .with(GAME_OVER_COLORS.foreground.unwrap())
+                    .on(GAME_OVER_COLORS.background.unwrap())
+            )
+        )?;
+    }
+    queue!(w, ResetColor)?;
+    Ok(())
+}
+
 /// See if selected tile can command focused tile
 fn can_command_tile(state: &mut PlayState) -> bool {
     // Commander has to be selected
@@ -482,7 +1163,7 @@ fn can_command_tile(state: &mut PlayState) -> bool {
     }
 
     let selected = state.selected.unwrap();
-    let actions = logic::get_tile_actions(&state.state, selected);
+    let actions = logic::get_tile_actions(state.session.state(), selected);
     for a in actions.iter() {
         match a {
             Action::Command(cd) if cd.command_tile_pos == state.focus => {
@@ -504,14 +1185,16 @@ fn try_tile_action(state: &mut PlayState) -> bool {
 
     let selected = state.selected.unwrap();
 
-    if state.state.board[selected.y as usize][selected.x as usize]
-        .tile
-        .is_none()
-    {
+    if state.session.square(selected).tile.is_none() {
         return false;
     }
 
-    let actions = logic::get_tile_actions(&state.state, selected);
+    // Only legal actions, player may not leave own Duke in check.
+    let legal = state.session.current_legal_actions().to_vec();
+    let actions: Vec<Action> = logic::get_tile_actions(state.session.state(), selected)
+        .into_iter()
+        .filter(|a| legal.contains(a))
+        .collect();
 
     for a in actions.iter() {
         match a {
@@ -522,19 +1205,17 @@ fn try_tile_action(state: &mut PlayState) -> bool {
             | Action::Strike(ad)
                 if ad.target_pos == state.focus && state.selected_command.is_none() =>
             {
-                logic::do_unsafe_action(&mut state.state, a);
                 state.selected = None;
-                return true;
+                return do_human_action(state, *a);
             }
             Action::Command(cd) if cd.target_pos == state.focus => {
                 // Command is two stage
                 if state.selected_command.is_some() {
                     let selected_command = state.selected_command.unwrap();
                     if selected_command == cd.command_tile_pos {
-                        logic::do_unsafe_action(&mut state.state, a);
                         state.selected = None;
                         state.selected_command = None;
-                        return true;
+                        return do_human_action(state, *a);
                     }
                 }
             }
@@ -546,14 +1227,11 @@ fn try_tile_action(state: &mut PlayState) -> bool {
 }
 
 fn draw_new_tile(state: &mut PlayState) -> bool {
-    let actions = logic::get_actions(&state.state);
+    let actions = state.session.current_legal_actions().to_vec();
 
     for a in actions {
         match a {
-            Action::NewFromBag => {
-                logic::do_unsafe_action(&mut state.state, &a);
-                return true;
-            }
+            Action::NewFromBag => return do_human_action(state, a),
             _ => {}
         }
     }
@@ -562,14 +1240,11 @@ fn draw_new_tile(state: &mut PlayState) -> bool {
 }
 
 fn place_new_tile(state: &mut PlayState) -> bool {
-    let actions = logic::get_actions(&state.state);
+    let actions = state.session.current_legal_actions().to_vec();
 
     for a in actions {
         match a {
-            Action::PlaceNew(c) if c == state.focus => {
-                logic::do_unsafe_action(&mut state.state, &a);
-                return true;
-            }
+            Action::PlaceNew(c) if c == state.focus => return do_human_action(state, a),
             _ => {}
         }
     }
@@ -577,230 +1252,364 @@ fn place_new_tile(state: &mut PlayState) -> bool {
     false
 }
 
-fn ai_turn(agent: &Agent, state: &mut GameState) -> Result<()> {
-    let a = alpha_beta::get_action(agent, state);
-
-    if a.is_none() {
-        // This means game over. But don't do anything now.
-        return Ok(());
+/// Do and record action of a human player. Announces a capture, or why the
+/// action was rejected.
+fn do_human_action(state: &mut PlayState, action: Action) -> bool {
+    match state.session.submit_action(action) {
+        Ok(outcome) => {
+            log_events(&outcome.delta);
+            state.message = capture_message(&outcome.delta);
+            true
+        }
+        Err(e) => {
+            state.message = Some(format!("Action rejected: {}.", e));
+            false
+        }
     }
+}
 
-    let mut a = a.unwrap();
+fn log_events(delta: &StateDelta) {
+    for event in delta.events() {
+        log::debug!("{:?}", event);
+    }
+}
 
-    logic::do_unsafe_action(state, &a);
+/// Announcement of the tile captured by an action, if any.
+fn capture_message(delta: &StateDelta) -> Option<String> {
+    delta.captured.as_ref().map(|captured| {
+        format!(
+            "{:?} {:?} captured on {}.",
+            captured.tile.color, captured.tile.kind, captured.square
+        )
+    })
+}
 
-    // New from bag action is 2 stage
-    match a {
-        Action::NewFromBag => {
-            a = alpha_beta::get_action(agent, state).expect("AI is unable to deploy drawn tile.");
-            logic::do_unsafe_action(state, &a);
+/// Write game record to the file given with "--record", if any.
+fn save_record(record: &GameRecord) {
+    if let Some(path) = arg_value("--record") {
+        let result = persist::write(
+            Path::new(&path),
+            record.to_text().as_bytes(),
+            persist::DEFAULT_LOCK_TIMEOUT,
+        );
+        if let Err(e) = result {
+            log::error!("Unable to write game record to {}: {}", path, e);
         }
-        _ => {}
     }
+}
 
-    Ok(())
+/// Save game to a new save file in the working directory. Returns message
+/// for the user.
+fn save_game(play_state: &PlayState) -> String {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |t| t.as_secs());
+    let path = format!("{}{}{}", SAVE_PREFIX, time, SAVE_EXTENSION);
+    match persist::write(
+        Path::new(&path),
+        play_state.to_save_text().as_bytes(),
+        persist::DEFAULT_LOCK_TIMEOUT,
+    ) {
+        Ok(()) => format!("Game saved to {}.", path),
+        Err(e) => {
+            log::error!("Unable to save game to {}: {}", path, e);
+            format!("Unable to save game: {}", e)
+        }
+    }
 }
 
-fn player_vs_ai<W>(w: &mut W, state: &mut State) -> Result<()>
-where
-    W: Write,
-{
-    let play_state: &mut PlayState;
+/// Handle key of the human player to go. Selects tiles, moves focus and
+/// does actions, the board is not drawn.
+fn human_turn(play_state: &mut PlayState, event: &Event) -> HumanInput {
+    let ply = play_state.session.ply;
+    let key = match event {
+        Event::Key(event) => event.code,
+        _ => return HumanInput::Continue,
+    };
 
-    match state {
-        State::Play(s) => {
-            play_state = s;
+    match key {
+        // Quit
+        KeyCode::Char('q') => return HumanInput::Quit,
+        KeyCode::Char('r') if play_state.session.game_over.is_none() => return HumanInput::Resign,
+        // Cancel
+        KeyCode::Esc => {
+            if play_state.selected_command.is_some() {
+                play_state.selected_command = None;
+            } else {
+                play_state.selected = None;
+            }
         }
-        _ => {
-            panic!("Illegal state.");
+        // Multi function key. Place new tile or select tile or perform action.
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            if !play_state.session.drawn().is_empty() {
+                place_new_tile(play_state);
+            } else if play_state.selected.is_some() {
+                // Try do action. This also works for commanded tile.
+                try_tile_action(play_state);
+            } else if let Some(tile) = play_state.session.tile_at(play_state.focus) {
+                // If not selected, select.
+                if tile.color == ply {
+                    play_state.selected = Some(play_state.focus);
+                }
+            }
         }
+        // Command tile
+        // Maybe just select second tile to command?
+        KeyCode::Char('c') if can_command_tile(play_state) => {
+            play_state.selected_command = Some(play_state.focus);
+        }
+        // Grab new tile from bag.
+        KeyCode::Char('n') if draw_new_tile(play_state) => {
+            play_state.selected_command = None;
+            play_state.selected = None;
+        }
+        KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down => {
+            move_focus(play_state, key);
+        }
+        _ => {}
     }
 
-    let player_color = play_state.player_color.expect("No player color.");
-
-    // Black player goes first.
-    if player_color == TileColor::White {
-        ai_turn(&play_state.agent, &mut play_state.state)?;
+    if play_state.session.ply != ply {
+        HumanInput::TurnDone
+    } else {
+        HumanInput::Continue
     }
+}
 
-    loop {
-        print_board(w, play_state)?;
-        w.flush()?;
-
-        match read()? {
-            Event::Key(event) if event.code == KeyCode::Char('q') =>
-            // Quit
-            {
-                *state = State::MainMenu;
-                break;
-            }
-            Event::Key(event) if event.code == KeyCode::Esc =>
-            // Cancel
-            {
-                if play_state.selected_command.is_some() {
-                    play_state.selected_command = None;
-                    continue;
-                }
+/// Move focus with arrow key, left and right as seen on the board.
+fn move_focus(play_state: &mut PlayState, key: KeyCode) {
+    let mirrored = play_state.view_color() == TileColor::White;
+    let focus = &mut play_state.focus;
+    match key {
+        KeyCode::Left if !mirrored => focus.x = (focus.x + logic::WIDTH - 1) % logic::WIDTH,
+        KeyCode::Left => focus.x = (focus.x + logic::WIDTH + 1) % logic::WIDTH,
+        KeyCode::Right if !mirrored => focus.x = (focus.x + logic::WIDTH + 1) % logic::WIDTH,
+        KeyCode::Right => focus.x = (focus.x + logic::WIDTH - 1) % logic::WIDTH,
+        KeyCode::Up => focus.y = (focus.y + logic::HEIGHT + 1) % logic::HEIGHT,
+        KeyCode::Down => focus.y = (focus.y + logic::HEIGHT - 1) % logic::HEIGHT,
+        _ => {}
+    }
+}
 
-                play_state.selected = None;
-            }
-            Event::Key(event)
-                if event.code == KeyCode::Enter || event.code == KeyCode::Char(' ') =>
-            // Multi function key. Place new tile or select tile or perform action.
-            {
-                if !play_state.state.drawn().is_empty() {
-                    if place_new_tile(play_state) {
-                        // If success, let AI player do her turn.
-                        ai_turn(&play_state.agent, &mut play_state.state)?;
-                    }
-                } else {
-                    let square = &play_state.state.board[play_state.focus.y as usize]
-                        [play_state.focus.x as usize];
-
-                    // If selected, do action stuff
-                    if play_state.selected.is_some() {
-                        // Try do action. This also works for commanded tile.
-                        if try_tile_action(play_state) {
-                            // If success, let AI player do her turn.
-                            ai_turn(&play_state.agent, &mut play_state.state)?;
-                        }
-                    } else if square.tile.is_some() {
-                        // If not selected, select.
-                        let tile = &square.tile.unwrap();
-                        if tile.color == player_color {
-                            play_state.selected = Some(play_state.focus);
-                        }
-                    }
-                }
-            }
-            // Command tile
-            Event::Key(event) if event.code == KeyCode::Char('c') => {
-                if play_state.selected.is_some() {
-                    // Maybe just select second tile to command?
-                    if can_command_tile(play_state) {
-                        play_state.selected_command = Some(play_state.focus);
-                    }
-                }
-            }
-            // Grab new tile from bag.
-            Event::Key(event) if event.code == KeyCode::Char('n') => {
-                if draw_new_tile(play_state) {
-                    play_state.selected_command = None;
-                    play_state.selected = None;
-                }
-            }
-            Event::Key(event) if event.code == KeyCode::Left => {
-                if player_color == TileColor::Black {
-                    play_state.focus.x = (play_state.focus.x + logic::WIDTH - 1) % logic::WIDTH;
-                } else {
-                    play_state.focus.x = (play_state.focus.x + logic::WIDTH + 1) % logic::WIDTH;
-                }
-            }
-            Event::Key(event) if event.code == KeyCode::Right => {
-                if player_color == TileColor::Black {
-                    play_state.focus.x = (play_state.focus.x + logic::WIDTH + 1) % logic::WIDTH;
-                } else {
-                    play_state.focus.x = (play_state.focus.x + logic::WIDTH - 1) % logic::WIDTH;
-                }
-            }
-            Event::Key(event) if event.code == KeyCode::Up => {
-                play_state.focus.y = (play_state.focus.y + logic::HEIGHT + 1) % logic::HEIGHT;
-            }
-            Event::Key(event) if event.code == KeyCode::Down => {
-                play_state.focus.y = (play_state.focus.y + logic::HEIGHT - 1) % logic::HEIGHT;
-            }
-            _ => {}
+/// Blank screen between turns of a hotseat game, so the next player does not
+/// see the board before taking the seat.
+fn hotseat_interstitial<W>(w: &mut W, color: TileColor) -> Result<()>
+where
+    W: Write,
+{
+    execute!(
+        w,
+        ResetColor,
+        terminal::Clear(terminal::ClearType::All),
+        MoveTo(0, TERM_HEIGHT / 2),
+        Print(format!(
+            "{: ^width$}",
+            format!("Press any key to start {:?}'s turn.", color),
+            width = TERM_WIDTH as usize
+        )),
+    )?;
+    loop {
+        if let Event::Key(_) = read()? {
+            break;
         }
     }
-
+    execute!(w, terminal::Clear(terminal::ClearType::All))?;
     Ok(())
 }
 
-fn ai_vs_ai<W>(w: &mut W, state: &mut State) -> Result<()>
+/// Ask question on the message line, true if answered with 'y'.
+fn confirm<W>(w: &mut W, question: &str) -> Result<bool>
+where
+    W: Write,
+{
+    execute!(
+        w,
+        ResetColor,
+        MoveTo(0, TERM_HEIGHT - 2),
+        Print(format!(
+            "{: <width$}",
+            question,
+            width = TERM_WIDTH as usize
+        )),
+    )?;
+    loop {
+        if let Event::Key(key) = read()? {
+            return Ok(key.code == KeyCode::Char('y'));
+        }
+    }
+}
+
+/// Game loop of all modes. Humans play with `human_turn`. In games with a
+/// human, AI plays after the human, once min_ai_turn has passed. AI vs AI
+/// games go one turn per enter or space.
+/// Play game of state. Games with a human player are timed if clock is set,
+/// the clock is copied for the game.
+fn play<W>(
+    w: &mut W,
+    state: &mut State,
+    clock: Option<GameClock>,
+    min_ai_turn: Duration,
+) -> Result<()>
 where
     W: Write,
 {
     let play_state: &mut PlayState;
-    let black_ai: &Agent;
-    let white_ai: &Agent;
 
     match state {
-        State::Play(s) => {
-            play_state = s;
+        State::Play(data) => {
+            play_state = data;
         }
         _ => {
             panic!("Illegal state.");
         }
     }
+    execute!(w, terminal::Clear(terminal::ClearType::All))?;
 
-    if play_state.agent.color == TileColor::Black {
-        black_ai = &play_state.agent;
-        white_ai = play_state.agent2.as_ref().unwrap();
-    } else {
-        black_ai = play_state.agent2.as_ref().unwrap();
-        white_ai = &play_state.agent;
+    let hotseat = play_state.player2_color.is_some();
+    let pass_screen = hotseat && play_state.pass_screen;
+    let ai_only = play_state.player_color.is_none();
+    if let Some(clock) = clock.filter(|_| !ai_only) {
+        play_state.session.set_clock(clock);
     }
-
-    let mut current_ai = black_ai;
+    let mut pacing = AiPacing::new(min_ai_turn);
 
     loop {
+        play_state.update_clock();
+        let ply = play_state.session.ply;
+        let game_over = play_state.session.game_over.is_some();
+
+        let ai_to_go = !game_over && !ai_only && !play_state.is_human(ply);
+        let mut deadline = None;
+        if ai_to_go {
+            let now = Instant::now();
+            let wait = play_state.paced_ai_turn(&mut pacing, now);
+            if wait.is_zero() {
+                continue;
+            }
+            deadline = Some(now + wait);
+        }
+
         print_board(w, play_state)?;
+        if game_over {
+            print_game_over(w, play_state.session.state())?;
+        }
         w.flush()?;
 
-        match read()? {
-            Event::Key(event) if event.code == KeyCode::Char('q') => {
-                *state = State::MainMenu;
+        // Until the AI may play, keys are only taken to look at the board.
+        let mut timed_out = false;
+        while play_state.clock_runs() || deadline.is_some() {
+            let timeout = deadline.map_or(CLOCK_REFRESH, |deadline| {
+                deadline
+                    .saturating_duration_since(Instant::now())
+                    .min(CLOCK_REFRESH)
+            });
+            if poll(timeout)? {
                 break;
             }
-            Event::Key(event)
-                if event.code == KeyCode::Enter || event.code == KeyCode::Char(' ') =>
-            {
-                ai_turn(current_ai, &mut play_state.state)?;
-                if current_ai.color == TileColor::Black {
-                    current_ai = white_ai;
-                } else {
-                    current_ai = black_ai;
-                }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                timed_out = true;
+                break;
             }
-            Event::Key(event) if event.code == KeyCode::Left => {
-                play_state.focus.x = (play_state.focus.x + logic::WIDTH - 1) % logic::WIDTH;
+            play_state.update_clock();
+            print_status(w, play_state)?;
+            w.flush()?;
+        }
+        if timed_out {
+            continue;
+        }
+        let event = read()?;
+        play_state.message = None;
+        if let Event::Key(key) = event {
+            if key.code == KeyCode::Char('s') {
+                play_state.message = Some(save_game(play_state));
+                play_state.inspect = false;
+                continue;
             }
-            Event::Key(event) if event.code == KeyCode::Right => {
-                play_state.focus.x = (play_state.focus.x + logic::WIDTH + 1) % logic::WIDTH;
+            if key.code == KeyCode::Char('t') {
+                play_state.show_threats = !play_state.show_threats;
+                play_state.inspect = false;
+                continue;
             }
-            Event::Key(event) if event.code == KeyCode::Up => {
-                play_state.focus.y = (play_state.focus.y + logic::HEIGHT + 1) % logic::HEIGHT;
+            if key.code == KeyCode::Char('i') && !play_state.inspect {
+                if play_state.session.tile_at(play_state.focus).is_some() {
+                    play_state.inspect = true;
+                } else {
+                    play_state.message = Some("No tile to inspect.".to_string());
+                }
+                continue;
             }
-            Event::Key(event) if event.code == KeyCode::Down => {
-                play_state.focus.y = (play_state.focus.y + logic::HEIGHT - 1) % logic::HEIGHT;
+        }
+        play_state.inspect = false;
+
+        // Finished games only take keys to look at the board, or to go on.
+        if game_over {
+            match event {
+                Event::Key(event)
+                    if event.code == KeyCode::Char('q') || event.code == KeyCode::Char('m') =>
+                {
+                    save_record(play_state.session.record());
+                    *state = State::MainMenu;
+                    break;
+                }
+                Event::Key(event) if event.code == KeyCode::Char('r') => {
+                    save_record(play_state.session.record());
+                    *play_state = play_state.rematch();
+                    pacing.done();
+                    if let Some(clock) = clock.filter(|_| !ai_only) {
+                        play_state.session.set_clock(clock);
+                    }
+                    execute!(w, terminal::Clear(terminal::ClearType::All))?;
+                    if pass_screen {
+                        hotseat_interstitial(w, play_state.session.ply)?;
+                    }
+                }
+                Event::Key(event) => move_focus(play_state, event.code),
+                _ => {}
             }
-            _ => {}
+            continue;
         }
-    }
-
-    Ok(())
-}
 
-fn play<W>(w: &mut W, state: &mut State) -> Result<()>
-where
-    W: Write,
-{
-    let play_state: &mut PlayState;
-
-    match state {
-        State::Play(data) => {
-            play_state = data;
-        }
-        _ => {
-            panic!("Illegal state.");
+        if ai_only || ai_to_go {
+            match event {
+                Event::Key(event) if event.code == KeyCode::Char('q') => {
+                    save_record(play_state.session.record());
+                    *state = State::MainMenu;
+                    break;
+                }
+                Event::Key(event)
+                    if ai_only
+                        && !game_over
+                        && (event.code == KeyCode::Enter || event.code == KeyCode::Char(' ')) =>
+                {
+                    play_state.ai_turn();
+                }
+                Event::Key(event) => move_focus(play_state, event.code),
+                _ => {}
+            }
+            continue;
         }
-    }
-    execute!(w, terminal::Clear(terminal::ClearType::All))?;
 
-    if play_state.player_color.is_some() {
-        player_vs_ai(w, state)?;
-    } else {
-        ai_vs_ai(w, state)?;
+        match human_turn(play_state, &event) {
+            HumanInput::Quit => {
+                save_record(play_state.session.record());
+                *state = State::MainMenu;
+                break;
+            }
+            HumanInput::Resign => {
+                if confirm(w, "Resign? Press y to confirm.")? {
+                    play_state.session.resign(ply);
+                    play_state.message = Some(format!("{:?} resigned.", ply));
+                } else {
+                    play_state.message = Some("Resignation cancelled.".to_string());
+                }
+            }
+            HumanInput::TurnDone if pass_screen && play_state.session.game_over.is_none() => {
+                // The next player's time runs from here.
+                play_state.update_clock();
+                hotseat_interstitial(w, play_state.session.ply)?;
+            }
+            _ => {}
+        }
     }
 
     Ok(())
@@ -809,36 +1618,33 @@ where
 const AI_SCREEN: &str = r#"Configure AI
 
 Controls:
- - Type numerical value and hit enter.
+ - Type numerical value and hit enter. Empty level asks for search depth
+   and duration instead, empty depth is the Normal depth.
  - 'q' - quit or return to this menu
 
 "#;
 
-fn ai_screen<W>(w: &mut W, state: &mut State) -> Result<()>
+/// Calibration of AI search depth, and whether it was done on other hardware.
+struct AiCalibration {
+    calibration: Calibration,
+    stale: bool,
+}
+
+fn ai_screen<W>(
+    w: &mut W,
+    state: &mut State,
+    variant: Variant,
+    calibration: &AiCalibration,
+) -> Result<()>
 where
     W: Write,
 {
-    let player_color: Option<TileColor>;
-    let ai_color: TileColor;
-
-    match state {
-        State::AiMenu(Some(c)) => {
-            if *c == TileColor::Black {
-                player_color = Some(TileColor::Black);
-                ai_color = TileColor::White;
-            } else {
-                player_color = Some(TileColor::White);
-                ai_color = TileColor::Black;
-            }
-        }
-        State::AiMenu(None) => {
-            player_color = None;
-            ai_color = TileColor::White;
-        }
+    let player_color = match state {
+        State::AiMenu(color) => *color,
         _ => {
             panic!("Illegal state.");
         }
-    }
+    };
 
     queue!(
         w,
@@ -852,6 +1658,25 @@ where
         queue!(w, Print(line), cursor::MoveToNextLine(1))?;
     }
 
+    let mut suggested = String::from("Suggested depths:");
+    for difficulty in Difficulty::ALL {
+        suggested += &format!(
+            " {} {},",
+            difficulty,
+            calibration.calibration.depth_for_difficulty(difficulty)
+        );
+    }
+    suggested.pop();
+    queue!(w, Print(suggested), cursor::MoveToNextLine(1))?;
+    if calibration.stale {
+        queue!(
+            w,
+            Print("Hardware has changed since calibration, run with --calibrate to recalibrate."),
+            cursor::MoveToNextLine(1)
+        )?;
+    }
+    queue!(w, cursor::MoveToNextLine(1))?;
+
     w.flush()?;
 
     terminal::disable_raw_mode()?;
@@ -861,11 +1686,49 @@ where
     let depth: Option<u8>;
     let duration_ms: Option<Duration>;
 
+    let level = loop {
+        execute!(
+            w,
+            Print(format!("AI level [{}-{}]: ", MIN_LEVEL, MAX_LEVEL))
+        )?;
+
+        r.read_line(&mut input)?;
+
+        if input.trim().is_empty() {
+            break None;
+        }
+        match input.trim().parse::<u8>() {
+            Ok(n) if (MIN_LEVEL..=MAX_LEVEL).contains(&n) => break Some(n),
+            _ => input.clear(),
+        }
+    };
+    if let Some(level) = level {
+        *state = State::Play(Box::new(PlayState::new(
+            variant,
+            player_color,
+            None,
+            Some(AiSettings::Level(level)),
+        )));
+        terminal::enable_raw_mode()?;
+        return Ok(());
+    }
+
+    input = String::new();
+
     loop {
         execute!(w, Print("Alpha Beta AI search depth: ".to_string()))?;
 
         r.read_line(&mut input)?;
 
+        if input.trim().is_empty() {
+            depth = Some(
+                calibration
+                    .calibration
+                    .depth_for_difficulty(Difficulty::Normal),
+            );
+            break;
+        }
+
         match input.trim().parse::<u8>() {
             Ok(n) => {
                 if n == 0 {
@@ -903,34 +1766,17 @@ where
         }
     }
 
-    if player_color.is_some() {
-        *state = State::Play(PlayState {
-            state: GameState::new(),
-            agent: Agent::new(ai_color, depth, duration_ms),
-            agent2: None,
-            player_color: player_color,
-            focus: Coordinate {
-                x: logic::WIDTH / 2,
-                y: 0,
-            },
-            selected: None,
-            selected_command: None,
-        });
-    } else {
-        *state = State::Play(PlayState {
-            state: GameState::new(),
-            agent: Agent::new(TileColor::Black, depth, duration_ms),
-            // There is only one kind of AI for now.
-            agent2: Some(Agent::new(TileColor::White, depth, duration_ms)),
-            player_color: None,
-            focus: Coordinate {
-                x: logic::WIDTH / 2,
-                y: 0,
-            },
-            selected: None,
-            selected_command: None,
-        });
-    }
+    // There is only one kind of AI for now.
+    let ai = AiSettings::Search {
+        depth,
+        duration: duration_ms,
+    };
+    *state = State::Play(Box::new(PlayState::new(
+        variant,
+        player_color,
+        None,
+        Some(ai),
+    )));
 
     terminal::enable_raw_mode()?;
 
@@ -948,10 +1794,23 @@ Select color:
 1. Black
 2. White
 3. AI vs AI
+4. Hotseat, two players
+5. Load game
+
+'v' - switch variant
+'p' - switch pass screen between turns of hotseat games
 
+In game: 's' - save, 'i' - show moves of focused tile, 't' - show threats,
+'r' - resign
+Game over: 'r' - rematch with colors swapped, 'm' - return to this menu
 "#;
 
-fn main_menu<W>(w: &mut W, state: &mut State) -> Result<()>
+fn main_menu<W>(
+    w: &mut W,
+    state: &mut State,
+    variant: &mut Variant,
+    pass_screen: &mut bool,
+) -> Result<()>
 where
     W: Write,
 {
@@ -973,6 +1832,15 @@ where
     for line in MAIN_MENU.split('\n') {
         queue!(w, style::Print(line), cursor::MoveToNextLine(1))?;
     }
+    queue!(
+        w,
+        style::Print(format!("Variant: {}", variant)),
+        cursor::MoveToNextLine(1),
+        style::Print(format!(
+            "Pass screen: {}",
+            if *pass_screen { "on" } else { "off" }
+        ))
+    )?;
 
     w.flush()?;
 
@@ -994,6 +1862,30 @@ where
                 *state = State::AiMenu(None);
                 break;
             }
+            Event::Key(event) if event.code == KeyCode::Char('4') => {
+                let mut play_state = PlayState::new(
+                    *variant,
+                    Some(TileColor::Black),
+                    Some(TileColor::White),
+                    None,
+                );
+                play_state.pass_screen = *pass_screen;
+                *state = State::Play(Box::new(play_state));
+                break;
+            }
+            Event::Key(event) if event.code == KeyCode::Char('5') => {
+                *state = State::LoadMenu(None);
+                break;
+            }
+            Event::Key(event) if event.code == KeyCode::Char('v') => {
+                // Stay in main menu, it is drawn again with the new variant.
+                *variant = variant.next();
+                break;
+            }
+            Event::Key(event) if event.code == KeyCode::Char('p') => {
+                *pass_screen = !*pass_screen;
+                break;
+            }
             _ => {}
         }
     }
@@ -1001,7 +1893,121 @@ where
     Ok(())
 }
 
-fn run<W>(w: &mut W) -> Result<()>
+/// Save files in directory, newest first.
+fn save_files(directory: &Path) -> io::Result<Vec<String>> {
+    let mut files: Vec<String> = std::fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.starts_with(SAVE_PREFIX) && name.ends_with(SAVE_EXTENSION))
+        .collect();
+    // Same length names sort by time, longer times are newer.
+    files.sort_by(|a, b| b.len().cmp(&a.len()).then(b.cmp(a)));
+    Ok(files)
+}
+
+const LOAD_MENU: &str = r#"Load game
+
+- Press number to load game.
+- 'q' - return to main menu
+
+"#;
+
+fn load_menu<W>(w: &mut W, state: &mut State) -> Result<()>
+where
+    W: Write,
+{
+    let error = match state {
+        State::LoadMenu(error) => error.take(),
+        _ => {
+            panic!("Illegal state.");
+        }
+    };
+
+    queue!(
+        w,
+        style::ResetColor,
+        terminal::Clear(terminal::ClearType::All),
+        cursor::Hide,
+        cursor::MoveTo(0, 0)
+    )?;
+    for line in LOAD_MENU.split('\n') {
+        queue!(w, style::Print(line), cursor::MoveToNextLine(1))?;
+    }
+
+    let files = match save_files(Path::new(".")) {
+        Ok(files) => files,
+        Err(e) => {
+            queue!(
+                w,
+                style::Print(format!("Unable to list saves: {}", e)),
+                cursor::MoveToNextLine(1)
+            )?;
+            Vec::new()
+        }
+    };
+    let files = &files[..files.len().min(LOAD_MENU_SIZE)];
+    if files.is_empty() {
+        queue!(
+            w,
+            style::Print("No saved games."),
+            cursor::MoveToNextLine(1)
+        )?;
+    }
+    for (i, file) in files.iter().enumerate() {
+        queue!(
+            w,
+            style::Print(format!("{}. {}", i + 1, file)),
+            cursor::MoveToNextLine(1)
+        )?;
+    }
+    if let Some(error) = error {
+        queue!(
+            w,
+            cursor::MoveToNextLine(1),
+            style::Print(error),
+            cursor::MoveToNextLine(1)
+        )?;
+    }
+    w.flush()?;
+
+    loop {
+        match read()? {
+            Event::Key(event) if event.code == KeyCode::Char('q') => {
+                *state = State::MainMenu;
+                break;
+            }
+            Event::Key(event) => {
+                let file = match event.code {
+                    KeyCode::Char(c) => c
+                        .to_digit(10)
+                        .and_then(|n| files.get((n as usize).checked_sub(1)?)),
+                    _ => None,
+                };
+                if let Some(file) = file {
+                    *state = match std::fs::read_to_string(file) {
+                        Ok(text) => match PlayState::from_save_text(&text) {
+                            Ok(play_state) => State::Play(Box::new(play_state)),
+                            Err(e) => {
+                                State::LoadMenu(Some(format!("Unable to load {}: {}", file, e)))
+                            }
+                        },
+                        Err(e) => State::LoadMenu(Some(format!("Unable to read {}: {}", file, e))),
+                    };
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn run<W>(
+    w: &mut W,
+    calibration: &AiCalibration,
+    clock: Option<GameClock>,
+    min_ai_turn: Duration,
+) -> Result<()>
 where
     W: Write,
 {
@@ -1015,18 +2021,23 @@ where
         SetTitle("Rusty Duke")
     )?;
 
-    let mut state = &mut State::MainMenu;
+    let state = &mut State::MainMenu;
+    let mut variant = Variant::Standard;
+    let mut pass_screen = true;
 
     loop {
         match state {
             State::MainMenu => {
-                main_menu(w, &mut state)?;
+                main_menu(w, state, &mut variant, &mut pass_screen)?;
             }
             State::AiMenu(_) => {
-                ai_screen(w, &mut state)?;
+                ai_screen(w, state, variant, calibration)?;
+            }
+            State::LoadMenu(_) => {
+                load_menu(w, state)?;
             }
             State::Play(_) => {
-                play(w, &mut state)?;
+                play(w, state, clock, min_ai_turn)?;
             }
             State::Exit => {
                 break;
@@ -1049,13 +2060,873 @@ where
 
 // FIXME: Terminal cleanup on SIGTERM.
 
+/// Value of command line argument, given as "--name value" or "--name=value".
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args();
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix(name).and_then(|a| a.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// Settings of the log file. Defaults are overridden by `LOG_CONFIG_FILE`,
+/// then by environment variables, then by "--log-level".
+///
+/// The file has a `key value` line per setting, lines starting with '#' are
+/// ignored:
+///
+/// ```text
+/// level info, rusty_duke_logic::ai=debug
+/// file_size 1000000
+/// files_kept 3
+/// directory logs
+/// ```
+///
+/// Environment variables are the keys in upper case with prefix
+/// `RUSTY_DUKE_LOG_`, e.g. `RUSTY_DUKE_LOG_LEVEL`.
+#[derive(Debug, Clone, PartialEq)]
+struct LogConfig {
+    /// Log level as flexi_logger spec, so verbosity can be set per module,
+    /// e.g. "info, rusty_duke_logic::ai=debug".
+    level: String,
+    /// Log file is rotated when it reaches this size in bytes.
+    file_size: u64,
+    /// Rotated log files kept, older ones are deleted.
+    files_kept: usize,
+    /// Directory of the log files, working directory if `None`.
+    directory: Option<PathBuf>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            level: DEFAULT_LOG_LEVEL.to_string(),
+            file_size: LOG_FILE_SIZE,
+            files_kept: LOG_FILES_KEPT,
+            directory: None,
+        }
+    }
+}
+
+impl LogConfig {
+    /// Settings from the file, environment and command line.
+    fn load() -> std::result::Result<LogConfig, String> {
+        let mut config = LogConfig::default();
+        if let Ok(text) = std::fs::read_to_string(LOG_CONFIG_FILE) {
+            config
+                .apply_text(&text)
+                .map_err(|e| format!("{}: {}", LOG_CONFIG_FILE, e))?;
+        }
+        config.apply_env(|name| std::env::var(name).ok())?;
+        if let Some(level) = arg_value("--log-level") {
+            config.level = level;
+        }
+        Ok(config)
+    }
+
+    /// Set value of key, as in `LOG_CONFIG_FILE`.
+    fn set(&mut self, key: &str, value: &str) -> std::result::Result<(), String> {
+        let invalid = || format!("Invalid {}: {}", key, value);
+        match key {
+            "level" => self.level = value.to_string(),
+            "file_size" => self.file_size = value.parse().map_err(|_| invalid())?,
+            "files_kept" => self.files_kept = value.parse().map_err(|_| invalid())?,
+            "directory" => self.directory = Some(PathBuf::from(value)),
+            _ => return Err(format!("Unknown log setting: {}", key)),
+        }
+        Ok(())
+    }
+
+    /// Override settings with the lines of a `LOG_CONFIG_FILE`.
+    fn apply_text(&mut self, text: &str) -> std::result::Result<(), String> {
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            self.set(key, value.trim())?;
+        }
+        Ok(())
+    }
+
+    /// Override settings with environment variables, var gets their values.
+    fn apply_env<F: Fn(&str) -> Option<String>>(
+        &mut self,
+        var: F,
+    ) -> std::result::Result<(), String> {
+        for key in ["level", "file_size", "files_kept", "directory"] {
+            let name = format!("RUSTY_DUKE_LOG_{}", key.to_uppercase());
+            if let Some(value) = var(&name) {
+                self.set(key, value.trim())
+                    .map_err(|e| format!("{}: {}", name, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Start logging to rotated files.
+    fn start(&self) -> std::result::Result<LoggerHandle, FlexiLoggerError> {
+        Logger::try_with_str(&self.level)?
+            .log_to_file(FileSpec::default().o_directory(self.directory.as_ref()))
+            .rotate(
+                Criterion::Size(self.file_size),
+                Naming::Numbers,
+                Cleanup::KeepLogFiles(self.files_kept),
+            )
+            .cleanup_in_background_thread(false)
+            .start()
+    }
+}
+
+/// Validate the built in tile set of every variant and print diagnostics, and
+/// compare tile definitions with the printed cards. Returns true if there
+/// were errors.
+/// Clock from "minutes[+increment seconds]".
+fn parse_clock(value: &str) -> Option<GameClock> {
+    let (minutes, increment) = value.split_once('+').unwrap_or((value, "0"));
+    let minutes: u64 = minutes.trim().parse().ok()?;
+    let increment: u64 = increment.trim().parse().ok()?;
+    if minutes == 0 {
+        return None;
+    }
+    Some(GameClock::new(
+        Duration::from_secs(minutes * 60),
+        Duration::from_secs(increment),
+    ))
+}
+
+fn validate_tiles() -> bool {
+    let mismatches = cards::check(&logic::TILE_ACTIONS);
+    println!("Cards:");
+    for mismatch in mismatches.iter() {
+        println!("{}", mismatch);
+    }
+    println!("{} mismatch(es).", mismatches.len());
+    let mut errors = !mismatches.is_empty();
+    for variant in Variant::ALL {
+        let diagnostics = validate::validate_ruleset(&RulesetSource::for_rules(&variant.rules()));
+        println!("{}:", variant);
+        for diagnostic in diagnostics.iter() {
+            println!("{}", diagnostic);
+        }
+        println!("{} diagnostic(s).", diagnostics.len());
+        errors |= validate::has_errors(&diagnostics);
+    }
+    errors
+}
+
+/// Count action tree nodes to depth, per action and in total, also of legal
+/// actions only, and print how long it took. Counts from the start position, or from the end of the text
+/// record given with "--perft-from".
+fn run_perft(depth: &str) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let depth: u8 = depth.parse()?;
+    let state = match arg_value("--perft-from") {
+        Some(path) => GameRecord::from_text(&std::fs::read_to_string(path)?)?.replay(),
+        None => GameState::new_seeded(0),
+    };
+
+    for (action, nodes) in perft::divide(&state, depth, DrawMode::Expand) {
+        println!("{}: {}", action_to_notation(&state, &action), nodes);
+    }
+    for (draws, legal) in [
+        (DrawMode::Expand, false),
+        (DrawMode::Exclude, false),
+        (DrawMode::Expand, true),
+    ] {
+        let start = Instant::now();
+        let nodes = if legal {
+            perft::perft(&state, depth, draws)
+        } else {
+            perft::count_action_nodes(&state, depth, draws)
+        };
+        let elapsed = start.elapsed();
+        println!(
+            "Depth {}, draws {:?}{}: {} nodes in {} ms, {:.0} nodes/s.",
+            depth,
+            draws,
+            if legal { ", legal actions only" } else { "" },
+            nodes,
+            elapsed.as_millis(),
+            nodes as f64 / elapsed.as_secs_f64().max(1e-9)
+        );
+    }
+    Ok(())
+}
+
+/// Result of one self play game.
+struct SelfplayGame {
+    seed: u64,
+    winner: Option<Winner>,
+    reason: Option<WinReason>,
+    turns: u16,
+    duration: Duration,
+    /// Why the game was stopped in an illegal state.
+    error: Option<String>,
+}
+
+impl SelfplayGame {
+    /// One line of JSON.
+    fn to_json(&self, game: u64) -> String {
+        let winner = match &self.winner {
+            Some(Winner::Color(TileColor::Black)) => "\"black\"".to_string(),
+            Some(Winner::Color(TileColor::White)) => "\"white\"".to_string(),
+            Some(Winner::Draw) => "\"draw\"".to_string(),
+            None => "null".to_string(),
+        };
+        let optional = |value: Option<String>| match value {
+            Some(value) => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"game\":{},\"seed\":{},\"winner\":{},\"reason\":{},\"turns\":{},\"duration_ms\":{},\"error\":{}}}",
+            game,
+            self.seed,
+            winner,
+            optional(self.reason.as_ref().map(|reason| format!("{:?}", reason))),
+            self.turns,
+            self.duration.as_millis(),
+            optional(self.error.clone())
+        )
+    }
+}
+
+/// Self play agent of color, depth from "--<color>-depth" and time per move
+/// from "--<color>-time-ms".
+fn selfplay_agent(color: TileColor) -> std::result::Result<Agent, Box<dyn std::error::Error>> {
+    let name = format!("{:?}", color).to_lowercase();
+    let depth = match arg_value(&format!("--{}-depth", name)) {
+        Some(depth) => depth.parse()?,
+        None => SELFPLAY_DEPTH,
+    };
+    let duration = match arg_value(&format!("--{}-time-ms", name)) {
+        Some(ms) => Some(Duration::from_millis(ms.parse()?)),
+        None => None,
+    };
+    Ok(Agent::new(color, Some(depth), duration))
+}
+
+/// Play a game between agents to the end. Actions are validated, the game
+/// stops at the first illegal action or when an agent finds none.
+fn selfplay_game(rules: &GameRules, seed: u64, agents: &mut [Agent; 2]) -> SelfplayGame {
+    let start = Instant::now();
+    let mut state = GameState::new_with_rules(rules, Some(seed));
+    let mut error = None;
+    while state.game_over.is_none() {
+        let action = match agents[state.ply as usize].choose_action(&state) {
+            Some(action) => action,
+            None => {
+                error = Some(format!("{:?} found no action", state.ply));
+                break;
+            }
+        };
+        if let Err(e) = logic::do_action(&mut state, &action) {
+            error = Some(format!("{:?}: {:?}", action, e));
+            break;
+        }
+    }
+    SelfplayGame {
+        seed,
+        winner: state.game_over.clone(),
+        reason: state.win_reason,
+        turns: state.turn,
+        duration: start.elapsed(),
+        error,
+    }
+}
+
+/// Play games between AI agents without drawing the board. Prints one JSON
+/// line per game, to stdout or to the file given with "--out", and a summary.
+/// Game n is seeded with "--seed" plus n. Returns true if any game ended in
+/// an illegal state.
+fn run_selfplay(games: &str) -> std::result::Result<bool, Box<dyn std::error::Error>> {
+    let games: u64 = games.parse()?;
+    let seed: u64 = match arg_value("--seed") {
+        Some(seed) => seed.parse()?,
+        None => 0,
+    };
+    let variant = match arg_value("--variant") {
+        Some(name) => Variant::from_name(&name).ok_or(format!("Unknown variant: {}", name))?,
+        None => Variant::Standard,
+    };
+    let mut rules = variant.rules();
+    rules.turn_limit = rules.turn_limit.or(Some(SELFPLAY_TURN_LIMIT));
+    let mut agents = [
+        selfplay_agent(TileColor::Black)?,
+        selfplay_agent(TileColor::White)?,
+    ];
+    let mut out: Box<dyn Write> = match arg_value("--out") {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+
+    // Wins, losses and draws per color.
+    let mut results = [[0u64; 3]; 2];
+    let mut errors = 0;
+    for game in 0..games {
+        let result = selfplay_game(&rules, seed.wrapping_add(game), &mut agents);
+        writeln!(out, "{}", result.to_json(game))?;
+        match result.winner {
+            Some(Winner::Color(color)) => {
+                results[color as usize][0] += 1;
+                results[color.opponent() as usize][1] += 1;
+            }
+            Some(Winner::Draw) => {
+                results[0][2] += 1;
+                results[1][2] += 1;
+            }
+            None => {}
+        }
+        if result.error.is_some() {
+            errors += 1;
+        }
+    }
+    out.flush()?;
+
+    println!("{:<8}{:>8}{:>8}{:>8}", "", "Wins", "Losses", "Draws");
+    for color in [TileColor::Black, TileColor::White] {
+        let [wins, losses, draws] = results[color as usize];
+        println!(
+            "{:<8}{:>8}{:>8}{:>8}",
+            format!("{:?}", color),
+            wins,
+            losses,
+            draws
+        );
+    }
+    if errors > 0 {
+        println!("{} game(s) ended in an illegal state.", errors);
+    }
+    Ok(errors > 0)
+}
+
+/// Run calibration benchmark, print estimated depths and save result.
+fn run_calibration() -> std::result::Result<Calibration, persist::PersistError> {
+    println!(
+        "Calibrating AI search, takes about {} s.",
+        calibrate::BENCHMARK_BUDGET.as_secs()
+    );
+    let calibration = calibrate::calibrate(calibrate::BENCHMARK_BUDGET);
+    println!(
+        "{:.0} nodes/s per thread, branching factor {:.2}, {} core(s).",
+        calibration.nodes_per_second, calibration.branching_factor, calibration.fingerprint.cores
+    );
+    for difficulty in Difficulty::ALL {
+        let depth = calibration.depth_for_difficulty(difficulty);
+        println!(
+            "{}: depth {}, about {} ms per move.",
+            difficulty,
+            depth,
+            calibration.estimate_time(depth).as_millis()
+        );
+    }
+    calibration.save(Path::new(calibrate::DEFAULT_CALIBRATION_FILE))?;
+    Ok(calibration)
+}
+
+/// Stored calibration, or a new one on first run.
+fn load_calibration() -> std::result::Result<AiCalibration, persist::PersistError> {
+    match Calibration::load(Path::new(calibrate::DEFAULT_CALIBRATION_FILE))? {
+        Some(calibration) => {
+            let stale = calibration.needs_recalibration(&HardwareFingerprint::measure());
+            Ok(AiCalibration { calibration, stale })
+        }
+        None => Ok(AiCalibration {
+            calibration: run_calibration()?,
+            stale: false,
+        }),
+    }
+}
+
 fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
-    // Init logger
-    Logger::try_with_str("debug")?
-        .log_to_file(FileSpec::default())
-        .start()?;
+    if std::env::args().any(|arg| arg == "--validate-tiles") {
+        if validate_tiles() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(depth) = arg_value("--perft") {
+        return run_perft(&depth);
+    }
+
+    // AI against AI, e.g. "--games 10 --black-depth 4 --white-depth 6".
+    if let Some(games) = arg_value("--games") {
+        if run_selfplay(&games)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if std::env::args().any(|arg| arg == "--calibrate") {
+        run_calibration()?;
+        return Ok(());
+    }
+
+    // Init logger, see `LogConfig`.
+    let _logger = LogConfig::load()?.start()?;
+
+    let calibration = load_calibration()?;
+
+    // Timed games with "--clock minutes[+increment seconds]", e.g. 5+3.
+    let clock = match arg_value("--clock") {
+        Some(value) => Some(parse_clock(&value).ok_or(format!("Invalid clock: {}", value))?),
+        None => None,
+    };
+
+    // Minimum AI turn in milliseconds, e.g. 0 for instant replies.
+    let min_ai_turn = match arg_value("--ai-min-turn") {
+        Some(value) => Duration::from_millis(
+            value
+                .parse()
+                .map_err(|_| format!("Invalid AI turn time: {}", value))?,
+        ),
+        None => DEFAULT_MIN_AI_TURN,
+    };
 
     let mut stdout = io::stdout();
-    run(&mut stdout)?;
+    run(&mut stdout, &calibration, clock, min_ai_turn)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyEvent, KeyModifiers};
+    use rusty_duke_logic::logic::builder::GameStateBuilder;
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    /// Move focus to square with arrow keys, as a player would.
+    fn focus_on(play_state: &mut PlayState, cord: Coordinate) {
+        for _ in 0..logic::WIDTH * logic::HEIGHT {
+            let code = if play_state.focus.y < cord.y {
+                KeyCode::Up
+            } else if play_state.focus.y > cord.y {
+                KeyCode::Down
+            } else if play_state.focus.x == cord.x {
+                return;
+            } else {
+                KeyCode::Right
+            };
+            assert_eq!(human_turn(play_state, &key(code)), HumanInput::Continue);
+        }
+        panic!("Focus did not reach {:?}.", cord);
+    }
+
+    #[test]
+    fn builtin_tiles_validate() {
+        // True on errors.
+        assert!(!validate_tiles());
+    }
+
+    #[test]
+    fn hotseat_turns_alternate() {
+        let mut play_state = PlayState::new(
+            Variant::Standard,
+            Some(TileColor::Black),
+            Some(TileColor::White),
+            None,
+        );
+        assert!(play_state.is_human(TileColor::Black) && play_state.is_human(TileColor::White));
+
+        // Setup, then draws and placements. Placing a drawn tile ends the
+        // turn, drawing it does not.
+        let mut turns = Vec::new();
+        while turns.len() < 8 {
+            let ply = play_state.session.ply;
+            assert_eq!(play_state.view_color(), ply);
+            let input = if play_state.session.drawn().is_empty() {
+                human_turn(&mut play_state, &key(KeyCode::Char('n')))
+            } else {
+                let cord = play_state
+                    .session
+                    .current_legal_actions()
+                    .iter()
+                    .find_map(|action| match action {
+                        Action::PlaceNew(cord) => Some(*cord),
+                        _ => None,
+                    })
+                    .unwrap();
+                focus_on(&mut play_state, cord);
+                human_turn(&mut play_state, &key(KeyCode::Enter))
+            };
+            if input == HumanInput::TurnDone {
+                assert_eq!(play_state.session.ply, ply.opponent());
+                turns.push(ply);
+            } else {
+                assert_eq!(input, HumanInput::Continue);
+                assert_eq!(play_state.session.ply, ply);
+            }
+        }
+        for pair in turns.windows(2) {
+            assert_eq!(pair[1], pair[0].opponent());
+        }
+        assert_eq!(turns[0], TileColor::Black);
+
+        // Keys of the other player's tiles do nothing.
+        let ply = play_state.session.ply;
+        let theirs = (0..logic::HEIGHT)
+            .flat_map(|y| (0..logic::WIDTH).map(move |x| Coordinate { x, y }))
+            .find(|cord| {
+                play_state
+                    .session
+                    .tile_at(*cord)
+                    .is_some_and(|tile| tile.color != ply)
+            });
+        if let Some(cord) = theirs {
+            focus_on(&mut play_state, cord);
+            assert_eq!(
+                human_turn(&mut play_state, &key(KeyCode::Enter)),
+                HumanInput::Continue
+            );
+            assert_eq!(play_state.selected, None);
+        }
+        assert_eq!(
+            human_turn(&mut play_state, &key(KeyCode::Char('q'))),
+            HumanInput::Quit
+        );
+    }
+
+    /// Hotseat game after actions, each the first legal one.
+    fn hotseat_game(actions: usize) -> PlayState {
+        let mut play_state = PlayState::new(
+            Variant::Standard,
+            Some(TileColor::Black),
+            Some(TileColor::White),
+            None,
+        );
+        for _ in 0..actions {
+            let action = play_state.session.current_legal_actions()[0];
+            play_state.session.submit_action(action).unwrap();
+        }
+        play_state
+    }
+
+    #[test]
+    fn saved_games_load_at_the_same_position() {
+        // Placements are done and Black has drawn a tile.
+        let saved = hotseat_game(7);
+        assert_eq!(saved.session.ply, TileColor::Black);
+        assert_eq!(saved.session.drawn().len(), 1);
+
+        let loaded = PlayState::from_save_text(&saved.to_save_text()).unwrap();
+        let (saved_state, loaded_state) = (saved.session.state(), loaded.session.state());
+        assert_eq!(loaded_state.to_fen(), saved_state.to_fen());
+        assert_eq!(loaded_state.hash(), saved_state.hash());
+        assert_eq!(loaded.session.ply, TileColor::Black);
+        assert_eq!(loaded.session.drawn(), saved.session.drawn());
+        assert_eq!(
+            loaded.session.record().actions,
+            saved.session.record().actions
+        );
+        assert_eq!(loaded.player_color, Some(TileColor::Black));
+        assert_eq!(loaded.player2_color, Some(TileColor::White));
+        assert_eq!(loaded.ai, None);
+        assert_eq!(loaded.to_save_text(), saved.to_save_text());
+    }
+
+    #[test]
+    fn saved_ai_settings_load() {
+        let settings = [
+            AiSettings::Level(3),
+            AiSettings::Search {
+                depth: Some(2),
+                duration: None,
+            },
+            AiSettings::Search {
+                depth: None,
+                duration: Some(Duration::from_millis(1500)),
+            },
+        ];
+        for ai in settings {
+            let saved = PlayState::new(Variant::Quick, Some(TileColor::White), None, Some(ai));
+            let loaded = PlayState::from_save_text(&saved.to_save_text()).unwrap();
+            assert_eq!(loaded.ai, Some(ai));
+            assert_eq!(loaded.player_color, Some(TileColor::White));
+            assert_eq!(loaded.player2_color, None);
+            assert!(!loaded.is_human(TileColor::Black));
+            assert_eq!(loaded.session.record().variant, Variant::Quick);
+        }
+    }
+
+    #[test]
+    fn broken_saves_are_reported() {
+        let record = hotseat_game(2).session.record().to_text();
+        let load = |header: &str| PlayState::from_save_text(&format!("{}{}", header, record));
+
+        assert!(load("human Black\nhuman White\n").is_ok());
+        assert_eq!(
+            load("human Red\nhuman White\n").err().unwrap(),
+            "Unknown color: Red"
+        );
+        assert_eq!(
+            load("human Black\nai fast\n").err().unwrap(),
+            "Invalid AI settings: fast"
+        );
+        assert_eq!(
+            load("human Black\nai level x\n").err().unwrap(),
+            "Invalid AI settings: level x"
+        );
+        assert_eq!(load("human Black\n").err().unwrap(), "AI settings missing.");
+        assert!(PlayState::from_save_text("human Black\nai level 2\nnot a record\n").is_err());
+    }
+
+    #[test]
+    fn saves_with_unknown_tiles_are_reported() {
+        let record = hotseat_game(2).session.record().to_text();
+        assert!(record.contains(" Duke"), "{}", record);
+        let text = format!(
+            "human Black\nhuman White\n{}",
+            record.replace("Duke", "Gawain")
+        );
+        assert_eq!(
+            PlayState::from_save_text(&text).err().unwrap(),
+            "This game uses tiles from a newer version or a different ruleset (Gawain)."
+        );
+    }
+
+    #[test]
+    fn save_files_are_listed_newest_first() {
+        let directory =
+            std::env::temp_dir().join(format!("rusty-duke-saves-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        for name in [
+            "duke-save-999.txt",
+            "duke-save-1000.txt",
+            "duke-save-1001.txt",
+            "duke-save-1001.json",
+            "notes.txt",
+        ] {
+            std::fs::write(directory.join(name), "").unwrap();
+        }
+        let files = save_files(&directory);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            files.unwrap(),
+            [
+                "duke-save-1001.txt",
+                "duke-save-1000.txt",
+                "duke-save-999.txt"
+            ]
+        );
+    }
+
+    fn tile(kind: TileType, color: TileColor, flipped: bool) -> Tile {
+        Tile {
+            kind,
+            flipped,
+            color,
+        }
+    }
+
+    #[test]
+    fn inspector_shows_the_current_side() {
+        let black = TileColor::Black;
+        assert_eq!(
+            inspector_grid(&tile(TileType::Duke, black, false), black),
+            [".....", ".....", ".STS.", ".....", "....."]
+        );
+        assert_eq!(
+            inspector_grid(&tile(TileType::Duke, black, true), black),
+            [".....", "..S..", "..T..", "..S..", "....."]
+        );
+        assert_eq!(
+            inspector_grid(&tile(TileType::Knight, black, true), black),
+            [".....", "..S..", "..T..", ".M.M.", "M...M"]
+        );
+    }
+
+    #[test]
+    fn inspector_turns_white_tiles() {
+        let (black, white) = (TileColor::Black, TileColor::White);
+        let pikeman = [".....", ".....", "..T..", ".M.M.", "M...M"];
+        assert_eq!(
+            inspector_grid(&tile(TileType::Pikeman, white, false), black),
+            pikeman
+        );
+        // White sees the board mirrored, the card is the same both ways.
+        assert_eq!(
+            inspector_grid(&tile(TileType::Pikeman, white, false), white),
+            pikeman
+        );
+        assert_eq!(
+            inspector_grid(&tile(TileType::Pikeman, black, false), white),
+            ["M...M", ".M.M.", "..T..", ".....", "....."]
+        );
+    }
+
+    /// Text printed by f, without escape sequences.
+    fn printed(f: impl FnOnce(&mut Vec<u8>) -> Result<()>) -> String {
+        let mut out = Vec::new();
+        f(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let mut text = String::new();
+        let mut chars = out.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                // Skip to the final byte of the sequence.
+                if chars.next() == Some('[') {
+                    chars.find(|c| c.is_ascii_alphabetic());
+                }
+            } else {
+                text.push(c);
+            }
+        }
+        text
+    }
+
+    #[test]
+    fn tiles_show_their_side() {
+        let front = tile(TileType::Pikeman, TileColor::Black, false);
+        let back = tile(TileType::Pikeman, TileColor::Black, true);
+        let text = printed(|w| print_tile(w, (0, 0), TileState::Normal, &front));
+        assert!(text.contains("front") && !text.contains("back"), "{}", text);
+        let text = printed(|w| print_tile(w, (0, 0), TileState::Normal, &back));
+        assert!(text.contains("back") && !text.contains("front"), "{}", text);
+    }
+
+    #[test]
+    fn squares_show_their_effects() {
+        // White Camelot on c3 defends b2, c2, d2, b3 and d3. White Morgana
+        // on f3 puts Dread on f2.
+        let state = GameStateBuilder::new()
+            .tile_at(
+                Coordinate::new(0, 0),
+                TileType::Duke,
+                TileColor::Black,
+                false,
+            )
+            .tile_at(
+                Coordinate::new(0, 5),
+                TileType::Duke,
+                TileColor::White,
+                false,
+            )
+            .tile_at(
+                Coordinate::new(2, 2),
+                TileType::Camelot,
+                TileColor::White,
+                false,
+            )
+            .tile_at(
+                Coordinate::new(5, 2),
+                TileType::Morgana,
+                TileColor::White,
+                false,
+            )
+            .build()
+            .unwrap();
+        let text = |x, y| {
+            let effects = state.square(Coordinate::new(x, y)).effects;
+            printed(|w| print_effects(w, (0, 0), effects))
+        };
+        assert_eq!(text(2, 1), "Defence");
+        assert_eq!(text(5, 1), "Dread");
+        assert_eq!(text(4, 4), "");
+    }
+
+    #[test]
+    fn ai_turns_wait_for_the_minimum() {
+        let ms = Duration::from_millis;
+        let start = Instant::now();
+        let mut pacing = AiPacing::new(ms(500));
+        // Turn starts when the AI is first to go.
+        assert_eq!(pacing.wait(start), ms(500));
+        assert_eq!(pacing.wait(start + ms(200)), ms(300));
+        assert_eq!(pacing.wait(start + ms(500)), Duration::ZERO);
+        assert_eq!(pacing.wait(start + ms(900)), Duration::ZERO);
+        // Time the human takes doesn't count for the next turn.
+        pacing.done();
+        assert_eq!(pacing.wait(start + ms(5000)), ms(500));
+
+        assert_eq!(AiPacing::new(Duration::ZERO).wait(start), Duration::ZERO);
+    }
+
+    #[test]
+    fn ai_turn_is_not_played_before_the_minimum() {
+        let ms = Duration::from_millis;
+        let ai = AiSettings::Search {
+            depth: Some(1),
+            duration: None,
+        };
+        let mut play_state =
+            PlayState::new(Variant::Standard, Some(TileColor::White), None, Some(ai));
+        let mut pacing = AiPacing::new(ms(500));
+        let start = Instant::now();
+
+        for now in [start, start + ms(100), start + ms(499)] {
+            assert!(!play_state.paced_ai_turn(&mut pacing, now).is_zero());
+            assert_eq!(play_state.session.ply, TileColor::Black);
+            assert!(play_state.session.record().actions.is_empty());
+        }
+        assert!(play_state
+            .paced_ai_turn(&mut pacing, start + ms(500))
+            .is_zero());
+        assert_eq!(play_state.session.ply, TileColor::White);
+        assert!(!play_state.session.record().actions.is_empty());
+    }
+
+    #[test]
+    fn log_config_from_file_then_environment() {
+        let mut config = LogConfig::default();
+        config
+            .apply_text("# Quieter\nlevel info, rusty_duke_logic::ai=debug\n\nfiles_kept 2\n")
+            .unwrap();
+        assert_eq!(config.level, "info, rusty_duke_logic::ai=debug");
+        assert_eq!(config.files_kept, 2);
+        assert_eq!(config.file_size, LOG_FILE_SIZE);
+
+        // Environment wins over the file.
+        let env = |name: &str| match name {
+            "RUSTY_DUKE_LOG_FILES_KEPT" => Some("7".to_string()),
+            "RUSTY_DUKE_LOG_DIRECTORY" => Some("logs".to_string()),
+            _ => None,
+        };
+        config.apply_env(env).unwrap();
+        assert_eq!(config.files_kept, 7);
+        assert_eq!(config.directory, Some(PathBuf::from("logs")));
+        assert_eq!(config.level, "info, rusty_duke_logic::ai=debug");
+
+        assert!(config.apply_text("file_size lots").is_err());
+        assert!(config.apply_text("colour green").is_err());
+        let env = |name: &str| (name == "RUSTY_DUKE_LOG_FILE_SIZE").then(|| "-1".to_string());
+        assert!(config.apply_env(env).is_err());
+    }
+
+    #[test]
+    fn log_files_are_rotated() {
+        // Only test to start a logger, there is one per process.
+        let directory =
+            std::env::temp_dir().join(format!("rusty-duke-logs-{}", std::process::id()));
+        let config = LogConfig {
+            level: "info".to_string(),
+            file_size: 2000,
+            files_kept: 2,
+            directory: Some(directory.clone()),
+        };
+        let logger = config.start().unwrap();
+        for i in 0..200 {
+            log::info!("Line {} of a long session, padded to fill the file.", i);
+        }
+        logger.flush();
+
+        let files: Vec<u64> = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().metadata().unwrap().len())
+            .collect();
+        logger.shutdown();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        // Kept files plus the current one, none much over the limit.
+        assert_eq!(files.len(), 3, "{:?}", files);
+        assert!(files.iter().all(|len| *len < 2200), "{:?}", files);
+    }
+}